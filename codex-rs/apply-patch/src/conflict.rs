@@ -0,0 +1,168 @@
+//! Structured reports for update hunks that do not apply, so the model can
+//! see what it expected, what the file holds now, and regenerate the hunk.
+
+use std::fmt;
+use std::path::Path;
+use std::path::PathBuf;
+
+use crate::parser::UpdateFileChunk;
+use crate::seek_sequence;
+
+/// Lines of the current file shown around the closest match.
+const SURROUNDING_LINES: usize = 3;
+
+/// Why the hunks of an update did not apply.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ConflictCause {
+    /// The lines the hunk expected are not in the file, and no earlier
+    /// version of the file was known to merge against.
+    NotFound,
+    /// The file changed since the model read it, and those changes overlap
+    /// the patch, so a three-way merge was not possible.
+    MergeFailed,
+}
+
+/// One update hunk that could not be placed in the current file.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HunkConflict {
+    /// 1-based position of the hunk among the file's update hunks.
+    pub hunk: usize,
+    /// The `@@` context line of the hunk, if it had one.
+    pub change_context: Option<String>,
+    /// The lines the hunk expected to find.
+    pub expected: Vec<String>,
+    /// 1-based line number of the first line in `current`.
+    pub current_start: usize,
+    /// The part of the current file that most resembles `expected`, with a
+    /// few surrounding lines.
+    pub current: Vec<String>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ConflictReport {
+    pub path: PathBuf,
+    pub cause: ConflictCause,
+    /// Hunk count of the file's update, for context.
+    pub total_hunks: usize,
+    pub conflicts: Vec<HunkConflict>,
+}
+
+impl ConflictReport {
+    /// Report the hunks in `chunks` that cannot be placed in `current_lines`.
+    pub(crate) fn new(
+        path: &Path,
+        current_lines: &[String],
+        chunks: &[UpdateFileChunk],
+        cause: ConflictCause,
+    ) -> Self {
+        let mut conflicts = Vec::new();
+        let mut line_index = 0;
+        for (i, chunk) in chunks.iter().enumerate() {
+            match locate(current_lines, chunk, line_index) {
+                Some(end) => line_index = end,
+                None => {
+                    let (current_start, current) = closest_region(current_lines, &chunk.old_lines);
+                    conflicts.push(HunkConflict {
+                        hunk: i + 1,
+                        change_context: chunk.change_context.clone(),
+                        expected: chunk.old_lines.clone(),
+                        current_start,
+                        current,
+                    });
+                }
+            }
+        }
+        Self {
+            path: path.to_path_buf(),
+            cause,
+            total_hunks: chunks.len(),
+            conflicts,
+        }
+    }
+}
+
+impl fmt::Display for ConflictReport {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let cause = match self.cause {
+            ConflictCause::NotFound => "the expected lines are not in the file",
+            ConflictCause::MergeFailed => {
+                "the file changed since you read it and the changes overlap this patch"
+            }
+        };
+        write!(f, "Patch conflict in {}: {cause}.", self.path.display())?;
+        for conflict in &self.conflicts {
+            write!(f, "\n\nHunk {} of {}", conflict.hunk, self.total_hunks)?;
+            if let Some(context) = &conflict.change_context {
+                write!(f, " (@@ {context})")?;
+            }
+            write!(f, " expected:")?;
+            for line in &conflict.expected {
+                write!(f, "\n  {line}")?;
+            }
+            if conflict.current.is_empty() {
+                write!(f, "\nThe file is empty.")?;
+                continue;
+            }
+            write!(
+                f,
+                "\nThe file currently has, from line {}:",
+                conflict.current_start
+            )?;
+            for (offset, line) in conflict.current.iter().enumerate() {
+                write!(f, "\n{:>5} | {line}", conflict.current_start + offset)?;
+            }
+        }
+        write!(
+            f,
+            "\n\nRe-read {} and regenerate the failing hunks against its current contents.",
+            self.path.display()
+        )
+    }
+}
+
+/// Where `chunk` applies in `lines` at or after `start`, as the index just
+/// past the matched lines.
+fn locate(lines: &[String], chunk: &UpdateFileChunk, mut start: usize) -> Option<usize> {
+    if let Some(context) = &chunk.change_context {
+        start =
+            seek_sequence::seek_sequence(lines, std::slice::from_ref(context), start, false)? + 1;
+    }
+    if chunk.old_lines.is_empty() {
+        return Some(start);
+    }
+    let mut pattern: &[String] = &chunk.old_lines;
+    let mut found = seek_sequence::seek_sequence(lines, pattern, start, chunk.is_end_of_file);
+    if found.is_none() && pattern.last().is_some_and(|s| s.is_empty()) {
+        pattern = &pattern[..pattern.len() - 1];
+        found = seek_sequence::seek_sequence(lines, pattern, start, chunk.is_end_of_file);
+    }
+    found.map(|idx| idx + pattern.len())
+}
+
+/// The window of `lines` sharing the most trimmed lines with `expected`,
+/// widened by [`SURROUNDING_LINES`] on each side. Returns its 1-based start
+/// line and contents.
+fn closest_region(lines: &[String], expected: &[String]) -> (usize, Vec<String>) {
+    if lines.is_empty() {
+        return (1, Vec::new());
+    }
+    let window = expected.len().clamp(1, lines.len());
+    let mut best = (0, 0);
+    for start in 0..=lines.len() - window {
+        let score = lines[start..start + window]
+            .iter()
+            .zip(expected)
+            .filter(|(line, want)| line.trim() == want.trim())
+            .count()
+            + lines[start..start + window]
+                .iter()
+                .filter(|line| expected.iter().any(|want| want.trim() == line.trim()))
+                .count();
+        if score > best.1 {
+            best = (start, score);
+        }
+    }
+    let from = best.0.saturating_sub(SURROUNDING_LINES);
+    let to = (best.0 + window + SURROUNDING_LINES).min(lines.len());
+    (from + 1, lines[from..to].to_vec())
+}
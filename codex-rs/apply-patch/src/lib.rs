@@ -1,7 +1,12 @@
+mod conflict;
+mod merge;
 mod parser;
 mod seek_sequence;
 mod standalone_executable;
+mod text_format;
+mod transaction;
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
@@ -9,6 +14,9 @@ use std::str::Utf8Error;
 
 use anyhow::Context;
 use anyhow::Result;
+pub use conflict::ConflictCause;
+pub use conflict::ConflictReport;
+pub use conflict::HunkConflict;
 use once_cell::sync::Lazy;
 pub use parser::Hunk;
 pub use parser::ParseError;
@@ -24,6 +32,13 @@ use tree_sitter::QueryCursor;
 use tree_sitter::StreamingIterator;
 use tree_sitter_bash::LANGUAGE as BASH;
 
+pub use text_format::Encoding;
+pub use text_format::LineEnding;
+pub use text_format::TextFormat;
+pub use text_format::WriteOptions;
+pub use text_format::read_text;
+use transaction::FileOp;
+
 pub use standalone_executable::main;
 
 /// Detailed instructions for gpt-4.1 on how to use the `apply_patch` tool.
@@ -40,6 +55,10 @@ pub enum ApplyPatchError {
     /// Error that occurs while computing replacements when applying patch chunks
     #[error("{0}")]
     ComputeReplacements(String),
+    /// Update hunks that could not be placed in the current file, even after
+    /// trying a three-way merge.
+    #[error("{0}")]
+    Conflict(ConflictReport),
     /// A raw patch body was provided without an explicit `apply_patch` invocation.
     #[error(
         "patch detected without explicit call to apply_patch. Rerun as [\"apply_patch\", \"<patch>\"]"
@@ -214,6 +233,22 @@ impl ApplyPatchAction {
 /// cwd must be an absolute path so that we can resolve relative paths in the
 /// patch.
 pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApplyPatchVerified {
+    maybe_parse_apply_patch_verified_with_pre_images(argv, cwd, &|_| None)
+}
+
+/// Like [`maybe_parse_apply_patch_verified`], but when the update hunks for a
+/// file no longer apply, `pre_image` is asked for the contents the model last
+/// saw of that file. The patch is applied to those and the result merged
+/// three-way into the current file. If that fails, or no pre-image is known,
+/// the error is an [`ApplyPatchError::Conflict`] describing each failing hunk.
+///
+/// When a merge succeeds, the returned action's `patch` is rewritten so that
+/// it applies to the files as they are now.
+pub fn maybe_parse_apply_patch_verified_with_pre_images(
+    argv: &[String],
+    cwd: &Path,
+    pre_image: &dyn Fn(&Path) -> Option<String>,
+) -> MaybeApplyPatchVerified {
     // Detect a raw patch body passed directly as the command or as the body of a bash -lc
     // script. In these cases, report an explicit error rather than applying the patch.
     match argv {
@@ -252,6 +287,7 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                 })
                 .unwrap_or_else(|| cwd.to_path_buf());
             let mut changes = HashMap::new();
+            let mut merged = false;
             for hunk in hunks {
                 let path = hunk.resolve_path(&effective_cwd);
                 match hunk {
@@ -259,8 +295,8 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                         changes.insert(path, ApplyPatchFileChange::Add { content: contents });
                     }
                     Hunk::DeleteFile { .. } => {
-                        let content = match std::fs::read_to_string(&path) {
-                            Ok(content) => content,
+                        let content = match read_text(&path) {
+                            Ok((content, _)) => content,
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(
                                     ApplyPatchError::IoError(IoError {
@@ -280,6 +316,17 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                             content: contents,
                         } = match unified_diff_from_chunks(&path, &chunks) {
                             Ok(diff) => diff,
+                            Err(ApplyPatchError::ComputeReplacements(_)) => {
+                                match merge_update(&path, &chunks, pre_image(&path)) {
+                                    Ok(diff) => {
+                                        merged = true;
+                                        diff
+                                    }
+                                    Err(e) => {
+                                        return MaybeApplyPatchVerified::CorrectnessError(e);
+                                    }
+                                }
+                            }
                             Err(e) => {
                                 return MaybeApplyPatchVerified::CorrectnessError(e);
                             }
@@ -295,6 +342,11 @@ pub fn maybe_parse_apply_patch_verified(argv: &[String], cwd: &Path) -> MaybeApp
                     }
                 }
             }
+            let patch = if merged {
+                patch_from_changes(&changes)
+            } else {
+                patch
+            };
             MaybeApplyPatchVerified::Body(ApplyPatchAction {
                 changes,
                 patch,
@@ -473,6 +525,16 @@ pub fn apply_patch(
     patch: &str,
     stdout: &mut impl std::io::Write,
     stderr: &mut impl std::io::Write,
+) -> Result<(), ApplyPatchError> {
+    apply_patch_with_options(patch, &WriteOptions::default(), stdout, stderr)
+}
+
+/// [`apply_patch`], writing files in the format `options` asks for.
+pub fn apply_patch_with_options(
+    patch: &str,
+    options: &WriteOptions,
+    stdout: &mut impl std::io::Write,
+    stderr: &mut impl std::io::Write,
 ) -> Result<(), ApplyPatchError> {
     let hunks = match parse_patch(patch) {
         Ok(source) => source.hunks,
@@ -496,7 +558,7 @@ pub fn apply_patch(
         }
     };
 
-    apply_hunks(&hunks, stdout, stderr)?;
+    apply_hunks_with_options(&hunks, options, stdout, stderr)?;
 
     Ok(())
 }
@@ -506,6 +568,16 @@ pub fn apply_hunks(
     hunks: &[Hunk],
     stdout: &mut impl std::io::Write,
     stderr: &mut impl std::io::Write,
+) -> Result<(), ApplyPatchError> {
+    apply_hunks_with_options(hunks, &WriteOptions::default(), stdout, stderr)
+}
+
+/// [`apply_hunks`], writing files in the format `options` asks for.
+pub fn apply_hunks_with_options(
+    hunks: &[Hunk],
+    options: &WriteOptions,
+    stdout: &mut impl std::io::Write,
+    stderr: &mut impl std::io::Write,
 ) -> Result<(), ApplyPatchError> {
     let _existing_paths: Vec<&Path> = hunks
         .iter()
@@ -534,13 +606,13 @@ pub fn apply_hunks(
         .collect::<Vec<&Path>>();
 
     // Delegate to a helper that applies each hunk to the filesystem.
-    match apply_hunks_to_files(hunks) {
+    match apply_hunks_to_files(hunks, options) {
         Ok(affected) => {
             print_summary(&affected, stdout).map_err(ApplyPatchError::from)?;
             Ok(())
         }
         Err(err) => {
-            let msg = err.to_string();
+            let msg = format!("{err:#}");
             writeln!(stderr, "{msg}").map_err(ApplyPatchError::from)?;
             if let Some(io) = err.downcast_ref::<std::io::Error>() {
                 Err(ApplyPatchError::from(io))
@@ -564,32 +636,41 @@ pub struct AffectedPaths {
 }
 
 /// Apply the hunks to the filesystem, returning which files were added, modified, or deleted.
-/// Returns an error if the patch could not be applied.
-fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
+/// Returns an error if the patch could not be applied, in which case no file
+/// was changed. Updated files keep their encoding and line endings, as does a
+/// file an added one replaces, unless `options` overrides them.
+fn apply_hunks_to_files(hunks: &[Hunk], options: &WriteOptions) -> anyhow::Result<AffectedPaths> {
     if hunks.is_empty() {
         anyhow::bail!("No files were modified.");
     }
 
+    // Work out the final state of every path first (later hunks see the
+    // results of earlier ones), then write them all in one transaction.
+    let mut staged: BTreeMap<PathBuf, Option<(String, TextFormat)>> = BTreeMap::new();
+    let current =
+        |staged: &BTreeMap<PathBuf, Option<(String, TextFormat)>>, path: &Path| match staged
+            .get(path)
+        {
+            Some(Some(text)) => Ok(text.clone()),
+            Some(None) => Err(std::io::Error::from(std::io::ErrorKind::NotFound)),
+            None => read_text(path),
+        };
     let mut added: Vec<PathBuf> = Vec::new();
     let mut modified: Vec<PathBuf> = Vec::new();
     let mut deleted: Vec<PathBuf> = Vec::new();
     for hunk in hunks {
         match hunk {
             Hunk::AddFile { path, contents } => {
-                if let Some(parent) = path.parent()
-                    && !parent.as_os_str().is_empty()
-                {
-                    std::fs::create_dir_all(parent).with_context(|| {
-                        format!("Failed to create parent directories for {}", path.display())
-                    })?;
-                }
-                std::fs::write(path, contents)
-                    .with_context(|| format!("Failed to write file {}", path.display()))?;
+                let found = current(&staged, path)
+                    .map(|(_, format)| format)
+                    .unwrap_or_default();
+                staged.insert(path.clone(), Some((contents.clone(), options.apply(found))));
                 added.push(path.clone());
             }
             Hunk::DeleteFile { path } => {
-                std::fs::remove_file(path)
+                current(&staged, path)
                     .with_context(|| format!("Failed to delete file {}", path.display()))?;
+                staged.insert(path.clone(), None);
                 deleted.push(path.clone());
             }
             Hunk::UpdateFile {
@@ -597,29 +678,52 @@ fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
                 move_path,
                 chunks,
             } => {
+                let (original_contents, format) = current(&staged, path).map_err(|err| {
+                    ApplyPatchError::IoError(IoError {
+                        context: format!("Failed to read file to update {}", path.display()),
+                        source: err,
+                    })
+                })?;
                 let AppliedPatch { new_contents, .. } =
-                    derive_new_contents_from_chunks(path, chunks)?;
+                    apply_chunks(path, original_contents, format, chunks)?;
+                let new_state = Some((new_contents, options.apply(format)));
                 if let Some(dest) = move_path {
-                    if let Some(parent) = dest.parent()
-                        && !parent.as_os_str().is_empty()
-                    {
-                        std::fs::create_dir_all(parent).with_context(|| {
-                            format!("Failed to create parent directories for {}", dest.display())
-                        })?;
-                    }
-                    std::fs::write(dest, new_contents)
-                        .with_context(|| format!("Failed to write file {}", dest.display()))?;
-                    std::fs::remove_file(path)
-                        .with_context(|| format!("Failed to remove original {}", path.display()))?;
+                    staged.insert(path.clone(), None);
+                    staged.insert(dest.clone(), new_state);
                     modified.push(dest.clone());
                 } else {
-                    std::fs::write(path, new_contents)
-                        .with_context(|| format!("Failed to write file {}", path.display()))?;
+                    staged.insert(path.clone(), new_state);
                     modified.push(path.clone());
                 }
             }
         }
     }
+
+    let mut ops = Vec::new();
+    for (path, state) in staged {
+        match state {
+            Some((text, format)) => {
+                let bytes = format
+                    .encode(&text)
+                    .with_context(|| format!("Failed to write file {}", path.display()))?;
+                // Write through a symlink rather than replacing it.
+                let path = match std::fs::symlink_metadata(&path) {
+                    Ok(meta) if meta.file_type().is_symlink() => {
+                        std::fs::canonicalize(&path).unwrap_or(path)
+                    }
+                    _ => path,
+                };
+                ops.push(FileOp::Write { path, bytes });
+            }
+            // Skip files the patch both added and deleted.
+            None if std::fs::symlink_metadata(&path).is_ok() => {
+                ops.push(FileOp::Remove { path });
+            }
+            None => {}
+        }
+    }
+    transaction::commit(ops)?;
+
     Ok(AffectedPaths {
         added,
         modified,
@@ -630,6 +734,8 @@ fn apply_hunks_to_files(hunks: &[Hunk]) -> anyhow::Result<AffectedPaths> {
 struct AppliedPatch {
     original_contents: String,
     new_contents: String,
+    /// How the file was stored, to write it back the same way.
+    format: TextFormat,
 }
 
 /// Return *only* the new file contents (joined into a single `String`) after
@@ -638,8 +744,8 @@ fn derive_new_contents_from_chunks(
     path: &Path,
     chunks: &[UpdateFileChunk],
 ) -> std::result::Result<AppliedPatch, ApplyPatchError> {
-    let original_contents = match std::fs::read_to_string(path) {
-        Ok(contents) => contents,
+    let (original_contents, format) = match read_text(path) {
+        Ok(read) => read,
         Err(err) => {
             return Err(ApplyPatchError::IoError(IoError {
                 context: format!("Failed to read file to update {}", path.display()),
@@ -647,31 +753,136 @@ fn derive_new_contents_from_chunks(
             }));
         }
     };
+    apply_chunks(path, original_contents, format, chunks)
+}
 
-    let mut original_lines: Vec<String> = original_contents
-        .split('\n')
-        .map(|s| s.to_string())
-        .collect();
-
-    // Drop the trailing empty element that results from the final newline so
-    // that line counts match the behaviour of standard `diff`.
-    if original_lines.last().is_some_and(|s| s.is_empty()) {
-        original_lines.pop();
-    }
-
+/// Apply `chunks` to `original_contents`, the text of the file at `path`.
+fn apply_chunks(
+    path: &Path,
+    original_contents: String,
+    format: TextFormat,
+    chunks: &[UpdateFileChunk],
+) -> std::result::Result<AppliedPatch, ApplyPatchError> {
+    let original_lines = split_lines(&original_contents);
     let replacements = compute_replacements(&original_lines, path, chunks)?;
-    let new_lines = apply_replacements(original_lines, &replacements);
-    let mut new_lines = new_lines;
-    if !new_lines.last().is_some_and(|s| s.is_empty()) {
-        new_lines.push(String::new());
-    }
-    let new_contents = new_lines.join("\n");
+    let new_contents = join_lines(apply_replacements(original_lines, &replacements));
     Ok(AppliedPatch {
         original_contents,
         new_contents,
+        format,
     })
 }
 
+/// The lines of `contents`, without the trailing empty element that results
+/// from the final newline so that line counts match the behaviour of
+/// standard `diff`.
+fn split_lines(contents: &str) -> Vec<String> {
+    let mut lines: Vec<String> = contents.split('\n').map(|s| s.to_string()).collect();
+    if lines.last().is_some_and(|s| s.is_empty()) {
+        lines.pop();
+    }
+    lines
+}
+
+/// Join `lines` back into file contents ending in a newline.
+fn join_lines(mut lines: Vec<String>) -> String {
+    if !lines.last().is_some_and(|s| s.is_empty()) {
+        lines.push(String::new());
+    }
+    lines.join("\n")
+}
+
+/// Apply `chunks` to `pre_image`, the file as the model last saw it, and
+/// merge the result into the file's current contents.
+fn merge_update(
+    path: &Path,
+    chunks: &[UpdateFileChunk],
+    pre_image: Option<String>,
+) -> std::result::Result<ApplyPatchFileUpdate, ApplyPatchError> {
+    let (current_contents, _) = read_text(path).map_err(|err| {
+        ApplyPatchError::IoError(IoError {
+            context: format!("Failed to read file to update {}", path.display()),
+            source: err,
+        })
+    })?;
+    let current_lines = split_lines(&current_contents);
+    let Some(pre_image) = pre_image else {
+        return Err(ApplyPatchError::Conflict(ConflictReport::new(
+            path,
+            &current_lines,
+            chunks,
+            ConflictCause::NotFound,
+        )));
+    };
+
+    let base_lines = split_lines(&pre_image);
+    let merged = compute_replacements(&base_lines, path, chunks)
+        .ok()
+        .and_then(|replacements| {
+            let ours = apply_replacements(base_lines.clone(), &replacements);
+            merge::merge3(&base_lines, &ours, &current_lines)
+        });
+    let Some(merged) = merged else {
+        return Err(ApplyPatchError::Conflict(ConflictReport::new(
+            path,
+            &current_lines,
+            chunks,
+            ConflictCause::MergeFailed,
+        )));
+    };
+    let new_contents = join_lines(merged);
+    let unified_diff = TextDiff::from_lines(&current_contents, &new_contents)
+        .unified_diff()
+        .context_radius(1)
+        .to_string();
+    Ok(ApplyPatchFileUpdate {
+        unified_diff,
+        content: new_contents,
+    })
+}
+
+/// A patch that makes `changes` to the files as they are now. Updates are
+/// written as a single hunk replacing the whole file, so they apply no
+/// matter how the file is laid out.
+fn patch_from_changes(changes: &HashMap<PathBuf, ApplyPatchFileChange>) -> String {
+    let mut paths: Vec<&PathBuf> = changes.keys().collect();
+    paths.sort();
+    let mut patch = String::from("*** Begin Patch\n");
+    for path in paths {
+        match &changes[path] {
+            ApplyPatchFileChange::Add { content } => {
+                patch.push_str(&format!("*** Add File: {}\n", path.display()));
+                for line in split_lines(content) {
+                    patch.push_str(&format!("+{line}\n"));
+                }
+            }
+            ApplyPatchFileChange::Delete { .. } => {
+                patch.push_str(&format!("*** Delete File: {}\n", path.display()));
+            }
+            ApplyPatchFileChange::Update {
+                move_path,
+                new_content,
+                ..
+            } => {
+                patch.push_str(&format!("*** Update File: {}\n", path.display()));
+                if let Some(dest) = move_path {
+                    patch.push_str(&format!("*** Move to: {}\n", dest.display()));
+                }
+                patch.push_str("@@\n");
+                let current = read_text(path).map(|(text, _)| text).unwrap_or_default();
+                for line in split_lines(&current) {
+                    patch.push_str(&format!("-{line}\n"));
+                }
+                for line in split_lines(new_content) {
+                    patch.push_str(&format!("+{line}\n"));
+                }
+            }
+        }
+    }
+    patch.push_str("*** End Patch");
+    patch
+}
+
 /// Compute a list of replacements needed to transform `original_lines` into the
 /// new lines, given the patch `chunks`. Each replacement is returned as
 /// `(start_index, old_len, new_lines)`.
@@ -815,6 +1026,7 @@ pub fn unified_diff_from_chunks_with_context(
     let AppliedPatch {
         original_contents,
         new_contents,
+        ..
     } = derive_new_contents_from_chunks(path, chunks)?;
     let text_diff = TextDiff::from_lines(&original_contents, &new_contents);
     let unified_diff = text_diff.unified_diff().context_radius(context).to_string();
@@ -1145,6 +1357,62 @@ PATCH"#,
         assert_eq!(contents, "foo\nbaz\n");
     }
 
+    #[test]
+    fn test_failed_hunk_leaves_every_file_untouched() {
+        let dir = tempdir().unwrap();
+        let first = dir.path().join("first.txt");
+        let added = dir.path().join("added.txt");
+        let second = dir.path().join("second.txt");
+        fs::write(&first, "a\n").unwrap();
+        fs::write(&second, "b\n").unwrap();
+        let patch = wrap_patch(&format!(
+            "*** Update File: {}\n@@\n-a\n+A\n*** Add File: {}\n+new\n*** Update File: {}\n@@\n-missing\n+B",
+            first.display(),
+            added.display(),
+            second.display()
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        assert!(apply_patch(&patch, &mut stdout, &mut stderr).is_err());
+        assert_eq!(fs::read_to_string(&first).unwrap(), "a\n");
+        assert_eq!(fs::read_to_string(&second).unwrap(), "b\n");
+        assert!(!added.exists());
+    }
+
+    #[test]
+    fn test_update_file_hunk_keeps_line_endings_and_encoding() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("windows.txt");
+        fs::write(&path, b"caf\xE9\r\nbar\r\n").unwrap();
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+ café
+-bar
++baz"#,
+            path.display()
+        ));
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&patch, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(fs::read(&path).unwrap(), b"caf\xE9\r\nbaz\r\n");
+
+        let options = WriteOptions {
+            line_ending: Some(LineEnding::Lf),
+            encoding: Some(Encoding::Utf8),
+        };
+        let patch = wrap_patch(&format!(
+            r#"*** Update File: {}
+@@
+ café
+-baz
++qux"#,
+            path.display()
+        ));
+        apply_patch_with_options(&patch, &options, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "café\nqux\n");
+    }
+
     #[test]
     fn test_update_file_hunk_can_move_file() {
         let dir = tempdir().unwrap();
@@ -1623,4 +1891,64 @@ g
         let result = apply_patch(&patch, &mut stdout, &mut stderr);
         assert!(result.is_err());
     }
+
+    #[test]
+    fn test_stale_update_merges_against_pre_image_or_reports_conflict() {
+        let dir = tempdir().unwrap();
+        let path = dir.path().join("lib.rs");
+        let pre_image = "fn a() {}\nfn b() {}\nfn c() {}\n".to_string();
+        // Someone edited the end of the file after the model read it.
+        fs::write(&path, "fn a() {}\nfn b() {}\nfn c() { todo!() }\n").unwrap();
+        let argv = vec![
+            "apply_patch".to_string(),
+            wrap_patch(
+                "*** Update File: lib.rs\n@@\n-fn a() {}\n+fn a() { 1 }\n fn b() {}\n fn c() {}",
+            ),
+        ];
+
+        let known = |p: &Path| (p == path.as_path()).then(|| pre_image.clone());
+        let MaybeApplyPatchVerified::Body(action) =
+            maybe_parse_apply_patch_verified_with_pre_images(&argv, dir.path(), &known)
+        else {
+            panic!("expected the update to merge");
+        };
+        let Some(ApplyPatchFileChange::Update { new_content, .. }) = action.changes().get(&path)
+        else {
+            panic!("expected an update of {}", path.display());
+        };
+        assert_eq!(new_content, "fn a() { 1 }\nfn b() {}\nfn c() { todo!() }\n");
+        // The rewritten patch applies to the file as it is now.
+        let mut stdout = Vec::new();
+        let mut stderr = Vec::new();
+        apply_patch(&action.patch, &mut stdout, &mut stderr).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), *new_content);
+
+        fs::write(&path, "fn a() { 2 }\nfn b() {}\n").unwrap();
+        let result = maybe_parse_apply_patch_verified_with_pre_images(&argv, dir.path(), &known);
+        let MaybeApplyPatchVerified::CorrectnessError(ApplyPatchError::Conflict(report)) = result
+        else {
+            panic!("expected a conflict, got {result:?}");
+        };
+        assert_eq!(report.cause, ConflictCause::MergeFailed);
+        assert_eq!(
+            report.conflicts,
+            vec![HunkConflict {
+                hunk: 1,
+                change_context: None,
+                expected: strs_to_strings(&["fn a() {}", "fn b() {}", "fn c() {}"]),
+                current_start: 1,
+                current: strs_to_strings(&["fn a() { 2 }", "fn b() {}"]),
+            }]
+        );
+        assert_eq!(
+            report.to_string(),
+            format!(
+                "Patch conflict in {path}: the file changed since you read it and the changes overlap this patch.\n\n\
+                 Hunk 1 of 1 expected:\n  fn a() {{}}\n  fn b() {{}}\n  fn c() {{}}\n\
+                 The file currently has, from line 1:\n    1 | fn a() {{ 2 }}\n    2 | fn b() {{}}\n\n\
+                 Re-read {path} and regenerate the failing hunks against its current contents.",
+                path = path.display()
+            )
+        );
+    }
 }
@@ -0,0 +1,153 @@
+//! Line-based three-way merge, used when a patch no longer applies because
+//! the file changed after the model read it.
+
+use std::ops::Range;
+
+use similar::Algorithm;
+use similar::DiffOp;
+use similar::capture_diff_slices;
+
+/// A run of `base` lines one side replaced with `lines`.
+#[derive(Debug)]
+struct Edit<'a> {
+    base: Range<usize>,
+    /// Where `lines` sit in the side's version of the file.
+    other: Range<usize>,
+    lines: &'a [String],
+}
+
+/// Merge the changes `base → ours` and `base → theirs`. Returns `None` when
+/// the two sides change the same or adjacent lines differently.
+pub(crate) fn merge3(base: &[String], ours: &[String], theirs: &[String]) -> Option<Vec<String>> {
+    let ours = edits(base, ours);
+    let theirs = edits(base, theirs);
+    let (mut i, mut j) = (0, 0);
+    let mut merged = Vec::new();
+    let mut pos = 0;
+
+    while i < ours.len() || j < theirs.len() {
+        // Start a cluster at the earlier edit and absorb every edit from
+        // either side that overlaps or touches it.
+        let start = match (ours.get(i), theirs.get(j)) {
+            (Some(a), Some(b)) => a.base.start.min(b.base.start),
+            (Some(a), None) => a.base.start,
+            (None, Some(b)) => b.base.start,
+            (None, None) => break,
+        };
+        let mut end = start;
+        let (first_ours, first_theirs) = (i, j);
+        loop {
+            if let Some(edit) = ours.get(i)
+                && edit.base.start <= end
+            {
+                end = end.max(edit.base.end);
+                i += 1;
+            } else if let Some(edit) = theirs.get(j)
+                && edit.base.start <= end
+            {
+                end = end.max(edit.base.end);
+                j += 1;
+            } else {
+                break;
+            }
+        }
+
+        merged.extend_from_slice(&base[pos..start]);
+        let ours_region = apply(base, start..end, &ours[first_ours..i]);
+        let theirs_region = apply(base, start..end, &theirs[first_theirs..j]);
+        if first_ours == i {
+            merged.extend(theirs_region);
+        } else if first_theirs == j || ours_region == theirs_region {
+            merged.extend(ours_region);
+        } else {
+            return None;
+        }
+        pos = end;
+    }
+    merged.extend_from_slice(&base[pos..]);
+    Some(merged)
+}
+
+/// The edits that turn `base` into `other`, with adjacent changes combined.
+fn edits<'a>(base: &[String], other: &'a [String]) -> Vec<Edit<'a>> {
+    let mut edits: Vec<Edit<'a>> = Vec::new();
+    for op in capture_diff_slices(Algorithm::Myers, base, other) {
+        let (base_range, other_range) = match op {
+            DiffOp::Equal { .. } => continue,
+            DiffOp::Delete {
+                old_index,
+                old_len,
+                new_index,
+            } => (old_index..old_index + old_len, new_index..new_index),
+            DiffOp::Insert {
+                old_index,
+                new_index,
+                new_len,
+            } => (old_index..old_index, new_index..new_index + new_len),
+            DiffOp::Replace {
+                old_index,
+                old_len,
+                new_index,
+                new_len,
+            } => (
+                old_index..old_index + old_len,
+                new_index..new_index + new_len,
+            ),
+        };
+        if let Some(last) = edits.last_mut()
+            && last.base.end == base_range.start
+            && last.other.end == other_range.start
+        {
+            last.base.end = base_range.end;
+            last.other.end = other_range.end;
+            last.lines = &other[last.other.clone()];
+            continue;
+        }
+        edits.push(Edit {
+            base: base_range,
+            lines: &other[other_range.clone()],
+            other: other_range,
+        });
+    }
+    edits
+}
+
+/// `base[range]` with `edits` (all inside `range`) applied.
+fn apply(base: &[String], range: Range<usize>, edits: &[Edit<'_>]) -> Vec<String> {
+    let mut out = Vec::new();
+    let mut pos = range.start;
+    for edit in edits {
+        out.extend_from_slice(&base[pos..edit.base.start]);
+        out.extend_from_slice(edit.lines);
+        pos = edit.base.end;
+    }
+    out.extend_from_slice(&base[pos..range.end]);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn lines(text: &str) -> Vec<String> {
+        text.lines().map(str::to_string).collect()
+    }
+
+    #[test]
+    fn merges_disjoint_changes_and_rejects_overlapping_ones() {
+        let base = lines("a\nb\nc\nd\ne\nf");
+        let ours = lines("a\nB\nc\nd\ne\nf");
+        let theirs = lines("a\nb\nc\nd\nE\nf\ng");
+        assert_eq!(
+            merge3(&base, &ours, &theirs),
+            Some(lines("a\nB\nc\nd\nE\nf\ng"))
+        );
+
+        // Both sides made the same change.
+        assert_eq!(merge3(&base, &ours, &ours), Some(ours.clone()));
+
+        let theirs = lines("a\nX\nc\nd\ne\nf");
+        assert_eq!(merge3(&base, &ours, &theirs), None);
+    }
+}
@@ -0,0 +1,245 @@
+//! Encodings and line endings of the files a patch touches.
+//!
+//! Patches are always written with `\n` line endings in UTF-8. Files are
+//! decoded into that form when read, and encoded back into the format they
+//! were found in when written, so that a patch to a CRLF, UTF-16 or Latin-1
+//! file changes only the lines it names. [`WriteOptions`] can force a line
+//! ending or UTF-8 instead.
+//!
+//! A file that mixes line endings is read by its majority ending. In a mostly
+//! LF file the `\r\n` lines keep their `\r`, but a mostly CRLF file is
+//! written back with `\r\n` on every line, including those that ended in a
+//! bare `\n`.
+
+use std::path::Path;
+
+/// Marker that starts a UTF-8 file written with a byte order mark.
+const UTF8_BOM: &[u8] = &[0xEF, 0xBB, 0xBF];
+const UTF16_LE_BOM: &[u8] = &[0xFF, 0xFE];
+const UTF16_BE_BOM: &[u8] = &[0xFE, 0xFF];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum LineEnding {
+    #[default]
+    Lf,
+    CrLf,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Encoding {
+    #[default]
+    Utf8,
+    /// UTF-8 starting with a byte order mark.
+    Utf8Bom,
+    /// UTF-16, little endian, with a byte order mark.
+    Utf16Le,
+    /// UTF-16, big endian, with a byte order mark.
+    Utf16Be,
+    /// Any file that is not valid UTF-8 is read as Latin-1 (ISO 8859-1),
+    /// which maps every byte to a character and so round-trips exactly.
+    Latin1,
+}
+
+/// How a file's text is stored on disk.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct TextFormat {
+    pub encoding: Encoding,
+    pub line_ending: LineEnding,
+}
+
+/// Overrides for the format of files a patch writes. `None` keeps the format
+/// each file already has; new files are written as LF UTF-8.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WriteOptions {
+    pub line_ending: Option<LineEnding>,
+    /// Only [`Encoding::Utf8`] is a sensible override, converting files to
+    /// plain UTF-8.
+    pub encoding: Option<Encoding>,
+}
+
+impl WriteOptions {
+    /// The options as command-line arguments for `apply_patch`, the inverse
+    /// of [`WriteOptions::from_args`].
+    pub fn to_args(&self) -> Vec<String> {
+        let mut args = Vec::new();
+        match self.line_ending {
+            Some(LineEnding::Lf) => args.push("--line-endings=lf".to_string()),
+            Some(LineEnding::CrLf) => args.push("--line-endings=crlf".to_string()),
+            None => {}
+        }
+        if self.encoding.is_some() {
+            args.push("--encoding=utf-8".to_string());
+        }
+        args
+    }
+
+    pub fn from_args(args: &[String]) -> Result<Self, String> {
+        let mut options = Self::default();
+        for arg in args {
+            match arg.as_str() {
+                "--line-endings=lf" => options.line_ending = Some(LineEnding::Lf),
+                "--line-endings=crlf" => options.line_ending = Some(LineEnding::CrLf),
+                "--encoding=utf-8" => options.encoding = Some(Encoding::Utf8),
+                other => return Err(format!("unknown apply_patch option: {other}")),
+            }
+        }
+        Ok(options)
+    }
+
+    /// The format to write a file found in `found` with.
+    pub(crate) fn apply(&self, found: TextFormat) -> TextFormat {
+        TextFormat {
+            encoding: self.encoding.unwrap_or(found.encoding),
+            line_ending: self.line_ending.unwrap_or(found.line_ending),
+        }
+    }
+}
+
+impl TextFormat {
+    /// Decode `bytes`, returning the text with `\n` line endings and the
+    /// format it was stored in. A file is CRLF when most of its lines end in
+    /// `\r\n`; its bare `\n` lines then become `\r\n` when it is encoded
+    /// again.
+    pub fn decode(bytes: &[u8]) -> (String, TextFormat) {
+        let (text, encoding) = if let Some(rest) = bytes.strip_prefix(UTF8_BOM) {
+            (
+                String::from_utf8_lossy(rest).into_owned(),
+                Encoding::Utf8Bom,
+            )
+        } else if let Some(rest) = bytes.strip_prefix(UTF16_LE_BOM) {
+            (decode_utf16(rest, u16::from_le_bytes), Encoding::Utf16Le)
+        } else if let Some(rest) = bytes.strip_prefix(UTF16_BE_BOM) {
+            (decode_utf16(rest, u16::from_be_bytes), Encoding::Utf16Be)
+        } else {
+            match std::str::from_utf8(bytes) {
+                Ok(text) => (text.to_string(), Encoding::Utf8),
+                Err(_) => (
+                    bytes.iter().map(|&b| char::from(b)).collect(),
+                    Encoding::Latin1,
+                ),
+            }
+        };
+
+        let crlf = text.matches("\r\n").count();
+        let lf = text.matches('\n').count() - crlf;
+        if crlf > 0 && crlf >= lf {
+            let format = TextFormat {
+                encoding,
+                line_ending: LineEnding::CrLf,
+            };
+            (text.replace("\r\n", "\n"), format)
+        } else {
+            let format = TextFormat {
+                encoding,
+                line_ending: LineEnding::Lf,
+            };
+            (text, format)
+        }
+    }
+
+    /// Encode `text`, which uses `\n` line endings, in this format. Fails for
+    /// Latin-1 when `text` has characters Latin-1 cannot represent.
+    pub fn encode(&self, text: &str) -> std::io::Result<Vec<u8>> {
+        let text = match self.line_ending {
+            LineEnding::Lf => text.to_string(),
+            LineEnding::CrLf => text.replace("\r\n", "\n").replace('\n', "\r\n"),
+        };
+        let bytes = match self.encoding {
+            Encoding::Utf8 => text.into_bytes(),
+            Encoding::Utf8Bom => [UTF8_BOM, text.as_bytes()].concat(),
+            Encoding::Utf16Le => UTF16_LE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_le_bytes))
+                .collect(),
+            Encoding::Utf16Be => UTF16_BE_BOM
+                .iter()
+                .copied()
+                .chain(text.encode_utf16().flat_map(u16::to_be_bytes))
+                .collect(),
+            Encoding::Latin1 => text
+                .chars()
+                .map(|ch| {
+                    u8::try_from(u32::from(ch)).map_err(|_| {
+                        std::io::Error::new(
+                            std::io::ErrorKind::InvalidData,
+                            format!(
+                                "the file is Latin-1 encoded and cannot hold {ch:?}; \
+                                 use only Latin-1 characters in it"
+                            ),
+                        )
+                    })
+                })
+                .collect::<std::io::Result<Vec<u8>>>()?,
+        };
+        Ok(bytes)
+    }
+}
+
+/// Read `path` as text with `\n` line endings, along with its format.
+pub fn read_text(path: &Path) -> std::io::Result<(String, TextFormat)> {
+    let bytes = std::fs::read(path)?;
+    Ok(TextFormat::decode(&bytes))
+}
+
+fn decode_utf16(bytes: &[u8], from_bytes: fn([u8; 2]) -> u16) -> String {
+    let units: Vec<u16> = bytes
+        .chunks_exact(2)
+        .map(|pair| from_bytes([pair[0], pair[1]]))
+        .collect();
+    String::from_utf16_lossy(&units)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn formats_round_trip() {
+        let crlf_bom = b"\xEF\xBB\xBFa\r\nb\r\n".to_vec();
+        let (text, format) = TextFormat::decode(&crlf_bom);
+        assert_eq!(text, "a\nb\n");
+        assert_eq!(
+            format,
+            TextFormat {
+                encoding: Encoding::Utf8Bom,
+                line_ending: LineEnding::CrLf,
+            }
+        );
+        assert_eq!(format.encode(&text).expect("encode"), crlf_bom);
+
+        // Mixed endings follow the majority.
+        let (text, format) = TextFormat::decode(b"a\r\nb\r\nc\n");
+        assert_eq!(text, "a\nb\nc\n");
+        assert_eq!(
+            format.encode(&text).expect("encode"),
+            b"a\r\nb\r\nc\r\n".to_vec()
+        );
+        let (text, format) = TextFormat::decode(b"a\nb\nc\r\n");
+        assert_eq!(text, "a\nb\nc\r\n");
+        assert_eq!(
+            format.encode(&text).expect("encode"),
+            b"a\nb\nc\r\n".to_vec()
+        );
+
+        let utf16 = [UTF16_LE_BOM, b"h\0i\0\n\0"].concat();
+        let (text, format) = TextFormat::decode(&utf16);
+        assert_eq!(text, "hi\n");
+        assert_eq!(format.encoding, Encoding::Utf16Le);
+        assert_eq!(format.encode(&text).expect("encode"), utf16);
+
+        let latin1 = b"caf\xE9\n".to_vec();
+        let (text, format) = TextFormat::decode(&latin1);
+        assert_eq!(text, "café\n");
+        assert_eq!(format.encode(&text).expect("encode"), latin1);
+        assert!(format.encode("€\n").is_err());
+
+        let forced = WriteOptions {
+            line_ending: Some(LineEnding::Lf),
+            encoding: Some(Encoding::Utf8),
+        };
+        assert_eq!(WriteOptions::from_args(&forced.to_args()), Ok(forced));
+        assert_eq!(forced.apply(format), TextFormat::default());
+    }
+}
@@ -0,0 +1,338 @@
+//! All-or-nothing application of the file writes a patch computes.
+//!
+//! New contents are first staged in temporary files next to their targets and
+//! synced to disk. Only then are the targets replaced, each by a rename, with
+//! the previous version of every replaced or deleted file kept as a backup.
+//! If any step fails, everything done so far is undone, so a failed patch
+//! leaves the workspace exactly as it was. A crash part-way through the
+//! renames leaves the backups (`.<name>.<pid>.codex-backup`) behind.
+//!
+//! A replaced file keeps its permissions and, where allowed, its owner. A
+//! file with other hard links is instead rewritten in place after a copy of
+//! it is backed up, so the links keep sharing it along with its ACLs and
+//! extended attributes, which a rename would drop.
+
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+
+const STAGED_SUFFIX: &str = "codex-staged";
+const BACKUP_SUFFIX: &str = "codex-backup";
+
+/// The final state of one path.
+#[derive(Debug)]
+pub(crate) enum FileOp {
+    Write { path: PathBuf, bytes: Vec<u8> },
+    Remove { path: PathBuf },
+}
+
+impl FileOp {
+    fn path(&self) -> &Path {
+        match self {
+            FileOp::Write { path, .. } | FileOp::Remove { path } => path,
+        }
+    }
+}
+
+/// What has been done so far, to undo on failure.
+#[derive(Default)]
+struct Transaction {
+    /// Directories created for new files, outermost first.
+    created_dirs: Vec<PathBuf>,
+    /// Staged file → its target.
+    staged: Vec<(PathBuf, PathBuf)>,
+    /// Target → backup of its previous version.
+    backups: Vec<(PathBuf, PathBuf)>,
+    /// Target rewritten in place → copy of its previous contents.
+    rewritten: Vec<(PathBuf, PathBuf)>,
+    /// Targets that now hold staged contents.
+    written: Vec<PathBuf>,
+}
+
+/// Apply `ops`, each naming a different path, as one unit.
+pub(crate) fn commit(ops: Vec<FileOp>) -> anyhow::Result<()> {
+    let mut tx = Transaction::default();
+    let result = stage(&ops, &mut tx).and_then(|()| swap(&ops, &mut tx));
+    match result {
+        Ok(()) => {
+            sync_parents(&ops);
+            for (_, backup) in tx.backups.into_iter().chain(tx.rewritten) {
+                let _ = std::fs::remove_file(backup);
+            }
+            Ok(())
+        }
+        Err(err) => {
+            tx.roll_back();
+            Err(err.context("patch not applied, no files were changed"))
+        }
+    }
+}
+
+/// Write every new file's contents next to its target and sync it.
+fn stage(ops: &[FileOp], tx: &mut Transaction) -> anyhow::Result<()> {
+    for op in ops {
+        let FileOp::Write { path, bytes } = op else {
+            continue;
+        };
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            create_dirs(parent, tx).with_context(|| {
+                format!("Failed to create parent directories for {}", path.display())
+            })?;
+        }
+        let staged = sibling(path, STAGED_SUFFIX);
+        tx.staged.push((staged.clone(), path.clone()));
+        let mut file = std::fs::File::create(&staged)
+            .with_context(|| format!("Failed to write file {}", path.display()))?;
+        // A replaced file keeps its permissions, e.g. its executable bit.
+        let existing = std::fs::metadata(path);
+        if let Ok(meta) = &existing {
+            copy_owner(&file, meta);
+        }
+        file.write_all(bytes)
+            .and_then(|()| match &existing {
+                Ok(meta) => file.set_permissions(meta.permissions()),
+                Err(_) => Ok(()),
+            })
+            .and_then(|()| file.sync_all())
+            .with_context(|| format!("Failed to write file {}", path.display()))?;
+    }
+    Ok(())
+}
+
+/// Move the previous versions aside and the staged files into place.
+fn swap(ops: &[FileOp], tx: &mut Transaction) -> anyhow::Result<()> {
+    for op in ops {
+        let path = op.path();
+        if let FileOp::Write { bytes, .. } = op
+            && std::fs::symlink_metadata(path).is_ok_and(|meta| has_other_links(&meta))
+        {
+            rewrite_in_place(path, bytes, tx)
+                .with_context(|| format!("Failed to write file {}", path.display()))?;
+            continue;
+        }
+        if std::fs::symlink_metadata(path).is_ok() {
+            let backup = sibling(path, BACKUP_SUFFIX);
+            std::fs::rename(path, &backup).with_context(|| match op {
+                FileOp::Write { .. } => format!("Failed to write file {}", path.display()),
+                FileOp::Remove { .. } => format!("Failed to delete file {}", path.display()),
+            })?;
+            tx.backups.push((path.to_path_buf(), backup));
+        } else if let FileOp::Remove { .. } = op {
+            anyhow::bail!(
+                "Failed to delete file {}: it does not exist",
+                path.display()
+            );
+        }
+        if let FileOp::Write { .. } = op
+            && let Some(index) = tx.staged.iter().position(|(_, target)| target == path)
+        {
+            let (staged, _) = tx.staged.remove(index);
+            std::fs::rename(&staged, path)
+                .with_context(|| format!("Failed to write file {}", path.display()))?;
+            tx.written.push(path.to_path_buf());
+        }
+    }
+    Ok(())
+}
+
+/// Back up a copy of `path` and overwrite it with `bytes`, keeping its inode.
+fn rewrite_in_place(path: &Path, bytes: &[u8], tx: &mut Transaction) -> std::io::Result<()> {
+    let backup = sibling(path, BACKUP_SUFFIX);
+    std::fs::copy(path, &backup)?;
+    tx.rewritten.push((path.to_path_buf(), backup));
+    if let Some(index) = tx.staged.iter().position(|(_, target)| target == path) {
+        let (staged, _) = tx.staged.remove(index);
+        let _ = std::fs::remove_file(staged);
+    }
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .truncate(true)
+        .open(path)?;
+    file.write_all(bytes)?;
+    file.sync_all()
+}
+
+impl Transaction {
+    /// Undo everything, newest first. Failures are ignored: each step
+    /// restores as much as it can.
+    fn roll_back(self) {
+        for path in self.written.iter().rev() {
+            let _ = std::fs::remove_file(path);
+        }
+        for (path, backup) in self.backups.iter().rev() {
+            let _ = std::fs::rename(backup, path);
+        }
+        // Copying back writes through the existing inode, like the rewrite.
+        for (path, backup) in self.rewritten.iter().rev() {
+            if std::fs::copy(backup, path).is_ok() {
+                let _ = std::fs::remove_file(backup);
+            }
+        }
+        for (staged, _) in &self.staged {
+            let _ = std::fs::remove_file(staged);
+        }
+        for dir in self.created_dirs.iter().rev() {
+            let _ = std::fs::remove_dir(dir);
+        }
+    }
+}
+
+/// `create_dir_all`, remembering which directories did not exist yet.
+fn create_dirs(dir: &Path, tx: &mut Transaction) -> std::io::Result<()> {
+    let missing: Vec<&Path> = dir.ancestors().take_while(|d| !d.exists()).collect();
+    for dir in missing.into_iter().rev() {
+        std::fs::create_dir(dir)?;
+        tx.created_dirs.push(dir.to_path_buf());
+    }
+    Ok(())
+}
+
+/// `.<name>.<pid>.<suffix>` in the same directory as `path`, so a rename
+/// between the two never crosses file systems.
+fn sibling(path: &Path, suffix: &str) -> PathBuf {
+    let name = path
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    path.with_file_name(format!(".{name}.{}.{suffix}", std::process::id()))
+}
+
+#[cfg(unix)]
+fn has_other_links(meta: &std::fs::Metadata) -> bool {
+    use std::os::unix::fs::MetadataExt;
+    meta.is_file() && meta.nlink() > 1
+}
+
+#[cfg(not(unix))]
+fn has_other_links(_meta: &std::fs::Metadata) -> bool {
+    false
+}
+
+/// Give the staged file the owner of the file it replaces. Only root may
+/// change the owner, so failures are ignored.
+#[cfg(unix)]
+fn copy_owner(file: &std::fs::File, meta: &std::fs::Metadata) {
+    use std::os::unix::fs::MetadataExt;
+    let _ = std::os::unix::fs::fchown(file, Some(meta.uid()), Some(meta.gid()));
+}
+
+#[cfg(not(unix))]
+fn copy_owner(_file: &std::fs::File, _meta: &std::fs::Metadata) {}
+
+/// Make the renames durable by syncing the directories that hold them.
+#[cfg(unix)]
+fn sync_parents(ops: &[FileOp]) {
+    let mut dirs: Vec<&Path> = ops.iter().filter_map(|op| op.path().parent()).collect();
+    dirs.sort();
+    dirs.dedup();
+    for dir in dirs {
+        if let Ok(dir) = std::fs::File::open(dir) {
+            let _ = dir.sync_all();
+        }
+    }
+}
+
+#[cfg(not(unix))]
+fn sync_parents(_ops: &[FileOp]) {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn failed_commit_restores_every_file() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let kept = dir.path().join("kept.txt");
+        let gone = dir.path().join("gone.txt");
+        std::fs::write(&kept, "old").expect("write");
+        std::fs::write(&gone, "bye").expect("write");
+
+        let ops = vec![
+            FileOp::Write {
+                path: kept.clone(),
+                bytes: b"new".to_vec(),
+            },
+            FileOp::Write {
+                path: dir.path().join("sub/dir/added.txt"),
+                bytes: b"added".to_vec(),
+            },
+            FileOp::Remove { path: gone.clone() },
+            FileOp::Remove {
+                path: dir.path().join("missing.txt"),
+            },
+        ];
+        assert!(commit(ops).is_err());
+
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .expect("read_dir")
+            .map(|entry| {
+                entry
+                    .expect("entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["gone.txt", "kept.txt"]);
+        assert_eq!(std::fs::read_to_string(&kept).expect("read"), "old");
+        assert_eq!(std::fs::read_to_string(&gone).expect("read"), "bye");
+
+        let ops = vec![
+            FileOp::Write {
+                path: kept.clone(),
+                bytes: b"new".to_vec(),
+            },
+            FileOp::Remove { path: gone.clone() },
+        ];
+        commit(ops).expect("commit");
+        assert_eq!(std::fs::read_to_string(&kept).expect("read"), "new");
+        assert!(!gone.exists());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn hard_linked_files_are_rewritten_in_place() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let target = dir.path().join("target.txt");
+        let link = dir.path().join("link.txt");
+        std::fs::write(&target, "old").expect("write");
+        std::fs::hard_link(&target, &link).expect("hard_link");
+
+        let ops = vec![
+            FileOp::Write {
+                path: target.clone(),
+                bytes: b"new".to_vec(),
+            },
+            FileOp::Remove {
+                path: dir.path().join("missing.txt"),
+            },
+        ];
+        assert!(commit(ops).is_err());
+        assert_eq!(std::fs::read_to_string(&link).expect("read"), "old");
+
+        let ops = vec![FileOp::Write {
+            path: target.clone(),
+            bytes: b"new".to_vec(),
+        }];
+        commit(ops).expect("commit");
+        assert_eq!(std::fs::read_to_string(&link).expect("read"), "new");
+        let mut names: Vec<String> = std::fs::read_dir(dir.path())
+            .expect("read_dir")
+            .map(|entry| {
+                entry
+                    .expect("entry")
+                    .file_name()
+                    .to_string_lossy()
+                    .into_owned()
+            })
+            .collect();
+        names.sort();
+        assert_eq!(names, vec!["link.txt", "target.txt"]);
+    }
+}
@@ -2,6 +2,7 @@ use std::future::Future;
 use std::path::Path;
 use std::path::PathBuf;
 
+use codex_apply_patch::WriteOptions;
 use codex_core::CODEX_APPLY_PATCH_ARG1;
 #[cfg(unix)]
 use std::os::unix::fs::symlink;
@@ -55,16 +56,27 @@ where
     let argv1 = args.next().unwrap_or_default();
     if argv1 == CODEX_APPLY_PATCH_ARG1 {
         let patch_arg = args.next().and_then(|s| s.to_str().map(|s| s.to_owned()));
-        let exit_code = match patch_arg {
-            Some(patch_arg) => {
+        // Options for the written files' format follow the patch.
+        let options: Vec<String> = args.map(|arg| arg.to_string_lossy().into_owned()).collect();
+        let exit_code = match (patch_arg, WriteOptions::from_args(&options)) {
+            (Some(patch_arg), Ok(options)) => {
                 let mut stdout = std::io::stdout();
                 let mut stderr = std::io::stderr();
-                match codex_apply_patch::apply_patch(&patch_arg, &mut stdout, &mut stderr) {
+                match codex_apply_patch::apply_patch_with_options(
+                    &patch_arg,
+                    &options,
+                    &mut stdout,
+                    &mut stderr,
+                ) {
                     Ok(()) => 0,
                     Err(_) => 1,
                 }
             }
-            None => {
+            (Some(_), Err(message)) => {
+                eprintln!("Error: {message}");
+                1
+            }
+            (None, _) => {
                 eprintln!("Error: {CODEX_APPLY_PATCH_ARG1} requires a UTF-8 PATCH argument.");
                 1
             }
@@ -13,11 +13,12 @@ pub(crate) async fn chatgpt_get_request<T: DeserializeOwned>(
     path: String,
 ) -> anyhow::Result<T> {
     let chatgpt_base_url = &config.chatgpt_base_url;
+    let url = format!("{chatgpt_base_url}{path}");
+    codex_core::offline::ensure_network_allowed(&url)?;
     init_chatgpt_token_from_auth(&config.codex_home).await?;
 
     // Make direct HTTP request to ChatGPT backend API with the token
     let client = create_client();
-    let url = format!("{chatgpt_base_url}{path}");
 
     let token =
         get_chatgpt_token_data().ok_or_else(|| anyhow::anyhow!("ChatGPT token not available"))?;
@@ -0,0 +1,7 @@
+fn main() {
+    // `codex update` downloads the release artifact built for the same target.
+    if let Ok(target) = std::env::var("TARGET") {
+        println!("cargo:rustc-env=CODEX_BUILD_TARGET={target}");
+    }
+    println!("cargo:rerun-if-env-changed=CODEX_RELEASE_SIGNING_KEY");
+}
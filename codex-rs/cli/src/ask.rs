@@ -0,0 +1,99 @@
+//! `codex ask`: answer a question about the code without changing anything.
+//!
+//! This is `codex exec --read-only` with only the options that make sense for
+//! a question. The session offers the model no tool that can write files or
+//! reach MCP servers, runs shell commands under a read-only sandbox, and never
+//! asks for approval, so it is safe to point at a production checkout.
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_exec::Cli as ExecCli;
+use codex_exec::Color;
+
+#[derive(Debug, Parser)]
+pub struct AskCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Model the agent should use.
+    #[arg(long, short = 'm')]
+    pub model: Option<String>,
+
+    /// Configuration profile from config.toml to specify default options.
+    #[arg(long = "profile", short = 'p')]
+    pub config_profile: Option<String>,
+
+    /// Directory to explore (defaults to the current directory).
+    #[clap(long = "cd", short = 'C', value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Allow running outside a Git repository.
+    #[arg(long = "skip-git-repo-check", default_value_t = false)]
+    pub skip_git_repo_check: bool,
+
+    /// Print events to stdout as JSONL.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// Write the answer to this file as well.
+    #[arg(long = "output-last-message")]
+    pub last_message_file: Option<PathBuf>,
+
+    /// The question. Read from stdin if omitted or `-`.
+    #[arg(value_name = "QUESTION")]
+    pub question: Option<String>,
+}
+
+impl AskCommand {
+    /// The equivalent `codex exec --read-only` invocation.
+    pub fn into_exec_cli(self) -> ExecCli {
+        ExecCli {
+            images: Vec::new(),
+            model: self.model,
+            oss: false,
+            sandbox_mode: None,
+            config_profile: self.config_profile,
+            full_auto: false,
+            dangerously_bypass_approvals_and_sandbox: false,
+            read_only: true,
+            cwd: self.cwd,
+            skip_git_repo_check: self.skip_git_repo_check,
+            offline: false,
+            config_overrides: self.config_overrides,
+            color: Color::Auto,
+            json: self.json,
+            last_message_file: self.last_message_file,
+            result_file: None,
+            ci: false,
+            best_of: None,
+            variant_presets: Vec::new(),
+            judge: false,
+            critic: false,
+            critic_preset: None,
+            critic_rounds: None,
+            detach: false,
+            attachable: false,
+            prompt: self.question,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ask_is_a_read_only_exec() {
+        let ask = AskCommand::try_parse_from(["ask", "-C", "/srv/app", "how does auth work?"])
+            .expect("parse");
+        let exec = ask.into_exec_cli();
+        assert!(exec.read_only);
+        assert!(exec.sandbox_mode.is_none());
+        assert!(!exec.full_auto && !exec.dangerously_bypass_approvals_and_sandbox);
+        assert_eq!(exec.cwd, Some(PathBuf::from("/srv/app")));
+        assert_eq!(exec.prompt.as_deref(), Some("how does auth work?"));
+    }
+}
@@ -0,0 +1,130 @@
+//! `codex attach`: open the TUI on a session running in another process,
+//! started with `codex exec --detach` (or `--attachable`).
+
+use std::path::PathBuf;
+
+use clap::Parser;
+use codex_tui::Cli as TuiCli;
+
+#[derive(Debug, Parser)]
+pub struct AttachCommand {
+    /// Id of the session to attach to, or a unique prefix of it. Without
+    /// one, the only attachable session is used, or all of them are listed.
+    pub id: Option<String>,
+
+    /// Attach through this socket instead, e.g. one forwarded from another
+    /// machine with `ssh -L`.
+    #[arg(long, value_name = "PATH", conflicts_with = "id")]
+    pub socket: Option<PathBuf>,
+}
+
+#[cfg(unix)]
+pub async fn run_attach(
+    cmd: AttachCommand,
+    mut tui_cli: TuiCli,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let socket = match cmd.socket {
+        Some(socket) => socket,
+        None => {
+            let codex_home = codex_core::config::find_codex_home()?;
+            let sessions = codex_core::attach::list_attachable_sessions(&codex_home)?;
+            match pick_session(&sessions, cmd.id.as_deref())? {
+                Some(session) => session.socket.clone(),
+                None => {
+                    print!("{}", format_sessions(&sessions));
+                    return Ok(());
+                }
+            }
+        }
+    };
+    tui_cli.attach = Some(socket);
+    codex_tui::run_main(tui_cli, codex_linux_sandbox_exe).await?;
+    Ok(())
+}
+
+#[cfg(not(unix))]
+pub async fn run_attach(
+    _cmd: AttachCommand,
+    _tui_cli: TuiCli,
+    _codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    anyhow::bail!("codex attach is only supported on Unix")
+}
+
+/// The session `id` names, or the only one when no id is given. `None` when
+/// there are several to choose from.
+#[cfg(unix)]
+fn pick_session<'a>(
+    sessions: &'a [codex_core::attach::AttachableSession],
+    id: Option<&str>,
+) -> anyhow::Result<Option<&'a codex_core::attach::AttachableSession>> {
+    let Some(id) = id else {
+        return match sessions {
+            [] => anyhow::bail!("no attachable sessions; start one with `codex exec --detach`"),
+            [session] => Ok(Some(session)),
+            _ => Ok(None),
+        };
+    };
+    let matching: Vec<_> = sessions
+        .iter()
+        .filter(|session| session.conversation_id.to_string().starts_with(id))
+        .collect();
+    match matching.as_slice() {
+        [] => anyhow::bail!("no attachable session matches {id}"),
+        [session] => Ok(Some(session)),
+        _ => anyhow::bail!("{id} matches several sessions; give more of the id"),
+    }
+}
+
+#[cfg(unix)]
+fn format_sessions(sessions: &[codex_core::attach::AttachableSession]) -> String {
+    let mut out = "Attachable sessions (pass an id to `codex attach`):\n".to_string();
+    for session in sessions {
+        out.push_str(&format!(
+            "  {}  pid {}  {}\n      {}\n",
+            session.conversation_id,
+            session.pid,
+            session.cwd.display(),
+            session.prompt
+        ));
+    }
+    out
+}
+
+#[cfg(all(test, unix))]
+mod tests {
+    use super::*;
+    use codex_core::attach::AttachableSession;
+    use pretty_assertions::assert_eq;
+
+    fn session(id: &str) -> AttachableSession {
+        AttachableSession {
+            conversation_id: serde_json::from_str(&format!("\"{id}\"")).expect("conversation id"),
+            pid: 1,
+            socket: PathBuf::from(format!("/tmp/{id}.sock")),
+            cwd: PathBuf::from("/repo"),
+            prompt: "fix the build".to_string(),
+        }
+    }
+
+    #[test]
+    fn sessions_are_picked_by_unique_id_prefix() {
+        let sessions = vec![
+            session("5f0c8a52-0000-4000-8000-000000000001"),
+            session("5f0c8a52-0000-4000-8000-000000000002"),
+            session("9b1d2e34-0000-4000-8000-000000000003"),
+        ];
+
+        let picked = pick_session(&sessions, Some("9b1d")).expect("pick");
+        assert_eq!(picked, Some(&sessions[2]));
+        assert!(pick_session(&sessions, Some("5f0c")).is_err());
+        assert!(pick_session(&sessions, Some("0000")).is_err());
+        assert_eq!(pick_session(&sessions, None).expect("pick"), None);
+        assert_eq!(
+            pick_session(&sessions[..1], None).expect("pick"),
+            Some(&sessions[0])
+        );
+        assert!(pick_session(&[], None).is_err());
+    }
+}
@@ -0,0 +1,183 @@
+//! Shell completion support.
+//!
+//! `codex completion <shell>` prints a small registration script that calls
+//! back into `codex` (with `COMPLETE=<shell>` set) whenever the user presses
+//! Tab. Completing at runtime rather than from a static script lets `--model`
+//! and `--profile` offer the presets and profiles the user actually has.
+
+use std::collections::BTreeMap;
+use std::ffi::OsStr;
+use std::io::Write;
+
+use clap::Arg;
+use clap::Command;
+use clap_complete::CompletionCandidate;
+use clap_complete::Shell;
+use clap_complete::engine::ArgValueCompleter;
+use clap_complete::env::EnvCompleter;
+use codex_common::model_presets::OwnedModelPreset;
+use codex_common::model_presets::load_model_presets_owned;
+use codex_core::config::ConfigToml;
+use codex_core::config::find_codex_home;
+use codex_core::config::load_config_as_toml_with_cli_overrides;
+
+/// Environment variable that switches `codex` into completion mode.
+pub const COMPLETE_ENV_VAR: &str = "COMPLETE";
+
+const BIN_NAME: &str = "codex";
+
+/// Attach the dynamic `--model` / `--profile` completers to `cmd` and to each
+/// of the named subcommands that accept those flags.
+pub fn with_dynamic_completions(cmd: Command, subcommands: &[&str]) -> Command {
+    let cmd = add_value_completers(cmd);
+    subcommands.iter().fold(cmd, |cmd, name| {
+        cmd.mut_subcommand(*name, add_value_completers)
+    })
+}
+
+fn add_value_completers(cmd: Command) -> Command {
+    cmd.mut_arg("model", |arg: Arg| {
+        arg.add(ArgValueCompleter::new(complete_model))
+    })
+    .mut_arg("config_profile", |arg: Arg| {
+        arg.add(ArgValueCompleter::new(complete_profile))
+    })
+}
+
+/// Write the script that registers `codex` completions with `shell`.
+pub fn write_registration(shell: Shell, buf: &mut dyn Write) -> std::io::Result<()> {
+    let completer: &dyn EnvCompleter = match shell {
+        Shell::Bash => &clap_complete::env::Bash,
+        Shell::Elvish => &clap_complete::env::Elvish,
+        Shell::Fish => &clap_complete::env::Fish,
+        Shell::PowerShell => &clap_complete::env::Powershell,
+        Shell::Zsh => &clap_complete::env::Zsh,
+        _ => {
+            return Err(std::io::Error::new(
+                std::io::ErrorKind::Unsupported,
+                format!("completions are not supported for {shell}"),
+            ));
+        }
+    };
+    completer.write_registration(COMPLETE_ENV_VAR, BIN_NAME, BIN_NAME, BIN_NAME, buf)
+}
+
+fn complete_model(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    model_candidates(&load_model_presets_owned(), load_config_toml().as_ref())
+        .into_iter()
+        .filter(|(model, _)| model.starts_with(prefix.as_ref()))
+        .map(|(model, help)| CompletionCandidate::new(model).help(help.map(Into::into)))
+        .collect()
+}
+
+fn complete_profile(current: &OsStr) -> Vec<CompletionCandidate> {
+    let prefix = current.to_string_lossy();
+    profile_candidates(load_config_toml().as_ref())
+        .into_iter()
+        .filter(|(profile, _)| profile.starts_with(prefix.as_ref()))
+        .map(|(profile, help)| CompletionCandidate::new(profile).help(help.map(Into::into)))
+        .collect()
+}
+
+/// Completion runs on every Tab press, so errors are swallowed: a broken
+/// config.toml just means fewer suggestions.
+fn load_config_toml() -> Option<ConfigToml> {
+    let codex_home = find_codex_home().ok()?;
+    load_config_as_toml_with_cli_overrides(&codex_home, Vec::new()).ok()
+}
+
+/// Model slugs from the presets plus any model named in config.toml, each
+/// with an optional description.
+fn model_candidates(
+    presets: &[OwnedModelPreset],
+    config: Option<&ConfigToml>,
+) -> Vec<(String, Option<String>)> {
+    let mut models: BTreeMap<String, Option<String>> = BTreeMap::new();
+    for preset in presets {
+        let help = (!preset.description.is_empty()).then(|| preset.description.clone());
+        models.entry(preset.model.clone()).or_insert(help);
+    }
+    if let Some(config) = config {
+        if let Some(model) = &config.model {
+            models
+                .entry(model.clone())
+                .or_insert_with(|| Some("from config.toml".to_string()));
+        }
+        for (name, profile) in &config.profiles {
+            if let Some(model) = &profile.model {
+                models
+                    .entry(model.clone())
+                    .or_insert_with(|| Some(format!("from profile {name}")));
+            }
+        }
+    }
+    models.into_iter().collect()
+}
+
+fn profile_candidates(config: Option<&ConfigToml>) -> Vec<(String, Option<String>)> {
+    let Some(config) = config else {
+        return Vec::new();
+    };
+    let mut profiles: Vec<(String, Option<String>)> = config
+        .profiles
+        .iter()
+        .map(|(name, profile)| (name.clone(), profile.model.clone()))
+        .collect();
+    profiles.sort();
+    profiles
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn config() -> ConfigToml {
+        toml::from_str(
+            r#"
+model = "gpt-5"
+
+[profiles.local]
+model = "qwen3-coder"
+model_provider = "oss"
+
+[profiles.fast]
+"#,
+        )
+        .expect("valid config")
+    }
+
+    #[test]
+    fn models_include_presets_and_config() {
+        let presets = vec![OwnedModelPreset {
+            id: "gpt-5-high".to_string(),
+            label: "gpt-5 high".to_string(),
+            description: "Deep reasoning".to_string(),
+            model: "gpt-5".to_string(),
+            effort: None,
+        }];
+        assert_eq!(
+            model_candidates(&presets, Some(&config())),
+            vec![
+                ("gpt-5".to_string(), Some("Deep reasoning".to_string())),
+                (
+                    "qwen3-coder".to_string(),
+                    Some("from profile local".to_string())
+                ),
+            ]
+        );
+    }
+
+    #[test]
+    fn profiles_are_sorted_with_their_model() {
+        assert_eq!(
+            profile_candidates(Some(&config())),
+            vec![
+                ("fast".to_string(), None),
+                ("local".to_string(), Some("qwen3-coder".to_string())),
+            ]
+        );
+        assert_eq!(profile_candidates(None), Vec::new());
+    }
+}
@@ -0,0 +1,280 @@
+//! `codex doctor`: check the local setup and point out recurring problems
+//! recorded in the event log.
+
+use std::time::Duration;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::Local;
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::CodexAuth;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::event_log::EventLogSummary;
+use codex_core::event_log::event_log_status;
+use codex_core::event_log::summarize_event_log;
+use codex_protocol::mcp_protocol::AuthMode;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+/// Tools that failed at least this share of their calls are reported.
+const TOOL_FAILURE_THRESHOLD: f64 = 0.5;
+
+/// Tools called fewer times than this are not judged.
+const MIN_TOOL_CALLS: u64 = 5;
+
+#[derive(Debug, Parser)]
+pub struct DoctorCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Look for recurring problems in the events of the last N days.
+    #[arg(long, value_name = "N", default_value_t = 7)]
+    pub days: u64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Status {
+    Ok,
+    Warning,
+    Error,
+}
+
+#[derive(Debug, PartialEq)]
+struct Check {
+    status: Status,
+    what: String,
+    detail: String,
+}
+
+impl Check {
+    fn new(status: Status, what: impl Into<String>, detail: impl Into<String>) -> Self {
+        Self {
+            status,
+            what: what.into(),
+            detail: detail.into(),
+        }
+    }
+}
+
+pub async fn run_doctor(cmd: DoctorCommand) -> anyhow::Result<()> {
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = match Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default()) {
+        Ok(config) => config,
+        Err(e) => {
+            print_check(&Check::new(Status::Error, "config", e.to_string()));
+            std::process::exit(1);
+        }
+    };
+
+    let mut checks = vec![Check::new(
+        Status::Ok,
+        "config",
+        format!("loaded from {}", config.codex_home.display()),
+    )];
+    checks.push(check_credentials(&config));
+    checks.push(check_git());
+    checks.extend(check_event_log(&config, cmd.days));
+
+    for check in &checks {
+        print_check(check);
+    }
+    if checks.iter().any(|check| check.status == Status::Error) {
+        std::process::exit(1);
+    }
+    Ok(())
+}
+
+fn print_check(check: &Check) {
+    let status = match check.status {
+        Status::Ok => "ok",
+        Status::Warning => "warning",
+        Status::Error => "error",
+    };
+    println!("{status:>7}  {}: {}", check.what, check.detail);
+}
+
+fn check_credentials(config: &Config) -> Check {
+    let provider = &config.model_provider;
+    if provider.requires_openai_auth {
+        return match CodexAuth::from_codex_home(&config.codex_home) {
+            Ok(Some(auth)) => match auth.mode {
+                AuthMode::ApiKey => Check::new(Status::Ok, "login", "using an API key"),
+                AuthMode::ChatGPT => Check::new(Status::Ok, "login", "using ChatGPT"),
+            },
+            Ok(None) => Check::new(Status::Error, "login", "not logged in; run `codex login`"),
+            Err(e) => Check::new(
+                Status::Error,
+                "login",
+                format!("cannot read auth.json: {e}"),
+            ),
+        };
+    }
+    match &provider.env_key {
+        Some(key) if std::env::var(key).is_err() => Check::new(
+            Status::Error,
+            "login",
+            format!("{} needs {key} to be set", provider.name),
+        ),
+        _ => Check::new(
+            Status::Ok,
+            "login",
+            format!("credentials for {} found", provider.name),
+        ),
+    }
+}
+
+fn check_git() -> Check {
+    match std::process::Command::new("git").arg("--version").output() {
+        Ok(output) if output.status.success() => Check::new(
+            Status::Ok,
+            "git",
+            String::from_utf8_lossy(&output.stdout).trim().to_string(),
+        ),
+        _ => Check::new(
+            Status::Warning,
+            "git",
+            "not found; `codex exec` only runs in git repositories unless given \
+             --skip-git-repo-check",
+        ),
+    }
+}
+
+fn check_event_log(config: &Config, days: u64) -> Vec<Check> {
+    if !config.telemetry.local {
+        return vec![Check::new(
+            Status::Ok,
+            "event log",
+            "disabled (enable with `[telemetry] local = true`)",
+        )];
+    }
+    let status = match event_log_status(&config.codex_home) {
+        Ok(Some(status)) => status,
+        Ok(None) => {
+            return vec![Check::new(
+                Status::Ok,
+                "event log",
+                "no events recorded yet",
+            )];
+        }
+        Err(e) => {
+            return vec![Check::new(
+                Status::Warning,
+                "event log",
+                format!("cannot be read: {e:#}"),
+            )];
+        }
+    };
+    let since = status
+        .oldest
+        .map(|oldest| {
+            format!(
+                " since {}",
+                DateTime::<Local>::from(oldest).format("%Y-%m-%d")
+            )
+        })
+        .unwrap_or_default();
+    let mut checks = vec![Check::new(
+        Status::Ok,
+        "event log",
+        format!(
+            "{} events{since} (schema version {})",
+            status.events, status.schema_version
+        ),
+    )];
+
+    let start = SystemTime::now() - Duration::from_secs(days * SECONDS_PER_DAY);
+    match summarize_event_log(&config.codex_home, Some(start)) {
+        Ok(Some(summary)) => checks.extend(diagnose(&summary, days)),
+        Ok(None) => {}
+        Err(e) => checks.push(Check::new(
+            Status::Warning,
+            "event log",
+            format!("cannot be summarized: {e:#}"),
+        )),
+    }
+    checks
+}
+
+/// Recurring errors and failing tools among recent events.
+fn diagnose(summary: &EventLogSummary, days: u64) -> Vec<Check> {
+    let mut checks = Vec::new();
+    for (category, count) in &summary.errors {
+        let hint = match category.as_str() {
+            "auth" => "check `codex login status`",
+            "usage_limit" => "a plan or rate limit was reached",
+            "context_window" => "long sessions ran out of context; try /compact or new sessions",
+            "provider" => "the model provider failed or was unreachable",
+            "sandbox" => "commands were blocked by the sandbox; see docs/sandbox.md",
+            "stream" => "responses were interrupted and retried",
+            _ => "see the session logs for details",
+        };
+        checks.push(Check::new(
+            Status::Warning,
+            format!("{category} errors"),
+            format!("{count} in the last {days} days; {hint}"),
+        ));
+    }
+    for tool in &summary.tools {
+        if tool.calls >= MIN_TOOL_CALLS
+            && tool.failures as f64 / tool.calls as f64 >= TOOL_FAILURE_THRESHOLD
+        {
+            checks.push(Check::new(
+                Status::Warning,
+                format!("{} calls", tool.name),
+                format!(
+                    "{} of {} failed in the last {days} days",
+                    tool.failures, tool.calls
+                ),
+            ));
+        }
+    }
+    checks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::event_log::ToolUsage;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn recurring_errors_and_failing_tools_are_reported() {
+        let tool = |name: &str, calls, failures| ToolUsage {
+            name: name.to_string(),
+            calls,
+            failures,
+            avg_duration_ms: None,
+        };
+        let summary = EventLogSummary {
+            sessions: 4,
+            tools: vec![
+                tool("shell", 40, 2),
+                tool("mcp:jira", 6, 4),
+                tool("apply_patch", 2, 2),
+            ],
+            errors: vec![("auth".to_string(), 3)],
+            features: Vec::new(),
+            aborted_turns: Vec::new(),
+        };
+        assert_eq!(
+            diagnose(&summary, 7),
+            vec![
+                Check::new(
+                    Status::Warning,
+                    "auth errors",
+                    "3 in the last 7 days; check `codex login status`"
+                ),
+                Check::new(
+                    Status::Warning,
+                    "mcp:jira calls",
+                    "4 of 6 failed in the last 7 days"
+                ),
+            ]
+        );
+    }
+}
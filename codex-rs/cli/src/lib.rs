@@ -1,7 +1,18 @@
+pub mod ask;
+pub mod attach;
+pub mod completion;
 pub mod debug_sandbox;
+pub mod doctor;
 mod exit_status;
 pub mod login;
+pub mod prompt;
 pub mod proto;
+pub mod replay;
+pub mod schedule;
+pub mod share;
+pub mod stats;
+pub mod update;
+pub mod work_on;
 
 use clap::Parser;
 use codex_common::CliConfigOverrides;
@@ -25,6 +25,13 @@ pub async fn login_with_chatgpt(codex_home: PathBuf) -> std::io::Result<()> {
 pub async fn run_login_with_chatgpt(cli_config_overrides: CliConfigOverrides) -> ! {
     let config = load_config_or_exit(cli_config_overrides);
 
+    if config.offline.is_some() {
+        eprintln!(
+            "Error logging in: signing in with ChatGPT requires network access (offline mode)"
+        );
+        std::process::exit(1);
+    }
+
     match login_with_chatgpt(config.codex_home).await {
         Ok(_) => {
             eprintln!("Successfully logged in");
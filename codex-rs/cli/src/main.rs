@@ -1,17 +1,39 @@
 use clap::CommandFactory;
 use clap::Parser;
+use clap_complete::CompleteEnv;
 use clap_complete::Shell;
-use clap_complete::generate;
 use codex_arg0::arg0_dispatch_or_else;
 use codex_chatgpt::apply_command::ApplyCommand;
 use codex_chatgpt::apply_command::run_apply_command;
 use codex_cli::LandlockCommand;
 use codex_cli::SeatbeltCommand;
+use codex_cli::ask::AskCommand;
+use codex_cli::attach::AttachCommand;
+use codex_cli::attach::run_attach;
+use codex_cli::completion;
+use codex_cli::doctor::DoctorCommand;
+use codex_cli::doctor::run_doctor;
 use codex_cli::login::run_login_status;
 use codex_cli::login::run_login_with_api_key;
 use codex_cli::login::run_login_with_chatgpt;
 use codex_cli::login::run_logout;
+use codex_cli::prompt::PromptCommand;
+use codex_cli::prompt::run_prompt;
 use codex_cli::proto;
+use codex_cli::replay::ReplayCommand;
+use codex_cli::replay::run_replay;
+use codex_cli::schedule::ScheduleCommand;
+use codex_cli::schedule::run_schedule;
+use codex_cli::share::ImportCommand;
+use codex_cli::share::ShareCommand;
+use codex_cli::share::run_import;
+use codex_cli::share::run_share;
+use codex_cli::stats::StatsCommand;
+use codex_cli::stats::run_stats;
+use codex_cli::update::UpdateCommand;
+use codex_cli::update::run_update;
+use codex_cli::work_on::WorkOnCommand;
+use codex_cli::work_on::run_work_on;
 use codex_common::CliConfigOverrides;
 use codex_exec::Cli as ExecCli;
 use codex_tui::Cli as TuiCli;
@@ -50,6 +72,14 @@ enum Subcommand {
     #[clap(visible_alias = "e")]
     Exec(ExecCli),
 
+    /// Ask a question about the code without changing anything: no file
+    /// writes, no MCP tools, and shell commands only under a read-only sandbox.
+    Ask(AskCommand),
+
+    /// Attach the TUI to a session running in the background (see
+    /// `codex exec --detach`) to watch it, answer approvals and steer it.
+    Attach(AttachCommand),
+
     /// Manage login.
     Login(LoginCommand),
 
@@ -59,13 +89,52 @@ enum Subcommand {
     /// Experimental: run Codex as an MCP server.
     Mcp,
 
+    /// Experimental: serve Codex's sandboxed shell, apply_patch and search
+    /// tools over MCP.
+    McpTools,
+
     /// Run the Protocol stream via stdin/stdout
     #[clap(visible_alias = "p")]
     Proto(ProtoCli),
 
-    /// Generate shell completion scripts.
+    /// Inspect the prompt sent to the model (`codex prompt show`).
+    Prompt(PromptCommand),
+
+    /// Print the shell snippet that enables tab completion (including
+    /// `--model` and `--profile` values), e.g. `source <(codex completion bash)`.
     Completion(CompletionCommand),
 
+    /// Update Codex to the latest release on the selected channel.
+    Update(UpdateCommand),
+
+    /// Step through a recorded session, optionally re-running a turn with a
+    /// different model.
+    Replay(ReplayCommand),
+
+    /// Export a recorded session as a single bundle (redacted, optionally
+    /// signed) that a teammate can open with `codex import`.
+    Share(ShareCommand),
+
+    /// Open a bundle written by `codex share` read-only.
+    Import(ImportCommand),
+
+    /// Summarize past sessions: sessions per week, tokens and estimated cost
+    /// by model, approval rates, the most edited files, and tool calls and
+    /// errors from the local event log.
+    Stats(StatsCommand),
+
+    /// Check the local setup (config, login, git) and report recurring
+    /// errors and failing tools from the local event log.
+    Doctor(DoctorCommand),
+
+    /// Register recurring `codex exec` runs from `[schedules]` in config.toml
+    /// with the platform scheduler.
+    Schedule(ScheduleCommand),
+
+    /// Work on a GitHub or GitLab issue on a new branch and link the result
+    /// back to the issue.
+    WorkOn(WorkOnCommand),
+
     /// Internal debugging commands.
     Debug(DebugArgs),
 
@@ -143,6 +212,11 @@ fn main() -> anyhow::Result<()> {
 }
 
 async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
+    // When invoked by a registered completion script, print candidates and exit.
+    CompleteEnv::with_factory(completion_command)
+        .var(completion::COMPLETE_ENV_VAR)
+        .complete();
+
     let cli = MultitoolCli::parse();
 
     match cli.subcommand {
@@ -158,9 +232,22 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             prepend_config_flags(&mut exec_cli.config_overrides, cli.config_overrides);
             codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
         }
+        Some(Subcommand::Ask(mut ask_cli)) => {
+            prepend_config_flags(&mut ask_cli.config_overrides, cli.config_overrides);
+            codex_exec::run_main(ask_cli.into_exec_cli(), codex_linux_sandbox_exe).await?;
+        }
+        Some(Subcommand::Attach(attach_cli)) => {
+            let mut tui_cli = cli.interactive;
+            prepend_config_flags(&mut tui_cli.config_overrides, cli.config_overrides);
+            run_attach(attach_cli, tui_cli, codex_linux_sandbox_exe).await?;
+        }
         Some(Subcommand::Mcp) => {
             codex_mcp_server::run_main(codex_linux_sandbox_exe, cli.config_overrides).await?;
         }
+        Some(Subcommand::McpTools) => {
+            codex_mcp_server::run_local_tools_main(codex_linux_sandbox_exe, cli.config_overrides)
+                .await?;
+        }
         Some(Subcommand::Login(mut login_cli)) => {
             prepend_config_flags(&mut login_cli.config_overrides, cli.config_overrides);
             match login_cli.action {
@@ -185,7 +272,43 @@ async fn cli_main(codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()
             proto::run_main(proto_cli).await?;
         }
         Some(Subcommand::Completion(completion_cli)) => {
-            print_completion(completion_cli);
+            completion::write_registration(completion_cli.shell, &mut std::io::stdout())?;
+        }
+        Some(Subcommand::Prompt(mut prompt_cli)) => {
+            prepend_config_flags(&mut prompt_cli.config_overrides, cli.config_overrides);
+            run_prompt(prompt_cli).await?;
+        }
+        Some(Subcommand::Update(mut update_cli)) => {
+            prepend_config_flags(&mut update_cli.config_overrides, cli.config_overrides);
+            run_update(update_cli).await?;
+        }
+        Some(Subcommand::Replay(mut replay_cli)) => {
+            prepend_config_flags(&mut replay_cli.config_overrides, cli.config_overrides);
+            run_replay(replay_cli).await?;
+        }
+        Some(Subcommand::Share(mut share_cli)) => {
+            prepend_config_flags(&mut share_cli.config_overrides, cli.config_overrides);
+            run_share(share_cli).await?;
+        }
+        Some(Subcommand::Import(mut import_cli)) => {
+            prepend_config_flags(&mut import_cli.config_overrides, cli.config_overrides);
+            run_import(import_cli).await?;
+        }
+        Some(Subcommand::Stats(mut stats_cli)) => {
+            prepend_config_flags(&mut stats_cli.config_overrides, cli.config_overrides);
+            run_stats(stats_cli).await?;
+        }
+        Some(Subcommand::Doctor(mut doctor_cli)) => {
+            prepend_config_flags(&mut doctor_cli.config_overrides, cli.config_overrides);
+            run_doctor(doctor_cli).await?;
+        }
+        Some(Subcommand::Schedule(mut schedule_cli)) => {
+            prepend_config_flags(&mut schedule_cli.config_overrides, cli.config_overrides);
+            run_schedule(schedule_cli).await?;
+        }
+        Some(Subcommand::WorkOn(mut work_on_cli)) => {
+            prepend_config_flags(&mut work_on_cli.config_overrides, cli.config_overrides);
+            run_work_on(work_on_cli, codex_linux_sandbox_exe).await?;
         }
         Some(Subcommand::Debug(debug_args)) => match debug_args.cmd {
             DebugCommand::Seatbelt(mut seatbelt_cli) => {
@@ -228,8 +351,6 @@ fn prepend_config_flags(
         .splice(0..0, cli_config_overrides.raw_overrides);
 }
 
-fn print_completion(cmd: CompletionCommand) {
-    let mut app = MultitoolCli::command();
-    let name = "codex";
-    generate(cmd.shell, &mut app, name, &mut std::io::stdout());
+fn completion_command() -> clap::Command {
+    completion::with_dynamic_completions(MultitoolCli::command(), &["exec"])
 }
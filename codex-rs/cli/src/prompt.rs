@@ -0,0 +1,72 @@
+//! `codex prompt show`: print the instructions, context and tools a new
+//! session would send with its first request.
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::prompt_preview::format_prompt_preview;
+use codex_core::prompt_preview::preview_prompt;
+use codex_core::trust_store::TrustDecision;
+use codex_core::trust_store::TrustStore;
+use codex_core::trust_store::lock_down_untrusted;
+
+#[derive(Debug, Parser)]
+pub struct PromptCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub action: PromptSubcommand,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum PromptSubcommand {
+    /// Print the assembled prompt. MCP tools are not listed; use `/prompt`
+    /// in a session to see them.
+    Show(PromptShowCommand),
+}
+
+#[derive(Debug, Parser)]
+pub struct PromptShowCommand {
+    /// Show the prompt for this model instead of the configured one.
+    #[arg(long, short = 'm', value_name = "MODEL")]
+    pub model: Option<String>,
+
+    /// Configuration profile from config.toml.
+    #[arg(long, short = 'p', value_name = "PROFILE")]
+    pub profile: Option<String>,
+
+    /// Print the prompt as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+pub async fn run_prompt(cmd: PromptCommand) -> anyhow::Result<()> {
+    let PromptSubcommand::Show(show) = cmd.action;
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let overrides = ConfigOverrides {
+        model: show.model,
+        config_profile: show.profile,
+        ..Default::default()
+    };
+    let mut config = Config::load_with_cli_overrides(cli_overrides, overrides)?;
+    // Show what a session here would actually send: an untrusted directory
+    // runs without its AGENTS.md.
+    if TrustStore::load(&config.codex_home).decision_for(&config.cwd)
+        == Some(TrustDecision::Untrusted)
+    {
+        lock_down_untrusted(&mut config, false);
+    }
+
+    let preview = preview_prompt(&config).await;
+    if show.json {
+        println!("{}", serde_json::to_string_pretty(&preview)?);
+    } else {
+        print!("{}", format_prompt_preview(&preview));
+    }
+    Ok(())
+}
@@ -0,0 +1,360 @@
+//! `codex replay`: step through a recorded session, optionally re-running one
+//! of its turns against a different model.
+
+use std::io::IsTerminal;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::ReplayEntry;
+use codex_core::UserTurn;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::find_codex_home;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::RolloutItem;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_core::read_replay;
+use codex_core::resolve_rollout_path;
+use codex_core::user_turns;
+use codex_protocol::config_types::SandboxMode;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ReasoningItemReasoningSummary;
+use codex_protocol::models::ResponseItem;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::BufReader;
+
+/// Longest summary printed for a single rollout line.
+const SUMMARY_MAX_CHARS: usize = 160;
+
+#[derive(Debug, Parser)]
+pub struct ReplayCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Session to replay: a conversation id (or unique prefix) or a path to a
+    /// rollout `.jsonl` file.
+    #[arg(value_name = "SESSION")]
+    pub session: String,
+
+    /// Pause after every line until Enter is pressed (`q` to stop).
+    #[arg(long, default_value_t = false)]
+    pub step: bool,
+
+    /// Start from this user turn (1-based).
+    #[arg(long, value_name = "N")]
+    pub turn: Option<usize>,
+
+    /// Re-run the selected turn (the last one by default) with this model and
+    /// print its answer next to the recorded one. The re-run uses a read-only
+    /// sandbox and never asks for approval.
+    #[arg(long, short = 'm', value_name = "MODEL")]
+    pub model: Option<String>,
+}
+
+pub async fn run_replay(cmd: ReplayCommand) -> anyhow::Result<()> {
+    let ReplayCommand {
+        config_overrides,
+        session,
+        step,
+        turn,
+        model,
+    } = cmd;
+
+    let codex_home = find_codex_home()?;
+    let path = resolve_rollout_path(&codex_home, &session)?;
+    let entries = read_replay(&path).await?;
+    let turns = user_turns(&entries);
+    let selected = match turn {
+        Some(n) => Some(
+            turns
+                .get(n.wrapping_sub(1))
+                .ok_or_else(|| anyhow::anyhow!("session has {} turns", turns.len()))?,
+        ),
+        None => None,
+    };
+
+    println!("Replaying {} ({} turns)", path.display(), turns.len());
+    let start = selected.map_or(0, |turn| turn.entry_index);
+    let stepping = step && std::io::stdin().is_terminal();
+    let mut stdin = BufReader::new(tokio::io::stdin()).lines();
+    for entry in &entries[start..] {
+        println!("{}", format_entry(entry));
+        if stepping {
+            match stdin.next_line().await? {
+                Some(line) if line.trim() == "q" => break,
+                Some(_) => {}
+                None => break,
+            }
+        }
+    }
+
+    let Some(model) = model else {
+        return Ok(());
+    };
+    let Some(turn) = selected.or(turns.last()) else {
+        anyhow::bail!("session has no user turns to re-run");
+    };
+    rerun_turn(config_overrides, &entries, turn, model, path).await
+}
+
+/// Fork the session just before `turn`, send the same user message to `model`
+/// and compare the outcome with what was recorded.
+async fn rerun_turn(
+    config_overrides: CliConfigOverrides,
+    entries: &[ReplayEntry],
+    turn: &UserTurn,
+    model: String,
+    path: PathBuf,
+) -> anyhow::Result<()> {
+    let cwd = entries.iter().find_map(|entry| match &entry.item {
+        RolloutItem::SessionMeta(meta) if meta.meta.cwd.is_dir() => Some(meta.meta.cwd.clone()),
+        _ => None,
+    });
+    let overrides = ConfigOverrides {
+        model: Some(model.clone()),
+        approval_policy: Some(AskForApproval::Never),
+        sandbox_mode: Some(SandboxMode::ReadOnly),
+        cwd,
+        ..Default::default()
+    };
+    let cli_overrides = config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, overrides)?;
+
+    let conversation_manager =
+        ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+    let NewConversation { conversation, .. } = conversation_manager
+        .fork_conversation(turn.nth_user_message, config, path)
+        .await?;
+
+    println!();
+    println!("Re-running turn with {model}: {}", truncate(&turn.text));
+    conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text {
+                text: turn.text.clone(),
+            }],
+        })
+        .await?;
+    let started = std::time::Instant::now();
+    let rerun_answer = loop {
+        let event = conversation.next_event().await?;
+        match event.msg {
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                break last_agent_message;
+            }
+            EventMsg::AgentMessageDelta(_)
+            | EventMsg::AgentReasoningDelta(_)
+            | EventMsg::AgentReasoningRawContentDelta(_) => {}
+            msg => println!(
+                "[{:>9}] {}",
+                format_offset(started.elapsed()),
+                summarize_event(&msg)
+            ),
+        }
+    };
+    conversation.submit(Op::Shutdown).await.ok();
+
+    println!();
+    println!("Recorded answer:");
+    println!("{}", recorded_answer(entries, turn).unwrap_or("(none)"));
+    println!();
+    println!("{model} answer:");
+    println!("{}", rerun_answer.as_deref().unwrap_or("(none)"));
+    Ok(())
+}
+
+/// The last agent message recorded between `turn` and the next user turn.
+fn recorded_answer<'a>(entries: &'a [ReplayEntry], turn: &UserTurn) -> Option<&'a str> {
+    entries[turn.entry_index + 1..]
+        .iter()
+        .take_while(|entry| !is_user_message(&entry.item))
+        .filter_map(|entry| match &entry.item {
+            RolloutItem::EventMsg(EventMsg::AgentMessage(ev)) => Some(ev.message.as_str()),
+            _ => None,
+        })
+        .last()
+}
+
+fn is_user_message(item: &RolloutItem) -> bool {
+    matches!(item, RolloutItem::ResponseItem(ResponseItem::Message { role, .. }) if role == "user")
+}
+
+pub(crate) fn format_entry(entry: &ReplayEntry) -> String {
+    format!(
+        "[{:>9}] {}",
+        format_offset(entry.offset),
+        summarize_item(&entry.item)
+    )
+}
+
+fn format_offset(offset: Duration) -> String {
+    format!("+{:.3}s", offset.as_secs_f64())
+}
+
+fn summarize_item(item: &RolloutItem) -> String {
+    match item {
+        RolloutItem::SessionMeta(meta) => format!(
+            "session {} in {} (codex {})",
+            meta.meta.id,
+            meta.meta.cwd.display(),
+            meta.meta.cli_version
+        ),
+        RolloutItem::TurnContext(ctx) => format!(
+            "turn context: model={} approval={} sandbox={}",
+            ctx.model, ctx.approval_policy, ctx.sandbox_policy
+        ),
+        RolloutItem::Compacted(_) => "history compacted".to_string(),
+        RolloutItem::ResponseItem(item) => summarize_response_item(item),
+        RolloutItem::EventMsg(msg) => summarize_event(msg),
+        RolloutItem::Approval(approval) => format!(
+            "approval {}: {:?}{}",
+            approval.call_id,
+            approval.decision,
+            if approval.timed_out {
+                " (timed out)"
+            } else {
+                ""
+            }
+        ),
+    }
+}
+
+fn summarize_response_item(item: &ResponseItem) -> String {
+    match item {
+        ResponseItem::Message { role, content, .. } => {
+            let text = content
+                .iter()
+                .filter_map(|item| match item {
+                    ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                        Some(text.as_str())
+                    }
+                    ContentItem::InputImage { .. } => Some("[image]"),
+                })
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("{role}: {}", truncate(&text))
+        }
+        ResponseItem::Reasoning { summary, .. } => {
+            let text = summary
+                .iter()
+                .map(|ReasoningItemReasoningSummary::SummaryText { text }| text.as_str())
+                .collect::<Vec<_>>()
+                .join(" ");
+            format!("reasoning: {}", truncate(&text))
+        }
+        ResponseItem::LocalShellCall { action, .. } => match action {
+            LocalShellAction::Exec(exec) => {
+                format!("shell: {}", truncate(&exec.command.join(" ")))
+            }
+        },
+        ResponseItem::FunctionCall {
+            name, arguments, ..
+        } => format!("call {name}: {}", truncate(arguments)),
+        ResponseItem::FunctionCallOutput { output, .. } => {
+            format!("call output: {}", truncate(&output.content))
+        }
+        ResponseItem::CustomToolCall { name, input, .. } => {
+            format!("call {name}: {}", truncate(input))
+        }
+        ResponseItem::CustomToolCallOutput { output, .. } => {
+            format!("call output: {}", truncate(output))
+        }
+        ResponseItem::WebSearchCall { .. } => "web search".to_string(),
+        ResponseItem::Other => "other item".to_string(),
+    }
+}
+
+fn summarize_event(msg: &EventMsg) -> String {
+    match msg {
+        EventMsg::AgentMessage(ev) => format!("agent: {}", truncate(&ev.message)),
+        EventMsg::UserMessage(ev) => format!("user: {}", truncate(&ev.message)),
+        EventMsg::ExecCommandBegin(ev) => format!("exec: {}", truncate(&ev.command.join(" "))),
+        EventMsg::ExecCommandEnd(ev) => format!("exec exited {}", ev.exit_code),
+        EventMsg::Error(ev) => format!("error: {}", truncate(&ev.message)),
+        EventMsg::TaskComplete(_) => "task complete".to_string(),
+        // The event name is enough for everything else.
+        other => format!("event {other}"),
+    }
+}
+
+fn truncate(text: &str) -> String {
+    let text = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    if text.chars().count() <= SUMMARY_MAX_CHARS {
+        text
+    } else {
+        let prefix: String = text.chars().take(SUMMARY_MAX_CHARS - 1).collect();
+        format!("{prefix}…")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::AgentMessageEvent;
+    use pretty_assertions::assert_eq;
+
+    fn entry(item: RolloutItem) -> ReplayEntry {
+        ReplayEntry {
+            offset: Duration::from_millis(1500),
+            item,
+        }
+    }
+
+    fn message(role: &str, text: &str) -> RolloutItem {
+        RolloutItem::ResponseItem(ResponseItem::Message {
+            id: None,
+            role: role.to_string(),
+            content: vec![ContentItem::InputText {
+                text: text.to_string(),
+            }],
+        })
+    }
+
+    #[test]
+    fn formats_entries_with_offset() {
+        assert_eq!(
+            format_entry(&entry(message("user", "fix\n  the tests"))),
+            "[  +1.500s] user: fix the tests"
+        );
+        assert_eq!(
+            format_entry(&entry(RolloutItem::EventMsg(EventMsg::AgentMessage(
+                AgentMessageEvent {
+                    message: "done".to_string(),
+                }
+            )))),
+            "[  +1.500s] agent: done"
+        );
+    }
+
+    #[test]
+    fn recorded_answer_stops_at_next_turn() {
+        let agent = |text: &str| {
+            entry(RolloutItem::EventMsg(EventMsg::AgentMessage(
+                AgentMessageEvent {
+                    message: text.to_string(),
+                },
+            )))
+        };
+        let entries = vec![
+            entry(message("user", "first")),
+            agent("working on it"),
+            agent("first answer"),
+            entry(message("user", "second")),
+            agent("second answer"),
+        ];
+        let turns = user_turns(&entries);
+        assert_eq!(recorded_answer(&entries, &turns[0]), Some("first answer"));
+        assert_eq!(recorded_answer(&entries, &turns[1]), Some("second answer"));
+    }
+}
@@ -0,0 +1,805 @@
+//! `codex schedule`: recurring `codex exec` runs driven by the platform
+//! scheduler.
+//!
+//! Runs are declared as `[schedules.<name>]` in `config.toml`. `install`
+//! registers each one as a systemd user timer (Linux), a launchd agent
+//! (macOS) or a Task Scheduler task (Windows). The registered job calls
+//! `codex schedule run <name>`, which runs `codex exec`, writes its output to
+//! `$CODEX_HOME/log/schedules/<name>/`, and sends a `scheduled-run-failed`
+//! notification through the configured `notify` program when the run fails.
+
+use std::collections::BTreeSet;
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config::log_dir;
+use codex_core::config_types::ScheduledRun;
+use codex_core::user_notification::UserNotification;
+
+/// Directory under the log directory holding one folder of run logs per
+/// schedule.
+const SCHEDULE_LOG_SUBDIR: &str = "schedules";
+
+/// Run logs kept per schedule; older ones are deleted before each run.
+const MAX_LOGS_PER_SCHEDULE: usize = 30;
+
+/// launchd needs one calendar entry per combination of listed values; specs
+/// that expand beyond this are refused.
+const MAX_LAUNCHD_INTERVALS: usize = 1000;
+
+const WEEKDAYS: [&str; 7] = ["Sun", "Mon", "Tue", "Wed", "Thu", "Fri", "Sat"];
+const MONTHS: [&str; 12] = [
+    "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+];
+
+#[derive(Debug, Parser)]
+pub struct ScheduleCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    #[command(subcommand)]
+    pub action: ScheduleAction,
+}
+
+#[derive(Debug, clap::Subcommand)]
+pub enum ScheduleAction {
+    /// List the runs in `[schedules]` and whether each is installed.
+    List,
+
+    /// Register runs with the platform scheduler. Installs every configured
+    /// run when no name is given; reinstalling updates an existing entry.
+    Install {
+        #[arg(value_name = "NAME")]
+        names: Vec<String>,
+    },
+
+    /// Unregister runs from the platform scheduler.
+    Remove {
+        #[arg(value_name = "NAME", required = true)]
+        names: Vec<String>,
+    },
+
+    /// Run a schedule now, as the platform scheduler does.
+    Run {
+        #[arg(value_name = "NAME")]
+        name: String,
+    },
+}
+
+pub async fn run_schedule(cmd: ScheduleCommand) -> anyhow::Result<()> {
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())?;
+
+    match cmd.action {
+        ScheduleAction::List => list(&config),
+        ScheduleAction::Install { names } => install(&config, names),
+        ScheduleAction::Remove { names } => remove(names),
+        ScheduleAction::Run { name } => run_now(&config, &name).await,
+    }
+}
+
+fn list(config: &Config) -> anyhow::Result<()> {
+    if config.schedules.is_empty() {
+        println!("No runs are configured; add a [schedules.<name>] table to config.toml.");
+        return Ok(());
+    }
+    let scheduler = Scheduler::current()?;
+    for (name, run) in &config.schedules {
+        let state = match CronSpec::parse(&run.cron) {
+            Err(e) => format!("invalid: {e}"),
+            Ok(_) if scheduler.is_installed(name) => "installed".to_string(),
+            Ok(_) => "not installed".to_string(),
+        };
+        println!("{name}  {}  ({state})", run.cron);
+        println!("  in {}: {}", run.cwd.display(), run.prompt);
+    }
+    Ok(())
+}
+
+fn install(config: &Config, names: Vec<String>) -> anyhow::Result<()> {
+    let names = if names.is_empty() {
+        config.schedules.keys().cloned().collect()
+    } else {
+        names
+    };
+    if names.is_empty() {
+        anyhow::bail!("no runs are configured; add a [schedules.<name>] table to config.toml");
+    }
+    let scheduler = Scheduler::current()?;
+    let exe = std::env::current_exe()?;
+    for name in names {
+        validate_name(&name)?;
+        let run = config
+            .schedules
+            .get(&name)
+            .ok_or_else(|| anyhow::anyhow!("no schedule named `{name}` in config.toml"))?;
+        let spec =
+            CronSpec::parse(&run.cron).map_err(|e| anyhow::anyhow!("schedule `{name}`: {e}"))?;
+        let job = Job {
+            name: &name,
+            cron: &run.cron,
+            spec: &spec,
+            exe: &exe,
+            codex_home: &config.codex_home,
+        };
+        scheduler.install(&job)?;
+        println!("Installed `{name}` ({}).", run.cron);
+    }
+    Ok(())
+}
+
+fn remove(names: Vec<String>) -> anyhow::Result<()> {
+    let scheduler = Scheduler::current()?;
+    for name in names {
+        validate_name(&name)?;
+        if !scheduler.is_installed(&name) {
+            println!("`{name}` is not installed.");
+            continue;
+        }
+        scheduler.remove(&name)?;
+        println!("Removed `{name}`.");
+    }
+    Ok(())
+}
+
+/// Run `codex exec` for the schedule, logging its output and notifying the
+/// user when it fails.
+async fn run_now(config: &Config, name: &str) -> anyhow::Result<()> {
+    let run = config
+        .schedules
+        .get(name)
+        .ok_or_else(|| anyhow::anyhow!("no schedule named `{name}` in config.toml"))?;
+
+    let logs = log_dir(config)?.join(SCHEDULE_LOG_SUBDIR).join(name);
+    std::fs::create_dir_all(&logs)?;
+    prune_logs(&logs, MAX_LOGS_PER_SCHEDULE - 1);
+    let log_file = logs.join(format!(
+        "{}.log",
+        chrono::Local::now().format("%Y%m%d-%H%M%S")
+    ));
+    let log = std::fs::File::create(&log_file)?;
+
+    let status = tokio::process::Command::new(std::env::current_exe()?)
+        .args(exec_args(run))
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        .status()
+        .await;
+    let exit_code = match status {
+        Ok(status) if status.success() => {
+            println!("`{name}` finished; output in {}", log_file.display());
+            return Ok(());
+        }
+        Ok(status) => status.code(),
+        Err(e) => {
+            std::fs::write(&log_file, format!("failed to start codex exec: {e}\n"))?;
+            None
+        }
+    };
+
+    if let Some(notify) = &config.notify {
+        UserNotification::ScheduledRunFailed {
+            schedule: name.to_string(),
+            exit_code,
+            log_file: log_file.clone(),
+        }
+        .send(notify);
+    }
+    anyhow::bail!(
+        "scheduled run `{name}` failed; output in {}",
+        log_file.display()
+    )
+}
+
+/// Arguments to `codex` that perform one run.
+fn exec_args(run: &ScheduledRun) -> Vec<String> {
+    let mut args = vec![
+        "exec".to_string(),
+        "--cd".to_string(),
+        run.cwd.to_string_lossy().into_owned(),
+    ];
+    if let Some(profile) = &run.profile {
+        args.push("--profile".to_string());
+        args.push(profile.clone());
+    }
+    if run.full_auto {
+        args.push("--full-auto".to_string());
+    }
+    args.push("--".to_string());
+    args.push(run.prompt.clone());
+    args
+}
+
+/// Delete the oldest logs in `dir` so that at most `keep` remain. Log names
+/// are timestamps, so name order is age order.
+fn prune_logs(dir: &Path, keep: usize) {
+    let Ok(entries) = std::fs::read_dir(dir) else {
+        return;
+    };
+    let mut logs: Vec<PathBuf> = entries
+        .flatten()
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "log"))
+        .collect();
+    logs.sort();
+    let excess = logs.len().saturating_sub(keep);
+    for path in logs.into_iter().take(excess) {
+        let _ = std::fs::remove_file(path);
+    }
+}
+
+/// Schedule names become unit, agent and task names, so they are limited to
+/// characters every scheduler accepts.
+fn validate_name(name: &str) -> anyhow::Result<()> {
+    if name.is_empty()
+        || !name
+            .chars()
+            .all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_')
+    {
+        anyhow::bail!("schedule name `{name}` may only contain letters, digits, `-` and `_`");
+    }
+    Ok(())
+}
+
+/// A parsed five-field cron spec. `None` matches every value of a field.
+#[derive(Debug, Clone, PartialEq, Eq)]
+struct CronSpec {
+    minutes: Option<Vec<u32>>,
+    hours: Option<Vec<u32>>,
+    days: Option<Vec<u32>>,
+    months: Option<Vec<u32>>,
+    /// 0 is Sunday.
+    weekdays: Option<Vec<u32>>,
+}
+
+impl CronSpec {
+    /// Parse `minute hour day-of-month month day-of-week`, with `*`, lists,
+    /// ranges, `/` steps, month and weekday names, and the `@hourly`,
+    /// `@daily`, `@weekly`, `@monthly` and `@yearly` shorthands.
+    fn parse(spec: &str) -> anyhow::Result<Self> {
+        let spec = match spec.trim() {
+            "@hourly" => "0 * * * *",
+            "@daily" | "@midnight" => "0 0 * * *",
+            "@weekly" => "0 0 * * 0",
+            "@monthly" => "0 0 1 * *",
+            "@yearly" | "@annually" => "0 0 1 1 *",
+            other => other,
+        };
+        let fields: Vec<&str> = spec.split_whitespace().collect();
+        let [minute, hour, day, month, weekday] = fields.as_slice() else {
+            anyhow::bail!(
+                "`{spec}` is not a cron spec; expected minute, hour, day of month, month and day of week"
+            );
+        };
+        let weekdays = parse_field(weekday, 0, 7, &WEEKDAYS)?.and_then(|days| {
+            let days: BTreeSet<u32> = days.into_iter().map(|day| day % 7).collect();
+            (days.len() < 7).then(|| days.into_iter().collect())
+        });
+        Ok(Self {
+            minutes: parse_field(minute, 0, 59, &[])?,
+            hours: parse_field(hour, 0, 23, &[])?,
+            days: parse_field(day, 1, 31, &[])?,
+            months: parse_field(month, 1, 12, &MONTHS)?,
+            weekdays,
+        })
+    }
+}
+
+/// Values of one cron field between `min` and `max`, or `None` when it
+/// matches all of them. `names[i]` stands for `min + i`.
+fn parse_field(
+    field: &str,
+    min: u32,
+    max: u32,
+    names: &[&str],
+) -> anyhow::Result<Option<Vec<u32>>> {
+    let value = |text: &str| -> anyhow::Result<u32> {
+        let value = match names
+            .iter()
+            .position(|name| name.eq_ignore_ascii_case(text))
+        {
+            Some(index) => min + index as u32,
+            None => text
+                .parse()
+                .map_err(|_| anyhow::anyhow!("`{text}` in `{field}` is not a number"))?,
+        };
+        if !(min..=max).contains(&value) {
+            anyhow::bail!("{value} in `{field}` is outside {min}-{max}");
+        }
+        Ok(value)
+    };
+
+    let mut values = BTreeSet::new();
+    for part in field.split(',') {
+        let (range, step) = match part.split_once('/') {
+            Some((range, step)) => match step.parse::<u32>() {
+                Ok(step) if step > 0 => (range, step),
+                _ => anyhow::bail!("invalid step `{step}` in `{field}`"),
+            },
+            None => (part, 1),
+        };
+        let (start, end) = if range == "*" {
+            (min, max)
+        } else if let Some((start, end)) = range.split_once('-') {
+            (value(start)?, value(end)?)
+        } else {
+            let start = value(range)?;
+            // `5/15` means every 15 starting at 5.
+            (start, if step > 1 { max } else { start })
+        };
+        if start > end {
+            anyhow::bail!("range `{range}` in `{field}` is backwards");
+        }
+        values.extend((start..=end).step_by(step as usize));
+    }
+    if values.len() == (max - min + 1) as usize {
+        return Ok(None);
+    }
+    Ok(Some(values.into_iter().collect()))
+}
+
+/// Everything a scheduler needs to register one run.
+struct Job<'a> {
+    name: &'a str,
+    cron: &'a str,
+    spec: &'a CronSpec,
+    /// The `codex` executable the job invokes.
+    exe: &'a Path,
+    codex_home: &'a Path,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Scheduler {
+    Systemd,
+    Launchd,
+    TaskScheduler,
+}
+
+impl Scheduler {
+    fn current() -> anyhow::Result<Self> {
+        if cfg!(target_os = "macos") {
+            Ok(Self::Launchd)
+        } else if cfg!(windows) {
+            Ok(Self::TaskScheduler)
+        } else if cfg!(target_os = "linux") {
+            Ok(Self::Systemd)
+        } else {
+            anyhow::bail!(
+                "codex schedule supports systemd (Linux), launchd (macOS) and Task Scheduler (Windows)"
+            )
+        }
+    }
+
+    fn install(self, job: &Job) -> anyhow::Result<()> {
+        match self {
+            Self::Systemd => {
+                let dir = systemd_unit_dir()?;
+                std::fs::create_dir_all(&dir)?;
+                let unit = systemd_unit_name(job.name);
+                let timer = format!("{unit}.timer");
+                std::fs::write(dir.join(format!("{unit}.service")), systemd_service(job))?;
+                std::fs::write(dir.join(&timer), systemd_timer(job))?;
+                run_tool("systemctl", &["--user", "daemon-reload"])?;
+                run_tool("systemctl", &["--user", "enable", "--now", timer.as_str()])
+            }
+            Self::Launchd => {
+                let path = launchd_plist_path(job.name)?;
+                if let Some(dir) = path.parent() {
+                    std::fs::create_dir_all(dir)?;
+                }
+                let plist = launchd_plist(job)?;
+                // Unload any previous version so the new calendar takes effect.
+                let path_arg = path.to_string_lossy().into_owned();
+                let _ = run_tool("launchctl", &["unload", path_arg.as_str()]);
+                std::fs::write(&path, plist)?;
+                run_tool("launchctl", &["load", "-w", path_arg.as_str()])
+            }
+            Self::TaskScheduler => {
+                let task_run = format!("\"{}\" schedule run {}", job.exe.display(), job.name);
+                let mut args = vec![
+                    "/Create".to_string(),
+                    "/F".to_string(),
+                    "/TN".to_string(),
+                    task_name(job.name),
+                    "/TR".to_string(),
+                    task_run,
+                ];
+                args.extend(schtasks_trigger(job.spec, job.cron)?);
+                let args: Vec<&str> = args.iter().map(String::as_str).collect();
+                run_tool("schtasks", &args)
+            }
+        }
+    }
+
+    fn remove(self, name: &str) -> anyhow::Result<()> {
+        match self {
+            Self::Systemd => {
+                let unit = systemd_unit_name(name);
+                let timer = format!("{unit}.timer");
+                let _ = run_tool("systemctl", &["--user", "disable", "--now", timer.as_str()]);
+                let dir = systemd_unit_dir()?;
+                std::fs::remove_file(dir.join(&timer))?;
+                let _ = std::fs::remove_file(dir.join(format!("{unit}.service")));
+                run_tool("systemctl", &["--user", "daemon-reload"])
+            }
+            Self::Launchd => {
+                let path = launchd_plist_path(name)?;
+                let path_arg = path.to_string_lossy().into_owned();
+                let _ = run_tool("launchctl", &["unload", "-w", path_arg.as_str()]);
+                std::fs::remove_file(path)?;
+                Ok(())
+            }
+            Self::TaskScheduler => {
+                let task = task_name(name);
+                run_tool("schtasks", &["/Delete", "/F", "/TN", task.as_str()])
+            }
+        }
+    }
+
+    fn is_installed(self, name: &str) -> bool {
+        match self {
+            Self::Systemd => systemd_unit_dir().is_ok_and(|dir| {
+                dir.join(format!("{}.timer", systemd_unit_name(name)))
+                    .exists()
+            }),
+            Self::Launchd => launchd_plist_path(name).is_ok_and(|path| path.exists()),
+            Self::TaskScheduler => std::process::Command::new("schtasks")
+                .args(["/Query", "/TN", &task_name(name)])
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .is_ok_and(|status| status.success()),
+        }
+    }
+}
+
+/// Run a scheduler's command-line tool, failing with its stderr.
+fn run_tool(program: &str, args: &[&str]) -> anyhow::Result<()> {
+    let output = std::process::Command::new(program)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run {program}: {e}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "{program} {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(())
+}
+
+fn systemd_unit_dir() -> anyhow::Result<PathBuf> {
+    let config_home = std::env::var_os("XDG_CONFIG_HOME")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".config")))
+        .ok_or_else(|| anyhow::anyhow!("could not find the home directory"))?;
+    Ok(config_home.join("systemd").join("user"))
+}
+
+fn systemd_unit_name(name: &str) -> String {
+    format!("codex-schedule-{name}")
+}
+
+fn systemd_service(job: &Job) -> String {
+    let mut environment = format!(
+        "Environment={}\n",
+        systemd_quote(&format!("CODEX_HOME={}", job.codex_home.display()))
+    );
+    // Runs need the same tools the user has on PATH, which a user manager's
+    // default environment may lack.
+    if let Ok(path) = std::env::var("PATH") {
+        environment.push_str(&format!(
+            "Environment={}\n",
+            systemd_quote(&format!("PATH={path}"))
+        ));
+    }
+    format!(
+        "[Unit]\nDescription=Codex scheduled run: {name}\n\n[Service]\nType=oneshot\n{environment}ExecStart={exe} schedule run {name}\n",
+        name = job.name,
+        exe = systemd_quote(&job.exe.to_string_lossy()),
+    )
+}
+
+fn systemd_timer(job: &Job) -> String {
+    let calendars: String = systemd_calendars(job.spec)
+        .into_iter()
+        .map(|calendar| format!("OnCalendar={calendar}\n"))
+        .collect();
+    format!(
+        "[Unit]\nDescription=Codex schedule: {name} ({cron})\n\n[Timer]\n{calendars}Persistent=true\n\n[Install]\nWantedBy=timers.target\n",
+        name = job.name,
+        cron = job.cron,
+    )
+}
+
+/// `OnCalendar=` values for `spec`. Cron runs when either the day of month
+/// or the weekday matches if both are restricted, while systemd requires
+/// both, so that case becomes two calendars.
+fn systemd_calendars(spec: &CronSpec) -> Vec<String> {
+    let list = |field: &Option<Vec<u32>>| match field {
+        None => "*".to_string(),
+        Some(values) => values
+            .iter()
+            .map(|value| format!("{value:02}"))
+            .collect::<Vec<_>>()
+            .join(","),
+    };
+    let calendar = |days: &Option<Vec<u32>>, weekdays: &Option<Vec<u32>>| {
+        let weekdays = match weekdays {
+            None => String::new(),
+            Some(days) => {
+                let names: Vec<&str> = days.iter().map(|day| WEEKDAYS[*day as usize]).collect();
+                format!("{} ", names.join(","))
+            }
+        };
+        format!(
+            "{weekdays}*-{}-{} {}:{}:00",
+            list(&spec.months),
+            list(days),
+            list(&spec.hours),
+            list(&spec.minutes)
+        )
+    };
+    if spec.days.is_some() && spec.weekdays.is_some() {
+        vec![calendar(&spec.days, &None), calendar(&None, &spec.weekdays)]
+    } else {
+        vec![calendar(&spec.days, &spec.weekdays)]
+    }
+}
+
+/// Quote a value for a systemd unit file.
+fn systemd_quote(value: &str) -> String {
+    let escaped = value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('%', "%%");
+    format!("\"{escaped}\"")
+}
+
+fn launchd_label(name: &str) -> String {
+    format!("com.openai.codex.schedule.{name}")
+}
+
+fn launchd_plist_path(name: &str) -> anyhow::Result<PathBuf> {
+    let home =
+        dirs::home_dir().ok_or_else(|| anyhow::anyhow!("could not find the home directory"))?;
+    Ok(home
+        .join("Library")
+        .join("LaunchAgents")
+        .join(format!("{}.plist", launchd_label(name))))
+}
+
+fn launchd_plist(job: &Job) -> anyhow::Result<String> {
+    let string = |value: &str| format!("<string>{}</string>", xml_escape(value));
+    let program: String = [
+        job.exe.to_string_lossy().as_ref(),
+        "schedule",
+        "run",
+        job.name,
+    ]
+    .iter()
+    .map(|arg| format!("\n    {}", string(*arg)))
+    .collect();
+    let mut environment = format!(
+        "\n    <key>CODEX_HOME</key>{}",
+        string(&job.codex_home.to_string_lossy())
+    );
+    if let Ok(path) = std::env::var("PATH") {
+        environment.push_str(&format!("\n    <key>PATH</key>{}", string(&path)));
+    }
+    let intervals: String = launchd_intervals(job.spec, job.cron)?
+        .into_iter()
+        .map(|entry| {
+            let keys: String = entry
+                .into_iter()
+                .map(|(key, value)| format!("<key>{key}</key><integer>{value}</integer>"))
+                .collect();
+            format!("\n    <dict>{keys}</dict>")
+        })
+        .collect();
+    Ok(format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<!DOCTYPE plist PUBLIC "-//Apple//DTD PLIST 1.0//EN" "http://www.apple.com/DTDs/PropertyList-1.0.dtd">
+<plist version="1.0">
+<dict>
+  <key>Label</key>{label}
+  <key>ProgramArguments</key>
+  <array>{program}
+  </array>
+  <key>EnvironmentVariables</key>
+  <dict>{environment}
+  </dict>
+  <key>StartCalendarInterval</key>
+  <array>{intervals}
+  </array>
+</dict>
+</plist>
+"#,
+        label = string(&launchd_label(job.name)),
+    ))
+}
+
+/// launchd `StartCalendarInterval` entries for `spec`: one per combination
+/// of listed values, with matching-everything fields left out.
+fn launchd_intervals(spec: &CronSpec, cron: &str) -> anyhow::Result<Vec<Vec<(&'static str, u32)>>> {
+    let any = None;
+    // As with systemd, a restricted day of month and weekday match
+    // independently.
+    let variants = if spec.days.is_some() && spec.weekdays.is_some() {
+        vec![(&spec.days, &any), (&any, &spec.weekdays)]
+    } else {
+        vec![(&spec.days, &spec.weekdays)]
+    };
+    let mut intervals = Vec::new();
+    for (days, weekdays) in variants {
+        let mut entries: Vec<Vec<(&'static str, u32)>> = vec![Vec::new()];
+        for (key, field) in [
+            ("Month", &spec.months),
+            ("Day", days),
+            ("Weekday", weekdays),
+            ("Hour", &spec.hours),
+            ("Minute", &spec.minutes),
+        ] {
+            let Some(values) = field else {
+                continue;
+            };
+            entries = entries
+                .into_iter()
+                .flat_map(|entry| {
+                    values.iter().map(move |value| {
+                        let mut entry = entry.clone();
+                        entry.push((key, *value));
+                        entry
+                    })
+                })
+                .collect();
+            if entries.len() > MAX_LAUNCHD_INTERVALS {
+                anyhow::bail!(
+                    "`{cron}` needs more than {MAX_LAUNCHD_INTERVALS} launchd calendar entries; use a coarser schedule"
+                );
+            }
+        }
+        intervals.extend(entries);
+    }
+    Ok(intervals)
+}
+
+fn xml_escape(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn task_name(name: &str) -> String {
+    format!("Codex\\{name}")
+}
+
+/// `schtasks /Create` trigger arguments for `spec`. Task Scheduler triggers
+/// are far less general than cron, so only hourly runs at a fixed minute and
+/// daily, weekly or monthly runs at a fixed time are accepted.
+fn schtasks_trigger(spec: &CronSpec, cron: &str) -> anyhow::Result<Vec<String>> {
+    let single = |field: &Option<Vec<u32>>| match field.as_deref() {
+        Some([value]) => Some(*value),
+        _ => None,
+    };
+    let unsupported = || {
+        anyhow::anyhow!(
+            "Task Scheduler cannot run `{cron}`; use a fixed minute, and either every hour or a fixed hour on every day, some weekdays or some days of the month"
+        )
+    };
+    let minute = single(&spec.minutes).ok_or_else(unsupported)?;
+    if spec.months.is_some() {
+        return Err(unsupported());
+    }
+    let join = |values: Vec<String>| values.join(",");
+    let args: Vec<String> = match (&spec.hours, &spec.days, &spec.weekdays) {
+        (None, None, None) => vec!["/SC", "HOURLY", "/ST"]
+            .into_iter()
+            .map(str::to_string)
+            .chain([format!("00:{minute:02}")])
+            .collect(),
+        (Some(_), days, weekdays) => {
+            let hour = single(&spec.hours).ok_or_else(unsupported)?;
+            let start = format!("{hour:02}:{minute:02}");
+            match (days, weekdays) {
+                (None, None) => vec!["/SC".into(), "DAILY".into(), "/ST".into(), start],
+                (None, Some(weekdays)) => vec![
+                    "/SC".into(),
+                    "WEEKLY".into(),
+                    "/D".into(),
+                    join(
+                        weekdays
+                            .iter()
+                            .map(|day| WEEKDAYS[*day as usize].to_ascii_uppercase())
+                            .collect(),
+                    ),
+                    "/ST".into(),
+                    start,
+                ],
+                (Some(days), None) => vec![
+                    "/SC".into(),
+                    "MONTHLY".into(),
+                    "/D".into(),
+                    join(days.iter().map(u32::to_string).collect()),
+                    "/ST".into(),
+                    start,
+                ],
+                (Some(_), Some(_)) => return Err(unsupported()),
+            }
+        }
+        _ => return Err(unsupported()),
+    };
+    Ok(args)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn cron_specs_parse_and_translate_per_scheduler() {
+        let nightly = CronSpec::parse("30 2 * * mon-fri").expect("parse");
+        assert_eq!(
+            nightly,
+            CronSpec {
+                minutes: Some(vec![30]),
+                hours: Some(vec![2]),
+                days: None,
+                months: None,
+                weekdays: Some(vec![1, 2, 3, 4, 5]),
+            }
+        );
+        assert_eq!(
+            systemd_calendars(&nightly),
+            vec!["Mon,Tue,Wed,Thu,Fri *-*-* 02:30:00"]
+        );
+        assert_eq!(
+            schtasks_trigger(&nightly, "30 2 * * mon-fri").expect("schtasks"),
+            vec!["/SC", "WEEKLY", "/D", "MON,TUE,WED,THU,FRI", "/ST", "02:30"]
+        );
+        let intervals = launchd_intervals(&nightly, "30 2 * * mon-fri").expect("launchd");
+        assert_eq!(intervals.len(), 5);
+        assert_eq!(
+            intervals[0],
+            vec![("Weekday", 1), ("Hour", 2), ("Minute", 30)]
+        );
+
+        // Cron matches either field when both days are restricted.
+        let either = CronSpec::parse("0 */12 1,15 * 7").expect("parse");
+        assert_eq!(either.hours, Some(vec![0, 12]));
+        assert_eq!(either.weekdays, Some(vec![0]));
+        assert_eq!(
+            systemd_calendars(&either),
+            vec!["*-*-01,15 00,12:00:00", "Sun *-*-* 00,12:00:00"]
+        );
+        assert_eq!(launchd_intervals(&either, "").expect("launchd").len(), 6);
+        assert!(schtasks_trigger(&either, "").is_err());
+
+        assert_eq!(
+            CronSpec::parse("@daily").expect("parse"),
+            CronSpec::parse("0 0 * * *").expect("parse")
+        );
+        assert_eq!(
+            CronSpec::parse("* * * * 0-7").expect("parse").weekdays,
+            None
+        );
+        assert!(CronSpec::parse("0 24 * * *").is_err());
+        assert!(CronSpec::parse("0 2 * *").is_err());
+        assert!(validate_name("nightly-triage").is_ok());
+        assert!(validate_name("../etc").is_err());
+    }
+}
@@ -0,0 +1,391 @@
+//! `codex share` and `codex import`: hand a recorded session to a teammate.
+//!
+//! A bundle is a single JSON file holding the session's rollout lines and the
+//! patches it applied (see [`codex_core::SharePayload`]), optionally signed
+//! with an ed25519 key kept in `$CODEX_HOME`. The payload is stored as an
+//! embedded JSON string so the signature covers the exact bytes written.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use base64::Engine;
+use base64::prelude::BASE64_STANDARD;
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::Redactor;
+use codex_core::SharePayload;
+use codex_core::build_share_payload;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::read_replay;
+use codex_core::resolve_rollout_path;
+use ed25519_dalek::Signature;
+use ed25519_dalek::Signer;
+use ed25519_dalek::SigningKey;
+use ed25519_dalek::VerifyingKey;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::replay::format_entry;
+
+/// Value of the `format` field of bundles this version writes and reads.
+const BUNDLE_FORMAT: &str = "codex-share/1";
+
+/// File under `$CODEX_HOME` holding the base64 seed of the signing key.
+const SIGNING_KEY_FILE: &str = "share_signing_key";
+
+/// Directory under `$CODEX_HOME` where imported sessions are stored. It is
+/// separate from `sessions/` so imports never show up in `codex resume`.
+const IMPORTED_SUBDIR: &str = "imported";
+
+#[derive(Debug, Parser)]
+pub struct ShareCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Session to export: a conversation id (or unique prefix) or a path to a
+    /// rollout `.jsonl` file.
+    #[arg(value_name = "SESSION")]
+    pub session: String,
+
+    /// Where to write the bundle. Defaults to `<rollout name>.codex-share.json`
+    /// in the current directory.
+    #[arg(long, short = 'o', value_name = "FILE")]
+    pub output: Option<PathBuf>,
+
+    /// Sign the bundle with the key in `$CODEX_HOME/share_signing_key`,
+    /// creating it on first use.
+    #[arg(long, default_value_t = false)]
+    pub sign: bool,
+
+    /// Export the session verbatim, without applying `[share]` redaction.
+    #[arg(long, default_value_t = false)]
+    pub no_redact: bool,
+}
+
+#[derive(Debug, Parser)]
+pub struct ImportCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Bundle written by `codex share`.
+    #[arg(value_name = "BUNDLE")]
+    pub bundle: PathBuf,
+
+    /// Refuse the bundle unless it is signed by this public key (base64, as
+    /// printed by `codex share --sign`).
+    #[arg(long, value_name = "KEY")]
+    pub expect_key: Option<String>,
+
+    /// Print every patch the session applied after the timeline.
+    #[arg(long, default_value_t = false)]
+    pub diffs: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct Bundle {
+    format: String,
+    /// JSON-encoded [`SharePayload`].
+    payload: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    signature: Option<BundleSignature>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct BundleSignature {
+    /// Base64 ed25519 public key.
+    public_key: String,
+    /// Base64 signature over the `payload` string.
+    signature: String,
+}
+
+pub async fn run_share(cmd: ShareCommand) -> anyhow::Result<()> {
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())?;
+
+    let path = resolve_rollout_path(&config.codex_home, &cmd.session)?;
+    let redactor = if cmd.no_redact || !config.share.redact {
+        None
+    } else {
+        Some(Redactor::new(&config.share.redact_patterns)?)
+    };
+    let payload = build_share_payload(&path, redactor.as_ref()).await?;
+    let payload_json = serde_json::to_string(&payload)?;
+    let signature = if cmd.sign {
+        let key = load_or_create_signing_key(&config.codex_home)?;
+        Some(sign(&key, &payload_json))
+    } else {
+        None
+    };
+
+    let output = cmd.output.unwrap_or_else(|| {
+        let stem = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "session".to_string());
+        PathBuf::from(format!("{stem}.codex-share.json"))
+    });
+    let bundle = Bundle {
+        format: BUNDLE_FORMAT.to_string(),
+        payload: payload_json,
+        signature,
+    };
+    std::fs::write(&output, serde_json::to_string_pretty(&bundle)?)?;
+
+    println!(
+        "Wrote {} ({} lines, {} patches, {}).",
+        output.display(),
+        payload.lines.len(),
+        payload.diffs.len(),
+        if payload.redacted {
+            "redacted"
+        } else {
+            "not redacted"
+        }
+    );
+    if let Some(signature) = &bundle.signature {
+        println!("Signed with key {}", signature.public_key);
+    }
+    Ok(())
+}
+
+pub async fn run_import(cmd: ImportCommand) -> anyhow::Result<()> {
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())?;
+
+    let text = std::fs::read_to_string(&cmd.bundle)?;
+    let bundle: Bundle = serde_json::from_str(&text).map_err(|e| {
+        anyhow::anyhow!("{} is not a codex share bundle: {e}", cmd.bundle.display())
+    })?;
+    let signer = verify_bundle(&bundle, cmd.expect_key.as_deref())?;
+    let payload: SharePayload = serde_json::from_str(&bundle.payload)?;
+
+    let name = session_id(&payload).unwrap_or_else(|| {
+        cmd.bundle
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| "session".to_string())
+    });
+    let path = config
+        .codex_home
+        .join(IMPORTED_SUBDIR)
+        .join(format!("{name}.jsonl"));
+    write_read_only(&path, &payload.lines)?;
+
+    match &signer {
+        Some(key) => println!("Signed by {key}"),
+        None => println!("Bundle is not signed"),
+    }
+    println!(
+        "Imported session exported at {}{}",
+        payload.exported_at,
+        if payload.redacted { " (redacted)" } else { "" }
+    );
+    for entry in read_replay(&path).await? {
+        println!("{}", format_entry(&entry));
+    }
+    if cmd.diffs {
+        for diff in &payload.diffs {
+            println!();
+            println!("{} {}", diff.timestamp, diff.call_id);
+            println!("{}", diff.patch);
+            if let Some(output) = &diff.output {
+                println!("-> {output}");
+            }
+        }
+    } else if !payload.diffs.is_empty() {
+        println!();
+        println!(
+            "{} patches applied; rerun with --diffs to print them.",
+            payload.diffs.len()
+        );
+    }
+    println!();
+    println!(
+        "Saved a read-only copy to {0}; step through it with `codex replay {0}`.",
+        path.display()
+    );
+    Ok(())
+}
+
+/// Check the bundle's format and signature, returning the signer's public key
+/// if it is signed.
+fn verify_bundle(bundle: &Bundle, expect_key: Option<&str>) -> anyhow::Result<Option<String>> {
+    if bundle.format != BUNDLE_FORMAT {
+        anyhow::bail!("unsupported bundle format {}", bundle.format);
+    }
+    let Some(signature) = &bundle.signature else {
+        if expect_key.is_some() {
+            anyhow::bail!("bundle is not signed");
+        }
+        return Ok(None);
+    };
+    if let Some(expected) = expect_key
+        && expected.trim() != signature.public_key
+    {
+        anyhow::bail!(
+            "bundle is signed by {}, not the expected key",
+            signature.public_key
+        );
+    }
+
+    let key_bytes: [u8; 32] = BASE64_STANDARD
+        .decode(&signature.public_key)
+        .map_err(|_| anyhow::anyhow!("invalid bundle signing key"))?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("bundle signing key must be 32 bytes"))?;
+    let key = VerifyingKey::from_bytes(&key_bytes)
+        .map_err(|_| anyhow::anyhow!("invalid bundle signing key"))?;
+    let signature_bytes = BASE64_STANDARD
+        .decode(&signature.signature)
+        .map_err(|_| anyhow::anyhow!("malformed bundle signature"))?;
+    let sig = Signature::from_slice(&signature_bytes)
+        .map_err(|_| anyhow::anyhow!("malformed bundle signature"))?;
+    key.verify_strict(bundle.payload.as_bytes(), &sig)
+        .map_err(|_| {
+            anyhow::anyhow!("bundle signature verification failed; it may have been modified")
+        })?;
+    Ok(Some(signature.public_key.clone()))
+}
+
+fn sign(key: &SigningKey, payload: &str) -> BundleSignature {
+    BundleSignature {
+        public_key: BASE64_STANDARD.encode(key.verifying_key().to_bytes()),
+        signature: BASE64_STANDARD.encode(key.sign(payload.as_bytes()).to_bytes()),
+    }
+}
+
+fn load_or_create_signing_key(codex_home: &Path) -> anyhow::Result<SigningKey> {
+    let path = codex_home.join(SIGNING_KEY_FILE);
+    if let Ok(text) = std::fs::read_to_string(&path) {
+        let seed: [u8; 32] = BASE64_STANDARD
+            .decode(text.trim())?
+            .try_into()
+            .map_err(|_| anyhow::anyhow!("{} must hold a 32-byte seed", path.display()))?;
+        return Ok(SigningKey::from_bytes(&seed));
+    }
+
+    let seed: [u8; 32] = rand::random();
+    let mut options = std::fs::OpenOptions::new();
+    options.write(true).create_new(true);
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    std::fs::create_dir_all(codex_home)?;
+    let mut file = options.open(&path)?;
+    std::io::Write::write_all(&mut file, BASE64_STANDARD.encode(seed).as_bytes())?;
+    Ok(SigningKey::from_bytes(&seed))
+}
+
+/// The conversation id from the bundle's session meta line.
+fn session_id(payload: &SharePayload) -> Option<String> {
+    payload
+        .lines
+        .iter()
+        .find(|line| line.get("type").and_then(Value::as_str) == Some("session_meta"))?
+        .get("payload")?
+        .get("id")?
+        .as_str()
+        // The id names a file; never let a crafted bundle pick a path.
+        .filter(|id| !id.is_empty() && id.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .map(str::to_string)
+}
+
+fn write_read_only(path: &Path, lines: &[Value]) -> anyhow::Result<()> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    // A previous import of the same session is read-only too.
+    if let Ok(metadata) = std::fs::metadata(path) {
+        let mut permissions = metadata.permissions();
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        std::fs::set_permissions(path, permissions)?;
+    }
+    let text = lines
+        .iter()
+        .map(|line| format!("{line}\n"))
+        .collect::<String>();
+    std::fs::write(path, text)?;
+    let mut permissions = std::fs::metadata(path)?.permissions();
+    permissions.set_readonly(true);
+    std::fs::set_permissions(path, permissions)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn signed_bundle(payload: &str) -> (Bundle, String) {
+        let key = SigningKey::from_bytes(&[3u8; 32]);
+        let signature = sign(&key, payload);
+        let public_key = signature.public_key.clone();
+        (
+            Bundle {
+                format: BUNDLE_FORMAT.to_string(),
+                payload: payload.to_string(),
+                signature: Some(signature),
+            },
+            public_key,
+        )
+    }
+
+    #[test]
+    fn verifies_signed_bundles_and_detects_tampering() {
+        let (mut bundle, public_key) = signed_bundle("{\"lines\":[]}");
+        assert_eq!(
+            verify_bundle(&bundle, Some(&public_key)).expect("valid"),
+            Some(public_key.clone())
+        );
+        assert!(verify_bundle(&bundle, Some("c29tZW9uZSBlbHNl")).is_err());
+
+        bundle.payload = "{\"lines\":[1]}".to_string();
+        assert!(verify_bundle(&bundle, None).is_err());
+
+        bundle.signature = None;
+        assert_eq!(verify_bundle(&bundle, None).expect("unsigned"), None);
+        assert!(verify_bundle(&bundle, Some(&public_key)).is_err());
+    }
+
+    #[test]
+    fn signing_key_is_created_once() {
+        let home = tempfile::TempDir::new().expect("tempdir");
+        let first = load_or_create_signing_key(home.path()).expect("create");
+        let second = load_or_create_signing_key(home.path()).expect("load");
+        assert_eq!(first.to_bytes(), second.to_bytes());
+    }
+
+    #[test]
+    fn imported_sessions_are_named_by_conversation_id() {
+        let payload = SharePayload {
+            exported_at: "2025-01-01T00:00:00Z".to_string(),
+            redacted: true,
+            lines: vec![json!({
+                "timestamp": "2025-01-01T00:00:00Z",
+                "type": "session_meta",
+                "payload": {"id": "5973b6c0-94b8-487b-a530-2aeb6098ae0e"},
+            })],
+            diffs: Vec::new(),
+        };
+        assert_eq!(
+            session_id(&payload).as_deref(),
+            Some("5973b6c0-94b8-487b-a530-2aeb6098ae0e")
+        );
+
+        let mut crafted = payload;
+        crafted.lines[0]["payload"]["id"] = json!("../../.bashrc");
+        assert_eq!(session_id(&crafted), None);
+    }
+}
@@ -0,0 +1,207 @@
+//! `codex stats`: usage analytics over the sessions recorded under
+//! `CODEX_HOME` and the local event log, computed locally.
+
+use std::path::Path;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::ApprovalCounts;
+use codex_core::UsageStats;
+use codex_core::collect_usage_stats;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::event_log::EventLogSummary;
+use codex_core::event_log::summarize_event_log;
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Parser)]
+pub struct StatsCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Only count sessions started in the last N days.
+    #[arg(long, value_name = "N")]
+    pub days: Option<u64>,
+
+    /// Number of most edited files to list.
+    #[arg(long, value_name = "N", default_value_t = 10)]
+    pub top: usize,
+
+    /// Print the statistics as JSON.
+    #[arg(long, default_value_t = false)]
+    pub json: bool,
+}
+
+pub async fn run_stats(cmd: StatsCommand) -> anyhow::Result<()> {
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())?;
+    let since = cmd
+        .days
+        .map(|days| SystemTime::now() - Duration::from_secs(days * SECONDS_PER_DAY));
+    let mut stats = collect_usage_stats(&config.codex_home, since, &config.model_prices)?;
+    stats.edited_files.truncate(cmd.top);
+    let events = summarize_event_log(&config.codex_home, since).unwrap_or_else(|e| {
+        eprintln!("Failed to read the event log: {e:#}");
+        None
+    });
+
+    if cmd.json {
+        let mut json = serde_json::to_value(&stats)?;
+        if let Some(events) = &events
+            && let Some(object) = json.as_object_mut()
+        {
+            object.insert("event_log".to_string(), serde_json::to_value(events)?);
+        }
+        println!("{}", serde_json::to_string_pretty(&json)?);
+    } else {
+        print!("{}", format_stats(&stats, &config.cwd));
+        if let Some(events) = &events {
+            print!("{}", format_event_log(events));
+        }
+    }
+    Ok(())
+}
+
+fn format_stats(stats: &UsageStats, cwd: &Path) -> String {
+    let mut out = format!("Sessions: {}\n", stats.sessions);
+    for (week, sessions) in &stats.sessions_per_week {
+        out.push_str(&format!("  {week}  {sessions}\n"));
+    }
+
+    if !stats.models.is_empty() {
+        out.push_str("\nTokens by model:\n");
+        for (model, usage) in &stats.models {
+            let cost = usage
+                .cost_usd
+                .map_or_else(|| "no price".to_string(), |cost| format!("~${cost:.2}"));
+            out.push_str(&format!(
+                "  {model}: {} in ({} cached), {} out, {cost}\n",
+                usage.input_tokens, usage.cached_input_tokens, usage.output_tokens
+            ));
+        }
+        if let Some(total) = stats.total_cost_usd() {
+            out.push_str(&format!("  estimated total: ~${total:.2}\n"));
+        }
+    }
+
+    out.push_str(&format!("\nTasks: {}", stats.tasks));
+    if let Some(turns) = stats.turns_per_task() {
+        out.push_str(&format!(", {turns:.1} model turns per task on average"));
+    }
+    out.push('\n');
+
+    out.push_str("\nApprovals:\n");
+    out.push_str(&format!(
+        "  commands: {}\n",
+        format_approvals(&stats.exec_approvals)
+    ));
+    out.push_str(&format!(
+        "  patches: {}\n",
+        format_approvals(&stats.patch_approvals)
+    ));
+
+    if !stats.edited_files.is_empty() {
+        out.push_str("\nMost edited files:\n");
+        for file in &stats.edited_files {
+            let path = file.path.strip_prefix(cwd).unwrap_or(&file.path);
+            out.push_str(&format!("  {:>4}  {}\n", file.edits, path.display()));
+        }
+    }
+    out
+}
+
+/// Tool calls, errors and aborted turns from the event log.
+fn format_event_log(events: &EventLogSummary) -> String {
+    let mut out = String::new();
+    if !events.tools.is_empty() {
+        out.push_str("\nTool calls:\n");
+        for tool in &events.tools {
+            out.push_str(&format!("  {}: {}", tool.name, tool.calls));
+            if tool.failures > 0 {
+                out.push_str(&format!(", {} failed", tool.failures));
+            }
+            if let Some(ms) = tool.avg_duration_ms {
+                out.push_str(&format!(", {ms:.0} ms on average"));
+            }
+            out.push('\n');
+        }
+    }
+    if !events.errors.is_empty() {
+        out.push_str("\nErrors:\n");
+        for (category, count) in &events.errors {
+            out.push_str(&format!("  {category}: {count}\n"));
+        }
+    }
+    if !events.aborted_turns.is_empty() {
+        out.push_str("\nAborted turns:\n");
+        for (reason, count) in &events.aborted_turns {
+            out.push_str(&format!("  {reason}: {count}\n"));
+        }
+    }
+    out
+}
+
+fn format_approvals(counts: &ApprovalCounts) -> String {
+    let Some(rate) = counts.approval_rate() else {
+        return "none requested".to_string();
+    };
+    let mut text = format!(
+        "{} of {} approved ({:.0}%)",
+        counts.approved,
+        counts.total(),
+        rate * 100.0
+    );
+    if counts.timed_out > 0 {
+        text.push_str(&format!(", {} timed out", counts.timed_out));
+    }
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::event_log::ToolUsage;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn approvals_show_rate_and_timeouts() {
+        assert_eq!(
+            format_approvals(&ApprovalCounts::default()),
+            "none requested"
+        );
+        assert_eq!(
+            format_approvals(&ApprovalCounts {
+                approved: 3,
+                denied: 0,
+                timed_out: 1,
+            }),
+            "3 of 4 approved (75%), 1 timed out"
+        );
+    }
+
+    #[test]
+    fn event_log_lists_tools_and_errors() {
+        let events = EventLogSummary {
+            sessions: 2,
+            tools: vec![ToolUsage {
+                name: "shell".to_string(),
+                calls: 12,
+                failures: 3,
+                avg_duration_ms: Some(850.4),
+            }],
+            errors: vec![("usage_limit".to_string(), 1)],
+            features: Vec::new(),
+            aborted_turns: Vec::new(),
+        };
+        assert_eq!(
+            format_event_log(&events),
+            "\nTool calls:\n  shell: 12, 3 failed, 850 ms on average\n\nErrors:\n  usage_limit: 1\n"
+        );
+    }
+}
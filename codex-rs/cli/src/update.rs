@@ -0,0 +1,308 @@
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Context;
+use base64::Engine;
+use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
+use clap::Parser;
+use clap::ValueEnum;
+use codex_common::CliConfigOverrides;
+use codex_common::install_method::InstallMethod;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::config_types::UpdateChannel;
+use codex_core::default_client::create_client;
+use codex_tui::updates::Release;
+use codex_tui::updates::fetch_latest_release;
+use codex_tui::updates::is_newer_on_channel;
+use ed25519_dalek::Signature;
+use ed25519_dalek::VerifyingKey;
+use serde::Deserialize;
+use sha2::Digest;
+use sha2::Sha256;
+
+const CURRENT_VERSION: &str = env!("CARGO_PKG_VERSION");
+const BUILD_TARGET: &str = env!("CODEX_BUILD_TARGET");
+
+/// Base64-encoded ed25519 public key that release manifests are signed with.
+/// Injected by the release workflow; builds without it cannot self-update.
+const RELEASE_SIGNING_KEY: Option<&str> = option_env!("CODEX_RELEASE_SIGNING_KEY");
+
+/// What the release workflow signs for each archive. Signing the version and
+/// target along with the digest stops a validly signed archive from another
+/// release or platform being passed off as this one.
+#[derive(Debug, Deserialize)]
+struct ReleaseManifest {
+    version: String,
+    target: String,
+    archive: String,
+    sha256: String,
+}
+
+#[derive(Debug, Parser)]
+pub struct UpdateCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Release channel to follow. Defaults to `update_channel` from config.toml.
+    #[arg(long, value_enum)]
+    pub channel: Option<UpdateChannelArg>,
+
+    /// Only report whether an update is available; do not install it.
+    #[arg(long, default_value_t = false)]
+    pub check: bool,
+}
+
+#[derive(Debug, Clone, Copy, ValueEnum)]
+pub enum UpdateChannelArg {
+    Stable,
+    Beta,
+}
+
+impl From<UpdateChannelArg> for UpdateChannel {
+    fn from(value: UpdateChannelArg) -> Self {
+        match value {
+            UpdateChannelArg::Stable => UpdateChannel::Stable,
+            UpdateChannelArg::Beta => UpdateChannel::Beta,
+        }
+    }
+}
+
+pub async fn run_update(cmd: UpdateCommand) -> anyhow::Result<()> {
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(cli_overrides, ConfigOverrides::default())?;
+    if config.offline.is_some() {
+        anyhow::bail!("offline mode: `codex update` requires network access");
+    }
+
+    let channel = cmd
+        .channel
+        .map(UpdateChannel::from)
+        .unwrap_or(config.update_channel);
+    let release = fetch_latest_release(channel).await?;
+    if !is_newer_on_channel(&release.version, CURRENT_VERSION, channel).unwrap_or(false) {
+        println!("codex {CURRENT_VERSION} is up to date ({channel:?} channel).");
+        return Ok(());
+    }
+    println!(
+        "Update available: {CURRENT_VERSION} -> {}.",
+        release.version
+    );
+
+    let install_method = InstallMethod::detect();
+    let pinned_version = matches!(channel, UpdateChannel::Beta).then_some(release.version.as_str());
+    if let Some(upgrade_cmd) = install_method.upgrade_command(pinned_version) {
+        println!("codex was installed with {install_method:?}; run `{upgrade_cmd}` to update.");
+        return Ok(());
+    }
+    if cmd.check {
+        println!("Run `codex update` to install it.");
+        return Ok(());
+    }
+
+    let binary = download_verified_binary(&release).await?;
+    let exe = std::env::current_exe().context("failed to locate the running codex binary")?;
+    replace_executable(&exe, &binary)?;
+    println!("Updated {} to {}.", exe.display(), release.version);
+    Ok(())
+}
+
+fn binary_name() -> String {
+    if cfg!(windows) {
+        format!("codex-{BUILD_TARGET}.exe")
+    } else {
+        format!("codex-{BUILD_TARGET}")
+    }
+}
+
+async fn download_verified_binary(release: &Release) -> anyhow::Result<Vec<u8>> {
+    let Some(public_key) = RELEASE_SIGNING_KEY else {
+        anyhow::bail!(
+            "this build of codex cannot verify release signatures; download {} manually from https://github.com/openai/codex/releases",
+            release.version
+        );
+    };
+
+    let archive_name = format!("{}.tar.gz", binary_name());
+    let manifest_name = format!("{archive_name}.manifest.json");
+    let signature_name = format!("{manifest_name}.sig");
+    let mut urls = Vec::new();
+    for name in [&archive_name, &manifest_name, &signature_name] {
+        let asset = release
+            .asset(name)
+            .with_context(|| format!("release {} has no {name}", release.version))?;
+        urls.push(asset.browser_download_url.as_str());
+    }
+
+    let manifest = download(urls[1]).await?;
+    let signature = download(urls[2]).await?;
+    verify_signature(public_key, &manifest, &signature)?;
+    let archive = download(urls[0]).await?;
+    verify_manifest(&manifest, &release.version, &archive_name, &archive)?;
+    extract_binary(&archive, &binary_name())
+}
+
+async fn download(url: &str) -> anyhow::Result<Vec<u8>> {
+    let bytes = create_client()
+        .get(url)
+        .send()
+        .await?
+        .error_for_status()?
+        .bytes()
+        .await?;
+    Ok(bytes.to_vec())
+}
+
+fn verify_signature(public_key_b64: &str, data: &[u8], signature: &[u8]) -> anyhow::Result<()> {
+    let key_bytes: [u8; 32] = BASE64_STANDARD
+        .decode(public_key_b64.trim())
+        .context("invalid release signing key")?
+        .try_into()
+        .map_err(|_| anyhow::anyhow!("release signing key must be 32 bytes"))?;
+    let key = VerifyingKey::from_bytes(&key_bytes).context("invalid release signing key")?;
+    let signature = Signature::from_slice(signature).context("malformed release signature")?;
+    key.verify_strict(data, &signature)
+        .context("release signature verification failed; refusing to install")
+}
+
+/// Check that the signed `manifest` describes `archive` as `archive_name` of
+/// `version` for this build's target.
+fn verify_manifest(
+    manifest: &[u8],
+    version: &str,
+    archive_name: &str,
+    archive: &[u8],
+) -> anyhow::Result<()> {
+    let manifest: ReleaseManifest =
+        serde_json::from_slice(manifest).context("malformed release manifest")?;
+    if manifest.version != version {
+        anyhow::bail!(
+            "release manifest is for version {}, not {version}; refusing to install",
+            manifest.version
+        );
+    }
+    if manifest.target != BUILD_TARGET || manifest.archive != archive_name {
+        anyhow::bail!(
+            "release manifest is for {} ({}), not {archive_name} ({BUILD_TARGET}); refusing to install",
+            manifest.archive,
+            manifest.target
+        );
+    }
+    let digest = format!("{:x}", Sha256::digest(archive));
+    if !manifest.sha256.eq_ignore_ascii_case(&digest) {
+        anyhow::bail!("{archive_name} does not match its signed digest; refusing to install");
+    }
+    Ok(())
+}
+
+/// Pull the single `name` entry out of a release `.tar.gz`.
+fn extract_binary(archive: &[u8], name: &str) -> anyhow::Result<Vec<u8>> {
+    let mut archive = tar::Archive::new(flate2::read::GzDecoder::new(archive));
+    for entry in archive.entries()? {
+        let mut entry = entry?;
+        if entry.path()?.as_os_str() == name {
+            let mut binary = Vec::new();
+            entry.read_to_end(&mut binary)?;
+            return Ok(binary);
+        }
+    }
+    anyhow::bail!("release archive does not contain {name}")
+}
+
+/// Swap `exe` for `binary`. The new file is written next to the old one and
+/// renamed into place so an interrupted update never leaves a partial binary.
+fn replace_executable(exe: &Path, binary: &[u8]) -> anyhow::Result<()> {
+    let staged = exe.with_extension("update");
+    std::fs::write(&staged, binary)
+        .with_context(|| format!("failed to write {}", staged.display()))?;
+
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        std::fs::set_permissions(&staged, std::fs::Permissions::from_mode(0o755))?;
+    }
+
+    // Windows refuses to overwrite a running executable but does allow
+    // renaming it out of the way.
+    #[cfg(windows)]
+    {
+        let old = exe.with_extension("old.exe");
+        let _ = std::fs::remove_file(&old);
+        std::fs::rename(exe, &old)?;
+    }
+
+    std::fs::rename(&staged, exe).with_context(|| format!("failed to replace {}", exe.display()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ed25519_dalek::Signer;
+    use ed25519_dalek::SigningKey;
+    use pretty_assertions::assert_eq;
+
+    fn tarball(name: &str, contents: &[u8]) -> Vec<u8> {
+        let encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        let mut builder = tar::Builder::new(encoder);
+        let mut header = tar::Header::new_gnu();
+        header.set_size(contents.len() as u64);
+        header.set_mode(0o755);
+        header.set_cksum();
+        builder
+            .append_data(&mut header, name, contents)
+            .expect("append");
+        builder.into_inner().expect("tar").finish().expect("gzip")
+    }
+
+    fn manifest(version: &str, target: &str, archive: &[u8]) -> Vec<u8> {
+        serde_json::json!({
+            "version": version,
+            "target": target,
+            "archive": "codex-test.tar.gz",
+            "sha256": format!("{:x}", Sha256::digest(archive)),
+        })
+        .to_string()
+        .into_bytes()
+    }
+
+    #[test]
+    fn verifies_signed_manifest_and_extracts_binary() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = BASE64_STANDARD.encode(signing_key.verifying_key().to_bytes());
+        let archive = tarball("codex-test", b"binary");
+        let manifest = manifest("1.2.3", BUILD_TARGET, &archive);
+        let signature = signing_key.sign(&manifest).to_bytes();
+
+        verify_signature(&public_key, &manifest, &signature).expect("valid signature");
+        verify_manifest(&manifest, "1.2.3", "codex-test.tar.gz", &archive).expect("valid manifest");
+        assert_eq!(
+            extract_binary(&archive, "codex-test").expect("extract"),
+            b"binary".to_vec()
+        );
+        assert!(extract_binary(&archive, "codex-other").is_err());
+    }
+
+    #[test]
+    fn rejects_tampered_or_mismatched_releases() {
+        let signing_key = SigningKey::from_bytes(&[7u8; 32]);
+        let public_key = BASE64_STANDARD.encode(signing_key.verifying_key().to_bytes());
+        let archive = tarball("codex-test", b"binary");
+        let manifest = manifest("1.2.3", BUILD_TARGET, &archive);
+        let signature = signing_key.sign(&manifest).to_bytes();
+
+        let tampered = String::from_utf8(manifest.clone())
+            .expect("utf-8")
+            .replace("1.2.3", "9.9.9");
+        assert!(verify_signature(&public_key, tampered.as_bytes(), &signature).is_err());
+
+        let evil = tarball("codex-test", b"evil");
+        assert!(verify_manifest(&manifest, "1.2.3", "codex-test.tar.gz", &evil).is_err());
+        // A signed older release cannot be served as the latest one.
+        assert!(verify_manifest(&manifest, "1.2.4", "codex-test.tar.gz", &archive).is_err());
+        let other_target = manifest("1.2.3", "other-target", &archive);
+        assert!(verify_manifest(&other_target, "1.2.3", "codex-test.tar.gz", &archive).is_err());
+    }
+}
@@ -0,0 +1,289 @@
+//! `codex work-on <issue-url>`: resolve a GitHub or GitLab issue end to end.
+//!
+//! The issue's title, description and comments become the prompt. The agent
+//! runs as `codex exec --full-auto` (read-only in a directory marked
+//! untrusted) on a fresh branch named after the issue, its changes are
+//! committed, and the commits are linked back to the issue in a comment. With
+//! `--push`, the branch is pushed and a pull or merge request is opened too.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+
+use clap::Parser;
+use codex_common::CliConfigOverrides;
+use codex_core::code_host::IssueClient;
+use codex_core::code_host::IssueRef;
+use codex_core::code_host::issue_prompt;
+use codex_core::config::Config;
+use codex_core::config::ConfigOverrides;
+use codex_core::trust_store::TrustDecision;
+use codex_core::trust_store::TrustStore;
+use codex_exec::Cli as ExecCli;
+use codex_exec::Color;
+
+/// Longest slug of the issue title used in branch names.
+const MAX_SLUG_CHARS: usize = 40;
+
+/// Fetch an issue, work on it on a new branch and link the result back.
+///
+/// Reading public issues needs no token. Commenting and opening pull requests
+/// use GITHUB_TOKEN (or GH_TOKEN) for GitHub and CODEX_GITLAB_TOKEN (or
+/// GITLAB_TOKEN) for GitLab.
+#[derive(Debug, Parser)]
+pub struct WorkOnCommand {
+    #[clap(skip)]
+    pub config_overrides: CliConfigOverrides,
+
+    /// Model the agent should use.
+    #[arg(long, short = 'm')]
+    pub model: Option<String>,
+
+    /// Configuration profile from config.toml to specify default options.
+    #[arg(long = "profile", short = 'p')]
+    pub config_profile: Option<String>,
+
+    /// Repository to work in (defaults to the current directory).
+    #[clap(long = "cd", short = 'C', value_name = "DIR")]
+    pub cwd: Option<PathBuf>,
+
+    /// Branch to create (defaults to `codex/<number>-<title>`).
+    #[arg(long, value_name = "NAME")]
+    pub branch: Option<String>,
+
+    /// Push the branch to this remote and open a pull or merge request.
+    #[arg(long, value_name = "REMOTE", num_args = 0..=1, default_missing_value = "origin")]
+    pub push: Option<String>,
+
+    /// Do not comment on the issue.
+    #[arg(long = "no-link", default_value_t = false)]
+    pub no_link: bool,
+
+    /// Print events to stdout as JSONL.
+    #[arg(long = "json", default_value_t = false)]
+    pub json: bool,
+
+    /// URL of the issue, e.g. https://github.com/owner/repo/issues/42 or
+    /// https://gitlab.com/group/project/-/issues/7.
+    #[arg(value_name = "ISSUE_URL")]
+    pub issue_url: String,
+}
+
+pub async fn run_work_on(
+    cmd: WorkOnCommand,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+) -> anyhow::Result<()> {
+    let repo = match &cmd.cwd {
+        Some(cwd) => cwd.clone(),
+        None => std::env::current_dir()?,
+    };
+    // Loading the config applies offline mode, which must be in force before
+    // the issue is fetched.
+    let cli_overrides = cmd
+        .config_overrides
+        .parse_overrides()
+        .map_err(anyhow::Error::msg)?;
+    let config = Config::load_with_cli_overrides(
+        cli_overrides,
+        ConfigOverrides {
+            config_profile: cmd.config_profile.clone(),
+            cwd: Some(repo.clone()),
+            ..Default::default()
+        },
+    )?;
+    // `--full-auto` would override the read-only sandbox of a directory the
+    // user declined to trust, so it is only used for the others.
+    let untrusted = TrustStore::load(&config.codex_home).decision_for(&config.cwd)
+        == Some(TrustDecision::Untrusted);
+    if untrusted {
+        eprintln!(
+            "{} is marked untrusted; the agent runs with a read-only sandbox.",
+            repo.display()
+        );
+    }
+
+    let issue_ref = IssueRef::parse(&cmd.issue_url)?;
+    let client = IssueClient::new(issue_ref.clone());
+    let issue = client.fetch().await?;
+    eprintln!("Working on #{}: {}", issue_ref.number, issue.title);
+
+    if !git(&repo, &["status", "--porcelain"])?.is_empty() {
+        anyhow::bail!("the working tree has uncommitted changes; commit or stash them first");
+    }
+    let base_branch = git(&repo, &["rev-parse", "--abbrev-ref", "HEAD"])?;
+    // The base branch is the target of the pull request and named in the
+    // output; a detached HEAD has no name to give.
+    if base_branch == "HEAD" {
+        anyhow::bail!("HEAD is detached; check out the branch to base the work on first");
+    }
+    let base_commit = git(&repo, &["rev-parse", "HEAD"])?;
+    let branch = cmd
+        .branch
+        .clone()
+        .unwrap_or_else(|| branch_name(issue_ref.number, &issue.title));
+    git(&repo, &["switch", "--create", &branch])?;
+    eprintln!("Created branch {branch} from {base_branch}");
+
+    let exec_cli = ExecCli {
+        images: Vec::new(),
+        model: cmd.model,
+        oss: false,
+        sandbox_mode: None,
+        config_profile: cmd.config_profile,
+        full_auto: !untrusted,
+        dangerously_bypass_approvals_and_sandbox: false,
+        read_only: false,
+        cwd: cmd.cwd,
+        skip_git_repo_check: false,
+        offline: false,
+        config_overrides: cmd.config_overrides,
+        color: Color::Auto,
+        json: cmd.json,
+        last_message_file: None,
+        result_file: None,
+        ci: false,
+        best_of: None,
+        variant_presets: Vec::new(),
+        judge: false,
+        critic: false,
+        critic_preset: None,
+        critic_rounds: None,
+        detach: false,
+        attachable: false,
+        prompt: Some(issue_prompt(&issue)),
+    };
+    // A failed run exits from here with `codex exec`'s exit code.
+    codex_exec::run_main(exec_cli, codex_linux_sandbox_exe).await?;
+
+    // The sandbox keeps .git read-only, so the agent cannot commit its own
+    // work; commit whatever it left in the working tree.
+    if !git(&repo, &["status", "--porcelain"])?.is_empty() {
+        git(&repo, &["add", "--all"])?;
+        let refs = format!("Refs {}", issue.url);
+        git(
+            &repo,
+            &["commit", "--quiet", "-m", &issue.title, "-m", &refs],
+        )?;
+    }
+
+    let range = format!("{base_commit}..HEAD");
+    let commits = git(&repo, &["log", "--reverse", "--format=%h %s", &range])?;
+    if commits.is_empty() {
+        eprintln!("The agent made no changes on {branch}; nothing to link.");
+        return Ok(());
+    }
+
+    let mut pull_request = None;
+    if let Some(remote) = &cmd.push {
+        git(&repo, &["push", "--set-upstream", remote, &branch])?;
+        let body = format!("Closes {}", issue.url);
+        let url = client
+            .open_pull_request(&branch, &base_branch, &issue.title, &body)
+            .await?;
+        eprintln!("Opened {url}");
+        pull_request = Some(url);
+    }
+
+    if cmd.no_link {
+        return Ok(());
+    }
+    if !client.has_token() {
+        eprintln!(
+            "No {} token set; not linking the commits to the issue.",
+            issue_ref.host_name()
+        );
+        return Ok(());
+    }
+    client
+        .comment(&link_comment(&branch, &commits, pull_request.as_deref()))
+        .await?;
+    eprintln!("Linked the work to {}", issue.url);
+    Ok(())
+}
+
+/// `codex/<number>-<slug of title>`.
+fn branch_name(number: u64, title: &str) -> String {
+    let mut slug = String::new();
+    for ch in title.chars().flat_map(char::to_lowercase) {
+        if ch.is_ascii_alphanumeric() {
+            slug.push(ch);
+        } else if !slug.is_empty() && !slug.ends_with('-') {
+            slug.push('-');
+        }
+        if slug.len() >= MAX_SLUG_CHARS {
+            break;
+        }
+    }
+    let slug = slug.trim_end_matches('-');
+    if slug.is_empty() {
+        format!("codex/{number}")
+    } else {
+        format!("codex/{number}-{slug}")
+    }
+}
+
+/// The comment that links the work to the issue. `commits` holds one
+/// `<short sha> <subject>` line per commit.
+fn link_comment(branch: &str, commits: &str, pull_request: Option<&str>) -> String {
+    let mut comment = format!("Codex worked on this issue on branch `{branch}`:\n\n");
+    for commit in commits.lines() {
+        comment.push_str(&format!("- {commit}\n"));
+    }
+    if let Some(url) = pull_request {
+        comment.push_str(&format!("\nPull request: {url}\n"));
+    }
+    comment
+}
+
+/// Run git in `repo` and return its trimmed stdout.
+fn git(repo: &Path, args: &[&str]) -> anyhow::Result<String> {
+    let output = std::process::Command::new("git")
+        .arg("-C")
+        .arg(repo)
+        .args(args)
+        .stdin(Stdio::null())
+        .output()
+        .map_err(|e| anyhow::anyhow!("failed to run git: {e}"))?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn branches_and_links_are_named_after_the_issue() {
+        assert_eq!(
+            branch_name(42, "Crash when `config.toml` is empty!"),
+            "codex/42-crash-when-config-toml-is-empty"
+        );
+        assert_eq!(branch_name(7, "???"), "codex/7");
+        assert_eq!(
+            branch_name(
+                9,
+                "A very long title that keeps going well past the limit for branch names"
+            ),
+            "codex/9-a-very-long-title-that-keeps-going-well"
+        );
+
+        assert_eq!(
+            link_comment(
+                "codex/42-crash",
+                "abc1234 Fix crash\ndef5678 Add test",
+                Some("https://github.com/o/r/pull/43"),
+            ),
+            "Codex worked on this issue on branch `codex/42-crash`:\n\n\
+             - abc1234 Fix crash\n\
+             - def5678 Add test\n\n\
+             Pull request: https://github.com/o/r/pull/43\n"
+        );
+    }
+}
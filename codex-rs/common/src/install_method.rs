@@ -0,0 +1,85 @@
+use std::path::Path;
+
+/// How the running `codex` binary was installed. Package-manager installs must
+/// be upgraded through that package manager; only standalone binaries may
+/// replace themselves via `codex update`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InstallMethod {
+    /// Installed via `npm install -g @openai/codex`. The npm launcher sets
+    /// `CODEX_MANAGED_BY_NPM` before exec'ing the native binary.
+    Npm,
+    /// Installed via `brew install codex`.
+    Homebrew,
+    /// A binary downloaded from GitHub releases (or built from source).
+    Standalone,
+}
+
+impl InstallMethod {
+    pub fn detect() -> Self {
+        let managed_by_npm = std::env::var_os("CODEX_MANAGED_BY_NPM").is_some();
+        match std::env::current_exe() {
+            Ok(exe) => Self::detect_from(managed_by_npm, &exe),
+            Err(_) if managed_by_npm => InstallMethod::Npm,
+            Err(_) => InstallMethod::Standalone,
+        }
+    }
+
+    fn detect_from(managed_by_npm: bool, exe: &Path) -> Self {
+        if managed_by_npm {
+            return InstallMethod::Npm;
+        }
+        let in_cellar = exe
+            .components()
+            .any(|component| component.as_os_str() == "Cellar");
+        let in_brew_prefix = cfg!(target_os = "macos")
+            && (exe.starts_with("/opt/homebrew") || exe.starts_with("/usr/local"));
+        if in_cellar || in_brew_prefix || exe.starts_with("/home/linuxbrew/.linuxbrew") {
+            InstallMethod::Homebrew
+        } else {
+            InstallMethod::Standalone
+        }
+    }
+
+    /// The command the user should run to upgrade a package-manager install,
+    /// or `None` for standalone binaries (which `codex update` handles).
+    /// `version` pins a specific release, which is needed for pre-releases.
+    pub fn upgrade_command(self, version: Option<&str>) -> Option<String> {
+        match self {
+            InstallMethod::Npm => {
+                let version = version.unwrap_or("latest");
+                Some(format!("npm install -g @openai/codex@{version}"))
+            }
+            InstallMethod::Homebrew => Some("brew upgrade codex".to_string()),
+            InstallMethod::Standalone => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::path::PathBuf;
+
+    #[test]
+    fn npm_takes_precedence_over_path() {
+        let exe = PathBuf::from("/opt/homebrew/Cellar/codex/0.1.0/bin/codex");
+        assert_eq!(InstallMethod::detect_from(true, &exe), InstallMethod::Npm);
+        assert_eq!(
+            InstallMethod::detect_from(false, &exe),
+            InstallMethod::Homebrew
+        );
+        assert_eq!(
+            InstallMethod::detect_from(false, &PathBuf::from("/home/me/bin/codex")),
+            InstallMethod::Standalone
+        );
+    }
+
+    #[test]
+    fn upgrade_command_pins_version() {
+        assert_eq!(
+            InstallMethod::Npm.upgrade_command(Some("0.40.0-beta.1")),
+            Some("npm install -g @openai/codex@0.40.0-beta.1".to_string())
+        );
+        assert_eq!(InstallMethod::Standalone.upgrade_command(None), None);
+    }
+}
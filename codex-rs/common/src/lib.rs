@@ -4,6 +4,9 @@ mod approval_mode_cli_arg;
 #[cfg(feature = "elapsed")]
 pub mod elapsed;
 
+#[cfg(feature = "logging")]
+pub mod logging;
+
 #[cfg(feature = "cli")]
 pub use approval_mode_cli_arg::ApprovalModeCliArg;
 
@@ -34,3 +37,5 @@ pub mod model_presets;
 // Shared approval presets (AskForApproval + Sandbox) used by TUI and MCP server
 // Not to be confused with AskForApproval, which we should probably rename to EscalationPolicy.
 pub mod approval_presets;
+// Detects npm/Homebrew installs so update prompts print the right command
+pub mod install_method;
@@ -0,0 +1,267 @@
+//! Tracing setup shared by the Codex frontends.
+//!
+//! Levels come from `[log]` in config.toml (`RUST_LOG` still wins when set),
+//! the file under `$CODEX_HOME/log` is rotated once it reaches
+//! `max_file_size_mb`, and the active filter can be replaced at runtime with
+//! [`set_filter`] so diagnosing a live session does not require a restart.
+
+use std::fs::File;
+use std::fs::OpenOptions;
+use std::io;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use codex_core::config_types::Log;
+use codex_core::config_types::LogFormat;
+use tracing_appender::non_blocking::WorkerGuard;
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::Registry;
+use tracing_subscriber::prelude::*;
+use tracing_subscriber::reload;
+
+static FILTER_HANDLE: OnceLock<reload::Handle<EnvFilter, Registry>> = OnceLock::new();
+
+/// Install a global subscriber that writes to `log_dir/file_name`. Keep the
+/// returned guard alive for as long as logs should be flushed.
+pub fn init_file_logging(
+    log: &Log,
+    log_dir: &Path,
+    file_name: &str,
+    default_directives: &str,
+) -> io::Result<WorkerGuard> {
+    std::fs::create_dir_all(log_dir)?;
+    let writer = RotatingFile::open(
+        log_dir.join(file_name),
+        log.max_file_size_mb.saturating_mul(1024 * 1024),
+        log.max_files,
+    )?;
+    let (non_blocking, guard) = tracing_appender::non_blocking(writer);
+
+    let (filter, handle) = reload::Layer::new(build_filter(log, default_directives));
+    let fmt_layer = match log.format {
+        LogFormat::Text => tracing_subscriber::fmt::layer()
+            .with_writer(non_blocking)
+            .with_target(false)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .boxed(),
+        LogFormat::Json => tracing_subscriber::fmt::layer()
+            .json()
+            .with_writer(non_blocking)
+            .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
+            .boxed(),
+    };
+    if tracing_subscriber::registry()
+        .with(filter)
+        .with(fmt_layer)
+        .try_init()
+        .is_ok()
+    {
+        let _ = FILTER_HANDLE.set(handle);
+    }
+    Ok(guard)
+}
+
+/// The filter for `log`: `RUST_LOG` if set, otherwise `level` (or
+/// `default_directives`) followed by the per-target overrides.
+pub fn build_filter(log: &Log, default_directives: &str) -> EnvFilter {
+    EnvFilter::try_from_default_env()
+        .unwrap_or_else(|_| EnvFilter::new(filter_directives(log, default_directives)))
+}
+
+fn filter_directives(log: &Log, default_directives: &str) -> String {
+    let base = log
+        .level
+        .as_deref()
+        .map(expand_directives)
+        .unwrap_or_else(|| default_directives.to_string());
+    let mut directives = vec![base];
+    for (target, level) in &log.targets {
+        directives.extend(
+            expand_target(target)
+                .into_iter()
+                .map(|target| format!("{target}={level}")),
+        );
+    }
+    directives.join(",")
+}
+
+/// Replace the active filter, e.g. with `debug` or `core::exec=trace`.
+/// Fails if logging was not initialized through [`init_file_logging`] or the
+/// directives do not parse.
+pub fn set_filter(directives: &str) -> Result<(), String> {
+    let handle = FILTER_HANDLE
+        .get()
+        .ok_or_else(|| "logging is not configured for runtime changes".to_string())?;
+    let filter = EnvFilter::try_new(expand_directives(directives)).map_err(|e| e.to_string())?;
+    handle.reload(filter).map_err(|e| e.to_string())
+}
+
+/// The active filter, if logging was initialized through
+/// [`init_file_logging`].
+pub fn current_filter() -> Option<String> {
+    FILTER_HANDLE
+        .get()
+        .and_then(|handle| handle.with_current(ToString::to_string).ok())
+}
+
+/// Expand subsystem names in comma-separated `target=level` directives.
+fn expand_directives(directives: &str) -> String {
+    directives
+        .split(',')
+        .map(str::trim)
+        .filter(|directive| !directive.is_empty())
+        .flat_map(|directive| match directive.split_once('=') {
+            Some((target, level)) => expand_target(target)
+                .into_iter()
+                .map(|target| format!("{target}={level}"))
+                .collect(),
+            None => vec![directive.to_string()],
+        })
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Map a subsystem name from config.toml to `tracing` targets. `mcp` and
+/// `provider` span several modules; anything else is a crate path with the
+/// `codex_` prefix optional (`core::exec` is `codex_core::exec`).
+fn expand_target(name: &str) -> Vec<String> {
+    match name {
+        "mcp" => vec![
+            "codex_mcp_client".to_string(),
+            "codex_core::mcp_connection_manager".to_string(),
+            "codex_core::mcp_tool_call".to_string(),
+        ],
+        "provider" => vec![
+            "codex_core::client".to_string(),
+            "codex_core::chat_completions".to_string(),
+        ],
+        name if name.starts_with("codex_") => vec![name.to_string()],
+        name => vec![format!("codex_{name}").replace('-', "_")],
+    }
+}
+
+/// A log file that is renamed to `<name>.1` (shifting older files up to
+/// `<name>.<max_files>`) once it would grow past `max_bytes`.
+struct RotatingFile {
+    path: PathBuf,
+    file: File,
+    size: u64,
+    max_bytes: u64,
+    max_files: usize,
+}
+
+impl RotatingFile {
+    fn open(path: PathBuf, max_bytes: u64, max_files: usize) -> io::Result<Self> {
+        let file = open_log_file(&path, false)?;
+        let size = file.metadata()?.len();
+        Ok(Self {
+            path,
+            file,
+            size,
+            max_bytes,
+            max_files,
+        })
+    }
+
+    fn rotated_path(&self, index: usize) -> PathBuf {
+        let mut name = self.path.clone().into_os_string();
+        name.push(format!(".{index}"));
+        PathBuf::from(name)
+    }
+
+    fn rotate(&mut self) -> io::Result<()> {
+        self.file.flush()?;
+        if self.max_files > 0 {
+            for index in (1..self.max_files).rev() {
+                let from = self.rotated_path(index);
+                if from.exists() {
+                    std::fs::rename(&from, self.rotated_path(index + 1))?;
+                }
+            }
+            std::fs::rename(&self.path, self.rotated_path(1))?;
+        }
+        self.file = open_log_file(&self.path, true)?;
+        self.size = 0;
+        Ok(())
+    }
+}
+
+impl Write for RotatingFile {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        if self.max_bytes > 0 && self.size > 0 && self.size + buf.len() as u64 > self.max_bytes {
+            self.rotate()?;
+        }
+        let written = self.file.write(buf)?;
+        self.size += written as u64;
+        Ok(written)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        self.file.flush()
+    }
+}
+
+fn open_log_file(path: &Path, truncate: bool) -> io::Result<File> {
+    let mut options = OpenOptions::new();
+    options.create(true);
+    if truncate {
+        options.write(true).truncate(true);
+    } else {
+        options.append(true);
+    }
+    // Logs can contain prompts and command output; keep them private.
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::OpenOptionsExt;
+        options.mode(0o600);
+    }
+    options.open(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn expands_subsystem_targets() {
+        let log = Log {
+            level: Some("warn".to_string()),
+            targets: [
+                ("core::exec".to_string(), "debug".to_string()),
+                ("provider".to_string(), "trace".to_string()),
+            ]
+            .into_iter()
+            .collect(),
+            ..Log::default()
+        };
+        assert_eq!(
+            filter_directives(&log, "info"),
+            "warn,codex_core::exec=debug,codex_core::client=trace,codex_core::chat_completions=trace"
+        );
+        assert_eq!(
+            expand_directives("debug, tui=trace"),
+            "debug,codex_tui=trace"
+        );
+    }
+
+    #[test]
+    fn rotates_when_file_exceeds_cap() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("codex.log");
+        let mut file = RotatingFile::open(path.clone(), 10, 2).expect("open");
+        for line in ["aaaaaaaa\n", "bbbbbbbb\n", "cccccccc\n", "dddddddd\n"] {
+            file.write_all(line.as_bytes()).expect("write");
+        }
+        file.flush().expect("flush");
+
+        let read = |path: PathBuf| std::fs::read_to_string(path).expect("read");
+        assert_eq!(read(path.clone()), "dddddddd\n");
+        assert_eq!(read(file.rotated_path(1)), "cccccccc\n");
+        assert_eq!(read(file.rotated_path(2)), "bbbbbbbb\n");
+        assert!(!file.rotated_path(3).exists());
+    }
+}
@@ -1,9 +1,19 @@
 use crate::codex::Session;
 use crate::codex::TurnContext;
+use crate::config_types::PatchGuardAction;
+use crate::patch_guard::describe_violations;
+use crate::patch_guard::find_patch_guard_violations;
+use crate::protocol::ApprovalKind;
+use crate::protocol::AskForApproval;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
 use crate::protocol::FileChange;
+use crate::protocol::PatchGuardBlockedEvent;
 use crate::protocol::ReviewDecision;
+use crate::protocol::WorkspaceEscapeBlockedEvent;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_patch_safety;
+use crate::workspace_guard::find_workspace_escapes;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::ApplyPatchFileChange;
 use codex_protocol::models::FunctionCallOutputPayload;
@@ -46,6 +56,106 @@ pub(crate) async fn apply_patch(
     call_id: &str,
     action: ApplyPatchAction,
 ) -> InternalApplyPatchInvocation {
+    // Symlinks and `..` can point a path that looks like it is in the
+    // workspace somewhere else entirely; refuse before anyone is asked.
+    let escapes = find_workspace_escapes(
+        &action,
+        &turn_context.sandbox_policy,
+        &turn_context.cwd,
+        sess.workspace_guard_allow(),
+    );
+    if !escapes.is_empty() {
+        let listing = escapes
+            .iter()
+            .map(|escape| {
+                format!(
+                    "{} resolves to {}",
+                    escape.path.display(),
+                    escape.resolved.display()
+                )
+            })
+            .collect::<Vec<_>>()
+            .join("; ");
+        sess.send_event(Event {
+            id: sub_id.to_owned(),
+            msg: EventMsg::WorkspaceEscapeBlocked(WorkspaceEscapeBlockedEvent {
+                call_id: call_id.to_owned(),
+                escapes,
+            }),
+        })
+        .await;
+        return ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.to_owned(),
+            output: FunctionCallOutputPayload {
+                content: format!(
+                    "patch rejected: writes outside the writable roots are not allowed ({listing})"
+                ),
+                success: Some(false),
+            },
+        }
+        .into();
+    }
+
+    // Binary, oversized and generated files are only edited with the user's
+    // explicit approval, and never when `[patch_guard]` says to reject.
+    let violations = find_patch_guard_violations(&action, sess.patch_guard(), &turn_context.cwd);
+    if !violations.is_empty() {
+        let listing = describe_violations(&violations);
+        if sess.patch_guard().on_violation == PatchGuardAction::Ask
+            && !matches!(turn_context.approval_policy, AskForApproval::Never)
+        {
+            let reason = format!("This patch edits protected files:\n{listing}");
+            let rx_approve = sess
+                .request_patch_approval(
+                    sub_id.to_owned(),
+                    call_id.to_owned(),
+                    &action,
+                    Some(reason),
+                    None,
+                )
+                .await;
+            return match sess
+                .wait_for_approval(sub_id, call_id, ApprovalKind::Patch, rx_approve)
+                .await
+            {
+                ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+                    InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
+                        action,
+                        user_explicitly_approved_this_action: true,
+                    })
+                }
+                ReviewDecision::Denied | ReviewDecision::Abort => {
+                    ResponseInputItem::FunctionCallOutput {
+                        call_id: call_id.to_owned(),
+                        output: FunctionCallOutputPayload {
+                            content: "patch rejected by user".to_string(),
+                            success: Some(false),
+                        },
+                    }
+                    .into()
+                }
+            };
+        }
+        sess.send_event(Event {
+            id: sub_id.to_owned(),
+            msg: EventMsg::PatchGuardBlocked(PatchGuardBlockedEvent {
+                call_id: call_id.to_owned(),
+                violations,
+            }),
+        })
+        .await;
+        return ResponseInputItem::FunctionCallOutput {
+            call_id: call_id.to_owned(),
+            output: FunctionCallOutputPayload {
+                content: format!(
+                    "patch rejected: it edits binary, oversized or generated files, which must not be rewritten by hand:\n{listing}\nRegenerate them with the tool that produces them instead."
+                ),
+                success: Some(false),
+            },
+        }
+        .into();
+    }
+
     match assess_patch_safety(
         &action,
         turn_context.approval_policy,
@@ -69,7 +179,10 @@ pub(crate) async fn apply_patch(
             let rx_approve = sess
                 .request_patch_approval(sub_id.to_owned(), call_id.to_owned(), &action, None, None)
                 .await;
-            match rx_approve.await.unwrap_or_default() {
+            match sess
+                .wait_for_approval(sub_id, call_id, ApprovalKind::Patch, rx_approve)
+                .await
+            {
                 ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
                     InternalApplyPatchInvocation::DelegateToExec(ApplyPatchExec {
                         action,
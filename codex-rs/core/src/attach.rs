@@ -0,0 +1,461 @@
+//! Attaching to a running headless session (`codex exec --detach`).
+//!
+//! While a session is attachable it listens on a Unix socket under
+//! `~/.codex/attach/`, next to a marker file describing it. Clients read the
+//! session's events and submit ops as JSON lines, one [`Event`] or [`Op`] per
+//! line, the same messages `codex proto` exchanges over stdio. The directory
+//! is only accessible to its owner; forwarding the socket with `ssh -L` lets
+//! a TUI on another machine attach.
+//!
+//! A client that connects late is first sent the events so far, without
+//! streaming deltas and approval requests that were already answered, so it
+//! can render the session as if it had been watching from the start.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use tokio::io::AsyncBufReadExt;
+use tokio::io::AsyncWriteExt;
+use tokio::io::BufReader;
+use tokio::net::UnixListener;
+use tokio::net::UnixStream;
+use tokio::net::unix::OwnedWriteHalf;
+use tokio::sync::broadcast;
+use tokio::task::JoinHandle;
+use tracing::debug;
+use tracing::warn;
+
+use crate::CodexConversation;
+use crate::protocol::Event;
+use crate::protocol::EventMsg;
+use crate::protocol::Op;
+use crate::rollout::recovery::is_process_alive;
+
+pub const ATTACH_SUBDIR: &str = "attach";
+
+/// Events kept for clients that attach later; the oldest are dropped first.
+const MAX_REPLAYED_EVENTS: usize = 10_000;
+
+/// Events a client may fall behind by before it is disconnected.
+const CLIENT_BACKLOG: usize = 4096;
+
+/// Contents of the marker file next to an attachable session's socket.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct AttachableSession {
+    pub conversation_id: ConversationId,
+    pub pid: u32,
+    pub socket: PathBuf,
+    pub cwd: PathBuf,
+    /// First line of the initial prompt.
+    pub prompt: String,
+}
+
+/// What a newly attached client is sent before live events.
+struct Replay {
+    /// Configures the client's session; always sent first.
+    session_configured: Event,
+    events: VecDeque<Event>,
+    /// Approval requests not answered yet, oldest first: (submission id,
+    /// call id). Requests made by one turn share its submission id.
+    pending_approvals: Vec<(String, String)>,
+}
+
+impl Replay {
+    fn new(session_configured: Event) -> Self {
+        Self {
+            session_configured,
+            events: VecDeque::new(),
+            pending_approvals: Vec::new(),
+        }
+    }
+
+    fn record(&mut self, event: &Event) {
+        match &event.msg {
+            EventMsg::AgentMessageDelta(_)
+            | EventMsg::AgentReasoningDelta(_)
+            | EventMsg::AgentReasoningRawContentDelta(_)
+            | EventMsg::ExecCommandOutputDelta(_) => return,
+            EventMsg::ExecApprovalRequest(request) => {
+                self.pending_approvals
+                    .push((event.id.clone(), request.call_id.clone()));
+            }
+            EventMsg::ApplyPatchApprovalRequest(request) => {
+                self.pending_approvals
+                    .push((event.id.clone(), request.call_id.clone()));
+            }
+            EventMsg::ApprovalTimedOut(timed_out) => {
+                self.pending_approvals
+                    .retain(|(_, call_id)| *call_id != timed_out.call_id);
+            }
+            EventMsg::TaskComplete(_) | EventMsg::TurnAborted(_) => {
+                self.pending_approvals.retain(|(id, _)| *id != event.id);
+            }
+            _ => {}
+        }
+        if self.events.len() >= MAX_REPLAYED_EVENTS {
+            self.events.pop_front();
+        }
+        self.events.push_back(event.clone());
+    }
+
+    /// An approval answers the submission's most recent request, the one
+    /// the session itself resolves.
+    fn answered(&mut self, op: &Op) {
+        if let Op::ExecApproval { id, .. } | Op::PatchApproval { id, .. } = op
+            && let Some(idx) = self
+                .pending_approvals
+                .iter()
+                .rposition(|(pending_id, _)| pending_id == id)
+        {
+            self.pending_approvals.remove(idx);
+        }
+    }
+
+    fn snapshot(&self) -> Vec<Event> {
+        std::iter::once(&self.session_configured)
+            .chain(self.events.iter().filter(|event| {
+                let call_id = match &event.msg {
+                    EventMsg::ExecApprovalRequest(request) => &request.call_id,
+                    EventMsg::ApplyPatchApprovalRequest(request) => &request.call_id,
+                    _ => return true,
+                };
+                self.pending_approvals
+                    .iter()
+                    .any(|(id, pending)| *id == event.id && pending == call_id)
+            }))
+            .cloned()
+            .collect()
+    }
+}
+
+/// Serves a session to attached clients until dropped.
+pub struct AttachServer {
+    marker_path: PathBuf,
+    socket_path: PathBuf,
+    replay: Arc<Mutex<Replay>>,
+    events: broadcast::Sender<Event>,
+    accept_loop: JoinHandle<()>,
+}
+
+impl AttachServer {
+    /// Make the session attachable. `session_configured` is the first event
+    /// every client receives.
+    pub fn start(
+        codex_home: &Path,
+        conversation_id: ConversationId,
+        conversation: Arc<CodexConversation>,
+        session_configured: Event,
+        cwd: PathBuf,
+        prompt: &str,
+    ) -> std::io::Result<Self> {
+        let dir = codex_home.join(ATTACH_SUBDIR);
+        fs::create_dir_all(&dir)?;
+        // Whoever can connect can approve commands.
+        fs::set_permissions(&dir, fs::Permissions::from_mode(0o700))?;
+
+        let socket_path = dir.join(format!("{conversation_id}.sock"));
+        let _ = fs::remove_file(&socket_path);
+        let listener = UnixListener::bind(&socket_path)?;
+        let marker_path = dir.join(format!("{conversation_id}.json"));
+        let marker = AttachableSession {
+            conversation_id,
+            pid: std::process::id(),
+            socket: socket_path.clone(),
+            cwd,
+            prompt: prompt.lines().next().unwrap_or_default().to_string(),
+        };
+        fs::write(&marker_path, serde_json::to_vec(&marker)?)?;
+
+        let (events, _) = broadcast::channel(CLIENT_BACKLOG);
+        let replay = Arc::new(Mutex::new(Replay::new(session_configured)));
+        let accept_loop = tokio::spawn(accept_clients(
+            listener,
+            conversation,
+            replay.clone(),
+            events.clone(),
+        ));
+        Ok(Self {
+            marker_path,
+            socket_path,
+            replay,
+            events,
+            accept_loop,
+        })
+    }
+
+    /// Pass `event` on to attached clients and keep it for later ones.
+    pub fn publish(&self, event: &Event) {
+        let mut replay = lock(&self.replay);
+        replay.record(event);
+        // Failing to send only means nobody is attached.
+        let _ = self.events.send(event.clone());
+    }
+
+    pub fn socket_path(&self) -> &Path {
+        &self.socket_path
+    }
+}
+
+impl Drop for AttachServer {
+    fn drop(&mut self) {
+        self.accept_loop.abort();
+        for path in [&self.socket_path, &self.marker_path] {
+            if let Err(e) = fs::remove_file(path)
+                && e.kind() != ErrorKind::NotFound
+            {
+                warn!("failed to remove {}: {e}", path.display());
+            }
+        }
+    }
+}
+
+async fn accept_clients(
+    listener: UnixListener,
+    conversation: Arc<CodexConversation>,
+    replay: Arc<Mutex<Replay>>,
+    events: broadcast::Sender<Event>,
+) {
+    loop {
+        let stream = match listener.accept().await {
+            Ok((stream, _)) => stream,
+            Err(e) => {
+                warn!("attach: failed to accept a client: {e}");
+                continue;
+            }
+        };
+        // Take the snapshot and subscribe together so no event is missed or
+        // sent twice.
+        let (backlog, rx) = {
+            let replay = lock(&replay);
+            (replay.snapshot(), events.subscribe())
+        };
+        tokio::spawn(serve_client(
+            stream,
+            backlog,
+            rx,
+            conversation.clone(),
+            replay.clone(),
+        ));
+    }
+}
+
+async fn serve_client(
+    stream: UnixStream,
+    backlog: Vec<Event>,
+    mut rx: broadcast::Receiver<Event>,
+    conversation: Arc<CodexConversation>,
+    replay: Arc<Mutex<Replay>>,
+) {
+    debug!("attach: client connected");
+    let (read, mut write) = stream.into_split();
+    let send = async move {
+        for event in &backlog {
+            if write_event(&mut write, event).await.is_err() {
+                return;
+            }
+        }
+        loop {
+            match rx.recv().await {
+                Ok(event) => {
+                    if write_event(&mut write, &event).await.is_err() {
+                        return;
+                    }
+                }
+                Err(broadcast::error::RecvError::Lagged(skipped)) => {
+                    warn!("attach: client fell {skipped} events behind; disconnecting it");
+                    return;
+                }
+                Err(broadcast::error::RecvError::Closed) => return,
+            }
+        }
+    };
+    let receive = async move {
+        let mut lines = BufReader::new(read).lines();
+        while let Ok(Some(line)) = lines.next_line().await {
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            let op = match serde_json::from_str::<Op>(line) {
+                Ok(op) => op,
+                Err(e) => {
+                    warn!("attach: invalid op: {e}");
+                    continue;
+                }
+            };
+            // Clients detach by disconnecting; only the process running the
+            // session ends it.
+            if matches!(op, Op::Shutdown) {
+                continue;
+            }
+            lock(&replay).answered(&op);
+            if let Err(e) = conversation.submit(op).await {
+                warn!("attach: failed to submit op: {e}");
+                return;
+            }
+        }
+    };
+    tokio::select! {
+        _ = send => {}
+        _ = receive => {}
+    }
+    debug!("attach: client disconnected");
+}
+
+async fn write_event(write: &mut OwnedWriteHalf, event: &Event) -> std::io::Result<()> {
+    let mut line = serde_json::to_string(event)?;
+    line.push('\n');
+    write.write_all(line.as_bytes()).await
+}
+
+/// Attachable sessions whose process is still running. Files left behind by
+/// processes that are gone are removed.
+pub fn list_attachable_sessions(codex_home: &Path) -> std::io::Result<Vec<AttachableSession>> {
+    let entries = match fs::read_dir(codex_home.join(ATTACH_SUBDIR)) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e),
+    };
+    let mut sessions = Vec::new();
+    for entry in entries.flatten() {
+        let marker_path = entry.path();
+        if marker_path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let session = match fs::read_to_string(&marker_path)
+            .map_err(|e| e.to_string())
+            .and_then(|contents| {
+                serde_json::from_str::<AttachableSession>(&contents).map_err(|e| e.to_string())
+            }) {
+            Ok(session) => session,
+            Err(e) => {
+                warn!(
+                    "ignoring malformed attach marker {}: {e}",
+                    marker_path.display()
+                );
+                continue;
+            }
+        };
+        if !is_process_alive(session.pid) {
+            let _ = fs::remove_file(&session.socket);
+            let _ = fs::remove_file(&marker_path);
+            continue;
+        }
+        sessions.push(session);
+    }
+    Ok(sessions)
+}
+
+fn lock(replay: &Mutex<Replay>) -> std::sync::MutexGuard<'_, Replay> {
+    replay.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::protocol::AgentMessageDeltaEvent;
+    use crate::protocol::ExecApprovalRequestEvent;
+    use crate::protocol::ReviewDecision;
+    use crate::protocol::SessionConfiguredEvent;
+    use crate::protocol::TaskStartedEvent;
+    use pretty_assertions::assert_eq;
+
+    fn event(id: &str, msg: EventMsg) -> Event {
+        Event {
+            id: id.to_string(),
+            msg,
+        }
+    }
+
+    fn approval(call_id: &str) -> EventMsg {
+        EventMsg::ExecApprovalRequest(ExecApprovalRequestEvent {
+            call_id: call_id.to_string(),
+            command: vec!["cargo".to_string(), "test".to_string()],
+            cwd: PathBuf::from("/repo"),
+            reason: None,
+        })
+    }
+
+    fn new_replay() -> Replay {
+        Replay::new(event(
+            "",
+            EventMsg::SessionConfigured(SessionConfiguredEvent {
+                session_id: ConversationId::new(),
+                model: "gpt-5".to_string(),
+                reasoning_effort: None,
+                history_log_id: 0,
+                history_entry_count: 0,
+                initial_messages: None,
+                rollout_path: PathBuf::from("/rollout.jsonl"),
+            }),
+        ))
+    }
+
+    fn ids(events: &[Event]) -> Vec<String> {
+        events
+            .iter()
+            .map(|event| match &event.msg {
+                EventMsg::ExecApprovalRequest(request) => {
+                    format!("{}:{}", event.id, request.call_id)
+                }
+                _ => event.id.clone(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn late_clients_get_history_without_deltas_or_answered_approvals() {
+        let mut replay = new_replay();
+        replay.record(&event(
+            "1",
+            EventMsg::TaskStarted(TaskStartedEvent {
+                model_context_window: None,
+            }),
+        ));
+        replay.record(&event(
+            "1",
+            EventMsg::AgentMessageDelta(AgentMessageDeltaEvent {
+                delta: "Run".to_string(),
+            }),
+        ));
+        replay.record(&event("1", approval("call-a")));
+        replay.answered(&Op::ExecApproval {
+            id: "1".to_string(),
+            decision: ReviewDecision::Approved,
+        });
+        replay.record(&event("1", approval("call-b")));
+
+        // Only the request still waiting for an answer is replayed.
+        assert_eq!(ids(&replay.snapshot()), vec!["", "1", "1:call-b"]);
+
+        replay.answered(&Op::ExecApproval {
+            id: "1".to_string(),
+            decision: ReviewDecision::Denied,
+        });
+        assert_eq!(ids(&replay.snapshot()), vec!["", "1"]);
+    }
+
+    #[test]
+    fn outstanding_approvals_of_one_turn_are_all_replayed() {
+        let mut replay = new_replay();
+        replay.record(&event("1", approval("call-a")));
+        replay.record(&event("1", approval("call-b")));
+        assert_eq!(ids(&replay.snapshot()), vec!["", "1:call-a", "1:call-b"]);
+
+        replay.answered(&Op::ExecApproval {
+            id: "1".to_string(),
+            decision: ReviewDecision::Approved,
+        });
+        assert_eq!(ids(&replay.snapshot()), vec!["", "1:call-a"]);
+    }
+}
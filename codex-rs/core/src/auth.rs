@@ -312,9 +312,11 @@ async fn try_refresh_token(
         scope: "openid profile email",
     };
 
+    crate::offline::ensure_network_allowed(REFRESH_TOKEN_URL).map_err(std::io::Error::other)?;
+
     // Use shared client factory to include standard headers
     let response = client
-        .post("https://auth.openai.com/oauth/token")
+        .post(REFRESH_TOKEN_URL)
         .header("Content-Type", "application/json")
         .json(&refresh_request)
         .send()
@@ -365,6 +367,7 @@ pub struct AuthDotJson {
 
 // Shared constant for token refresh (client id used for oauth token refresh flow)
 pub const CLIENT_ID: &str = "app_EMoamEEZ73f0CkXaXp7hrann";
+const REFRESH_TOKEN_URL: &str = "https://auth.openai.com/oauth/token";
 
 use std::sync::RwLock;
 
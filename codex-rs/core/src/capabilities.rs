@@ -0,0 +1,302 @@
+//! Detecting what an OpenAI-compatible Chat Completions server supports.
+//!
+//! Third-party servers vary widely: some reject `tools`, others cannot stream
+//! or choke on unknown fields. Rather than failing every turn, the first time
+//! a request to a provider/model is rejected Codex sends a handful of tiny
+//! probe requests, records which features were accepted in
+//! `$CODEX_HOME/capabilities.json`, and from then on shapes its requests to
+//! match. Models that have never been probed are assumed to accept exactly
+//! what Codex has always sent, so working setups see no extra traffic.
+
+use std::collections::BTreeMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use reqwest::StatusCode;
+use serde::Deserialize;
+use serde::Serialize;
+use serde_json::Value;
+use serde_json::json;
+use tracing::debug;
+use tracing::info;
+
+use crate::error::CodexErr;
+use crate::model_provider_info::ModelProviderInfo;
+
+/// File under `$CODEX_HOME` where probe results are cached.
+pub const CAPABILITIES_FILE: &str = "capabilities.json";
+
+/// Probe results older than this are ignored so server upgrades are noticed.
+const CACHE_TTL_SECS: u64 = 7 * 24 * 60 * 60;
+
+/// 1x1 transparent PNG used to check image input support.
+const PROBE_IMAGE: &str = "data:image/png;base64,iVBORw0KGgoAAAANSUhEUgAAAAEAAAABCAYAAAAfFcSJAAAADUlEQVR42mNkYPhfDwAChwGA60e6kgAAAABJRU5ErkJggg==";
+
+/// Features of a Chat Completions endpoint that Codex adapts to.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ProviderCapabilities {
+    /// Native function calling via `tools`.
+    pub tools: bool,
+    /// Accepts `reasoning_effort`.
+    pub reasoning: bool,
+    /// Accepts `image_url` content parts.
+    pub vision: bool,
+    /// Supports `stream: true` with server-sent events.
+    pub streaming: bool,
+    /// Accepts `response_format: {"type": "json_object"}`.
+    pub json_mode: bool,
+}
+
+impl Default for ProviderCapabilities {
+    /// What Codex sends to a Chat Completions provider that was never probed.
+    fn default() -> Self {
+        Self {
+            tools: true,
+            reasoning: false,
+            vision: false,
+            streaming: true,
+            json_mode: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+struct CachedCapabilities {
+    /// Seconds since the Unix epoch when the probe ran.
+    probed_at: u64,
+    #[serde(flatten)]
+    capabilities: ProviderCapabilities,
+}
+
+/// Key under which results for `model` served by `provider_id` are cached.
+pub fn cache_key(provider_id: &str, model: &str) -> String {
+    format!("{provider_id}/{model}")
+}
+
+fn cache_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(CAPABILITIES_FILE)
+}
+
+async fn read_cache(codex_home: &Path) -> BTreeMap<String, CachedCapabilities> {
+    match tokio::fs::read_to_string(cache_path(codex_home)).await {
+        Ok(text) => serde_json::from_str(&text).unwrap_or_default(),
+        Err(_) => BTreeMap::new(),
+    }
+}
+
+fn now_secs() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default()
+}
+
+/// Cached capabilities for `key`, if a probe ran recently enough.
+pub async fn load_cached(codex_home: &Path, key: &str) -> Option<ProviderCapabilities> {
+    let entry = read_cache(codex_home).await.remove(key)?;
+    (now_secs().saturating_sub(entry.probed_at) < CACHE_TTL_SECS).then_some(entry.capabilities)
+}
+
+/// Record `capabilities` for `key`, keeping entries for other models.
+pub async fn store(
+    codex_home: &Path,
+    key: &str,
+    capabilities: ProviderCapabilities,
+) -> io::Result<()> {
+    let mut cache = read_cache(codex_home).await;
+    cache.insert(
+        key.to_string(),
+        CachedCapabilities {
+            probed_at: now_secs(),
+            capabilities,
+        },
+    );
+    let json = serde_json::to_string_pretty(&cache).map_err(io::Error::other)?;
+    tokio::fs::write(cache_path(codex_home), json).await
+}
+
+/// Whether `err` looks like the server rejecting the shape of a request (as
+/// opposed to auth, rate limits or outages), which is when probing helps.
+pub(crate) fn is_unsupported_request_error(err: &CodexErr) -> bool {
+    match err {
+        CodexErr::UnexpectedStatus(status, _) => {
+            status.is_client_error()
+                && !matches!(
+                    *status,
+                    StatusCode::UNAUTHORIZED
+                        | StatusCode::FORBIDDEN
+                        | StatusCode::NOT_FOUND
+                        | StatusCode::TOO_MANY_REQUESTS
+                )
+        }
+        _ => false,
+    }
+}
+
+/// Outcome of a single probe request.
+enum ProbeResult {
+    Accepted,
+    Rejected,
+    /// Network failure, auth error or server error: says nothing about the
+    /// feature being probed.
+    Inconclusive,
+}
+
+/// Send one minimal request per feature to the provider's Chat Completions
+/// endpoint. Returns `None` if the endpoint could not be reached or rejects
+/// even a plain request, in which case nothing should be cached.
+pub async fn probe(
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    model: &str,
+) -> Option<ProviderCapabilities> {
+    let base = json!({
+        "model": model,
+        "messages": [{"role": "user", "content": "Reply with the word ok."}],
+        "max_tokens": 1,
+        "stream": false,
+    });
+    if !matches!(
+        send_probe(client, provider, &base).await,
+        ProbeResult::Accepted
+    ) {
+        return None;
+    }
+
+    let with = |extra: Value| {
+        let mut payload = base.clone();
+        if let (Some(payload), Some(extra)) = (payload.as_object_mut(), extra.as_object()) {
+            payload.extend(extra.clone());
+        }
+        payload
+    };
+    let checks = [
+        with(json!({
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "noop",
+                    "description": "Does nothing.",
+                    "parameters": {"type": "object", "properties": {}},
+                },
+            }],
+        })),
+        with(json!({"reasoning_effort": "low"})),
+        with(json!({
+            "messages": [{
+                "role": "user",
+                "content": [
+                    {"type": "text", "text": "Reply with the word ok."},
+                    {"type": "image_url", "image_url": {"url": PROBE_IMAGE}},
+                ],
+            }],
+        })),
+        with(json!({"stream": true})),
+        with(json!({
+            "messages": [{"role": "user", "content": "Reply with an empty JSON object."}],
+            "response_format": {"type": "json_object"},
+        })),
+    ];
+
+    let mut accepted = [false; 5];
+    for (slot, payload) in accepted.iter_mut().zip(&checks) {
+        *slot = match send_probe(client, provider, payload).await {
+            ProbeResult::Accepted => true,
+            ProbeResult::Rejected => false,
+            ProbeResult::Inconclusive => return None,
+        };
+    }
+    let [tools, reasoning, vision, streaming, json_mode] = accepted;
+    let capabilities = ProviderCapabilities {
+        tools,
+        reasoning,
+        vision,
+        streaming,
+        json_mode,
+    };
+    info!("probed {model} at {}: {capabilities:?}", provider.name);
+    Some(capabilities)
+}
+
+async fn send_probe(
+    client: &reqwest::Client,
+    provider: &ModelProviderInfo,
+    payload: &Value,
+) -> ProbeResult {
+    let Ok(builder) = provider.create_request_builder(client, &None).await else {
+        return ProbeResult::Inconclusive;
+    };
+    let streaming = payload.get("stream").and_then(Value::as_bool) == Some(true);
+    match builder.json(payload).send().await {
+        Ok(resp) if resp.status().is_success() => {
+            let is_sse = resp
+                .headers()
+                .get(reqwest::header::CONTENT_TYPE)
+                .and_then(|v| v.to_str().ok())
+                .is_some_and(|v| v.contains("text/event-stream"));
+            if !streaming || is_sse {
+                ProbeResult::Accepted
+            } else {
+                ProbeResult::Rejected
+            }
+        }
+        Ok(resp) => {
+            let err = CodexErr::UnexpectedStatus(resp.status(), String::new());
+            debug!("capability probe rejected: {err}");
+            if is_unsupported_request_error(&err) {
+                ProbeResult::Rejected
+            } else {
+                ProbeResult::Inconclusive
+            }
+        }
+        Err(_) => ProbeResult::Inconclusive,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[tokio::test]
+    async fn cache_round_trips_and_expires() {
+        let home = TempDir::new().expect("tempdir");
+        let key = cache_key("lan-ollama", "llama3");
+        assert_eq!(load_cached(home.path(), &key).await, None);
+
+        let capabilities = ProviderCapabilities {
+            tools: false,
+            ..ProviderCapabilities::default()
+        };
+        store(home.path(), &key, capabilities).await.expect("store");
+        assert_eq!(load_cached(home.path(), &key).await, Some(capabilities));
+        assert_eq!(load_cached(home.path(), "lan-ollama/other").await, None);
+
+        let mut cache = read_cache(home.path()).await;
+        if let Some(entry) = cache.get_mut(&key) {
+            entry.probed_at = 0;
+        }
+        std::fs::write(
+            cache_path(home.path()),
+            serde_json::to_string(&cache).expect("serialize"),
+        )
+        .expect("write");
+        assert_eq!(load_cached(home.path(), &key).await, None);
+    }
+
+    #[test]
+    fn only_request_shape_errors_trigger_probing() {
+        let status = |code: u16| {
+            CodexErr::UnexpectedStatus(StatusCode::from_u16(code).expect("status"), String::new())
+        };
+        assert!(is_unsupported_request_error(&status(400)));
+        assert!(is_unsupported_request_error(&status(422)));
+        assert!(!is_unsupported_request_error(&status(401)));
+        assert!(!is_unsupported_request_error(&status(429)));
+        assert!(!is_unsupported_request_error(&status(500)));
+    }
+}
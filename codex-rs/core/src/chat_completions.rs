@@ -16,29 +16,42 @@ use tracing::debug;
 use tracing::trace;
 
 use crate::ModelProviderInfo;
+use crate::capabilities::ProviderCapabilities;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::client_common::ResponseStream;
 use crate::error::CodexErr;
 use crate::error::Result;
 use crate::model_family::ModelFamily;
+use crate::openai_tools::OpenAiTool;
 use crate::openai_tools::create_tools_json_for_chat_completions_api;
+use crate::tool_emulation;
 use crate::util::backoff;
+use codex_protocol::config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_protocol::models::ContentItem;
 use codex_protocol::models::ReasoningItemContent;
 use codex_protocol::models::ResponseItem;
 
-/// Implementation for the classic Chat Completions API.
+/// Implementation for the classic Chat Completions API. The request only uses
+/// the features listed in `capabilities`; without native tool support the
+/// tools are emulated through the prompt (see [`tool_emulation`]).
 pub(crate) async fn stream_chat_completions(
     prompt: &Prompt,
     model_family: &ModelFamily,
     client: &reqwest::Client,
     provider: &ModelProviderInfo,
+    capabilities: ProviderCapabilities,
+    effort: Option<ReasoningEffortConfig>,
 ) -> Result<ResponseStream> {
     // Build messages array
     let mut messages = Vec::<serde_json::Value>::new();
 
-    let full_instructions = prompt.get_full_instructions(model_family);
+    let emulate_tools = !capabilities.tools && !prompt.tools.is_empty();
+    let mut full_instructions = prompt.get_full_instructions(model_family).into_owned();
+    if emulate_tools {
+        full_instructions.push_str("\n\n");
+        full_instructions.push_str(&tool_emulation::instructions(&prompt.tools));
+    }
     messages.push(json!({"role": "system", "content": full_instructions}));
 
     let input = prompt.get_formatted_input();
@@ -147,13 +160,14 @@ pub(crate) async fn stream_chat_completions(
         match item {
             ResponseItem::Message { role, content, .. } => {
                 let mut text = String::new();
+                let mut images = Vec::new();
                 for c in content {
                     match c {
                         ContentItem::InputText { text: t }
                         | ContentItem::OutputText { text: t } => {
                             text.push_str(t);
                         }
-                        _ => {}
+                        ContentItem::InputImage { image_url } => images.push(image_url),
                     }
                 }
                 // Skip exact-duplicate assistant messages.
@@ -166,7 +180,17 @@ pub(crate) async fn stream_chat_completions(
                     last_assistant_text = Some(text.clone());
                 }
 
-                let mut msg = json!({"role": role, "content": text});
+                let mut msg = if capabilities.vision && !images.is_empty() {
+                    let mut parts = vec![json!({"type": "text", "text": text})];
+                    parts.extend(
+                        images
+                            .into_iter()
+                            .map(|url| json!({"type": "image_url", "image_url": {"url": url}})),
+                    );
+                    json!({"role": role, "content": parts})
+                } else {
+                    json!({"role": role, "content": text})
+                };
                 if role == "assistant"
                     && let Some(reasoning) = reasoning_by_anchor_index.get(&idx)
                     && let Some(obj) = msg.as_object_mut()
@@ -175,6 +199,32 @@ pub(crate) async fn stream_chat_completions(
                 }
                 messages.push(msg);
             }
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } if emulate_tools => {
+                messages.push(json!({
+                    "role": "assistant",
+                    "content": tool_emulation::render_call(name, arguments),
+                }));
+            }
+            ResponseItem::FunctionCallOutput { output, .. } if emulate_tools => {
+                messages.push(json!({
+                    "role": "user",
+                    "content": tool_emulation::render_output(&output.content),
+                }));
+            }
+            ResponseItem::CustomToolCall { name, input, .. } if emulate_tools => {
+                messages.push(json!({
+                    "role": "assistant",
+                    "content": tool_emulation::render_call(name, input),
+                }));
+            }
+            ResponseItem::CustomToolCallOutput { output, .. } if emulate_tools => {
+                messages.push(json!({
+                    "role": "user",
+                    "content": tool_emulation::render_output(output),
+                }));
+            }
             ResponseItem::FunctionCall {
                 name,
                 arguments,
@@ -267,13 +317,22 @@ pub(crate) async fn stream_chat_completions(
         }
     }
 
-    let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
-    let payload = json!({
+    let mut payload = json!({
         "model": model_family.slug,
         "messages": messages,
-        "stream": true,
-        "tools": tools_json,
+        "stream": capabilities.streaming,
     });
+    if let Some(obj) = payload.as_object_mut() {
+        if capabilities.tools {
+            let tools_json = create_tools_json_for_chat_completions_api(&prompt.tools)?;
+            obj.insert("tools".to_string(), json!(tools_json));
+        }
+        if capabilities.reasoning
+            && let Some(effort) = effort
+        {
+            obj.insert("reasoning_effort".to_string(), json!(effort));
+        }
+    }
 
     debug!(
         "POST to {}: {}",
@@ -288,13 +347,25 @@ pub(crate) async fn stream_chat_completions(
 
         let req_builder = provider.create_request_builder(client, &None).await?;
 
-        let res = req_builder
-            .header(reqwest::header::ACCEPT, "text/event-stream")
-            .json(&payload)
-            .send()
-            .await;
+        let req_builder = if capabilities.streaming {
+            req_builder.header(reqwest::header::ACCEPT, "text/event-stream")
+        } else {
+            req_builder
+        };
+        let res = req_builder.json(&payload).send().await;
 
         match res {
+            Ok(resp) if resp.status().is_success() && !capabilities.streaming => {
+                let body = resp.json::<serde_json::Value>().await?;
+                let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(16);
+                tokio::spawn(emit_chat_completion(body, tx_event));
+                let stream = ResponseStream { rx_event };
+                return Ok(if emulate_tools {
+                    emulate_tool_calls(stream, prompt.tools.clone())
+                } else {
+                    stream
+                });
+            }
             Ok(resp) if resp.status().is_success() => {
                 let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(1600);
                 let stream = resp.bytes_stream().map_err(CodexErr::Reqwest);
@@ -303,7 +374,12 @@ pub(crate) async fn stream_chat_completions(
                     tx_event,
                     provider.stream_idle_timeout(),
                 ));
-                return Ok(ResponseStream { rx_event });
+                let stream = ResponseStream { rx_event };
+                return Ok(if emulate_tools {
+                    emulate_tool_calls(stream, prompt.tools.clone())
+                } else {
+                    stream
+                });
             }
             Ok(res) => {
                 let status = res.status();
@@ -338,6 +414,128 @@ pub(crate) async fn stream_chat_completions(
     }
 }
 
+/// Replace the final assistant message with the text and tool calls parsed
+/// out of it. Text deltas are dropped because they would show the raw
+/// `tool_call` blocks; the cleaned-up message arrives in one piece instead.
+fn emulate_tool_calls(mut stream: ResponseStream, tools: Vec<OpenAiTool>) -> ResponseStream {
+    let (tx_event, rx_event) = mpsc::channel::<Result<ResponseEvent>>(16);
+    tokio::spawn(async move {
+        while let Some(event) = stream.rx_event.recv().await {
+            let events = match event {
+                Ok(ResponseEvent::OutputTextDelta(_)) => continue,
+                Ok(ResponseEvent::OutputItemDone(ResponseItem::Message {
+                    role, content, ..
+                })) if role == "assistant" => {
+                    let text: String = content
+                        .iter()
+                        .filter_map(|c| match c {
+                            ContentItem::OutputText { text } => Some(text.as_str()),
+                            _ => None,
+                        })
+                        .collect();
+                    let (text, calls) = tool_emulation::parse_response(&text, &tools);
+                    let message = (!text.is_empty()).then(|| ResponseItem::Message {
+                        id: None,
+                        role,
+                        content: vec![ContentItem::OutputText { text }],
+                    });
+                    message
+                        .into_iter()
+                        .chain(calls)
+                        .map(|item| Ok(ResponseEvent::OutputItemDone(item)))
+                        .collect()
+                }
+                other => vec![other],
+            };
+            for event in events {
+                if tx_event.send(event).await.is_err() {
+                    return;
+                }
+            }
+        }
+    });
+    ResponseStream { rx_event }
+}
+
+/// Map a non-streaming Chat Completions response onto the same events
+/// [`process_chat_sse`] produces, for servers that cannot stream.
+async fn emit_chat_completion(
+    body: serde_json::Value,
+    tx_event: mpsc::Sender<Result<ResponseEvent>>,
+) {
+    trace!("chat_completions received response: {body:?}");
+    let message = body
+        .get("choices")
+        .and_then(|c| c.get(0))
+        .and_then(|c| c.get("message"));
+    let text = |value: Option<&serde_json::Value>| {
+        value
+            .and_then(|v| {
+                v.as_str()
+                    .or_else(|| v.get("text").and_then(|t| t.as_str()))
+                    .or_else(|| v.get("content").and_then(|t| t.as_str()))
+            })
+            .filter(|s| !s.is_empty())
+            .map(str::to_string)
+    };
+
+    if let Some(content) = text(message.and_then(|m| m.get("content"))) {
+        let _ = tx_event
+            .send(Ok(ResponseEvent::OutputTextDelta(content.clone())))
+            .await;
+        let item = ResponseItem::Message {
+            role: "assistant".to_string(),
+            content: vec![ContentItem::OutputText { text: content }],
+            id: None,
+        };
+        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+    }
+
+    if let Some(reasoning) = text(message.and_then(|m| m.get("reasoning"))) {
+        let _ = tx_event
+            .send(Ok(ResponseEvent::ReasoningContentDelta(reasoning.clone())))
+            .await;
+        let item = ResponseItem::Reasoning {
+            id: String::new(),
+            summary: Vec::new(),
+            content: Some(vec![ReasoningItemContent::ReasoningText {
+                text: reasoning,
+            }]),
+            encrypted_content: None,
+        };
+        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+    }
+
+    let tool_calls = message
+        .and_then(|m| m.get("tool_calls"))
+        .and_then(|tc| tc.as_array())
+        .cloned()
+        .unwrap_or_default();
+    for tool_call in tool_calls {
+        let function = tool_call.get("function");
+        let field = |v: Option<&serde_json::Value>, key: &str| {
+            v.and_then(|v| v.get(key))
+                .and_then(|v| v.as_str())
+                .unwrap_or_default()
+                .to_string()
+        };
+        let item = ResponseItem::FunctionCall {
+            id: None,
+            name: field(function, "name"),
+            arguments: field(function, "arguments"),
+            call_id: field(Some(&tool_call), "id"),
+        };
+        let _ = tx_event.send(Ok(ResponseEvent::OutputItemDone(item))).await;
+    }
+
+    let _ = tx_event
+        .send(Ok(ResponseEvent::Completed {
+            response_id: String::new(),
+            token_usage: None,
+        }))
+        .await;
+}
+
 /// Lightweight SSE processor for the Chat Completions streaming format. The
 /// output is mapped onto Codex's internal [`ResponseEvent`] so that the rest
 /// of the pipeline can stay agnostic of the underlying wire format.
@@ -539,6 +737,17 @@ async fn process_chat_sse<S>(
                     if let Some(args_fragment) = function.get("arguments").and_then(|a| a.as_str())
                     {
                         fn_call_state.arguments.push_str(args_fragment);
+                        if let (Some(call_id), Some(name)) =
+                            (&fn_call_state.call_id, &fn_call_state.name)
+                        {
+                            let _ = tx_event
+                                .send(Ok(ResponseEvent::FunctionCallArgumentsDelta {
+                                    call_id: call_id.clone(),
+                                    name: name.clone(),
+                                    arguments: fn_call_state.arguments.clone(),
+                                }))
+                                .await;
+                        }
                     }
                 }
             }
@@ -812,6 +1021,9 @@ where
                 Poll::Ready(Some(Ok(ResponseEvent::WebSearchCallBegin { call_id }))) => {
                     return Poll::Ready(Some(Ok(ResponseEvent::WebSearchCallBegin { call_id })));
                 }
+                Poll::Ready(Some(Ok(event @ ResponseEvent::FunctionCallArgumentsDelta { .. }))) => {
+                    return Poll::Ready(Some(Ok(event)));
+                }
             }
         }
     }
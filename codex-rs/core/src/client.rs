@@ -1,6 +1,9 @@
+use std::collections::HashMap;
 use std::io::BufRead;
 use std::path::Path;
 use std::sync::OnceLock;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use crate::AuthManager;
@@ -21,6 +24,8 @@ use tracing::debug;
 use tracing::trace;
 use tracing::warn;
 
+use crate::capabilities;
+use crate::capabilities::ProviderCapabilities;
 use crate::chat_completions::AggregateStreamExt;
 use crate::chat_completions::stream_chat_completions;
 use crate::client_common::Prompt;
@@ -74,6 +79,12 @@ pub struct ModelClient {
     conversation_id: ConversationId,
     effort: Option<ReasoningEffortConfig>,
     summary: ReasoningSummaryConfig,
+    /// Chat Completions features this provider/model accepts, resolved from
+    /// `capabilities.json` on first use.
+    capabilities: Arc<tokio::sync::Mutex<Option<ProviderCapabilities>>>,
+    /// Whether the provider was probed during this session, so a request
+    /// that keeps failing is not probed again.
+    probed: Arc<AtomicBool>,
 }
 
 impl ModelClient {
@@ -95,6 +106,8 @@ impl ModelClient {
             conversation_id,
             effort,
             summary,
+            capabilities: Arc::new(tokio::sync::Mutex::new(None)),
+            probed: Arc::new(AtomicBool::new(false)),
         }
     }
 
@@ -117,14 +130,24 @@ impl ModelClient {
         match self.provider.wire_api {
             WireApi::Responses => self.stream_responses(prompt).await,
             WireApi::Chat => {
-                // Create the raw streaming connection first.
-                let response_stream = stream_chat_completions(
-                    prompt,
-                    &self.config.model_family,
-                    &self.client,
-                    &self.provider,
-                )
-                .await?;
+                // Create the raw streaming connection first. If the server
+                // rejects the request, find out what it does support and
+                // retry once with a request it can accept.
+                let capabilities = self.chat_capabilities().await;
+                let response_stream = match self.stream_chat(prompt, capabilities).await {
+                    Err(err)
+                        if capabilities::is_unsupported_request_error(&err)
+                            && !self.probed.swap(true, Ordering::SeqCst) =>
+                    {
+                        match self.probe_chat_capabilities().await {
+                            Some(probed) if probed != capabilities => {
+                                self.stream_chat(prompt, probed).await?
+                            }
+                            _ => return Err(err),
+                        }
+                    }
+                    result => result?,
+                };
 
                 // Wrap it with the aggregation adapter so callers see *only*
                 // the final assistant message per turn (matching the
@@ -154,6 +177,54 @@ impl ModelClient {
         }
     }
 
+    async fn stream_chat(
+        &self,
+        prompt: &Prompt,
+        capabilities: ProviderCapabilities,
+    ) -> Result<ResponseStream> {
+        stream_chat_completions(
+            prompt,
+            &self.config.model_family,
+            &self.client,
+            &self.provider,
+            capabilities,
+            self.effort,
+        )
+        .await
+    }
+
+    fn capabilities_key(&self) -> String {
+        capabilities::cache_key(&self.config.model_provider_id, &self.config.model)
+    }
+
+    async fn chat_capabilities(&self) -> ProviderCapabilities {
+        let mut resolved = self.capabilities.lock().await;
+        if let Some(capabilities) = *resolved {
+            return capabilities;
+        }
+        let capabilities =
+            capabilities::load_cached(&self.config.codex_home, &self.capabilities_key())
+                .await
+                .unwrap_or_default();
+        *resolved = Some(capabilities);
+        capabilities
+    }
+
+    async fn probe_chat_capabilities(&self) -> Option<ProviderCapabilities> {
+        let probed = capabilities::probe(&self.client, &self.provider, &self.config.model).await?;
+        warn!(
+            "{} rejected a request; adapting to probed capabilities {probed:?}",
+            self.provider.name
+        );
+        if let Err(e) =
+            capabilities::store(&self.config.codex_home, &self.capabilities_key(), probed).await
+        {
+            warn!("failed to cache provider capabilities: {e}");
+        }
+        *self.capabilities.lock().await = Some(probed);
+        Some(probed)
+    }
+
     /// Implementation for the OpenAI *Responses* experimental API.
     async fn stream_responses(&self, prompt: &Prompt) -> Result<ResponseStream> {
         if let Some(path) = &*CODEX_RS_SSE_FIXTURE {
@@ -371,6 +442,12 @@ impl ModelClient {
         self.config.model.clone()
     }
 
+    /// Returns the instructions appended to the base instructions for this
+    /// provider and model.
+    pub fn get_appended_instructions(&self) -> Option<String> {
+        self.config.appended_instructions()
+    }
+
     /// Returns the currently configured model family.
     pub fn get_model_family(&self) -> ModelFamily {
         self.config.model_family.clone()
@@ -397,12 +474,21 @@ struct SseEvent {
     kind: String,
     response: Option<Value>,
     item: Option<Value>,
+    item_id: Option<String>,
     delta: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
 struct ResponseCreated {}
 
+/// A function call whose arguments are still arriving via
+/// `response.function_call_arguments.delta`.
+struct StreamingFunctionCall {
+    call_id: String,
+    name: String,
+    arguments: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct ResponseCompleted {
     id: String,
@@ -486,6 +572,8 @@ async fn process_sse<S>(
     // The response id returned from the "complete" message.
     let mut response_completed: Option<ResponseCompleted> = None;
     let mut response_error: Option<CodexErr> = None;
+    // Function calls in progress, keyed by output item id.
+    let mut function_calls: HashMap<String, StreamingFunctionCall> = HashMap::new();
 
     loop {
         let sse = match timeout(idle_timeout, stream.next()).await {
@@ -638,8 +726,22 @@ async fn process_sse<S>(
                     };
                 };
             }
+            "response.function_call_arguments.delta" => {
+                if let (Some(item_id), Some(delta)) = (event.item_id, event.delta)
+                    && let Some(call) = function_calls.get_mut(&item_id)
+                {
+                    call.arguments.push_str(&delta);
+                    let event = ResponseEvent::FunctionCallArgumentsDelta {
+                        call_id: call.call_id.clone(),
+                        name: call.name.clone(),
+                        arguments: call.arguments.clone(),
+                    };
+                    if tx_event.send(Ok(event)).await.is_err() {
+                        return;
+                    }
+                }
+            }
             "response.content_part.done"
-            | "response.function_call_arguments.delta"
             | "response.custom_tool_call_input.delta"
             | "response.custom_tool_call_input.done" // also emitted as response.output_item.done
             | "response.in_progress"
@@ -660,6 +762,25 @@ async fn process_sse<S>(
                             return;
                         }
                     }
+
+                    // Remember function calls so their argument deltas can be
+                    // attributed to a call id and tool name.
+                    if item.get("type").and_then(|v| v.as_str()) == Some("function_call")
+                        && let (Some(id), Some(call_id), Some(name)) = (
+                            item.get("id").and_then(|v| v.as_str()),
+                            item.get("call_id").and_then(|v| v.as_str()),
+                            item.get("name").and_then(|v| v.as_str()),
+                        )
+                    {
+                        function_calls.insert(
+                            id.to_string(),
+                            StreamingFunctionCall {
+                                call_id: call_id.to_string(),
+                                name: name.to_string(),
+                                arguments: String::new(),
+                            },
+                        );
+                    }
                 }
             }
             "response.reasoning_summary_part.added" => {
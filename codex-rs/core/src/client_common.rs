@@ -34,6 +34,11 @@ pub struct Prompt {
 
     /// Optional override for the built-in BASE_INSTRUCTIONS.
     pub base_instructions_override: Option<String>,
+
+    /// `base_instructions_append`, `[provider_instructions]` and
+    /// `[model_instructions]` text for the turn's model, appended after the
+    /// base instructions.
+    pub appended_instructions: Option<String>,
 }
 
 impl Prompt {
@@ -57,6 +62,9 @@ impl Prompt {
         {
             sections.push(APPLY_PATCH_TOOL_INSTRUCTIONS);
         }
+        if let Some(appended_instructions) = &self.appended_instructions {
+            sections.push(appended_instructions);
+        }
         Cow::Owned(sections.join("\n"))
     }
 
@@ -80,6 +88,13 @@ pub enum ResponseEvent {
     WebSearchCallBegin {
         call_id: String,
     },
+    /// A function call whose arguments are still streaming; `arguments` holds
+    /// everything received so far.
+    FunctionCallArgumentsDelta {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
 }
 
 #[derive(Debug, Serialize)]
@@ -191,6 +206,18 @@ mod tests {
         assert_eq!(full, expected);
     }
 
+    #[test]
+    fn get_full_instructions_appends_configured_instructions() {
+        let prompt = Prompt {
+            base_instructions_override: Some("base".to_string()),
+            appended_instructions: Some("Keep patches small.".to_string()),
+            ..Default::default()
+        };
+        let model_family = find_family_for_model("gpt-4.1").expect("known model slug");
+        let full = prompt.get_full_instructions(&model_family);
+        assert_eq!(full, "base\nKeep patches small.");
+    }
+
     #[test]
     fn serializes_text_verbosity_when_set() {
         let input: Vec<ResponseItem> = vec![];
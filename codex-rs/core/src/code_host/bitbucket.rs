@@ -0,0 +1,91 @@
+//! Bitbucket Pipelines. The agent's message is posted on the pull request and
+//! the outcome recorded as a build status on the commit, authenticated with a
+//! repository access token in `BITBUCKET_ACCESS_TOKEN`.
+
+use std::collections::HashMap;
+
+use reqwest::Method;
+use serde_json::json;
+
+use super::CiReport;
+use super::CodeHost;
+use super::HostRequest;
+use super::comment_body;
+use super::required_var;
+use super::short_description;
+use super::var;
+
+const API_URL: &str = "https://api.bitbucket.org/2.0";
+
+#[derive(Debug, Clone)]
+pub struct Bitbucket {
+    workspace: String,
+    repo_slug: String,
+    sha: String,
+    pull_request_id: Option<u64>,
+    build_number: String,
+    token: String,
+}
+
+impl Bitbucket {
+    pub fn from_env(env: &HashMap<String, String>) -> anyhow::Result<Self> {
+        Ok(Self {
+            workspace: required_var(env, "BITBUCKET_WORKSPACE")?.to_string(),
+            repo_slug: required_var(env, "BITBUCKET_REPO_SLUG")?.to_string(),
+            sha: required_var(env, "BITBUCKET_COMMIT")?.to_string(),
+            pull_request_id: var(env, "BITBUCKET_PR_ID").and_then(|id| id.parse().ok()),
+            build_number: required_var(env, "BITBUCKET_BUILD_NUMBER")?.to_string(),
+            token: required_var(env, "BITBUCKET_ACCESS_TOKEN")?.to_string(),
+        })
+    }
+
+    fn repository_url(&self) -> String {
+        format!(
+            "{API_URL}/repositories/{}/{}",
+            self.workspace, self.repo_slug
+        )
+    }
+
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        vec![("Authorization", format!("Bearer {}", self.token))]
+    }
+}
+
+impl CodeHost for Bitbucket {
+    fn name(&self) -> &'static str {
+        "Bitbucket"
+    }
+
+    fn comment_request(&self, report: &CiReport) -> Option<HostRequest> {
+        let id = self.pull_request_id?;
+        Some(HostRequest {
+            method: Method::POST,
+            url: format!("{}/pullrequests/{id}/comments", self.repository_url()),
+            headers: self.headers(),
+            body: json!({ "content": { "raw": comment_body(report) } }),
+        })
+    }
+
+    fn status_request(&self, report: &CiReport) -> Option<HostRequest> {
+        let pipeline_url = format!(
+            "https://bitbucket.org/{}/{}/pipelines/results/{}",
+            self.workspace, self.repo_slug, self.build_number
+        );
+        Some(HostRequest {
+            method: Method::POST,
+            url: format!(
+                "{}/commit/{}/statuses/build",
+                self.repository_url(),
+                self.sha
+            ),
+            headers: self.headers(),
+            body: json!({
+                "key": "codex",
+                "name": "Codex",
+                "state": if report.success { "SUCCESSFUL" } else { "FAILED" },
+                "description": short_description(report),
+                "url": pipeline_url,
+            }),
+        })
+    }
+}
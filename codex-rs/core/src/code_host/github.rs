@@ -0,0 +1,131 @@
+//! GitHub Actions. The outcome is written to the job log as a workflow
+//! command and to the job summary; with `GITHUB_TOKEN` exported to the step,
+//! the agent's message is also posted on the pull request and the run is
+//! recorded as a commit status.
+
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::PathBuf;
+
+use reqwest::Method;
+use serde_json::json;
+
+use super::CiReport;
+use super::CodeHost;
+use super::HostRequest;
+use super::comment_body;
+use super::required_var;
+use super::short_description;
+use super::var;
+
+const DEFAULT_API_URL: &str = "https://api.github.com";
+const DEFAULT_SERVER_URL: &str = "https://github.com";
+
+#[derive(Debug, Clone)]
+pub struct GitHub {
+    api_url: String,
+    /// `owner/name`.
+    repository: String,
+    sha: Option<String>,
+    pull_request: Option<u64>,
+    run_url: Option<String>,
+    token: Option<String>,
+    step_summary: Option<PathBuf>,
+}
+
+impl GitHub {
+    pub fn from_env(env: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let repository = required_var(env, "GITHUB_REPOSITORY")?.to_string();
+        // Pull request workflows check out `refs/pull/<number>/merge`.
+        let pull_request = var(env, "GITHUB_REF")
+            .and_then(|r| r.strip_prefix("refs/pull/"))
+            .and_then(|r| r.split('/').next())
+            .and_then(|number| number.parse().ok());
+        let run_url = var(env, "GITHUB_RUN_ID").map(|run_id| {
+            let server = var(env, "GITHUB_SERVER_URL").unwrap_or(DEFAULT_SERVER_URL);
+            format!("{server}/{repository}/actions/runs/{run_id}")
+        });
+        Ok(Self {
+            api_url: var(env, "GITHUB_API_URL")
+                .unwrap_or(DEFAULT_API_URL)
+                .trim_end_matches('/')
+                .to_string(),
+            sha: var(env, "GITHUB_SHA").map(str::to_string),
+            pull_request,
+            run_url,
+            token: var(env, "GITHUB_TOKEN").map(str::to_string),
+            step_summary: var(env, "GITHUB_STEP_SUMMARY").map(PathBuf::from),
+            repository,
+        })
+    }
+
+    fn headers(&self, token: &str) -> Vec<(&'static str, String)> {
+        vec![
+            ("Authorization", format!("Bearer {token}")),
+            ("Accept", "application/vnd.github+json".to_string()),
+        ]
+    }
+}
+
+impl CodeHost for GitHub {
+    fn name(&self) -> &'static str {
+        "GitHub"
+    }
+
+    fn comment_request(&self, report: &CiReport) -> Option<HostRequest> {
+        let token = self.token.as_deref()?;
+        let number = self.pull_request?;
+        Some(HostRequest {
+            method: Method::POST,
+            url: format!(
+                "{}/repos/{}/issues/{number}/comments",
+                self.api_url, self.repository
+            ),
+            headers: self.headers(token),
+            body: json!({ "body": comment_body(report) }),
+        })
+    }
+
+    fn status_request(&self, report: &CiReport) -> Option<HostRequest> {
+        let token = self.token.as_deref()?;
+        let sha = self.sha.as_deref()?;
+        let mut body = json!({
+            "state": if report.success { "success" } else { "failure" },
+            "context": "codex",
+            "description": short_description(report),
+        });
+        if let Some(run_url) = &self.run_url {
+            body["target_url"] = json!(run_url);
+        }
+        Some(HostRequest {
+            method: Method::POST,
+            url: format!("{}/repos/{}/statuses/{sha}", self.api_url, self.repository),
+            headers: self.headers(token),
+            body,
+        })
+    }
+
+    fn log_commands(&self, report: &CiReport) -> Vec<String> {
+        let command = if report.success { "notice" } else { "error" };
+        vec![format!(
+            "::{command} title=Codex::{}",
+            escape_workflow_data(report.summary.trim())
+        )]
+    }
+
+    fn annotate(&self, report: &CiReport) -> std::io::Result<()> {
+        if let Some(path) = &self.step_summary {
+            let mut file = OpenOptions::new().create(true).append(true).open(path)?;
+            writeln!(file, "{}\n", comment_body(report))?;
+        }
+        Ok(())
+    }
+}
+
+/// Escape a workflow command's message so multi-line text stays one command.
+fn escape_workflow_data(text: &str) -> String {
+    text.replace('%', "%25")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
@@ -0,0 +1,97 @@
+//! GitLab CI/CD. The agent's message becomes a note on the merge request and
+//! the outcome an external commit status in the pipeline. The job token
+//! cannot do either, so a project or personal access token with the `api`
+//! scope must be provided as `CODEX_GITLAB_TOKEN` or `GITLAB_TOKEN`.
+
+use std::collections::HashMap;
+
+use reqwest::Method;
+use serde_json::json;
+
+use super::CiReport;
+use super::CodeHost;
+use super::HostRequest;
+use super::comment_body;
+use super::required_var;
+use super::short_description;
+use super::var;
+
+#[derive(Debug, Clone)]
+pub struct GitLab {
+    /// The v4 API root, e.g. `https://gitlab.com/api/v4`.
+    api_url: String,
+    project_id: String,
+    sha: String,
+    merge_request_iid: Option<u64>,
+    pipeline_id: Option<u64>,
+    pipeline_url: Option<String>,
+    token: String,
+}
+
+impl GitLab {
+    pub fn from_env(env: &HashMap<String, String>) -> anyhow::Result<Self> {
+        let token = var(env, "CODEX_GITLAB_TOKEN")
+            .or_else(|| var(env, "GITLAB_TOKEN"))
+            .ok_or_else(|| {
+                anyhow::anyhow!("set CODEX_GITLAB_TOKEN to a token with the `api` scope")
+            })?;
+        Ok(Self {
+            api_url: required_var(env, "CI_API_V4_URL")?
+                .trim_end_matches('/')
+                .to_string(),
+            project_id: required_var(env, "CI_PROJECT_ID")?.to_string(),
+            sha: required_var(env, "CI_COMMIT_SHA")?.to_string(),
+            merge_request_iid: var(env, "CI_MERGE_REQUEST_IID").and_then(|iid| iid.parse().ok()),
+            pipeline_id: var(env, "CI_PIPELINE_ID").and_then(|id| id.parse().ok()),
+            pipeline_url: var(env, "CI_PIPELINE_URL").map(str::to_string),
+            token: token.to_string(),
+        })
+    }
+
+    fn headers(&self) -> Vec<(&'static str, String)> {
+        vec![("PRIVATE-TOKEN", self.token.clone())]
+    }
+}
+
+impl CodeHost for GitLab {
+    fn name(&self) -> &'static str {
+        "GitLab"
+    }
+
+    fn comment_request(&self, report: &CiReport) -> Option<HostRequest> {
+        let iid = self.merge_request_iid?;
+        Some(HostRequest {
+            method: Method::POST,
+            url: format!(
+                "{}/projects/{}/merge_requests/{iid}/notes",
+                self.api_url, self.project_id
+            ),
+            headers: self.headers(),
+            body: json!({ "body": comment_body(report) }),
+        })
+    }
+
+    fn status_request(&self, report: &CiReport) -> Option<HostRequest> {
+        let mut body = json!({
+            "state": if report.success { "success" } else { "failed" },
+            "name": "codex",
+            "description": short_description(report),
+        });
+        // Attach the status to the running pipeline rather than a new one.
+        if let Some(pipeline_id) = self.pipeline_id {
+            body["pipeline_id"] = json!(pipeline_id);
+        }
+        if let Some(pipeline_url) = &self.pipeline_url {
+            body["target_url"] = json!(pipeline_url);
+        }
+        Some(HostRequest {
+            method: Method::POST,
+            url: format!(
+                "{}/projects/{}/statuses/{}",
+                self.api_url, self.project_id, self.sha
+            ),
+            headers: self.headers(),
+            body,
+        })
+    }
+}
@@ -0,0 +1,422 @@
+//! Issues on GitHub and GitLab, for `codex work-on`: fetching one with its
+//! comments, commenting on it and opening the pull or merge request that
+//! resolves it.
+
+use std::collections::HashMap;
+
+use reqwest::Method;
+use reqwest::Url;
+use serde::Deserialize;
+use serde_json::json;
+
+use super::HostRequest;
+use super::send;
+use super::var;
+use crate::default_client::create_client;
+
+const GITHUB_API_URL: &str = "https://api.github.com";
+
+/// Comments beyond this many are not fetched.
+const MAX_COMMENTS: usize = 100;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum IssueHost {
+    GitHub,
+    GitLab,
+}
+
+/// Where an issue lives, parsed from its web URL.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueRef {
+    host: IssueHost,
+    api_url: String,
+    /// `owner/name` on GitHub; the full project path on GitLab.
+    project: String,
+    pub number: u64,
+    pub url: String,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Issue {
+    pub title: String,
+    pub body: String,
+    pub url: String,
+    pub comments: Vec<IssueComment>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IssueComment {
+    pub author: String,
+    pub body: String,
+}
+
+impl IssueRef {
+    /// Parse `https://github.com/<owner>/<repo>/issues/<n>` (or the same on a
+    /// GitHub Enterprise server) and `https://<gitlab>/<project>/-/issues/<n>`.
+    pub fn parse(url: &str) -> anyhow::Result<Self> {
+        let parsed =
+            Url::parse(url).map_err(|e| anyhow::anyhow!("invalid issue URL {url}: {e}"))?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| anyhow::anyhow!("invalid issue URL {url}: no host"))?;
+        let origin = match parsed.port() {
+            Some(port) => format!("{}://{host}:{port}", parsed.scheme()),
+            None => format!("{}://{host}", parsed.scheme()),
+        };
+        let segments: Vec<&str> = parsed
+            .path_segments()
+            .map(|segments| segments.filter(|s| !s.is_empty()).collect())
+            .unwrap_or_default();
+        let number = segments
+            .last()
+            .and_then(|n| n.parse().ok())
+            .ok_or_else(|| anyhow::anyhow!("{url} does not end in an issue number"))?;
+
+        let (host, api_url, project) = match segments.as_slice() {
+            [project @ .., "-", "issues", _] if !project.is_empty() => (
+                IssueHost::GitLab,
+                format!("{origin}/api/v4"),
+                project.join("/"),
+            ),
+            [owner, repo, "issues", _] => {
+                let api_url = if host == "github.com" {
+                    GITHUB_API_URL.to_string()
+                } else {
+                    format!("{origin}/api/v3")
+                };
+                (IssueHost::GitHub, api_url, format!("{owner}/{repo}"))
+            }
+            _ => anyhow::bail!(
+                "{url} is not a GitHub (…/owner/repo/issues/N) or GitLab (…/project/-/issues/N) issue URL"
+            ),
+        };
+        Ok(Self {
+            host,
+            api_url,
+            project,
+            number,
+            url: url.to_string(),
+        })
+    }
+
+    /// Name of the code host, e.g. "GitLab".
+    pub fn host_name(&self) -> &'static str {
+        match self.host {
+            IssueHost::GitHub => "GitHub",
+            IssueHost::GitLab => "GitLab",
+        }
+    }
+
+    /// The API URL of the repository or project.
+    fn project_url(&self) -> String {
+        match self.host {
+            IssueHost::GitHub => format!("{}/repos/{}", self.api_url, self.project),
+            IssueHost::GitLab => format!(
+                "{}/projects/{}",
+                self.api_url,
+                self.project.replace('/', "%2F")
+            ),
+        }
+    }
+
+    fn issue_url(&self) -> String {
+        format!("{}/issues/{}", self.project_url(), self.number)
+    }
+}
+
+/// Talks to the code host an issue lives on. Reading public issues needs no
+/// token; commenting and opening pull requests do.
+pub struct IssueClient {
+    issue: IssueRef,
+    token: Option<String>,
+    client: reqwest::Client,
+}
+
+impl IssueClient {
+    /// A client for `issue`, authenticated with `GITHUB_TOKEN`/`GH_TOKEN` or
+    /// `CODEX_GITLAB_TOKEN`/`GITLAB_TOKEN` from the environment.
+    pub fn new(issue: IssueRef) -> Self {
+        let env: HashMap<String, String> = std::env::vars().collect();
+        let token = match issue.host {
+            IssueHost::GitHub => var(&env, "GITHUB_TOKEN").or_else(|| var(&env, "GH_TOKEN")),
+            IssueHost::GitLab => {
+                var(&env, "CODEX_GITLAB_TOKEN").or_else(|| var(&env, "GITLAB_TOKEN"))
+            }
+        };
+        Self {
+            token: token.map(str::to_string),
+            issue,
+            client: create_client(),
+        }
+    }
+
+    pub fn has_token(&self) -> bool {
+        self.token.is_some()
+    }
+
+    /// The issue with its title, description and comments.
+    pub async fn fetch(&self) -> anyhow::Result<Issue> {
+        let issue_url = self.issue.issue_url();
+        match self.issue.host {
+            IssueHost::GitHub => {
+                let issue: GitHubIssue = self.get(&issue_url).await?;
+                let comments: Vec<GitHubComment> = self
+                    .get(&format!("{issue_url}/comments?per_page={MAX_COMMENTS}"))
+                    .await?;
+                Ok(Issue {
+                    title: issue.title,
+                    body: issue.body.unwrap_or_default(),
+                    url: issue.html_url,
+                    comments: comments
+                        .into_iter()
+                        .map(|c| IssueComment {
+                            author: c.user.login,
+                            body: c.body.unwrap_or_default(),
+                        })
+                        .collect(),
+                })
+            }
+            IssueHost::GitLab => {
+                let issue: GitLabIssue = self.get(&issue_url).await?;
+                let notes: Vec<GitLabNote> = self
+                    .get(&format!(
+                        "{issue_url}/notes?sort=asc&order_by=created_at&per_page={MAX_COMMENTS}"
+                    ))
+                    .await?;
+                Ok(Issue {
+                    title: issue.title,
+                    body: issue.description.unwrap_or_default(),
+                    url: issue.web_url,
+                    comments: notes
+                        .into_iter()
+                        // System notes record label changes and the like.
+                        .filter(|n| !n.system)
+                        .map(|n| IssueComment {
+                            author: n.author.username,
+                            body: n.body,
+                        })
+                        .collect(),
+                })
+            }
+        }
+    }
+
+    /// Post `body` as a comment on the issue.
+    pub async fn comment(&self, body: &str) -> anyhow::Result<()> {
+        let (url, payload) = match self.issue.host {
+            IssueHost::GitHub => (
+                format!("{}/comments", self.issue.issue_url()),
+                json!({ "body": body }),
+            ),
+            IssueHost::GitLab => (
+                format!("{}/notes", self.issue.issue_url()),
+                json!({ "body": body }),
+            ),
+        };
+        self.request(Method::POST, url, payload).await?;
+        Ok(())
+    }
+
+    /// Open a pull (GitHub) or merge (GitLab) request from `head` into
+    /// `base` and return its web URL.
+    pub async fn open_pull_request(
+        &self,
+        head: &str,
+        base: &str,
+        title: &str,
+        body: &str,
+    ) -> anyhow::Result<String> {
+        let project_url = self.issue.project_url();
+        let (url, payload) = match self.issue.host {
+            IssueHost::GitHub => (
+                format!("{project_url}/pulls"),
+                json!({ "head": head, "base": base, "title": title, "body": body }),
+            ),
+            IssueHost::GitLab => (
+                format!("{project_url}/merge_requests"),
+                json!({
+                    "source_branch": head,
+                    "target_branch": base,
+                    "title": title,
+                    "description": body,
+                }),
+            ),
+        };
+        let created: CreatedPullRequest = self
+            .request(Method::POST, url, payload)
+            .await?
+            .json()
+            .await?;
+        created
+            .html_url
+            .or(created.web_url)
+            .ok_or_else(|| anyhow::anyhow!("{} did not return a URL", self.issue.host_name()))
+    }
+
+    async fn get<T: serde::de::DeserializeOwned>(&self, url: &str) -> anyhow::Result<T> {
+        let response = self
+            .request(Method::GET, url.to_string(), serde_json::Value::Null)
+            .await?;
+        Ok(response.json().await?)
+    }
+
+    async fn request(
+        &self,
+        method: Method,
+        url: String,
+        body: serde_json::Value,
+    ) -> anyhow::Result<reqwest::Response> {
+        let mut headers = Vec::new();
+        match (self.issue.host, &self.token) {
+            (IssueHost::GitHub, token) => {
+                headers.push(("Accept", "application/vnd.github+json".to_string()));
+                if let Some(token) = token {
+                    headers.push(("Authorization", format!("Bearer {token}")));
+                }
+            }
+            (IssueHost::GitLab, Some(token)) => headers.push(("PRIVATE-TOKEN", token.clone())),
+            (IssueHost::GitLab, None) => {}
+        }
+        if method != Method::GET && self.token.is_none() {
+            anyhow::bail!(
+                "{} needs a token to write; see `codex work-on --help`",
+                self.issue.host_name()
+            );
+        }
+        send(
+            &self.client,
+            &HostRequest {
+                method,
+                url,
+                headers,
+                body,
+            },
+        )
+        .await
+    }
+}
+
+/// The initial prompt for working on `issue`.
+pub fn issue_prompt(issue: &Issue) -> String {
+    let mut prompt = format!(
+        "Resolve the following issue.\n\n# {}\n\n{}\n\n{}\n",
+        issue.title.trim(),
+        issue.url,
+        issue.body.trim()
+    );
+    if !issue.comments.is_empty() {
+        prompt.push_str("\n## Comments\n");
+        for comment in &issue.comments {
+            prompt.push_str(&format!(
+                "\n@{} wrote:\n\n{}\n",
+                comment.author,
+                comment.body.trim()
+            ));
+        }
+    }
+    prompt.push_str(
+        "\nWork on the current branch and leave your changes in the working tree; they are committed for you when you are done.\n",
+    );
+    prompt
+}
+
+#[derive(Deserialize)]
+struct GitHubIssue {
+    title: String,
+    body: Option<String>,
+    html_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitHubComment {
+    user: GitHubUser,
+    body: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct GitHubUser {
+    login: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabIssue {
+    title: String,
+    description: Option<String>,
+    web_url: String,
+}
+
+#[derive(Deserialize)]
+struct GitLabNote {
+    author: GitLabUser,
+    body: String,
+    #[serde(default)]
+    system: bool,
+}
+
+#[derive(Deserialize)]
+struct GitLabUser {
+    username: String,
+}
+
+/// `html_url` on GitHub, `web_url` on GitLab.
+#[derive(Deserialize)]
+struct CreatedPullRequest {
+    html_url: Option<String>,
+    web_url: Option<String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn issue_urls_resolve_to_api_endpoints() {
+        let github = IssueRef::parse("https://github.com/openai/codex/issues/42").expect("github");
+        assert_eq!(github.host_name(), "GitHub");
+        assert_eq!(
+            github.issue_url(),
+            "https://api.github.com/repos/openai/codex/issues/42"
+        );
+
+        let enterprise =
+            IssueRef::parse("https://git.corp.example/team/app/issues/7").expect("enterprise");
+        assert_eq!(
+            enterprise.issue_url(),
+            "https://git.corp.example/api/v3/repos/team/app/issues/7"
+        );
+
+        let gitlab =
+            IssueRef::parse("https://gitlab.com/group/sub/app/-/issues/9").expect("gitlab");
+        assert_eq!(gitlab.host_name(), "GitLab");
+        assert_eq!(gitlab.number, 9);
+        assert_eq!(
+            gitlab.issue_url(),
+            "https://gitlab.com/api/v4/projects/group%2Fsub%2Fapp/issues/9"
+        );
+
+        assert!(IssueRef::parse("https://github.com/openai/codex/pull/42").is_err());
+        assert!(IssueRef::parse("not a url").is_err());
+
+        let prompt = issue_prompt(&Issue {
+            title: "Crash on empty config".to_string(),
+            body: "Steps to reproduce…".to_string(),
+            url: github.url.clone(),
+            comments: vec![IssueComment {
+                author: "octocat".to_string(),
+                body: "Also on 0.2".to_string(),
+            }],
+        });
+        assert_eq!(
+            prompt,
+            "Resolve the following issue.\n\n\
+             # Crash on empty config\n\n\
+             https://github.com/openai/codex/issues/42\n\n\
+             Steps to reproduce…\n\n\
+             ## Comments\n\n\
+             @octocat wrote:\n\n\
+             Also on 0.2\n\n\
+             Work on the current branch and leave your changes in the working tree; they are committed for you when you are done.\n"
+        );
+    }
+}
@@ -0,0 +1,266 @@
+//! Reporting `codex exec --ci` runs back to the code host a pipeline runs on.
+//!
+//! Each supported host is a [`CodeHost`], picked from the variables its CI
+//! system sets: GitHub Actions, GitLab CI/CD or Bitbucket Pipelines. When a run
+//! ends, the host posts the agent's final message on the pull or merge request
+//! that triggered the pipeline and records the outcome against the commit.
+//!
+//! [`IssueClient`] covers the other direction for `codex work-on`: reading an
+//! issue to work on and linking the result back to it.
+
+mod bitbucket;
+mod github;
+mod gitlab;
+mod issue;
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+pub use bitbucket::Bitbucket;
+pub use github::GitHub;
+pub use gitlab::GitLab;
+pub use issue::Issue;
+pub use issue::IssueClient;
+pub use issue::IssueComment;
+pub use issue::IssueRef;
+pub use issue::issue_prompt;
+use reqwest::Method;
+use reqwest::Response;
+
+use crate::default_client::create_client;
+use crate::offline::ensure_network_allowed;
+
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Commit statuses show at most this many characters of description.
+const MAX_DESCRIPTION_CHARS: usize = 140;
+
+/// How a run ended, as reported to the code host.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CiReport {
+    pub success: bool,
+    /// The agent's final message, or why the run failed.
+    pub summary: String,
+}
+
+/// One call to a code host's REST API.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HostRequest {
+    pub method: Method,
+    pub url: String,
+    pub headers: Vec<(&'static str, String)>,
+    pub body: serde_json::Value,
+}
+
+pub trait CodeHost: Send + Sync {
+    /// Name shown to the user, e.g. "GitLab".
+    fn name(&self) -> &'static str;
+
+    /// A comment on the pull or merge request that triggered the pipeline,
+    /// or `None` when the pipeline was not triggered by one.
+    fn comment_request(&self, report: &CiReport) -> Option<HostRequest>;
+
+    /// The run's outcome recorded against the commit under test.
+    fn status_request(&self, report: &CiReport) -> Option<HostRequest>;
+
+    /// Lines for the caller to print to the job log, e.g. workflow commands
+    /// that turn into annotations.
+    fn log_commands(&self, _report: &CiReport) -> Vec<String> {
+        Vec::new()
+    }
+
+    /// Report through the pipeline itself rather than the API, e.g. in the
+    /// job summary.
+    fn annotate(&self, _report: &CiReport) -> std::io::Result<()> {
+        Ok(())
+    }
+}
+
+/// The code host of the CI environment this process runs in, if any.
+pub fn detect() -> anyhow::Result<Option<Box<dyn CodeHost>>> {
+    detect_from(&std::env::vars().collect())
+}
+
+/// Like [`detect`], reading variables from `env`. Fails when the CI system is
+/// recognized but a variable the backend needs is missing.
+pub fn detect_from(env: &HashMap<String, String>) -> anyhow::Result<Option<Box<dyn CodeHost>>> {
+    let host: Box<dyn CodeHost> = if var(env, "GITHUB_ACTIONS").is_some() {
+        Box::new(GitHub::from_env(env)?)
+    } else if var(env, "GITLAB_CI").is_some() {
+        Box::new(GitLab::from_env(env)?)
+    } else if var(env, "BITBUCKET_BUILD_NUMBER").is_some() {
+        Box::new(Bitbucket::from_env(env)?)
+    } else {
+        return Ok(None);
+    };
+    Ok(Some(host))
+}
+
+/// Report `report` through `host`. Every channel is attempted; the returned
+/// list describes the ones that failed.
+pub async fn report_run(host: &dyn CodeHost, report: &CiReport) -> Vec<String> {
+    let mut problems = Vec::new();
+    if let Err(e) = host.annotate(report) {
+        problems.push(format!("failed to write the job summary: {e}"));
+    }
+    let requests = [host.comment_request(report), host.status_request(report)];
+    let client = create_client();
+    for request in requests.into_iter().flatten() {
+        if let Err(e) = send(&client, &request).await {
+            problems.push(format!("{} {}: {e}", request.method, request.url));
+        }
+    }
+    problems
+}
+
+/// Send `request`, failing on an error status. A `null` body is not sent.
+async fn send(client: &reqwest::Client, request: &HostRequest) -> anyhow::Result<Response> {
+    ensure_network_allowed(&request.url)?;
+    let mut builder = client
+        .request(request.method.clone(), &request.url)
+        .timeout(REQUEST_TIMEOUT);
+    if !request.body.is_null() {
+        builder = builder.json(&request.body);
+    }
+    for (name, value) in &request.headers {
+        builder = builder.header(*name, value);
+    }
+    Ok(builder.send().await?.error_for_status()?)
+}
+
+/// The non-empty value of `name` in `env`.
+fn var<'a>(env: &'a HashMap<String, String>, name: &str) -> Option<&'a str> {
+    env.get(name).map(String::as_str).filter(|v| !v.is_empty())
+}
+
+fn required_var<'a>(env: &'a HashMap<String, String>, name: &str) -> anyhow::Result<&'a str> {
+    var(env, name).ok_or_else(|| anyhow::anyhow!("{name} is not set"))
+}
+
+/// The markdown comment posted on the pull or merge request.
+fn comment_body(report: &CiReport) -> String {
+    let heading = if report.success {
+        "**Codex** completed the task."
+    } else {
+        "**Codex** failed to complete the task."
+    };
+    format!("{heading}\n\n{}", report.summary.trim())
+}
+
+/// A one-line description of the run for commit statuses.
+fn short_description(report: &CiReport) -> String {
+    let line = report
+        .summary
+        .lines()
+        .map(str::trim)
+        .find(|line| !line.is_empty())
+        .unwrap_or(if report.success {
+            "Task completed"
+        } else {
+            "Task failed"
+        });
+    if line.chars().count() <= MAX_DESCRIPTION_CHARS {
+        return line.to_string();
+    }
+    let mut short: String = line.chars().take(MAX_DESCRIPTION_CHARS - 1).collect();
+    short.push('…');
+    short
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use serde_json::json;
+
+    fn env(vars: &[(&str, &str)]) -> HashMap<String, String> {
+        vars.iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect()
+    }
+
+    #[test]
+    fn detects_host_from_ci_environment() {
+        let report = CiReport {
+            success: false,
+            summary: "Tests still fail\n\nDetails follow".to_string(),
+        };
+
+        assert!(detect_from(&env(&[])).expect("detect").is_none());
+        assert!(detect_from(&env(&[("GITLAB_CI", "true")])).is_err());
+
+        let gitlab = detect_from(&env(&[
+            ("GITLAB_CI", "true"),
+            ("CI_API_V4_URL", "https://gitlab.example.com/api/v4"),
+            ("CI_PROJECT_ID", "42"),
+            ("CI_COMMIT_SHA", "abc123"),
+            ("CI_MERGE_REQUEST_IID", "7"),
+            ("CI_PIPELINE_ID", "900"),
+            ("CODEX_GITLAB_TOKEN", "secret"),
+        ]))
+        .expect("detect")
+        .expect("gitlab");
+        assert_eq!(gitlab.name(), "GitLab");
+        let comment = gitlab.comment_request(&report).expect("comment");
+        assert_eq!(
+            comment.url,
+            "https://gitlab.example.com/api/v4/projects/42/merge_requests/7/notes"
+        );
+        assert_eq!(
+            comment.headers,
+            vec![("PRIVATE-TOKEN", "secret".to_string())]
+        );
+        assert_eq!(
+            comment.body,
+            json!({
+                "body": "**Codex** failed to complete the task.\n\nTests still fail\n\nDetails follow"
+            })
+        );
+        let status = gitlab.status_request(&report).expect("status");
+        assert_eq!(
+            (status.url.as_str(), &status.body),
+            (
+                "https://gitlab.example.com/api/v4/projects/42/statuses/abc123",
+                &json!({
+                    "state": "failed",
+                    "name": "codex",
+                    "description": "Tests still fail",
+                    "pipeline_id": 900,
+                })
+            )
+        );
+
+        let bitbucket = detect_from(&env(&[
+            ("BITBUCKET_BUILD_NUMBER", "12"),
+            ("BITBUCKET_WORKSPACE", "team"),
+            ("BITBUCKET_REPO_SLUG", "app"),
+            ("BITBUCKET_COMMIT", "def456"),
+            ("BITBUCKET_ACCESS_TOKEN", "secret"),
+        ]))
+        .expect("detect")
+        .expect("bitbucket");
+        assert_eq!(bitbucket.name(), "Bitbucket");
+        // Not a pull request pipeline, so there is nothing to comment on.
+        assert!(bitbucket.comment_request(&report).is_none());
+        assert_eq!(
+            bitbucket.status_request(&report).expect("status").url,
+            "https://api.bitbucket.org/2.0/repositories/team/app/commit/def456/statuses/build"
+        );
+
+        let github = detect_from(&env(&[
+            ("GITHUB_ACTIONS", "true"),
+            ("GITHUB_REPOSITORY", "octo/app"),
+            ("GITHUB_REF", "refs/pull/15/merge"),
+            ("GITHUB_SHA", "fed789"),
+        ]))
+        .expect("detect")
+        .expect("github");
+        assert_eq!(github.name(), "GitHub");
+        // Without a token only the job log annotations are written.
+        assert!(github.comment_request(&report).is_none());
+        assert_eq!(
+            github.log_commands(&report),
+            vec!["::error title=Codex::Tests still fail%0A%0ADetails follow".to_string()]
+        );
+    }
+}
@@ -1,11 +1,14 @@
 use std::borrow::Cow;
 use std::collections::HashMap;
 use std::collections::HashSet;
+use std::path::Path;
 use std::path::PathBuf;
 use std::sync::Arc;
 use std::sync::Mutex;
 use std::sync::MutexGuard;
+use std::sync::atomic::AtomicU32;
 use std::sync::atomic::AtomicU64;
+use std::sync::atomic::Ordering;
 use std::time::Duration;
 
 use crate::AuthManager;
@@ -15,17 +18,21 @@ use async_channel::Receiver;
 use async_channel::Sender;
 use codex_apply_patch::ApplyPatchAction;
 use codex_apply_patch::MaybeApplyPatchVerified;
-use codex_apply_patch::maybe_parse_apply_patch_verified;
+use codex_apply_patch::maybe_parse_apply_patch_verified_with_pre_images;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::protocol::ConversationPathResponseEvent;
 use codex_protocol::protocol::ReviewRequest;
 use codex_protocol::protocol::RolloutItem;
 use codex_protocol::protocol::TaskStartedEvent;
+use codex_protocol::protocol::TurnAbortCleanup;
 use codex_protocol::protocol::TurnAbortReason;
 use codex_protocol::protocol::TurnAbortedEvent;
 use codex_protocol::protocol::TurnContextItem;
 use futures::prelude::*;
 use mcp_types::CallToolResult;
+use mcp_types::ModelContextProtocolNotification;
+use mcp_types::ResourceUpdatedNotification;
+use mcp_types::ResourceUpdatedNotificationParams;
 use serde::Deserialize;
 use serde::Serialize;
 use serde_json;
@@ -47,13 +54,19 @@ use crate::client::ModelClient;
 use crate::client_common::Prompt;
 use crate::client_common::ResponseEvent;
 use crate::config::Config;
+use crate::config_types::ApprovalTimeout;
+use crate::config_types::FetchUrl;
+use crate::config_types::FileFormat;
+use crate::config_types::PatchGuard;
 use crate::config_types::ShellEnvironmentPolicy;
+use crate::config_types::SyntaxCheck;
 use crate::conversation_history::ConversationHistory;
 use crate::environment_context::EnvironmentContext;
 use crate::error::CodexErr;
 use crate::error::Result as CodexResult;
 use crate::error::SandboxErr;
 use crate::error::get_error_message_ui;
+use crate::event_log::EventLog;
 use crate::exec::ExecParams;
 use crate::exec::ExecToolCallOutput;
 use crate::exec::SandboxType;
@@ -66,6 +79,8 @@ use crate::exec_command::ExecSessionManager;
 use crate::exec_command::WRITE_STDIN_TOOL_NAME;
 use crate::exec_command::WriteStdinParams;
 use crate::exec_env::create_env;
+use crate::file_watcher::FileWatcher;
+use crate::file_watcher::file_changes_message;
 use crate::mcp_connection_manager::McpConnectionManager;
 use crate::mcp_tool_call::handle_mcp_tool_call;
 use crate::model_family::find_family_for_model;
@@ -75,15 +90,22 @@ use crate::openai_tools::ToolsConfig;
 use crate::openai_tools::ToolsConfigParams;
 use crate::openai_tools::get_openai_tools;
 use crate::parse_command::parse_command;
+use crate::plan_tool::UpdatePlanArgs;
 use crate::plan_tool::handle_update_plan;
+use crate::pre_images::PreImages;
 use crate::project_doc::get_user_instructions;
+use crate::prompt_preview::assemble_prompt_preview;
 use crate::protocol::AgentMessageDeltaEvent;
 use crate::protocol::AgentReasoningDeltaEvent;
 use crate::protocol::AgentReasoningRawContentDeltaEvent;
 use crate::protocol::AgentReasoningSectionBreakEvent;
 use crate::protocol::ApplyPatchApprovalRequestEvent;
+use crate::protocol::ApprovalItem;
+use crate::protocol::ApprovalKind;
+use crate::protocol::ApprovalTimedOutEvent;
 use crate::protocol::AskForApproval;
 use crate::protocol::BackgroundEventEvent;
+use crate::protocol::ErrorCategory;
 use crate::protocol::ErrorEvent;
 use crate::protocol::Event;
 use crate::protocol::EventMsg;
@@ -93,6 +115,7 @@ use crate::protocol::ExecCommandEndEvent;
 use crate::protocol::FileChange;
 use crate::protocol::InputItem;
 use crate::protocol::ListCustomPromptsResponseEvent;
+use crate::protocol::McpResourceUpdatedEvent;
 use crate::protocol::Op;
 use crate::protocol::PatchApplyBeginEvent;
 use crate::protocol::PatchApplyEndEvent;
@@ -102,17 +125,33 @@ use crate::protocol::SandboxPolicy;
 use crate::protocol::SessionConfiguredEvent;
 use crate::protocol::StreamErrorEvent;
 use crate::protocol::Submission;
+use crate::protocol::SubtaskStatus;
 use crate::protocol::TaskCompleteEvent;
 use crate::protocol::TokenUsage;
 use crate::protocol::TokenUsageInfo;
 use crate::protocol::TurnDiffEvent;
 use crate::protocol::WebSearchBeginEvent;
+use crate::repo_map::load_repo_map;
+use crate::repo_map::repo_map_message;
 use crate::rollout::RolloutRecorder;
 use crate::rollout::RolloutRecorderParams;
+use crate::rollout::recovery;
+use crate::rollout::recovery::PendingPatch;
+use crate::rollout::recovery::RecoveryJournal;
 use crate::safety::SafetyCheck;
 use crate::safety::assess_command_safety;
+use crate::safety::assess_read_only_command_safety;
 use crate::safety::assess_safety_for_untrusted_command;
+use crate::scratch::CODEX_SCRATCH_DIR_ENV_VAR;
+use crate::scratch::ScratchDir;
 use crate::shell;
+use crate::speculative_exec::SpeculativeExec;
+use crate::speculative_exec::predict_shell_params;
+use crate::subtasks::SubtaskTracker;
+use crate::subtasks::command_progress_message;
+use crate::syntax_check;
+use crate::token_counter::TokenCounter;
+use crate::tool_emulation::INVALID_TOOL_CALL;
 use crate::turn_diff_tracker::TurnDiffTracker;
 use crate::unified_exec::UnifiedExecSessionManager;
 use crate::user_instructions::UserInstructions;
@@ -271,6 +310,8 @@ struct State {
     history: ConversationHistory,
     token_info: Option<TokenUsageInfo>,
     next_internal_sub_id: u64,
+    subtasks: SubtaskTracker,
+    pre_images: PreImages,
 }
 
 /// Context for an initialized model agent
@@ -285,6 +326,10 @@ pub(crate) struct Session {
     session_manager: ExecSessionManager,
     unified_exec_manager: UnifiedExecSessionManager,
 
+    /// Number of `shell` tool calls whose process is currently running. Used
+    /// to report what was torn down when a turn is aborted.
+    running_exec_calls: AtomicU32,
+
     /// External notifier command (will be passed as args to exec()). When
     /// `None` this feature is disabled.
     notify: Option<Vec<String>>,
@@ -292,10 +337,38 @@ pub(crate) struct Session {
     /// Optional rollout recorder for persisting the conversation transcript so
     /// sessions can be replayed or inspected later.
     rollout: Mutex<Option<RolloutRecorder>>,
+    /// Marker used to detect and recover from an unclean exit. `None` if it
+    /// could not be created.
+    recovery: Option<RecoveryJournal>,
     state: Mutex<State>,
     codex_linux_sandbox_exe: Option<PathBuf>,
     user_shell: shell::Shell,
     show_raw_agent_reasoning: bool,
+    approval_timeout: Option<ApprovalTimeout>,
+    /// Drafts of read-only `shell` calls started while their arguments were
+    /// still streaming. `None` unless `speculative_exec` is enabled.
+    speculative_exec: Option<SpeculativeExec>,
+    /// `[workspace_guard] allow`: directories patches may write into even
+    /// when they resolve outside the writable roots.
+    workspace_guard_allow: Vec<PathBuf>,
+    /// `[patch_guard]`: binary, oversized and generated files patches may
+    /// not edit freely.
+    patch_guard: PatchGuard,
+    /// `[syntax_check]`: validators run on files after a patch writes them.
+    syntax_check: SyntaxCheck,
+    /// `[file_format]`: line endings and encoding of files patches write.
+    file_format: FileFormat,
+    /// `[fetch_url]` limits for the `fetch_url` tool.
+    fetch_url: FetchUrl,
+    /// This session's scratch directory, removed when the session ends.
+    /// `None` if it could not be created.
+    scratch: Option<ScratchDir>,
+    /// `[repo_map]`: map of the working directory in the initial context.
+    repo_map: Option<String>,
+    /// `[telemetry]`: local log of feature usage and errors.
+    event_log: Option<EventLog>,
+    /// `[file_watcher]`: edits made outside the agent, reported each turn.
+    file_watcher: Option<FileWatcher>,
 }
 
 /// The context needed for a single turn of the conversation.
@@ -408,15 +481,28 @@ impl Session {
         // - spin up MCP connection manager
         // - perform default shell discovery
         // - load history metadata
+        // - build the repository map
         let rollout_fut = RolloutRecorder::new(&config, rollout_params);
 
         let mcp_fut = McpConnectionManager::new(config.mcp_servers.clone());
         let default_shell_fut = shell::default_user_shell();
         let history_meta_fut = crate::message_history::history_metadata(&config);
+        let repo_map_fut = load_repo_map(&config.repo_map, &config.codex_home, &cwd);
 
         // Join all independent futures.
-        let (rollout_recorder, mcp_res, default_shell, (history_log_id, history_entry_count)) =
-            tokio::join!(rollout_fut, mcp_fut, default_shell_fut, history_meta_fut);
+        let (
+            rollout_recorder,
+            mcp_res,
+            default_shell,
+            (history_log_id, history_entry_count),
+            repo_map,
+        ) = tokio::join!(
+            rollout_fut,
+            mcp_fut,
+            default_shell_fut,
+            history_meta_fut,
+            repo_map_fut
+        );
 
         let rollout_recorder = rollout_recorder.map_err(|e| {
             error!("failed to initialize rollout recorder: {e:#}");
@@ -437,7 +523,10 @@ impl Session {
                 error!("{message}");
                 post_session_configured_error_events.push(Event {
                     id: INITIAL_SUBMIT_ID.to_owned(),
-                    msg: EventMsg::Error(ErrorEvent { message }),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message,
+                        category: None,
+                    }),
                 });
                 (McpConnectionManager::default(), Default::default())
             }
@@ -450,7 +539,10 @@ impl Session {
                 error!("{message}");
                 post_session_configured_error_events.push(Event {
                     id: INITIAL_SUBMIT_ID.to_owned(),
-                    msg: EventMsg::Error(ErrorEvent { message }),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message,
+                        category: None,
+                    }),
                 });
             }
         }
@@ -465,6 +557,24 @@ impl Session {
             model_reasoning_summary,
             conversation_id,
         );
+        let scratch = match ScratchDir::create(&config.codex_home, conversation_id) {
+            Ok(scratch) => Some(scratch),
+            Err(e) => {
+                warn!("failed to create scratch dir: {e}");
+                None
+            }
+        };
+        let mut shell_environment_policy = config.shell_environment_policy.clone();
+        let sandbox_policy = match &scratch {
+            Some(scratch) => {
+                shell_environment_policy.r#set.insert(
+                    CODEX_SCRATCH_DIR_ENV_VAR.to_string(),
+                    scratch.path().to_string_lossy().into_owned(),
+                );
+                scratch.extend_sandbox_policy(sandbox_policy)
+            }
+            None => sandbox_policy,
+        };
         let turn_context = TurnContext {
             client,
             tools_config: ToolsConfig::new(&ToolsConfigParams {
@@ -476,28 +586,68 @@ impl Session {
                 include_web_search_request: config.tools_web_search_request,
                 use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                 include_view_image_tool: config.include_view_image_tool,
+                include_fetch_url_tool: config.include_fetch_url_tool,
                 experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                read_only: config.read_only,
             }),
             user_instructions,
             base_instructions,
             approval_policy,
             sandbox_policy,
-            shell_environment_policy: config.shell_environment_policy.clone(),
+            shell_environment_policy,
             cwd,
             is_review_mode: false,
         };
+        // A marker left behind by an earlier run of this conversation means
+        // that run crashed; remember the patch it may have been applying.
+        let interrupted_patch = match &initial_history {
+            InitialHistory::Resumed(_) => {
+                recovery::take_stale_marker(&config.codex_home, conversation_id)
+                    .and_then(|marker| marker.pending_patch)
+            }
+            InitialHistory::New | InitialHistory::Forked(_) => None,
+        };
+        let recovery_journal = match RecoveryJournal::create(
+            &config.codex_home,
+            conversation_id,
+            rollout_path.clone(),
+            turn_context.cwd.clone(),
+        ) {
+            Ok(journal) => Some(journal),
+            Err(e) => {
+                warn!("failed to create recovery marker: {e}");
+                None
+            }
+        };
         let sess = Arc::new(Session {
             conversation_id,
             tx_event: tx_event.clone(),
             mcp_connection_manager,
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            running_exec_calls: AtomicU32::new(0),
             notify,
             state: Mutex::new(state),
             rollout: Mutex::new(Some(rollout_recorder)),
+            recovery: recovery_journal,
             codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
             user_shell: default_shell,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            approval_timeout: config.approval_timeout,
+            speculative_exec: config.speculative_exec.then(SpeculativeExec::default),
+            workspace_guard_allow: config.workspace_guard.allow.clone(),
+            patch_guard: config.patch_guard.clone(),
+            syntax_check: config.syntax_check.clone(),
+            file_format: config.file_format,
+            fetch_url: config.fetch_url.clone(),
+            scratch,
+            repo_map,
+            event_log: EventLog::start(&config, conversation_id.to_string()),
+            file_watcher: config
+                .file_watcher
+                .enabled
+                .then(|| FileWatcher::start(&config.cwd, config.file_watcher.max_files))
+                .flatten(),
         });
 
         // Dispatch the SessionConfiguredEvent first and then report any errors.
@@ -506,6 +656,23 @@ impl Session {
         sess.record_initial_history(&turn_context, initial_history)
             .await;
 
+        // Let both the model and the user know how far the interrupted patch got.
+        if let Some(patch) = interrupted_patch {
+            let message = patch.reconciliation_message();
+            sess.record_conversation_items(&[ResponseItem::Message {
+                id: None,
+                role: "user".to_string(),
+                content: vec![ContentItem::InputText {
+                    text: message.clone(),
+                }],
+            }])
+            .await;
+            post_session_configured_error_events.push(Event {
+                id: INITIAL_SUBMIT_ID.to_owned(),
+                msg: EventMsg::BackgroundEvent(BackgroundEventEvent { message }),
+            });
+        }
+
         let events = std::iter::once(Event {
             id: INITIAL_SUBMIT_ID.to_owned(),
             msg: EventMsg::SessionConfigured(SessionConfiguredEvent {
@@ -523,6 +690,8 @@ impl Session {
             sess.send_event(event).await;
         }
 
+        forward_mcp_resource_updates(&sess.mcp_connection_manager, tx_event);
+
         Ok((sess, turn_context))
     }
 
@@ -572,9 +741,10 @@ impl Session {
                     self.record_into_history(&reconstructed_history);
                 }
 
-                // If persisting, persist all rollout items as-is (recorder filters)
+                // If persisting, persist all rollout items as-is (recorder filters).
+                // The event log counted them when they first happened.
                 if persist && !rollout_items.is_empty() {
-                    self.persist_rollout_items(&rollout_items).await;
+                    self.write_rollout_items(&rollout_items).await;
                 }
             }
         }
@@ -590,6 +760,39 @@ impl Session {
         }
     }
 
+    /// The contents of `path` as the model last saw them, if recorded.
+    fn pre_image(&self, path: &Path) -> Option<String> {
+        self.state.lock_unchecked().pre_images.get(path)
+    }
+
+    /// Apply `update` to the sub-task tracker and send the events it yields.
+    async fn update_subtasks(
+        &self,
+        sub_id: &str,
+        update: impl FnOnce(&mut SubtaskTracker) -> Vec<EventMsg>,
+    ) {
+        let events = update(&mut self.state.lock_unchecked().subtasks);
+        for msg in events {
+            self.send_event(Event {
+                id: sub_id.to_string(),
+                msg,
+            })
+            .await;
+        }
+    }
+
+    /// Report the steps of an `update_plan` call as sub-tasks.
+    pub(crate) async fn report_plan_progress(&self, sub_id: &str, args: &UpdatePlanArgs) {
+        self.update_subtasks(sub_id, |tracker| tracker.on_plan_update(sub_id, args))
+            .await;
+    }
+
+    /// Close the sub-tasks the task `sub_id` left open.
+    async fn finish_subtasks(&self, sub_id: &str, status: SubtaskStatus) {
+        self.update_subtasks(sub_id, |tracker| tracker.finish(sub_id, status))
+            .await;
+    }
+
     pub async fn request_command_approval(
         &self,
         sub_id: String,
@@ -654,6 +857,70 @@ impl Session {
         rx_approve
     }
 
+    /// Wait for the user's answer to an approval request. If
+    /// `approval_timeout_secs` is configured and elapses first, the configured
+    /// action is applied on the user's behalf and recorded in the event stream.
+    pub(crate) async fn wait_for_approval(
+        &self,
+        sub_id: &str,
+        call_id: &str,
+        kind: ApprovalKind,
+        rx_approve: oneshot::Receiver<ReviewDecision>,
+    ) -> ReviewDecision {
+        let (decision, timed_out) = self
+            .await_approval_decision(sub_id, call_id, rx_approve)
+            .await;
+        self.persist_rollout_items(&[RolloutItem::Approval(ApprovalItem {
+            call_id: call_id.to_string(),
+            kind,
+            decision,
+            timed_out,
+        })])
+        .await;
+        decision
+    }
+
+    /// The decision for a pending approval, and whether it came from
+    /// `approval_timeout_secs` rather than the user.
+    async fn await_approval_decision(
+        &self,
+        sub_id: &str,
+        call_id: &str,
+        rx_approve: oneshot::Receiver<ReviewDecision>,
+    ) -> (ReviewDecision, bool) {
+        let Some(timeout) = self.approval_timeout else {
+            return (rx_approve.await.unwrap_or_default(), false);
+        };
+        match tokio::time::timeout(timeout.duration, rx_approve).await {
+            Ok(decision) => (decision.unwrap_or_default(), false),
+            Err(_) => {
+                {
+                    let mut state = self.state.lock_unchecked();
+                    state.pending_approvals.remove(sub_id);
+                }
+                info!(
+                    "approval request for {call_id} timed out; applying {:?}",
+                    timeout.action
+                );
+                self.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::ApprovalTimedOut(ApprovalTimedOutEvent {
+                        call_id: call_id.to_string(),
+                        timeout_secs: timeout.duration.as_secs(),
+                        action: timeout.action,
+                    }),
+                })
+                .await;
+                let decision = timeout.action.decision();
+                if decision == ReviewDecision::Abort {
+                    // Same as the user answering "abort": stop the turn.
+                    self.interrupt_task();
+                }
+                (decision, true)
+            }
+        }
+    }
+
     pub fn notify_approval(&self, sub_id: &str, decision: ReviewDecision) {
         let entry = {
             let mut state = self.state.lock_unchecked();
@@ -669,6 +936,61 @@ impl Session {
         }
     }
 
+    pub(crate) fn workspace_guard_allow(&self) -> &[PathBuf] {
+        &self.workspace_guard_allow
+    }
+
+    pub(crate) fn patch_guard(&self) -> &PatchGuard {
+        &self.patch_guard
+    }
+
+    /// Syntax-check the files a successfully applied patch wrote. Returns the
+    /// report for the model when some of them do not parse.
+    async fn check_patch_syntax(
+        &self,
+        sub_id: &str,
+        changes: &HashMap<PathBuf, FileChange>,
+        cwd: &Path,
+    ) -> Option<String> {
+        if !self.syntax_check.enabled {
+            return None;
+        }
+        let mut paths: Vec<PathBuf> = changes
+            .iter()
+            .filter_map(|(path, change)| match change {
+                FileChange::Add { .. } => Some(path.clone()),
+                FileChange::Delete { .. } => None,
+                FileChange::Update { move_path, .. } => {
+                    Some(move_path.clone().unwrap_or_else(|| path.clone()))
+                }
+            })
+            .collect();
+        paths.sort();
+        let errors = syntax_check::check_files(&paths, &self.syntax_check, cwd).await;
+        if errors.is_empty() {
+            return None;
+        }
+        let names = errors
+            .iter()
+            .map(|error| {
+                let path = error.path.strip_prefix(cwd).unwrap_or(&error.path);
+                path.display().to_string()
+            })
+            .collect::<Vec<_>>()
+            .join(", ");
+        self.notify_background_event(sub_id, format!("syntax check failed: {names}"))
+            .await;
+        Some(syntax_check::format_errors(&errors, cwd))
+    }
+
+    /// `policy` with this session's scratch directory made writable.
+    fn with_scratch_dir(&self, policy: SandboxPolicy) -> SandboxPolicy {
+        match &self.scratch {
+            Some(scratch) => scratch.extend_sandbox_policy(policy),
+            None => policy,
+        }
+    }
+
     pub fn add_approved_command(&self, cmd: Vec<String>) {
         let mut state = self.state.lock_unchecked();
         state.approved_commands.insert(cmd);
@@ -726,7 +1048,7 @@ impl Session {
     }
 
     fn build_initial_context(&self, turn_context: &TurnContext) -> Vec<ResponseItem> {
-        let mut items = Vec::<ResponseItem>::with_capacity(2);
+        let mut items = Vec::<ResponseItem>::with_capacity(3);
         if let Some(user_instructions) = turn_context.user_instructions.as_deref() {
             items.push(UserInstructions::new(user_instructions.to_string()).into());
         }
@@ -735,11 +1057,26 @@ impl Session {
             Some(turn_context.approval_policy),
             Some(turn_context.sandbox_policy.clone()),
             Some(self.user_shell.clone()),
+            // Read-only sessions cannot write to it, so do not offer it.
+            self.scratch
+                .as_ref()
+                .filter(|_| turn_context.sandbox_policy != SandboxPolicy::ReadOnly)
+                .map(|scratch| scratch.path().to_path_buf()),
         )));
+        if let Some(repo_map) = &self.repo_map {
+            items.push(repo_map_message(repo_map));
+        }
         items
     }
 
     async fn persist_rollout_items(&self, items: &[RolloutItem]) {
+        if let Some(event_log) = &self.event_log {
+            event_log.observe(items);
+        }
+        self.write_rollout_items(items).await;
+    }
+
+    async fn write_rollout_items(&self, items: &[RolloutItem]) {
         let recorder = {
             let guard = self.rollout.lock_unchecked();
             guard.as_ref().cloned()
@@ -814,15 +1151,22 @@ impl Session {
                     changes,
                 })
             }
-            None => EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
-                call_id,
-                command: command_for_display.clone(),
-                cwd,
-                parsed_cmd: parse_command(&command_for_display)
-                    .into_iter()
-                    .map(Into::into)
-                    .collect(),
-            }),
+            None => {
+                let message = command_progress_message(&command_for_display);
+                self.update_subtasks(&sub_id, |tracker| {
+                    tracker.progress(&sub_id, message).into_iter().collect()
+                })
+                .await;
+                EventMsg::ExecCommandBegin(ExecCommandBeginEvent {
+                    call_id,
+                    command: command_for_display.clone(),
+                    cwd,
+                    parsed_cmd: parse_command(&command_for_display)
+                        .into_iter()
+                        .map(Into::into)
+                        .collect(),
+                })
+            }
         };
         let event = Event {
             id: sub_id.to_string(),
@@ -908,14 +1252,28 @@ impl Session {
         self.on_exec_command_begin(turn_diff_tracker, begin_ctx.clone())
             .await;
 
-        let result = process_exec_tool_call(
-            exec_args.params,
-            exec_args.sandbox_type,
-            exec_args.sandbox_policy,
-            exec_args.codex_linux_sandbox_exe,
-            exec_args.stdout_stream,
-        )
-        .await;
+        // If the turn is aborted while the command is running this future is
+        // dropped, which kills the command's process group and releases the
+        // guard.
+        let running = RunningExecGuard::new(&self.running_exec_calls);
+        let prefetched = match &self.speculative_exec {
+            Some(speculative) => speculative.take(&call_id, &exec_args.params).await,
+            None => None,
+        };
+        let result = match prefetched {
+            Some(output) => Ok(output),
+            None => {
+                process_exec_tool_call(
+                    exec_args.params,
+                    exec_args.sandbox_type,
+                    exec_args.sandbox_policy,
+                    exec_args.codex_linux_sandbox_exe,
+                    exec_args.stdout_stream,
+                )
+                .await
+            }
+        };
+        drop(running);
 
         let output_stderr;
         let borrowed: &ExecToolCallOutput = match &result {
@@ -940,9 +1298,44 @@ impl Session {
         )
         .await;
 
+        if borrowed.exit_code == 0 {
+            let mut state = self.state.lock_unchecked();
+            match &begin_ctx.apply_patch {
+                Some(ApplyPatchCommandContext { changes, .. }) => {
+                    state.pre_images.record_patch(changes)
+                }
+                None => state
+                    .pre_images
+                    .record_reads(&begin_ctx.command_for_display, &begin_ctx.cwd),
+            }
+        }
+
         result
     }
 
+    /// Start a draft of a `shell` call from its partially streamed arguments
+    /// when speculative execution is enabled.
+    fn speculate_shell_call(
+        &self,
+        turn_context: &TurnContext,
+        call_id: &str,
+        name: &str,
+        arguments: &str,
+    ) {
+        let Some(speculative) = &self.speculative_exec else {
+            return;
+        };
+        if !matches!(name, "shell" | "container.exec") {
+            return;
+        }
+        let Some(params) = predict_shell_params(arguments) else {
+            return;
+        };
+        let params =
+            maybe_translate_shell_command(to_exec_params(params, turn_context), self, turn_context);
+        speculative.start(call_id, params, &self.codex_linux_sandbox_exe);
+    }
+
     /// Helper that emits a BackgroundEvent with the given message. This keeps
     /// the call‑sites terse so adding more diagnostics does not clutter the
     /// core agent logic.
@@ -972,6 +1365,60 @@ impl Session {
         [self.state.lock_unchecked().history.contents(), extra].concat()
     }
 
+    /// Fetch the MCP resources and prompts referenced by `items` and extract
+    /// the text of attached documents. On failure the user is sent an error
+    /// and `None` is returned so the turn is dropped rather than run without
+    /// the content they asked for.
+    async fn resolve_input(&self, sub_id: &str, items: Vec<InputItem>) -> Option<Vec<InputItem>> {
+        let resolved =
+            match crate::mcp_input::resolve_mcp_input(&self.mcp_connection_manager, items).await {
+                Ok(items) => crate::documents::resolve_documents(items).await,
+                Err(e) => Err(e),
+            };
+        match resolved {
+            Ok(items) => Some(items),
+            Err(e) => {
+                self.send_event(Event {
+                    id: sub_id.to_string(),
+                    msg: EventMsg::Error(ErrorEvent {
+                        message: format!("{e:#}"),
+                        category: None,
+                    }),
+                })
+                .await;
+                None
+            }
+        }
+    }
+
+    /// Start a turn for `items`, or add them to the running one once their
+    /// MCP resources and documents are resolved. Resolution happens off the
+    /// submission loop so a slow server cannot hold up Interrupt, approvals
+    /// or Shutdown.
+    fn submit_input(
+        self: &Arc<Self>,
+        turn_context: Arc<TurnContext>,
+        sub_id: String,
+        items: Vec<InputItem>,
+    ) {
+        if self.state.lock_unchecked().current_task.is_none() {
+            let task = AgentTask::spawn(Arc::clone(self), turn_context, sub_id, items);
+            self.set_task(task);
+            return;
+        }
+        let sess = Arc::clone(self);
+        tokio::spawn(async move {
+            let Some(items) = sess.resolve_input(&sub_id, items).await else {
+                return;
+            };
+            // The running task may have finished while we were resolving.
+            if let Err(items) = sess.inject_input(items) {
+                let task = AgentTask::spawn(Arc::clone(&sess), turn_context, sub_id, items);
+                sess.set_task(task);
+            }
+        });
+    }
+
     /// Returns the input if there was no task running to inject into
     pub fn inject_input(&self, input: Vec<InputItem>) -> Result<(), Vec<InputItem>> {
         let mut state = self.state.lock_unchecked();
@@ -1006,6 +1453,14 @@ impl Session {
             .await
     }
 
+    /// Closes the interactive PTY sessions that turn `sub_id` started with
+    /// `exec_command` or `unified_exec`, returning how many were still
+    /// running. Sessions earlier turns left open stay up.
+    async fn close_pty_sessions(&self, sub_id: &str) -> usize {
+        self.session_manager.terminate_turn(sub_id).await
+            + self.unified_exec_manager.terminate_turn(sub_id).await
+    }
+
     fn interrupt_task(&self) {
         info!("interrupt received: abort current task, if any");
         let mut state = self.state.lock_unchecked();
@@ -1020,28 +1475,8 @@ impl Session {
     /// the last argument. Failures are logged but otherwise ignored so that
     /// notification issues do not interfere with the main workflow.
     fn maybe_notify(&self, notification: UserNotification) {
-        let Some(notify_command) = &self.notify else {
-            return;
-        };
-
-        if notify_command.is_empty() {
-            return;
-        }
-
-        let Ok(json) = serde_json::to_string(&notification) else {
-            error!("failed to serialise notification payload");
-            return;
-        };
-
-        let mut command = std::process::Command::new(&notify_command[0]);
-        if notify_command.len() > 1 {
-            command.args(&notify_command[1..]);
-        }
-        command.arg(json);
-
-        // Fire-and-forget – we do not wait for completion.
-        if let Err(e) = command.spawn() {
-            warn!("failed to spawn notifier '{}': {e}", notify_command[0]);
+        if let Some(notify_command) = &self.notify {
+            notification.send(notify_command);
         }
     }
 }
@@ -1074,6 +1509,24 @@ enum AgentTaskKind {
     Compact,
 }
 
+/// Counts a `shell` tool call as running for as long as it is alive.
+struct RunningExecGuard<'a> {
+    counter: &'a AtomicU32,
+}
+
+impl<'a> RunningExecGuard<'a> {
+    fn new(counter: &'a AtomicU32) -> Self {
+        counter.fetch_add(1, Ordering::SeqCst);
+        Self { counter }
+    }
+}
+
+impl Drop for RunningExecGuard<'_> {
+    fn drop(&mut self) {
+        self.counter.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
 /// A series of Turns in response to user input.
 pub(crate) struct AgentTask {
     sess: Arc<Session>,
@@ -1093,7 +1546,15 @@ impl AgentTask {
             let sess = sess.clone();
             let sub_id = sub_id.clone();
             let tc = Arc::clone(&turn_context);
-            tokio::spawn(async move { run_task(sess, tc, sub_id, input).await }).abort_handle()
+            tokio::spawn(async move {
+                // Resolving inside the task lets Interrupt abort a slow fetch.
+                let Some(input) = sess.resolve_input(&sub_id, input).await else {
+                    sess.remove_task(&sub_id);
+                    return;
+                };
+                run_task(sess, tc, sub_id, input).await
+            })
+            .abort_handle()
         };
         Self {
             sess,
@@ -1157,13 +1618,28 @@ impl AgentTask {
                     exit_review_mode(sess, sub_id, None).await;
                 });
             }
+            // Aborting the task drops any in-flight exec future, which kills
+            // the process group of the running command (see
+            // `consume_truncated_output`).
+            let terminated_tool_calls = self.sess.running_exec_calls.load(Ordering::SeqCst);
             self.handle.abort();
-            let event = Event {
-                id: self.sub_id,
-                msg: EventMsg::TurnAborted(TurnAbortedEvent { reason }),
-            };
+            let sub_id = self.sub_id;
             let sess = self.sess;
             tokio::spawn(async move {
+                sess.finish_subtasks(&sub_id, SubtaskStatus::Cancelled)
+                    .await;
+                let closed_pty_sessions = sess.close_pty_sessions(&sub_id).await;
+                let event = Event {
+                    id: sub_id,
+                    msg: EventMsg::TurnAborted(TurnAbortedEvent {
+                        reason,
+                        cleanup: TurnAbortCleanup {
+                            terminated_tool_calls,
+                            closed_pty_sessions: u32::try_from(closed_pty_sessions)
+                                .unwrap_or(u32::MAX),
+                        },
+                    }),
+                };
                 sess.send_event(event).await;
             });
         }
@@ -1193,6 +1669,12 @@ async fn submission_loop(
                 effort,
                 summary,
             } => {
+                // A read-only session keeps its policies whatever the client asks.
+                let (approval_policy, sandbox_policy) = if config.read_only {
+                    (None, None)
+                } else {
+                    (approval_policy, sandbox_policy)
+                };
                 // Recalculate the persistent turn context with provided overrides.
                 let prev = Arc::clone(&turn_context);
                 let provider = prev.client.get_provider();
@@ -1230,9 +1712,11 @@ async fn submission_loop(
                 );
 
                 let new_approval_policy = approval_policy.unwrap_or(prev.approval_policy);
-                let new_sandbox_policy = sandbox_policy
-                    .clone()
-                    .unwrap_or(prev.sandbox_policy.clone());
+                let new_sandbox_policy = sess.with_scratch_dir(
+                    sandbox_policy
+                        .clone()
+                        .unwrap_or(prev.sandbox_policy.clone()),
+                );
                 let new_cwd = cwd.clone().unwrap_or_else(|| prev.cwd.clone());
 
                 let tools_config = ToolsConfig::new(&ToolsConfigParams {
@@ -1244,7 +1728,9 @@ async fn submission_loop(
                     include_web_search_request: config.tools_web_search_request,
                     use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
                     include_view_image_tool: config.include_view_image_tool,
+                    include_fetch_url_tool: config.include_fetch_url_tool,
                     experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                    read_only: config.read_only,
                 });
 
                 let new_turn_context = TurnContext {
@@ -1270,18 +1756,13 @@ async fn submission_loop(
                         sandbox_policy,
                         // Shell is not configurable from turn to turn
                         None,
+                        None,
                     ))])
                     .await;
                 }
             }
             Op::UserInput { items } => {
-                // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items) {
-                    // no current task, spawn a new one
-                    let task =
-                        AgentTask::spawn(sess.clone(), Arc::clone(&turn_context), sub.id, items);
-                    sess.set_task(task);
-                }
+                sess.submit_input(Arc::clone(&turn_context), sub.id, items);
             }
             Op::UserTurn {
                 items,
@@ -1292,64 +1773,61 @@ async fn submission_loop(
                 effort,
                 summary,
             } => {
-                // attempt to inject input into current task
-                if let Err(items) = sess.inject_input(items) {
-                    // Derive a fresh TurnContext for this turn using the provided overrides.
-                    let provider = turn_context.client.get_provider();
-                    let auth_manager = turn_context.client.get_auth_manager();
-
-                    // Derive a model family for the requested model; fall back to the session's.
-                    let model_family = find_family_for_model(&model)
-                        .unwrap_or_else(|| config.model_family.clone());
-
-                    // Create a per‑turn Config clone with the requested model/family.
-                    let mut per_turn_config = (*config).clone();
-                    per_turn_config.model = model.clone();
-                    per_turn_config.model_family = model_family.clone();
-                    if let Some(model_info) = get_model_info(&model_family) {
-                        per_turn_config.model_context_window = Some(model_info.context_window);
-                    }
+                // Derive a fresh TurnContext for this turn using the provided overrides.
+                // It is only used if no task is running to take the input once it
+                // is resolved.
+                let sandbox_policy = sess.with_scratch_dir(sandbox_policy);
+                let provider = turn_context.client.get_provider();
+                let auth_manager = turn_context.client.get_auth_manager();
+
+                // Derive a model family for the requested model; fall back to the session's.
+                let model_family =
+                    find_family_for_model(&model).unwrap_or_else(|| config.model_family.clone());
+
+                // Create a per‑turn Config clone with the requested model/family.
+                let mut per_turn_config = (*config).clone();
+                per_turn_config.model = model.clone();
+                per_turn_config.model_family = model_family.clone();
+                if let Some(model_info) = get_model_info(&model_family) {
+                    per_turn_config.model_context_window = Some(model_info.context_window);
+                }
 
-                    // Build a new client with per‑turn reasoning settings.
-                    // Reuse the same provider and session id; auth defaults to env/API key.
-                    let client = ModelClient::new(
-                        Arc::new(per_turn_config),
-                        auth_manager,
-                        provider,
-                        effort,
-                        summary,
-                        sess.conversation_id,
-                    );
+                // Build a new client with per‑turn reasoning settings.
+                // Reuse the same provider and session id; auth defaults to env/API key.
+                let client = ModelClient::new(
+                    Arc::new(per_turn_config),
+                    auth_manager,
+                    provider,
+                    effort,
+                    summary,
+                    sess.conversation_id,
+                );
 
-                    let fresh_turn_context = TurnContext {
-                        client,
-                        tools_config: ToolsConfig::new(&ToolsConfigParams {
-                            model_family: &model_family,
-                            approval_policy,
-                            sandbox_policy: sandbox_policy.clone(),
-                            include_plan_tool: config.include_plan_tool,
-                            include_apply_patch_tool: config.include_apply_patch_tool,
-                            include_web_search_request: config.tools_web_search_request,
-                            use_streamable_shell_tool: config
-                                .use_experimental_streamable_shell_tool,
-                            include_view_image_tool: config.include_view_image_tool,
-                            experimental_unified_exec_tool: config
-                                .use_experimental_unified_exec_tool,
-                        }),
-                        user_instructions: turn_context.user_instructions.clone(),
-                        base_instructions: turn_context.base_instructions.clone(),
+                let fresh_turn_context = TurnContext {
+                    client,
+                    tools_config: ToolsConfig::new(&ToolsConfigParams {
+                        model_family: &model_family,
                         approval_policy,
-                        sandbox_policy,
-                        shell_environment_policy: turn_context.shell_environment_policy.clone(),
-                        cwd,
-                        is_review_mode: false,
-                    };
-                    // TODO: record the new environment context in the conversation history
-                    // no current task, spawn a new one with the per‑turn context
-                    let task =
-                        AgentTask::spawn(sess.clone(), Arc::new(fresh_turn_context), sub.id, items);
-                    sess.set_task(task);
-                }
+                        sandbox_policy: sandbox_policy.clone(),
+                        include_plan_tool: config.include_plan_tool,
+                        include_apply_patch_tool: config.include_apply_patch_tool,
+                        include_web_search_request: config.tools_web_search_request,
+                        use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+                        include_view_image_tool: config.include_view_image_tool,
+                        include_fetch_url_tool: config.include_fetch_url_tool,
+                        experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+                        read_only: config.read_only,
+                    }),
+                    user_instructions: turn_context.user_instructions.clone(),
+                    base_instructions: turn_context.base_instructions.clone(),
+                    approval_policy,
+                    sandbox_policy,
+                    shell_environment_policy: turn_context.shell_environment_policy.clone(),
+                    cwd,
+                    is_review_mode: false,
+                };
+                // TODO: record the new environment context in the conversation history
+                sess.submit_input(Arc::new(fresh_turn_context), sub.id, items);
             }
             Op::ExecApproval { id, decision } => match decision {
                 ReviewDecision::Abort => {
@@ -1410,15 +1888,37 @@ async fn submission_loop(
             Op::ListMcpTools => {
                 let sub_id = sub.id.clone();
 
-                // This is a cheap lookup from the connection manager's cache.
+                // Tools and prompts come from the connection manager's cache;
+                // resources are listed live, in their own task so a slow server
+                // does not stall the submission loop.
                 let tools = sess.mcp_connection_manager.list_all_tools();
-                let event = Event {
-                    id: sub_id,
-                    msg: EventMsg::McpListToolsResponse(
-                        crate::protocol::McpListToolsResponseEvent { tools },
-                    ),
+                let prompts = sess.mcp_connection_manager.list_all_prompts();
+                let sess = sess.clone();
+                tokio::spawn(async move {
+                    let resources = sess.mcp_connection_manager.list_all_resources().await;
+                    let event = Event {
+                        id: sub_id,
+                        msg: EventMsg::McpListToolsResponse(
+                            crate::protocol::McpListToolsResponseEvent {
+                                tools,
+                                resources,
+                                prompts,
+                            },
+                        ),
+                    };
+                    sess.send_event(event).await;
+                });
+            }
+            Op::SubscribeMcpResource { server, uri } => {
+                let message = match sess
+                    .mcp_connection_manager
+                    .subscribe_resource(&server, &uri)
+                    .await
+                {
+                    Ok(()) => format!("Watching {server}:{uri} for changes"),
+                    Err(e) => format!("Could not subscribe to {server}:{uri}: {e:#}"),
                 };
-                sess.send_event(event).await;
+                sess.notify_background_event(&sub.id, message).await;
             }
             Op::ListCustomPrompts => {
                 let sub_id = sub.id.clone();
@@ -1434,10 +1934,34 @@ async fn submission_loop(
                     id: sub_id,
                     msg: EventMsg::ListCustomPromptsResponse(ListCustomPromptsResponseEvent {
                         custom_prompts,
+                        mcp_prompts: sess.mcp_connection_manager.list_all_prompts(),
                     }),
                 };
                 sess.send_event(event).await;
             }
+            Op::GetPrompt => {
+                // Assemble the prompt exactly as `run_turn` would for the
+                // next turn, with the conversation left out.
+                let prompt = Prompt {
+                    input: Vec::new(),
+                    tools: get_openai_tools(
+                        &turn_context.tools_config,
+                        Some(sess.mcp_connection_manager.list_all_tools()),
+                    ),
+                    base_instructions_override: turn_context.base_instructions.clone(),
+                    appended_instructions: turn_context.client.get_appended_instructions(),
+                };
+                let preview = assemble_prompt_preview(
+                    &prompt,
+                    &turn_context.client.get_model_family(),
+                    &sess.build_initial_context(&turn_context),
+                );
+                sess.send_event(Event {
+                    id: sub.id.clone(),
+                    msg: EventMsg::PromptPreview(preview),
+                })
+                .await;
+            }
             Op::Compact => {
                 // Attempt to inject input into current task
                 if let Err(items) = sess.inject_input(vec![InputItem::Text {
@@ -1453,6 +1977,9 @@ async fn submission_loop(
             }
             Op::Shutdown => {
                 info!("Shutting down Codex instance");
+                // A turn still running would keep going, and writing to the
+                // rollout, after the session is gone.
+                sess.interrupt_task();
 
                 // Gracefully flush and shutdown rollout recorder on session end so tests
                 // that inspect the rollout file do not race with the background writer.
@@ -1465,10 +1992,17 @@ async fn submission_loop(
                         id: sub.id.clone(),
                         msg: EventMsg::Error(ErrorEvent {
                             message: "Failed to shutdown rollout recorder".to_string(),
+                            category: None,
                         }),
                     };
                     sess.send_event(event).await;
                 }
+                if let Some(journal) = &sess.recovery {
+                    journal.complete();
+                }
+                if let Some(scratch) = &sess.scratch {
+                    scratch.remove();
+                }
 
                 let event = Event {
                     id: sub.id.clone(),
@@ -1522,6 +2056,45 @@ async fn submission_loop(
     debug!("Agent loop exited");
 }
 
+/// Relay `notifications/resources/updated` from every MCP server as
+/// `McpResourceUpdated` events. Each relay ends when its server's client is
+/// dropped with the session.
+fn forward_mcp_resource_updates(manager: &McpConnectionManager, tx_event: Sender<Event>) {
+    for (server, mut notifications) in manager.subscribe_notifications() {
+        let tx_event = tx_event.clone();
+        tokio::spawn(async move {
+            loop {
+                let notification = match notifications.recv().await {
+                    Ok(notification) => notification,
+                    Err(tokio::sync::broadcast::error::RecvError::Lagged(skipped)) => {
+                        warn!("dropped {skipped} notifications from MCP server `{server}`");
+                        continue;
+                    }
+                    Err(tokio::sync::broadcast::error::RecvError::Closed) => break,
+                };
+                if notification.method != ResourceUpdatedNotification::METHOD {
+                    continue;
+                }
+                let Some(params) = notification.params.and_then(|params| {
+                    serde_json::from_value::<ResourceUpdatedNotificationParams>(params).ok()
+                }) else {
+                    continue;
+                };
+                let event = Event {
+                    id: INITIAL_SUBMIT_ID.to_owned(),
+                    msg: EventMsg::McpResourceUpdated(McpResourceUpdatedEvent {
+                        server: server.clone(),
+                        uri: params.uri,
+                    }),
+                };
+                if tx_event.send(event).await.is_err() {
+                    break;
+                }
+            }
+        });
+    }
+}
+
 /// Spawn a review thread using the given prompt.
 async fn spawn_review_thread(
     sess: Arc<Session>,
@@ -1542,7 +2115,9 @@ async fn spawn_review_thread(
         include_web_search_request: false,
         use_streamable_shell_tool: false,
         include_view_image_tool: false,
+        include_fetch_url_tool: false,
         experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        read_only: config.read_only,
     });
 
     let base_instructions = Some(REVIEW_PROMPT.to_string());
@@ -1591,6 +2166,10 @@ async fn spawn_review_thread(
     sess.set_task(task);
 
     // Announce entering review mode so UIs can switch modes.
+    sess.update_subtasks(&sub_id_for_event, |tracker| {
+        tracker.begin_task(&sub_id_for_event, "Review".to_string())
+    })
+    .await;
     sess.send_event(Event {
         id: sub_id_for_event,
         msg: EventMsg::EnteredReviewMode(review_request),
@@ -1641,9 +2220,19 @@ async fn run_task(
     if is_review_mode {
         review_thread_history.push(initial_input_for_turn.into());
     } else {
+        // Tell the model about edits made outside it before the request that
+        // may act on them.
+        if let Some(watcher) = &sess.file_watcher
+            && let Some(note) =
+                watcher.take_note(&turn_context.cwd, |path: &Path| sess.pre_image(path))
+        {
+            sess.record_conversation_items(&[file_changes_message(&note)])
+                .await;
+        }
         sess.record_input_and_rollout_usermsg(&initial_input_for_turn)
             .await;
     }
+    let _turn_guard = sess.file_watcher.as_ref().map(FileWatcher::turn_started);
 
     let mut last_agent_message: Option<String> = None;
     // Although from the perspective of codex.rs, TurnDiffTracker has the lifecycle of a Task which contains
@@ -1834,6 +2423,7 @@ async fn run_task(
                                 message: format!(
                                     "Conversation is still above the token limit after automatic summarization (limit {limit_str}, current {current_tokens}). Please start a new session or trim your input."
                                 ),
+                                category: Some(ErrorCategory::ContextWindow),
                             }),
                         };
                         sess.send_event(event).await;
@@ -1865,6 +2455,7 @@ async fn run_task(
                     id: sub_id.clone(),
                     msg: EventMsg::Error(ErrorEvent {
                         message: e.to_string(),
+                        category: Some(e.category()),
                     }),
                 };
                 sess.send_event(event).await;
@@ -1890,6 +2481,8 @@ async fn run_task(
         .await;
     }
 
+    sess.finish_subtasks(&sub_id, SubtaskStatus::Completed)
+        .await;
     sess.remove_task(&sub_id);
     let event = Event {
         id: sub_id,
@@ -1939,6 +2532,7 @@ async fn run_turn(
         input,
         tools,
         base_instructions_override: turn_context.base_instructions.clone(),
+        appended_instructions: turn_context.client.get_appended_instructions(),
     };
 
     let mut retries = 0;
@@ -1947,7 +2541,11 @@ async fn run_turn(
             Ok(output) => return Ok(output),
             Err(CodexErr::Interrupted) => return Err(CodexErr::Interrupted),
             Err(CodexErr::EnvVar(var)) => return Err(CodexErr::EnvVar(var)),
-            Err(e @ (CodexErr::UsageLimitReached(_) | CodexErr::UsageNotIncluded)) => {
+            Err(
+                e @ (CodexErr::UsageLimitReached(_)
+                | CodexErr::UsageNotIncluded
+                | CodexErr::OfflineNetworkDenied(_)),
+            ) => {
                 return Err(e);
             }
             Err(e) => {
@@ -2070,6 +2668,9 @@ async fn try_run_turn(
         summary: turn_context.client.get_reasoning_summary(),
     });
     sess.persist_rollout_items(&[rollout_item]).await;
+    if let Some(speculative) = &sess.speculative_exec {
+        speculative.clear();
+    }
     let mut stream = turn_context.client.clone().stream(&prompt).await?;
 
     let mut output = Vec::new();
@@ -2110,6 +2711,13 @@ async fn try_run_turn(
                 .await?;
                 output.push(ProcessedResponseItem { item, response });
             }
+            ResponseEvent::FunctionCallArgumentsDelta {
+                call_id,
+                name,
+                arguments,
+            } => {
+                sess.speculate_shell_call(turn_context, &call_id, &name, &arguments);
+            }
             ResponseEvent::WebSearchCallBegin { call_id } => {
                 let _ = sess
                     .tx_event
@@ -2123,6 +2731,10 @@ async fn try_run_turn(
                 response_id: _,
                 token_usage,
             } => {
+                // Many OpenAI-compatible servers omit `usage`; count locally so
+                // the context indicator and auto-compaction still work.
+                let token_usage = token_usage
+                    .or_else(|| Some(estimate_token_usage(turn_context, &prompt.input, &output)));
                 let info = sess.update_token_usage_info(turn_context, &token_usage);
                 let _ = sess
                     .send_event(Event {
@@ -2190,6 +2802,26 @@ async fn try_run_turn(
     }
 }
 
+/// Build a [`TokenUsage`] from local tokenizer counts of the prompt and the
+/// items produced so far in this response.
+fn estimate_token_usage(
+    turn_context: &TurnContext,
+    input: &[ResponseItem],
+    output: &[ProcessedResponseItem],
+) -> TokenUsage {
+    let counter = TokenCounter::for_model(&turn_context.client.get_model());
+    let input_tokens = counter.count_items(input);
+    let output_items: Vec<ResponseItem> = output.iter().map(|p| p.item.clone()).collect();
+    let output_tokens = counter.count_items(&output_items);
+    TokenUsage {
+        input_tokens,
+        cached_input_tokens: 0,
+        output_tokens,
+        reasoning_output_tokens: 0,
+        total_tokens: input_tokens + output_tokens,
+    }
+}
+
 async fn handle_response_item(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2316,10 +2948,12 @@ async fn handle_response_item(
 
 async fn handle_unified_exec_tool_call(
     sess: &Session,
+    sub_id: &str,
     call_id: String,
     session_id: Option<String>,
     arguments: Vec<String>,
     timeout_ms: Option<u64>,
+    env: HashMap<String, String>,
 ) -> ResponseInputItem {
     let parsed_session_id = if let Some(session_id) = session_id {
         match session_id.parse::<i32>() {
@@ -2342,6 +2976,8 @@ async fn handle_unified_exec_tool_call(
         session_id: parsed_session_id,
         input_chunks: &arguments,
         timeout_ms,
+        env: Some(&env),
+        sub_id,
     };
 
     let result = sess.unified_exec_manager.handle_request(request).await;
@@ -2389,6 +3025,15 @@ async fn handle_function_call(
     arguments: String,
     call_id: String,
 ) -> ResponseInputItem {
+    if turn_context.tools_config.read_only && !is_read_only_tool(&name) {
+        return ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: format!("`{name}` is not available in read-only mode"),
+                success: Some(false),
+            },
+        };
+    }
     match name.as_str() {
         "container.exec" | "shell" => {
             let params = match parse_container_exec_arguments(arguments, turn_context, &call_id) {
@@ -2432,10 +3077,12 @@ async fn handle_function_call(
 
             handle_unified_exec_tool_call(
                 sess,
+                &sub_id,
                 call_id,
                 args.session_id,
                 args.input,
                 args.timeout_ms,
+                create_env(&turn_context.shell_environment_policy),
             )
             .await
         }
@@ -2457,6 +3104,29 @@ async fn handle_function_call(
                 }
             };
             let abs = turn_context.resolve_path(Some(args.path));
+            // Documents are answered with their text rather than attached.
+            if crate::documents::is_document(&abs) {
+                let path = abs.clone();
+                let extracted = tokio::task::spawn_blocking(move || {
+                    crate::documents::extract_document_text(&path)
+                })
+                .await;
+                let output = match extracted {
+                    Ok(Ok(text)) => FunctionCallOutputPayload {
+                        content: crate::documents::document_block(&abs, &text),
+                        success: Some(true),
+                    },
+                    Ok(Err(err)) => FunctionCallOutputPayload {
+                        content: format!("{err:#}"),
+                        success: Some(false),
+                    },
+                    Err(err) => FunctionCallOutputPayload {
+                        content: format!("failed to read document: {err}"),
+                        success: Some(false),
+                    },
+                };
+                return ResponseInputItem::FunctionCallOutput { call_id, output };
+            }
             let output = match sess.inject_input(vec![InputItem::LocalImage { path: abs }]) {
                 Ok(()) => FunctionCallOutputPayload {
                     content: "attached local image path".to_string(),
@@ -2469,6 +3139,37 @@ async fn handle_function_call(
             };
             ResponseInputItem::FunctionCallOutput { call_id, output }
         }
+        "fetch_url" if turn_context.tools_config.include_fetch_url_tool => {
+            #[derive(serde::Deserialize)]
+            struct FetchUrlArgs {
+                url: String,
+            }
+            let args = match serde_json::from_str::<FetchUrlArgs>(&arguments) {
+                Ok(a) => a,
+                Err(e) => {
+                    return ResponseInputItem::FunctionCallOutput {
+                        call_id,
+                        output: FunctionCallOutputPayload {
+                            content: format!("failed to parse function arguments: {e}"),
+                            success: Some(false),
+                        },
+                    };
+                }
+            };
+            let counter = TokenCounter::for_model(&turn_context.client.get_model());
+            let output =
+                match crate::fetch_url::fetch_url(&args.url, &sess.fetch_url, &counter).await {
+                    Ok(content) => FunctionCallOutputPayload {
+                        content,
+                        success: Some(true),
+                    },
+                    Err(content) => FunctionCallOutputPayload {
+                        content,
+                        success: Some(false),
+                    },
+                };
+            ResponseInputItem::FunctionCallOutput { call_id, output }
+        }
         "apply_patch" => {
             let args = match serde_json::from_str::<ApplyPatchToolArgs>(&arguments) {
                 Ok(a) => a,
@@ -2517,7 +3218,11 @@ async fn handle_function_call(
             };
             let result = sess
                 .session_manager
-                .handle_exec_command_request(exec_params)
+                .handle_exec_command_request(
+                    exec_params,
+                    create_env(&turn_context.shell_environment_policy),
+                    &sub_id,
+                )
                 .await;
             let function_call_output = crate::exec_command::result_into_payload(result);
             ResponseInputItem::FunctionCallOutput {
@@ -2549,6 +3254,15 @@ async fn handle_function_call(
                 output: function_call_output,
             }
         }
+        // An emulated tool call that failed schema validation: report why
+        // instead of running it.
+        INVALID_TOOL_CALL => ResponseInputItem::FunctionCallOutput {
+            call_id,
+            output: FunctionCallOutputPayload {
+                content: crate::tool_emulation::invalid_call_message(&arguments),
+                success: Some(false),
+            },
+        },
         _ => {
             match sess.mcp_connection_manager.parse_tool_name(&name) {
                 Some((server, tool_name)) => {
@@ -2574,6 +3288,15 @@ async fn handle_function_call(
     }
 }
 
+/// Tools a read-only session may call. `shell` is further restricted in
+/// [`handle_container_exec_with_params`].
+fn is_read_only_tool(name: &str) -> bool {
+    matches!(
+        name,
+        "container.exec" | "shell" | "view_image" | "update_plan" | INVALID_TOOL_CALL
+    )
+}
+
 async fn handle_custom_tool_call(
     sess: &Session,
     turn_context: &TurnContext,
@@ -2693,8 +3416,27 @@ async fn handle_container_exec_with_params(
     sub_id: String,
     call_id: String,
 ) -> ResponseInputItem {
+    let read_only = turn_context.tools_config.read_only;
     // check if this was a patch, and apply it if so
-    let apply_patch_exec = match maybe_parse_apply_patch_verified(&params.command, &params.cwd) {
+    // A patch written against an older version of a file is merged into the
+    // current one using what the model last saw of it.
+    let verified = maybe_parse_apply_patch_verified_with_pre_images(
+        &params.command,
+        &params.cwd,
+        &|path: &Path| sess.pre_image(path),
+    );
+    let apply_patch_exec = match verified {
+        MaybeApplyPatchVerified::Body(_) | MaybeApplyPatchVerified::CorrectnessError(_)
+            if read_only =>
+        {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: "apply_patch is not available in read-only mode".to_string(),
+                    success: Some(false),
+                },
+            };
+        }
         MaybeApplyPatchVerified::Body(changes) => {
             match apply_patch::apply_patch(sess, turn_context, &sub_id, &call_id, changes).await {
                 InternalApplyPatchInvocation::Output(item) => return item,
@@ -2740,12 +3482,14 @@ async fn handle_container_exec_with_params(
                 };
             };
 
+            let mut command = vec![
+                path_to_codex,
+                CODEX_APPLY_PATCH_ARG1.to_string(),
+                patch.clone(),
+            ];
+            command.extend(sess.file_format.write_options().to_args());
             let params = ExecParams {
-                command: vec![
-                    path_to_codex,
-                    CODEX_APPLY_PATCH_ARG1.to_string(),
-                    patch.clone(),
-                ],
+                command,
                 cwd: cwd.clone(),
                 timeout_ms: params.timeout_ms,
                 env: HashMap::new(),
@@ -2769,6 +3513,14 @@ async fn handle_container_exec_with_params(
                 vec!["apply_patch".to_string(), patch.clone()],
             )
         }
+        None if read_only => {
+            let safety = assess_read_only_command_safety(
+                &params.command,
+                sess.codex_linux_sandbox_exe.is_some(),
+            );
+            let command_for_display = params.command.clone();
+            (params, safety, command_for_display)
+        }
         None => {
             let safety = {
                 let state = sess.state.lock_unchecked();
@@ -2797,7 +3549,10 @@ async fn handle_container_exec_with_params(
                     params.justification.clone(),
                 )
                 .await;
-            match rx_approve.await.unwrap_or_default() {
+            match sess
+                .wait_for_approval(&sub_id, &call_id, ApprovalKind::Exec, rx_approve)
+                .await
+            {
                 ReviewDecision::Approved => (),
                 ReviewDecision::ApprovedForSession => {
                     sess.add_approved_command(params.command.clone());
@@ -2829,6 +3584,9 @@ async fn handle_container_exec_with_params(
         }
     };
 
+    let pending_patch = apply_patch_exec
+        .as_ref()
+        .map(|exec| PendingPatch::from_action(&call_id, &exec.action));
     let exec_command_context = ExecCommandContext {
         sub_id: sub_id.clone(),
         call_id: call_id.clone(),
@@ -2846,6 +3604,16 @@ async fn handle_container_exec_with_params(
     };
 
     let params = maybe_translate_shell_command(params, sess, turn_context);
+    let read_only_policy = SandboxPolicy::ReadOnly;
+    let sandbox_policy = if read_only {
+        &read_only_policy
+    } else {
+        &turn_context.sandbox_policy
+    };
+    let journal = sess.recovery.as_ref();
+    if let (Some(journal), Some(pending_patch)) = (journal, pending_patch) {
+        journal.begin_patch(pending_patch);
+    }
     let output_result = sess
         .run_exec_with_events(
             turn_diff_tracker,
@@ -2853,7 +3621,7 @@ async fn handle_container_exec_with_params(
             ExecInvokeArgs {
                 params: params.clone(),
                 sandbox_type,
-                sandbox_policy: &turn_context.sandbox_policy,
+                sandbox_policy,
                 codex_linux_sandbox_exe: &sess.codex_linux_sandbox_exe,
                 stdout_stream: if exec_command_context.apply_patch.is_some() {
                     None
@@ -2867,13 +3635,26 @@ async fn handle_container_exec_with_params(
             },
         )
         .await;
+    if let Some(journal) = journal
+        && exec_command_context.apply_patch.is_some()
+    {
+        journal.end_patch();
+    }
 
     match output_result {
         Ok(output) => {
             let ExecToolCallOutput { exit_code, .. } = &output;
 
             let is_success = *exit_code == 0;
-            let content = format_exec_output(&output);
+            let mut content = format_exec_output(&output);
+            if is_success
+                && let Some(patch) = &exec_command_context.apply_patch
+                && let Some(report) = sess
+                    .check_patch_syntax(&sub_id, &patch.changes, &exec_command_context.cwd)
+                    .await
+            {
+                content.push_str(&format!("\n\n{report}"));
+            }
             ResponseInputItem::FunctionCallOutput {
                 call_id: call_id.clone(),
                 output: FunctionCallOutputPayload {
@@ -2932,7 +3713,8 @@ async fn handle_sandbox_error(
     }
 
     // Early out if either the user never wants to be asked for approval, or
-    // we're letting the model manage escalation requests. Otherwise, continue
+    // we're letting the model manage escalation requests, or the session is
+    // read-only and must never run unsandboxed. Otherwise, continue
     match turn_context.approval_policy {
         AskForApproval::Never | AskForApproval::OnRequest => {
             return ResponseInputItem::FunctionCallOutput {
@@ -2945,6 +3727,19 @@ async fn handle_sandbox_error(
                 },
             };
         }
+        AskForApproval::UnlessTrusted | AskForApproval::OnFailure
+            if turn_context.tools_config.read_only =>
+        {
+            return ResponseInputItem::FunctionCallOutput {
+                call_id,
+                output: FunctionCallOutputPayload {
+                    content: format!(
+                        "failed in read-only sandbox {sandbox_type:?} with execution error: {error}"
+                    ),
+                    success: Some(false),
+                },
+            };
+        }
         AskForApproval::UnlessTrusted | AskForApproval::OnFailure => (),
     }
 
@@ -2970,7 +3765,10 @@ async fn handle_sandbox_error(
         )
         .await;
 
-    match rx_approve.await.unwrap_or_default() {
+    match sess
+        .wait_for_approval(&sub_id, &call_id, ApprovalKind::Exec, rx_approve)
+        .await
+    {
         ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
             // Persist this command as pre‑approved for the
             // remainder of the session so future
@@ -3010,7 +3808,15 @@ async fn handle_sandbox_error(
                     let ExecToolCallOutput { exit_code, .. } = &retry_output;
 
                     let is_success = *exit_code == 0;
-                    let content = format_exec_output(&retry_output);
+                    let mut content = format_exec_output(&retry_output);
+                    if is_success
+                        && let Some(patch) = &exec_command_context.apply_patch
+                        && let Some(report) = sess
+                            .check_patch_syntax(&sub_id, &patch.changes, &exec_command_context.cwd)
+                            .await
+                    {
+                        content.push_str(&format!("\n\n{report}"));
+                    }
 
                     ResponseInputItem::FunctionCallOutput {
                         call_id: call_id.clone(),
@@ -3144,7 +3950,7 @@ fn take_last_bytes_at_char_boundary(s: &str, maxb: usize) -> &str {
 }
 
 /// Exec output is a pre-serialized JSON payload
-fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
+pub(crate) fn format_exec_output(exec_output: &ExecToolCallOutput) -> String {
     let ExecToolCallOutput {
         exit_code,
         duration,
@@ -3494,7 +4300,9 @@ mod tests {
             include_web_search_request: config.tools_web_search_request,
             use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
             include_view_image_tool: config.include_view_image_tool,
+            include_fetch_url_tool: config.include_fetch_url_tool,
             experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+            read_only: config.read_only,
         });
         let turn_context = TurnContext {
             client,
@@ -3513,8 +4321,10 @@ mod tests {
             mcp_connection_manager: McpConnectionManager::default(),
             session_manager: ExecSessionManager::default(),
             unified_exec_manager: UnifiedExecSessionManager::default(),
+            running_exec_calls: AtomicU32::new(0),
             notify: None,
             rollout: Mutex::new(None),
+            recovery: None,
             state: Mutex::new(State {
                 history: ConversationHistory::new(),
                 ..Default::default()
@@ -3522,6 +4332,17 @@ mod tests {
             codex_linux_sandbox_exe: None,
             user_shell: shell::Shell::Unknown,
             show_raw_agent_reasoning: config.show_raw_agent_reasoning,
+            approval_timeout: None,
+            speculative_exec: None,
+            workspace_guard_allow: Vec::new(),
+            patch_guard: PatchGuard::default(),
+            syntax_check: SyntaxCheck::default(),
+            file_format: FileFormat::default(),
+            fetch_url: FetchUrl::default(),
+            scratch: None,
+            repo_map: None,
+            event_log: None,
+            file_watcher: None,
         };
         (session, turn_context)
     }
@@ -115,6 +115,7 @@ async fn run_compact_task_inner(
         input: turn_input,
         tools: Vec::new(),
         base_instructions_override: Some(instructions_override),
+        appended_instructions: None,
     };
 
     let max_retries = turn_context.client.get_provider().stream_max_retries();
@@ -158,6 +159,7 @@ async fn run_compact_task_inner(
                         id: sub_id.clone(),
                         msg: EventMsg::Error(ErrorEvent {
                             message: e.to_string(),
+                            category: Some(e.category()),
                         }),
                     };
                     sess.send_event(event).await;
@@ -239,7 +241,10 @@ pub(crate) fn collect_user_messages(items: &[ResponseItem]) -> Vec<String> {
 fn is_session_prefix_message(text: &str) -> bool {
     matches!(
         InputMessageKind::from(("user", text)),
-        InputMessageKind::UserInstructions | InputMessageKind::EnvironmentContext
+        InputMessageKind::UserInstructions
+            | InputMessageKind::EnvironmentContext
+            | InputMessageKind::RepoMap
+            | InputMessageKind::FileChanges
     )
 }
 
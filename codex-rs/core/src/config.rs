@@ -1,18 +1,37 @@
 use crate::config_profile::ConfigProfile;
+use crate::config_types::ApprovalTimeout;
+use crate::config_types::ExecCritic;
+use crate::config_types::ExecToml;
+use crate::config_types::FetchUrl;
+use crate::config_types::FileFormat;
+use crate::config_types::FileWatcher;
 use crate::config_types::History;
+use crate::config_types::InstructionSnippet;
+use crate::config_types::Log;
 use crate::config_types::McpServerConfig;
+use crate::config_types::ModelPrice;
+use crate::config_types::PatchGuard;
 use crate::config_types::ReasoningSummaryFormat;
+use crate::config_types::RepoMap;
 use crate::config_types::SandboxWorkspaceWrite;
+use crate::config_types::ScheduledRun;
+use crate::config_types::Share;
 use crate::config_types::ShellEnvironmentPolicy;
 use crate::config_types::ShellEnvironmentPolicyToml;
+use crate::config_types::SyntaxCheck;
+use crate::config_types::Telemetry;
 use crate::config_types::Tui;
+use crate::config_types::UpdateChannel;
 use crate::config_types::UriBasedFileOpener;
+use crate::config_types::WorkspaceGuard;
 use crate::git_info::resolve_root_git_project_for_trust;
 use crate::model_family::ModelFamily;
 use crate::model_family::find_family_for_model;
 use crate::model_provider_info::ModelProviderInfo;
 use crate::model_provider_info::built_in_model_providers;
+use crate::offline::OfflinePolicy;
 use crate::openai_model_info::get_model_info;
+use crate::protocol::ApprovalTimeoutAction;
 use crate::protocol::AskForApproval;
 use crate::protocol::SandboxPolicy;
 use anyhow::Context;
@@ -24,10 +43,11 @@ use codex_protocol::mcp_protocol::Tools;
 use codex_protocol::mcp_protocol::UserSavedConfig;
 use dirs::home_dir;
 use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::Path;
 use std::path::PathBuf;
-use tempfile::NamedTempFile;
+use std::time::Duration;
 use toml::Value as TomlValue;
 use toml_edit::DocumentMut;
 
@@ -73,6 +93,10 @@ pub struct Config {
 
     pub sandbox_policy: SandboxPolicy,
 
+    /// The policy `workspace-write` runs with, from `[sandbox_workspace_write]`,
+    /// for switching to that mode mid-session.
+    pub workspace_write_policy: SandboxPolicy,
+
     pub shell_environment_policy: ShellEnvironmentPolicy,
 
     /// When `true`, `AgentReasoning` events emitted by the backend will be
@@ -180,6 +204,15 @@ pub struct Config {
     /// Include the `view_image` tool that lets the agent attach a local image path to context.
     pub include_view_image_tool: bool,
 
+    /// Include the `fetch_url` tool that downloads a web page as markdown.
+    pub include_fetch_url_tool: bool,
+
+    /// Limits applied by the `fetch_url` tool.
+    pub fetch_url: FetchUrl,
+
+    /// Recurring runs managed by `codex schedule`, keyed by name.
+    pub schedules: BTreeMap<String, ScheduledRun>,
+
     /// The active profile name used to derive this `Config` (if any).
     pub active_profile: Option<String>,
 
@@ -187,6 +220,71 @@ pub struct Config {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: bool,
+
+    /// Set when running in offline mode: no network traffic except to
+    /// loopback and explicitly allowed hosts.
+    pub offline: Option<OfflinePolicy>,
+
+    /// Release channel used by the update check and `codex update`.
+    pub update_channel: UpdateChannel,
+
+    /// When set, approval requests that go unanswered for this long are
+    /// resolved automatically instead of blocking the session forever.
+    pub approval_timeout: Option<ApprovalTimeout>,
+
+    /// Log levels, format and rotation for `$CODEX_HOME/log`.
+    pub log: Log,
+
+    /// Start known read-only `shell` calls while their arguments are still
+    /// streaming, so the output is ready when the call completes.
+    pub speculative_exec: bool,
+
+    /// Redaction applied by `codex share`.
+    pub share: Share,
+
+    /// Read-only session (`codex ask`): tools that could modify anything are
+    /// not offered and calls to them are refused, shell commands only run
+    /// under a read-only sandbox, and nothing is sent for approval.
+    pub read_only: bool,
+
+    /// Directories patches may write into even when they resolve outside the
+    /// writable roots.
+    pub workspace_guard: WorkspaceGuard,
+
+    /// Binary, oversized and generated files patches may not edit freely.
+    pub patch_guard: PatchGuard,
+
+    /// Validators run on files after a patch writes them.
+    pub syntax_check: SyntaxCheck,
+
+    /// Line endings and encoding of the files patches write.
+    pub file_format: FileFormat,
+
+    /// Map of the working directory included in the first prompt.
+    pub repo_map: RepoMap,
+
+    /// Tell the model about files edited outside the agent.
+    pub file_watcher: FileWatcher,
+
+    /// Token prices keyed by model slug or slug prefix, on top of the
+    /// built-in table `codex stats` uses.
+    pub model_prices: HashMap<String, ModelPrice>,
+
+    /// Critic that reviews and may send back the result of `codex exec` runs.
+    pub exec_critic: ExecCritic,
+
+    /// Local event log and its opt-in export.
+    pub telemetry: Telemetry,
+
+    /// `[model_instructions]` snippets keyed by model slug or slug prefix.
+    pub model_instructions: HashMap<String, String>,
+
+    /// `[provider_instructions]` snippets keyed by model provider id.
+    pub provider_instructions: HashMap<String, String>,
+
+    /// Text appended to the base instructions (built-in or replaced) for
+    /// every model.
+    pub base_instructions_append: Option<String>,
 }
 
 impl Config {
@@ -221,10 +319,43 @@ impl Config {
         })?;
 
         // Step 4: merge with the strongly-typed overrides.
-        Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)
+        let config = Self::load_from_base_config_with_overrides(cfg, overrides, codex_home)?;
+
+        // Step 5: enforce offline mode for every network call made by this
+        // process from here on.
+        if let Some(policy) = &config.offline {
+            crate::offline::activate(policy);
+        }
+        Ok(config)
     }
 }
 
+/// Fail loudly if offline mode is requested for a setup that would need to
+/// reach a host that is not allowed.
+fn validate_offline_config(
+    policy: &OfflinePolicy,
+    model_provider_id: &str,
+    model_provider: &ModelProviderInfo,
+    tools_web_search_request: bool,
+) -> std::io::Result<()> {
+    let url = model_provider.get_full_url(&None);
+    if !policy.allows_url(&url) {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            format!(
+                "offline mode: model provider `{model_provider_id}` uses {url}; add its host to `offline_allowed_hosts` or use a local provider"
+            ),
+        ));
+    }
+    if tools_web_search_request {
+        return Err(std::io::Error::new(
+            std::io::ErrorKind::InvalidInput,
+            "offline mode: the web search tool requires network access; disable `tools.web_search`",
+        ));
+    }
+    Ok(())
+}
+
 pub fn load_config_as_toml_with_cli_overrides(
     codex_home: &Path,
     cli_overrides: Vec<(String, TomlValue)>,
@@ -266,97 +397,6 @@ pub fn load_config_as_toml(codex_home: &Path) -> std::io::Result<TomlValue> {
     }
 }
 
-fn set_project_trusted_inner(doc: &mut DocumentMut, project_path: &Path) -> anyhow::Result<()> {
-    // Ensure we render a human-friendly structure:
-    //
-    // [projects]
-    // [projects."/path/to/project"]
-    // trust_level = "trusted"
-    //
-    // rather than inline tables like:
-    //
-    // [projects]
-    // "/path/to/project" = { trust_level = "trusted" }
-    let project_key = project_path.to_string_lossy().to_string();
-
-    // Ensure top-level `projects` exists as a non-inline, explicit table. If it
-    // exists but was previously represented as a non-table (e.g., inline),
-    // replace it with an explicit table.
-    {
-        let root = doc.as_table_mut();
-        // If `projects` exists but isn't a standard table (e.g., it's an inline table),
-        // convert it to an explicit table while preserving existing entries.
-        let existing_projects = root.get("projects").cloned();
-        if existing_projects.as_ref().is_none_or(|i| !i.is_table()) {
-            let mut projects_tbl = toml_edit::Table::new();
-            projects_tbl.set_implicit(true);
-
-            // If there was an existing inline table, migrate its entries to explicit tables.
-            if let Some(inline_tbl) = existing_projects.as_ref().and_then(|i| i.as_inline_table()) {
-                for (k, v) in inline_tbl.iter() {
-                    if let Some(inner_tbl) = v.as_inline_table() {
-                        let new_tbl = inner_tbl.clone().into_table();
-                        projects_tbl.insert(k, toml_edit::Item::Table(new_tbl));
-                    }
-                }
-            }
-
-            root.insert("projects", toml_edit::Item::Table(projects_tbl));
-        }
-    }
-    let Some(projects_tbl) = doc["projects"].as_table_mut() else {
-        return Err(anyhow::anyhow!(
-            "projects table missing after initialization"
-        ));
-    };
-
-    // Ensure the per-project entry is its own explicit table. If it exists but
-    // is not a table (e.g., an inline table), replace it with an explicit table.
-    let needs_proj_table = !projects_tbl.contains_key(project_key.as_str())
-        || projects_tbl
-            .get(project_key.as_str())
-            .and_then(|i| i.as_table())
-            .is_none();
-    if needs_proj_table {
-        projects_tbl.insert(project_key.as_str(), toml_edit::table());
-    }
-    let Some(proj_tbl) = projects_tbl
-        .get_mut(project_key.as_str())
-        .and_then(|i| i.as_table_mut())
-    else {
-        return Err(anyhow::anyhow!("project table missing for {}", project_key));
-    };
-    proj_tbl.set_implicit(false);
-    proj_tbl["trust_level"] = toml_edit::value("trusted");
-    Ok(())
-}
-
-/// Patch `CODEX_HOME/config.toml` project state.
-/// Use with caution.
-pub fn set_project_trusted(codex_home: &Path, project_path: &Path) -> anyhow::Result<()> {
-    let config_path = codex_home.join(CONFIG_TOML_FILE);
-    // Parse existing config if present; otherwise start a new document.
-    let mut doc = match std::fs::read_to_string(config_path.clone()) {
-        Ok(s) => s.parse::<DocumentMut>()?,
-        Err(e) if e.kind() == std::io::ErrorKind::NotFound => DocumentMut::new(),
-        Err(e) => return Err(e.into()),
-    };
-
-    set_project_trusted_inner(&mut doc, project_path)?;
-
-    // ensure codex_home exists
-    std::fs::create_dir_all(codex_home)?;
-
-    // create a tmp_file
-    let tmp_file = NamedTempFile::new_in(codex_home)?;
-    std::fs::write(tmp_file.path(), doc.to_string())?;
-
-    // atomically move the tmp file into config.toml
-    tmp_file.persist(config_path)?;
-
-    Ok(())
-}
-
 fn ensure_profile_table<'a>(
     doc: &'a mut DocumentMut,
     profile_name: &str,
@@ -537,6 +577,10 @@ pub struct ConfigToml {
     #[serde(default)]
     pub shell_environment_policy: ShellEnvironmentPolicyToml,
 
+    /// Settings for processes spawned by tools; `[exec.env]` supersedes
+    /// `[shell_environment_policy]`.
+    pub exec: Option<ExecToml>,
+
     /// Sandbox mode to use.
     pub sandbox_mode: Option<SandboxMode>,
 
@@ -607,6 +651,15 @@ pub struct ConfigToml {
     /// Experimental path to a file whose contents replace the built-in BASE_INSTRUCTIONS.
     pub experimental_instructions_file: Option<PathBuf>,
 
+    /// Path to a file whose contents replace the built-in base instructions.
+    /// Relative paths are resolved against the cwd. Takes precedence over
+    /// `experimental_instructions_file`.
+    pub base_instructions_file: Option<PathBuf>,
+
+    /// Text appended to the base instructions, whether built-in or replaced
+    /// by `base_instructions_file`.
+    pub base_instructions_append: Option<String>,
+
     pub experimental_use_exec_command_tool: Option<bool>,
     pub experimental_use_unified_exec_tool: Option<bool>,
 
@@ -619,6 +672,77 @@ pub struct ConfigToml {
     /// All characters are inserted as they are received, and no buffering
     /// or placeholder replacement will occur for fast keypress bursts.
     pub disable_paste_burst: Option<bool>,
+
+    /// When true, Codex refuses to make network requests to anything other
+    /// than loopback and `offline_allowed_hosts`.
+    pub offline: Option<bool>,
+
+    /// Hosts (e.g. a LAN Ollama server) that may still be contacted in
+    /// offline mode.
+    pub offline_allowed_hosts: Option<Vec<String>>,
+
+    /// Release channel to follow for updates (`stable` or `beta`).
+    pub update_channel: Option<UpdateChannel>,
+
+    /// Seconds to wait for an answer to an approval request before applying
+    /// `approval_timeout_action`. Unset (or 0) waits forever.
+    pub approval_timeout_secs: Option<u64>,
+
+    /// What to do when an approval request times out (default: `deny`).
+    pub approval_timeout_action: Option<ApprovalTimeoutAction>,
+
+    /// Log levels, format and rotation for `$CODEX_HOME/log`.
+    pub log: Option<Log>,
+
+    /// Prefetch known read-only `shell` calls from the partially streamed
+    /// response (default: false).
+    pub speculative_exec: Option<bool>,
+
+    /// Redaction applied by `codex share`.
+    pub share: Option<Share>,
+
+    /// Exceptions to the symlink and `..` escape check on patches.
+    pub workspace_guard: Option<WorkspaceGuard>,
+
+    /// Binary, size and generated-path checks on patches.
+    pub patch_guard: Option<PatchGuard>,
+
+    /// Per-extension syntax validators run after patches.
+    pub syntax_check: Option<SyntaxCheck>,
+
+    /// Keep or convert the line endings and encoding of patched files.
+    pub file_format: Option<FileFormat>,
+
+    /// Opt-in repository map for the initial context.
+    pub repo_map: Option<RepoMap>,
+
+    /// Opt-in watcher for files the user edits during a session.
+    pub file_watcher: Option<FileWatcher>,
+
+    /// Per-model token prices for cost estimates in `codex stats`.
+    pub model_prices: Option<HashMap<String, ModelPrice>>,
+
+    /// Post-run review of `codex exec` results.
+    pub exec_critic: Option<ExecCritic>,
+
+    /// Local event log of feature usage and errors.
+    pub telemetry: Option<Telemetry>,
+
+    /// Host lists, robots.txt handling and size cap for the `fetch_url` tool.
+    pub fetch_url: Option<FetchUrl>,
+
+    /// Recurring `codex exec` runs, registered with `codex schedule install`.
+    #[serde(default)]
+    pub schedules: BTreeMap<String, ScheduledRun>,
+
+    /// Instructions appended to the base prompt for particular models. A key
+    /// applies to the model with that slug, or to every model whose slug
+    /// starts with it when no key matches exactly.
+    pub model_instructions: Option<HashMap<String, InstructionSnippet>>,
+
+    /// Instructions appended to the base prompt for particular model
+    /// providers, keyed by provider id.
+    pub provider_instructions: Option<HashMap<String, InstructionSnippet>>,
 }
 
 impl From<ConfigToml> for UserSavedConfig {
@@ -657,6 +781,10 @@ pub struct ToolsToml {
     /// Enable the `view_image` tool that lets the agent attach local images.
     #[serde(default)]
     pub view_image: Option<bool>,
+
+    /// Enable the `fetch_url` tool that downloads a web page as markdown.
+    #[serde(default)]
+    pub fetch_url: Option<bool>,
 }
 
 impl From<ToolsToml> for Tools {
@@ -676,24 +804,30 @@ impl ConfigToml {
             .unwrap_or_default();
         match resolved_sandbox_mode {
             SandboxMode::ReadOnly => SandboxPolicy::new_read_only_policy(),
-            SandboxMode::WorkspaceWrite => match self.sandbox_workspace_write.as_ref() {
-                Some(SandboxWorkspaceWrite {
-                    writable_roots,
-                    network_access,
-                    exclude_tmpdir_env_var,
-                    exclude_slash_tmp,
-                }) => SandboxPolicy::WorkspaceWrite {
-                    writable_roots: writable_roots.clone(),
-                    network_access: *network_access,
-                    exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
-                    exclude_slash_tmp: *exclude_slash_tmp,
-                },
-                None => SandboxPolicy::new_workspace_write_policy(),
-            },
+            SandboxMode::WorkspaceWrite => self.workspace_write_policy(),
             SandboxMode::DangerFullAccess => SandboxPolicy::DangerFullAccess,
         }
     }
 
+    /// The `workspace-write` policy with the `[sandbox_workspace_write]`
+    /// settings applied.
+    fn workspace_write_policy(&self) -> SandboxPolicy {
+        match self.sandbox_workspace_write.as_ref() {
+            Some(SandboxWorkspaceWrite {
+                writable_roots,
+                network_access,
+                exclude_tmpdir_env_var,
+                exclude_slash_tmp,
+            }) => SandboxPolicy::WorkspaceWrite {
+                writable_roots: writable_roots.clone(),
+                network_access: *network_access,
+                exclude_tmpdir_env_var: *exclude_tmpdir_env_var,
+                exclude_slash_tmp: *exclude_slash_tmp,
+            },
+            None => SandboxPolicy::new_workspace_write_policy(),
+        }
+    }
+
     pub fn is_cwd_trusted(&self, resolved_cwd: &Path) -> bool {
         let projects = self.projects.clone().unwrap_or_default();
 
@@ -759,6 +893,8 @@ pub struct ConfigOverrides {
     pub include_view_image_tool: Option<bool>,
     pub show_raw_agent_reasoning: Option<bool>,
     pub tools_web_search_request: Option<bool>,
+    pub offline: Option<bool>,
+    pub read_only: Option<bool>,
 }
 
 impl Config {
@@ -787,6 +923,8 @@ impl Config {
             include_view_image_tool,
             show_raw_agent_reasoning,
             tools_web_search_request: override_tools_web_search_request,
+            offline: override_offline,
+            read_only,
         } = overrides;
 
         let active_profile_name = config_profile_key
@@ -807,7 +945,13 @@ impl Config {
             None => ConfigProfile::default(),
         };
 
-        let sandbox_policy = cfg.derive_sandbox_policy(sandbox_mode);
+        let read_only = read_only.unwrap_or(false);
+        let sandbox_policy = if read_only {
+            SandboxPolicy::new_read_only_policy()
+        } else {
+            cfg.derive_sandbox_policy(sandbox_mode)
+        };
+        let workspace_write_policy = cfg.workspace_write_policy();
 
         let mut model_providers = built_in_model_providers();
         // Merge user-defined providers into the built-in list.
@@ -829,7 +973,11 @@ impl Config {
             })?
             .clone();
 
-        let shell_environment_policy = cfg.shell_environment_policy.into();
+        let shell_environment_policy = cfg
+            .exec
+            .and_then(|exec| exec.env)
+            .unwrap_or(cfg.shell_environment_policy)
+            .into();
 
         let resolved_cwd = {
             use std::env;
@@ -860,6 +1008,19 @@ impl Config {
             .or(cfg.tools.as_ref().and_then(|t| t.view_image))
             .unwrap_or(true);
 
+        let offline = override_offline
+            .or(cfg.offline)
+            .unwrap_or(false)
+            .then(|| OfflinePolicy::new(cfg.offline_allowed_hosts.unwrap_or_default()));
+        if let Some(policy) = &offline {
+            validate_offline_config(
+                policy,
+                &model_provider_id,
+                &model_provider,
+                tools_web_search_request,
+            )?;
+        }
+
         let model = model
             .or(config_profile.model)
             .or(cfg.model)
@@ -905,6 +1066,7 @@ impl Config {
         let experimental_instructions_path = config_profile
             .experimental_instructions_file
             .as_ref()
+            .or(cfg.base_instructions_file.as_ref())
             .or(cfg.experimental_instructions_file.as_ref());
         let file_base_instructions =
             Self::get_base_instructions(experimental_instructions_path, &resolved_cwd)?;
@@ -925,11 +1087,16 @@ impl Config {
             model_provider_id,
             model_provider,
             cwd: resolved_cwd,
-            approval_policy: approval_policy
-                .or(config_profile.approval_policy)
-                .or(cfg.approval_policy)
-                .unwrap_or_else(AskForApproval::default),
+            approval_policy: if read_only {
+                AskForApproval::Never
+            } else {
+                approval_policy
+                    .or(config_profile.approval_policy)
+                    .or(cfg.approval_policy)
+                    .unwrap_or_else(AskForApproval::default)
+            },
             sandbox_policy,
+            workspace_write_policy,
             shell_environment_policy,
             notify: cfg.notify,
             user_instructions,
@@ -971,8 +1138,43 @@ impl Config {
                 .experimental_use_unified_exec_tool
                 .unwrap_or(false),
             include_view_image_tool,
+            include_fetch_url_tool: cfg
+                .tools
+                .as_ref()
+                .and_then(|t| t.fetch_url)
+                .unwrap_or(false),
+            fetch_url: cfg.fetch_url.unwrap_or_default(),
+            schedules: cfg.schedules,
             active_profile: active_profile_name,
             disable_paste_burst: cfg.disable_paste_burst.unwrap_or(false),
+            offline,
+            update_channel: cfg.update_channel.unwrap_or_default(),
+            approval_timeout: cfg
+                .approval_timeout_secs
+                .filter(|secs| *secs > 0)
+                .map(|secs| ApprovalTimeout {
+                    duration: Duration::from_secs(secs),
+                    action: cfg.approval_timeout_action.unwrap_or_default(),
+                }),
+            log: cfg.log.unwrap_or_default(),
+            speculative_exec: cfg.speculative_exec.unwrap_or(false),
+            share: cfg.share.unwrap_or_default(),
+            read_only,
+            workspace_guard: cfg.workspace_guard.unwrap_or_default(),
+            patch_guard: cfg.patch_guard.unwrap_or_default(),
+            syntax_check: cfg.syntax_check.unwrap_or_default(),
+            file_format: cfg.file_format.unwrap_or_default(),
+            repo_map: cfg.repo_map.unwrap_or_default(),
+            file_watcher: cfg.file_watcher.unwrap_or_default(),
+            model_prices: cfg.model_prices.unwrap_or_default(),
+            exec_critic: cfg.exec_critic.unwrap_or_default(),
+            telemetry: cfg.telemetry.unwrap_or_default(),
+            model_instructions: snippet_texts(cfg.model_instructions),
+            provider_instructions: snippet_texts(cfg.provider_instructions),
+            base_instructions_append: cfg
+                .base_instructions_append
+                .map(|append| append.trim().to_string())
+                .filter(|append| !append.is_empty()),
         };
         Ok(config)
     }
@@ -1035,6 +1237,62 @@ impl Config {
             Ok(Some(s))
         }
     }
+
+    /// The `[provider_instructions]` and `[model_instructions]` entries that
+    /// apply to the current provider and model, in the order they are
+    /// appended to the base instructions, labelled by their config key.
+    pub fn instruction_snippets(&self) -> Vec<(String, &str)> {
+        let mut snippets = Vec::new();
+        if let Some(text) = self.provider_instructions.get(&self.model_provider_id) {
+            snippets.push((
+                format!("provider_instructions.{}", self.model_provider_id),
+                text.as_str(),
+            ));
+        }
+        // An exact slug wins; otherwise the longest key the slug starts with.
+        let model_key = self
+            .model_instructions
+            .keys()
+            .filter(|key| self.model.starts_with(key.as_str()))
+            .max_by_key(|key| key.len());
+        if let Some(key) = model_key
+            && let Some(text) = self.model_instructions.get(key)
+        {
+            snippets.push((format!("model_instructions.\"{key}\""), text.as_str()));
+        }
+        snippets
+    }
+
+    /// Text appended after the base instructions: `base_instructions_append`
+    /// followed by the [`instruction_snippets`](Self::instruction_snippets).
+    /// `None` when nothing applies.
+    pub fn appended_instructions(&self) -> Option<String> {
+        let mut texts: Vec<&str> = Vec::new();
+        if let Some(append) = &self.base_instructions_append {
+            texts.push(append);
+        }
+        texts.extend(
+            self.instruction_snippets()
+                .into_iter()
+                .map(|(_, text)| text),
+        );
+        if texts.is_empty() {
+            return None;
+        }
+        Some(texts.join("\n\n"))
+    }
+}
+
+/// Keep the text of every non-empty snippet.
+fn snippet_texts(snippets: Option<HashMap<String, InstructionSnippet>>) -> HashMap<String, String> {
+    snippets
+        .unwrap_or_default()
+        .into_iter()
+        .filter_map(|(key, snippet)| {
+            let text = snippet.instructions.trim();
+            (!text.is_empty()).then(|| (key, text.to_string()))
+        })
+        .collect()
 }
 
 fn default_model() -> String {
@@ -1083,6 +1341,7 @@ pub fn log_dir(cfg: &Config) -> std::io::Result<PathBuf> {
 #[cfg(test)]
 mod tests {
     use crate::config_types::HistoryPersistence;
+    use crate::config_types::ShellEnvironmentPolicyInherit;
 
     use super::*;
     use pretty_assertions::assert_eq;
@@ -1177,6 +1436,193 @@ exclude_slash_tmp = true
         );
     }
 
+    #[test]
+    fn approval_timeout_requires_positive_secs() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let load = |cfg: ConfigToml| {
+            Config::load_from_base_config_with_overrides(
+                cfg,
+                ConfigOverrides {
+                    cwd: Some(codex_home.path().to_path_buf()),
+                    ..Default::default()
+                },
+                codex_home.path().to_path_buf(),
+            )
+        };
+
+        let cfg = toml::from_str::<ConfigToml>("approval_timeout_secs = 0")
+            .expect("TOML deserialization should succeed");
+        assert_eq!(load(cfg)?.approval_timeout, None);
+
+        let cfg = toml::from_str::<ConfigToml>("approval_timeout_secs = 600")
+            .expect("TOML deserialization should succeed");
+        assert_eq!(
+            load(cfg)?.approval_timeout,
+            Some(ApprovalTimeout {
+                duration: Duration::from_secs(600),
+                action: ApprovalTimeoutAction::Deny,
+            })
+        );
+
+        let cfg = toml::from_str::<ConfigToml>(
+            "approval_timeout_secs = 30\napproval_timeout_action = \"abort\"",
+        )
+        .expect("TOML deserialization should succeed");
+        assert_eq!(
+            load(cfg)?.approval_timeout.map(|timeout| timeout.action),
+            Some(ApprovalTimeoutAction::Abort)
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn telemetry_is_opt_in() {
+        let cfg = toml::from_str::<ConfigToml>("[telemetry]\nretention_days = 30")
+            .expect("TOML deserialization should succeed");
+        assert_eq!(
+            cfg.telemetry,
+            Some(Telemetry {
+                retention_days: 30,
+                ..Telemetry::default()
+            })
+        );
+        assert!(!Telemetry::default().local);
+
+        let cfg = toml::from_str::<ConfigToml>("[telemetry]\nlocal = true")
+            .expect("TOML deserialization should succeed");
+        assert!(cfg.telemetry.is_some_and(|telemetry| telemetry.local));
+    }
+
+    #[test]
+    fn instruction_snippets_follow_the_provider_and_longest_model_prefix() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+model = "qwen2.5-coder:32b"
+model_provider = "oss"
+
+[model_instructions."qwen"]
+instructions = "Call apply_patch with the *** Begin Patch envelope."
+
+[model_instructions."qwen2.5-coder"]
+instructions = "Keep patches small."
+
+[model_instructions."gpt-5"]
+instructions = "Be terse."
+
+[provider_instructions.oss]
+instructions = "You are running locally."
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(codex_home.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+
+        assert_eq!(
+            config.instruction_snippets(),
+            vec![
+                (
+                    "provider_instructions.oss".to_string(),
+                    "You are running locally."
+                ),
+                (
+                    "model_instructions.\"qwen2.5-coder\"".to_string(),
+                    "Keep patches small."
+                ),
+            ]
+        );
+        assert_eq!(
+            config.appended_instructions().as_deref(),
+            Some("You are running locally.\n\nKeep patches small.")
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn exec_env_table_supersedes_shell_environment_policy() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let cfg = toml::from_str::<ConfigToml>(
+            r#"
+[shell_environment_policy]
+inherit = "none"
+
+[exec.env]
+inherit = "core"
+allow = ["PATH", "HOME"]
+deny = ["AWS_*"]
+set = { CI = "1" }
+"#,
+        )
+        .expect("TOML deserialization should succeed");
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides {
+                cwd: Some(codex_home.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )?;
+        let policy = config.shell_environment_policy;
+        assert_eq!(policy.inherit, ShellEnvironmentPolicyInherit::Core);
+        assert!(!policy.ignore_default_excludes);
+        assert_eq!(policy.include_only.len(), 2);
+        assert!(policy.exclude[0].matches("AWS_SECRET_ACCESS_KEY"));
+        assert_eq!(policy.r#set.get("CI").map(String::as_str), Some("1"));
+        Ok(())
+    }
+
+    #[test]
+    fn offline_mode_requires_an_allowed_model_provider() -> std::io::Result<()> {
+        let codex_home = TempDir::new()?;
+        let offline_overrides = || ConfigOverrides {
+            cwd: Some(codex_home.path().to_path_buf()),
+            offline: Some(true),
+            ..Default::default()
+        };
+
+        // The default OpenAI provider needs the network.
+        let err = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            offline_overrides(),
+            codex_home.path().to_path_buf(),
+        )
+        .expect_err("remote provider should be rejected offline");
+        assert_eq!(err.kind(), std::io::ErrorKind::InvalidInput);
+
+        // The local OSS provider is fine.
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml {
+                model_provider: Some(crate::BUILT_IN_OSS_MODEL_PROVIDER_ID.to_string()),
+                ..Default::default()
+            },
+            offline_overrides(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(config.offline, Some(OfflinePolicy::default()));
+
+        // So is a remote provider whose host has been explicitly allowed.
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml {
+                offline_allowed_hosts: Some(vec!["api.openai.com".to_string()]),
+                ..Default::default()
+            },
+            offline_overrides(),
+            codex_home.path().to_path_buf(),
+        )?;
+        assert_eq!(
+            config.offline,
+            Some(OfflinePolicy::new(vec!["api.openai.com".to_string()]))
+        );
+
+        Ok(())
+    }
+
     #[tokio::test]
     async fn persist_model_selection_updates_defaults() -> anyhow::Result<()> {
         let codex_home = TempDir::new()?;
@@ -1469,6 +1915,7 @@ model_verbosity = "high"
                 model_provider: fixture.openai_provider.clone(),
                 approval_policy: AskForApproval::Never,
                 sandbox_policy: SandboxPolicy::new_read_only_policy(),
+                workspace_write_policy: SandboxPolicy::new_workspace_write_policy(),
                 shell_environment_policy: ShellEnvironmentPolicy::default(),
                 user_instructions: None,
                 notify: None,
@@ -1494,8 +1941,30 @@ model_verbosity = "high"
                 use_experimental_streamable_shell_tool: false,
                 use_experimental_unified_exec_tool: false,
                 include_view_image_tool: true,
+                include_fetch_url_tool: false,
+                fetch_url: FetchUrl::default(),
+                schedules: BTreeMap::new(),
                 active_profile: Some("o3".to_string()),
                 disable_paste_burst: false,
+                offline: None,
+                update_channel: UpdateChannel::Stable,
+                approval_timeout: None,
+                log: Log::default(),
+                speculative_exec: false,
+                share: Share::default(),
+                read_only: false,
+                workspace_guard: WorkspaceGuard::default(),
+                patch_guard: PatchGuard::default(),
+                syntax_check: SyntaxCheck::default(),
+                file_format: FileFormat::default(),
+                repo_map: RepoMap::default(),
+                file_watcher: FileWatcher::default(),
+                model_prices: HashMap::new(),
+                exec_critic: ExecCritic::default(),
+                telemetry: Telemetry::default(),
+                model_instructions: HashMap::new(),
+                provider_instructions: HashMap::new(),
+                base_instructions_append: None,
             },
             o3_profile_config
         );
@@ -1527,6 +1996,7 @@ model_verbosity = "high"
             model_provider: fixture.openai_chat_completions_provider.clone(),
             approval_policy: AskForApproval::UnlessTrusted,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            workspace_write_policy: SandboxPolicy::new_workspace_write_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
@@ -1552,8 +2022,30 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
+            fetch_url: FetchUrl::default(),
+            schedules: BTreeMap::new(),
             active_profile: Some("gpt3".to_string()),
             disable_paste_burst: false,
+            offline: None,
+            update_channel: UpdateChannel::Stable,
+            approval_timeout: None,
+            log: Log::default(),
+            speculative_exec: false,
+            share: Share::default(),
+            read_only: false,
+            workspace_guard: WorkspaceGuard::default(),
+            patch_guard: PatchGuard::default(),
+            syntax_check: SyntaxCheck::default(),
+            file_format: FileFormat::default(),
+            repo_map: RepoMap::default(),
+            file_watcher: FileWatcher::default(),
+            model_prices: HashMap::new(),
+            exec_critic: ExecCritic::default(),
+            telemetry: Telemetry::default(),
+            model_instructions: HashMap::new(),
+            provider_instructions: HashMap::new(),
+            base_instructions_append: None,
         };
 
         assert_eq!(expected_gpt3_profile_config, gpt3_profile_config);
@@ -1600,6 +2092,7 @@ model_verbosity = "high"
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            workspace_write_policy: SandboxPolicy::new_workspace_write_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
@@ -1625,8 +2118,30 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
+            fetch_url: FetchUrl::default(),
+            schedules: BTreeMap::new(),
             active_profile: Some("zdr".to_string()),
             disable_paste_burst: false,
+            offline: None,
+            update_channel: UpdateChannel::Stable,
+            approval_timeout: None,
+            log: Log::default(),
+            speculative_exec: false,
+            share: Share::default(),
+            read_only: false,
+            workspace_guard: WorkspaceGuard::default(),
+            patch_guard: PatchGuard::default(),
+            syntax_check: SyntaxCheck::default(),
+            file_format: FileFormat::default(),
+            repo_map: RepoMap::default(),
+            file_watcher: FileWatcher::default(),
+            model_prices: HashMap::new(),
+            exec_critic: ExecCritic::default(),
+            telemetry: Telemetry::default(),
+            model_instructions: HashMap::new(),
+            provider_instructions: HashMap::new(),
+            base_instructions_append: None,
         };
 
         assert_eq!(expected_zdr_profile_config, zdr_profile_config);
@@ -1659,6 +2174,7 @@ model_verbosity = "high"
             model_provider: fixture.openai_provider.clone(),
             approval_policy: AskForApproval::OnFailure,
             sandbox_policy: SandboxPolicy::new_read_only_policy(),
+            workspace_write_policy: SandboxPolicy::new_workspace_write_policy(),
             shell_environment_policy: ShellEnvironmentPolicy::default(),
             user_instructions: None,
             notify: None,
@@ -1684,108 +2200,34 @@ model_verbosity = "high"
             use_experimental_streamable_shell_tool: false,
             use_experimental_unified_exec_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
+            fetch_url: FetchUrl::default(),
+            schedules: BTreeMap::new(),
             active_profile: Some("gpt5".to_string()),
             disable_paste_burst: false,
+            offline: None,
+            update_channel: UpdateChannel::Stable,
+            approval_timeout: None,
+            log: Log::default(),
+            speculative_exec: false,
+            share: Share::default(),
+            read_only: false,
+            workspace_guard: WorkspaceGuard::default(),
+            patch_guard: PatchGuard::default(),
+            syntax_check: SyntaxCheck::default(),
+            file_format: FileFormat::default(),
+            repo_map: RepoMap::default(),
+            file_watcher: FileWatcher::default(),
+            model_prices: HashMap::new(),
+            exec_critic: ExecCritic::default(),
+            telemetry: Telemetry::default(),
+            model_instructions: HashMap::new(),
+            provider_instructions: HashMap::new(),
+            base_instructions_append: None,
         };
 
         assert_eq!(expected_gpt5_profile_config, gpt5_profile_config);
 
         Ok(())
     }
-
-    #[test]
-    fn test_set_project_trusted_writes_explicit_tables() -> anyhow::Result<()> {
-        let project_dir = Path::new("/some/path");
-        let mut doc = DocumentMut::new();
-
-        set_project_trusted_inner(&mut doc, project_dir)?;
-
-        let contents = doc.to_string();
-
-        let raw_path = project_dir.to_string_lossy();
-        let path_str = if raw_path.contains('\\') {
-            format!("'{raw_path}'")
-        } else {
-            format!("\"{raw_path}\"")
-        };
-        let expected = format!(
-            r#"[projects.{path_str}]
-trust_level = "trusted"
-"#
-        );
-        assert_eq!(contents, expected);
-
-        Ok(())
-    }
-
-    #[test]
-    fn test_set_project_trusted_converts_inline_to_explicit() -> anyhow::Result<()> {
-        let project_dir = Path::new("/some/path");
-
-        // Seed config.toml with an inline project entry under [projects]
-        let raw_path = project_dir.to_string_lossy();
-        let path_str = if raw_path.contains('\\') {
-            format!("'{raw_path}'")
-        } else {
-            format!("\"{raw_path}\"")
-        };
-        // Use a quoted key so backslashes don't require escaping on Windows
-        let initial = format!(
-            r#"[projects]
-{path_str} = {{ trust_level = "untrusted" }}
-"#
-        );
-        let mut doc = initial.parse::<DocumentMut>()?;
-
-        // Run the function; it should convert to explicit tables and set trusted
-        set_project_trusted_inner(&mut doc, project_dir)?;
-
-        let contents = doc.to_string();
-
-        // Assert exact output after conversion to explicit table
-        let expected = format!(
-            r#"[projects]
-
-[projects.{path_str}]
-trust_level = "trusted"
-"#
-        );
-        assert_eq!(contents, expected);
-
-        Ok(())
-    }
-
-    #[test]
-    fn test_set_project_trusted_migrates_top_level_inline_projects_preserving_entries()
-    -> anyhow::Result<()> {
-        let initial = r#"toplevel = "baz"
-projects = { "/Users/mbolin/code/codex4" = { trust_level = "trusted", foo = "bar" } , "/Users/mbolin/code/codex3" = { trust_level = "trusted" } }
-model = "foo""#;
-        let mut doc = initial.parse::<DocumentMut>()?;
-
-        // Approve a new directory
-        let new_project = Path::new("/Users/mbolin/code/codex2");
-        set_project_trusted_inner(&mut doc, new_project)?;
-
-        let contents = doc.to_string();
-
-        // Since we created the [projects] table as part of migration, it is kept implicit.
-        // Expect explicit per-project tables, preserving prior entries and appending the new one.
-        let expected = r#"toplevel = "baz"
-model = "foo"
-
-[projects."/Users/mbolin/code/codex4"]
-trust_level = "trusted"
-foo = "bar"
-
-[projects."/Users/mbolin/code/codex3"]
-trust_level = "trusted"
-
-[projects."/Users/mbolin/code/codex2"]
-trust_level = "trusted"
-"#;
-        assert_eq!(contents, expected);
-
-        Ok(())
-    }
 }
@@ -3,11 +3,19 @@
 // Note this file should generally be restricted to simple struct/enum
 // definitions that do not contain business logic.
 
+use std::collections::BTreeMap;
 use std::collections::HashMap;
 use std::path::PathBuf;
+use std::time::Duration;
 use wildmatch::WildMatchPattern;
 
+use codex_apply_patch::Encoding;
+use codex_apply_patch::LineEnding;
+use codex_apply_patch::WriteOptions;
 use serde::Deserialize;
+use serde::Serialize;
+
+use crate::protocol::ApprovalTimeoutAction;
 
 #[derive(Deserialize, Debug, Clone, PartialEq)]
 pub struct McpServerConfig {
@@ -76,6 +84,403 @@ pub enum HistoryPersistence {
     None,
 }
 
+/// Resolved `approval_timeout_secs` / `approval_timeout_action` settings.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ApprovalTimeout {
+    /// How long an approval request may stay unanswered.
+    pub duration: Duration,
+    /// What happens once it expires.
+    pub action: ApprovalTimeoutAction,
+}
+
+/// Which releases `codex update` and the update-available notice follow.
+#[derive(Serialize, Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum UpdateChannel {
+    /// Only full releases.
+    #[default]
+    Stable,
+    /// Also alpha and beta pre-releases.
+    Beta,
+}
+
+/// Settings for Codex's own log files (`[log]` in config.toml).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Log {
+    /// Default level (`info`, `debug`, ...) or full `tracing` filter
+    /// directives. `RUST_LOG` takes precedence when set.
+    pub level: Option<String>,
+
+    /// Per-subsystem levels, e.g. `"core::exec" = "debug"` or `mcp = "trace"`.
+    pub targets: BTreeMap<String, String>,
+
+    /// Format of lines written to the log file.
+    pub format: LogFormat,
+
+    /// Size at which the log file is rotated.
+    pub max_file_size_mb: u64,
+
+    /// Number of rotated files kept next to the active one.
+    pub max_files: usize,
+}
+
+impl Default for Log {
+    fn default() -> Self {
+        Self {
+            level: None,
+            targets: BTreeMap::new(),
+            format: LogFormat::default(),
+            max_file_size_mb: 10,
+            max_files: 5,
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Copy, Clone, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LogFormat {
+    #[default]
+    Text,
+    /// One JSON object per line.
+    Json,
+}
+
+/// Settings for `codex share` bundles (`[share]` in config.toml).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Share {
+    /// Scrub secrets and the home directory from exported sessions.
+    pub redact: bool,
+
+    /// Extra regular expressions whose matches are redacted, on top of the
+    /// built-in secret patterns.
+    pub redact_patterns: Vec<String>,
+}
+
+impl Default for Share {
+    fn default() -> Self {
+        Self {
+            redact: true,
+            redact_patterns: Vec::new(),
+        }
+    }
+}
+
+/// Exceptions to the check that rejects patches whose paths resolve, through
+/// symlinks or `..`, outside the writable roots (`[workspace_guard]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct WorkspaceGuard {
+    /// Directories patches may write into even though they lie outside the
+    /// writable roots, e.g. a shared directory the workspace links to.
+    pub allow: Vec<PathBuf>,
+}
+
+/// Files patches may not edit without explicit approval (`[patch_guard]`):
+/// binary files, files over a size limit, and generated paths.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct PatchGuard {
+    /// Files larger than this, before or after the patch, are protected.
+    /// `0` disables the limit.
+    pub max_file_bytes: u64,
+
+    /// Globs of generated paths. A glob without `/` matches file names at any
+    /// depth; one with `/` matches the path relative to the repository root.
+    pub protected_paths: Vec<String>,
+
+    /// Treat paths marked `linguist-generated`, `binary` or `-diff` in the
+    /// repository's `.gitattributes` as generated.
+    pub gitattributes: bool,
+
+    /// What happens to a patch that touches a protected file.
+    pub on_violation: PatchGuardAction,
+}
+
+impl Default for PatchGuard {
+    fn default() -> Self {
+        Self {
+            max_file_bytes: 1024 * 1024,
+            protected_paths: ["*.lock", "package-lock.json", "pnpm-lock.yaml", "go.sum"]
+                .map(String::from)
+                .to_vec(),
+            gitattributes: true,
+            on_violation: PatchGuardAction::default(),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum PatchGuardAction {
+    /// Ask the user to approve the patch. Without an approval channel
+    /// (`approval_policy = "never"`) the patch is rejected.
+    #[default]
+    Ask,
+    /// Reject the patch.
+    Reject,
+}
+
+/// Encoding and line endings of files patches write (`[file_format]`). By
+/// default each file keeps the format it has.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(default)]
+pub struct FileFormat {
+    pub line_endings: LineEndingPolicy,
+    pub encoding: EncodingPolicy,
+}
+
+impl FileFormat {
+    /// The options passed to `apply_patch` when it applies a patch.
+    pub fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            line_ending: match self.line_endings {
+                LineEndingPolicy::Preserve => None,
+                LineEndingPolicy::Lf => Some(LineEnding::Lf),
+                LineEndingPolicy::Crlf => Some(LineEnding::CrLf),
+            },
+            encoding: match self.encoding {
+                EncodingPolicy::Preserve => None,
+                EncodingPolicy::Utf8 => Some(Encoding::Utf8),
+            },
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum LineEndingPolicy {
+    /// Keep each file's line endings; new files use LF.
+    #[default]
+    Preserve,
+    Lf,
+    Crlf,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EncodingPolicy {
+    /// Keep each file's encoding (UTF-8 with or without a BOM, UTF-16 or
+    /// Latin-1); new files use UTF-8.
+    #[default]
+    #[serde(rename = "preserve")]
+    Preserve,
+    /// Convert the files patches write to UTF-8 without a BOM.
+    #[serde(rename = "utf-8")]
+    Utf8,
+}
+
+/// Repository map added to the first prompt (`[repo_map]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct RepoMap {
+    pub enabled: bool,
+
+    /// Upper bound on the size of the map. Symbols of files that do not fit
+    /// are left out, shallowest files first.
+    pub max_bytes: usize,
+
+    /// List the public symbols of source files.
+    pub symbols: bool,
+}
+
+impl Default for RepoMap {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_bytes: 8 * 1024,
+            symbols: true,
+        }
+    }
+}
+
+/// Watch the working directory for edits made outside the agent
+/// (`[file_watcher]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FileWatcher {
+    pub enabled: bool,
+
+    /// Changed files listed to the model per turn at most.
+    pub max_files: usize,
+}
+
+impl Default for FileWatcher {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_files: 20,
+        }
+    }
+}
+
+/// Review of the diff a `codex exec` run produced (`[exec_critic]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct ExecCritic {
+    pub enabled: bool,
+
+    /// Model preset the critic runs with; the session's model when unset.
+    pub preset: Option<String>,
+
+    /// Revision rounds the critic may request before the run ends anyway.
+    pub max_rounds: u32,
+}
+
+impl Default for ExecCritic {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            preset: None,
+            max_rounds: 2,
+        }
+    }
+}
+
+/// Local event log of anonymized feature usage and errors (`[telemetry]`),
+/// kept in `$CODEX_HOME/events.db`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct Telemetry {
+    /// Record events locally. Off unless opted into; nothing leaves the
+    /// machine unless `export` is set as well.
+    pub local: bool,
+
+    /// Events older than this are deleted.
+    pub retention_days: u32,
+
+    /// Upper bound on the number of stored events; the oldest go first.
+    pub max_events: u64,
+
+    /// Opt-in upload of redacted events.
+    pub export: Option<TelemetryExport>,
+}
+
+impl Default for Telemetry {
+    fn default() -> Self {
+        Self {
+            local: false,
+            retention_days: 90,
+            max_events: 100_000,
+            export: None,
+        }
+    }
+}
+
+/// Where redacted events are uploaded (`[telemetry.export]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct TelemetryExport {
+    /// URL the events are POSTed to as JSON.
+    pub endpoint: String,
+
+    /// Extra HTTP headers, e.g. for authentication.
+    #[serde(default)]
+    pub headers: HashMap<String, String>,
+}
+
+/// Token prices for one model (`[model_prices.<slug>]`), in US dollars per
+/// million tokens, used by `codex stats` to estimate cost.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq)]
+pub struct ModelPrice {
+    pub input: f64,
+    /// Price of input tokens served from the prompt cache; `input` when unset.
+    #[serde(default)]
+    pub cached_input: Option<f64>,
+    pub output: f64,
+}
+
+/// Syntax checks on files after a patch writes them (`[syntax_check]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct SyntaxCheck {
+    pub enabled: bool,
+
+    /// Validator commands by file extension (without the dot), e.g.
+    /// `py = ["python3", "-m", "py_compile", "{path}"]`. `{path}` is replaced
+    /// with the file's path, which is appended when absent. A non-zero exit
+    /// status means the file does not parse.
+    pub validators: HashMap<String, Vec<String>>,
+
+    /// Parse `.sh` and `.bash` files with tree-sitter when no validator is
+    /// configured for them.
+    pub tree_sitter: bool,
+
+    /// How long a validator may run before it counts as failed.
+    pub timeout_ms: u64,
+}
+
+impl Default for SyntaxCheck {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            validators: HashMap::new(),
+            tree_sitter: true,
+            timeout_ms: 10_000,
+        }
+    }
+}
+
+/// Settings for the `fetch_url` tool (`[fetch_url]` in config.toml). The tool
+/// itself is enabled with `tools.fetch_url`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(default)]
+pub struct FetchUrl {
+    /// Hosts the tool refuses to fetch from. An entry also covers its
+    /// subdomains.
+    pub deny_hosts: Vec<String>,
+
+    /// When non-empty, the only hosts (and their subdomains) that may be
+    /// fetched.
+    pub allow_hosts: Vec<String>,
+
+    /// Skip pages the site's robots.txt disallows.
+    pub respect_robots_txt: bool,
+
+    /// Approximate number of tokens of page text returned to the model.
+    pub max_tokens: usize,
+}
+
+impl Default for FetchUrl {
+    fn default() -> Self {
+        Self {
+            deny_hosts: Vec::new(),
+            allow_hosts: Vec::new(),
+            respect_robots_txt: true,
+            max_tokens: 8_000,
+        }
+    }
+}
+
+/// A recurring `codex exec` run (`[schedules.<name>]`), registered with the
+/// platform scheduler by `codex schedule install`.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ScheduledRun {
+    /// When to run, as a five-field cron spec: minute, hour, day of month,
+    /// month and day of week.
+    pub cron: String,
+
+    /// Prompt given to `codex exec`.
+    pub prompt: String,
+
+    /// Directory the run works in.
+    pub cwd: PathBuf,
+
+    /// Config profile the run uses.
+    #[serde(default)]
+    pub profile: Option<String>,
+
+    /// Run with `--full-auto`, so the agent may edit files in `cwd`.
+    #[serde(default)]
+    pub full_auto: bool,
+}
+
+/// Text appended to the base instructions for one model or provider
+/// (`[model_instructions."<model>"]`, `[provider_instructions.<id>]`).
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct InstructionSnippet {
+    pub instructions: String,
+}
+
 /// Collection of settings that are specific to the TUI.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct Tui {}
@@ -118,31 +523,42 @@ pub enum ShellEnvironmentPolicyInherit {
     None,
 }
 
-/// Policy for building the `env` when spawning a process via either the
-/// `shell` or `local_shell` tool.
+/// Policy for building the `env` of every process spawned for a tool call:
+/// `shell`, `local_shell`, `exec_command` and `unified_exec`. Read from
+/// `[exec.env]`, or from the older `[shell_environment_policy]` table.
 #[derive(Deserialize, Debug, Clone, PartialEq, Default)]
 pub struct ShellEnvironmentPolicyToml {
     pub inherit: Option<ShellEnvironmentPolicyInherit>,
 
     pub ignore_default_excludes: Option<bool>,
 
-    /// List of regular expressions.
+    /// List of glob patterns (`deny` in `[exec.env]`).
+    #[serde(alias = "deny")]
     pub exclude: Option<Vec<String>>,
 
     pub r#set: Option<HashMap<String, String>>,
 
-    /// List of regular expressions.
+    /// List of glob patterns (`allow` in `[exec.env]`).
+    #[serde(alias = "allow")]
     pub include_only: Option<Vec<String>>,
 
     pub experimental_use_profile: Option<bool>,
 }
 
+/// The `[exec]` table in config.toml.
+#[derive(Deserialize, Debug, Clone, PartialEq, Default)]
+pub struct ExecToml {
+    /// Environment passed to spawned tool processes. Takes precedence over
+    /// `[shell_environment_policy]` when both are present.
+    pub env: Option<ShellEnvironmentPolicyToml>,
+}
+
 pub type EnvironmentVariablePattern = WildMatchPattern<'*', '?'>;
 
 /// Deriving the `env` based on this policy works as follows:
 /// 1. Create an initial map based on the `inherit` policy.
 /// 2. If `ignore_default_excludes` is false, filter the map using the default
-///    exclude pattern(s), which are: `"*KEY*"` and `"*TOKEN*"`.
+///    exclude pattern(s), which are: `"*KEY*"`, `"*SECRET*"` and `"*TOKEN*"`.
 /// 3. If `exclude` is not empty, filter the map using the provided patterns.
 /// 4. Insert any entries from `r#set` into the map.
 /// 5. If non-empty, filter the map using the `include_only` patterns.
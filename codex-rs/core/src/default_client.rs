@@ -106,6 +106,14 @@ fn sanitize_user_agent(candidate: String, fallback: &str) -> String {
 
 /// Create a reqwest client with default `originator` and `User-Agent` headers set.
 pub fn create_client() -> reqwest::Client {
+    create_client_builder()
+        .build()
+        .unwrap_or_else(|_| reqwest::Client::new())
+}
+
+/// Builder for a client like [`create_client`], for callers that need to
+/// adjust it further.
+pub fn create_client_builder() -> reqwest::ClientBuilder {
     use reqwest::header::HeaderMap;
 
     let mut headers = HeaderMap::new();
@@ -116,8 +124,6 @@ pub fn create_client() -> reqwest::Client {
         // Set UA via dedicated helper to avoid header validation pitfalls
         .user_agent(ua)
         .default_headers(headers)
-        .build()
-        .unwrap_or_else(|_| reqwest::Client::new())
 }
 
 #[cfg(test)]
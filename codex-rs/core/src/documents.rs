@@ -0,0 +1,239 @@
+//! Text extraction for documents the model cannot read as they are: PDFs and
+//! Word (`.docx`) files attached by the user, or opened by the agent with
+//! `view_image`, are turned into plain text before they reach the model.
+
+use std::io::Read;
+use std::path::Path;
+
+use anyhow::Context;
+
+use crate::protocol::InputItem;
+
+/// Extracted text beyond this many bytes is cut off.
+const MAX_DOCUMENT_TEXT_BYTES: usize = 256 * 1024;
+
+/// A `word/document.xml` larger than this is refused rather than inflated
+/// into memory; markup is typically several times the size of the text.
+const MAX_DOCX_XML_BYTES: u64 = 64 * 1024 * 1024;
+
+/// Whether `path` names a document whose text [`extract_document_text`] can
+/// extract, judged by its extension.
+pub fn is_document(path: &Path) -> bool {
+    document_kind(path).is_some()
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum DocumentKind {
+    Pdf,
+    Docx,
+}
+
+fn document_kind(path: &Path) -> Option<DocumentKind> {
+    match path
+        .extension()
+        .and_then(|ext| ext.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("pdf") => Some(DocumentKind::Pdf),
+        Some("docx") => Some(DocumentKind::Docx),
+        _ => None,
+    }
+}
+
+/// The text of the document at `path`, truncated to a fixed size.
+pub fn extract_document_text(path: &Path) -> anyhow::Result<String> {
+    let text = match document_kind(path) {
+        Some(DocumentKind::Pdf) => pdf_extract::extract_text(path)
+            .with_context(|| format!("could not extract text from {}", path.display()))?,
+        Some(DocumentKind::Docx) => docx_text(path)
+            .with_context(|| format!("could not extract text from {}", path.display()))?,
+        None => anyhow::bail!("{} is not a PDF or DOCX file", path.display()),
+    };
+    Ok(truncate(text.trim()))
+}
+
+/// The labelled block the model sees in place of the document.
+pub(crate) fn document_block(path: &Path, text: &str) -> String {
+    format!(
+        "<document path=\"{}\">\n{text}\n</document>",
+        path.display()
+    )
+}
+
+/// Replace every `LocalFile` in `items` with the text of the document. Fails
+/// on the first document that cannot be read, so the user hears about it
+/// instead of the model answering without it.
+pub(crate) async fn resolve_documents(items: Vec<InputItem>) -> anyhow::Result<Vec<InputItem>> {
+    if !items
+        .iter()
+        .any(|item| matches!(item, InputItem::LocalFile { .. }))
+    {
+        return Ok(items);
+    }
+    let mut resolved = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            InputItem::LocalFile { path } => {
+                let text = {
+                    let path = path.clone();
+                    tokio::task::spawn_blocking(move || extract_document_text(&path)).await??
+                };
+                resolved.push(InputItem::Text {
+                    text: document_block(&path, &text),
+                });
+            }
+            other => resolved.push(other),
+        }
+    }
+    Ok(resolved)
+}
+
+/// Paragraph text from `word/document.xml`: `<w:t>` runs joined, one line
+/// per `<w:p>`, with tabs and breaks kept.
+fn docx_text(path: &Path) -> anyhow::Result<String> {
+    let file = std::fs::File::open(path)?;
+    let mut archive = zip::ZipArchive::new(file)?;
+    let xml = read_capped(archive.by_name("word/document.xml")?, MAX_DOCX_XML_BYTES)?;
+    Ok(document_xml_text(&xml))
+}
+
+/// Read all of `reader`, failing once more than `limit` bytes come out.
+fn read_capped(reader: impl Read, limit: u64) -> anyhow::Result<String> {
+    // Bytes first: the cut may fall inside a multi-byte character.
+    let mut bytes = Vec::new();
+    reader.take(limit + 1).read_to_end(&mut bytes)?;
+    if bytes.len() as u64 > limit {
+        anyhow::bail!("document body is larger than {limit} bytes");
+    }
+    Ok(String::from_utf8(bytes)?)
+}
+
+fn document_xml_text(xml: &str) -> String {
+    let mut out = String::new();
+    let mut in_text = false;
+    let mut rest = xml;
+    while let Some(lt) = rest.find('<') {
+        if in_text {
+            out.push_str(&decode_xml_entities(&rest[..lt]));
+        }
+        let Some(gt) = rest[lt..].find('>').map(|gt| lt + gt) else {
+            break;
+        };
+        let tag = &rest[lt + 1..gt];
+        let name = tag
+            .trim_start_matches('/')
+            .split(|c: char| c.is_whitespace() || c == '/')
+            .next()
+            .unwrap_or_default();
+        match (name, tag.starts_with('/')) {
+            ("w:t", false) => in_text = !tag.ends_with('/'),
+            ("w:t", true) => in_text = false,
+            ("w:tab", false) => out.push('\t'),
+            ("w:br" | "w:cr", false) => out.push('\n'),
+            ("w:p", true) => out.push('\n'),
+            _ => {}
+        }
+        rest = &rest[gt + 1..];
+    }
+    out
+}
+
+fn decode_xml_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').and_then(|end| {
+            let ch = match &rest[1..end] {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                entity => entity
+                    .strip_prefix("#x")
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+fn truncate(text: &str) -> String {
+    if text.len() <= MAX_DOCUMENT_TEXT_BYTES {
+        return text.to_string();
+    }
+    let mut end = MAX_DOCUMENT_TEXT_BYTES;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    format!(
+        "{}\n[truncated: {} more bytes]",
+        &text[..end],
+        text.len() - end
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::io::Write;
+
+    #[test]
+    fn docx_paragraphs_become_lines() {
+        let xml = r#"<?xml version="1.0"?><w:document><w:body>
+            <w:p><w:r><w:t>Design</w:t></w:r><w:r><w:t xml:space="preserve"> &amp; scope</w:t></w:r></w:p>
+            <w:p><w:r><w:t>Step</w:t><w:tab/><w:t>one</w:t><w:br/><w:t>two</w:t></w:r></w:p>
+            </w:body></w:document>"#;
+        assert_eq!(document_xml_text(xml), "Design & scope\nStep\tone\ntwo\n");
+
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("spec.DOCX");
+        let mut writer = zip::ZipWriter::new(std::fs::File::create(&path).expect("create docx"));
+        writer
+            .start_file(
+                "word/document.xml",
+                zip::write::SimpleFileOptions::default(),
+            )
+            .expect("start file");
+        writer.write_all(xml.as_bytes()).expect("write xml");
+        writer.finish().expect("finish docx");
+
+        assert!(is_document(&path));
+        assert!(!is_document(Path::new("notes.txt")));
+        assert_eq!(
+            extract_document_text(&path).expect("extract docx"),
+            "Design & scope\nStep\tone\ntwo"
+        );
+    }
+
+    #[test]
+    fn oversized_document_body_is_refused() {
+        assert_eq!(
+            read_capped("<w:t>ok</w:t>".as_bytes(), 13).expect("within cap"),
+            "<w:t>ok</w:t>"
+        );
+        let err = read_capped("<w:t>too long</w:t>".as_bytes(), 13).expect_err("over cap");
+        assert_eq!(err.to_string(), "document body is larger than 13 bytes");
+    }
+}
@@ -28,6 +28,8 @@ pub(crate) struct EnvironmentContext {
     pub network_access: Option<NetworkAccess>,
     pub writable_roots: Option<Vec<PathBuf>>,
     pub shell: Option<Shell>,
+    /// The session's scratch directory for temporary files.
+    pub scratch_path: Option<PathBuf>,
 }
 
 impl EnvironmentContext {
@@ -36,6 +38,7 @@ impl EnvironmentContext {
         approval_policy: Option<AskForApproval>,
         sandbox_policy: Option<SandboxPolicy>,
         shell: Option<Shell>,
+        scratch_path: Option<PathBuf>,
     ) -> Self {
         Self {
             cwd,
@@ -69,6 +72,7 @@ impl EnvironmentContext {
                 _ => None,
             },
             shell,
+            scratch_path,
         }
     }
 }
@@ -86,6 +90,7 @@ impl EnvironmentContext {
     ///   <writable_roots>...</writable_roots>
     ///   <network_access>...</network_access>
     ///   <shell>...</shell>
+    ///   <scratch_path>...</scratch_path>
     /// </environment_context>
     /// ```
     pub fn serialize_to_xml(self) -> String {
@@ -121,6 +126,12 @@ impl EnvironmentContext {
         {
             lines.push(format!("  <shell>{shell_name}</shell>"));
         }
+        if let Some(scratch_path) = self.scratch_path {
+            lines.push(format!(
+                "  <scratch_path>{}</scratch_path>",
+                scratch_path.to_string_lossy()
+            ));
+        }
         lines.push(ENVIRONMENT_CONTEXT_CLOSE_TAG.to_string());
         lines.join("\n")
     }
@@ -159,6 +170,7 @@ mod tests {
             Some(AskForApproval::OnRequest),
             Some(workspace_write_policy(vec!["/repo", "/tmp"], false)),
             None,
+            Some(PathBuf::from("/home/me/.codex/scratch/abc")),
         );
 
         let expected = r#"<environment_context>
@@ -170,6 +182,7 @@ mod tests {
     <root>/repo</root>
     <root>/tmp</root>
   </writable_roots>
+  <scratch_path>/home/me/.codex/scratch/abc</scratch_path>
 </environment_context>"#;
 
         assert_eq!(context.serialize_to_xml(), expected);
@@ -182,6 +195,7 @@ mod tests {
             Some(AskForApproval::Never),
             Some(SandboxPolicy::ReadOnly),
             None,
+            None,
         );
 
         let expected = r#"<environment_context>
@@ -200,6 +214,7 @@ mod tests {
             Some(AskForApproval::OnFailure),
             Some(SandboxPolicy::DangerFullAccess),
             None,
+            None,
         );
 
         let expected = r#"<environment_context>
@@ -1,6 +1,7 @@
 use crate::token_data::KnownPlan;
 use crate::token_data::PlanType;
 use codex_protocol::mcp_protocol::ConversationId;
+use codex_protocol::protocol::ErrorCategory;
 use reqwest::StatusCode;
 use serde_json;
 use std::io;
@@ -100,6 +101,13 @@ pub enum CodexErr {
     #[error("codex-linux-sandbox was required but not provided")]
     LandlockSandboxExecutableNotProvided,
 
+    /// Offline mode is active and the request targets a host that is not in
+    /// `offline_allowed_hosts`.
+    #[error(
+        "offline mode: refusing to connect to {0}; add its host to `offline_allowed_hosts` to allow it"
+    )]
+    OfflineNetworkDenied(String),
+
     // -----------------------------------------------------------------
     // Automatic conversions for common external error types
     // -----------------------------------------------------------------
@@ -241,6 +249,37 @@ impl CodexErr {
     pub fn downcast_ref<T: std::any::Any>(&self) -> Option<&T> {
         (self as &dyn std::any::Any).downcast_ref::<T>()
     }
+
+    /// Classify the error for clients that surface it programmatically (e.g.
+    /// `codex exec` exit codes).
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            CodexErr::UnexpectedStatus(status, _) | CodexErr::RetryLimit(status)
+                if is_auth_status(*status) =>
+            {
+                ErrorCategory::Auth
+            }
+            CodexErr::UsageNotIncluded | CodexErr::EnvVar(_) => ErrorCategory::Auth,
+            CodexErr::UsageLimitReached(_) => ErrorCategory::UsageLimit,
+            CodexErr::Stream(..)
+            | CodexErr::UnexpectedStatus(..)
+            | CodexErr::RetryLimit(_)
+            | CodexErr::InternalServerError
+            | CodexErr::Reqwest(_)
+            | CodexErr::OfflineNetworkDenied(_) => ErrorCategory::Provider,
+            CodexErr::Sandbox(SandboxErr::Timeout) => ErrorCategory::Internal,
+            CodexErr::Sandbox(_) | CodexErr::LandlockSandboxExecutableNotProvided => {
+                ErrorCategory::Sandbox
+            }
+            #[cfg(target_os = "linux")]
+            CodexErr::LandlockRuleset(_) | CodexErr::LandlockPathFd(_) => ErrorCategory::Sandbox,
+            _ => ErrorCategory::Internal,
+        }
+    }
+}
+
+fn is_auth_status(status: StatusCode) -> bool {
+    status == StatusCode::UNAUTHORIZED || status == StatusCode::FORBIDDEN
 }
 
 pub fn get_error_message_ui(e: &CodexErr) -> String {
@@ -256,6 +295,31 @@ pub fn get_error_message_ui(e: &CodexErr) -> String {
 mod tests {
     use super::*;
 
+    #[test]
+    fn categorizes_errors() {
+        assert_eq!(
+            CodexErr::UnexpectedStatus(StatusCode::UNAUTHORIZED, String::new()).category(),
+            ErrorCategory::Auth
+        );
+        assert_eq!(
+            CodexErr::RetryLimit(StatusCode::BAD_GATEWAY).category(),
+            ErrorCategory::Provider
+        );
+        assert_eq!(
+            CodexErr::UsageLimitReached(UsageLimitReachedError {
+                plan_type: None,
+                resets_in_seconds: None,
+            })
+            .category(),
+            ErrorCategory::UsageLimit
+        );
+        assert_eq!(
+            CodexErr::Sandbox(SandboxErr::Denied(1, String::new(), String::new())).category(),
+            ErrorCategory::Sandbox
+        );
+        assert_eq!(CodexErr::Spawn.category(), ErrorCategory::Internal);
+    }
+
     #[test]
     fn usage_limit_reached_error_formats_plus_plan() {
         let err = UsageLimitReachedError {
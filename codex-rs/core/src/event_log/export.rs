@@ -0,0 +1,173 @@
+//! Opt-in upload of the event log (`[telemetry.export]`). Only a redacted
+//! form leaves the machine: no session ids, timestamps coarsened to the UTC
+//! day, MCP server names and unrecognized model slugs replaced.
+
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use chrono::DateTime;
+use chrono::Utc;
+use serde::Serialize;
+use tracing::debug;
+use tracing::warn;
+
+use super::store;
+use super::store::StoredEvent;
+use crate::config_types::TelemetryExport;
+use crate::default_client::create_client;
+
+/// Events uploaded per request.
+const BATCH_SIZE: usize = 500;
+
+/// Model slug prefixes of publicly available models, exported verbatim.
+/// Anything else, e.g. a fine-tune or a local model, becomes `other`.
+const PUBLIC_MODEL_PREFIXES: &[&str] = &["gpt-", "o1", "o3", "o4", "codex-"];
+
+#[derive(Debug, Serialize, PartialEq)]
+struct ExportedEvent {
+    /// UTC day, `YYYY-MM-DD`.
+    day: String,
+    kind: String,
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    success: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    value: Option<i64>,
+}
+
+#[derive(Serialize)]
+struct ExportBatch {
+    events: Vec<ExportedEvent>,
+}
+
+/// Upload every event not yet sent to `export.endpoint`. Stops at the first
+/// failure; the remaining events go out with a later session.
+pub(super) async fn export_pending(path: PathBuf, export: TelemetryExport) {
+    let client = create_client();
+    loop {
+        let endpoint = export.endpoint.clone();
+        let db = path.clone();
+        let pending = tokio::task::spawn_blocking(move || {
+            let conn = store::open(&db)?;
+            store::pending_export(&conn, &endpoint, BATCH_SIZE)
+        })
+        .await;
+        let pending = match pending {
+            Ok(Ok(pending)) => pending,
+            Ok(Err(e)) => {
+                warn!("event export: {e:#}");
+                return;
+            }
+            Err(e) => {
+                warn!("event export: {e}");
+                return;
+            }
+        };
+        let Some(last_id) = pending.last().map(|event| event.id) else {
+            return;
+        };
+        let done = pending.len() < BATCH_SIZE;
+        let batch = ExportBatch {
+            events: pending.iter().map(redact).collect(),
+        };
+
+        let mut request = client.post(&export.endpoint).json(&batch);
+        for (name, value) in &export.headers {
+            request = request.header(name, value);
+        }
+        match request.send().await {
+            Ok(response) if response.status().is_success() => {}
+            Ok(response) => {
+                warn!(
+                    "event export: {} answered {}",
+                    export.endpoint,
+                    response.status()
+                );
+                return;
+            }
+            Err(e) => {
+                debug!("event export: {e}");
+                return;
+            }
+        }
+
+        let endpoint = export.endpoint.clone();
+        let db = path.clone();
+        let saved = tokio::task::spawn_blocking(move || {
+            let conn = store::open(&db)?;
+            store::set_export_cursor(&conn, &endpoint, last_id)
+        })
+        .await;
+        if !matches!(saved, Ok(Ok(()))) || done {
+            return;
+        }
+    }
+}
+
+fn redact(event: &StoredEvent) -> ExportedEvent {
+    let name = match event.kind.as_str() {
+        "tool_call" if event.name.starts_with("mcp:") => "mcp".to_string(),
+        "session_start" | "model_request" => public_model(&event.name),
+        _ => event.name.clone(),
+    };
+    ExportedEvent {
+        day: utc_day(event.at),
+        kind: event.kind.clone(),
+        name,
+        success: event.success,
+        value: event.value,
+    }
+}
+
+fn public_model(slug: &str) -> String {
+    if PUBLIC_MODEL_PREFIXES
+        .iter()
+        .any(|prefix| slug.starts_with(prefix))
+    {
+        slug.to_string()
+    } else {
+        "other".to_string()
+    }
+}
+
+fn utc_day(at: SystemTime) -> String {
+    DateTime::<Utc>::from(at).format("%Y-%m-%d").to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::time::Duration;
+    use std::time::UNIX_EPOCH;
+
+    #[test]
+    fn redaction_drops_identifying_details() {
+        let at = UNIX_EPOCH + Duration::from_secs(1_760_531_400);
+        let event = |kind: &str, name: &str| StoredEvent {
+            id: 7,
+            at,
+            kind: kind.to_string(),
+            name: name.to_string(),
+            success: Some(true),
+            value: Some(120),
+        };
+
+        assert_eq!(
+            redact(&event("tool_call", "mcp:acme-internal-jira")),
+            ExportedEvent {
+                day: "2025-10-15".to_string(),
+                kind: "tool_call".to_string(),
+                name: "mcp".to_string(),
+                success: Some(true),
+                value: Some(120),
+            }
+        );
+        assert_eq!(
+            redact(&event("model_request", "acme-finetune-v2")).name,
+            "other"
+        );
+        assert_eq!(redact(&event("session_start", "gpt-5")).name, "gpt-5");
+        assert_eq!(redact(&event("error", "usage_limit")).name, "usage_limit");
+    }
+}
@@ -0,0 +1,356 @@
+//! Local event log (`$CODEX_HOME/events.db`): anonymized feature usage and
+//! errors, recorded as sessions run and read back by `codex stats` and
+//! `codex doctor`.
+//!
+//! Events carry no prompts, paths, commands or output, only what kind of
+//! thing happened: which tool ran, whether it succeeded and how long it
+//! took, which approval was granted, which error category occurred. The log
+//! is opt-in (`[telemetry] local = true`). Nothing leaves the machine unless
+//! `[telemetry.export]` is configured too, and then only in the redacted form
+//! produced by `export`.
+
+mod export;
+mod store;
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+use std::sync::mpsc;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::RolloutItem;
+use tracing::warn;
+
+use crate::config::Config;
+use crate::config_types::Telemetry;
+
+pub use store::EventLogStatus;
+pub use store::EventLogSummary;
+pub use store::ToolUsage;
+
+/// File name of the event log under `CODEX_HOME`.
+pub const EVENTS_DB_FILENAME: &str = "events.db";
+
+const SECONDS_PER_DAY: u64 = 24 * 60 * 60;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum EventKind {
+    /// A session started; `name` is the model.
+    SessionStart,
+    /// An optional feature was on for a session; `name` is the feature.
+    Feature,
+    /// A tool call finished; `value` is its duration in milliseconds.
+    ToolCall,
+    /// An approval was answered; `value` is 1 when it timed out.
+    Approval,
+    /// A model request finished; `name` is the model, `value` its tokens.
+    ModelRequest,
+    /// `name` is the error category.
+    Error,
+    /// `name` is the reason.
+    TurnAborted,
+}
+
+impl EventKind {
+    pub(crate) fn as_str(self) -> &'static str {
+        match self {
+            EventKind::SessionStart => "session_start",
+            EventKind::Feature => "feature",
+            EventKind::ToolCall => "tool_call",
+            EventKind::Approval => "approval",
+            EventKind::ModelRequest => "model_request",
+            EventKind::Error => "error",
+            EventKind::TurnAborted => "turn_aborted",
+        }
+    }
+}
+
+/// One row of the log.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct LoggedEvent {
+    pub(crate) kind: EventKind,
+    pub(crate) name: String,
+    pub(crate) success: Option<bool>,
+    pub(crate) value: Option<i64>,
+}
+
+impl LoggedEvent {
+    fn new(kind: EventKind, name: impl Into<String>) -> Self {
+        Self {
+            kind,
+            name: name.into(),
+            success: None,
+            value: None,
+        }
+    }
+
+    fn success(mut self, success: bool) -> Self {
+        self.success = Some(success);
+        self
+    }
+
+    fn value(mut self, value: i64) -> Self {
+        self.value = Some(value);
+        self
+    }
+}
+
+/// A session's handle on the log. Rows are written by a background thread,
+/// so recording never blocks the session; failures are logged and dropped.
+pub(crate) struct EventLog {
+    tx: mpsc::Sender<Vec<(SystemTime, LoggedEvent)>>,
+    /// Model of the current turn, to attribute model requests to.
+    model: Mutex<String>,
+}
+
+impl EventLog {
+    /// Start logging for a session, if `[telemetry] local = true`.
+    /// Old events are pruned and, when configured, pending ones exported.
+    pub(crate) fn start(config: &Config, session: String) -> Option<Self> {
+        let mut settings = config.telemetry.clone();
+        if !settings.local {
+            return None;
+        }
+        if config.offline.is_some() {
+            settings.export = None;
+        }
+        let path = events_db_path(&config.codex_home);
+        let (tx, rx) = mpsc::channel::<Vec<(SystemTime, LoggedEvent)>>();
+        let export_path = path.clone();
+        // The export runs on the session's runtime once the schema is
+        // current.
+        let runtime = tokio::runtime::Handle::try_current().ok();
+        let spawned = std::thread::Builder::new()
+            .name("codex-event-log".to_string())
+            .spawn(move || {
+                let mut conn = match open_and_prune(&path, &settings) {
+                    Ok(conn) => conn,
+                    Err(e) => {
+                        warn!("event log disabled: {e:#}");
+                        return;
+                    }
+                };
+                if let Some(export) = settings.export
+                    && let Some(runtime) = runtime
+                {
+                    runtime.spawn(export::export_pending(export_path, export));
+                }
+                while let Ok(mut batch) = rx.recv() {
+                    // Write whatever else has queued up in the same
+                    // transaction.
+                    while let Ok(more) = rx.try_recv() {
+                        batch.extend(more);
+                    }
+                    if let Err(e) = store::insert(&mut conn, &session, &batch) {
+                        warn!("failed to write to the event log: {e:#}");
+                    }
+                }
+            });
+        if let Err(e) = spawned {
+            warn!("event log disabled: {e}");
+            return None;
+        }
+
+        let log = Self {
+            tx,
+            model: Mutex::new(config.model.clone()),
+        };
+        let mut events = vec![LoggedEvent::new(
+            EventKind::SessionStart,
+            config.model.clone(),
+        )];
+        events.extend(
+            enabled_features(config)
+                .into_iter()
+                .map(|feature| LoggedEvent::new(EventKind::Feature, feature)),
+        );
+        log.record(events);
+        Some(log)
+    }
+
+    /// Log whatever in `items` is worth counting.
+    pub(crate) fn observe(&self, items: &[RolloutItem]) {
+        let mut events = Vec::new();
+        for item in items {
+            match item {
+                RolloutItem::TurnContext(turn) => {
+                    *self.model.lock().unwrap_or_else(PoisonError::into_inner) = turn.model.clone();
+                }
+                RolloutItem::Approval(approval) => {
+                    let approved = matches!(
+                        approval.decision,
+                        ReviewDecision::Approved | ReviewDecision::ApprovedForSession
+                    );
+                    let kind = serde_json::to_value(approval.kind)
+                        .ok()
+                        .and_then(|kind| kind.as_str().map(str::to_string))
+                        .unwrap_or_default();
+                    events.push(
+                        LoggedEvent::new(EventKind::Approval, kind)
+                            .success(approved)
+                            .value(i64::from(approval.timed_out)),
+                    );
+                }
+                RolloutItem::EventMsg(msg) => {
+                    let model = self
+                        .model
+                        .lock()
+                        .unwrap_or_else(PoisonError::into_inner)
+                        .clone();
+                    events.extend(event_for(msg, &model));
+                }
+                RolloutItem::SessionMeta(_)
+                | RolloutItem::ResponseItem(_)
+                | RolloutItem::Compacted(_) => {}
+            }
+        }
+        if !events.is_empty() {
+            self.record(events);
+        }
+    }
+
+    fn record(&self, events: Vec<LoggedEvent>) {
+        let now = SystemTime::now();
+        let _ = self
+            .tx
+            .send(events.into_iter().map(|event| (now, event)).collect());
+    }
+}
+
+/// The row, if any, that `msg` contributes.
+fn event_for(msg: &EventMsg, model: &str) -> Option<LoggedEvent> {
+    let event = match msg {
+        EventMsg::ExecCommandEnd(end) => LoggedEvent::new(EventKind::ToolCall, "shell")
+            .success(end.exit_code == 0)
+            .value(millis(end.duration)),
+        EventMsg::PatchApplyEnd(end) => {
+            LoggedEvent::new(EventKind::ToolCall, "apply_patch").success(end.success)
+        }
+        EventMsg::McpToolCallEnd(end) => LoggedEvent::new(
+            EventKind::ToolCall,
+            format!("mcp:{}", end.invocation.server),
+        )
+        .success(end.is_success())
+        .value(millis(end.duration)),
+        EventMsg::WebSearchEnd(_) => LoggedEvent::new(EventKind::ToolCall, "web_search"),
+        EventMsg::TokenCount(count) => {
+            let usage = &count.info.as_ref()?.last_token_usage;
+            LoggedEvent::new(EventKind::ModelRequest, model)
+                .value(i64::try_from(usage.total_tokens).unwrap_or(i64::MAX))
+        }
+        EventMsg::Error(err) => {
+            let category = err
+                .category
+                .and_then(|category| serde_json::to_value(category).ok())
+                .and_then(|category| category.as_str().map(str::to_string))
+                .unwrap_or_else(|| "uncategorized".to_string());
+            LoggedEvent::new(EventKind::Error, category)
+        }
+        EventMsg::StreamError(_) => LoggedEvent::new(EventKind::Error, "stream"),
+        EventMsg::TurnAborted(aborted) => {
+            let reason = serde_json::to_value(&aborted.reason)
+                .ok()
+                .and_then(|reason| reason.as_str().map(str::to_string))
+                .unwrap_or_default();
+            LoggedEvent::new(EventKind::TurnAborted, reason)
+        }
+        _ => return None,
+    };
+    Some(event)
+}
+
+/// Optional features that are on, by config name.
+fn enabled_features(config: &Config) -> Vec<&'static str> {
+    [
+        (config.include_plan_tool, "plan_tool"),
+        (config.tools_web_search_request, "web_search"),
+        (config.include_view_image_tool, "view_image"),
+        (config.include_fetch_url_tool, "fetch_url"),
+        (
+            config.use_experimental_streamable_shell_tool,
+            "streamable_shell",
+        ),
+        (config.use_experimental_unified_exec_tool, "unified_exec"),
+        (config.speculative_exec, "speculative_exec"),
+        (config.repo_map.enabled, "repo_map"),
+        (config.syntax_check.enabled, "syntax_check"),
+        (config.offline.is_some(), "offline"),
+        (config.read_only, "read_only"),
+        (!config.mcp_servers.is_empty(), "mcp_servers"),
+        (config.approval_timeout.is_some(), "approval_timeout"),
+    ]
+    .into_iter()
+    .filter_map(|(on, name)| on.then_some(name))
+    .collect()
+}
+
+fn open_and_prune(path: &Path, settings: &Telemetry) -> anyhow::Result<rusqlite::Connection> {
+    let conn = store::open(path)?;
+    let retention = Duration::from_secs(u64::from(settings.retention_days) * SECONDS_PER_DAY);
+    store::prune(&conn, SystemTime::now(), retention, settings.max_events)?;
+    Ok(conn)
+}
+
+fn millis(duration: Duration) -> i64 {
+    i64::try_from(duration.as_millis()).unwrap_or(i64::MAX)
+}
+
+fn events_db_path(codex_home: &Path) -> PathBuf {
+    codex_home.join(EVENTS_DB_FILENAME)
+}
+
+/// Aggregates over events recorded since `since`; `None` when nothing has
+/// been logged yet.
+pub fn summarize_event_log(
+    codex_home: &Path,
+    since: Option<SystemTime>,
+) -> anyhow::Result<Option<EventLogSummary>> {
+    match store::open_existing(&events_db_path(codex_home))? {
+        Some(conn) => Ok(Some(store::summarize(&conn, since)?)),
+        None => Ok(None),
+    }
+}
+
+/// Schema version and extent of the log; `None` when it does not exist.
+pub fn event_log_status(codex_home: &Path) -> anyhow::Result<Option<EventLogStatus>> {
+    match store::open_existing(&events_db_path(codex_home))? {
+        Some(conn) => Ok(Some(store::status(&conn)?)),
+        None => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::protocol::ErrorCategory;
+    use codex_protocol::protocol::ErrorEvent;
+    use codex_protocol::protocol::PatchApplyEndEvent;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn events_keep_only_what_happened() {
+        let patch = EventMsg::PatchApplyEnd(PatchApplyEndEvent {
+            call_id: "call-1".to_string(),
+            stdout: "Success. Updated src/secret_plans.rs".to_string(),
+            stderr: String::new(),
+            success: true,
+        });
+        assert_eq!(
+            event_for(&patch, "gpt-5"),
+            Some(LoggedEvent::new(EventKind::ToolCall, "apply_patch").success(true))
+        );
+
+        let error = EventMsg::Error(ErrorEvent {
+            message: "invalid api key sk-...".to_string(),
+            category: Some(ErrorCategory::Auth),
+        });
+        assert_eq!(
+            event_for(&error, "gpt-5"),
+            Some(LoggedEvent::new(EventKind::Error, "auth"))
+        );
+    }
+}
@@ -0,0 +1,387 @@
+//! SQLite storage of the event log: schema migrations, inserts, pruning and
+//! the aggregate queries behind `codex stats` and `codex doctor`.
+
+use std::path::Path;
+use std::time::Duration;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use rusqlite::Connection;
+use rusqlite::OptionalExtension;
+use rusqlite::params;
+
+use super::LoggedEvent;
+
+/// Schema migrations, applied in order. `PRAGMA user_version` holds the
+/// number applied so far; append new steps, never edit old ones.
+const MIGRATIONS: &[&str] = &[
+    // `AUTOINCREMENT` so ids of pruned rows are never reused, which the
+    // export cursor relies on.
+    "CREATE TABLE events (
+        id INTEGER PRIMARY KEY AUTOINCREMENT,
+        ts_ms INTEGER NOT NULL,
+        session TEXT NOT NULL,
+        kind TEXT NOT NULL,
+        name TEXT NOT NULL,
+        success INTEGER,
+        value INTEGER
+    );
+    CREATE INDEX events_ts ON events (ts_ms);
+    CREATE INDEX events_kind ON events (kind, name);",
+    "CREATE TABLE export_state (
+        endpoint TEXT PRIMARY KEY,
+        last_id INTEGER NOT NULL
+    );",
+];
+
+/// Open the database at `path`, creating it and bringing its schema up to
+/// date as needed.
+pub(super) fn open(path: &Path) -> anyhow::Result<Connection> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    let mut conn = Connection::open(path)?;
+    // Several sessions may write at once.
+    conn.busy_timeout(Duration::from_secs(5))?;
+    conn.pragma_update(None, "journal_mode", "WAL")?;
+    migrate(&mut conn)?;
+    Ok(conn)
+}
+
+/// Open an existing database without creating or migrating it.
+pub(super) fn open_existing(path: &Path) -> anyhow::Result<Option<Connection>> {
+    if !path.exists() {
+        return Ok(None);
+    }
+    let conn = Connection::open(path)?;
+    conn.busy_timeout(Duration::from_secs(5))?;
+    Ok(Some(conn))
+}
+
+fn migrate(conn: &mut Connection) -> anyhow::Result<()> {
+    let applied: usize = schema_version(conn)?.try_into()?;
+    if applied > MIGRATIONS.len() {
+        anyhow::bail!(
+            "events.db has schema version {applied}, newer than this version of Codex supports"
+        );
+    }
+    for (index, migration) in MIGRATIONS.iter().enumerate().skip(applied) {
+        let tx = conn.transaction()?;
+        tx.execute_batch(migration)?;
+        tx.pragma_update(None, "user_version", index + 1)?;
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+pub(super) fn schema_version(conn: &Connection) -> anyhow::Result<u32> {
+    Ok(conn.query_row("PRAGMA user_version", [], |row| row.get(0))?)
+}
+
+pub(super) fn insert(
+    conn: &mut Connection,
+    session: &str,
+    events: &[(SystemTime, LoggedEvent)],
+) -> anyhow::Result<()> {
+    let tx = conn.transaction()?;
+    {
+        let mut stmt = tx.prepare_cached(
+            "INSERT INTO events (ts_ms, session, kind, name, success, value)
+             VALUES (?1, ?2, ?3, ?4, ?5, ?6)",
+        )?;
+        for (at, event) in events {
+            stmt.execute(params![
+                unix_ms(*at),
+                session,
+                event.kind.as_str(),
+                event.name,
+                event.success,
+                event.value,
+            ])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
+
+/// Delete events older than `retention` and, beyond that, all but the
+/// newest `max_events`. Returns the number deleted.
+pub(super) fn prune(
+    conn: &Connection,
+    now: SystemTime,
+    retention: Duration,
+    max_events: u64,
+) -> anyhow::Result<usize> {
+    let cutoff = unix_ms(now.checked_sub(retention).unwrap_or(UNIX_EPOCH));
+    let mut deleted = conn.execute("DELETE FROM events WHERE ts_ms < ?1", params![cutoff])?;
+    deleted += conn.execute(
+        "DELETE FROM events WHERE id <= (
+            SELECT id FROM events ORDER BY id DESC LIMIT 1 OFFSET ?1
+        )",
+        params![i64::try_from(max_events).unwrap_or(i64::MAX)],
+    )?;
+    Ok(deleted)
+}
+
+/// A stored event with its id and time, as read back for export.
+#[derive(Debug, Clone, PartialEq)]
+pub(super) struct StoredEvent {
+    pub(super) id: i64,
+    pub(super) at: SystemTime,
+    pub(super) kind: String,
+    pub(super) name: String,
+    pub(super) success: Option<bool>,
+    pub(super) value: Option<i64>,
+}
+
+/// Up to `limit` events not yet exported to `endpoint`, oldest first.
+pub(super) fn pending_export(
+    conn: &Connection,
+    endpoint: &str,
+    limit: usize,
+) -> anyhow::Result<Vec<StoredEvent>> {
+    let last_id = export_cursor(conn, endpoint)?;
+    let mut stmt = conn.prepare(
+        "SELECT id, ts_ms, kind, name, success, value FROM events
+         WHERE id > ?1 ORDER BY id LIMIT ?2",
+    )?;
+    let rows = stmt.query_map(
+        params![last_id, i64::try_from(limit).unwrap_or(i64::MAX)],
+        |row| {
+            Ok(StoredEvent {
+                id: row.get(0)?,
+                at: from_unix_ms(row.get(1)?),
+                kind: row.get(2)?,
+                name: row.get(3)?,
+                success: row.get(4)?,
+                value: row.get(5)?,
+            })
+        },
+    )?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+pub(super) fn export_cursor(conn: &Connection, endpoint: &str) -> anyhow::Result<i64> {
+    Ok(conn
+        .query_row(
+            "SELECT last_id FROM export_state WHERE endpoint = ?1",
+            params![endpoint],
+            |row| row.get(0),
+        )
+        .optional()?
+        .unwrap_or(0))
+}
+
+pub(super) fn set_export_cursor(
+    conn: &Connection,
+    endpoint: &str,
+    last_id: i64,
+) -> anyhow::Result<()> {
+    conn.execute(
+        "INSERT INTO export_state (endpoint, last_id) VALUES (?1, ?2)
+         ON CONFLICT (endpoint) DO UPDATE SET last_id = excluded.last_id",
+        params![endpoint, last_id],
+    )?;
+    Ok(())
+}
+
+/// Calls and failures of one tool.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct ToolUsage {
+    pub name: String,
+    pub calls: u64,
+    pub failures: u64,
+    /// Mean duration of the calls that reported one.
+    pub avg_duration_ms: Option<f64>,
+}
+
+/// Aggregates over the events recorded in a period.
+#[derive(Debug, Clone, Default, PartialEq, serde::Serialize)]
+pub struct EventLogSummary {
+    pub sessions: u64,
+    /// Most used first.
+    pub tools: Vec<ToolUsage>,
+    /// (category, count), most frequent first.
+    pub errors: Vec<(String, u64)>,
+    /// (feature, sessions that had it on), most common first.
+    pub features: Vec<(String, u64)>,
+    /// (reason, count) of aborted turns.
+    pub aborted_turns: Vec<(String, u64)>,
+}
+
+pub(super) fn summarize(
+    conn: &Connection,
+    since: Option<SystemTime>,
+) -> anyhow::Result<EventLogSummary> {
+    let since = since.map(unix_ms).unwrap_or(0);
+    let sessions = conn.query_row(
+        "SELECT COUNT(*) FROM events WHERE kind = 'session_start' AND ts_ms >= ?1",
+        params![since],
+        |row| row.get::<_, i64>(0),
+    )?;
+
+    let mut stmt = conn.prepare(
+        "SELECT name, COUNT(*), SUM(success = 0), AVG(value) FROM events
+         WHERE kind = 'tool_call' AND ts_ms >= ?1
+         GROUP BY name ORDER BY COUNT(*) DESC, name",
+    )?;
+    let tools = stmt
+        .query_map(params![since], |row| {
+            Ok(ToolUsage {
+                name: row.get(0)?,
+                calls: count(row.get(1)?),
+                failures: count(row.get::<_, Option<i64>>(2)?.unwrap_or(0)),
+                avg_duration_ms: row.get(3)?,
+            })
+        })?
+        .collect::<Result<_, _>>()?;
+
+    Ok(EventLogSummary {
+        sessions: count(sessions),
+        tools,
+        errors: counts_by_name(conn, "error", since)?,
+        features: counts_by_name(conn, "feature", since)?,
+        aborted_turns: counts_by_name(conn, "turn_aborted", since)?,
+    })
+}
+
+fn counts_by_name(conn: &Connection, kind: &str, since: i64) -> anyhow::Result<Vec<(String, u64)>> {
+    let mut stmt = conn.prepare(
+        "SELECT name, COUNT(*) FROM events WHERE kind = ?1 AND ts_ms >= ?2
+         GROUP BY name ORDER BY COUNT(*) DESC, name",
+    )?;
+    let rows = stmt.query_map(params![kind, since], |row| {
+        Ok((row.get::<_, String>(0)?, count(row.get(1)?)))
+    })?;
+    Ok(rows.collect::<Result<_, _>>()?)
+}
+
+/// Size and span of the log, for `codex doctor`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct EventLogStatus {
+    pub schema_version: u32,
+    pub events: u64,
+    pub oldest: Option<SystemTime>,
+    pub newest: Option<SystemTime>,
+}
+
+pub(super) fn status(conn: &Connection) -> anyhow::Result<EventLogStatus> {
+    let schema_version = schema_version(conn)?;
+    if schema_version == 0 {
+        return Ok(EventLogStatus {
+            schema_version,
+            events: 0,
+            oldest: None,
+            newest: None,
+        });
+    }
+    let (events, oldest, newest) = conn.query_row(
+        "SELECT COUNT(*), MIN(ts_ms), MAX(ts_ms) FROM events",
+        [],
+        |row| {
+            Ok((
+                row.get::<_, i64>(0)?,
+                row.get::<_, Option<i64>>(1)?,
+                row.get::<_, Option<i64>>(2)?,
+            ))
+        },
+    )?;
+    Ok(EventLogStatus {
+        schema_version,
+        events: count(events),
+        oldest: oldest.map(from_unix_ms),
+        newest: newest.map(from_unix_ms),
+    })
+}
+
+fn count(n: i64) -> u64 {
+    u64::try_from(n).unwrap_or(0)
+}
+
+fn unix_ms(at: SystemTime) -> i64 {
+    at.duration_since(UNIX_EPOCH)
+        .map(|d| i64::try_from(d.as_millis()).unwrap_or(i64::MAX))
+        .unwrap_or(0)
+}
+
+fn from_unix_ms(ms: i64) -> SystemTime {
+    UNIX_EPOCH + Duration::from_millis(u64::try_from(ms).unwrap_or(0))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event_log::EventKind;
+    use pretty_assertions::assert_eq;
+
+    fn tool_call(name: &str, success: bool, duration_ms: i64) -> LoggedEvent {
+        LoggedEvent {
+            kind: EventKind::ToolCall,
+            name: name.to_string(),
+            success: Some(success),
+            value: Some(duration_ms),
+        }
+    }
+
+    #[test]
+    fn migrates_inserts_prunes_and_summarizes() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let path = dir.path().join("events.db");
+        let mut conn = open(&path).expect("open");
+        assert_eq!(schema_version(&conn).expect("version"), 2);
+        drop(conn);
+        // Reopening an up-to-date database is a no-op.
+        conn = open(&path).expect("reopen");
+
+        let now = SystemTime::now();
+        let old = now - Duration::from_secs(100 * 24 * 60 * 60);
+        let session_start = LoggedEvent {
+            kind: EventKind::SessionStart,
+            name: "gpt-5".to_string(),
+            success: None,
+            value: None,
+        };
+        insert(
+            &mut conn,
+            "s1",
+            &[
+                (old, session_start.clone()),
+                (old, tool_call("shell", true, 10)),
+                (now, session_start),
+                (now, tool_call("shell", true, 10)),
+                (now, tool_call("shell", false, 30)),
+                (now, tool_call("apply_patch", true, 5)),
+            ],
+        )
+        .expect("insert");
+
+        let retention = Duration::from_secs(90 * 24 * 60 * 60);
+        assert_eq!(prune(&conn, now, retention, 3).expect("prune"), 3);
+        let summary = summarize(&conn, None).expect("summarize");
+        assert_eq!(
+            summary.tools,
+            vec![
+                ToolUsage {
+                    name: "shell".to_string(),
+                    calls: 2,
+                    failures: 1,
+                    avg_duration_ms: Some(20.0),
+                },
+                ToolUsage {
+                    name: "apply_patch".to_string(),
+                    calls: 1,
+                    failures: 0,
+                    avg_duration_ms: Some(5.0),
+                },
+            ]
+        );
+        assert_eq!(summary.sessions, 0);
+
+        let pending = pending_export(&conn, "https://example.com", 10).expect("pending");
+        assert_eq!(pending.len(), 3);
+        set_export_cursor(&conn, "https://example.com", pending[1].id).expect("cursor");
+        let pending = pending_export(&conn, "https://example.com", 10).expect("pending");
+        assert_eq!(pending.len(), 1);
+        assert_eq!(pending[0].name, "apply_patch");
+    }
+}
@@ -39,6 +39,10 @@ pub(crate) fn map_response_item_to_event_messages(
                                 Some(InputMessageKind::EnvironmentContext)
                             } else if trimmed.starts_with("<user_instructions>") {
                                 Some(InputMessageKind::UserInstructions)
+                            } else if trimmed.starts_with("<repo_map>") {
+                                Some(InputMessageKind::RepoMap)
+                            } else if trimmed.starts_with("<file_changes>") {
+                                Some(InputMessageKind::FileChanges)
                             } else {
                                 Some(InputMessageKind::Plain)
                             };
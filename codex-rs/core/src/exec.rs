@@ -25,6 +25,7 @@ use crate::protocol::ExecOutputStream;
 use crate::protocol::SandboxPolicy;
 use crate::seatbelt::spawn_command_under_seatbelt;
 use crate::spawn::StdioPolicy;
+use crate::spawn::kill_child_process_group;
 use crate::spawn::spawn_child_async;
 
 const DEFAULT_TIMEOUT_MS: u64 = 10_000;
@@ -283,6 +284,11 @@ async fn consume_truncated_output(
         ))
     })?;
 
+    // If this future is dropped before the child exits (e.g., because the
+    // turn was interrupted and the task running it was aborted), make sure the
+    // whole process group goes away rather than only the direct child.
+    let mut process_group = ProcessGroupGuard::new(child.id());
+
     let (agg_tx, agg_rx) = async_channel::unbounded::<Vec<u8>>();
 
     let stdout_handle = tokio::spawn(read_capped(
@@ -301,10 +307,16 @@ async fn consume_truncated_output(
     let exit_status = tokio::select! {
         result = tokio::time::timeout(timeout, child.wait()) => {
             match result {
-                Ok(Ok(exit_status)) => exit_status,
+                Ok(Ok(exit_status)) => {
+                    // Background jobs the command left behind are not ours
+                    // to clean up once it has exited on its own.
+                    process_group.disarm();
+                    exit_status
+                }
                 Ok(e) => e?,
                 Err(_) => {
                     // timeout
+                    process_group.kill()?;
                     child.start_kill()?;
                     // Debatable whether `child.wait().await` should be called here.
                     synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + TIMEOUT_CODE)
@@ -312,6 +324,7 @@ async fn consume_truncated_output(
             }
         }
         _ = tokio::signal::ctrl_c() => {
+            process_group.kill()?;
             child.start_kill()?;
             synthetic_exit_status(EXIT_CODE_SIGNAL_BASE + SIGKILL_CODE)
         }
@@ -339,6 +352,37 @@ async fn consume_truncated_output(
     })
 }
 
+/// Kills the process group of a child spawned with
+/// `StdioPolicy::RedirectForShellTool` when dropped, unless disarmed first.
+struct ProcessGroupGuard {
+    pid: Option<u32>,
+}
+
+impl ProcessGroupGuard {
+    fn new(pid: Option<u32>) -> Self {
+        Self { pid }
+    }
+
+    fn disarm(&mut self) {
+        self.pid = None;
+    }
+
+    fn kill(&mut self) -> io::Result<()> {
+        match self.pid.take() {
+            Some(pid) => kill_child_process_group(pid),
+            None => Ok(()),
+        }
+    }
+}
+
+impl Drop for ProcessGroupGuard {
+    fn drop(&mut self) {
+        if let Err(e) = self.kill() {
+            tracing::warn!("failed to kill process group: {e}");
+        }
+    }
+}
+
 async fn read_capped<R: AsyncRead + Unpin + Send + 'static>(
     mut reader: R,
     stream: Option<StdoutStream>,
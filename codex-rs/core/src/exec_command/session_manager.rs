@@ -20,13 +20,21 @@ use crate::exec_command::exec_command_params::ExecCommandParams;
 use crate::exec_command::exec_command_params::WriteStdinParams;
 use crate::exec_command::exec_command_session::ExecCommandSession;
 use crate::exec_command::session_id::SessionId;
-use crate::truncate::truncate_middle;
+use crate::token_counter::TokenCounter;
+use crate::truncate::truncate_middle_to_tokens;
 use codex_protocol::models::FunctionCallOutputPayload;
 
 #[derive(Debug, Default)]
 pub struct SessionManager {
     next_session_id: AtomicU32,
-    sessions: Mutex<HashMap<SessionId, ExecCommandSession>>,
+    sessions: Mutex<HashMap<SessionId, TurnSession>>,
+}
+
+/// A session and the turn (submission id) that opened it.
+#[derive(Debug)]
+struct TurnSession {
+    sub_id: String,
+    session: ExecCommandSession,
 }
 
 #[derive(Debug)]
@@ -83,9 +91,12 @@ pub fn result_into_payload(result: Result<ExecCommandOutput, String>) -> Functio
 
 impl SessionManager {
     /// Processes the request and is required to send a response via `outgoing`.
+    /// The shell is started with exactly the variables in `env`.
     pub async fn handle_exec_command_request(
         &self,
         params: ExecCommandParams,
+        env: HashMap<String, String>,
+        sub_id: &str,
     ) -> Result<ExecCommandOutput, String> {
         // Allocate a session id.
         let session_id = SessionId(
@@ -93,25 +104,29 @@ impl SessionManager {
                 .fetch_add(1, std::sync::atomic::Ordering::SeqCst),
         );
 
-        let (session, mut exit_rx) =
-            create_exec_command_session(params.clone())
-                .await
-                .map_err(|err| {
-                    format!(
-                        "failed to create exec command session for session id {}: {err}",
-                        session_id.0
-                    )
-                })?;
+        let (session, mut exit_rx) = create_exec_command_session(params.clone(), env)
+            .await
+            .map_err(|err| {
+                format!(
+                    "failed to create exec command session for session id {}: {err}",
+                    session_id.0
+                )
+            })?;
 
         // Insert into session map.
         let mut output_rx = session.output_receiver();
-        self.sessions.lock().await.insert(session_id, session);
+        self.sessions.lock().await.insert(
+            session_id,
+            TurnSession {
+                sub_id: sub_id.to_string(),
+                session,
+            },
+        );
 
         // Collect output until either timeout expires or process exits.
         // Do not cap during collection; truncate at the end if needed.
         // Use a modest initial capacity to avoid large preallocation.
-        let cap_bytes_u64 = params.max_output_tokens.saturating_mul(4);
-        let cap_bytes: usize = cap_bytes_u64.min(usize::MAX as u64) as usize;
+        let max_output_tokens = params.max_output_tokens;
         let mut collected: Vec<u8> = Vec::with_capacity(4096);
 
         let start_time = Instant::now();
@@ -167,8 +182,9 @@ impl SessionManager {
             ExitStatus::Ongoing(session_id)
         };
 
-        // If output exceeds cap, truncate the middle and record original token estimate.
-        let (output, original_token_count) = truncate_middle(&output, cap_bytes);
+        // If output exceeds cap, truncate the middle and record original token count.
+        let (output, original_token_count) =
+            truncate_middle_to_tokens(&output, max_output_tokens, &TokenCounter::default());
         Ok(ExecCommandOutput {
             wall_time: Instant::now().duration_since(start_time),
             exit_status,
@@ -177,6 +193,22 @@ impl SessionManager {
         })
     }
 
+    /// Kill and forget the sessions opened by turn `sub_id`, leaving those of
+    /// other turns running. Returns how many were still alive.
+    pub async fn terminate_turn(&self, sub_id: &str) -> usize {
+        let mut sessions = self.sessions.lock().await;
+        let ids: Vec<SessionId> = sessions
+            .iter()
+            .filter(|(_, s)| s.sub_id == sub_id)
+            .map(|(id, _)| *id)
+            .collect();
+        // Dropping an `ExecCommandSession` kills its child and closes the PTY.
+        ids.iter()
+            .filter_map(|id| sessions.remove(id))
+            .filter(|s| !s.session.has_exited())
+            .count()
+    }
+
     /// Write characters to a session's stdin and collect combined output for up to `yield_time_ms`.
     pub async fn handle_write_stdin_request(
         &self,
@@ -193,7 +225,7 @@ impl SessionManager {
         let (writer_tx, mut output_rx) = {
             let sessions = self.sessions.lock().await;
             match sessions.get(&session_id) {
-                Some(session) => (session.writer_sender(), session.output_receiver()),
+                Some(s) => (s.session.writer_sender(), s.session.output_receiver()),
                 None => {
                     return Err(format!("unknown session id {}", session_id.0));
                 }
@@ -205,7 +237,7 @@ impl SessionManager {
             return Err("failed to write to stdin".to_string());
         }
 
-        // Collect output up to yield_time_ms, truncating to max_output_tokens bytes.
+        // Collect output up to yield_time_ms, truncating to max_output_tokens tokens.
         let mut collected: Vec<u8> = Vec::with_capacity(4096);
         let start_time = Instant::now();
         let deadline = start_time + Duration::from_millis(yield_time_ms);
@@ -230,9 +262,8 @@ impl SessionManager {
 
         // Return structured output, truncating middle if over cap.
         let output = String::from_utf8_lossy(&collected).to_string();
-        let cap_bytes_u64 = max_output_tokens.saturating_mul(4);
-        let cap_bytes: usize = cap_bytes_u64.min(usize::MAX as u64) as usize;
-        let (output, original_token_count) = truncate_middle(&output, cap_bytes);
+        let (output, original_token_count) =
+            truncate_middle_to_tokens(&output, max_output_tokens, &TokenCounter::default());
         Ok(ExecCommandOutput {
             wall_time: Instant::now().duration_since(start_time),
             exit_status: ExitStatus::Ongoing(session_id),
@@ -245,6 +276,7 @@ impl SessionManager {
 /// Spawn PTY and child process per spawn_exec_command_session logic.
 async fn create_exec_command_session(
     params: ExecCommandParams,
+    env: HashMap<String, String>,
 ) -> anyhow::Result<(ExecCommandSession, oneshot::Receiver<i32>)> {
     let ExecCommandParams {
         cmd,
@@ -270,6 +302,10 @@ async fn create_exec_command_session(
     let shell_mode_opt = if login { "-lc" } else { "-c" };
     command_builder.arg(shell_mode_opt);
     command_builder.arg(cmd);
+    command_builder.env_clear();
+    for (key, value) in env {
+        command_builder.env(key, value);
+    }
 
     let mut child = pair.slave.spawn_command(command_builder)?;
     // Obtain a killer that can signal the process independently of `.wait()`.
@@ -393,7 +429,7 @@ PY"#
             login: false,
         };
         let initial_output = match session_manager
-            .handle_exec_command_request(params.clone())
+            .handle_exec_command_request(params.clone(), std::env::vars().collect(), "1")
             .await
         {
             Ok(v) => v,
@@ -431,17 +467,16 @@ PY"#
             session_id,
             chars: String::new(),
             yield_time_ms: 3_000,
-            max_output_tokens: 16, // 16 tokens ~= 64 bytes -> likely truncation
+            max_output_tokens: 16, // small enough to force truncation
         };
         let second = session_manager
             .handle_write_stdin_request(write_params)
             .await
             .expect("write stdin should succeed");
 
-        // Verify truncation metadata and size bound (cap is tokens*4 bytes).
+        // Verify truncation metadata and size bound (cap is measured in tokens).
         assert!(second.original_token_count.is_some());
-        let cap_bytes = (16u64 * 4) as usize;
-        assert!(second.output.len() <= cap_bytes);
+        assert!(TokenCounter::default().count(&second.output) <= 16);
         // New middle marker should be present.
         assert!(
             second.output.contains("tokens truncated") && second.output.contains('…'),
@@ -0,0 +1,753 @@
+//! The `fetch_url` tool: download a page, keep its main content as markdown
+//! and cut it down to the configured budget, so the model can read
+//! documentation without piping raw HTML through `curl`.
+
+use std::net::IpAddr;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+
+use futures::StreamExt;
+use reqwest::Url;
+use reqwest::dns::Addrs;
+use reqwest::dns::Name;
+use reqwest::dns::Resolve;
+use reqwest::dns::Resolving;
+use reqwest::header::CONTENT_TYPE;
+
+use crate::config_types::FetchUrl;
+use crate::default_client::create_client_builder;
+use crate::offline::ensure_network_allowed;
+use crate::token_counter::TokenCounter;
+
+const FETCH_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// Redirects followed before giving up.
+const MAX_REDIRECTS: usize = 10;
+
+/// Bodies larger than this are cut off while downloading.
+const MAX_DOWNLOAD_BYTES: usize = 5 * 1024 * 1024;
+
+/// The agent token matched against robots.txt `User-agent` lines.
+const ROBOTS_AGENT: &str = "codex";
+
+/// Elements whose content is navigation, page chrome or script rather than text.
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "script", "style", "noscript", "template", "svg", "iframe", "nav", "header", "footer", "aside",
+    "form", "button", "head",
+];
+
+/// Fetch `url` and return its text for the model, or an explanation of why
+/// it was not fetched.
+pub(crate) async fn fetch_url(
+    url: &str,
+    settings: &FetchUrl,
+    counter: &TokenCounter,
+) -> Result<String, String> {
+    let url = Url::parse(url).map_err(|err| format!("invalid URL {url}: {err}"))?;
+    check_url(&url, settings)?;
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+
+    // Every redirect is checked like the original URL, so an allowed host
+    // cannot bounce the request to a denied one, a private address or out of
+    // offline mode. Names are resolved by `PublicOnlyResolver`, which applies
+    // the same address check to whatever a hostname points at.
+    let redirect_settings = settings.clone();
+    let client = create_client_builder()
+        .dns_resolver(Arc::new(PublicOnlyResolver {
+            allow_hosts: settings.allow_hosts.clone(),
+        }))
+        .redirect(reqwest::redirect::Policy::custom(move |attempt| {
+            if attempt.previous().len() >= MAX_REDIRECTS {
+                return attempt.error(format!("more than {MAX_REDIRECTS} redirects"));
+            }
+            match check_url(attempt.url(), &redirect_settings) {
+                Ok(()) => attempt.follow(),
+                Err(err) => attempt.error(err),
+            }
+        }))
+        .build()
+        .map_err(|err| format!("failed to create HTTP client: {err}"))?;
+    if settings.respect_robots_txt && !robots_txt_allows(&client, &url).await {
+        return Err(format!("robots.txt for {host} disallows fetching {url}"));
+    }
+
+    let response = client
+        .get(url.clone())
+        .timeout(FETCH_TIMEOUT)
+        .send()
+        .await
+        .map_err(|err| match std::error::Error::source(&err) {
+            // Say why a redirect was refused, not just that it was.
+            Some(reason) if err.is_redirect() => format!("failed to fetch {url}: {reason}"),
+            _ => format!("failed to fetch {url}: {err}"),
+        })?;
+    let status = response.status();
+    if !status.is_success() {
+        return Err(format!("fetching {url} returned {status}"));
+    }
+    let final_url = response.url().clone();
+    let content_type = response
+        .headers()
+        .get(CONTENT_TYPE)
+        .and_then(|value| value.to_str().ok())
+        .unwrap_or("text/html")
+        .to_ascii_lowercase();
+    let is_html = content_type.contains("html");
+    if !is_html
+        && !content_type.starts_with("text/")
+        && !content_type.contains("json")
+        && !content_type.contains("xml")
+    {
+        return Err(format!(
+            "{url} is {content_type}, which fetch_url cannot read"
+        ));
+    }
+
+    let body = read_capped(response).await?;
+    let (title, text) = if is_html {
+        (html_title(&body), html_to_markdown(&body))
+    } else {
+        (None, body)
+    };
+
+    let mut out = format!("URL: {final_url}\n");
+    if let Some(title) = title {
+        out.push_str(&format!("Title: {title}\n"));
+    }
+    out.push('\n');
+    out.push_str(&truncate_to_budget(
+        text.trim(),
+        settings.max_tokens as u64,
+        counter,
+    ));
+    Ok(out)
+}
+
+/// Refuse URLs that are not http(s), hosts excluded by the settings,
+/// loopback, link-local and private addresses that are not in
+/// `allow_hosts`, and anything offline mode does not allow.
+fn check_url(url: &Url, settings: &FetchUrl) -> Result<(), String> {
+    if !matches!(url.scheme(), "http" | "https") {
+        return Err(format!(
+            "only http and https URLs can be fetched, not {url}"
+        ));
+    }
+    let host = url.host_str().unwrap_or_default().to_ascii_lowercase();
+    if settings
+        .deny_hosts
+        .iter()
+        .any(|denied| host_matches(&host, denied))
+    {
+        return Err(format!("{host} is in fetch_url.deny_hosts"));
+    }
+    if !settings.allow_hosts.is_empty()
+        && !settings
+            .allow_hosts
+            .iter()
+            .any(|allowed| host_matches(&host, allowed))
+    {
+        return Err(format!("{host} is not in fetch_url.allow_hosts"));
+    }
+    // Names are checked once resolved; literal addresses are never resolved.
+    let literal = host.trim_start_matches('[').trim_end_matches(']');
+    if let Ok(ip) = literal.parse::<IpAddr>()
+        && !is_public(ip)
+        && !allow_listed(&host, &settings.allow_hosts)
+    {
+        return Err(private_address_error(&host));
+    }
+    ensure_network_allowed(url.as_str()).map_err(|err| err.to_string())?;
+    Ok(())
+}
+
+async fn read_capped(response: reqwest::Response) -> Result<String, String> {
+    let mut body = Vec::new();
+    let mut stream = response.bytes_stream();
+    while let Some(chunk) = stream.next().await {
+        let chunk = chunk.map_err(|err| format!("failed to read response: {err}"))?;
+        body.extend_from_slice(&chunk);
+        if body.len() >= MAX_DOWNLOAD_BYTES {
+            body.truncate(MAX_DOWNLOAD_BYTES);
+            break;
+        }
+    }
+    Ok(String::from_utf8_lossy(&body).into_owned())
+}
+
+fn allow_listed(host: &str, allow_hosts: &[String]) -> bool {
+    allow_hosts
+        .iter()
+        .any(|allowed| host_matches(host, allowed))
+}
+
+fn private_address_error(host: &str) -> String {
+    format!(
+        "{host} is a loopback, link-local or private address; add it to fetch_url.allow_hosts to fetch it"
+    )
+}
+
+/// Whether `ip` is reachable on the public internet. Everything else
+/// (loopback, private ranges, link-local including cloud metadata services,
+/// shared, benchmarking, documentation and multicast ranges) is refused.
+fn is_public(ip: IpAddr) -> bool {
+    match ip {
+        IpAddr::V4(ip) => {
+            let [a, b, ..] = ip.octets();
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_private()
+                || ip.is_link_local()
+                || ip.is_broadcast()
+                || ip.is_documentation()
+                || ip.is_multicast()
+                || a == 0
+                || (a == 100 && (64..128).contains(&b))
+                || (a == 198 && (18..20).contains(&b))
+                || a >= 240)
+        }
+        IpAddr::V6(ip) => {
+            if let Some(v4) = ip.to_ipv4_mapped() {
+                return is_public(IpAddr::V4(v4));
+            }
+            let first = ip.segments()[0];
+            !(ip.is_unspecified()
+                || ip.is_loopback()
+                || ip.is_multicast()
+                || (first & 0xfe00) == 0xfc00
+                || (first & 0xffc0) == 0xfe80
+                || first == 0x2001 && ip.segments()[1] == 0x0db8)
+        }
+    }
+}
+
+/// Resolves hostnames for `fetch_url` and drops addresses that are not
+/// public, so a name pointing at `127.0.0.1` or a metadata service is
+/// refused like the literal address would be.
+struct PublicOnlyResolver {
+    allow_hosts: Vec<String>,
+}
+
+impl Resolve for PublicOnlyResolver {
+    fn resolve(&self, name: Name) -> Resolving {
+        let host = name.as_str().to_ascii_lowercase();
+        let allowed = allow_listed(&host, &self.allow_hosts);
+        Box::pin(async move {
+            let addrs: Vec<SocketAddr> = tokio::net::lookup_host((host.as_str(), 0))
+                .await?
+                .filter(|addr| allowed || is_public(addr.ip()))
+                .collect();
+            if addrs.is_empty() {
+                return Err(private_address_error(&host).into());
+            }
+            let addrs: Addrs = Box::new(addrs.into_iter());
+            Ok(addrs)
+        })
+    }
+}
+
+/// `host` is `pattern` or one of its subdomains.
+fn host_matches(host: &str, pattern: &str) -> bool {
+    let pattern = pattern.trim().trim_start_matches("*.").to_ascii_lowercase();
+    host == pattern || host.ends_with(&format!(".{pattern}"))
+}
+
+/// A robots.txt that is missing or cannot be read allows everything.
+async fn robots_txt_allows(client: &reqwest::Client, url: &Url) -> bool {
+    let mut robots_url = url.clone();
+    robots_url.set_path("/robots.txt");
+    robots_url.set_query(None);
+    robots_url.set_fragment(None);
+    let Ok(response) = client.get(robots_url).timeout(FETCH_TIMEOUT).send().await else {
+        return true;
+    };
+    if !response.status().is_success() {
+        return true;
+    }
+    let Ok(robots) = response.text().await else {
+        return true;
+    };
+    let mut path = url.path().to_string();
+    if let Some(query) = url.query() {
+        path.push('?');
+        path.push_str(query);
+    }
+    robots_allows(&robots, &path)
+}
+
+/// Apply the rules from the group for our agent, or the `*` group if there
+/// is none. The longest matching rule wins and `Allow` wins ties.
+fn robots_allows(robots: &str, path: &str) -> bool {
+    // (agents, rules) per group; a rule is (allow, prefix).
+    let mut groups: Vec<(Vec<String>, Vec<(bool, String)>)> = Vec::new();
+    let mut in_agent_lines = false;
+    for line in robots.lines() {
+        let line = line.split('#').next().unwrap_or("").trim();
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        let value = value.trim();
+        match key.trim().to_ascii_lowercase().as_str() {
+            "user-agent" => {
+                if !in_agent_lines || groups.is_empty() {
+                    groups.push((Vec::new(), Vec::new()));
+                }
+                if let Some((agents, _)) = groups.last_mut() {
+                    agents.push(value.to_ascii_lowercase());
+                }
+                in_agent_lines = true;
+            }
+            rule @ ("allow" | "disallow") => {
+                in_agent_lines = false;
+                if let Some((_, rules)) = groups.last_mut() {
+                    rules.push((rule == "allow", value.trim_end_matches('*').to_string()));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    let rules = groups
+        .iter()
+        .find(|(agents, _)| agents.iter().any(|agent| agent == ROBOTS_AGENT))
+        .or_else(|| {
+            groups
+                .iter()
+                .find(|(agents, _)| agents.iter().any(|agent| agent == "*"))
+        })
+        .map(|(_, rules)| rules.as_slice())
+        .unwrap_or_default();
+    rules
+        .iter()
+        .filter(|(_, prefix)| !prefix.is_empty() && path.starts_with(prefix.as_str()))
+        .max_by_key(|(allow, prefix)| (prefix.len(), *allow))
+        .is_none_or(|(allow, _)| *allow)
+}
+
+/// Keep the start of `text` within `max_tokens`, cutting on a line boundary
+/// where possible, and say how many tokens were left out.
+fn truncate_to_budget(text: &str, max_tokens: u64, counter: &TokenCounter) -> String {
+    let total = counter.count(text);
+    if total <= max_tokens {
+        return text.to_string();
+    }
+    // Longest prefix that fits the budget; token counts grow with the prefix.
+    let (mut lo, mut hi) = (0, text.len());
+    while lo < hi {
+        let mut mid = (lo + hi).div_ceil(2);
+        while !text.is_char_boundary(mid) {
+            mid -= 1;
+        }
+        if mid <= lo {
+            break;
+        }
+        if counter.count(&text[..mid]) <= max_tokens {
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    let mut end = lo;
+    while !text.is_char_boundary(end) {
+        end -= 1;
+    }
+    if let Some(newline) = text[..end].rfind('\n') {
+        end = newline;
+    }
+    let kept = &text[..end];
+    format!(
+        "{kept}\n\n[truncated: {} more tokens]",
+        total.saturating_sub(counter.count(kept))
+    )
+}
+
+fn html_title(html: &str) -> Option<String> {
+    let lower = html.to_ascii_lowercase();
+    let start = lower.find("<title")?;
+    let start = start + lower[start..].find('>')? + 1;
+    let end = start + lower[start..].find("</title")?;
+    let title = collapse_whitespace(&decode_entities(&html[start..end]));
+    (!title.is_empty()).then_some(title)
+}
+
+/// The part of the page most likely to be its content: the first `<main>`,
+/// else the first `<article>`, else `<body>`, else everything.
+fn main_content(html: &str) -> &str {
+    let lower = html.to_ascii_lowercase();
+    for tag in ["main", "article", "body"] {
+        let Some(open) = lower.find(&format!("<{tag}")) else {
+            continue;
+        };
+        let Some(content_start) = lower[open..].find('>').map(|i| open + i + 1) else {
+            continue;
+        };
+        let content_end = lower[content_start..]
+            .rfind(&format!("</{tag}"))
+            .map_or(html.len(), |i| content_start + i);
+        return &html[content_start..content_end];
+    }
+    html
+}
+
+/// A small HTML to markdown conversion: headings, paragraphs, lists, links,
+/// emphasis and code survive; navigation, scripts and styling do not.
+fn html_to_markdown(html: &str) -> String {
+    let html = main_content(html);
+    let mut out = String::new();
+    let mut skip: Option<(String, usize)> = None;
+    let mut pre_depth = 0usize;
+    let mut links: Vec<Option<String>> = Vec::new();
+    let mut rest = html;
+
+    while !rest.is_empty() {
+        let Some(lt) = rest.find('<') else {
+            push_text(&mut out, rest, pre_depth > 0, skip.is_some());
+            break;
+        };
+        push_text(&mut out, &rest[..lt], pre_depth > 0, skip.is_some());
+        rest = &rest[lt..];
+
+        if let Some(comment) = rest.strip_prefix("<!--") {
+            rest = comment.find("-->").map_or("", |end| &comment[end + 3..]);
+            continue;
+        }
+        let Some(gt) = rest.find('>') else {
+            push_text(&mut out, rest, pre_depth > 0, skip.is_some());
+            break;
+        };
+        let tag = &rest[1..gt];
+        rest = &rest[gt + 1..];
+
+        let closing = tag.starts_with('/');
+        let tag_body = tag.trim_start_matches('/');
+        let name: String = tag_body
+            .chars()
+            .take_while(|c| c.is_ascii_alphanumeric())
+            .collect::<String>()
+            .to_ascii_lowercase();
+        if name.is_empty() {
+            continue;
+        }
+
+        if let Some((skipped, depth)) = skip.as_mut() {
+            if *skipped == name {
+                if closing {
+                    *depth -= 1;
+                    if *depth == 0 {
+                        skip = None;
+                    }
+                } else if !tag.ends_with('/') {
+                    *depth += 1;
+                }
+            }
+            continue;
+        }
+        if !closing && SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            if !tag.ends_with('/') {
+                skip = Some((name, 1));
+            }
+            continue;
+        }
+
+        match (name.as_str(), closing) {
+            ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", false) => {
+                let level = name[1..].parse::<usize>().unwrap_or(1);
+                start_block(&mut out);
+                out.push_str(&"#".repeat(level));
+                out.push(' ');
+            }
+            ("p" | "div" | "section" | "table" | "ul" | "ol" | "dl" | "blockquote", _)
+            | ("h1" | "h2" | "h3" | "h4" | "h5" | "h6", true) => start_block(&mut out),
+            ("br", _) | ("tr", false) | ("dt" | "dd", false) => start_line(&mut out),
+            ("li", false) => {
+                start_line(&mut out);
+                out.push_str("- ");
+            }
+            ("td" | "th", false) => {
+                if !out.ends_with('\n') && !out.is_empty() {
+                    out.push_str(" | ");
+                }
+            }
+            ("pre", false) => {
+                start_block(&mut out);
+                out.push_str("```\n");
+                pre_depth += 1;
+            }
+            ("pre", true) => {
+                if pre_depth > 0 {
+                    pre_depth -= 1;
+                    start_line(&mut out);
+                    out.push_str("```");
+                    start_block(&mut out);
+                }
+            }
+            ("code", _) if pre_depth == 0 => out.push('`'),
+            ("strong" | "b", _) => out.push_str("**"),
+            ("em" | "i", _) => out.push('_'),
+            ("a", false) => {
+                let href = attribute(tag_body, "href")
+                    .filter(|href| !href.starts_with('#') && !href.starts_with("javascript:"));
+                if href.is_some() {
+                    out.push('[');
+                }
+                links.push(href);
+            }
+            ("a", true) => {
+                if let Some(Some(href)) = links.pop() {
+                    out.push_str(&format!("]({href})"));
+                }
+            }
+            ("img", false) => {
+                if let Some(alt) = attribute(tag_body, "alt").filter(|alt| !alt.is_empty()) {
+                    out.push_str(&format!("[image: {alt}]"));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    tidy_markdown(&out)
+}
+
+fn push_text(out: &mut String, text: &str, preformatted: bool, skipping: bool) {
+    if skipping || text.is_empty() {
+        return;
+    }
+    let text = decode_entities(text);
+    if preformatted {
+        out.push_str(&text);
+        return;
+    }
+    let collapsed = collapse_whitespace(&text);
+    if collapsed.is_empty() {
+        if !out.ends_with([' ', '\n']) && !out.is_empty() {
+            out.push(' ');
+        }
+        return;
+    }
+    if text.starts_with(char::is_whitespace) && !out.ends_with([' ', '\n']) && !out.is_empty() {
+        out.push(' ');
+    }
+    out.push_str(&collapsed);
+    if text.ends_with(char::is_whitespace) {
+        out.push(' ');
+    }
+}
+
+fn start_line(out: &mut String) {
+    let trimmed = out.trim_end_matches(' ').len();
+    out.truncate(trimmed);
+    if !out.is_empty() && !out.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+fn start_block(out: &mut String) {
+    start_line(out);
+    if !out.is_empty() && !out.ends_with("\n\n") {
+        out.push('\n');
+    }
+}
+
+/// Trim trailing spaces and squeeze runs of blank lines.
+fn tidy_markdown(text: &str) -> String {
+    let mut out = String::new();
+    let mut blank_lines = 0;
+    for line in text.lines() {
+        let line = line.trim_end();
+        if line.is_empty() {
+            blank_lines += 1;
+            if blank_lines > 1 {
+                continue;
+            }
+        } else {
+            blank_lines = 0;
+        }
+        out.push_str(line);
+        out.push('\n');
+    }
+    out.trim().to_string()
+}
+
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let lower = tag.to_ascii_lowercase();
+    let mut search_from = 0;
+    while let Some(found) = lower[search_from..].find(name) {
+        let start = search_from + found;
+        search_from = start + name.len();
+        let preceded_by_space = lower[..start].ends_with(char::is_whitespace);
+        let after = lower[search_from..].trim_start();
+        if !preceded_by_space || !after.starts_with('=') {
+            continue;
+        }
+        let value_start = tag.len() - after.len() + 1;
+        let value = tag[value_start..].trim_start();
+        let value = match value.chars().next() {
+            Some(quote @ ('"' | '\'')) => value[1..].split(quote).next().unwrap_or(""),
+            _ => value.split(char::is_whitespace).next().unwrap_or(""),
+        };
+        return Some(decode_entities(value));
+    }
+    None
+}
+
+fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
+
+fn decode_entities(text: &str) -> String {
+    if !text.contains('&') {
+        return text.to_string();
+    }
+    let mut out = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(amp) = rest.find('&') {
+        out.push_str(&rest[..amp]);
+        rest = &rest[amp..];
+        let decoded = rest.find(';').filter(|end| *end <= 10).and_then(|end| {
+            let entity = &rest[1..end];
+            let ch = match entity {
+                "amp" => Some('&'),
+                "lt" => Some('<'),
+                "gt" => Some('>'),
+                "quot" => Some('"'),
+                "apos" => Some('\''),
+                "nbsp" => Some(' '),
+                _ => entity
+                    .strip_prefix("#x")
+                    .or_else(|| entity.strip_prefix("#X"))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok())
+                    .or_else(|| entity.strip_prefix('#').and_then(|dec| dec.parse().ok()))
+                    .and_then(char::from_u32),
+            };
+            ch.map(|ch| (ch, end))
+        });
+        match decoded {
+            Some((ch, end)) => {
+                out.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                out.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    out.push_str(rest);
+    out
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use wiremock::Mock;
+    use wiremock::MockServer;
+    use wiremock::ResponseTemplate;
+    use wiremock::matchers::method;
+    use wiremock::matchers::path;
+
+    #[test]
+    fn html_main_content_becomes_markdown() {
+        let html = r#"<html><head><title>Guide &amp; Reference</title>
+            <script>var x = "<p>no</p>";</script></head>
+            <body><nav><a href="/">Home</a></nav>
+            <main><h1>Install</h1>
+            <p>Run <code>cargo   install</code> and read the <a href="/docs">docs</a>.</p>
+            <ul><li>One</li><li><b>Two</b></li></ul>
+            <pre>fn main() {
+    println!("hi");
+}</pre></main>
+            <footer>Copyright</footer></body></html>"#;
+        assert_eq!(html_title(html).as_deref(), Some("Guide & Reference"));
+        assert_eq!(
+            html_to_markdown(html),
+            "# Install\n\nRun `cargo install` and read the [docs](/docs).\n\n- One\n- **Two**\n\n```\nfn main() {\n    println!(\"hi\");\n}\n```"
+        );
+    }
+
+    #[test]
+    fn robots_rules_and_budget() {
+        let robots = "User-agent: *\nDisallow: /private\nAllow: /private/ok\n\nUser-agent: other\nDisallow: /\n";
+        assert!(robots_allows(robots, "/docs"));
+        assert!(!robots_allows(robots, "/private/x"));
+        assert!(robots_allows(robots, "/private/ok/page"));
+        assert!(!robots_allows("User-agent: codex\nDisallow: /\n", "/docs"));
+        assert!(robots_allows("User-agent: *\nDisallow:\n", "/docs"));
+
+        assert!(host_matches("docs.rs", "docs.rs"));
+        assert!(host_matches("www.example.com", "example.com"));
+        assert!(!host_matches("notexample.com", "example.com"));
+
+        let counter = TokenCounter::default();
+        let text = "line one\nline two\nline three";
+        let budget = counter.count("line one\nline");
+        let truncated = truncate_to_budget(text, budget, &counter);
+        let omitted = counter.count(text) - counter.count("line one");
+        assert_eq!(
+            truncated,
+            format!("line one\n\n[truncated: {omitted} more tokens]")
+        );
+        assert_eq!(truncate_to_budget(text, 1_000, &counter), text);
+    }
+
+    #[test]
+    fn private_addresses_need_allow_hosts() {
+        let settings = FetchUrl::default();
+        for url in [
+            "http://127.0.0.1/",
+            "http://169.254.169.254/latest/meta-data/",
+            "http://10.0.0.1/",
+            "http://[::1]/",
+            "http://[::ffff:192.168.1.1]/",
+        ] {
+            let url = Url::parse(url).expect("valid url");
+            let err = check_url(&url, &settings).expect_err("private address should be refused");
+            assert!(err.contains("add it to fetch_url.allow_hosts"), "{err}");
+        }
+
+        let settings = FetchUrl {
+            allow_hosts: vec!["127.0.0.1".to_string()],
+            ..FetchUrl::default()
+        };
+        let url = Url::parse("http://127.0.0.1:8080/docs").expect("valid url");
+        assert_eq!(check_url(&url, &settings), Ok(()));
+
+        assert!(is_public("93.184.216.34".parse().expect("ip")));
+        assert!(is_public("2606:2800:220:1::".parse().expect("ip")));
+        assert!(!is_public("100.64.0.1".parse().expect("ip")));
+        assert!(!is_public("fd00::1".parse().expect("ip")));
+        assert!(!is_public("fe80::1".parse().expect("ip")));
+    }
+
+    #[tokio::test]
+    async fn redirects_to_denied_hosts_are_refused() {
+        let server = MockServer::start().await;
+        Mock::given(method("GET"))
+            .and(path("/docs"))
+            .respond_with(
+                ResponseTemplate::new(302).insert_header("Location", "http://denied.example/page"),
+            )
+            .mount(&server)
+            .await;
+        let settings = FetchUrl {
+            deny_hosts: vec!["denied.example".to_string()],
+            // The mock server listens on loopback.
+            allow_hosts: vec!["127.0.0.1".to_string()],
+            respect_robots_txt: false,
+            ..FetchUrl::default()
+        };
+
+        let err = fetch_url(
+            &format!("{}/docs", server.uri()),
+            &settings,
+            &TokenCounter::default(),
+        )
+        .await
+        .expect_err("redirect should be refused");
+        assert!(
+            err.contains("denied.example is in fetch_url.deny_hosts"),
+            "unexpected error: {err}"
+        );
+    }
+}
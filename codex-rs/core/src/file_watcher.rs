@@ -0,0 +1,287 @@
+//! Notice files edited outside the agent during a session (`[file_watcher]`)
+//! and tell the model at the start of its next turn, so it re-reads them
+//! instead of overwriting the user's edits based on a stale read.
+//!
+//! Changes are collected from file system notifications. Only changes made
+//! between turns are reported, since changes during a turn are most likely
+//! the agent's own commands (`cargo fmt`, `sed -i`, code generators). At the
+//! start of a turn each such file is compared with what the model last saw of
+//! it (see [`crate::pre_images`]), so files it holds stale contents of are
+//! listed first and files changed back to what it saw are left out.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::Mutex;
+use std::sync::PoisonError;
+
+use codex_apply_patch::read_text;
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::FILE_CHANGES_CLOSE_TAG;
+use codex_protocol::protocol::FILE_CHANGES_OPEN_TAG;
+use ignore::gitignore::Gitignore;
+use notify::EventKind;
+use notify::RecommendedWatcher;
+use notify::RecursiveMode;
+use notify::Watcher;
+use notify::event::ModifyKind;
+use tracing::warn;
+
+/// Changed paths remembered between turns at most, so a build writing
+/// thousands of files cannot grow the set without bound.
+const MAX_TRACKED_CHANGES: usize = 1000;
+
+#[derive(Debug, Default)]
+struct WatchState {
+    /// Changed path (relative to the root) → whether it changed between
+    /// turns.
+    changed: BTreeMap<PathBuf, bool>,
+    turn_active: bool,
+}
+
+pub(crate) struct FileWatcher {
+    /// Kept alive for as long as notifications are wanted.
+    _watcher: RecommendedWatcher,
+    state: Arc<Mutex<WatchState>>,
+    /// Files listed per note at most.
+    max_files: usize,
+}
+
+/// Marks a turn as running until dropped, including when the turn is
+/// aborted.
+pub(crate) struct TurnGuard {
+    state: Arc<Mutex<WatchState>>,
+}
+
+impl Drop for TurnGuard {
+    fn drop(&mut self) {
+        lock(&self.state).turn_active = false;
+    }
+}
+
+/// How a changed file differs from what the model knows.
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Change {
+    /// Differs from the contents the model last read or wrote.
+    StaleRead,
+    /// Deleted after the model read it.
+    DeletedAfterRead,
+    /// Changed between turns; the model never read it.
+    Changed,
+    /// Deleted between turns; the model never read it.
+    Deleted,
+}
+
+impl FileWatcher {
+    /// Watch `root` recursively. Files ignored by its `.gitignore` and
+    /// hidden paths are skipped. `None` if the platform watcher fails.
+    pub(crate) fn start(root: &Path, max_files: usize) -> Option<Self> {
+        let root = root.canonicalize().unwrap_or_else(|_| root.to_path_buf());
+        let (gitignore, _) = Gitignore::new(root.join(".gitignore"));
+        let state = Arc::new(Mutex::new(WatchState::default()));
+
+        let handler_state = state.clone();
+        let handler_root = root.clone();
+        let watcher = notify::recommended_watcher(move |res: notify::Result<notify::Event>| {
+            let Ok(event) = res else {
+                return;
+            };
+            let relevant = match event.kind {
+                EventKind::Create(_) | EventKind::Remove(_) => true,
+                EventKind::Modify(kind) => !matches!(kind, ModifyKind::Metadata(_)),
+                EventKind::Access(_) | EventKind::Any | EventKind::Other => false,
+            };
+            if !relevant {
+                return;
+            }
+            let mut state = lock(&handler_state);
+            for path in event.paths {
+                let Ok(relative) = path.strip_prefix(&handler_root) else {
+                    continue;
+                };
+                let hidden = relative
+                    .components()
+                    .any(|c| c.as_os_str().to_string_lossy().starts_with('.'));
+                if hidden
+                    || path.is_dir()
+                    || gitignore
+                        .matched_path_or_any_parents(relative, false)
+                        .is_ignore()
+                {
+                    continue;
+                }
+                if state.changed.len() >= MAX_TRACKED_CHANGES
+                    && !state.changed.contains_key(relative)
+                {
+                    continue;
+                }
+                let between_turns = !state.turn_active;
+                let entry = state.changed.entry(relative.to_path_buf()).or_insert(false);
+                *entry |= between_turns;
+            }
+        });
+        let mut watcher = match watcher {
+            Ok(watcher) => watcher,
+            Err(e) => {
+                warn!("file watcher disabled: {e}");
+                return None;
+            }
+        };
+        if let Err(e) = watcher.watch(&root, RecursiveMode::Recursive) {
+            warn!(
+                "file watcher disabled: cannot watch {}: {e}",
+                root.display()
+            );
+            return None;
+        }
+        Some(Self {
+            _watcher: watcher,
+            state,
+            max_files,
+        })
+    }
+
+    /// Mark a turn as running until the returned guard is dropped.
+    pub(crate) fn turn_started(&self) -> TurnGuard {
+        lock(&self.state).turn_active = true;
+        TurnGuard {
+            state: self.state.clone(),
+        }
+    }
+
+    /// The note to give the model about files changed since the last turn,
+    /// if any. `last_seen` returns what the model last saw of a file under
+    /// `cwd`.
+    pub(crate) fn take_note(
+        &self,
+        cwd: &Path,
+        last_seen: impl Fn(&Path) -> Option<String>,
+    ) -> Option<String> {
+        let changed = std::mem::take(&mut lock(&self.state).changed);
+        let changes: Vec<(PathBuf, Change)> = changed
+            .into_iter()
+            .filter_map(|(relative, between_turns)| {
+                let path = cwd.join(&relative);
+                let current = if path.exists() {
+                    Some(read_text(&path).map(|(text, _)| text).ok())
+                } else {
+                    None
+                };
+                classify(last_seen(&path), current, between_turns).map(|change| (relative, change))
+            })
+            .collect();
+        format_note(&changes, self.max_files)
+    }
+}
+
+/// `seen` is what the model last saw, `current` the file now (`None` when
+/// deleted, `Some(None)` when unreadable as text).
+fn classify(
+    seen: Option<String>,
+    current: Option<Option<String>>,
+    between_turns: bool,
+) -> Option<Change> {
+    if !between_turns {
+        return None;
+    }
+    match (seen, current) {
+        (Some(_), None) => Some(Change::DeletedAfterRead),
+        (Some(seen), Some(current)) => {
+            (current.as_deref() != Some(seen.as_str())).then_some(Change::StaleRead)
+        }
+        (None, None) => Some(Change::Deleted),
+        (None, Some(_)) => Some(Change::Changed),
+    }
+}
+
+fn format_note(changes: &[(PathBuf, Change)], max_files: usize) -> Option<String> {
+    if changes.is_empty() {
+        return None;
+    }
+    // Files the model holds stale contents of matter most.
+    let mut ordered: Vec<&(PathBuf, Change)> = changes.iter().collect();
+    ordered
+        .sort_by_key(|(_, change)| !matches!(change, Change::StaleRead | Change::DeletedAfterRead));
+    let mut note = format!(
+        "{FILE_CHANGES_OPEN_TAG}\nThe user changed these files outside of your edits. Re-read \
+         a file before editing it and keep the user's changes:\n"
+    );
+    for (path, change) in ordered.iter().take(max_files) {
+        let what = match change {
+            Change::StaleRead => "modified since you last read it",
+            Change::DeletedAfterRead => "deleted since you last read it",
+            Change::Changed => "modified",
+            Change::Deleted => "deleted",
+        };
+        note.push_str(&format!("- {} ({what})\n", path.display()));
+    }
+    if ordered.len() > max_files {
+        note.push_str(&format!("- … and {} more\n", ordered.len() - max_files));
+    }
+    note.push_str(FILE_CHANGES_CLOSE_TAG);
+    Some(note)
+}
+
+/// The note as the message that carries it into the turn.
+pub(crate) fn file_changes_message(note: &str) -> ResponseItem {
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: note.to_string(),
+        }],
+    }
+}
+
+fn lock(state: &Mutex<WatchState>) -> std::sync::MutexGuard<'_, WatchState> {
+    state.lock().unwrap_or_else(PoisonError::into_inner)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn stale_reads_are_reported_first_and_own_edits_skipped() {
+        let seen = |text: &str| Some(text.to_string());
+        let current = |text: &str| Some(Some(text.to_string()));
+        assert_eq!(classify(seen("a"), current("a"), true), None);
+        assert_eq!(
+            classify(seen("a"), current("b"), true),
+            Some(Change::StaleRead)
+        );
+        assert_eq!(
+            classify(seen("a"), None, true),
+            Some(Change::DeletedAfterRead)
+        );
+        assert_eq!(classify(None, current("b"), true), Some(Change::Changed));
+        // Changes during a turn are most likely the agent's own commands,
+        // even to files it read before running them.
+        assert_eq!(classify(seen("a"), current("b"), false), None);
+        assert_eq!(classify(seen("a"), None, false), None);
+        assert_eq!(classify(None, current("b"), false), None);
+
+        let note = format_note(
+            &[
+                (PathBuf::from("README.md"), Change::Changed),
+                (PathBuf::from("src/auth.rs"), Change::StaleRead),
+                (PathBuf::from("src/old.rs"), Change::Deleted),
+            ],
+            2,
+        );
+        assert_eq!(
+            note.as_deref(),
+            Some(
+                "<file_changes>\nThe user changed these files outside of your edits. Re-read a \
+                 file before editing it and keep the user's changes:\n\
+                 - src/auth.rs (modified since you last read it)\n\
+                 - README.md (modified)\n\
+                 - … and 1 more\n\
+                 </file_changes>"
+            )
+        );
+    }
+}
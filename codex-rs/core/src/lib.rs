@@ -6,11 +6,15 @@
 #![deny(clippy::print_stdout, clippy::print_stderr)]
 
 mod apply_patch;
+#[cfg(unix)]
+pub mod attach;
 pub mod auth;
 pub mod bash;
+pub mod capabilities;
 mod chat_completions;
 mod client;
 mod client_common;
+pub mod code_host;
 pub mod codex;
 mod codex_conversation;
 pub mod token_data;
@@ -21,21 +25,29 @@ pub mod config_profile;
 pub mod config_types;
 mod conversation_history;
 pub mod custom_prompts;
+pub mod documents;
 mod environment_context;
 pub mod error;
+pub mod event_log;
 pub mod exec;
 mod exec_command;
 pub mod exec_env;
+mod fetch_url;
+mod file_watcher;
 mod flags;
 pub mod git_info;
 pub mod internal_storage;
 mod is_safe_command;
 pub mod landlock;
+pub mod local_tools;
 mod mcp_connection_manager;
+mod mcp_input;
 mod mcp_tool_call;
 mod message_history;
 mod model_provider_info;
+pub mod offline;
 pub mod parse_command;
+mod patch_guard;
 mod truncate;
 mod unified_exec;
 mod user_instructions;
@@ -57,14 +69,24 @@ pub mod model_family;
 mod openai_model_info;
 mod openai_tools;
 pub mod plan_tool;
+mod pre_images;
 pub mod project_doc;
+pub mod prompt_preview;
+mod repo_map;
 mod rollout;
 pub(crate) mod safety;
+pub mod scratch;
 pub mod seatbelt;
 pub mod shell;
 pub mod spawn;
+mod speculative_exec;
+mod subtasks;
+mod syntax_check;
 pub mod terminal;
+pub mod token_counter;
 mod tool_apply_patch;
+mod tool_emulation;
+pub mod trust_store;
 pub mod turn_diff_tracker;
 pub use rollout::ARCHIVED_SESSIONS_SUBDIR;
 pub use rollout::RolloutRecorder;
@@ -73,8 +95,24 @@ pub use rollout::SessionMeta;
 pub use rollout::list::ConversationItem;
 pub use rollout::list::ConversationsPage;
 pub use rollout::list::Cursor;
-mod user_notification;
+pub use rollout::recovery::InterruptedSession;
+pub use rollout::recovery::discard_interrupted_session;
+pub use rollout::recovery::find_interrupted_session;
+pub use rollout::replay::ReplayEntry;
+pub use rollout::replay::UserTurn;
+pub use rollout::replay::read_replay;
+pub use rollout::replay::resolve_rollout_path;
+pub use rollout::replay::user_turns;
+pub use rollout::share::Redactor;
+pub use rollout::share::SharePayload;
+pub use rollout::share::SharedDiff;
+pub use rollout::share::build_share_payload;
+pub use rollout::stats::ApprovalCounts;
+pub use rollout::stats::UsageStats;
+pub use rollout::stats::collect_usage_stats;
+pub mod user_notification;
 pub mod util;
+mod workspace_guard;
 
 pub use apply_patch::CODEX_APPLY_PATCH_ARG1;
 pub use safety::get_platform_sandbox;
@@ -0,0 +1,442 @@
+//! Codex's sandboxed `shell` and `apply_patch` tools for callers outside a
+//! Codex session, such as `codex mcp-tools`.
+//!
+//! Each call goes through the same checks a session applies to the model's
+//! tool calls: [`assess_command_safety`] and [`assess_patch_safety`] decide
+//! whether to run in the platform sandbox, ask for approval or refuse,
+//! patches that escape the writable roots are rejected, patches to protected
+//! files (`[patch_guard]`) need approval, and a command that fails in the
+//! sandbox may be retried without it once approved. Approval is requested
+//! through a caller-supplied callback.
+
+use std::collections::HashMap;
+use std::collections::HashSet;
+use std::future::Future;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use codex_apply_patch::MaybeApplyPatchVerified;
+use codex_apply_patch::maybe_parse_apply_patch_verified;
+
+use crate::apply_patch::CODEX_APPLY_PATCH_ARG1;
+use crate::apply_patch::convert_apply_patch_to_protocol;
+use crate::codex::format_exec_output;
+use crate::config::Config;
+use crate::config_types::FileFormat;
+use crate::config_types::PatchGuard;
+use crate::config_types::PatchGuardAction;
+use crate::config_types::ShellEnvironmentPolicy;
+use crate::error::CodexErr;
+use crate::error::SandboxErr;
+use crate::exec::ExecParams;
+use crate::exec::SandboxType;
+use crate::exec::process_exec_tool_call;
+use crate::exec_env::create_env;
+use crate::patch_guard::describe_violations;
+use crate::patch_guard::find_patch_guard_violations;
+use crate::protocol::AskForApproval;
+use crate::protocol::FileChange;
+use crate::protocol::ReviewDecision;
+use crate::protocol::SandboxPolicy;
+use crate::safety::SafetyCheck;
+use crate::safety::assess_command_safety;
+use crate::safety::assess_patch_safety;
+use crate::safety::assess_safety_for_untrusted_command;
+use crate::workspace_guard::find_workspace_escapes;
+use crate::workspace_guard::is_inside_writable_roots;
+
+/// Something the caller must approve before it runs.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ApprovalRequest {
+    Exec {
+        command: Vec<String>,
+        cwd: PathBuf,
+        reason: Option<String>,
+    },
+    Patch {
+        changes: HashMap<PathBuf, FileChange>,
+        cwd: PathBuf,
+    },
+}
+
+/// The result of a tool call, formatted as it would be for the model.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LocalToolOutput {
+    pub content: String,
+    pub success: bool,
+}
+
+impl LocalToolOutput {
+    fn failure(content: impl Into<String>) -> Self {
+        Self {
+            content: content.into(),
+            success: false,
+        }
+    }
+}
+
+pub struct LocalTools {
+    approval_policy: AskForApproval,
+    sandbox_policy: SandboxPolicy,
+    cwd: PathBuf,
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    shell_environment_policy: ShellEnvironmentPolicy,
+    workspace_guard_allow: Vec<PathBuf>,
+    patch_guard: PatchGuard,
+    file_format: FileFormat,
+    /// Commands approved with `ApprovedForSession`, which later run without
+    /// asking again.
+    approved_commands: Mutex<HashSet<Vec<String>>>,
+}
+
+impl LocalTools {
+    pub fn new(config: &Config) -> Self {
+        Self {
+            approval_policy: config.approval_policy,
+            sandbox_policy: config.sandbox_policy.clone(),
+            cwd: config.cwd.clone(),
+            codex_linux_sandbox_exe: config.codex_linux_sandbox_exe.clone(),
+            shell_environment_policy: config.shell_environment_policy.clone(),
+            workspace_guard_allow: config.workspace_guard.allow.clone(),
+            patch_guard: config.patch_guard.clone(),
+            file_format: config.file_format,
+            approved_commands: Mutex::new(HashSet::new()),
+        }
+    }
+
+    /// Run `command` in `workdir` (relative to the configured cwd). An
+    /// `apply_patch` invocation is handled as [`apply_patch`](Self::apply_patch).
+    pub async fn shell<F, Fut>(
+        &self,
+        command: Vec<String>,
+        workdir: Option<PathBuf>,
+        timeout_ms: Option<u64>,
+        approve: F,
+    ) -> LocalToolOutput
+    where
+        F: Fn(ApprovalRequest) -> Fut,
+        Fut: Future<Output = ReviewDecision>,
+    {
+        let cwd = match self.resolve_cwd(workdir) {
+            Ok(cwd) => cwd,
+            Err(error) => return LocalToolOutput::failure(error),
+        };
+        match maybe_parse_apply_patch_verified(&command, &cwd) {
+            MaybeApplyPatchVerified::Body(_) => {
+                return self
+                    .apply_patch_argv(&command, cwd, timeout_ms, approve)
+                    .await;
+            }
+            MaybeApplyPatchVerified::CorrectnessError(error) => {
+                return LocalToolOutput::failure(format!("error: {error:#}"));
+            }
+            MaybeApplyPatchVerified::ShellParseError(_)
+            | MaybeApplyPatchVerified::NotApplyPatch => {}
+        }
+
+        let safety = {
+            let approved = self.approved_commands();
+            assess_command_safety(
+                &command,
+                self.approval_policy,
+                &self.sandbox_policy,
+                &approved,
+                false,
+            )
+        };
+        let sandbox_type = match safety {
+            SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+            SafetyCheck::AskUser => {
+                let request = ApprovalRequest::Exec {
+                    command: command.clone(),
+                    cwd: cwd.clone(),
+                    reason: None,
+                };
+                if !self.approved(&command, approve(request).await) {
+                    return LocalToolOutput::failure("exec command rejected by user");
+                }
+                SandboxType::None
+            }
+            SafetyCheck::Reject { reason } => {
+                return LocalToolOutput::failure(format!("exec command rejected: {reason}"));
+            }
+        };
+
+        let params = ExecParams {
+            command,
+            cwd,
+            timeout_ms,
+            env: create_env(&self.shell_environment_policy),
+            with_escalated_permissions: None,
+            justification: None,
+        };
+        self.run(params, sandbox_type, approve).await
+    }
+
+    /// Apply `patch` (in the `apply_patch` format) relative to `workdir`.
+    pub async fn apply_patch<F, Fut>(
+        &self,
+        patch: String,
+        workdir: Option<PathBuf>,
+        approve: F,
+    ) -> LocalToolOutput
+    where
+        F: Fn(ApprovalRequest) -> Fut,
+        Fut: Future<Output = ReviewDecision>,
+    {
+        let cwd = match self.resolve_cwd(workdir) {
+            Ok(cwd) => cwd,
+            Err(error) => return LocalToolOutput::failure(error),
+        };
+        let argv = vec!["apply_patch".to_string(), patch];
+        self.apply_patch_argv(&argv, cwd, None, approve).await
+    }
+
+    async fn apply_patch_argv<F, Fut>(
+        &self,
+        argv: &[String],
+        cwd: PathBuf,
+        timeout_ms: Option<u64>,
+        approve: F,
+    ) -> LocalToolOutput
+    where
+        F: Fn(ApprovalRequest) -> Fut,
+        Fut: Future<Output = ReviewDecision>,
+    {
+        let action = match maybe_parse_apply_patch_verified(argv, &cwd) {
+            MaybeApplyPatchVerified::Body(action) => action,
+            MaybeApplyPatchVerified::CorrectnessError(error) => {
+                return LocalToolOutput::failure(format!("error: {error:#}"));
+            }
+            MaybeApplyPatchVerified::ShellParseError(error) => {
+                return LocalToolOutput::failure(format!("error: {error:?}"));
+            }
+            MaybeApplyPatchVerified::NotApplyPatch => {
+                return LocalToolOutput::failure("error: not an apply_patch invocation");
+            }
+        };
+
+        // The checks run against the configured cwd, as a session's run
+        // against its turn's; `workdir` only says where relative paths start.
+        let escapes = find_workspace_escapes(
+            &action,
+            &self.sandbox_policy,
+            &self.cwd,
+            &self.workspace_guard_allow,
+        );
+        if !escapes.is_empty() {
+            let paths: Vec<String> = escapes
+                .iter()
+                .map(|escape| format!("{} -> {}", escape.path.display(), escape.resolved.display()))
+                .collect();
+            return LocalToolOutput::failure(format!(
+                "patch rejected: it writes outside the writable roots:\n{}",
+                paths.join("\n")
+            ));
+        }
+
+        // As in a session: a patch the user approved runs unsandboxed, one
+        // that was auto-approved is checked again as an ordinary command.
+        // Protected files turn the patch into one the user must approve.
+        let violations = find_patch_guard_violations(&action, &self.patch_guard, &self.cwd);
+        let patch_safety = if violations.is_empty() {
+            assess_patch_safety(
+                &action,
+                self.approval_policy,
+                &self.sandbox_policy,
+                &self.cwd,
+            )
+        } else if self.patch_guard.on_violation == PatchGuardAction::Ask
+            && !matches!(self.approval_policy, AskForApproval::Never)
+        {
+            SafetyCheck::AskUser
+        } else {
+            return LocalToolOutput::failure(format!(
+                "patch rejected: it edits binary, oversized or generated files:\n{}",
+                describe_violations(&violations)
+            ));
+        };
+        let safety = match patch_safety {
+            SafetyCheck::AutoApprove { .. } => assess_safety_for_untrusted_command(
+                self.approval_policy,
+                &self.sandbox_policy,
+                false,
+            ),
+            SafetyCheck::AskUser => {
+                let request = ApprovalRequest::Patch {
+                    changes: convert_apply_patch_to_protocol(&action),
+                    cwd: cwd.clone(),
+                };
+                match approve(request).await {
+                    ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+                        SafetyCheck::AutoApprove {
+                            sandbox_type: SandboxType::None,
+                        }
+                    }
+                    ReviewDecision::Denied | ReviewDecision::Abort => {
+                        return LocalToolOutput::failure("patch rejected by user");
+                    }
+                }
+            }
+            SafetyCheck::Reject { reason } => {
+                return LocalToolOutput::failure(format!("patch rejected: {reason}"));
+            }
+        };
+        let sandbox_type = match safety {
+            SafetyCheck::AutoApprove { sandbox_type } => sandbox_type,
+            SafetyCheck::AskUser => {
+                let request = ApprovalRequest::Exec {
+                    command: vec!["apply_patch".to_string(), action.patch.clone()],
+                    cwd: cwd.clone(),
+                    reason: None,
+                };
+                match approve(request).await {
+                    ReviewDecision::Approved | ReviewDecision::ApprovedForSession => {
+                        SandboxType::None
+                    }
+                    ReviewDecision::Denied | ReviewDecision::Abort => {
+                        return LocalToolOutput::failure("patch rejected by user");
+                    }
+                }
+            }
+            SafetyCheck::Reject { reason } => {
+                return LocalToolOutput::failure(format!("patch rejected: {reason}"));
+            }
+        };
+
+        // Like a session, apply the patch by re-running this executable so
+        // the write happens inside the sandbox.
+        let Ok(path_to_codex) = std::env::current_exe() else {
+            return LocalToolOutput::failure("failed to determine path to codex executable");
+        };
+        let mut command = vec![
+            path_to_codex.to_string_lossy().to_string(),
+            CODEX_APPLY_PATCH_ARG1.to_string(),
+            action.patch.clone(),
+        ];
+        command.extend(self.file_format.write_options().to_args());
+        let params = ExecParams {
+            command,
+            cwd: action.cwd.clone(),
+            timeout_ms,
+            env: HashMap::new(),
+            with_escalated_permissions: None,
+            justification: None,
+        };
+        self.run(params, sandbox_type, approve).await
+    }
+
+    /// Execute `params`, offering to retry outside the sandbox when the
+    /// approval policy allows it.
+    async fn run<F, Fut>(
+        &self,
+        params: ExecParams,
+        sandbox_type: SandboxType,
+        approve: F,
+    ) -> LocalToolOutput
+    where
+        F: Fn(ApprovalRequest) -> Fut,
+        Fut: Future<Output = ReviewDecision>,
+    {
+        let error = match process_exec_tool_call(
+            params.clone(),
+            sandbox_type,
+            &self.sandbox_policy,
+            &self.codex_linux_sandbox_exe,
+            None,
+        )
+        .await
+        {
+            Ok(output) => {
+                return LocalToolOutput {
+                    content: format_exec_output(&output),
+                    success: output.exit_code == 0,
+                };
+            }
+            Err(CodexErr::Sandbox(SandboxErr::Timeout)) => {
+                return LocalToolOutput::failure(format!(
+                    "command timed out after {} milliseconds",
+                    params.timeout_duration().as_millis()
+                ));
+            }
+            Err(CodexErr::Sandbox(error)) => error,
+            Err(e) => return LocalToolOutput::failure(format!("execution error: {e}")),
+        };
+
+        if matches!(
+            self.approval_policy,
+            AskForApproval::Never | AskForApproval::OnRequest
+        ) {
+            return LocalToolOutput::failure(format!(
+                "failed in sandbox {sandbox_type:?} with execution error: {error}"
+            ));
+        }
+        let request = ApprovalRequest::Exec {
+            command: params.command.clone(),
+            cwd: params.cwd.clone(),
+            reason: Some("command failed; retry without sandbox?".to_string()),
+        };
+        if !self.approved(&params.command, approve(request).await) {
+            return LocalToolOutput::failure("exec command rejected by user");
+        }
+        match process_exec_tool_call(
+            params,
+            SandboxType::None,
+            &self.sandbox_policy,
+            &self.codex_linux_sandbox_exe,
+            None,
+        )
+        .await
+        {
+            Ok(output) => LocalToolOutput {
+                content: format_exec_output(&output),
+                success: output.exit_code == 0,
+            },
+            Err(e) => LocalToolOutput::failure(format!("retry failed: {e}")),
+        }
+    }
+
+    /// `workdir` joined onto the configured cwd. It must stay inside the
+    /// writable roots, or a caller could move the workspace anywhere.
+    fn resolve_cwd(&self, workdir: Option<PathBuf>) -> Result<PathBuf, String> {
+        let Some(dir) = workdir else {
+            return Ok(self.cwd.clone());
+        };
+        let cwd = self.cwd.join(&dir);
+        if is_inside_writable_roots(
+            &cwd,
+            &self.sandbox_policy,
+            &self.cwd,
+            &self.workspace_guard_allow,
+        ) {
+            Ok(cwd)
+        } else {
+            Err(format!(
+                "workdir {} is outside the writable roots",
+                dir.display()
+            ))
+        }
+    }
+
+    fn approved_commands(&self) -> HashSet<Vec<String>> {
+        self.approved_commands
+            .lock()
+            .map(|approved| approved.clone())
+            .unwrap_or_default()
+    }
+
+    /// Whether `decision` lets `command` run, remembering it for the rest of
+    /// the server's life on `ApprovedForSession`.
+    fn approved(&self, command: &[String], decision: ReviewDecision) -> bool {
+        match decision {
+            ReviewDecision::Approved => true,
+            ReviewDecision::ApprovedForSession => {
+                if let Ok(mut approved) = self.approved_commands.lock() {
+                    approved.insert(command.to_vec());
+                }
+                true
+            }
+            ReviewDecision::Denied | ReviewDecision::Abort => false,
+        }
+    }
+}
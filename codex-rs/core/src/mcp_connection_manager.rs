@@ -5,6 +5,11 @@
 //! helpers to query the available tools across *all* servers and returns them
 //! in a single aggregated map using the fully-qualified tool name
 //! `"<server><MCP_TOOL_NAME_DELIMITER><tool>"` as the key.
+//!
+//! Servers that declare the `resources` or `prompts` capability can also be
+//! asked for their resources (read and subscribed to when the user attaches
+//! them with `@server:uri`) and prompts (offered as `/server:prompt` slash
+//! commands).
 
 use std::collections::HashMap;
 use std::collections::HashSet;
@@ -17,12 +22,19 @@ use anyhow::Result;
 use anyhow::anyhow;
 use codex_mcp_client::McpClient;
 use mcp_types::ClientCapabilities;
+use mcp_types::GetPromptResult;
 use mcp_types::Implementation;
+use mcp_types::JSONRPCNotification;
+use mcp_types::Prompt;
+use mcp_types::ReadResourceResult;
+use mcp_types::Resource;
+use mcp_types::ServerCapabilities;
 use mcp_types::Tool;
 
 use serde_json::json;
 use sha1::Digest;
 use sha1::Sha1;
+use tokio::sync::broadcast;
 use tokio::task::JoinSet;
 use tracing::info;
 use tracing::warn;
@@ -85,6 +97,8 @@ struct ToolInfo {
 struct ManagedClient {
     client: Arc<McpClient>,
     startup_timeout: Duration,
+    /// What the server declared in its `initialize` response.
+    capabilities: ServerCapabilities,
 }
 
 /// A thin wrapper around a set of running [`McpClient`] instances.
@@ -98,6 +112,9 @@ pub(crate) struct McpConnectionManager {
 
     /// Fully qualified tool name -> tool instance.
     tools: HashMap<String, ToolInfo>,
+
+    /// Server name -> prompts it offers, listed once at startup.
+    prompts: HashMap<String, Vec<Prompt>>,
 }
 
 impl McpConnectionManager {
@@ -179,7 +196,10 @@ impl McpConnectionManager {
                             )
                             .await
                         {
-                            Ok(_response) => (server_name, Ok((client, startup_timeout))),
+                            Ok(response) => (
+                                server_name,
+                                Ok((client, startup_timeout, response.capabilities)),
+                            ),
                             Err(e) => (server_name, Err(e)),
                         }
                     }
@@ -200,12 +220,13 @@ impl McpConnectionManager {
             };
 
             match client_res {
-                Ok((client, startup_timeout)) => {
+                Ok((client, startup_timeout, capabilities)) => {
                     clients.insert(
                         server_name,
                         ManagedClient {
                             client: Arc::new(client),
                             startup_timeout,
+                            capabilities,
                         },
                     );
                 }
@@ -224,8 +245,16 @@ impl McpConnectionManager {
         };
 
         let tools = qualify_tools(all_tools);
+        let prompts = list_all_prompts(&clients).await;
 
-        Ok((Self { clients, tools }, errors))
+        Ok((
+            Self {
+                clients,
+                tools,
+                prompts,
+            },
+            errors,
+        ))
     }
 
     /// Returns a single map that contains **all** tools. Each key is the
@@ -263,6 +292,161 @@ impl McpConnectionManager {
             .get(tool_name)
             .map(|tool| (tool.server_name.clone(), tool.tool_name.clone()))
     }
+
+    /// Prompts offered by each server that supports them, as listed at
+    /// startup.
+    pub fn list_all_prompts(&self) -> HashMap<String, Vec<Prompt>> {
+        self.prompts.clone()
+    }
+
+    /// Ask every server that supports resources for its current list. Unlike
+    /// tools and prompts this is not cached, since resources (files, rows)
+    /// come and go during a session.
+    pub async fn list_all_resources(&self) -> HashMap<String, Vec<Resource>> {
+        let mut join_set = JoinSet::new();
+        for (server_name, managed_client) in &self.clients {
+            if managed_client.capabilities.resources.is_none() {
+                continue;
+            }
+            let server_name = server_name.clone();
+            let client = managed_client.client.clone();
+            let timeout = managed_client.startup_timeout;
+            join_set.spawn(async move {
+                let res = client.list_resources(None, Some(timeout)).await;
+                (server_name, res)
+            });
+        }
+
+        let mut resources = HashMap::new();
+        while let Some(join_res) = join_set.join_next().await {
+            match join_res {
+                Ok((server_name, Ok(result))) => {
+                    resources.insert(server_name, result.resources);
+                }
+                Ok((server_name, Err(e))) => {
+                    warn!("Failed to list resources for MCP server '{server_name}': {e:#}");
+                }
+                Err(e) => warn!("Task panic when listing MCP resources: {e:#}"),
+            }
+        }
+        resources
+    }
+
+    /// Read the resource `uri` from `server`.
+    pub async fn read_resource(&self, server: &str, uri: &str) -> Result<ReadResourceResult> {
+        let managed_client = self.resource_client(server)?;
+        managed_client
+            .client
+            .read_resource(uri.to_string(), Some(managed_client.startup_timeout))
+            .await
+            .with_context(|| format!("failed to read `{uri}` from `{server}`"))
+    }
+
+    /// Ask `server` to send `notifications/resources/updated` whenever `uri`
+    /// changes.
+    pub async fn subscribe_resource(&self, server: &str, uri: &str) -> Result<()> {
+        let managed_client = self.resource_client(server)?;
+        let supports_subscribe = managed_client
+            .capabilities
+            .resources
+            .as_ref()
+            .and_then(|resources| resources.subscribe)
+            .unwrap_or(false);
+        if !supports_subscribe {
+            return Err(anyhow!(
+                "MCP server '{server}' does not support resource subscriptions"
+            ));
+        }
+        managed_client
+            .client
+            .subscribe_resource(uri.to_string(), Some(managed_client.startup_timeout))
+            .await
+            .with_context(|| format!("failed to subscribe to `{uri}` on `{server}`"))
+    }
+
+    /// Render the prompt `name` from `server` with `arguments`.
+    pub async fn get_prompt(
+        &self,
+        server: &str,
+        name: &str,
+        arguments: &HashMap<String, String>,
+    ) -> Result<GetPromptResult> {
+        let managed_client = self
+            .clients
+            .get(server)
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        let arguments = (!arguments.is_empty()).then(|| json!(arguments));
+        managed_client
+            .client
+            .get_prompt(
+                name.to_string(),
+                arguments,
+                Some(managed_client.startup_timeout),
+            )
+            .await
+            .with_context(|| format!("failed to get prompt `{name}` from `{server}`"))
+    }
+
+    /// One notification receiver per server, for forwarding server-initiated
+    /// events such as resource updates.
+    pub fn subscribe_notifications(
+        &self,
+    ) -> Vec<(String, broadcast::Receiver<JSONRPCNotification>)> {
+        self.clients
+            .iter()
+            .map(|(server_name, managed_client)| {
+                (
+                    server_name.clone(),
+                    managed_client.client.subscribe_notifications(),
+                )
+            })
+            .collect()
+    }
+
+    fn resource_client(&self, server: &str) -> Result<&ManagedClient> {
+        let managed_client = self
+            .clients
+            .get(server)
+            .ok_or_else(|| anyhow!("unknown MCP server '{server}'"))?;
+        if managed_client.capabilities.resources.is_none() {
+            return Err(anyhow!("MCP server '{server}' does not offer resources"));
+        }
+        Ok(managed_client)
+    }
+}
+
+/// Query every server that declares the `prompts` capability for its
+/// prompts. Servers that fail to answer are skipped.
+async fn list_all_prompts(
+    clients: &HashMap<String, ManagedClient>,
+) -> HashMap<String, Vec<Prompt>> {
+    let mut join_set = JoinSet::new();
+    for (server_name, managed_client) in clients {
+        if managed_client.capabilities.prompts.is_none() {
+            continue;
+        }
+        let server_name = server_name.clone();
+        let client = managed_client.client.clone();
+        let startup_timeout = managed_client.startup_timeout;
+        join_set.spawn(async move {
+            let res = client.list_prompts(None, Some(startup_timeout)).await;
+            (server_name, res)
+        });
+    }
+
+    let mut prompts = HashMap::new();
+    while let Some(join_res) = join_set.join_next().await {
+        match join_res {
+            Ok((server_name, Ok(result))) => {
+                prompts.insert(server_name, result.prompts);
+            }
+            Ok((server_name, Err(e))) => {
+                warn!("Failed to list prompts for MCP server '{server_name}': {e:#}");
+            }
+            Err(e) => warn!("Task panic when listing MCP prompts: {e:#}"),
+        }
+    }
+    prompts
 }
 
 /// Query every server for its available tools and return a single map that
@@ -0,0 +1,201 @@
+//! Turns the MCP items a user can put in a message (`@server:uri` resources
+//! and `/server:prompt` prompts) into plain text and images before the turn
+//! reaches the model.
+
+use mcp_types::ContentBlock;
+use mcp_types::EmbeddedResourceResource;
+use mcp_types::GetPromptResult;
+use mcp_types::ReadResourceResult;
+use mcp_types::ReadResourceResultContents;
+use mcp_types::Role;
+
+use crate::mcp_connection_manager::McpConnectionManager;
+use crate::protocol::InputItem;
+
+/// Replace every `McpResource` and `McpPrompt` in `items` with what the
+/// server returns for it. Fails on the first resource or prompt that cannot
+/// be fetched, so the user is told instead of the model silently missing it.
+pub(crate) async fn resolve_mcp_input(
+    manager: &McpConnectionManager,
+    items: Vec<InputItem>,
+) -> anyhow::Result<Vec<InputItem>> {
+    if !items.iter().any(is_mcp_item) {
+        return Ok(items);
+    }
+    let mut resolved = Vec::with_capacity(items.len());
+    for item in items {
+        match item {
+            InputItem::McpResource { server, uri } => {
+                let result = manager.read_resource(&server, &uri).await?;
+                resolved.extend(resource_to_input(&server, &uri, result));
+            }
+            InputItem::McpPrompt {
+                server,
+                name,
+                arguments,
+            } => {
+                let result = manager.get_prompt(&server, &name, &arguments).await?;
+                resolved.extend(prompt_to_input(result));
+            }
+            other => resolved.push(other),
+        }
+    }
+    Ok(resolved)
+}
+
+fn is_mcp_item(item: &InputItem) -> bool {
+    matches!(
+        item,
+        InputItem::McpResource { .. } | InputItem::McpPrompt { .. }
+    )
+}
+
+/// Text contents are wrapped in a `<resource>` block naming where they came
+/// from; image blobs are attached as images and other binary blobs are noted
+/// but left out.
+fn resource_to_input(server: &str, uri: &str, result: ReadResourceResult) -> Vec<InputItem> {
+    let mut items = Vec::new();
+    for contents in result.contents {
+        match contents {
+            ReadResourceResultContents::TextResourceContents(text) => {
+                items.push(InputItem::Text {
+                    text: format!(
+                        "<resource server=\"{server}\" uri=\"{}\">\n{}\n</resource>",
+                        text.uri, text.text
+                    ),
+                });
+            }
+            ReadResourceResultContents::BlobResourceContents(blob) => {
+                let mime = blob.mime_type.unwrap_or_default();
+                if mime.starts_with("image/") {
+                    items.push(InputItem::Image {
+                        image_url: format!("data:{mime};base64,{}", blob.blob),
+                    });
+                } else {
+                    items.push(InputItem::Text {
+                        text: format!(
+                            "<resource server=\"{server}\" uri=\"{}\">[binary content omitted]</resource>",
+                            blob.uri
+                        ),
+                    });
+                }
+            }
+        }
+    }
+    if items.is_empty() {
+        items.push(InputItem::Text {
+            text: format!("<resource server=\"{server}\" uri=\"{uri}\"></resource>"),
+        });
+    }
+    items
+}
+
+/// The prompt's messages in order. Assistant turns are labelled so the model
+/// can tell them apart from the user's own words.
+fn prompt_to_input(result: GetPromptResult) -> Vec<InputItem> {
+    let mut items = Vec::new();
+    for message in result.messages {
+        let prefix = match message.role {
+            Role::User => "",
+            Role::Assistant => "Assistant: ",
+        };
+        match message.content {
+            ContentBlock::TextContent(text) => items.push(InputItem::Text {
+                text: format!("{prefix}{}", text.text),
+            }),
+            ContentBlock::ImageContent(image) => items.push(InputItem::Image {
+                image_url: format!("data:{};base64,{}", image.mime_type, image.data),
+            }),
+            ContentBlock::EmbeddedResource(embedded) => match embedded.resource {
+                EmbeddedResourceResource::TextResourceContents(text) => {
+                    items.push(InputItem::Text {
+                        text: format!(
+                            "<resource uri=\"{}\">\n{}\n</resource>",
+                            text.uri, text.text
+                        ),
+                    })
+                }
+                EmbeddedResourceResource::BlobResourceContents(blob) => {
+                    items.push(InputItem::Text {
+                        text: format!(
+                            "<resource uri=\"{}\">[binary content omitted]</resource>",
+                            blob.uri
+                        ),
+                    })
+                }
+            },
+            ContentBlock::ResourceLink(link) => items.push(InputItem::Text {
+                text: format!("{prefix}{}", link.uri),
+            }),
+            ContentBlock::AudioContent(_) => {}
+        }
+    }
+    items
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use mcp_types::BlobResourceContents;
+    use mcp_types::PromptMessage;
+    use mcp_types::TextContent;
+    use mcp_types::TextResourceContents;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn resources_and_prompts_become_text_and_images() {
+        let resource = ReadResourceResult {
+            contents: vec![
+                ReadResourceResultContents::TextResourceContents(TextResourceContents {
+                    mime_type: Some("text/plain".to_string()),
+                    text: "hello".to_string(),
+                    uri: "file:///notes.txt".to_string(),
+                }),
+                ReadResourceResultContents::BlobResourceContents(BlobResourceContents {
+                    blob: "AAAA".to_string(),
+                    mime_type: Some("image/png".to_string()),
+                    uri: "file:///logo.png".to_string(),
+                }),
+            ],
+        };
+        assert_eq!(
+            resource_to_input("fs", "file:///notes.txt", resource),
+            vec![
+                InputItem::Text {
+                    text: "<resource server=\"fs\" uri=\"file:///notes.txt\">\nhello\n</resource>"
+                        .to_string(),
+                },
+                InputItem::Image {
+                    image_url: "data:image/png;base64,AAAA".to_string(),
+                },
+            ]
+        );
+
+        let text = |role, text: &str| PromptMessage {
+            content: ContentBlock::TextContent(TextContent {
+                annotations: None,
+                text: text.to_string(),
+                r#type: "text".to_string(),
+            }),
+            role,
+        };
+        let prompt = GetPromptResult {
+            description: None,
+            messages: vec![
+                text(Role::User, "Review this table"),
+                text(Role::Assistant, "Which columns?"),
+            ],
+        };
+        assert_eq!(
+            prompt_to_input(prompt),
+            vec![
+                InputItem::Text {
+                    text: "Review this table".to_string(),
+                },
+                InputItem::Text {
+                    text: "Assistant: Which columns?".to_string(),
+                },
+            ]
+        );
+    }
+}
@@ -115,6 +115,7 @@ impl ModelProviderInfo {
         };
 
         let url = self.get_full_url(&effective_auth);
+        crate::offline::ensure_network_allowed(&url)?;
 
         let mut builder = client.post(url);
 
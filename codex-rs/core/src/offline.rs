@@ -0,0 +1,119 @@
+//! Offline mode (`--offline` / `offline = true`).
+//!
+//! When enabled, Codex must not make network requests to anything other than
+//! loopback addresses and the hosts listed in `offline_allowed_hosts` (for
+//! example a LAN Ollama server). The policy is checked when the config is
+//! loaded, so a setup that would obviously need the network fails up front,
+//! and again by every code path that opens an outbound connection via
+//! [`ensure_network_allowed`].
+//!
+//! Like [`crate::default_client::USER_AGENT_SUFFIX`], the active policy is a
+//! process-wide singleton: HTTP requests are issued from many places that do
+//! not have access to the `Config`, and a missed code path must not be able
+//! to silently reach the network. Once activated it cannot be turned off for
+//! the lifetime of the process.
+
+use std::net::IpAddr;
+use std::sync::LazyLock;
+use std::sync::RwLock;
+
+use crate::error::CodexErr;
+use crate::error::Result;
+
+static ACTIVE_POLICY: LazyLock<RwLock<Option<OfflinePolicy>>> = LazyLock::new(|| RwLock::new(None));
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct OfflinePolicy {
+    /// Hostnames or IP addresses that may still be contacted. Loopback is
+    /// always allowed.
+    pub allowed_hosts: Vec<String>,
+}
+
+impl OfflinePolicy {
+    pub fn new(allowed_hosts: Vec<String>) -> Self {
+        Self { allowed_hosts }
+    }
+
+    /// Whether a request to `url` is permitted under this policy. URLs that
+    /// cannot be parsed are rejected.
+    pub fn allows_url(&self, url: &str) -> bool {
+        let Ok(url) = reqwest::Url::parse(url) else {
+            return false;
+        };
+        let Some(host) = url.host_str() else {
+            return false;
+        };
+        let host = host.trim_start_matches('[').trim_end_matches(']');
+        if host.eq_ignore_ascii_case("localhost")
+            || host.parse::<IpAddr>().is_ok_and(|ip| ip.is_loopback())
+        {
+            return true;
+        }
+        self.allowed_hosts
+            .iter()
+            .any(|allowed| allowed.eq_ignore_ascii_case(host))
+    }
+}
+
+/// Enable offline mode for the rest of the process. Calling this again adds
+/// to the set of allowed hosts; there is no way to go back online.
+pub fn activate(policy: &OfflinePolicy) {
+    let mut active = match ACTIVE_POLICY.write() {
+        Ok(guard) => guard,
+        Err(poisoned) => poisoned.into_inner(),
+    };
+    match active.as_mut() {
+        Some(existing) => {
+            for host in &policy.allowed_hosts {
+                if !existing.allowed_hosts.contains(host) {
+                    existing.allowed_hosts.push(host.clone());
+                }
+            }
+        }
+        None => *active = Some(policy.clone()),
+    }
+}
+
+/// Whether offline mode has been activated in this process.
+pub fn is_active() -> bool {
+    ACTIVE_POLICY.read().map(|p| p.is_some()).unwrap_or(true)
+}
+
+/// Returns an error if offline mode is active and `url` is not allowed.
+pub fn ensure_network_allowed(url: &str) -> Result<()> {
+    let allowed = match ACTIVE_POLICY.read() {
+        Ok(policy) => policy.as_ref().is_none_or(|p| p.allows_url(url)),
+        // Fail closed.
+        Err(_) => false,
+    };
+    if allowed {
+        Ok(())
+    } else {
+        Err(CodexErr::OfflineNetworkDenied(url.to_string()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn loopback_is_always_allowed() {
+        let policy = OfflinePolicy::default();
+        assert!(policy.allows_url("http://localhost:11434/v1"));
+        assert!(policy.allows_url("http://127.0.0.1:8080/v1/responses"));
+        assert!(policy.allows_url("http://[::1]:11434/v1"));
+        assert!(!policy.allows_url("https://api.openai.com/v1/responses"));
+        assert!(!policy.allows_url("not a url"));
+    }
+
+    #[test]
+    fn allowed_hosts_are_matched_exactly() {
+        let policy = OfflinePolicy::new(vec!["ollama.lan".to_string(), "10.0.0.5".to_string()]);
+        assert!(policy.allows_url("http://ollama.lan:11434/v1"));
+        assert!(policy.allows_url("http://OLLAMA.lan/v1"));
+        assert!(policy.allows_url("http://10.0.0.5:11434/v1"));
+        assert!(!policy.allows_url("http://evil.ollama.lan/v1"));
+        assert!(!policy.allows_url("http://10.0.0.6/v1"));
+    }
+}
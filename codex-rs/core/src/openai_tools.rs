@@ -70,7 +70,10 @@ pub(crate) struct ToolsConfig {
     pub apply_patch_tool_type: Option<ApplyPatchToolType>,
     pub web_search_request: bool,
     pub include_view_image_tool: bool,
+    pub include_fetch_url_tool: bool,
     pub experimental_unified_exec_tool: bool,
+    /// Only tools that cannot modify anything are offered (`codex ask`).
+    pub read_only: bool,
 }
 
 pub(crate) struct ToolsConfigParams<'a> {
@@ -82,7 +85,9 @@ pub(crate) struct ToolsConfigParams<'a> {
     pub(crate) include_web_search_request: bool,
     pub(crate) use_streamable_shell_tool: bool,
     pub(crate) include_view_image_tool: bool,
+    pub(crate) include_fetch_url_tool: bool,
     pub(crate) experimental_unified_exec_tool: bool,
+    pub(crate) read_only: bool,
 }
 
 impl ToolsConfig {
@@ -96,7 +101,9 @@ impl ToolsConfig {
             include_web_search_request,
             use_streamable_shell_tool,
             include_view_image_tool,
+            include_fetch_url_tool,
             experimental_unified_exec_tool,
+            read_only,
         } = params;
         let mut shell_type = if *use_streamable_shell_tool {
             ConfigShellToolType::StreamableShell
@@ -111,6 +118,17 @@ impl ToolsConfig {
             }
         }
 
+        // Interactive sessions and escalation requests have no place in a
+        // read-only session.
+        if *read_only
+            && matches!(
+                shell_type,
+                ConfigShellToolType::StreamableShell | ConfigShellToolType::ShellWithRequest { .. }
+            )
+        {
+            shell_type = ConfigShellToolType::DefaultShell;
+        }
+
         let apply_patch_tool_type = match model_family.apply_patch_tool_type {
             Some(ApplyPatchToolType::Freeform) => Some(ApplyPatchToolType::Freeform),
             Some(ApplyPatchToolType::Function) => Some(ApplyPatchToolType::Function),
@@ -126,10 +144,13 @@ impl ToolsConfig {
         Self {
             shell_type,
             plan_tool: *include_plan_tool,
-            apply_patch_tool_type,
+            apply_patch_tool_type: apply_patch_tool_type.filter(|_| !*read_only),
             web_search_request: *include_web_search_request,
             include_view_image_tool: *include_view_image_tool,
-            experimental_unified_exec_tool: *experimental_unified_exec_tool,
+            // Read-only sessions run without network access.
+            include_fetch_url_tool: *include_fetch_url_tool && !*read_only,
+            experimental_unified_exec_tool: *experimental_unified_exec_tool && !*read_only,
+            read_only: *read_only,
         }
     }
 }
@@ -306,14 +327,14 @@ fn create_view_image_tool() -> OpenAiTool {
     properties.insert(
         "path".to_string(),
         JsonSchema::String {
-            description: Some("Local filesystem path to an image file".to_string()),
+            description: Some("Local filesystem path to an image, PDF or DOCX file".to_string()),
         },
     );
 
     OpenAiTool::Function(ResponsesApiTool {
         name: "view_image".to_string(),
         description:
-            "Attach a local image (by filesystem path) to the conversation context for this turn."
+            "Attach a local image (by filesystem path) to the conversation context for this turn. For a PDF or DOCX file, its extracted text is returned instead."
                 .to_string(),
         strict: false,
         parameters: JsonSchema::Object {
@@ -323,6 +344,27 @@ fn create_view_image_tool() -> OpenAiTool {
         },
     })
 }
+fn create_fetch_url_tool() -> OpenAiTool {
+    let mut properties = BTreeMap::new();
+    properties.insert(
+        "url".to_string(),
+        JsonSchema::String {
+            description: Some("The http or https URL to fetch".to_string()),
+        },
+    );
+
+    OpenAiTool::Function(ResponsesApiTool {
+        name: "fetch_url".to_string(),
+        description: "Download a web page and return its main content as markdown, truncated to a size limit. Prefer this over curl for reading documentation.".to_string(),
+        strict: false,
+        parameters: JsonSchema::Object {
+            properties,
+            required: Some(vec!["url".to_string()]),
+            additional_properties: Some(false),
+        },
+    })
+}
+
 /// TODO(dylan): deprecate once we get rid of json tool
 #[derive(Serialize, Deserialize)]
 pub(crate) struct ApplyPatchToolArgs {
@@ -580,7 +622,14 @@ pub(crate) fn get_openai_tools(
     if config.include_view_image_tool {
         tools.push(create_view_image_tool());
     }
-    if let Some(mcp_tools) = mcp_tools {
+
+    if config.include_fetch_url_tool {
+        tools.push(create_fetch_url_tool());
+    }
+
+    // MCP tools may have side effects we cannot see, so read-only sessions
+    // do without them.
+    if let Some(mcp_tools) = mcp_tools.filter(|_| !config.read_only) {
         // Ensure deterministic ordering to maximize prompt cache hits.
         let mut entries: Vec<(String, mcp_types::Tool)> = mcp_tools.into_iter().collect();
         entries.sort_by(|a, b| a.0.cmp(&b.0));
@@ -643,7 +692,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -665,7 +716,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
         let tools = get_openai_tools(&config, Some(HashMap::new()));
 
@@ -675,6 +728,48 @@ mod tests {
         );
     }
 
+    #[test]
+    fn read_only_tools_cannot_modify_anything() {
+        let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
+        let config = ToolsConfig::new(&ToolsConfigParams {
+            model_family: &model_family,
+            approval_policy: AskForApproval::OnRequest,
+            sandbox_policy: SandboxPolicy::ReadOnly,
+            include_plan_tool: true,
+            include_apply_patch_tool: true,
+            include_web_search_request: false,
+            use_streamable_shell_tool: false,
+            include_view_image_tool: true,
+            include_fetch_url_tool: false,
+            experimental_unified_exec_tool: true,
+            read_only: true,
+        });
+        let tools = get_openai_tools(
+            &config,
+            Some(HashMap::from([(
+                "test_server/delete_everything".to_string(),
+                mcp_types::Tool {
+                    name: "delete_everything".to_string(),
+                    input_schema: ToolInputSchema {
+                        properties: None,
+                        required: None,
+                        r#type: "object".to_string(),
+                    },
+                    output_schema: None,
+                    title: None,
+                    annotations: None,
+                    description: None,
+                },
+            )])),
+        );
+
+        assert_eq_tool_names(&tools, &["shell", "update_plan", "view_image"]);
+        assert!(matches!(
+            config.shell_type,
+            ConfigShellToolType::DefaultShell
+        ));
+    }
+
     #[test]
     fn test_get_openai_tools_mcp_tools() {
         let model_family = find_family_for_model("o3").expect("o3 should be a valid model family");
@@ -687,7 +782,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
         let tools = get_openai_tools(
             &config,
@@ -793,7 +890,9 @@ mod tests {
             include_web_search_request: false,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
 
         // Intentionally construct a map with keys that would sort alphabetically.
@@ -871,7 +970,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
 
         let tools = get_openai_tools(
@@ -934,7 +1035,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
 
         let tools = get_openai_tools(
@@ -992,7 +1095,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
 
         let tools = get_openai_tools(
@@ -1053,7 +1158,9 @@ mod tests {
             include_web_search_request: true,
             use_streamable_shell_tool: false,
             include_view_image_tool: true,
+            include_fetch_url_tool: false,
             experimental_unified_exec_tool: true,
+            read_only: false,
         });
 
         let tools = get_openai_tools(
@@ -0,0 +1,201 @@
+//! Flags patches that edit files an agent should not rewrite by hand:
+//! binary files, files over `[patch_guard] max_file_bytes`, and generated
+//! paths such as lockfiles, matched by `protected_paths` globs or by the
+//! repository's `.gitattributes`.
+//!
+//! Only the `.gitattributes` file at the repository root is consulted.
+
+use std::io::Read;
+use std::path::Path;
+
+use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::ApplyPatchFileChange;
+use wildmatch::WildMatch;
+
+use crate::config_types::PatchGuard;
+use crate::git_info::get_git_repo_root;
+use crate::protocol::PatchGuardReason;
+use crate::protocol::PatchGuardViolation;
+
+/// Bytes of an existing file inspected for NUL bytes, as git does.
+const BINARY_SNIFF_BYTES: u64 = 8000;
+
+/// Byte order marks of UTF-16 text, which is full of NUL bytes but is text
+/// that `apply_patch` reads and writes.
+const UTF16_BOMS: &[&[u8]] = &[&[0xFF, 0xFE], &[0xFE, 0xFF]];
+
+/// `.gitattributes` attributes that mark a path as generated.
+const GENERATED_ATTRIBUTES: &[&str] = &[
+    "linguist-generated",
+    "linguist-generated=true",
+    "binary",
+    "-diff",
+];
+
+/// The files `action` may not edit under `guard`, sorted by path. Each file
+/// is reported once, with the first reason that applies.
+pub(crate) fn find_patch_guard_violations(
+    action: &ApplyPatchAction,
+    guard: &PatchGuard,
+    cwd: &Path,
+) -> Vec<PatchGuardViolation> {
+    let root = get_git_repo_root(cwd).unwrap_or_else(|| cwd.to_path_buf());
+    let mut rules: Vec<(String, String)> = guard
+        .protected_paths
+        .iter()
+        .map(|glob| (glob.clone(), format!("protected_paths: {glob}")))
+        .collect();
+    if guard.gitattributes {
+        rules.extend(gitattributes_rules(&root));
+    }
+
+    let mut violations = Vec::new();
+    for (path, change) in action.changes() {
+        let path = cwd.join(path);
+        let (new_content, dest) = match change {
+            ApplyPatchFileChange::Add { content } => (Some(content), None),
+            ApplyPatchFileChange::Delete { .. } => (None, None),
+            ApplyPatchFileChange::Update {
+                move_path,
+                new_content,
+                ..
+            } => (Some(new_content), move_path.as_ref().map(|p| cwd.join(p))),
+        };
+        let reason = binary_or_too_large(&path, new_content.map(String::as_str), guard)
+            .or_else(|| generated(&path, &root, &rules))
+            .or_else(|| dest.and_then(|dest| generated(&dest, &root, &rules)));
+        if let Some(reason) = reason {
+            violations.push(PatchGuardViolation { path, reason });
+        }
+    }
+    violations.sort_by(|a, b| a.path.cmp(&b.path));
+    violations
+}
+
+/// One line per violation, for messages to the model and approval prompts.
+pub(crate) fn describe_violations(violations: &[PatchGuardViolation]) -> String {
+    violations
+        .iter()
+        .map(|violation| format!("{}: {}", violation.path.display(), violation.reason))
+        .collect::<Vec<_>>()
+        .join("\n")
+}
+
+fn binary_or_too_large(
+    path: &Path,
+    new_content: Option<&str>,
+    guard: &PatchGuard,
+) -> Option<PatchGuardReason> {
+    let existing_len = std::fs::metadata(path)
+        .ok()
+        .filter(|meta| meta.is_file())
+        .map(|meta| meta.len());
+    if (existing_len.is_some() && sniff_binary(path))
+        || new_content.is_some_and(|content| content.contains('\0'))
+    {
+        return Some(PatchGuardReason::Binary);
+    }
+    let limit = guard.max_file_bytes;
+    let bytes = existing_len
+        .unwrap_or(0)
+        .max(new_content.map_or(0, |c| c.len() as u64));
+    (limit > 0 && bytes > limit).then_some(PatchGuardReason::TooLarge { bytes, limit })
+}
+
+fn sniff_binary(path: &Path) -> bool {
+    let mut head = Vec::new();
+    std::fs::File::open(path)
+        .and_then(|file| file.take(BINARY_SNIFF_BYTES).read_to_end(&mut head))
+        .is_ok_and(|_| !UTF16_BOMS.iter().any(|bom| head.starts_with(bom)) && head.contains(&0))
+}
+
+/// The rule in `rules` (glob, description) that marks `path` as generated.
+/// The last matching rule wins, as in `.gitattributes`.
+fn generated(path: &Path, root: &Path, rules: &[(String, String)]) -> Option<PatchGuardReason> {
+    let relative = path.strip_prefix(root).unwrap_or(path);
+    let relative = relative.to_string_lossy().replace('\\', "/");
+    let file_name = path.file_name()?.to_string_lossy();
+    rules
+        .iter()
+        .rev()
+        .find(|(glob, _)| match glob.strip_prefix('/') {
+            Some(anchored) => WildMatch::new(anchored).matches(&relative),
+            None if glob.contains('/') => WildMatch::new(glob).matches(&relative),
+            None => WildMatch::new(glob).matches(&file_name),
+        })
+        .map(|(_, rule)| PatchGuardReason::Generated { rule: rule.clone() })
+}
+
+/// `(pattern, description)` for every `.gitattributes` line under `root`
+/// that marks paths as generated.
+fn gitattributes_rules(root: &Path) -> Vec<(String, String)> {
+    let Ok(contents) = std::fs::read_to_string(root.join(".gitattributes")) else {
+        return Vec::new();
+    };
+    contents
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .filter_map(|line| {
+            let mut fields = line.split_whitespace();
+            let pattern = fields.next()?;
+            let attribute = fields.find(|attr| GENERATED_ATTRIBUTES.contains(attr))?;
+            Some((
+                pattern.to_string(),
+                format!(".gitattributes: {pattern} {attribute}"),
+            ))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn binary_large_and_generated_files_are_flagged() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        std::fs::create_dir(cwd.join(".git")).expect("mkdir");
+        std::fs::write(cwd.join(".gitattributes"), "dist/** linguist-generated\n").expect("write");
+        std::fs::write(cwd.join("logo.png"), b"\x89PNG\0\0").expect("write");
+        std::fs::write(cwd.join("big.txt"), "x".repeat(64)).expect("write");
+        std::fs::write(cwd.join("utf16.txt"), b"\xFF\xFEo\0k\0\n\0").expect("write");
+
+        let guard = PatchGuard {
+            max_file_bytes: 32,
+            ..PatchGuard::default()
+        };
+        let check = |name: &str| {
+            let action = ApplyPatchAction::new_add_for_test(&cwd.join(name), "ok\n".to_string());
+            find_patch_guard_violations(&action, &guard, cwd)
+                .into_iter()
+                .map(|violation| violation.reason)
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(check("logo.png"), vec![PatchGuardReason::Binary]);
+        assert_eq!(
+            check("big.txt"),
+            vec![PatchGuardReason::TooLarge {
+                bytes: 64,
+                limit: 32
+            }]
+        );
+        assert_eq!(
+            check("crates/Cargo.lock"),
+            vec![PatchGuardReason::Generated {
+                rule: "protected_paths: *.lock".to_string()
+            }]
+        );
+        assert_eq!(
+            check("dist/app.js"),
+            vec![PatchGuardReason::Generated {
+                rule: ".gitattributes: dist/** linguist-generated".to_string()
+            }]
+        );
+        assert_eq!(check("utf16.txt"), Vec::new());
+        assert_eq!(check("src/main.rs"), Vec::new());
+    }
+}
@@ -0,0 +1,139 @@
+//! What the model last saw of the files it works on.
+//!
+//! A file is recorded when a successful shell command reads it (`cat`,
+//! `head`, `sed -n`, … as classified by [`parse_command`]) and again after a
+//! patch from the agent changes it. When a later patch no longer applies
+//! because the file changed in between, the recorded contents serve as the
+//! base of a three-way merge.
+
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::read_text;
+
+use crate::parse_command::ParsedCommand;
+use crate::parse_command::parse_command;
+use crate::protocol::FileChange;
+
+/// Files remembered at most; the least recently recorded are dropped first.
+const MAX_FILES: usize = 256;
+
+/// Larger files are not remembered.
+const MAX_FILE_BYTES: u64 = 1024 * 1024;
+
+#[derive(Debug, Default)]
+pub(crate) struct PreImages {
+    /// Path → (recording order, contents).
+    files: HashMap<PathBuf, (u64, String)>,
+    next_seq: u64,
+}
+
+impl PreImages {
+    pub(crate) fn get(&self, path: &Path) -> Option<String> {
+        self.files.get(path).map(|(_, contents)| contents.clone())
+    }
+
+    /// Record the files `command`, run in `cwd`, read.
+    pub(crate) fn record_reads(&mut self, command: &[String], cwd: &Path) {
+        for parsed in parse_command(command) {
+            let ParsedCommand::Read { cmd, .. } = parsed else {
+                continue;
+            };
+            let Some(args) = shlex::split(&cmd) else {
+                continue;
+            };
+            for arg in args.iter().skip(1).filter(|arg| !arg.starts_with('-')) {
+                let path = cwd.join(arg);
+                if path.is_file() {
+                    self.record(path);
+                }
+            }
+        }
+    }
+
+    /// Record the files a successfully applied patch wrote.
+    pub(crate) fn record_patch(&mut self, changes: &HashMap<PathBuf, FileChange>) {
+        for (path, change) in changes {
+            match change {
+                FileChange::Add { .. } => self.record(path.clone()),
+                FileChange::Delete { .. } => {
+                    self.files.remove(path);
+                }
+                FileChange::Update { move_path, .. } => match move_path {
+                    Some(dest) => {
+                        self.files.remove(path);
+                        self.record(dest.clone());
+                    }
+                    None => self.record(path.clone()),
+                },
+            }
+        }
+    }
+
+    fn record(&mut self, path: PathBuf) {
+        let fits = std::fs::metadata(&path).is_ok_and(|meta| meta.len() <= MAX_FILE_BYTES);
+        // Decoded the way `apply_patch` reads files, so the two compare.
+        let contents = match read_text(&path) {
+            Ok((contents, _)) if fits => contents,
+            _ => {
+                self.files.remove(&path);
+                return;
+            }
+        };
+        if !self.files.contains_key(&path)
+            && self.files.len() >= MAX_FILES
+            && let Some(oldest) = self
+                .files
+                .iter()
+                .min_by_key(|(_, (seq, _))| *seq)
+                .map(|(path, _)| path.clone())
+        {
+            self.files.remove(&oldest);
+        }
+        self.files.insert(path, (self.next_seq, contents));
+        self.next_seq += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn reads_and_patches_are_recorded() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let file = dir.path().join("notes.txt");
+        std::fs::write(&file, "v1\n").expect("write");
+
+        let mut pre_images = PreImages::default();
+        pre_images.record_reads(
+            &[
+                "bash".to_string(),
+                "-lc".to_string(),
+                "cat notes.txt".to_string(),
+            ],
+            dir.path(),
+        );
+        assert_eq!(pre_images.get(&file).as_deref(), Some("v1\n"));
+
+        std::fs::write(&file, "v2\n").expect("write");
+        pre_images.record_patch(&HashMap::from([(
+            file.clone(),
+            FileChange::Update {
+                unified_diff: String::new(),
+                move_path: None,
+            },
+        )]));
+        assert_eq!(pre_images.get(&file).as_deref(), Some("v2\n"));
+
+        pre_images.record_patch(&HashMap::from([(
+            file.clone(),
+            FileChange::Delete {
+                content: "v2\n".to_string(),
+            },
+        )]));
+        assert_eq!(pre_images.get(&file), None);
+    }
+}
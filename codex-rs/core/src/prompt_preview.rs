@@ -0,0 +1,144 @@
+//! What a model request carries besides the conversation itself: the
+//! instructions, the context messages sent at the start of the conversation,
+//! and the tools. Shown by `codex prompt show` and the TUI's `/prompt`.
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+
+use crate::client_common::Prompt;
+use crate::config::Config;
+use crate::environment_context::EnvironmentContext;
+use crate::model_family::ModelFamily;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ToolsConfig;
+use crate::openai_tools::ToolsConfigParams;
+use crate::openai_tools::get_openai_tools;
+use crate::project_doc::get_user_instructions;
+use crate::protocol::PromptPreviewEvent;
+use crate::repo_map::load_repo_map;
+use crate::repo_map::repo_map_message;
+use crate::shell::default_user_shell;
+use crate::user_instructions::UserInstructions;
+
+/// Describe `prompt` as sent to `model_family`, together with the context
+/// messages that open the conversation.
+pub(crate) fn assemble_prompt_preview(
+    prompt: &Prompt,
+    model_family: &ModelFamily,
+    initial_context: &[ResponseItem],
+) -> PromptPreviewEvent {
+    PromptPreviewEvent {
+        instructions: prompt.get_full_instructions(model_family).into_owned(),
+        context: initial_context.iter().filter_map(message_text).collect(),
+        tools: prompt.tools.iter().map(tool_name).collect(),
+    }
+}
+
+/// The prompt a new session started with `config` would send first. Tools
+/// from MCP servers are not included, since no servers are started.
+pub async fn preview_prompt(config: &Config) -> PromptPreviewEvent {
+    let tools_config = ToolsConfig::new(&ToolsConfigParams {
+        model_family: &config.model_family,
+        approval_policy: config.approval_policy,
+        sandbox_policy: config.sandbox_policy.clone(),
+        include_plan_tool: config.include_plan_tool,
+        include_apply_patch_tool: config.include_apply_patch_tool,
+        include_web_search_request: config.tools_web_search_request,
+        use_streamable_shell_tool: config.use_experimental_streamable_shell_tool,
+        include_view_image_tool: config.include_view_image_tool,
+        include_fetch_url_tool: config.include_fetch_url_tool,
+        experimental_unified_exec_tool: config.use_experimental_unified_exec_tool,
+        read_only: config.read_only,
+    });
+    let prompt = Prompt {
+        input: Vec::new(),
+        tools: get_openai_tools(&tools_config, None),
+        base_instructions_override: config.base_instructions.clone(),
+        appended_instructions: config.appended_instructions(),
+    };
+
+    let mut initial_context = Vec::new();
+    if let Some(user_instructions) = get_user_instructions(config).await {
+        initial_context.push(UserInstructions::new(user_instructions).into());
+    }
+    initial_context.push(ResponseItem::from(EnvironmentContext::new(
+        Some(config.cwd.clone()),
+        Some(config.approval_policy),
+        Some(config.sandbox_policy.clone()),
+        Some(default_user_shell().await),
+        None,
+    )));
+    if let Some(repo_map) = load_repo_map(&config.repo_map, &config.codex_home, &config.cwd).await {
+        initial_context.push(repo_map_message(&repo_map));
+    }
+
+    assemble_prompt_preview(&prompt, &config.model_family, &initial_context)
+}
+
+/// Plain-text rendering of `preview` with a heading per section.
+pub fn format_prompt_preview(preview: &PromptPreviewEvent) -> String {
+    let mut out = format!("# Instructions\n\n{}\n", preview.instructions.trim_end());
+    out.push_str("\n# Context\n");
+    for message in &preview.context {
+        out.push_str(&format!("\n{}\n", message.trim_end()));
+    }
+    out.push_str("\n# Tools\n\n");
+    for tool in &preview.tools {
+        out.push_str(&format!("- {tool}\n"));
+    }
+    out
+}
+
+fn message_text(item: &ResponseItem) -> Option<String> {
+    let ResponseItem::Message { content, .. } = item else {
+        return None;
+    };
+    let text: Vec<&str> = content
+        .iter()
+        .filter_map(|content| match content {
+            ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                Some(text.as_str())
+            }
+            ContentItem::InputImage { .. } => None,
+        })
+        .collect();
+    (!text.is_empty()).then(|| text.join("\n"))
+}
+
+fn tool_name(tool: &OpenAiTool) -> String {
+    match tool {
+        OpenAiTool::Function(tool) => tool.name.clone(),
+        OpenAiTool::Freeform(tool) => tool.name.clone(),
+        OpenAiTool::LocalShell {} => "local_shell".to_string(),
+        OpenAiTool::WebSearch {} => "web_search".to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::model_family::find_family_for_model;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn preview_lists_instructions_context_and_tools() {
+        let prompt = Prompt {
+            tools: vec![OpenAiTool::LocalShell {}, OpenAiTool::WebSearch {}],
+            base_instructions_override: Some("You are Codex.".to_string()),
+            appended_instructions: Some("Keep patches small.".to_string()),
+            ..Default::default()
+        };
+        let model_family = find_family_for_model("gpt-5").expect("known model slug");
+        let context = vec![UserInstructions::new("Run the tests.".to_string()).into()];
+
+        let preview = assemble_prompt_preview(&prompt, &model_family, &context);
+        assert_eq!(preview.instructions, "You are Codex.\nKeep patches small.");
+        assert_eq!(preview.tools, vec!["local_shell", "web_search"]);
+        assert_eq!(preview.context.len(), 1);
+        assert!(preview.context[0].contains("Run the tests."));
+
+        let text = format_prompt_preview(&preview);
+        assert!(text.starts_with("# Instructions\n\nYou are Codex.\n"));
+        assert!(text.ends_with("# Tools\n\n- local_shell\n- web_search\n"));
+    }
+}
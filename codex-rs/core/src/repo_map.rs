@@ -0,0 +1,548 @@
+//! A compact map of the working directory added to the first prompt
+//! (`[repo_map]`), so the model starts out knowing the project's layout
+//! instead of spending its first turns listing directories.
+//!
+//! The map names the top-level directories, the build systems in use, a few
+//! key files and the public symbols of Rust, Python, Go, JavaScript and
+//! TypeScript files, parsed with tree-sitter. Symbols are cached per file
+//! under `CODEX_HOME/repo_map/` and parsed again only when the file's
+//! modification time changes.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::REPO_MAP_CLOSE_TAG;
+use codex_protocol::protocol::REPO_MAP_OPEN_TAG;
+use serde::Deserialize;
+use serde::Serialize;
+use sha1::Digest;
+use sha1::Sha1;
+use tracing::warn;
+use tree_sitter::Node;
+use tree_sitter::Parser;
+
+use crate::config_types::RepoMap;
+
+const REPO_MAP_SUBDIR: &str = "repo_map";
+
+/// Files looked at; larger trees are mapped partially.
+const MAX_FILES: usize = 5000;
+
+/// Larger source files are not parsed for symbols.
+const MAX_PARSE_BYTES: u64 = 256 * 1024;
+
+const MAX_SYMBOLS_PER_FILE: usize = 12;
+const MAX_TOP_LEVEL_ENTRIES: usize = 40;
+const MAX_KEY_FILES: usize = 20;
+
+/// Key files deeper than this are not listed.
+const MAX_KEY_FILE_DEPTH: usize = 3;
+
+const SYMBOLS_HEADING: &str = "Public symbols:\n";
+const SYMBOLS_ELIDED: &str = "  … more files not shown\n";
+
+/// Manifest file name → build system.
+const BUILD_SYSTEMS: &[(&str, &str)] = &[
+    ("Cargo.toml", "Cargo"),
+    ("package.json", "npm"),
+    ("pnpm-workspace.yaml", "pnpm"),
+    ("pyproject.toml", "Python (pyproject)"),
+    ("setup.py", "Python (setuptools)"),
+    ("go.mod", "Go modules"),
+    ("CMakeLists.txt", "CMake"),
+    ("Makefile", "Make"),
+    ("build.gradle", "Gradle"),
+    ("build.gradle.kts", "Gradle"),
+    ("pom.xml", "Maven"),
+    ("MODULE.bazel", "Bazel"),
+    ("WORKSPACE", "Bazel"),
+    ("Gemfile", "Bundler"),
+    ("justfile", "just"),
+    ("flake.nix", "Nix"),
+];
+
+/// Documentation and entry points worth reading first.
+const KEY_FILE_NAMES: &[&str] = &[
+    "README.md",
+    "README",
+    "AGENTS.md",
+    "CONTRIBUTING.md",
+    "main.rs",
+    "lib.rs",
+    "main.go",
+    "main.py",
+    "__main__.py",
+    "index.ts",
+    "index.js",
+];
+
+/// The repository map for `cwd`, or `None` when disabled or when `cwd` has
+/// no files.
+pub(crate) async fn load_repo_map(
+    config: &RepoMap,
+    codex_home: &Path,
+    cwd: &Path,
+) -> Option<String> {
+    if !config.enabled {
+        return None;
+    }
+    let config = config.clone();
+    let cache_path = cache_path(codex_home, cwd);
+    let cwd = cwd.to_path_buf();
+    match tokio::task::spawn_blocking(move || build_repo_map(&cwd, &config, &cache_path)).await {
+        Ok(map) => map,
+        Err(e) => {
+            warn!("failed to build repo map: {e}");
+            None
+        }
+    }
+}
+
+/// The map as the message that carries it in the initial context.
+pub(crate) fn repo_map_message(map: &str) -> ResponseItem {
+    ResponseItem::Message {
+        id: None,
+        role: "user".to_string(),
+        content: vec![ContentItem::InputText {
+            text: map.to_string(),
+        }],
+    }
+}
+
+/// Per-file symbols from earlier sessions in the same directory.
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct SymbolCache {
+    files: HashMap<String, CachedSymbols>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct CachedSymbols {
+    modified: SystemTime,
+    symbols: Vec<String>,
+}
+
+fn cache_path(codex_home: &Path, cwd: &Path) -> PathBuf {
+    let mut hasher = Sha1::new();
+    hasher.update(cwd.to_string_lossy().as_bytes());
+    codex_home
+        .join(REPO_MAP_SUBDIR)
+        .join(format!("{:x}.json", hasher.finalize()))
+}
+
+fn build_repo_map(cwd: &Path, config: &RepoMap, cache_path: &Path) -> Option<String> {
+    let mut files: Vec<PathBuf> = ignore::WalkBuilder::new(cwd)
+        .build()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_some_and(|ty| ty.is_file()))
+        .filter_map(|entry| entry.path().strip_prefix(cwd).ok().map(Path::to_path_buf))
+        .take(MAX_FILES + 1)
+        .collect();
+    if files.is_empty() {
+        return None;
+    }
+    let truncated = files.len() > MAX_FILES;
+    files.truncate(MAX_FILES);
+    // Shallow files first: they are the most likely entry points.
+    files.sort_by(|a, b| {
+        a.components()
+            .count()
+            .cmp(&b.components().count())
+            .then_with(|| a.cmp(b))
+    });
+
+    let mut out = format!("{REPO_MAP_OPEN_TAG}\nDirectory: {}\n", cwd.display());
+    let build_systems = build_systems(&files);
+    if !build_systems.is_empty() {
+        out.push_str(&format!("Build systems: {}\n", build_systems.join(", ")));
+    }
+    out.push_str("Top level:\n");
+    for entry in top_level(&files, truncated) {
+        out.push_str(&format!("  {entry}\n"));
+    }
+    let key_files: Vec<String> = files
+        .iter()
+        .filter(|path| path.components().count() <= MAX_KEY_FILE_DEPTH)
+        .filter(|path| {
+            path.file_name()
+                .is_some_and(|name| KEY_FILE_NAMES.contains(&name.to_string_lossy().as_ref()))
+        })
+        .take(MAX_KEY_FILES)
+        .map(|path| display_path(path))
+        .collect();
+    if !key_files.is_empty() {
+        out.push_str(&format!("Key files: {}\n", key_files.join(", ")));
+    }
+
+    if config.symbols {
+        let closing_len = REPO_MAP_CLOSE_TAG.len() + SYMBOLS_ELIDED.len();
+        let mut cache = read_cache(cache_path);
+        let mut new_cache = SymbolCache::default();
+        let mut lines = Vec::new();
+        let mut budget = config.max_bytes.saturating_sub(out.len() + closing_len);
+        let mut complete = true;
+        for path in &files {
+            let Some(language) = Language::from_path(path) else {
+                continue;
+            };
+            let key = display_path(path);
+            let Some(symbols) =
+                file_symbols(&cwd.join(path), &key, language, &mut cache).map(|cached| {
+                    let symbols = cached.symbols.clone();
+                    new_cache.files.insert(key.clone(), cached);
+                    symbols
+                })
+            else {
+                continue;
+            };
+            if symbols.is_empty() {
+                continue;
+            }
+            let line = format_symbols(&key, &symbols);
+            let heading_len = if lines.is_empty() {
+                SYMBOLS_HEADING.len()
+            } else {
+                0
+            };
+            let needed = line.len() + heading_len;
+            if needed > budget {
+                complete = false;
+                break;
+            }
+            budget -= needed;
+            lines.push(line);
+        }
+        if !lines.is_empty() {
+            out.push_str(SYMBOLS_HEADING);
+            for line in lines {
+                out.push_str(&line);
+            }
+            if !complete {
+                out.push_str(SYMBOLS_ELIDED);
+            }
+        }
+        write_cache(cache_path, &new_cache);
+    }
+    out.push_str(REPO_MAP_CLOSE_TAG);
+    Some(out)
+}
+
+/// `Name (path)` for each manifest, shallowest first.
+fn build_systems(files: &[PathBuf]) -> Vec<String> {
+    files
+        .iter()
+        .filter(|path| path.components().count() <= 2)
+        .filter_map(|path| {
+            let name = path.file_name()?.to_string_lossy();
+            let (_, system) = BUILD_SYSTEMS.iter().find(|(file, _)| *file == name)?;
+            Some(format!("{system} ({})", display_path(path)))
+        })
+        .collect()
+}
+
+/// Top-level directories with their file counts, then top-level files.
+fn top_level(files: &[PathBuf], truncated: bool) -> Vec<String> {
+    let mut dirs: BTreeMap<String, usize> = BTreeMap::new();
+    let mut root_files = Vec::new();
+    for path in files {
+        let mut components = path.components();
+        let first = components
+            .next()
+            .map(|c| c.as_os_str().to_string_lossy().into_owned())
+            .unwrap_or_default();
+        if components.next().is_some() {
+            *dirs.entry(first).or_default() += 1;
+        } else {
+            root_files.push(first);
+        }
+    }
+    let suffix = if truncated { "+" } else { "" };
+    let mut entries: Vec<String> = dirs
+        .into_iter()
+        .map(|(dir, count)| {
+            let noun = if count == 1 { "file" } else { "files" };
+            format!("{dir}/ ({count}{suffix} {noun})")
+        })
+        .chain(root_files)
+        .collect();
+    if entries.len() > MAX_TOP_LEVEL_ENTRIES {
+        let more = entries.len() - MAX_TOP_LEVEL_ENTRIES;
+        entries.truncate(MAX_TOP_LEVEL_ENTRIES);
+        entries.push(format!("… {more} more"));
+    }
+    entries
+}
+
+fn format_symbols(path: &str, symbols: &[String]) -> String {
+    let shown = &symbols[..symbols.len().min(MAX_SYMBOLS_PER_FILE)];
+    let mut line = format!("  {path}: {}", shown.join(", "));
+    if symbols.len() > MAX_SYMBOLS_PER_FILE {
+        line.push_str(", …");
+    }
+    line.push('\n');
+    line
+}
+
+fn display_path(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// The symbols of `path`, from `cache` when it has not changed since.
+fn file_symbols(
+    path: &Path,
+    key: &str,
+    language: Language,
+    cache: &mut SymbolCache,
+) -> Option<CachedSymbols> {
+    let meta = std::fs::metadata(path).ok()?;
+    if meta.len() > MAX_PARSE_BYTES {
+        return None;
+    }
+    let modified = meta.modified().ok()?;
+    if let Some(cached) = cache.files.remove(key)
+        && cached.modified == modified
+    {
+        return Some(cached);
+    }
+    let source = std::fs::read_to_string(path).ok()?;
+    let mut symbols = public_symbols(language, &source);
+    // One extra marks the list as cut short.
+    symbols.truncate(MAX_SYMBOLS_PER_FILE + 1);
+    Some(CachedSymbols { modified, symbols })
+}
+
+fn read_cache(path: &Path) -> SymbolCache {
+    std::fs::read_to_string(path)
+        .ok()
+        .and_then(|text| serde_json::from_str(&text).ok())
+        .unwrap_or_default()
+}
+
+fn write_cache(path: &Path, cache: &SymbolCache) {
+    let result = path
+        .parent()
+        .map_or(Ok(()), std::fs::create_dir_all)
+        .and_then(|()| {
+            let json = serde_json::to_string(cache).map_err(std::io::Error::other)?;
+            std::fs::write(path, json)
+        });
+    if let Err(e) = result {
+        warn!("failed to write repo map cache {}: {e}", path.display());
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Language {
+    Rust,
+    Python,
+    Go,
+    JavaScript,
+    TypeScript,
+    Tsx,
+}
+
+impl Language {
+    fn from_path(path: &Path) -> Option<Self> {
+        match path.extension()?.to_str()? {
+            "rs" => Some(Self::Rust),
+            "py" => Some(Self::Python),
+            "go" => Some(Self::Go),
+            "js" | "jsx" | "mjs" | "cjs" => Some(Self::JavaScript),
+            "ts" | "mts" | "cts" => Some(Self::TypeScript),
+            "tsx" => Some(Self::Tsx),
+            _ => None,
+        }
+    }
+
+    fn grammar(self) -> tree_sitter::Language {
+        match self {
+            Self::Rust => tree_sitter_rust::LANGUAGE.into(),
+            Self::Python => tree_sitter_python::LANGUAGE.into(),
+            Self::Go => tree_sitter_go::LANGUAGE.into(),
+            Self::JavaScript => tree_sitter_javascript::LANGUAGE.into(),
+            Self::TypeScript => tree_sitter_typescript::LANGUAGE_TYPESCRIPT.into(),
+            Self::Tsx => tree_sitter_typescript::LANGUAGE_TSX.into(),
+        }
+    }
+}
+
+/// Top-level public declarations in `source`, as `kind name`.
+fn public_symbols(language: Language, source: &str) -> Vec<String> {
+    let mut parser = Parser::new();
+    if parser.set_language(&language.grammar()).is_err() {
+        return Vec::new();
+    }
+    let Some(tree) = parser.parse(source, None) else {
+        return Vec::new();
+    };
+    let src = source.as_bytes();
+    let root = tree.root_node();
+    let mut cursor = root.walk();
+    let mut symbols = Vec::new();
+    for node in root.named_children(&mut cursor) {
+        match language {
+            Language::Rust => symbols.extend(rust_symbol(node, src)),
+            Language::Python => symbols.extend(python_symbol(node, src)),
+            Language::Go => symbols.extend(go_symbols(node, src)),
+            Language::JavaScript | Language::TypeScript | Language::Tsx => {
+                symbols.extend(exported_symbols(node, src))
+            }
+        }
+    }
+    symbols
+}
+
+fn name_of<'a>(node: Node<'_>, src: &'a [u8]) -> Option<&'a str> {
+    node.child_by_field_name("name")?.utf8_text(src).ok()
+}
+
+fn rust_symbol(node: Node<'_>, src: &[u8]) -> Option<String> {
+    let kind = match node.kind() {
+        "function_item" => "fn",
+        "struct_item" => "struct",
+        "enum_item" => "enum",
+        "trait_item" => "trait",
+        "type_item" => "type",
+        "const_item" => "const",
+        "static_item" => "static",
+        "mod_item" => "mod",
+        _ => return None,
+    };
+    let mut cursor = node.walk();
+    let public = node.named_children(&mut cursor).any(|child| {
+        child.kind() == "visibility_modifier" && child.utf8_text(src).ok() == Some("pub")
+    });
+    if !public {
+        return None;
+    }
+    Some(format!("{kind} {}", name_of(node, src)?))
+}
+
+fn python_symbol(node: Node<'_>, src: &[u8]) -> Option<String> {
+    let node = match node.kind() {
+        "decorated_definition" => node.child_by_field_name("definition")?,
+        _ => node,
+    };
+    let kind = match node.kind() {
+        "function_definition" => "def",
+        "class_definition" => "class",
+        _ => return None,
+    };
+    let name = name_of(node, src)?;
+    (!name.starts_with('_')).then(|| format!("{kind} {name}"))
+}
+
+fn go_symbols(node: Node<'_>, src: &[u8]) -> Vec<String> {
+    let exported = |name: &str| name.starts_with(|c: char| c.is_uppercase());
+    match node.kind() {
+        "function_declaration" => name_of(node, src)
+            .filter(|name| exported(name))
+            .map(|name| format!("func {name}"))
+            .into_iter()
+            .collect(),
+        "type_declaration" => {
+            let mut cursor = node.walk();
+            node.named_children(&mut cursor)
+                .filter(|spec| spec.kind() == "type_spec")
+                .filter_map(|spec| name_of(spec, src))
+                .filter(|name| exported(name))
+                .map(|name| format!("type {name}"))
+                .collect()
+        }
+        _ => Vec::new(),
+    }
+}
+
+fn exported_symbols(node: Node<'_>, src: &[u8]) -> Vec<String> {
+    if node.kind() != "export_statement" {
+        return Vec::new();
+    }
+    let Some(declaration) = node.child_by_field_name("declaration") else {
+        return Vec::new();
+    };
+    let kind = match declaration.kind() {
+        "function_declaration" | "generator_function_declaration" => "function",
+        "class_declaration" | "abstract_class_declaration" => "class",
+        "interface_declaration" => "interface",
+        "type_alias_declaration" => "type",
+        "enum_declaration" => "enum",
+        "lexical_declaration" | "variable_declaration" => {
+            let mut cursor = declaration.walk();
+            return declaration
+                .named_children(&mut cursor)
+                .filter(|child| child.kind() == "variable_declarator")
+                .filter_map(|child| name_of(child, src))
+                .map(|name| format!("const {name}"))
+                .collect();
+        }
+        _ => return Vec::new(),
+    };
+    name_of(declaration, src)
+        .map(|name| format!("{kind} {name}"))
+        .into_iter()
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn maps_layout_build_systems_and_public_symbols() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let root = dir.path().join("project");
+        std::fs::create_dir_all(root.join("src")).expect("mkdir");
+        std::fs::create_dir_all(root.join("web")).expect("mkdir");
+        std::fs::write(root.join("Cargo.toml"), "[package]\n").expect("write");
+        std::fs::write(root.join("README.md"), "# project\n").expect("write");
+        std::fs::write(
+            root.join("src/lib.rs"),
+            "pub fn run() {}\nfn helper() {}\npub(crate) struct Hidden;\npub struct Config;\n",
+        )
+        .expect("write");
+        std::fs::write(
+            root.join("web/app.ts"),
+            "export function start() {}\nexport const VERSION = 1;\nfunction local() {}\n",
+        )
+        .expect("write");
+        std::fs::write(
+            root.join("web/tool.py"),
+            "def main():\n    pass\n\ndef _private():\n    pass\n\nclass Runner:\n    pass\n",
+        )
+        .expect("write");
+
+        let config = RepoMap {
+            enabled: true,
+            ..RepoMap::default()
+        };
+        let cache_path = dir.path().join("cache.json");
+        let map = build_repo_map(&root, &config, &cache_path).expect("map");
+        let expected = format!(
+            "{REPO_MAP_OPEN_TAG}\n\
+             Directory: {}\n\
+             Build systems: Cargo (Cargo.toml)\n\
+             Top level:\n\
+             \x20 src/ (1 file)\n\
+             \x20 web/ (2 files)\n\
+             \x20 Cargo.toml\n\
+             \x20 README.md\n\
+             Key files: README.md, src/lib.rs\n\
+             Public symbols:\n\
+             \x20 src/lib.rs: fn run, struct Config\n\
+             \x20 web/app.ts: function start, const VERSION\n\
+             \x20 web/tool.py: def main, class Runner\n\
+             {REPO_MAP_CLOSE_TAG}",
+            root.display()
+        );
+        assert_eq!(map, expected);
+
+        // A second run is served from the cache.
+        assert!(cache_path.is_file());
+        assert_eq!(build_repo_map(&root, &config, &cache_path), Some(expected));
+    }
+}
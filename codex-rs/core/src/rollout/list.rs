@@ -321,7 +321,7 @@ async fn read_head_and_flags(
             RolloutItem::TurnContext(_) => {
                 // Not included in `head`; skip.
             }
-            RolloutItem::Compacted(_) => {
+            RolloutItem::Compacted(_) | RolloutItem::Approval(_) => {
                 // Not included in `head`; skip.
             }
             RolloutItem::EventMsg(ev) => {
@@ -6,6 +6,10 @@ pub const ARCHIVED_SESSIONS_SUBDIR: &str = "archived_sessions";
 pub mod list;
 pub(crate) mod policy;
 pub mod recorder;
+pub mod recovery;
+pub mod replay;
+pub mod share;
+pub mod stats;
 
 pub use codex_protocol::protocol::SessionMeta;
 pub use recorder::RolloutRecorder;
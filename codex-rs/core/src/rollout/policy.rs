@@ -9,9 +9,10 @@ pub(crate) fn is_persisted_response_item(item: &RolloutItem) -> bool {
         RolloutItem::ResponseItem(item) => should_persist_response_item(item),
         RolloutItem::EventMsg(ev) => should_persist_event_msg(ev),
         // Persist Codex executive markers so we can analyze flows (e.g., compaction, API turns).
-        RolloutItem::Compacted(_) | RolloutItem::TurnContext(_) | RolloutItem::SessionMeta(_) => {
-            true
-        }
+        RolloutItem::Compacted(_)
+        | RolloutItem::TurnContext(_)
+        | RolloutItem::SessionMeta(_)
+        | RolloutItem::Approval(_) => true,
     }
 }
 
@@ -40,7 +41,10 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::AgentReasoningRawContent(_)
         | EventMsg::TokenCount(_)
         | EventMsg::EnteredReviewMode(_)
-        | EventMsg::ExitedReviewMode(_) => true,
+        | EventMsg::ExitedReviewMode(_)
+        | EventMsg::ApprovalTimedOut(_)
+        | EventMsg::WorkspaceEscapeBlocked(_)
+        | EventMsg::PatchGuardBlocked(_) => true,
         EventMsg::Error(_)
         | EventMsg::TaskStarted(_)
         | EventMsg::TaskComplete(_)
@@ -66,7 +70,12 @@ pub(crate) fn should_persist_event_msg(ev: &EventMsg) -> bool {
         | EventMsg::GetHistoryEntryResponse(_)
         | EventMsg::McpListToolsResponse(_)
         | EventMsg::ListCustomPromptsResponse(_)
+        | EventMsg::McpResourceUpdated(_)
+        | EventMsg::PromptPreview(_)
         | EventMsg::PlanUpdate(_)
+        | EventMsg::SubtaskBegin(_)
+        | EventMsg::SubtaskProgress(_)
+        | EventMsg::SubtaskEnd(_)
         | EventMsg::TurnAborted(_)
         | EventMsg::ShutdownComplete
         | EventMsg::ConversationPath(_) => false,
@@ -0,0 +1,500 @@
+//! Crash recovery for interactive sessions.
+//!
+//! While a session is running, Codex keeps a small marker file under
+//! `~/.codex/recovery/` that points at the session's rollout. The marker is
+//! removed when the session shuts down cleanly, so a marker whose owning
+//! process is gone means Codex exited unexpectedly (power loss, terminal
+//! crash, `kill -9`, ...). Frontends use [`find_interrupted_session`] to offer
+//! resuming such a session on the next launch in the same directory.
+//!
+//! The marker also journals the patch that is being applied, if any. When the
+//! session is resumed, the files touched by that patch are compared against
+//! the journaled before/after states so the model can be told which parts of
+//! the patch actually landed on disk.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::ApplyPatchFileChange;
+use codex_apply_patch::read_text;
+use codex_protocol::mcp_protocol::ConversationId;
+use serde::Deserialize;
+use serde::Serialize;
+use sha1::Digest;
+use sha1::Sha1;
+use tracing::warn;
+
+pub const RECOVERY_SUBDIR: &str = "recovery";
+
+/// Contents of a recovery marker file.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct RecoveryMarker {
+    pub conversation_id: ConversationId,
+    pub rollout_path: PathBuf,
+    pub cwd: PathBuf,
+    pub pid: u32,
+    /// Patch that was being applied when the marker was last written.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub pending_patch: Option<PendingPatch>,
+}
+
+/// A patch whose application had started but not finished.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingPatch {
+    pub call_id: String,
+    pub files: Vec<PendingFileChange>,
+}
+
+/// Expected state of a single path before and after a patch. States are
+/// SHA-1 digests of the file contents; `None` means the file does not exist.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PendingFileChange {
+    pub path: PathBuf,
+    pub before: Option<String>,
+    pub after: Option<String>,
+}
+
+/// Where a file touched by an interrupted patch ended up.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FileRecoveryStatus {
+    /// The file matches the patched state.
+    Applied,
+    /// The file still matches the pre-patch state.
+    NotApplied,
+    /// The file matches neither state.
+    Diverged,
+}
+
+/// A session that did not shut down cleanly.
+#[derive(Debug, Clone, PartialEq)]
+pub struct InterruptedSession {
+    pub marker_path: PathBuf,
+    pub marker: RecoveryMarker,
+}
+
+impl PendingPatch {
+    pub(crate) fn from_action(call_id: &str, action: &ApplyPatchAction) -> Self {
+        let mut files = Vec::new();
+        for (path, change) in action.changes() {
+            match change {
+                ApplyPatchFileChange::Add { content } => files.push(PendingFileChange {
+                    path: path.clone(),
+                    before: digest_file(path),
+                    after: Some(digest(content.as_bytes())),
+                }),
+                ApplyPatchFileChange::Delete { content } => files.push(PendingFileChange {
+                    path: path.clone(),
+                    before: Some(digest(content.as_bytes())),
+                    after: None,
+                }),
+                ApplyPatchFileChange::Update {
+                    move_path,
+                    new_content,
+                    ..
+                } => {
+                    let before = digest_file(path);
+                    let after = Some(digest(new_content.as_bytes()));
+                    match move_path {
+                        Some(dest) => {
+                            files.push(PendingFileChange {
+                                path: path.clone(),
+                                before,
+                                after: None,
+                            });
+                            files.push(PendingFileChange {
+                                path: dest.clone(),
+                                before: digest_file(dest),
+                                after,
+                            });
+                        }
+                        None => files.push(PendingFileChange {
+                            path: path.clone(),
+                            before,
+                            after,
+                        }),
+                    }
+                }
+            }
+        }
+        files.sort_by(|a, b| a.path.cmp(&b.path));
+        Self {
+            call_id: call_id.to_string(),
+            files,
+        }
+    }
+
+    /// Compare each touched file on disk with its journaled states.
+    pub fn reconcile(&self) -> Vec<(PathBuf, FileRecoveryStatus)> {
+        self.files
+            .iter()
+            .map(|file| {
+                let current = digest_file(&file.path);
+                let status = if current == file.after {
+                    FileRecoveryStatus::Applied
+                } else if current == file.before {
+                    FileRecoveryStatus::NotApplied
+                } else {
+                    FileRecoveryStatus::Diverged
+                };
+                (file.path.clone(), status)
+            })
+            .collect()
+    }
+
+    /// Note for the model describing how far the patch got.
+    pub(crate) fn reconciliation_message(&self) -> String {
+        let statuses = self.reconcile();
+        let mut message = String::from(
+            "Codex exited unexpectedly while applying a patch in the previous session. \
+             Current state of the files it touched:\n",
+        );
+        for (path, status) in &statuses {
+            let status = match status {
+                FileRecoveryStatus::Applied => "applied",
+                FileRecoveryStatus::NotApplied => "not applied",
+                FileRecoveryStatus::Diverged => "changed since (neither original nor patched)",
+            };
+            message.push_str(&format!("- {}: {status}\n", path.display()));
+        }
+        if statuses
+            .iter()
+            .all(|(_, status)| *status == FileRecoveryStatus::Applied)
+        {
+            message.push_str("The patch was fully applied.");
+        } else {
+            message.push_str(
+                "The patch was not fully applied. Inspect these files and finish or redo the change before continuing.",
+            );
+        }
+        message
+    }
+}
+
+/// Owns the marker file for the running session.
+pub(crate) struct RecoveryJournal {
+    path: PathBuf,
+    marker: Mutex<RecoveryMarker>,
+}
+
+impl RecoveryJournal {
+    pub(crate) fn create(
+        codex_home: &Path,
+        conversation_id: ConversationId,
+        rollout_path: PathBuf,
+        cwd: PathBuf,
+    ) -> std::io::Result<Self> {
+        let dir = codex_home.join(RECOVERY_SUBDIR);
+        fs::create_dir_all(&dir)?;
+        let journal = Self {
+            path: marker_path(codex_home, conversation_id),
+            marker: Mutex::new(RecoveryMarker {
+                conversation_id,
+                rollout_path,
+                cwd,
+                pid: std::process::id(),
+                pending_patch: None,
+            }),
+        };
+        journal.update(|_| {})?;
+        Ok(journal)
+    }
+
+    /// Journal `patch` before any of its changes are written to disk.
+    pub(crate) fn begin_patch(&self, patch: PendingPatch) {
+        if let Err(e) = self.update(|marker| marker.pending_patch = Some(patch)) {
+            warn!("failed to journal pending patch: {e}");
+        }
+    }
+
+    pub(crate) fn end_patch(&self) {
+        if let Err(e) = self.update(|marker| marker.pending_patch = None) {
+            warn!("failed to clear pending patch: {e}");
+        }
+    }
+
+    /// The session shut down cleanly; nothing to recover.
+    pub(crate) fn complete(&self) {
+        if let Err(e) = fs::remove_file(&self.path)
+            && e.kind() != ErrorKind::NotFound
+        {
+            warn!("failed to remove recovery marker: {e}");
+        }
+    }
+
+    fn update(&self, f: impl FnOnce(&mut RecoveryMarker)) -> std::io::Result<()> {
+        let mut marker = match self.marker.lock() {
+            Ok(marker) => marker,
+            Err(poisoned) => poisoned.into_inner(),
+        };
+        f(&mut marker);
+        write_marker(&self.path, &marker)
+    }
+}
+
+/// Returns the most recent session started in `cwd` whose process exited
+/// without shutting down cleanly. Markers whose rollout no longer exists are
+/// discarded.
+pub fn find_interrupted_session(
+    codex_home: &Path,
+    cwd: &Path,
+) -> std::io::Result<Option<InterruptedSession>> {
+    let dir = codex_home.join(RECOVERY_SUBDIR);
+    let entries = match fs::read_dir(&dir) {
+        Ok(entries) => entries,
+        Err(e) if e.kind() == ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e),
+    };
+
+    let mut newest: Option<(std::time::SystemTime, InterruptedSession)> = None;
+    for entry in entries.flatten() {
+        let marker_path = entry.path();
+        if marker_path.extension().is_none_or(|ext| ext != "json") {
+            continue;
+        }
+        let Some(marker) = read_marker(&marker_path) else {
+            continue;
+        };
+        if marker.cwd != cwd || is_process_alive(marker.pid) {
+            continue;
+        }
+        if !marker.rollout_path.exists() {
+            let _ = fs::remove_file(&marker_path);
+            continue;
+        }
+        let modified = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .unwrap_or(std::time::UNIX_EPOCH);
+        if newest.as_ref().is_none_or(|(t, _)| modified > *t) {
+            newest = Some((
+                modified,
+                InterruptedSession {
+                    marker_path,
+                    marker,
+                },
+            ));
+        }
+    }
+    Ok(newest.map(|(_, session)| session))
+}
+
+/// Forget an interrupted session so it is not offered again.
+pub fn discard_interrupted_session(session: &InterruptedSession) -> std::io::Result<()> {
+    match fs::remove_file(&session.marker_path) {
+        Err(e) if e.kind() != ErrorKind::NotFound => Err(e),
+        _ => Ok(()),
+    }
+}
+
+/// Takes the marker left behind by a previous, crashed run of
+/// `conversation_id`, if any.
+pub(crate) fn take_stale_marker(
+    codex_home: &Path,
+    conversation_id: ConversationId,
+) -> Option<RecoveryMarker> {
+    let path = marker_path(codex_home, conversation_id);
+    let marker = read_marker(&path)?;
+    if is_process_alive(marker.pid) && marker.pid != std::process::id() {
+        return None;
+    }
+    let _ = fs::remove_file(&path);
+    Some(marker)
+}
+
+fn marker_path(codex_home: &Path, conversation_id: ConversationId) -> PathBuf {
+    codex_home
+        .join(RECOVERY_SUBDIR)
+        .join(format!("{conversation_id}.json"))
+}
+
+fn read_marker(path: &Path) -> Option<RecoveryMarker> {
+    let contents = fs::read_to_string(path).ok()?;
+    match serde_json::from_str(&contents) {
+        Ok(marker) => Some(marker),
+        Err(e) => {
+            warn!("ignoring malformed recovery marker {}: {e}", path.display());
+            None
+        }
+    }
+}
+
+/// Write via a temp file + rename so a crash mid-write never leaves a torn
+/// marker behind.
+fn write_marker(path: &Path, marker: &RecoveryMarker) -> std::io::Result<()> {
+    let dir = path
+        .parent()
+        .ok_or_else(|| std::io::Error::other("recovery marker has no parent"))?;
+    let json = serde_json::to_vec(marker)?;
+    let mut tmp = tempfile::NamedTempFile::new_in(dir)?;
+    tmp.write_all(&json)?;
+    tmp.as_file().sync_all()?;
+    tmp.persist(path).map_err(|e| e.error)?;
+    Ok(())
+}
+
+fn digest(bytes: &[u8]) -> String {
+    let mut hasher = Sha1::new();
+    hasher.update(bytes);
+    format!("{:x}", hasher.finalize())
+}
+
+/// Digest of the file's text as `apply_patch` reads it, which is what the
+/// journaled contents are in.
+fn digest_file(path: &Path) -> Option<String> {
+    read_text(path)
+        .ok()
+        .map(|(text, _)| digest(text.as_bytes()))
+}
+
+#[cfg(unix)]
+pub(crate) fn is_process_alive(pid: u32) -> bool {
+    let Ok(pid) = libc::pid_t::try_from(pid) else {
+        return false;
+    };
+    // SAFETY: signal 0 performs error checking only; nothing is delivered.
+    let result = unsafe { libc::kill(pid, 0) };
+    result == 0 || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+#[cfg(windows)]
+pub(crate) fn is_process_alive(pid: u32) -> bool {
+    use windows_sys::Win32::Foundation::CloseHandle;
+    use windows_sys::Win32::Foundation::ERROR_ACCESS_DENIED;
+    use windows_sys::Win32::Foundation::STILL_ACTIVE;
+    use windows_sys::Win32::System::Threading::GetExitCodeProcess;
+    use windows_sys::Win32::System::Threading::OpenProcess;
+    use windows_sys::Win32::System::Threading::PROCESS_QUERY_LIMITED_INFORMATION;
+
+    // SAFETY: the handle is only used while open and closed exactly once.
+    unsafe {
+        let handle = OpenProcess(PROCESS_QUERY_LIMITED_INFORMATION, 0, pid);
+        if handle.is_null() {
+            // Another user's process exists but cannot be opened.
+            return std::io::Error::last_os_error().raw_os_error()
+                == Some(ERROR_ACCESS_DENIED as i32);
+        }
+        let mut exit_code = 0;
+        let queried = GetExitCodeProcess(handle, &mut exit_code);
+        CloseHandle(handle);
+        queried != 0 && exit_code == STILL_ACTIVE as u32
+    }
+}
+
+/// Without a way to ask, every process is assumed to be alive, so a running
+/// session is never mistaken for a crashed one.
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn is_process_alive(_pid: u32) -> bool {
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn dead_marker(codex_home: &Path, cwd: &Path, rollout_path: &Path) -> PathBuf {
+        let conversation_id = ConversationId::default();
+        let path = marker_path(codex_home, conversation_id);
+        fs::create_dir_all(codex_home.join(RECOVERY_SUBDIR)).expect("mkdir");
+        write_marker(
+            &path,
+            &RecoveryMarker {
+                conversation_id,
+                rollout_path: rollout_path.to_path_buf(),
+                cwd: cwd.to_path_buf(),
+                // pid_t::MAX is never a live process.
+                pid: i32::MAX as u32,
+                pending_patch: None,
+            },
+        )
+        .expect("write marker");
+        path
+    }
+
+    #[test]
+    fn clean_shutdown_leaves_nothing_to_recover() {
+        let home = TempDir::new().expect("tempdir");
+        let rollout = home.path().join("rollout.jsonl");
+        fs::write(&rollout, "").expect("write rollout");
+        let journal = RecoveryJournal::create(
+            home.path(),
+            ConversationId::default(),
+            rollout,
+            PathBuf::from("/work"),
+        )
+        .expect("create journal");
+
+        // Our own (live) process is never reported as interrupted.
+        assert_eq!(
+            find_interrupted_session(home.path(), Path::new("/work")).expect("scan"),
+            None
+        );
+        journal.complete();
+        assert_eq!(
+            fs::read_dir(home.path().join(RECOVERY_SUBDIR))
+                .expect("read dir")
+                .count(),
+            0
+        );
+    }
+
+    #[test]
+    fn finds_interrupted_session_for_cwd() {
+        let home = TempDir::new().expect("tempdir");
+        let rollout = home.path().join("rollout.jsonl");
+        fs::write(&rollout, "").expect("write rollout");
+        let marker_path = dead_marker(home.path(), Path::new("/work"), &rollout);
+
+        assert_eq!(
+            find_interrupted_session(home.path(), Path::new("/elsewhere")).expect("scan"),
+            None
+        );
+        let found = find_interrupted_session(home.path(), Path::new("/work"))
+            .expect("scan")
+            .expect("interrupted session");
+        assert_eq!(found.marker_path, marker_path);
+        assert_eq!(found.marker.rollout_path, rollout);
+
+        discard_interrupted_session(&found).expect("discard");
+        assert_eq!(
+            find_interrupted_session(home.path(), Path::new("/work")).expect("scan"),
+            None
+        );
+    }
+
+    #[test]
+    fn reconcile_reports_partially_applied_patch() {
+        let dir = TempDir::new().expect("tempdir");
+        let done = dir.path().join("done.txt");
+        let pending = dir.path().join("pending.txt");
+        let edited = dir.path().join("edited.txt");
+        fs::write(&done, "new\n").expect("write");
+        fs::write(&pending, "old\n").expect("write");
+        fs::write(&edited, "something else\n").expect("write");
+
+        let file = |path: &Path| PendingFileChange {
+            path: path.to_path_buf(),
+            before: Some(digest(b"old\n")),
+            after: Some(digest(b"new\n")),
+        };
+        let patch = PendingPatch {
+            call_id: "call-1".to_string(),
+            files: vec![file(&done), file(&pending), file(&edited)],
+        };
+
+        assert_eq!(
+            patch.reconcile(),
+            vec![
+                (done, FileRecoveryStatus::Applied),
+                (pending, FileRecoveryStatus::NotApplied),
+                (edited, FileRecoveryStatus::Diverged),
+            ]
+        );
+    }
+}
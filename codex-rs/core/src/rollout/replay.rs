@@ -0,0 +1,234 @@
+//! Reading a recorded rollout back for `codex replay`.
+//!
+//! Unlike [`super::RolloutRecorder::get_rollout_history`], which only needs the
+//! items to rebuild a conversation, replay keeps each line's timestamp so the
+//! session can be stepped through with its original timing.
+
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use codex_protocol::models::ContentItem;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::protocol::InputMessageKind;
+use codex_protocol::protocol::RolloutItem;
+use codex_protocol::protocol::RolloutLine;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::warn;
+
+use super::SESSIONS_SUBDIR;
+
+/// One recorded rollout line.
+#[derive(Debug, Clone)]
+pub struct ReplayEntry {
+    /// Time since the first timestamped line of the session.
+    pub offset: Duration,
+    pub item: RolloutItem,
+}
+
+/// A user message that started a turn, as seen in the recorded history.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct UserTurn {
+    /// Index into the replay entries where the turn starts.
+    pub entry_index: usize,
+    /// Value to pass as `nth_user_message` to
+    /// [`crate::ConversationManager::fork_conversation`] to cut the history
+    /// just before this turn.
+    pub nth_user_message: usize,
+    pub text: String,
+}
+
+/// Resolve `session` to a rollout file: either a path to one, or a
+/// conversation id (or unique prefix of one) recorded under `codex_home`.
+pub fn resolve_rollout_path(codex_home: &Path, session: &str) -> io::Result<PathBuf> {
+    let as_path = PathBuf::from(session);
+    if as_path.is_file() {
+        return Ok(as_path);
+    }
+
+    let sessions_dir = codex_home.join(SESSIONS_SUBDIR);
+    let mut matches: Vec<PathBuf> = walkdir::WalkDir::new(&sessions_dir)
+        .into_iter()
+        .filter_map(Result::ok)
+        .filter(|entry| entry.file_type().is_file())
+        .filter(|entry| {
+            let name = entry.file_name().to_string_lossy();
+            name.starts_with("rollout-")
+                && name.ends_with(".jsonl")
+                && rollout_id(&name).is_some_and(|id| id.starts_with(session))
+        })
+        .map(|entry| entry.into_path())
+        .collect();
+    match matches.len() {
+        0 => Err(io::Error::new(
+            io::ErrorKind::NotFound,
+            format!("no recorded session matches {session}"),
+        )),
+        1 => Ok(matches.remove(0)),
+        n => Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("{n} recorded sessions match {session}; use a longer id"),
+        )),
+    }
+}
+
+/// Extract the conversation id from `rollout-YYYY-MM-DDThh-mm-ss-<uuid>.jsonl`.
+fn rollout_id(file_name: &str) -> Option<&str> {
+    let core = file_name.strip_prefix("rollout-")?.strip_suffix(".jsonl")?;
+    // The timestamp is fixed width: "YYYY-MM-DDThh-mm-ss-".
+    core.get(20..)
+}
+
+/// Read every line of a rollout file, keeping timing information.
+pub async fn read_replay(path: &Path) -> io::Result<Vec<ReplayEntry>> {
+    let text = tokio::fs::read_to_string(path).await?;
+    Ok(parse_replay(&text))
+}
+
+pub(super) fn parse_replay(text: &str) -> Vec<ReplayEntry> {
+    let mut start: Option<OffsetDateTime> = None;
+    let mut entries = Vec::new();
+    for line in text.lines().filter(|line| !line.trim().is_empty()) {
+        let rollout_line = match serde_json::from_str::<RolloutLine>(line) {
+            Ok(rollout_line) => rollout_line,
+            Err(e) => {
+                warn!("skipping unparsable rollout line: {e}");
+                continue;
+            }
+        };
+        let offset = match OffsetDateTime::parse(&rollout_line.timestamp, &Rfc3339) {
+            Ok(ts) => {
+                let start = *start.get_or_insert(ts);
+                Duration::try_from(ts - start).unwrap_or_default()
+            }
+            // Keep lines without a usable timestamp at the previous offset.
+            Err(_) => entries
+                .last()
+                .map(|entry: &ReplayEntry| entry.offset)
+                .unwrap_or_default(),
+        };
+        entries.push(ReplayEntry {
+            offset,
+            item: rollout_line.item,
+        });
+    }
+    entries
+}
+
+/// The user messages that started each turn, skipping the instructions and
+/// environment context Codex injects at the start of a session.
+pub fn user_turns(entries: &[ReplayEntry]) -> Vec<UserTurn> {
+    let mut turns = Vec::new();
+    let mut nth_user_message = 0;
+    for (entry_index, entry) in entries.iter().enumerate() {
+        let RolloutItem::ResponseItem(ResponseItem::Message { role, content, .. }) = &entry.item
+        else {
+            continue;
+        };
+        if role != "user" {
+            continue;
+        }
+        let text = content
+            .iter()
+            .filter_map(|item| match item {
+                ContentItem::InputText { text } | ContentItem::OutputText { text } => {
+                    Some(text.as_str())
+                }
+                ContentItem::InputImage { .. } => None,
+            })
+            .collect::<Vec<_>>()
+            .join("\n");
+        if matches!(
+            InputMessageKind::from(("user", text.as_str())),
+            InputMessageKind::Plain
+        ) {
+            turns.push(UserTurn {
+                entry_index,
+                nth_user_message,
+                text,
+            });
+        }
+        nth_user_message += 1;
+    }
+    turns
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn line(timestamp: &str, role: &str, text: &str) -> String {
+        serde_json::json!({
+            "timestamp": timestamp,
+            "type": "response_item",
+            "payload": {
+                "type": "message",
+                "role": role,
+                "content": [{ "type": "input_text", "text": text }],
+            },
+        })
+        .to_string()
+    }
+
+    #[test]
+    fn parses_offsets_and_user_turns() {
+        let text = [
+            line(
+                "2025-09-01T10:00:00.000Z",
+                "user",
+                "<environment_context>\n</environment_context>",
+            ),
+            line("2025-09-01T10:00:01.500Z", "user", "fix the tests"),
+            line("2025-09-01T10:00:04.000Z", "assistant", "done"),
+            "not json".to_string(),
+            line("2025-09-01T10:01:00.000Z", "user", "now the docs"),
+        ]
+        .join("\n");
+
+        let entries = parse_replay(&text);
+        assert_eq!(
+            entries.iter().map(|e| e.offset).collect::<Vec<_>>(),
+            vec![
+                Duration::ZERO,
+                Duration::from_millis(1500),
+                Duration::from_secs(4),
+                Duration::from_secs(60),
+            ]
+        );
+        assert_eq!(
+            user_turns(&entries),
+            vec![
+                UserTurn {
+                    entry_index: 1,
+                    nth_user_message: 1,
+                    text: "fix the tests".to_string(),
+                },
+                UserTurn {
+                    entry_index: 3,
+                    nth_user_message: 2,
+                    text: "now the docs".to_string(),
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn resolves_session_by_id_prefix() {
+        let home = TempDir::new().expect("tempdir");
+        let day = home.path().join(SESSIONS_SUBDIR).join("2025/09/01");
+        std::fs::create_dir_all(&day).expect("mkdir");
+        let path =
+            day.join("rollout-2025-09-01T10-00-00-0199a1b2-c3d4-7e5f-8a9b-0c1d2e3f4a5b.jsonl");
+        std::fs::write(&path, "").expect("write");
+
+        assert_eq!(
+            resolve_rollout_path(home.path(), "0199a1b2").expect("resolve"),
+            path
+        );
+        assert!(resolve_rollout_path(home.path(), "ffff").is_err());
+    }
+}
@@ -0,0 +1,474 @@
+//! Usage analytics over recorded sessions for `codex stats`.
+//!
+//! Everything is computed from the rollout files under `CODEX_HOME`, including
+//! archived sessions; nothing leaves the machine. Costs are estimates from
+//! per-model token prices: a built-in table of list prices that
+//! `[model_prices]` entries extend or override.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::io;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::SystemTime;
+
+use codex_apply_patch::MaybeApplyPatch;
+use codex_apply_patch::maybe_parse_apply_patch;
+use codex_protocol::models::LocalShellAction;
+use codex_protocol::models::ResponseItem;
+use codex_protocol::models::ShellToolCallParams;
+use codex_protocol::protocol::ApprovalItem;
+use codex_protocol::protocol::ApprovalKind;
+use codex_protocol::protocol::EventMsg;
+use codex_protocol::protocol::ReviewDecision;
+use codex_protocol::protocol::RolloutItem;
+use serde::Serialize;
+use time::OffsetDateTime;
+use time::format_description::well_known::Rfc3339;
+use tracing::warn;
+
+use super::ARCHIVED_SESSIONS_SUBDIR;
+use super::SESSIONS_SUBDIR;
+use super::replay::parse_replay;
+use super::replay::user_turns;
+use crate::config_types::ModelPrice;
+use crate::tool_apply_patch::ApplyPatchToolArgs;
+
+/// List prices in US dollars per million tokens, matched by slug prefix.
+const BUILT_IN_PRICES: &[(&str, ModelPrice)] = &[
+    ("gpt-5", price(1.25, 0.125, 10.0)),
+    ("gpt-5-mini", price(0.25, 0.025, 2.0)),
+    ("gpt-5-nano", price(0.05, 0.005, 0.4)),
+    ("gpt-4.1", price(2.0, 0.5, 8.0)),
+    ("gpt-4.1-mini", price(0.4, 0.1, 1.6)),
+    ("o3", price(2.0, 0.5, 8.0)),
+    ("o4-mini", price(1.1, 0.275, 4.4)),
+    ("codex-mini-latest", price(1.5, 0.375, 6.0)),
+];
+
+const fn price(input: f64, cached_input: f64, output: f64) -> ModelPrice {
+    ModelPrice {
+        input,
+        cached_input: Some(cached_input),
+        output,
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct UsageStats {
+    pub sessions: usize,
+    /// Sessions started in each ISO week, keyed `YYYY-Www`.
+    pub sessions_per_week: BTreeMap<String, usize>,
+    pub models: BTreeMap<String, ModelUsage>,
+    /// Files changed by patches that applied, most edited first.
+    pub edited_files: Vec<EditedFile>,
+    /// User messages that started a turn.
+    pub tasks: usize,
+    /// Requests sent to a model.
+    pub model_turns: usize,
+    pub exec_approvals: ApprovalCounts,
+    pub patch_approvals: ApprovalCounts,
+}
+
+impl UsageStats {
+    /// Average model requests per task.
+    pub fn turns_per_task(&self) -> Option<f64> {
+        (self.tasks > 0).then(|| self.model_turns as f64 / self.tasks as f64)
+    }
+
+    /// Estimated cost of all models that have a known price.
+    pub fn total_cost_usd(&self) -> Option<f64> {
+        let costs: Vec<f64> = self.models.values().filter_map(|m| m.cost_usd).collect();
+        (!costs.is_empty()).then(|| costs.iter().sum())
+    }
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize)]
+pub struct ModelUsage {
+    /// Input tokens, including those served from the prompt cache.
+    pub input_tokens: u64,
+    pub cached_input_tokens: u64,
+    /// Output tokens, including reasoning.
+    pub output_tokens: u64,
+    /// `None` when no price is known for the model.
+    pub cost_usd: Option<f64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+pub struct EditedFile {
+    pub path: PathBuf,
+    pub edits: usize,
+}
+
+/// Answers to approval requests of one kind.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq, Serialize)]
+pub struct ApprovalCounts {
+    pub approved: usize,
+    pub denied: usize,
+    /// Requests nobody answered before `approval_timeout_secs`.
+    pub timed_out: usize,
+}
+
+impl ApprovalCounts {
+    pub fn total(&self) -> usize {
+        self.approved + self.denied + self.timed_out
+    }
+
+    /// Share of requests that were approved.
+    pub fn approval_rate(&self) -> Option<f64> {
+        let total = self.total();
+        (total > 0).then(|| self.approved as f64 / total as f64)
+    }
+
+    fn record(&mut self, approval: &ApprovalItem) {
+        match approval.decision {
+            _ if approval.timed_out => self.timed_out += 1,
+            ReviewDecision::Approved | ReviewDecision::ApprovedForSession => self.approved += 1,
+            ReviewDecision::Denied | ReviewDecision::Abort => self.denied += 1,
+        }
+    }
+}
+
+/// Aggregate every session recorded under `codex_home` that started at or
+/// after `since`. `prices` take precedence over the built-in table.
+pub fn collect_usage_stats(
+    codex_home: &Path,
+    since: Option<SystemTime>,
+    prices: &HashMap<String, ModelPrice>,
+) -> io::Result<UsageStats> {
+    let since = since.map(OffsetDateTime::from);
+    let mut stats = UsageStats::default();
+    let mut edits: HashMap<PathBuf, usize> = HashMap::new();
+    for subdir in [SESSIONS_SUBDIR, ARCHIVED_SESSIONS_SUBDIR] {
+        let rollouts = walkdir::WalkDir::new(codex_home.join(subdir))
+            .into_iter()
+            .filter_map(Result::ok)
+            .filter(|entry| {
+                let name = entry.file_name().to_string_lossy();
+                entry.file_type().is_file()
+                    && name.starts_with("rollout-")
+                    && name.ends_with(".jsonl")
+            });
+        for entry in rollouts {
+            // One unreadable rollout should not hide all the others.
+            let text = match std::fs::read_to_string(entry.path()) {
+                Ok(text) => text,
+                Err(e) => {
+                    warn!("skipping rollout {}: {e}", entry.path().display());
+                    continue;
+                }
+            };
+            add_session(&mut stats, &mut edits, &text, since);
+        }
+    }
+
+    for (model, usage) in stats.models.iter_mut() {
+        usage.cost_usd = find_price(model, prices).map(|price| cost_usd(usage, &price));
+    }
+    let mut edited_files: Vec<EditedFile> = edits
+        .into_iter()
+        .map(|(path, edits)| EditedFile { path, edits })
+        .collect();
+    edited_files.sort_by(|a, b| b.edits.cmp(&a.edits).then_with(|| a.path.cmp(&b.path)));
+    stats.edited_files = edited_files;
+    Ok(stats)
+}
+
+fn add_session(
+    stats: &mut UsageStats,
+    edits: &mut HashMap<PathBuf, usize>,
+    text: &str,
+    since: Option<OffsetDateTime>,
+) {
+    let entries = parse_replay(text);
+    let Some(meta) = entries.iter().find_map(|entry| match &entry.item {
+        RolloutItem::SessionMeta(meta) => Some(&meta.meta),
+        _ => None,
+    }) else {
+        return;
+    };
+    let started = OffsetDateTime::parse(&meta.timestamp, &Rfc3339).ok();
+    if let Some(since) = since
+        && started.is_none_or(|started| started < since)
+    {
+        return;
+    }
+
+    stats.sessions += 1;
+    if let Some(started) = started {
+        let (year, week, _) = started.to_iso_week_date();
+        *stats
+            .sessions_per_week
+            .entry(format!("{year}-W{week:02}"))
+            .or_default() += 1;
+    }
+    stats.tasks += user_turns(&entries).len();
+
+    let mut model: Option<&str> = None;
+    let mut cwd = meta.cwd.clone();
+    // Files each patch call changes, until its output shows whether it applied.
+    let mut pending_patches: HashMap<&str, Vec<PathBuf>> = HashMap::new();
+    for entry in &entries {
+        match &entry.item {
+            RolloutItem::TurnContext(ctx) => {
+                stats.model_turns += 1;
+                model = Some(ctx.model.as_str());
+                cwd = ctx.cwd.clone();
+            }
+            RolloutItem::EventMsg(EventMsg::TokenCount(event)) => {
+                if let (Some(model), Some(info)) = (model, &event.info) {
+                    let usage = stats.models.entry(model.to_string()).or_default();
+                    let last = &info.last_token_usage;
+                    usage.input_tokens += last.input_tokens;
+                    usage.cached_input_tokens += last.cached_input_tokens;
+                    usage.output_tokens += last.output_tokens;
+                }
+            }
+            RolloutItem::Approval(approval) => match approval.kind {
+                ApprovalKind::Exec => stats.exec_approvals.record(approval),
+                ApprovalKind::Patch => stats.patch_approvals.record(approval),
+            },
+            RolloutItem::ResponseItem(item) => match item {
+                ResponseItem::FunctionCallOutput { call_id, output } => {
+                    if let Some(paths) = pending_patches.remove(call_id.as_str())
+                        && output.success != Some(false)
+                    {
+                        for path in paths {
+                            *edits.entry(path).or_default() += 1;
+                        }
+                    }
+                }
+                ResponseItem::CustomToolCallOutput { call_id, .. } => {
+                    if let Some(paths) = pending_patches.remove(call_id.as_str()) {
+                        for path in paths {
+                            *edits.entry(path).or_default() += 1;
+                        }
+                    }
+                }
+                _ => {
+                    if let Some((call_id, paths)) = patched_paths(item, &cwd) {
+                        pending_patches.insert(call_id, paths);
+                    }
+                }
+            },
+            _ => {}
+        }
+    }
+}
+
+/// The call id and target files of a tool call that applies a patch.
+fn patched_paths<'a>(item: &'a ResponseItem, cwd: &Path) -> Option<(&'a str, Vec<PathBuf>)> {
+    let (call_id, argv, workdir) = match item {
+        ResponseItem::CustomToolCall {
+            name,
+            input,
+            call_id,
+            ..
+        } if name == "apply_patch" => (
+            call_id,
+            vec!["apply_patch".to_string(), input.clone()],
+            None,
+        ),
+        ResponseItem::FunctionCall {
+            name,
+            arguments,
+            call_id,
+            ..
+        } => match name.as_str() {
+            "apply_patch" => {
+                let args: ApplyPatchToolArgs = serde_json::from_str(arguments).ok()?;
+                (call_id, vec!["apply_patch".to_string(), args.input], None)
+            }
+            "shell" | "container.exec" => {
+                let params: ShellToolCallParams = serde_json::from_str(arguments).ok()?;
+                (call_id, params.command, params.workdir)
+            }
+            _ => return None,
+        },
+        ResponseItem::LocalShellCall {
+            call_id: Some(call_id),
+            action: LocalShellAction::Exec(action),
+            ..
+        } => (
+            call_id,
+            action.command.clone(),
+            action.working_directory.clone(),
+        ),
+        _ => return None,
+    };
+    let MaybeApplyPatch::Body(args) = maybe_parse_apply_patch(&argv) else {
+        return None;
+    };
+    let mut dir = workdir.map_or_else(|| cwd.to_path_buf(), |workdir| cwd.join(workdir));
+    if let Some(patch_dir) = &args.workdir {
+        dir = dir.join(patch_dir);
+    }
+    let paths = args
+        .hunks
+        .iter()
+        .map(|hunk| hunk.resolve_path(&dir))
+        .collect();
+    Some((call_id.as_str(), paths))
+}
+
+/// The price for `model`: the longest matching slug prefix, preferring
+/// `configured` prices over built-in ones.
+fn find_price(model: &str, configured: &HashMap<String, ModelPrice>) -> Option<ModelPrice> {
+    longest_prefix(
+        model,
+        configured
+            .iter()
+            .map(|(slug, price)| (slug.as_str(), *price)),
+    )
+    .or_else(|| longest_prefix(model, BUILT_IN_PRICES.iter().copied()))
+}
+
+fn longest_prefix<'a>(
+    model: &str,
+    prices: impl Iterator<Item = (&'a str, ModelPrice)>,
+) -> Option<ModelPrice> {
+    prices
+        .filter(|(slug, _)| model.starts_with(slug))
+        .max_by_key(|(slug, _)| slug.len())
+        .map(|(_, price)| price)
+}
+
+fn cost_usd(usage: &ModelUsage, price: &ModelPrice) -> f64 {
+    let cached = usage.cached_input_tokens.min(usage.input_tokens);
+    let uncached = usage.input_tokens - cached;
+    let cost = |tokens: u64, per_million: f64| tokens as f64 * per_million / 1_000_000.0;
+    cost(uncached, price.input)
+        + cost(cached, price.cached_input.unwrap_or(price.input))
+        + cost(usage.output_tokens, price.output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn line(timestamp: &str, item: serde_json::Value) -> String {
+        let mut value = item;
+        value["timestamp"] = serde_json::json!(timestamp);
+        value.to_string()
+    }
+
+    #[test]
+    fn aggregates_sessions_tokens_edits_and_approvals() {
+        let home = tempfile::tempdir().expect("tempdir");
+        let dir = home.path().join(SESSIONS_SUBDIR).join("2025/09/15");
+        std::fs::create_dir_all(&dir).expect("mkdir");
+        let ts = "2025-09-15T10:00:00.000Z";
+        let patch = "*** Begin Patch\n*** Add File: src/new.rs\n+fn main() {}\n*** End Patch";
+        let lines = [
+            line(
+                ts,
+                serde_json::json!({"type": "session_meta", "payload": {
+                    "id": "0199a213-81c0-7800-8aa1-bbab2a035a53",
+                    "timestamp": ts, "cwd": "/repo", "originator": "test",
+                    "cli_version": "0.0.0", "instructions": null}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "response_item", "payload": {
+                    "type": "message", "role": "user",
+                    "content": [{"type": "input_text", "text": "add a binary"}]}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "turn_context", "payload": {
+                    "cwd": "/repo", "approval_policy": "on-request",
+                    "sandbox_policy": {"mode": "read-only"}, "model": "gpt-5-codex",
+                    "summary": "auto"}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "response_item", "payload": {
+                    "type": "custom_tool_call", "call_id": "p1",
+                    "name": "apply_patch", "input": patch}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "approval", "payload": {
+                    "call_id": "p1", "kind": "patch", "decision": "approved"}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "response_item", "payload": {
+                    "type": "custom_tool_call_output", "call_id": "p1", "output": "Done"}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "event_msg", "payload": {
+                    "type": "token_count", "info": {
+                        "total_token_usage": {"input_tokens": 0, "cached_input_tokens": 0,
+                            "output_tokens": 0, "reasoning_output_tokens": 0, "total_tokens": 0},
+                        "last_token_usage": {"input_tokens": 1_000_000,
+                            "cached_input_tokens": 0, "output_tokens": 100_000,
+                            "reasoning_output_tokens": 0, "total_tokens": 1_100_000},
+                        "model_context_window": null}}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "turn_context", "payload": {
+                    "cwd": "/repo", "approval_policy": "on-request",
+                    "sandbox_policy": {"mode": "read-only"}, "model": "gpt-5-codex",
+                    "summary": "auto"}}),
+            ),
+            line(
+                ts,
+                serde_json::json!({"type": "approval", "payload": {
+                    "call_id": "c1", "kind": "exec", "decision": "denied",
+                    "timed_out": true}}),
+            ),
+        ];
+        std::fs::write(
+            dir.join("rollout-2025-09-15T10-00-00-0199a213-81c0-7800-8aa1-bbab2a035a53.jsonl"),
+            lines.join("\n"),
+        )
+        .expect("write");
+        // A rollout that cannot be read as text is skipped.
+        std::fs::write(
+            dir.join("rollout-2025-09-15T11-00-00-0199a213-81c0-7800-8aa1-bbab2a035a54.jsonl"),
+            [0xff, 0xfe, 0xfd],
+        )
+        .expect("write");
+
+        let stats = collect_usage_stats(home.path(), None, &HashMap::new()).expect("stats");
+        assert_eq!(stats.sessions, 1);
+        assert_eq!(
+            stats.sessions_per_week,
+            BTreeMap::from([("2025-W38".to_string(), 1)])
+        );
+        assert_eq!(
+            stats.models.get("gpt-5-codex"),
+            Some(&ModelUsage {
+                input_tokens: 1_000_000,
+                cached_input_tokens: 0,
+                output_tokens: 100_000,
+                cost_usd: Some(2.25),
+            })
+        );
+        assert_eq!(
+            stats.edited_files,
+            vec![EditedFile {
+                path: PathBuf::from("/repo/src/new.rs"),
+                edits: 1,
+            }]
+        );
+        assert_eq!(stats.turns_per_task(), Some(2.0));
+        assert_eq!(stats.patch_approvals.approval_rate(), Some(1.0));
+        assert_eq!(
+            stats.exec_approvals,
+            ApprovalCounts {
+                approved: 0,
+                denied: 0,
+                timed_out: 1,
+            }
+        );
+
+        let stats = collect_usage_stats(home.path(), Some(SystemTime::now()), &HashMap::new())
+            .expect("stats");
+        assert_eq!(stats.sessions, 0);
+    }
+}
@@ -166,6 +166,30 @@ pub(crate) fn assess_safety_for_untrusted_command(
     }
 }
 
+/// Safety check for read-only sessions (`codex ask`). Nothing is ever sent
+/// for approval: commands known to be read-only run as usual, everything else
+/// runs under the platform sandbox (with a read-only policy) or, where there
+/// is none, is refused.
+pub fn assess_read_only_command_safety(
+    command: &[String],
+    linux_sandbox_available: bool,
+) -> SafetyCheck {
+    if is_known_safe_command(command) {
+        return SafetyCheck::AutoApprove {
+            sandbox_type: SandboxType::None,
+        };
+    }
+    match get_platform_sandbox() {
+        Some(SandboxType::LinuxSeccomp) if !linux_sandbox_available => SafetyCheck::Reject {
+            reason: "read-only mode: the Linux sandbox is unavailable, so only commands known to be read-only can run".to_string(),
+        },
+        Some(sandbox_type) => SafetyCheck::AutoApprove { sandbox_type },
+        None => SafetyCheck::Reject {
+            reason: "read-only mode: no sandbox is available on this platform, so only commands known to be read-only can run".to_string(),
+        },
+    }
+}
+
 pub fn get_platform_sandbox() -> Option<SandboxType> {
     if cfg!(target_os = "macos") {
         Some(SandboxType::MacosSeatbelt)
@@ -305,6 +329,27 @@ mod tests {
         ));
     }
 
+    #[test]
+    fn read_only_safety_never_asks() {
+        let ls = vec!["ls".to_string()];
+        assert_eq!(
+            assess_read_only_command_safety(&ls, false),
+            SafetyCheck::AutoApprove {
+                sandbox_type: SandboxType::None
+            }
+        );
+
+        let touch = vec!["touch".to_string(), "x".to_string()];
+        let expected_sandbox = get_platform_sandbox();
+        match assess_read_only_command_safety(&touch, true) {
+            SafetyCheck::AutoApprove { sandbox_type } => {
+                assert_eq!(Some(sandbox_type), expected_sandbox);
+            }
+            SafetyCheck::Reject { .. } => assert_eq!(expected_sandbox, None),
+            SafetyCheck::AskUser => panic!("read-only mode must not ask"),
+        }
+    }
+
     #[test]
     fn test_request_escalated_privileges() {
         // Should not be a trusted command
@@ -0,0 +1,188 @@
+//! Per-session scratch directories.
+//!
+//! Every session gets `~/.codex/scratch/<conversation id>/`, a place for
+//! temporary artifacts that is writable under the `workspace-write` sandbox
+//! even when `/tmp` is excluded, so the agent has no reason to litter the
+//! repository. The model learns the path from the environment context and
+//! from `CODEX_SCRATCH_DIR` in the environment of the commands it runs.
+//! Read-only sessions get the directory too but cannot write to it, so the
+//! model is not told about it there. The directory is removed when the
+//! session ends; directories left behind by sessions that did not shut down
+//! cleanly are removed once they go stale and their owner has exited.
+
+use std::fs;
+use std::io::ErrorKind;
+use std::path::Path;
+use std::path::PathBuf;
+use std::time::Duration;
+use std::time::SystemTime;
+
+use codex_protocol::mcp_protocol::ConversationId;
+use tracing::warn;
+
+use crate::protocol::SandboxPolicy;
+use crate::rollout::recovery::is_process_alive;
+
+pub const SCRATCH_SUBDIR: &str = "scratch";
+
+/// Environment variable holding the scratch directory in tool subprocesses.
+pub const CODEX_SCRATCH_DIR_ENV_VAR: &str = "CODEX_SCRATCH_DIR";
+
+/// Scratch directories untouched for this long belong to sessions that
+/// exited without cleaning up.
+const STALE_AFTER: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+#[derive(Debug)]
+pub(crate) struct ScratchDir {
+    path: PathBuf,
+}
+
+impl ScratchDir {
+    /// Create the scratch directory for `conversation_id`, clearing out stale
+    /// ones first.
+    pub(crate) fn create(
+        codex_home: &Path,
+        conversation_id: ConversationId,
+    ) -> std::io::Result<Self> {
+        let root = codex_home.join(SCRATCH_SUBDIR);
+        prune_stale(&root, STALE_AFTER);
+        let path = root.join(conversation_id.to_string());
+        fs::create_dir_all(&path)?;
+        // Kept next to the directory rather than in it, where commands could
+        // delete it.
+        fs::write(owner_file(&path), std::process::id().to_string())?;
+        Ok(Self { path })
+    }
+
+    pub(crate) fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// `policy` with the scratch directory added to its writable roots. A
+    /// read-only policy stays read-only, and full access needs no change.
+    pub(crate) fn extend_sandbox_policy(&self, policy: SandboxPolicy) -> SandboxPolicy {
+        match policy {
+            SandboxPolicy::WorkspaceWrite {
+                mut writable_roots,
+                network_access,
+                exclude_tmpdir_env_var,
+                exclude_slash_tmp,
+            } => {
+                if !writable_roots.contains(&self.path) {
+                    writable_roots.push(self.path.clone());
+                }
+                SandboxPolicy::WorkspaceWrite {
+                    writable_roots,
+                    network_access,
+                    exclude_tmpdir_env_var,
+                    exclude_slash_tmp,
+                }
+            }
+            other => other,
+        }
+    }
+
+    /// Delete the directory and everything in it.
+    pub(crate) fn remove(&self) {
+        match fs::remove_dir_all(&self.path) {
+            Ok(()) => {}
+            Err(e) if e.kind() == ErrorKind::NotFound => {}
+            Err(e) => warn!("failed to remove scratch dir {}: {e}", self.path.display()),
+        }
+        let _ = fs::remove_file(owner_file(&self.path));
+    }
+}
+
+impl Drop for ScratchDir {
+    fn drop(&mut self) {
+        self.remove();
+    }
+}
+
+/// `<dir>.pid`, holding the pid of the process that owns `dir`.
+fn owner_file(dir: &Path) -> PathBuf {
+    dir.with_extension("pid")
+}
+
+/// Remove scratch directories untouched for `stale_after` whose owning
+/// process is gone. A long session may leave its directory unmodified for
+/// longer than that, so age alone is not enough.
+fn prune_stale(root: &Path, stale_after: Duration) {
+    let Ok(entries) = fs::read_dir(root) else {
+        return;
+    };
+    let now = SystemTime::now();
+    for entry in entries.flatten() {
+        let Ok(meta) = entry.metadata() else {
+            continue;
+        };
+        if !meta.is_dir() {
+            continue;
+        }
+        let is_stale = meta
+            .modified()
+            .ok()
+            .and_then(|modified| now.duration_since(modified).ok())
+            .is_some_and(|age| age >= stale_after);
+        if !is_stale {
+            continue;
+        }
+        let path = entry.path();
+        let owner = fs::read_to_string(owner_file(&path))
+            .ok()
+            .and_then(|pid| pid.trim().parse::<u32>().ok());
+        if owner.is_some_and(is_process_alive) {
+            continue;
+        }
+        if let Err(e) = fs::remove_dir_all(&path) {
+            warn!("failed to remove stale scratch dir {}: {e}", path.display());
+            continue;
+        }
+        let _ = fs::remove_file(owner_file(&path));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn scratch_dir_is_writable_and_removed_on_drop() {
+        let codex_home = tempfile::tempdir().expect("tempdir");
+        let scratch =
+            ScratchDir::create(codex_home.path(), ConversationId::new()).expect("create scratch");
+        let path = scratch.path().to_path_buf();
+        assert!(path.is_dir());
+        assert!(path.starts_with(codex_home.path().join(SCRATCH_SUBDIR)));
+
+        let policy = scratch.extend_sandbox_policy(SandboxPolicy::new_workspace_write_policy());
+        let SandboxPolicy::WorkspaceWrite { writable_roots, .. } = policy else {
+            panic!("expected workspace-write");
+        };
+        assert_eq!(writable_roots, vec![path.clone()]);
+        assert_eq!(
+            scratch.extend_sandbox_policy(SandboxPolicy::ReadOnly),
+            SandboxPolicy::ReadOnly
+        );
+
+        // A live session's directory survives pruning however old it is.
+        let root = codex_home.path().join(SCRATCH_SUBDIR);
+        prune_stale(&root, Duration::ZERO);
+        assert!(path.is_dir());
+
+        fs::write(path.join("notes.txt"), "temp").expect("write scratch file");
+        drop(scratch);
+        assert!(!path.exists());
+        assert!(!owner_file(&path).exists());
+
+        // A leftover directory is pruned once it is old enough and nothing
+        // owns it.
+        let leftover = root.join("leftover");
+        fs::create_dir_all(&leftover).expect("create leftover");
+        fs::write(owner_file(&leftover), u32::MAX.to_string()).expect("write owner");
+        prune_stale(&root, Duration::ZERO);
+        assert!(!leftover.exists());
+        assert!(!owner_file(&leftover).exists());
+    }
+}
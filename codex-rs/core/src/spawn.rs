@@ -94,6 +94,12 @@ pub(crate) async fn spawn_child_async(
             cmd.stdin(Stdio::null());
 
             cmd.stdout(Stdio::piped()).stderr(Stdio::piped());
+
+            // Run the tool call in its own process group so that cancelling it
+            // can take down everything it spawned (e.g. `bash -c 'a | b &'`),
+            // not just the immediate child. See `kill_child_process_group()`.
+            #[cfg(unix)]
+            cmd.process_group(0);
         }
         StdioPolicy::Inherit => {
             // Inherit stdin, stdout, and stderr from the parent process.
@@ -105,3 +111,49 @@ pub(crate) async fn spawn_child_async(
 
     cmd.kill_on_drop(true).spawn()
 }
+
+/// Terminates the process group led by the child with the given `pid`, as
+/// created by `spawn_child_async()` for `StdioPolicy::RedirectForShellTool`.
+/// Sends SIGKILL to every member of the group, which includes grandchildren
+/// that may be holding the stdout/stderr pipes open. Returns `Ok(())` if the
+/// group no longer exists.
+#[cfg(unix)]
+pub(crate) fn kill_child_process_group(pid: u32) -> std::io::Result<()> {
+    let Ok(pgid) = libc::pid_t::try_from(pid) else {
+        return Ok(());
+    };
+    // SAFETY: killpg(2) has no memory-safety preconditions.
+    let result = unsafe { libc::killpg(pgid, libc::SIGKILL) };
+    if result == -1 {
+        let err = std::io::Error::last_os_error();
+        if err.raw_os_error() != Some(libc::ESRCH) {
+            return Err(err);
+        }
+    }
+    Ok(())
+}
+
+/// Windows has no process groups; `taskkill /T` ends the child's whole
+/// process tree instead.
+#[cfg(windows)]
+pub(crate) fn kill_child_process_group(pid: u32) -> std::io::Result<()> {
+    let status = std::process::Command::new("taskkill")
+        .args(["/T", "/F", "/PID", &pid.to_string()])
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .status()?;
+    // 128: the process has already exited.
+    if status.success() || status.code() == Some(128) {
+        Ok(())
+    } else {
+        Err(std::io::Error::other(format!(
+            "taskkill /T /F /PID {pid} failed: {status}"
+        )))
+    }
+}
+
+#[cfg(not(any(unix, windows)))]
+pub(crate) fn kill_child_process_group(_pid: u32) -> std::io::Result<()> {
+    Ok(())
+}
@@ -0,0 +1,205 @@
+//! Speculative ("draft then verify") execution of read-only shell calls.
+//!
+//! With `speculative_exec = true`, a `shell` call whose arguments are still
+//! streaming in is started as soon as its `command` can be parsed, provided it
+//! is on the [`is_known_safe_command`] list. The draft always runs under a
+//! read-only sandbox with no output streaming. When the call completes and
+//! clears the usual approval checks, the draft's result is used only if the
+//! final command, working directory and timeout match the draft exactly and the
+//! command succeeded; otherwise the draft is discarded and the call runs
+//! normally.
+
+use std::collections::HashMap;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use codex_protocol::models::ShellToolCallParams;
+use tokio::task::JoinHandle;
+
+use crate::error::Result;
+use crate::exec::ExecParams;
+use crate::exec::ExecToolCallOutput;
+use crate::exec::SandboxType;
+use crate::exec::process_exec_tool_call;
+use crate::is_safe_command::is_known_safe_command;
+use crate::protocol::SandboxPolicy;
+use crate::safety::get_platform_sandbox;
+
+/// A read-only command started before its call finished streaming.
+struct Draft {
+    command: Vec<String>,
+    cwd: PathBuf,
+    timeout_ms: Option<u64>,
+    handle: JoinHandle<Result<ExecToolCallOutput>>,
+}
+
+impl Draft {
+    fn matches(&self, params: &ExecParams) -> bool {
+        self.command == params.command
+            && self.cwd == params.cwd
+            && self.timeout_ms == params.timeout_ms
+    }
+}
+
+impl Drop for Draft {
+    fn drop(&mut self) {
+        // Dropping the exec future kills the child process.
+        self.handle.abort();
+    }
+}
+
+#[derive(Default)]
+pub(crate) struct SpeculativeExec {
+    drafts: Mutex<HashMap<String, Draft>>,
+}
+
+impl SpeculativeExec {
+    /// Start (or restart) a draft for `call_id` if `params` is a read-only
+    /// command that differs from the draft already running.
+    pub(crate) fn start(
+        &self,
+        call_id: &str,
+        params: ExecParams,
+        codex_linux_sandbox_exe: &Option<PathBuf>,
+    ) {
+        if params.with_escalated_permissions.unwrap_or(false)
+            || !is_known_safe_command(&params.command)
+        {
+            return;
+        }
+        let sandbox_type = match get_platform_sandbox() {
+            Some(SandboxType::LinuxSeccomp) if codex_linux_sandbox_exe.is_none() => return,
+            Some(sandbox_type) => sandbox_type,
+            None => return,
+        };
+        let Ok(mut drafts) = self.drafts.lock() else {
+            return;
+        };
+        if drafts
+            .get(call_id)
+            .is_some_and(|draft| draft.matches(&params))
+        {
+            return;
+        }
+
+        let command = params.command.clone();
+        let cwd = params.cwd.clone();
+        let timeout_ms = params.timeout_ms;
+        let codex_linux_sandbox_exe = codex_linux_sandbox_exe.clone();
+        let handle = tokio::spawn(async move {
+            process_exec_tool_call(
+                params,
+                sandbox_type,
+                &SandboxPolicy::ReadOnly,
+                &codex_linux_sandbox_exe,
+                None,
+            )
+            .await
+        });
+        drafts.insert(
+            call_id.to_string(),
+            Draft {
+                command,
+                cwd,
+                timeout_ms,
+                handle,
+            },
+        );
+    }
+
+    /// The draft result for `call_id` if it ran exactly `params` and
+    /// succeeded. Any draft for the call is consumed either way.
+    pub(crate) async fn take(
+        &self,
+        call_id: &str,
+        params: &ExecParams,
+    ) -> Option<ExecToolCallOutput> {
+        let mut draft = self.drafts.lock().ok()?.remove(call_id)?;
+        if !draft.matches(params) {
+            return None;
+        }
+        let handle = &mut draft.handle;
+        match handle.await {
+            Ok(Ok(output)) if output.exit_code == 0 => Some(output),
+            _ => None,
+        }
+    }
+
+    /// Abort every draft that was not claimed, e.g. at the start of a turn.
+    pub(crate) fn clear(&self) {
+        if let Ok(mut drafts) = self.drafts.lock() {
+            drafts.clear();
+        }
+    }
+}
+
+/// Best-effort parse of `shell` arguments that are still streaming. Succeeds
+/// once the `command` array and anything before the cut are complete JSON.
+pub(crate) fn predict_shell_params(partial_arguments: &str) -> Option<ShellToolCallParams> {
+    if let Ok(params) = serde_json::from_str(partial_arguments) {
+        return Some(params);
+    }
+    let truncated = partial_arguments.trim_end().trim_end_matches(',');
+    serde_json::from_str(&format!("{truncated}}}")).ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn predicts_command_once_array_is_closed() {
+        assert_eq!(predict_shell_params(r#"{"command":["cat","READ"#), None);
+        let predicted = predict_shell_params(r#"{"command":["cat","README.md"],"#)
+            .expect("command is complete");
+        assert_eq!(predicted.command, vec!["cat", "README.md"]);
+        assert_eq!(predicted.workdir, None);
+        assert_eq!(
+            predict_shell_params(r#"{"command":["ls"],"workdir":"sr"#),
+            None
+        );
+        assert_eq!(
+            predict_shell_params(r#"{"command":["ls"],"workdir":"src"}"#)
+                .and_then(|params| params.workdir),
+            Some("src".to_string())
+        );
+    }
+
+    #[tokio::test]
+    async fn drafts_are_only_used_when_the_final_call_matches() {
+        let speculative = SpeculativeExec::default();
+        let params = |command: &[&str]| ExecParams {
+            command: command.iter().map(ToString::to_string).collect(),
+            cwd: std::env::temp_dir(),
+            timeout_ms: None,
+            env: HashMap::new(),
+            with_escalated_permissions: None,
+            justification: None,
+        };
+
+        // Commands that could modify anything are never drafted.
+        speculative.start("call-1", params(&["rm", "-rf", "x"]), &None);
+        assert!(speculative.drafts.lock().expect("lock").is_empty());
+
+        // A draft for a different command than the final one is discarded.
+        if let Ok(mut drafts) = speculative.drafts.lock() {
+            drafts.insert(
+                "call-2".to_string(),
+                Draft {
+                    command: vec!["ls".to_string()],
+                    cwd: std::env::temp_dir(),
+                    timeout_ms: None,
+                    handle: tokio::spawn(async { Err(crate::error::CodexErr::Interrupted) }),
+                },
+            );
+        }
+        assert!(
+            speculative
+                .take("call-2", &params(&["ls", "-la"]))
+                .await
+                .is_none()
+        );
+        assert!(speculative.drafts.lock().expect("lock").is_empty());
+    }
+}
@@ -0,0 +1,284 @@
+//! Sub-task progress for long turns.
+//!
+//! The running task reports its structure as nested sub-tasks: a review
+//! becomes a sub-task of its own, the plan recorded with `update_plan` is one
+//! beneath it, and each plan step that gets going is a child of the plan.
+//! Commands run while a step is in progress are reported as that step's
+//! progress. Front-ends render the result as a tree.
+
+use crate::plan_tool::StepStatus;
+use crate::plan_tool::UpdatePlanArgs;
+use crate::protocol::EventMsg;
+use crate::protocol::SubtaskBeginEvent;
+use crate::protocol::SubtaskEndEvent;
+use crate::protocol::SubtaskProgressEvent;
+use crate::protocol::SubtaskStatus;
+
+/// Translates what the running task does into sub-task events. Reset
+/// whenever a new task starts reporting.
+#[derive(Debug, Default)]
+pub(crate) struct SubtaskTracker {
+    /// Submission id of the task the open sub-tasks belong to.
+    sub_id: Option<String>,
+    /// A sub-task standing for the whole task, such as a review.
+    task: Option<String>,
+    plan: Option<PlanProgress>,
+}
+
+#[derive(Debug)]
+struct PlanProgress {
+    id: String,
+    ended: bool,
+    steps: Vec<StepProgress>,
+    next_step: usize,
+}
+
+#[derive(Debug)]
+struct StepProgress {
+    step: String,
+    id: String,
+    ended: bool,
+}
+
+impl SubtaskTracker {
+    /// Open a sub-task standing for the task `sub_id` itself.
+    pub(crate) fn begin_task(&mut self, sub_id: &str, title: String) -> Vec<EventMsg> {
+        let mut events = self.switch_task(sub_id);
+        let id = format!("{sub_id}/task");
+        events.push(begin(&id, None, title));
+        self.task = Some(id);
+        events
+    }
+
+    /// Events that bring front-ends in line with the latest plan: steps that
+    /// started or finished since the previous update begin or end, and steps
+    /// dropped from the plan are cancelled.
+    pub(crate) fn on_plan_update(&mut self, sub_id: &str, args: &UpdatePlanArgs) -> Vec<EventMsg> {
+        let mut events = self.switch_task(sub_id);
+        let parent = self.task.clone();
+        let plan = self.plan.get_or_insert_with(|| {
+            let id = format!("{sub_id}/plan");
+            events.push(begin(&id, parent, "Plan".to_string()));
+            PlanProgress {
+                id,
+                ended: false,
+                steps: Vec::new(),
+                next_step: 0,
+            }
+        });
+
+        for tracked in plan.steps.iter_mut().filter(|tracked| !tracked.ended) {
+            if !args.plan.iter().any(|item| item.step == tracked.step) {
+                tracked.ended = true;
+                events.push(end(&tracked.id, SubtaskStatus::Cancelled));
+            }
+        }
+
+        for item in &args.plan {
+            if matches!(item.status, StepStatus::Pending) {
+                continue;
+            }
+            let index = match plan.steps.iter().position(|s| s.step == item.step) {
+                Some(index) => index,
+                None => {
+                    if plan.ended {
+                        plan.ended = false;
+                        events.push(begin(&plan.id, self.task.clone(), "Plan".to_string()));
+                    }
+                    let id = format!("{}/{}", plan.id, plan.next_step);
+                    plan.next_step += 1;
+                    events.push(begin(&id, Some(plan.id.clone()), item.step.clone()));
+                    plan.steps.push(StepProgress {
+                        step: item.step.clone(),
+                        id,
+                        ended: false,
+                    });
+                    plan.steps.len() - 1
+                }
+            };
+            let tracked = &mut plan.steps[index];
+            if matches!(item.status, StepStatus::Completed) && !tracked.ended {
+                tracked.ended = true;
+                events.push(end(&tracked.id, SubtaskStatus::Completed));
+            }
+        }
+
+        let all_done = args
+            .plan
+            .iter()
+            .all(|item| matches!(item.status, StepStatus::Completed));
+        if all_done && !args.plan.is_empty() && !plan.ended {
+            plan.ended = true;
+            events.push(end(&plan.id, SubtaskStatus::Completed));
+        }
+        events
+    }
+
+    /// Report `message` against the innermost open sub-task, if there is one.
+    pub(crate) fn progress(&self, sub_id: &str, message: String) -> Option<EventMsg> {
+        if self.sub_id.as_deref() != Some(sub_id) {
+            return None;
+        }
+        let plan = self.plan.as_ref().filter(|plan| !plan.ended);
+        let id = plan
+            .and_then(|plan| plan.steps.iter().rev().find(|step| !step.ended))
+            .map(|step| &step.id)
+            .or(plan.map(|plan| &plan.id))
+            .or(self.task.as_ref())?;
+        Some(EventMsg::SubtaskProgress(SubtaskProgressEvent {
+            id: id.clone(),
+            message,
+        }))
+    }
+
+    /// Close whatever the task `sub_id` left open, innermost first.
+    pub(crate) fn finish(&mut self, sub_id: &str, status: SubtaskStatus) -> Vec<EventMsg> {
+        if self.sub_id.as_deref() != Some(sub_id) {
+            return Vec::new();
+        }
+        self.close_all(status)
+    }
+
+    /// Start tracking `sub_id`, cancelling anything an earlier task left open.
+    fn switch_task(&mut self, sub_id: &str) -> Vec<EventMsg> {
+        if self.sub_id.as_deref() == Some(sub_id) {
+            return Vec::new();
+        }
+        let events = self.close_all(SubtaskStatus::Cancelled);
+        self.sub_id = Some(sub_id.to_string());
+        events
+    }
+
+    fn close_all(&mut self, status: SubtaskStatus) -> Vec<EventMsg> {
+        let mut events = Vec::new();
+        if let Some(plan) = self.plan.take() {
+            for step in plan.steps.iter().filter(|step| !step.ended) {
+                events.push(end(&step.id, status));
+            }
+            if !plan.ended {
+                events.push(end(&plan.id, status));
+            }
+        }
+        if let Some(task) = self.task.take() {
+            events.push(end(&task, status));
+        }
+        self.sub_id = None;
+        events
+    }
+}
+
+/// How a command shows up as progress: the script of a `bash -lc`
+/// invocation, otherwise the shell-quoted argv.
+pub(crate) fn command_progress_message(command: &[String]) -> String {
+    match command {
+        [shell, flag, script] if shell == "bash" && flag == "-lc" => script.clone(),
+        _ => shlex::try_join(command.iter().map(String::as_str))
+            .unwrap_or_else(|_| command.join(" ")),
+    }
+}
+
+fn begin(id: &str, parent_id: Option<String>, title: String) -> EventMsg {
+    EventMsg::SubtaskBegin(SubtaskBeginEvent {
+        id: id.to_string(),
+        parent_id,
+        title,
+    })
+}
+
+fn end(id: &str, status: SubtaskStatus) -> EventMsg {
+    EventMsg::SubtaskEnd(SubtaskEndEvent {
+        id: id.to_string(),
+        status,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::plan_tool::PlanItemArg;
+    use pretty_assertions::assert_eq;
+
+    fn plan(steps: &[(&str, StepStatus)]) -> UpdatePlanArgs {
+        UpdatePlanArgs {
+            explanation: None,
+            plan: steps
+                .iter()
+                .map(|(step, status)| PlanItemArg {
+                    step: step.to_string(),
+                    status: status.clone(),
+                })
+                .collect(),
+        }
+    }
+
+    /// Events rendered compactly so sequences are easy to compare.
+    fn describe(events: Vec<EventMsg>) -> Vec<String> {
+        events
+            .into_iter()
+            .map(|event| match event {
+                EventMsg::SubtaskBegin(ev) => format!(
+                    "begin {} under {} {:?}",
+                    ev.id,
+                    ev.parent_id.unwrap_or_else(|| "-".to_string()),
+                    ev.title
+                ),
+                EventMsg::SubtaskProgress(ev) => format!("progress {} {:?}", ev.id, ev.message),
+                EventMsg::SubtaskEnd(ev) => format!("end {} {:?}", ev.id, ev.status),
+                other => format!("unexpected {other:?}"),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn plan_steps_become_nested_subtasks() {
+        let mut tracker = SubtaskTracker::default();
+        assert_eq!(
+            describe(tracker.begin_task("7", "Review".to_string())),
+            vec!["begin 7/task under - \"Review\""]
+        );
+        assert_eq!(
+            describe(tracker.on_plan_update(
+                "7",
+                &plan(&[
+                    ("Read code", StepStatus::InProgress),
+                    ("Write tests", StepStatus::Pending),
+                ]),
+            )),
+            vec![
+                "begin 7/plan under 7/task \"Plan\"",
+                "begin 7/plan/0 under 7/plan \"Read code\"",
+            ]
+        );
+        assert_eq!(
+            describe(
+                tracker
+                    .progress("7", command_progress_message(&["ls".into(), "-la".into()]))
+                    .into_iter()
+                    .collect()
+            ),
+            vec!["progress 7/plan/0 \"ls -la\""]
+        );
+        assert_eq!(
+            describe(tracker.on_plan_update(
+                "7",
+                &plan(&[
+                    ("Read code", StepStatus::Completed),
+                    ("Write tests", StepStatus::InProgress),
+                ]),
+            )),
+            vec![
+                "end 7/plan/0 Completed",
+                "begin 7/plan/1 under 7/plan \"Write tests\"",
+            ]
+        );
+        assert_eq!(
+            describe(tracker.finish("7", SubtaskStatus::Cancelled)),
+            vec![
+                "end 7/plan/1 Cancelled",
+                "end 7/plan Cancelled",
+                "end 7/task Cancelled",
+            ]
+        );
+        assert!(tracker.progress("7", "late".to_string()).is_none());
+    }
+}
@@ -0,0 +1,192 @@
+//! Syntax checks on the files a patch just wrote (`[syntax_check]`).
+//!
+//! A file is checked by the validator configured for its extension, a
+//! command that exits non-zero when the file does not parse. Shell scripts
+//! without a configured validator are parsed with tree-sitter. Errors are
+//! appended to the patch's output so the model can fix them in the same turn
+//! instead of finding out from a later build or test run.
+
+use std::path::Path;
+use std::path::PathBuf;
+use std::process::Stdio;
+use std::time::Duration;
+
+use tokio::process::Command;
+
+use crate::bash::try_parse_bash;
+use crate::config_types::SyntaxCheck;
+
+/// Placeholder in a validator's argv replaced with the file's path.
+const PATH_PLACEHOLDER: &str = "{path}";
+
+/// Lines of validator output kept per file.
+const MAX_OUTPUT_LINES: usize = 20;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct SyntaxError {
+    pub(crate) path: PathBuf,
+    pub(crate) message: String,
+}
+
+/// Check every file in `paths` that has a validator (or a built-in parser).
+/// Files that no longer exist are skipped.
+pub(crate) async fn check_files(
+    paths: &[PathBuf],
+    config: &SyntaxCheck,
+    cwd: &Path,
+) -> Vec<SyntaxError> {
+    let mut errors = Vec::new();
+    for path in paths {
+        if !path.is_file() {
+            continue;
+        }
+        let extension = path
+            .extension()
+            .map(|ext| ext.to_string_lossy().to_lowercase())
+            .unwrap_or_default();
+        let message = match config.validators.get(&extension) {
+            Some(argv) => run_validator(argv, path, cwd, config.timeout_ms).await,
+            None if config.tree_sitter && matches!(extension.as_str(), "sh" | "bash") => {
+                check_bash(path)
+            }
+            None => None,
+        };
+        if let Some(message) = message {
+            errors.push(SyntaxError {
+                path: path.clone(),
+                message,
+            });
+        }
+    }
+    errors
+}
+
+/// The text appended to a patch's output when some files do not parse.
+pub(crate) fn format_errors(errors: &[SyntaxError], cwd: &Path) -> String {
+    let mut text = String::from("Syntax check failed for files this patch wrote:");
+    for SyntaxError { path, message } in errors {
+        let shown = path.strip_prefix(cwd).unwrap_or(path).display();
+        text.push_str(&format!("\n\n{shown}:\n{message}"));
+    }
+    text.push_str("\n\nFix these errors before moving on.");
+    text
+}
+
+/// Run `argv` on `path`; its output when it fails or times out.
+async fn run_validator(
+    argv: &[String],
+    path: &Path,
+    cwd: &Path,
+    timeout_ms: u64,
+) -> Option<String> {
+    let (program, args) = argv.split_first()?;
+    let path_arg = path.to_string_lossy();
+    let mut args: Vec<String> = args
+        .iter()
+        .map(|arg| arg.replace(PATH_PLACEHOLDER, &path_arg))
+        .collect();
+    if !argv.iter().any(|arg| arg.contains(PATH_PLACEHOLDER)) {
+        args.push(path_arg.to_string());
+    }
+
+    let output = Command::new(program)
+        .args(&args)
+        .current_dir(cwd)
+        .stdin(Stdio::null())
+        .kill_on_drop(true)
+        .output();
+    let output = match tokio::time::timeout(Duration::from_millis(timeout_ms), output).await {
+        Ok(Ok(output)) => output,
+        Ok(Err(e)) => {
+            tracing::warn!("syntax validator {program} could not run: {e}");
+            return None;
+        }
+        Err(_) => return Some(format!("{program} timed out after {timeout_ms}ms")),
+    };
+    if output.status.success() {
+        return None;
+    }
+    let combined = format!(
+        "{}{}",
+        String::from_utf8_lossy(&output.stdout),
+        String::from_utf8_lossy(&output.stderr)
+    );
+    let mut lines: Vec<&str> = combined.lines().filter(|l| !l.trim().is_empty()).collect();
+    if lines.is_empty() {
+        return Some(format!("{program} exited with {}", output.status));
+    }
+    if lines.len() > MAX_OUTPUT_LINES {
+        lines.truncate(MAX_OUTPUT_LINES);
+        lines.push("…");
+    }
+    Some(lines.join("\n"))
+}
+
+/// The first parse error in a shell script, as `line:column: message`.
+fn check_bash(path: &Path) -> Option<String> {
+    let source = std::fs::read_to_string(path).ok()?;
+    let tree = try_parse_bash(&source)?;
+    let root = tree.root_node();
+    if !root.has_error() {
+        return None;
+    }
+    let mut cursor = root.walk();
+    let mut node = root;
+    // Descend towards the first error or missing node.
+    'descend: loop {
+        for child in node.children(&mut cursor) {
+            if child.is_error() || child.is_missing() {
+                node = child;
+                break 'descend;
+            }
+            if child.has_error() {
+                node = child;
+                continue 'descend;
+            }
+        }
+        break;
+    }
+    let position = node.start_position();
+    let what = if node.is_missing() {
+        format!("missing {}", node.kind())
+    } else {
+        "syntax error".to_string()
+    };
+    Some(format!(
+        "{}:{}: {what}",
+        position.row + 1,
+        position.column + 1
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    #[tokio::test]
+    async fn reports_files_that_do_not_parse() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        let cwd = dir.path();
+        let good = cwd.join("good.sh");
+        let bad = cwd.join("bad.sh");
+        let data = cwd.join("data.txt");
+        std::fs::write(&good, "echo hi\n").expect("write");
+        std::fs::write(&bad, "if true; then\n  echo hi\n").expect("write");
+        std::fs::write(&data, "anything\n").expect("write");
+
+        let config = SyntaxCheck {
+            enabled: true,
+            validators: HashMap::from([(
+                "txt".to_string(),
+                vec!["grep".to_string(), "-q".to_string(), "nothing".to_string()],
+            )]),
+            ..SyntaxCheck::default()
+        };
+        let errors = check_files(&[good, bad.clone(), data.clone()], &config, cwd).await;
+        let paths: Vec<&PathBuf> = errors.iter().map(|error| &error.path).collect();
+        assert_eq!(paths, vec![&bad, &data]);
+        assert_eq!(errors[1].message, "grep exited with exit status: 1");
+    }
+}
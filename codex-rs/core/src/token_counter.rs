@@ -0,0 +1,173 @@
+//! Token counting backed by the tokenizer vocabulary of the active model.
+//!
+//! Context-window accounting used to rely on a 4 bytes/token heuristic, which
+//! is badly off for code, non-ASCII text and JSON-heavy tool output. The
+//! [`TokenCounter`] defined here runs the real BPE tokenizer (via
+//! `tiktoken-rs`) for the vocabulary that matches the model slug, so
+//! truncation decisions and the "context remaining" indicator line up with
+//! what the provider will actually bill.
+
+use std::sync::OnceLock;
+
+use codex_protocol::models::ResponseItem;
+use tiktoken_rs::CoreBPE;
+
+/// Tokens added by the wire format around every item (role markers,
+/// separators). Mirrors the per-message overhead documented for the chat
+/// format.
+const PER_ITEM_OVERHEAD_TOKENS: u64 = 4;
+
+/// Tokenizer vocabularies Codex knows how to load.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenizerVocab {
+    /// Used by gpt-4o, gpt-4.1, gpt-5, the o-series and codex models.
+    O200kBase,
+    /// Used by gpt-4 and gpt-3.5-turbo.
+    Cl100kBase,
+}
+
+impl TokenizerVocab {
+    /// Pick the vocabulary for `model`. Unknown models (including most
+    /// third-party/OSS models) fall back to `o200k_base`, which is the closest
+    /// general-purpose approximation.
+    pub fn for_model(model: &str) -> Self {
+        if model.starts_with("gpt-4o") || model.starts_with("gpt-4.1") {
+            TokenizerVocab::O200kBase
+        } else if model.starts_with("gpt-4") || model.starts_with("gpt-3.5") {
+            TokenizerVocab::Cl100kBase
+        } else {
+            TokenizerVocab::O200kBase
+        }
+    }
+
+    fn bpe(self) -> Option<&'static CoreBPE> {
+        static O200K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+        static CL100K: OnceLock<Option<CoreBPE>> = OnceLock::new();
+        let cell = match self {
+            TokenizerVocab::O200kBase => &O200K,
+            TokenizerVocab::Cl100kBase => &CL100K,
+        };
+        cell.get_or_init(|| {
+            let loaded = match self {
+                TokenizerVocab::O200kBase => tiktoken_rs::o200k_base(),
+                TokenizerVocab::Cl100kBase => tiktoken_rs::cl100k_base(),
+            };
+            match loaded {
+                Ok(bpe) => Some(bpe),
+                Err(e) => {
+                    tracing::warn!("failed to load {self:?} tokenizer: {e:#}");
+                    None
+                }
+            }
+        })
+        .as_ref()
+    }
+}
+
+/// Counts tokens for a specific model. Cheap to construct: the underlying
+/// vocabularies are loaded lazily once per process and shared.
+#[derive(Debug, Clone, Copy)]
+pub struct TokenCounter {
+    vocab: TokenizerVocab,
+}
+
+impl Default for TokenCounter {
+    fn default() -> Self {
+        Self {
+            vocab: TokenizerVocab::O200kBase,
+        }
+    }
+}
+
+impl TokenCounter {
+    pub fn for_model(model: &str) -> Self {
+        Self {
+            vocab: TokenizerVocab::for_model(model),
+        }
+    }
+
+    pub fn vocab(&self) -> TokenizerVocab {
+        self.vocab
+    }
+
+    /// Number of tokens in `text`. If the vocabulary could not be loaded this
+    /// degrades to the historical 4 bytes/token estimate.
+    pub fn count(&self, text: &str) -> u64 {
+        match self.vocab.bpe() {
+            Some(bpe) => bpe.encode_ordinary(text).len() as u64,
+            None => (text.len() as u64).div_ceil(4),
+        }
+    }
+
+    /// Approximate number of prompt tokens contributed by `items` when sent to
+    /// the model. Items are measured in their serialized wire form so tool
+    /// call arguments and outputs are accounted for as well as plain text.
+    pub fn count_items(&self, items: &[ResponseItem]) -> u64 {
+        items.iter().map(|item| self.count_item(item)).sum()
+    }
+
+    fn count_item(&self, item: &ResponseItem) -> u64 {
+        let body = match serde_json::to_string(item) {
+            Ok(body) => body,
+            Err(_) => return PER_ITEM_OVERHEAD_TOKENS,
+        };
+        self.count(&body) + PER_ITEM_OVERHEAD_TOKENS
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_protocol::models::ContentItem;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn selects_vocab_by_model_slug() {
+        assert_eq!(
+            TokenizerVocab::for_model("gpt-5"),
+            TokenizerVocab::O200kBase
+        );
+        assert_eq!(
+            TokenizerVocab::for_model("gpt-4o-mini"),
+            TokenizerVocab::O200kBase
+        );
+        assert_eq!(
+            TokenizerVocab::for_model("gpt-4.1"),
+            TokenizerVocab::O200kBase
+        );
+        assert_eq!(
+            TokenizerVocab::for_model("gpt-4"),
+            TokenizerVocab::Cl100kBase
+        );
+        assert_eq!(
+            TokenizerVocab::for_model("gpt-3.5-turbo"),
+            TokenizerVocab::Cl100kBase
+        );
+        assert_eq!(
+            TokenizerVocab::for_model("qwen3-coder"),
+            TokenizerVocab::O200kBase
+        );
+    }
+
+    #[test]
+    fn counts_are_not_byte_based() {
+        let counter = TokenCounter::for_model("gpt-5");
+        assert_eq!(counter.count(""), 0);
+        // A long run of a common word tokenizes far more densely than 4 bytes/token.
+        let text = "hello ".repeat(100);
+        assert!(counter.count(&text) < (text.len() as u64).div_ceil(4));
+    }
+
+    #[test]
+    fn count_items_includes_overhead() {
+        let counter = TokenCounter::default();
+        let items = vec![ResponseItem::Message {
+            id: None,
+            role: "user".to_string(),
+            content: vec![ContentItem::InputText {
+                text: "hi".to_string(),
+            }],
+        }];
+        assert!(counter.count_items(&items) > PER_ITEM_OVERHEAD_TOKENS);
+    }
+}
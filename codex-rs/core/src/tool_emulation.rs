@@ -0,0 +1,304 @@
+//! Tool calling for Chat Completions models without native function calling.
+//!
+//! When [`crate::capabilities::ProviderCapabilities::tools`] is false the
+//! function tools are described in the system prompt instead of being sent as
+//! `tools`, and the model is asked to answer with fenced `tool_call` blocks.
+//! Those blocks are parsed out of the completion, checked against the tool's
+//! JSON schema and turned into ordinary [`ResponseItem::FunctionCall`]s, so
+//! the rest of Codex cannot tell the difference. Past calls and their outputs
+//! are rendered back into the conversation as plain text.
+
+use serde_json::Value;
+use serde_json::json;
+
+use crate::openai_tools::JsonSchema;
+use crate::openai_tools::OpenAiTool;
+use crate::openai_tools::ResponsesApiTool;
+use codex_protocol::models::ResponseItem;
+
+/// Name given to calls that failed validation; handling it reports the error
+/// back to the model instead of running anything.
+pub(crate) const INVALID_TOOL_CALL: &str = "invalid_tool_call";
+
+/// Info string of the fenced code blocks the model is asked to emit.
+const FENCE_TAG: &str = "tool_call";
+
+/// System prompt section describing `tools` and how to call them.
+pub(crate) fn instructions(tools: &[OpenAiTool]) -> String {
+    let mut out = format!(
+        "# Tools\n\nYou can call the tools below. To call one, reply with a fenced code block tagged `{FENCE_TAG}` containing a JSON object with the tool `name` and its `arguments`, for example:\n\n```{FENCE_TAG}\n{{\"name\": \"shell\", \"arguments\": {{\"command\": [\"ls\", \"-la\"]}}}}\n```\n\nYou may call several tools in one reply by emitting several blocks. The result of each call is sent back to you in the next message. Only call the tools listed here, and make the arguments match the schema exactly.\n"
+    );
+    for tool in function_tools(tools) {
+        let parameters = serde_json::to_string(&tool.parameters).unwrap_or_default();
+        out.push_str(&format!(
+            "\n## {}\n\n{}\n\nArguments schema: {parameters}\n",
+            tool.name, tool.description
+        ));
+    }
+    out
+}
+
+/// Assistant message text standing in for a call made earlier.
+pub(crate) fn render_call(name: &str, arguments: &str) -> String {
+    let arguments = serde_json::from_str::<Value>(arguments)
+        .unwrap_or_else(|_| Value::String(arguments.to_string()));
+    let call = json!({ "name": name, "arguments": arguments });
+    format!("```{FENCE_TAG}\n{call}\n```")
+}
+
+/// User message text carrying the output of an earlier call.
+pub(crate) fn render_output(output: &str) -> String {
+    format!("Tool call result:\n{output}")
+}
+
+/// The message shown to the model when a call named [`INVALID_TOOL_CALL`] is
+/// handled.
+pub(crate) fn invalid_call_message(arguments: &str) -> String {
+    let parsed = serde_json::from_str::<Value>(arguments).unwrap_or_default();
+    let tool = parsed.get("tool").and_then(Value::as_str).unwrap_or("?");
+    let error = parsed
+        .get("error")
+        .and_then(Value::as_str)
+        .unwrap_or("invalid call");
+    format!("tool call `{tool}` was not run: {error}")
+}
+
+/// Split a completion into the text meant for the user and the tool calls it
+/// contains. Calls with an unknown tool or arguments that do not match the
+/// schema become [`INVALID_TOOL_CALL`] calls carrying the reason.
+pub(crate) fn parse_response(text: &str, tools: &[OpenAiTool]) -> (String, Vec<ResponseItem>) {
+    let mut remaining = String::new();
+    let mut calls = Vec::new();
+    let mut rest = text;
+    while let Some((before, block, after)) = next_fenced_block(rest) {
+        match parse_call(block) {
+            Some((name, arguments)) => {
+                remaining.push_str(before);
+                calls.push(to_function_call(name, arguments, tools));
+            }
+            // Not a tool call; keep the block as part of the message.
+            None => remaining.push_str(&rest[..rest.len() - after.len()]),
+        }
+        rest = after;
+    }
+    remaining.push_str(rest);
+    (remaining.trim().to_string(), calls)
+}
+
+/// Find the next ```` ``` ```` block tagged `tool_call`, returning the text
+/// before it, its body, and the text after it. Other blocks, `json` included,
+/// are part of the answer: JSON with a `name` key is not necessarily a call.
+fn next_fenced_block(text: &str) -> Option<(&str, &str, &str)> {
+    let mut search_from = 0;
+    loop {
+        let start = search_from + text[search_from..].find("```")?;
+        let header_end = start + text[start..].find('\n')?;
+        let tag = text[start + 3..header_end].trim();
+        let body_start = header_end + 1;
+        let body_len = text[body_start..].find("```")?;
+        let end = body_start + body_len + 3;
+        if tag == FENCE_TAG {
+            return Some((
+                &text[..start],
+                &text[body_start..body_start + body_len],
+                &text[end..],
+            ));
+        }
+        search_from = end;
+    }
+}
+
+fn parse_call(block: &str) -> Option<(String, Value)> {
+    let value: Value = serde_json::from_str(block.trim()).ok()?;
+    let name = value.get("name")?.as_str()?.to_string();
+    let arguments = match value.get("arguments") {
+        // Some models double-encode the arguments like the native API does.
+        Some(Value::String(s)) => serde_json::from_str(s).ok()?,
+        Some(arguments) => arguments.clone(),
+        None => json!({}),
+    };
+    Some((name, arguments))
+}
+
+fn to_function_call(name: String, arguments: Value, tools: &[OpenAiTool]) -> ResponseItem {
+    let call_id = format!("call_{}", uuid::Uuid::new_v4().simple());
+    let validation = match function_tools(tools).find(|tool| tool.name == name) {
+        Some(tool) => validate(&tool.parameters, &arguments, "arguments"),
+        None => Err(format!("unknown tool `{name}`")),
+    };
+    match validation {
+        Ok(()) => ResponseItem::FunctionCall {
+            id: None,
+            name,
+            arguments: arguments.to_string(),
+            call_id,
+        },
+        Err(error) => ResponseItem::FunctionCall {
+            id: None,
+            name: INVALID_TOOL_CALL.to_string(),
+            arguments: json!({ "tool": name, "error": error }).to_string(),
+            call_id,
+        },
+    }
+}
+
+fn function_tools(tools: &[OpenAiTool]) -> impl Iterator<Item = &ResponsesApiTool> {
+    tools.iter().filter_map(|tool| match tool {
+        OpenAiTool::Function(tool) => Some(tool),
+        _ => None,
+    })
+}
+
+/// Check `value` against `schema`, naming the offending field in the error.
+fn validate(schema: &JsonSchema, value: &Value, path: &str) -> Result<(), String> {
+    match (schema, value) {
+        (JsonSchema::Boolean { .. }, Value::Bool(_))
+        | (JsonSchema::String { .. }, Value::String(_))
+        | (JsonSchema::Number { .. }, Value::Number(_)) => Ok(()),
+        (JsonSchema::Array { items, .. }, Value::Array(values)) => values
+            .iter()
+            .enumerate()
+            .try_for_each(|(i, value)| validate(items, value, &format!("{path}[{i}]"))),
+        (
+            JsonSchema::Object {
+                properties,
+                required,
+                additional_properties,
+            },
+            Value::Object(map),
+        ) => {
+            for key in required.iter().flatten() {
+                if !map.contains_key(key) {
+                    return Err(format!("missing required field `{path}.{key}`"));
+                }
+            }
+            for (key, value) in map {
+                match properties.get(key) {
+                    Some(schema) => validate(schema, value, &format!("{path}.{key}"))?,
+                    None if *additional_properties == Some(false) => {
+                        return Err(format!("unexpected field `{path}.{key}`"));
+                    }
+                    None => {}
+                }
+            }
+            Ok(())
+        }
+        (schema, _) => {
+            let expected = match schema {
+                JsonSchema::Boolean { .. } => "a boolean",
+                JsonSchema::String { .. } => "a string",
+                JsonSchema::Number { .. } => "a number",
+                JsonSchema::Array { .. } => "an array",
+                JsonSchema::Object { .. } => "an object",
+            };
+            Err(format!("`{path}` must be {expected}"))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use std::collections::BTreeMap;
+
+    fn shell_tool() -> OpenAiTool {
+        OpenAiTool::Function(ResponsesApiTool {
+            name: "shell".to_string(),
+            description: "Runs a command.".to_string(),
+            strict: false,
+            parameters: JsonSchema::Object {
+                properties: BTreeMap::from([
+                    (
+                        "command".to_string(),
+                        JsonSchema::Array {
+                            items: Box::new(JsonSchema::String { description: None }),
+                            description: None,
+                        },
+                    ),
+                    (
+                        "timeout_ms".to_string(),
+                        JsonSchema::Number { description: None },
+                    ),
+                ]),
+                required: Some(vec!["command".to_string()]),
+                additional_properties: Some(false),
+            },
+        })
+    }
+
+    fn call_parts(item: &ResponseItem) -> (String, Value) {
+        match item {
+            ResponseItem::FunctionCall {
+                name, arguments, ..
+            } => (
+                name.clone(),
+                serde_json::from_str(arguments).expect("arguments json"),
+            ),
+            other => panic!("expected function call, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn extracts_valid_calls_and_keeps_text() {
+        let text = "Let me look.\n```tool_call\n{\"name\": \"shell\", \"arguments\": {\"command\": [\"ls\"]}}\n```\n```rust\nfn main() {}\n```";
+        let (remaining, calls) = parse_response(text, &[shell_tool()]);
+        assert_eq!(remaining, "Let me look.\n\n```rust\nfn main() {}\n```");
+        assert_eq!(calls.len(), 1);
+        assert_eq!(
+            call_parts(&calls[0]),
+            ("shell".to_string(), json!({"command": ["ls"]}))
+        );
+    }
+
+    #[test]
+    fn rejects_calls_that_do_not_match_the_schema() {
+        let text = "```tool_call\n{\"name\": \"shell\", \"arguments\": \"{\\\"command\\\": \\\"ls\\\"}\"}\n```\n```tool_call\n{\"name\": \"rm\", \"arguments\": {}}\n```";
+        let (remaining, calls) = parse_response(text, &[shell_tool()]);
+        assert_eq!(remaining, "");
+        assert_eq!(
+            calls.iter().map(call_parts).collect::<Vec<_>>(),
+            vec![
+                (
+                    INVALID_TOOL_CALL.to_string(),
+                    json!({"tool": "shell", "error": "`arguments.command` must be an array"}),
+                ),
+                (
+                    INVALID_TOOL_CALL.to_string(),
+                    json!({"tool": "rm", "error": "unknown tool `rm`"}),
+                ),
+            ]
+        );
+        let (_, arguments) = call_parts(&calls[1]);
+        assert_eq!(
+            invalid_call_message(&arguments.to_string()),
+            "tool call `rm` was not run: unknown tool `rm`"
+        );
+    }
+
+    #[test]
+    fn plain_json_in_an_answer_is_not_a_call() {
+        let text = "The manifest looks like this:\n```json\n{\"name\": \"shell\", \"arguments\": {\"command\": [\"ls\"]}}\n```";
+        let (remaining, calls) = parse_response(text, &[shell_tool()]);
+        assert_eq!(remaining, text);
+        assert!(calls.is_empty());
+    }
+
+    #[test]
+    fn renders_history_as_fenced_calls() {
+        let rendered = render_call("shell", "{\"command\":[\"ls\"]}");
+        let body = rendered
+            .strip_prefix("```tool_call\n")
+            .and_then(|s| s.strip_suffix("\n```"))
+            .expect("fenced block");
+        assert_eq!(
+            serde_json::from_str::<Value>(body).expect("json"),
+            json!({"name": "shell", "arguments": {"command": ["ls"]}})
+        );
+        let (_, calls) = parse_response(&rendered, &[shell_tool()]);
+        assert_eq!(
+            call_parts(&calls[0]),
+            ("shell".to_string(), json!({"command": ["ls"]}))
+        );
+    }
+}
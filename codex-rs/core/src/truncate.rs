@@ -1,16 +1,35 @@
 //! Utilities for truncating large chunks of output while preserving a prefix
 //! and suffix on UTF-8 boundaries.
 
+use crate::token_counter::TokenCounter;
+
 /// Truncate the middle of a UTF-8 string to at most `max_bytes` bytes,
 /// preserving the beginning and the end. Returns the possibly truncated
-/// string and `Some(original_token_count)` (estimated at 4 bytes/token)
-/// if truncation occurred; otherwise returns the original string and `None`.
-pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>) {
+/// string and `Some(original_token_count)` if truncation occurred; otherwise
+/// returns the original string and `None`. Tokens, both in the returned count
+/// and in the marker that replaces the middle, are counted with `counter`.
+pub(crate) fn truncate_middle(
+    s: &str,
+    max_bytes: usize,
+    counter: &TokenCounter,
+) -> (String, Option<u64>) {
+    if s.len() <= max_bytes {
+        return (s.to_string(), None);
+    }
+    truncate_middle_counted(s, max_bytes, counter, counter.count(s))
+}
+
+/// [`truncate_middle`] for a string already known to be `est_tokens` long.
+fn truncate_middle_counted(
+    s: &str,
+    max_bytes: usize,
+    counter: &TokenCounter,
+    est_tokens: u64,
+) -> (String, Option<u64>) {
     if s.len() <= max_bytes {
         return (s.to_string(), None);
     }
 
-    let est_tokens = (s.len() as u64).div_ceil(4);
     if max_bytes == 0 {
         return (format!("…{est_tokens} tokens truncated…"), Some(est_tokens));
     }
@@ -68,8 +87,7 @@ pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>
         }
 
         let kept_content_bytes = prefix_end + (s.len() - suffix_start);
-        let truncated_content_bytes = s.len().saturating_sub(kept_content_bytes);
-        let new_tokens = (truncated_content_bytes as u64).div_ceil(4);
+        let new_tokens = counter.count(&s[prefix_end..suffix_start]);
 
         if new_tokens == guess_tokens {
             let mut out = String::with_capacity(marker_len + kept_content_bytes + 1);
@@ -103,19 +121,55 @@ pub(crate) fn truncate_middle(s: &str, max_bytes: usize) -> (String, Option<u64>
     (out, Some(est_tokens))
 }
 
+/// Truncate the middle of `s` so the result fits in `max_tokens` tokens as
+/// measured by `counter`. Returns the possibly truncated string and
+/// `Some(original_token_count)` if truncation occurred; otherwise returns the
+/// original string and `None`.
+pub(crate) fn truncate_middle_to_tokens(
+    s: &str,
+    max_tokens: u64,
+    counter: &TokenCounter,
+) -> (String, Option<u64>) {
+    let original_tokens = counter.count(s);
+    if original_tokens <= max_tokens {
+        return (s.to_string(), None);
+    }
+
+    // Binary search for the largest byte budget whose truncated output still
+    // fits within the token budget. Token counts are monotonic enough in the
+    // number of kept bytes for this to converge on a tight result.
+    let mut best = truncate_middle_counted(s, 0, counter, original_tokens).0;
+    let mut lo = 0usize;
+    let mut hi = s.len();
+    while lo < hi {
+        let mid = lo + (hi - lo).div_ceil(2);
+        let (candidate, _) = truncate_middle_counted(s, mid, counter, original_tokens);
+        if counter.count(&candidate) <= max_tokens {
+            best = candidate;
+            lo = mid;
+        } else {
+            hi = mid - 1;
+        }
+    }
+    (best, Some(original_tokens))
+}
+
 #[cfg(test)]
 mod tests {
     use super::truncate_middle;
+    use super::truncate_middle_to_tokens;
+    use crate::token_counter::TokenCounter;
 
     #[test]
     fn truncate_middle_no_newlines_fallback() {
         let s = "abcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789ABCDEFGHIJKLMNOPQRSTUVWXYZ*";
         let max_bytes = 32;
-        let (out, original) = truncate_middle(s, max_bytes);
+        let counter = TokenCounter::default();
+        let (out, original) = truncate_middle(s, max_bytes, &counter);
         assert!(out.starts_with("abc"));
         assert!(out.contains("tokens truncated"));
         assert!(out.ends_with("XYZ*"));
-        assert_eq!(original, Some((s.len() as u64).div_ceil(4)));
+        assert_eq!(original, Some(counter.count(s)));
     }
 
     #[test]
@@ -127,22 +181,24 @@ mod tests {
         assert_eq!(s.len(), 80);
 
         let max_bytes = 64;
-        let (out, tokens) = truncate_middle(&s, max_bytes);
+        let counter = TokenCounter::default();
+        let (out, tokens) = truncate_middle(&s, max_bytes, &counter);
         assert!(out.starts_with("001\n002\n003\n004\n"));
         assert!(out.contains("tokens truncated"));
         assert!(out.ends_with("017\n018\n019\n020\n"));
-        assert_eq!(tokens, Some(20));
+        assert_eq!(tokens, Some(counter.count(&s)));
     }
 
     #[test]
     fn truncate_middle_handles_utf8_content() {
         let s = "😀😀😀😀😀😀😀😀😀😀\nsecond line with ascii text\n";
         let max_bytes = 32;
-        let (out, tokens) = truncate_middle(s, max_bytes);
+        let counter = TokenCounter::default();
+        let (out, tokens) = truncate_middle(s, max_bytes, &counter);
 
         assert!(out.contains("tokens truncated"));
         assert!(!out.contains('\u{fffd}'));
-        assert_eq!(tokens, Some((s.len() as u64).div_ceil(4)));
+        assert_eq!(tokens, Some(counter.count(s)));
     }
 
     #[test]
@@ -158,23 +214,49 @@ mod tests {
         // Choose a cap that forces truncation while leaving room for
         // a few lines on each side after accounting for the marker.
         let max_bytes = 64;
-        // Expect exact output: first 4 lines, marker, last 4 lines, and correct token estimate (80/4 = 20).
+        let counter = TokenCounter::default();
+        // Expect exact output: first 4 lines, a marker counting the tokens of
+        // the 12 omitted lines, last 4 lines, and the original token count.
+        let omitted = counter.count(&s[16..64]);
         assert_eq!(
-            truncate_middle(&s, max_bytes),
+            truncate_middle(&s, max_bytes, &counter),
             (
-                r#"001
-002
-003
-004
-…12 tokens truncated…
-017
-018
-019
-020
-"#
-                .to_string(),
-                Some(20)
+                format!("001\n002\n003\n004\n…{omitted} tokens truncated…\n017\n018\n019\n020\n"),
+                Some(counter.count(&s))
             )
         );
     }
+
+    #[test]
+    fn truncate_middle_to_tokens_respects_token_budget() {
+        let counter = TokenCounter::for_model("gpt-5");
+        let mut s = String::new();
+        for i in 1..=200 {
+            s.push_str(&format!("line {i}: the quick brown fox\n"));
+        }
+        let max_tokens = 64;
+        let (out, original) = truncate_middle_to_tokens(&s, max_tokens, &counter);
+        assert_eq!(original, Some(counter.count(&s)));
+        assert!(counter.count(&out) <= max_tokens);
+        assert!(out.starts_with("line 1:"));
+        assert!(out.contains("tokens truncated"));
+        assert!(out.ends_with("line 200: the quick brown fox\n"));
+
+        // The marker counts the omitted lines in real tokens.
+        let marker_start = out.find('…').expect("marker");
+        let suffix_start = out[marker_start..].find('\n').expect("newline") + marker_start + 1;
+        let omitted_end = s.len() - (out.len() - suffix_start);
+        let omitted = counter.count(&s[marker_start..omitted_end]);
+        assert!(out.contains(&format!("…{omitted} tokens truncated…")));
+    }
+
+    #[test]
+    fn truncate_middle_to_tokens_leaves_short_input_untouched() {
+        let counter = TokenCounter::default();
+        let s = "short output\n";
+        assert_eq!(
+            truncate_middle_to_tokens(s, 1_000, &counter),
+            (s.to_string(), None)
+        );
+    }
 }
@@ -0,0 +1,140 @@
+//! Per-directory trust decisions, stored in `$CODEX_HOME/trusted.json`.
+//!
+//! The first time Codex runs in a directory the TUI asks whether to trust it
+//! and records the answer here. Decisions are keyed by the Git repository
+//! root, so worktrees share the decision of their main checkout, or by the
+//! directory itself outside a repository. Directories the user declined to
+//! trust run with [`lock_down_untrusted`] applied.
+
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::path::PathBuf;
+
+use anyhow::Context;
+use serde::Deserialize;
+use serde::Serialize;
+
+use crate::config::Config;
+use crate::git_info::resolve_root_git_project_for_trust;
+use crate::protocol::SandboxPolicy;
+
+pub const TRUST_STORE_FILE: &str = "trusted.json";
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TrustDecision {
+    Trusted,
+    Untrusted,
+}
+
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TrustStore {
+    #[serde(skip)]
+    storage_path: PathBuf,
+    /// Decisions keyed by repository root or directory.
+    #[serde(default)]
+    directories: BTreeMap<PathBuf, TrustDecision>,
+}
+
+impl TrustStore {
+    /// Read the store from `codex_home`. A missing or unreadable file is an
+    /// empty store, so every directory is undecided.
+    pub fn load(codex_home: &Path) -> Self {
+        let storage_path = codex_home.join(TRUST_STORE_FILE);
+        let directories = match std::fs::read_to_string(&storage_path) {
+            Ok(serialized) => match serde_json::from_str::<Self>(&serialized) {
+                Ok(store) => store.directories,
+                Err(error) => {
+                    tracing::warn!("failed to parse {TRUST_STORE_FILE}: {error:?}");
+                    BTreeMap::new()
+                }
+            },
+            Err(error) if error.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(error) => {
+                tracing::warn!("failed to read {TRUST_STORE_FILE}: {error:?}");
+                BTreeMap::new()
+            }
+        };
+        Self {
+            storage_path,
+            directories,
+        }
+    }
+
+    /// The recorded decision for `cwd`, if the user has made one. An entry
+    /// for `cwd` itself wins over one for its repository root.
+    pub fn decision_for(&self, cwd: &Path) -> Option<TrustDecision> {
+        self.directories
+            .get(cwd)
+            .or_else(|| self.directories.get(&trust_key(cwd)))
+            .copied()
+    }
+
+    /// Remember `decision` for `cwd` (under its trust key) and write the
+    /// store back to disk.
+    pub fn record(&mut self, cwd: &Path, decision: TrustDecision) -> anyhow::Result<()> {
+        self.directories.insert(trust_key(cwd), decision);
+        if let Some(parent) = self.storage_path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("failed to create {}", parent.display()))?;
+        }
+        let serialized = serde_json::to_string_pretty(self)?;
+        // Write a sibling file and rename it so a crash never leaves a
+        // truncated store behind.
+        let tmp_path = self.storage_path.with_extension("json.tmp");
+        std::fs::write(&tmp_path, serialized)
+            .with_context(|| format!("failed to write {}", tmp_path.display()))?;
+        std::fs::rename(&tmp_path, &self.storage_path)
+            .with_context(|| format!("failed to persist {}", self.storage_path.display()))
+    }
+}
+
+/// The directory a decision about `cwd` is stored under: the root of the
+/// Git project containing it, or `cwd` itself.
+pub fn trust_key(cwd: &Path) -> PathBuf {
+    resolve_root_git_project_for_trust(cwd).unwrap_or_else(|| cwd.to_path_buf())
+}
+
+/// Defaults for a directory the user does not trust: commands run under a
+/// read-only sandbox unless `keep_sandbox` (the sandbox was chosen explicitly
+/// for this run), and the repository's `AGENTS.md` files are not loaded.
+pub fn lock_down_untrusted(config: &mut Config, keep_sandbox: bool) {
+    if !keep_sandbox {
+        config.sandbox_policy = SandboxPolicy::new_read_only_policy();
+    }
+    config.project_doc_max_bytes = 0;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    #[test]
+    fn decisions_round_trip_through_trusted_json() {
+        let codex_home = TempDir::new().unwrap();
+        let project = TempDir::new().unwrap();
+        let nested = project.path().join("sub");
+
+        let mut store = TrustStore::load(codex_home.path());
+        assert_eq!(store.decision_for(project.path()), None);
+
+        store
+            .record(project.path(), TrustDecision::Untrusted)
+            .unwrap();
+        store.record(&nested, TrustDecision::Trusted).unwrap();
+
+        let reloaded = TrustStore::load(codex_home.path());
+        assert_eq!(
+            reloaded.decision_for(project.path()),
+            Some(TrustDecision::Untrusted)
+        );
+        assert_eq!(reloaded.decision_for(&nested), Some(TrustDecision::Trusted));
+        assert!(
+            std::fs::read_to_string(codex_home.path().join(TRUST_STORE_FILE))
+                .unwrap()
+                .contains("\"untrusted\"")
+        );
+    }
+}
@@ -18,6 +18,7 @@ use tokio::time::Duration;
 use tokio::time::Instant;
 
 use crate::exec_command::ExecCommandSession;
+use crate::token_counter::TokenCounter;
 use crate::truncate::truncate_middle;
 
 mod errors;
@@ -33,6 +34,11 @@ pub(crate) struct UnifiedExecRequest<'a> {
     pub session_id: Option<i32>,
     pub input_chunks: &'a [String],
     pub timeout_ms: Option<u64>,
+    /// Environment for a newly opened session (see `create_env`). `None`
+    /// inherits the parent's environment.
+    pub env: Option<&'a HashMap<String, String>>,
+    /// Turn opening a new session; aborting it closes the session.
+    pub sub_id: &'a str,
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -50,6 +56,8 @@ pub(crate) struct UnifiedExecSessionManager {
 #[derive(Debug)]
 struct ManagedUnifiedExecSession {
     session: ExecCommandSession,
+    /// Turn that opened the session.
+    sub_id: String,
     output_buffer: OutputBuffer,
     /// Notifies waiters whenever new output has been appended to
     /// `output_buffer`, allowing clients to poll for fresh data.
@@ -100,7 +108,7 @@ type OutputBuffer = Arc<Mutex<OutputBufferState>>;
 type OutputHandles = (OutputBuffer, Arc<Notify>);
 
 impl ManagedUnifiedExecSession {
-    fn new(session: ExecCommandSession) -> Self {
+    fn new(session: ExecCommandSession, sub_id: String) -> Self {
         let output_buffer = Arc::new(Mutex::new(OutputBufferState::default()));
         let output_notify = Arc::new(Notify::new());
         let mut receiver = session.output_receiver();
@@ -117,6 +125,7 @@ impl ManagedUnifiedExecSession {
 
         Self {
             session,
+            sub_id,
             output_buffer,
             output_notify,
             output_task,
@@ -146,6 +155,21 @@ impl Drop for ManagedUnifiedExecSession {
 }
 
 impl UnifiedExecSessionManager {
+    /// Kill and forget the sessions opened by turn `sub_id`, leaving those of
+    /// other turns running. Returns how many were still alive.
+    pub async fn terminate_turn(&self, sub_id: &str) -> usize {
+        let mut sessions = self.sessions.lock().await;
+        let ids: Vec<i32> = sessions
+            .iter()
+            .filter(|(_, s)| s.sub_id == sub_id)
+            .map(|(id, _)| *id)
+            .collect();
+        ids.iter()
+            .filter_map(|id| sessions.remove(id))
+            .filter(|s| !s.has_exited())
+            .count()
+    }
+
     pub async fn handle_request(
         &self,
         request: UnifiedExecRequest<'_>,
@@ -193,8 +217,9 @@ impl UnifiedExecSessionManager {
         } else {
             let command = request.input_chunks.to_vec();
             let new_id = self.next_session_id.fetch_add(1, Ordering::SeqCst);
-            let session = create_unified_exec_session(&command).await?;
-            let managed_session = ManagedUnifiedExecSession::new(session);
+            let session = create_unified_exec_session(&command, request.env).await?;
+            let managed_session =
+                ManagedUnifiedExecSession::new(session, request.sub_id.to_string());
             let (buffer, notify) = managed_session.output_handles();
             writer_tx = managed_session.writer_sender();
             output_buffer = buffer;
@@ -253,6 +278,7 @@ impl UnifiedExecSessionManager {
         let (output, _maybe_tokens) = truncate_middle(
             &String::from_utf8_lossy(&collected),
             UNIFIED_EXEC_OUTPUT_MAX_BYTES,
+            &TokenCounter::default(),
         );
         let output = if let Some(warning) = timeout_warning {
             format!("{warning}{output}")
@@ -297,6 +323,7 @@ impl UnifiedExecSessionManager {
 
 async fn create_unified_exec_session(
     command: &[String],
+    env: Option<&HashMap<String, String>>,
 ) -> Result<ExecCommandSession, UnifiedExecError> {
     if command.is_empty() {
         return Err(UnifiedExecError::MissingCommandLine);
@@ -318,6 +345,12 @@ async fn create_unified_exec_session(
     for arg in &command[1..] {
         command_builder.arg(arg);
     }
+    if let Some(env) = env {
+        command_builder.env_clear();
+        for (key, value) in env {
+            command_builder.env(key, value);
+        }
+    }
 
     let mut child = pair
         .slave
@@ -422,6 +455,8 @@ mod tests {
                 session_id: None,
                 input_chunks: &["bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session_id");
@@ -434,6 +469,8 @@ mod tests {
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -442,6 +479,8 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         assert!(out_2.output.contains("codex"));
@@ -459,6 +498,8 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         let session_a = shell_a.session_id.expect("expected session id");
@@ -468,6 +509,8 @@ mod tests {
                 session_id: Some(session_a),
                 input_chunks: &["export CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -479,6 +522,8 @@ mod tests {
                     "$CODEX_INTERACTIVE_SHELL_VAR\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         assert!(!out_2.output.contains("codex"));
@@ -488,6 +533,8 @@ mod tests {
                 session_id: Some(session_a),
                 input_chunks: &["echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         assert!(out_3.output.contains("codex"));
@@ -505,6 +552,8 @@ mod tests {
                 session_id: None,
                 input_chunks: &["bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session id");
@@ -517,6 +566,8 @@ mod tests {
                     "CODEX_INTERACTIVE_SHELL_VAR=codex\n".to_string(),
                 ],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -525,6 +576,8 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["sleep 5 && echo $CODEX_INTERACTIVE_SHELL_VAR\n".to_string()],
                 timeout_ms: Some(10),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         assert!(!out_2.output.contains("codex"));
@@ -537,6 +590,8 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &empty,
                 timeout_ms: Some(100),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -555,6 +610,8 @@ mod tests {
                 session_id: None,
                 input_chunks: &["echo".to_string(), "codex".to_string()],
                 timeout_ms: Some(120_000),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -575,6 +632,8 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/echo".to_string(), "codex".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -586,6 +645,61 @@ mod tests {
         Ok(())
     }
 
+    #[cfg(unix)]
+    #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+    async fn terminating_a_turn_closes_only_its_sessions() -> Result<(), UnifiedExecError> {
+        let manager = UnifiedExecSessionManager::default();
+        let earlier_shell = manager
+            .handle_request(UnifiedExecRequest {
+                session_id: None,
+                input_chunks: &["bash".to_string(), "-i".to_string()],
+                timeout_ms: Some(1_500),
+                env: None,
+                sub_id: "0",
+            })
+            .await?;
+        let earlier_id = earlier_shell.session_id.expect("expected session id");
+        let open_shell = manager
+            .handle_request(UnifiedExecRequest {
+                session_id: None,
+                input_chunks: &["bash".to_string(), "-i".to_string()],
+                timeout_ms: Some(1_500),
+                env: None,
+                sub_id: "1",
+            })
+            .await?;
+        let session_id = open_shell.session_id.expect("expected session id");
+
+        assert_eq!(manager.terminate_turn("1").await, 1);
+        assert_eq!(
+            manager
+                .sessions
+                .lock()
+                .await
+                .keys()
+                .copied()
+                .collect::<Vec<_>>(),
+            vec![earlier_id]
+        );
+
+        let err = manager
+            .handle_request(UnifiedExecRequest {
+                session_id: Some(session_id),
+                input_chunks: &["echo hi\n".to_string()],
+                timeout_ms: Some(500),
+                env: None,
+                sub_id: "1",
+            })
+            .await
+            .expect_err("session should have been closed");
+        assert!(matches!(
+            err,
+            UnifiedExecError::UnknownSessionId { session_id: err_id } if err_id == session_id
+        ));
+
+        Ok(())
+    }
+
     #[cfg(unix)]
     #[tokio::test(flavor = "multi_thread", worker_threads = 2)]
     async fn reusing_completed_session_returns_unknown_session() -> Result<(), UnifiedExecError> {
@@ -596,6 +710,8 @@ mod tests {
                 session_id: None,
                 input_chunks: &["/bin/bash".to_string(), "-i".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
         let session_id = open_shell.session_id.expect("expected session id");
@@ -605,6 +721,8 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &["exit\n".to_string()],
                 timeout_ms: Some(2_500),
+                env: None,
+                sub_id: "1",
             })
             .await?;
 
@@ -615,6 +733,8 @@ mod tests {
                 session_id: Some(session_id),
                 input_chunks: &[],
                 timeout_ms: Some(100),
+                env: None,
+                sub_id: "1",
             })
             .await
             .expect_err("expected unknown session error");
@@ -1,11 +1,15 @@
+use std::path::PathBuf;
+
 use serde::Serialize;
+use tracing::error;
+use tracing::warn;
 
 /// User can configure a program that will receive notifications. Each
 /// notification is serialized as JSON and passed as an argument to the
 /// program.
 #[derive(Debug, Clone, PartialEq, Serialize)]
 #[serde(tag = "type", rename_all = "kebab-case")]
-pub(crate) enum UserNotification {
+pub enum UserNotification {
     #[serde(rename_all = "kebab-case")]
     AgentTurnComplete {
         turn_id: String,
@@ -16,6 +20,42 @@ pub(crate) enum UserNotification {
         /// The last message sent by the assistant in the turn.
         last_assistant_message: Option<String>,
     },
+
+    /// A run started by `codex schedule` did not succeed.
+    #[serde(rename_all = "kebab-case")]
+    ScheduledRunFailed {
+        schedule: String,
+
+        /// `None` when the run could not be started or was killed by a
+        /// signal.
+        exit_code: Option<i32>,
+
+        /// File holding the run's output.
+        log_file: PathBuf,
+    },
+}
+
+impl UserNotification {
+    /// Spawn `notify_command` with this notification as its last argument,
+    /// without waiting for it.
+    pub fn send(&self, notify_command: &[String]) {
+        let Some((program, args)) = notify_command.split_first() else {
+            return;
+        };
+
+        let Ok(json) = serde_json::to_string(self) else {
+            error!("failed to serialise notification payload");
+            return;
+        };
+
+        let mut command = std::process::Command::new(program);
+        command.args(args).arg(json);
+
+        // Fire-and-forget – we do not wait for completion.
+        if let Err(e) = command.spawn() {
+            warn!("failed to spawn notifier '{program}': {e}");
+        }
+    }
 }
 
 #[cfg(test)]
@@ -0,0 +1,234 @@
+//! Rejects patches whose paths escape the writable roots through symlinks or
+//! `..` components.
+//!
+//! `assess_patch_safety` only normalizes paths lexically, so a patch that
+//! writes to `workspace/link/file`, where `link` points at `/etc`, looks like a
+//! write inside the workspace. Here every target is resolved against the file
+//! system the way the OS would when writing it, and the result must lie under
+//! a writable root or a `[workspace_guard] allow` entry.
+
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_apply_patch::ApplyPatchAction;
+use codex_apply_patch::ApplyPatchFileChange;
+
+use crate::protocol::SandboxPolicy;
+use crate::protocol::WorkspaceEscape;
+
+/// Symlink hops followed before giving up on a path, matching Linux's limit.
+const MAX_SYMLINK_HOPS: usize = 40;
+
+/// Paths written by `action` (including rename targets) that resolve outside
+/// the writable roots of `sandbox_policy` and every directory in `allow`.
+/// Under `ReadOnly` the workspace is `cwd`; `DangerFullAccess` is unchecked.
+pub(crate) fn find_workspace_escapes(
+    action: &ApplyPatchAction,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    allow: &[PathBuf],
+) -> Vec<WorkspaceEscape> {
+    let Some(allowed) = allowed_roots(sandbox_policy, cwd, allow) else {
+        return Vec::new();
+    };
+
+    let mut targets: Vec<&PathBuf> = Vec::new();
+    for (path, change) in action.changes() {
+        targets.push(path);
+        if let ApplyPatchFileChange::Update {
+            move_path: Some(dest),
+            ..
+        } = change
+        {
+            targets.push(dest);
+        }
+    }
+    // `changes()` is a map, so sort for a stable order in the event.
+    targets.sort();
+
+    targets
+        .into_iter()
+        .filter_map(|path| {
+            let resolved = resolve_for_write(&cwd.join(path));
+            let inside = allowed.iter().any(|root| resolved.starts_with(root));
+            (!inside).then(|| WorkspaceEscape {
+                path: path.clone(),
+                resolved,
+            })
+        })
+        .collect()
+}
+
+/// Whether `dir`, resolved the way a write would be, lies under a writable
+/// root of `sandbox_policy` or a directory in `allow`. Always true under
+/// `DangerFullAccess`.
+pub(crate) fn is_inside_writable_roots(
+    dir: &Path,
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    allow: &[PathBuf],
+) -> bool {
+    let Some(allowed) = allowed_roots(sandbox_policy, cwd, allow) else {
+        return true;
+    };
+    let resolved = resolve_for_write(&cwd.join(dir));
+    allowed.iter().any(|root| resolved.starts_with(root))
+}
+
+/// The resolved writable roots plus `allow`, or `None` when everything is
+/// writable.
+fn allowed_roots(
+    sandbox_policy: &SandboxPolicy,
+    cwd: &Path,
+    allow: &[PathBuf],
+) -> Option<Vec<PathBuf>> {
+    let roots: Vec<PathBuf> = match sandbox_policy {
+        SandboxPolicy::DangerFullAccess => return None,
+        SandboxPolicy::ReadOnly => vec![cwd.to_path_buf()],
+        SandboxPolicy::WorkspaceWrite { .. } => sandbox_policy
+            .get_writable_roots_with_cwd(cwd)
+            .into_iter()
+            .map(|writable| writable.root)
+            .collect(),
+    };
+    Some(
+        roots
+            .iter()
+            .chain(allow)
+            .map(|root| resolve_for_write(&cwd.join(root)))
+            .collect(),
+    )
+}
+
+/// Where a write to `path` would land: symlinks are followed (including a
+/// dangling one in the final component) and `..` is applied to the resolved
+/// parent rather than lexically. Components that do not exist yet are
+/// appended as written.
+fn resolve_for_write(path: &Path) -> PathBuf {
+    resolve_with_hops(path, MAX_SYMLINK_HOPS)
+}
+
+fn resolve_with_hops(path: &Path, hops: usize) -> PathBuf {
+    let components: Vec<Component> = path.components().collect();
+    for split in (1..=components.len()).rev() {
+        let prefix: PathBuf = components[..split].iter().collect();
+        if let Ok(real) = prefix.canonicalize() {
+            return append_components(real, &components[split..]);
+        }
+        // A dangling symlink: follow it by hand, since the write would
+        // create its target.
+        if hops > 0
+            && let Ok(target) = std::fs::read_link(&prefix)
+        {
+            let base = prefix.parent().unwrap_or(Path::new("/"));
+            let redirected = append_components(base.join(target), &components[split..]);
+            return resolve_with_hops(&redirected, hops - 1);
+        }
+    }
+    append_components(PathBuf::new(), &components)
+}
+
+fn append_components(mut base: PathBuf, components: &[Component]) -> PathBuf {
+    for component in components {
+        match component {
+            Component::ParentDir => {
+                base.pop();
+            }
+            Component::CurDir => {}
+            other => base.push(other.as_os_str()),
+        }
+    }
+    base
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+    use tempfile::TempDir;
+
+    fn workspace_write() -> SandboxPolicy {
+        SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec![],
+            network_access: false,
+            exclude_tmpdir_env_var: true,
+            exclude_slash_tmp: true,
+        }
+    }
+
+    #[test]
+    fn parent_components_outside_the_workspace_are_rejected() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().join("repo");
+        std::fs::create_dir(&cwd).unwrap();
+
+        let inside = ApplyPatchAction::new_add_for_test(&cwd.join("a/../b.txt"), String::new());
+        assert_eq!(
+            find_workspace_escapes(&inside, &workspace_write(), &cwd, &[]),
+            vec![]
+        );
+
+        let outside =
+            ApplyPatchAction::new_add_for_test(&cwd.join("new/../../x.txt"), String::new());
+        let escapes = find_workspace_escapes(&outside, &workspace_write(), &cwd, &[]);
+        assert_eq!(escapes.len(), 1);
+        assert_eq!(
+            escapes[0].resolved,
+            tmp.path().canonicalize().unwrap().join("x.txt")
+        );
+        assert_eq!(
+            find_workspace_escapes(&outside, &SandboxPolicy::DangerFullAccess, &cwd, &[]),
+            vec![]
+        );
+    }
+
+    #[test]
+    fn working_directories_must_stay_inside_the_writable_roots() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().join("repo");
+        std::fs::create_dir_all(cwd.join("src")).unwrap();
+
+        let inside =
+            |dir: &str| is_inside_writable_roots(Path::new(dir), &workspace_write(), &cwd, &[]);
+        assert!(inside("src"));
+        assert!(inside("."));
+        assert!(!inside(".."));
+        assert!(!inside("src/../.."));
+        assert!(!inside("/"));
+        assert!(is_inside_writable_roots(
+            Path::new("/"),
+            &SandboxPolicy::DangerFullAccess,
+            &cwd,
+            &[]
+        ));
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn symlinks_out_of_the_workspace_are_rejected_unless_allowed() {
+        let tmp = TempDir::new().unwrap();
+        let cwd = tmp.path().join("repo");
+        let shared = tmp.path().join("shared");
+        std::fs::create_dir(&cwd).unwrap();
+        std::fs::create_dir(&shared).unwrap();
+        std::os::unix::fs::symlink(&shared, cwd.join("link")).unwrap();
+        std::os::unix::fs::symlink(shared.join("missing.txt"), cwd.join("dangling")).unwrap();
+
+        for name in ["link/file.txt", "dangling"] {
+            let action = ApplyPatchAction::new_add_for_test(&cwd.join(name), String::new());
+            let escapes = find_workspace_escapes(&action, &workspace_write(), &cwd, &[]);
+            assert_eq!(escapes.len(), 1, "{name} should escape");
+            assert!(
+                escapes[0]
+                    .resolved
+                    .starts_with(shared.canonicalize().unwrap())
+            );
+
+            assert_eq!(
+                find_workspace_escapes(&action, &workspace_write(), &cwd, &[shared.clone()]),
+                vec![]
+            );
+        }
+    }
+}
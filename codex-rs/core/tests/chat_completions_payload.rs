@@ -19,6 +19,7 @@ use tempfile::TempDir;
 use wiremock::Mock;
 use wiremock::MockServer;
 use wiremock::ResponseTemplate;
+use wiremock::matchers::body_string_contains;
 use wiremock::matchers::method;
 use wiremock::matchers::path;
 
@@ -343,3 +344,91 @@ async fn suppresses_duplicate_assistant_messages() {
         Value::String("dup".into())
     );
 }
+
+#[tokio::test(flavor = "multi_thread", worker_threads = 2)]
+async fn probes_and_retries_without_rejected_tools() {
+    if network_disabled() {
+        println!(
+            "Skipping test because it cannot execute when network is disabled in a Codex sandbox."
+        );
+        return;
+    }
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_string_contains("\"tools\""))
+        .respond_with(ResponseTemplate::new(400).set_body_string("tools are not supported"))
+        .with_priority(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("content-type", "text/event-stream")
+                .set_body_raw(
+                    "data: {\"choices\":[{\"delta\":{}}]}\n\ndata: [DONE]\n\n",
+                    "text/event-stream",
+                ),
+        )
+        .mount(&server)
+        .await;
+
+    let provider = ModelProviderInfo {
+        name: "mock".into(),
+        base_url: Some(format!("{}/v1", server.uri())),
+        env_key: None,
+        env_key_instructions: None,
+        wire_api: WireApi::Chat,
+        query_params: None,
+        http_headers: None,
+        env_http_headers: None,
+        request_max_retries: Some(0),
+        stream_max_retries: Some(0),
+        stream_idle_timeout_ms: Some(5_000),
+        requires_openai_auth: false,
+    };
+    let codex_home = TempDir::new().expect("tempdir");
+    let mut config = load_default_config_for_test(&codex_home);
+    config.model_provider_id = provider.name.clone();
+    config.model_provider = provider.clone();
+    let effort = config.model_reasoning_effort;
+    let summary = config.model_reasoning_summary;
+    let client = ModelClient::new(
+        Arc::new(config),
+        None,
+        provider,
+        effort,
+        summary,
+        ConversationId::new(),
+    );
+
+    let mut prompt = Prompt::default();
+    prompt.input = vec![user_message("u1")];
+    let mut stream = client.stream(&prompt).await.expect("stream after probing");
+    while let Some(event) = stream.next().await {
+        event.expect("stream event");
+    }
+
+    let requests = server.received_requests().await.expect("requests");
+    let last: Value = requests
+        .last()
+        .expect("retried request")
+        .body_json()
+        .expect("json body");
+    assert!(last.get("tools").is_none());
+    assert_eq!(last["stream"], Value::Bool(true));
+
+    let cached: Value = serde_json::from_str(
+        &std::fs::read_to_string(codex_home.path().join("capabilities.json"))
+            .expect("capabilities.json"),
+    )
+    .expect("cache json");
+    let entry = cached
+        .as_object()
+        .and_then(|cache| cache.values().next())
+        .expect("cache entry");
+    assert_eq!(entry["tools"], Value::Bool(false));
+    assert_eq!(entry["streaming"], Value::Bool(true));
+}
@@ -4,6 +4,8 @@ use std::collections::HashMap;
 use std::path::PathBuf;
 
 use async_channel::Receiver;
+use codex_core::error::CodexErr;
+use codex_core::error::SandboxErr;
 use codex_core::exec::ExecParams;
 use codex_core::exec::SandboxType;
 use codex_core::exec::StdoutStream;
@@ -170,3 +172,37 @@ async fn test_aggregated_output_interleaves_in_order() {
     assert_eq!(result.aggregated_output.text, "O1\nE1\nO2\nE2\n");
     assert_eq!(result.aggregated_output.truncated_after_lines, None);
 }
+
+#[tokio::test]
+async fn test_timeout_kills_background_grandchildren() {
+    // The backgrounded `sleep` inherits stdout. Unless the whole process group
+    // is killed on timeout, reading the output would block until it exits.
+    let cmd = vec![
+        "/bin/sh".to_string(),
+        "-c".to_string(),
+        "sleep 30 & sleep 30".to_string(),
+    ];
+
+    let params = ExecParams {
+        command: cmd,
+        cwd: std::env::current_dir().unwrap_or_else(|_| PathBuf::from(".")),
+        timeout_ms: Some(200),
+        env: HashMap::new(),
+        with_escalated_permissions: None,
+        justification: None,
+    };
+
+    let policy = SandboxPolicy::new_read_only_policy();
+
+    let start = std::time::Instant::now();
+    let result = process_exec_tool_call(params, SandboxType::None, &policy, &None, None).await;
+
+    assert!(
+        matches!(result, Err(CodexErr::Sandbox(SandboxErr::Timeout))),
+        "expected timeout, got {result:?}"
+    );
+    assert!(
+        start.elapsed() < std::time::Duration::from_secs(10),
+        "exec did not return promptly after timeout"
+    );
+}
@@ -240,11 +240,15 @@ async fn prefixes_context_and_instructions_once_and_consistently_across_requests
 
     let conversation_manager =
         ConversationManager::with_auth(CodexAuth::from_api_key("Test API Key"));
-    let codex = conversation_manager
+    let new_conversation = conversation_manager
         .new_conversation(config)
         .await
-        .expect("create new conversation")
-        .conversation;
+        .expect("create new conversation");
+    let codex = new_conversation.conversation;
+    let scratch_path = codex_home
+        .path()
+        .join("scratch")
+        .join(new_conversation.conversation_id.to_string());
 
     codex
         .submit(Op::UserInput {
@@ -277,12 +281,14 @@ async fn prefixes_context_and_instructions_once_and_consistently_across_requests
   <approval_policy>on-request</approval_policy>
   <sandbox_mode>read-only</sandbox_mode>
   <network_access>restricted</network_access>
-{}</environment_context>"#,
+{}  <scratch_path>{}</scratch_path>
+</environment_context>"#,
         cwd.path().to_string_lossy(),
         match shell.name() {
             Some(name) => format!("  <shell>{name}</shell>\n"),
             None => String::new(),
-        }
+        },
+        scratch_path.to_string_lossy()
     );
     let expected_ui_text =
         "<user_instructions>\n\nbe consistent and helpful\n\n</user_instructions>";
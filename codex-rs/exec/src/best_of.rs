@@ -0,0 +1,490 @@
+//! `codex exec --best-of N`: run the same task N times at once, each in its
+//! own git worktree and optionally with a different model preset, then show
+//! the resulting diffs next to each other so one can be applied. With
+//! `--judge`, a read-only session ranks the variants first.
+
+use std::io::IsTerminal;
+use std::io::Write;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::time::SystemTime;
+use std::time::UNIX_EPOCH;
+
+use codex_common::model_presets::OwnedModelPreset;
+use codex_common::model_presets::load_model_presets_owned;
+use codex_core::AuthManager;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::git_info::get_git_repo_root;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::TaskCompleteEvent;
+use tokio::task::JoinSet;
+
+use crate::git::git;
+
+/// Subdirectory of `CODEX_HOME` holding the worktrees of `--best-of` runs.
+const WORKTREES_SUBDIR: &str = "worktrees";
+
+/// Characters of each variant's diff shown to the judge.
+const MAX_JUDGE_DIFF_CHARS: usize = 20_000;
+
+/// Prefix of the line in which the judge ranks the variants.
+const RANKING_PREFIX: &str = "RANKING:";
+
+pub(crate) struct BestOfOptions {
+    pub(crate) count: usize,
+    /// Preset ids, assigned to the variants in turn.
+    pub(crate) presets: Vec<String>,
+    pub(crate) judge: bool,
+}
+
+/// One attempt at the task and what it produced.
+struct Variant {
+    /// 1-based.
+    number: usize,
+    label: String,
+    worktree: PathBuf,
+    /// Tree of the worktree before the agent started, the base of its diff.
+    base_tree: String,
+    outcome: anyhow::Result<Option<String>>,
+    diff: String,
+    files_changed: usize,
+    insertions: usize,
+    deletions: usize,
+}
+
+pub(crate) async fn run_best_of(
+    config: Config,
+    prompt: String,
+    options: BestOfOptions,
+) -> anyhow::Result<()> {
+    let Some(root) = get_git_repo_root(&config.cwd) else {
+        anyhow::bail!(
+            "--best-of runs each variant in a git worktree; run it inside a git repository"
+        );
+    };
+    let relative_cwd = config
+        .cwd
+        .strip_prefix(&root)
+        .map(Path::to_path_buf)
+        .unwrap_or_default();
+    let presets = resolve_presets(&options.presets)?;
+
+    let stamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let run_dir = config
+        .codex_home
+        .join(WORKTREES_SUBDIR)
+        .join(format!("best-of-{stamp}"));
+    std::fs::create_dir_all(&run_dir)?;
+
+    // Variants start from the working tree as it is, uncommitted changes to
+    // tracked files included.
+    let uncommitted = git(&root, &["diff", "--binary", "HEAD"], None).await?;
+    let mut variants = Vec::with_capacity(options.count);
+    for number in 1..=options.count {
+        let worktree = run_dir.join(format!("variant-{number}"));
+        let worktree_arg = worktree.to_string_lossy();
+        git(
+            &root,
+            &["worktree", "add", "--detach", &worktree_arg, "HEAD"],
+            None,
+        )
+        .await?;
+        if !uncommitted.is_empty() {
+            git(&worktree, &["apply", "--binary"], Some(&uncommitted)).await?;
+        }
+        let base_tree = snapshot_tree(&worktree).await?;
+        let preset = presets.get((number - 1) % presets.len().max(1)).cloned();
+        let label = match &preset {
+            Some(preset) => format!("{number}: {}", preset.label),
+            None => format!("{number}: {}", config.model),
+        };
+        variants.push((number, label, worktree, base_tree, preset));
+    }
+
+    let manager = Arc::new(ConversationManager::new(AuthManager::shared(
+        config.codex_home.clone(),
+    )));
+    let mut runs = JoinSet::new();
+    for (number, label, worktree, _, preset) in &variants {
+        let mut variant_config = config.clone();
+        variant_config.cwd = worktree.join(&relative_cwd);
+        let manager = manager.clone();
+        let prompt = prompt.clone();
+        let label = label.clone();
+        let preset = preset.clone();
+        let number = *number;
+        runs.spawn(async move {
+            let outcome =
+                run_variant(&manager, variant_config, prompt, preset.as_ref(), &label).await;
+            (number, outcome)
+        });
+    }
+    let mut outcomes = Vec::new();
+    while let Some(joined) = runs.join_next().await {
+        outcomes.push(joined?);
+    }
+
+    let mut finished = Vec::new();
+    for (number, label, worktree, base_tree, _) in variants {
+        let outcome = outcomes
+            .iter_mut()
+            .find(|(n, _)| *n == number)
+            .map(|(_, outcome)| std::mem::replace(outcome, Ok(None)))
+            .unwrap_or(Ok(None));
+        let diff = worktree_diff(&worktree, &base_tree).await?;
+        let (files_changed, insertions, deletions) = diff_stats(&worktree, &base_tree).await?;
+        std::fs::write(run_dir.join(format!("variant-{number}.diff")), &diff)?;
+        finished.push(Variant {
+            number,
+            label,
+            worktree,
+            base_tree,
+            outcome,
+            diff,
+            files_changed,
+            insertions,
+            deletions,
+        });
+    }
+
+    print!("{}", format_summary(&finished));
+    for variant in &finished {
+        println!("\n===== variant {} =====", variant.label);
+        if variant.diff.is_empty() {
+            println!("(no changes)");
+        } else {
+            print!("{}", variant.diff);
+        }
+    }
+
+    let mut recommended = None;
+    if options.judge {
+        match judge(&manager, &config, &root, &prompt, &finished).await {
+            Ok((ranking, rationale)) => {
+                println!("\n===== judge =====\n{rationale}");
+                let order: Vec<String> = ranking.iter().map(ToString::to_string).collect();
+                println!("\nRanking: {}", order.join(" > "));
+                recommended = ranking.first().copied();
+            }
+            Err(e) => eprintln!("The judge could not rank the variants: {e:#}"),
+        }
+    }
+
+    let chosen = if std::io::stdin().is_terminal() {
+        ask_for_variant(&finished, recommended)?
+    } else {
+        None
+    };
+    match chosen.and_then(|n| finished.iter().find(|v| v.number == n)) {
+        Some(variant) => {
+            if !variant.diff.is_empty() {
+                git(&root, &["apply", "--binary"], Some(variant.diff.as_bytes())).await?;
+            }
+            println!("Applied variant {} to {}", variant.label, root.display());
+            for variant in &finished {
+                let worktree_arg = variant.worktree.to_string_lossy();
+                if let Err(e) = git(
+                    &root,
+                    &["worktree", "remove", "--force", &worktree_arg],
+                    None,
+                )
+                .await
+                {
+                    eprintln!("Failed to remove {}: {e:#}", variant.worktree.display());
+                }
+            }
+        }
+        None => {
+            println!(
+                "\nNothing applied. The variants' worktrees and diffs are in {}; apply one with \
+                 `git apply {}/variant-<n>.diff` and remove them with `git worktree remove`.",
+                run_dir.display(),
+                run_dir.display()
+            );
+        }
+    }
+    Ok(())
+}
+
+/// The presets named by `ids`, in order.
+fn resolve_presets(ids: &[String]) -> anyhow::Result<Vec<OwnedModelPreset>> {
+    let available = load_model_presets_owned();
+    ids.iter()
+        .map(|id| {
+            available
+                .iter()
+                .find(|preset| &preset.id == id)
+                .cloned()
+                .ok_or_else(|| {
+                    let known: Vec<&str> = available.iter().map(|p| p.id.as_str()).collect();
+                    anyhow::anyhow!("unknown preset {id}; available: {}", known.join(", "))
+                })
+        })
+        .collect()
+}
+
+/// Run the task in one worktree; the agent's last message on success.
+async fn run_variant(
+    manager: &ConversationManager,
+    config: Config,
+    prompt: String,
+    preset: Option<&OwnedModelPreset>,
+    label: &str,
+) -> anyhow::Result<Option<String>> {
+    let NewConversation { conversation, .. } = manager.new_conversation(config).await?;
+    if let Some(preset) = preset {
+        conversation
+            .submit(Op::OverrideTurnContext {
+                cwd: None,
+                approval_policy: None,
+                sandbox_policy: None,
+                model: Some(preset.model.clone()),
+                effort: Some(preset.effort),
+                summary: None,
+            })
+            .await?;
+    }
+    conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text: prompt }],
+        })
+        .await?;
+    eprintln!("[{label}] started");
+
+    let result = loop {
+        let event = conversation.next_event().await?;
+        match event.msg {
+            // Nobody can answer approval prompts here.
+            EventMsg::ExecApprovalRequest(_) => {
+                conversation
+                    .submit(Op::ExecApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::ApplyPatchApprovalRequest(_) => {
+                conversation
+                    .submit(Op::PatchApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::ExecCommandBegin(begin) => {
+                eprintln!("[{label}] $ {}", begin.command.join(" "));
+            }
+            EventMsg::PatchApplyEnd(end) if end.success => {
+                eprintln!("[{label}] patch applied");
+            }
+            EventMsg::Error(err) => break Err(anyhow::anyhow!(err.message)),
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                break Ok(last_agent_message);
+            }
+            EventMsg::ShutdownComplete => break Ok(None),
+            _ => {}
+        }
+    };
+    match &result {
+        Ok(_) => eprintln!("[{label}] done"),
+        Err(e) => eprintln!("[{label}] failed: {e}"),
+    }
+    let _ = conversation.submit(Op::Shutdown).await;
+    result
+}
+
+/// Stage everything in `worktree` and return the tree it forms.
+async fn snapshot_tree(worktree: &Path) -> anyhow::Result<String> {
+    git(worktree, &["add", "-A"], None).await?;
+    let tree = git(worktree, &["write-tree"], None).await?;
+    Ok(String::from_utf8_lossy(&tree).trim().to_string())
+}
+
+/// Everything the agent changed in `worktree`, new files included.
+async fn worktree_diff(worktree: &Path, base_tree: &str) -> anyhow::Result<String> {
+    git(worktree, &["add", "-A"], None).await?;
+    let diff = git(worktree, &["diff", "--cached", "--binary", base_tree], None).await?;
+    Ok(String::from_utf8_lossy(&diff).into_owned())
+}
+
+/// (files changed, insertions, deletions) of the staged diff.
+async fn diff_stats(worktree: &Path, base_tree: &str) -> anyhow::Result<(usize, usize, usize)> {
+    let numstat = git(
+        worktree,
+        &["diff", "--cached", "--numstat", base_tree],
+        None,
+    )
+    .await?;
+    Ok(parse_numstat(&String::from_utf8_lossy(&numstat)))
+}
+
+fn parse_numstat(numstat: &str) -> (usize, usize, usize) {
+    numstat.lines().filter(|line| !line.trim().is_empty()).fold(
+        (0, 0, 0),
+        |(files, ins, del), line| {
+            let mut fields = line.split('\t');
+            // Binary files show `-` for both counts.
+            let added = fields.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            let removed = fields.next().and_then(|n| n.parse().ok()).unwrap_or(0);
+            (files + 1, ins + added, del + removed)
+        },
+    )
+}
+
+/// One row per variant: how it ended and the size of its diff.
+fn format_summary(variants: &[Variant]) -> String {
+    let width = variants.iter().map(|v| v.label.len()).max().unwrap_or(0);
+    let mut out = String::from("\n===== variants =====\n");
+    for variant in variants {
+        let status = match &variant.outcome {
+            Ok(Some(message)) => message.lines().next().unwrap_or_default().to_string(),
+            Ok(None) => "finished without a final message".to_string(),
+            Err(e) => format!("failed: {e}"),
+        };
+        out.push_str(&format!(
+            "{:<width$}  {:>3} files  +{:<5} -{:<5}  {status}\n",
+            variant.label, variant.files_changed, variant.insertions, variant.deletions
+        ));
+    }
+    out
+}
+
+/// Ask which variant to apply; `None` to apply nothing.
+fn ask_for_variant(
+    variants: &[Variant],
+    recommended: Option<usize>,
+) -> anyhow::Result<Option<usize>> {
+    let default = recommended.map(|n| format!(" [{n}]")).unwrap_or_default();
+    loop {
+        print!(
+            "\nApply which variant? 1-{}{default}, or n for none: ",
+            variants.len()
+        );
+        std::io::stdout().flush()?;
+        let mut answer = String::new();
+        if std::io::stdin().read_line(&mut answer)? == 0 {
+            return Ok(None);
+        }
+        match answer.trim() {
+            "" if recommended.is_some() => return Ok(recommended),
+            "" | "n" | "N" => return Ok(None),
+            other => match other.parse::<usize>() {
+                Ok(n) if (1..=variants.len()).contains(&n) => return Ok(Some(n)),
+                _ => println!("Enter a number between 1 and {}.", variants.len()),
+            },
+        }
+    }
+}
+
+/// Ask a read-only session to rank the variants; (ranking, its answer).
+async fn judge(
+    manager: &ConversationManager,
+    config: &Config,
+    root: &Path,
+    task: &str,
+    variants: &[Variant],
+) -> anyhow::Result<(Vec<usize>, String)> {
+    let mut judge_config = config.clone();
+    judge_config.cwd = root.to_path_buf();
+    judge_config.sandbox_policy = SandboxPolicy::new_read_only_policy();
+
+    let mut prompt = format!(
+        "Several attempts were made at the same task, each starting from the current state of \
+         this repository. Compare the resulting diffs for correctness, completeness and code \
+         quality. You may read files in the repository, but do not change anything.\n\n\
+         Task:\n{task}\n"
+    );
+    for variant in variants {
+        let mut diff = variant.diff.clone();
+        if diff.len() > MAX_JUDGE_DIFF_CHARS {
+            let mut end = MAX_JUDGE_DIFF_CHARS;
+            while !diff.is_char_boundary(end) {
+                end -= 1;
+            }
+            diff.truncate(end);
+            diff.push_str("\n[diff truncated]");
+        }
+        if variant.outcome.is_err() {
+            diff.push_str("\n[this attempt failed before finishing]");
+        }
+        prompt.push_str(&format!(
+            "\nVariant {}:\n```diff\n{diff}\n```\n",
+            variant.number
+        ));
+    }
+    prompt.push_str(&format!(
+        "\nExplain your assessment briefly, then end with a line of the form \
+         `{RANKING_PREFIX} 2, 1, 3` listing every variant number from best to worst."
+    ));
+
+    let answer = run_variant(manager, judge_config, prompt, None, "judge")
+        .await?
+        .ok_or_else(|| anyhow::anyhow!("the judge gave no answer"))?;
+    let ranking = parse_ranking(&answer, variants.len())
+        .ok_or_else(|| anyhow::anyhow!("the judge's answer has no {RANKING_PREFIX} line"))?;
+    Ok((ranking, answer))
+}
+
+/// The variant numbers in the judge's last ranking line, best first. Numbers
+/// out of range or repeated are dropped and missing ones appended.
+fn parse_ranking(answer: &str, count: usize) -> Option<Vec<usize>> {
+    let line = answer.lines().rev().find_map(|line| {
+        line.trim()
+            .trim_matches('`')
+            .trim()
+            .strip_prefix(RANKING_PREFIX)
+    })?;
+    let mut ranking: Vec<usize> = Vec::new();
+    for n in line
+        .split(|c: char| !c.is_ascii_digit())
+        .filter_map(|n| n.parse::<usize>().ok())
+    {
+        if (1..=count).contains(&n) && !ranking.contains(&n) {
+            ranking.push(n);
+        }
+    }
+    if ranking.is_empty() {
+        return None;
+    }
+    for n in 1..=count {
+        if !ranking.contains(&n) {
+            ranking.push(n);
+        }
+    }
+    Some(ranking)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn ranking_is_read_from_the_last_ranking_line() {
+        let answer = "Variant 2 handles the edge case.\n\n`RANKING: 2, 3`";
+        assert_eq!(parse_ranking(answer, 3), Some(vec![2, 3, 1]));
+        assert_eq!(
+            parse_ranking("RANKING: 1\nRANKING: 3, 3, 9, 1", 3),
+            Some(vec![3, 1, 2])
+        );
+        assert_eq!(parse_ranking("no verdict", 3), None);
+    }
+
+    #[test]
+    fn numstat_counts_files_and_lines() {
+        assert_eq!(
+            parse_numstat("3\t1\tsrc/lib.rs\n-\t-\tlogo.png\n10\t0\tsrc/new.rs\n"),
+            (3, 13, 1)
+        );
+    }
+}
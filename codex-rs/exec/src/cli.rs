@@ -6,7 +6,8 @@ use std::path::PathBuf;
 #[derive(Parser, Debug)]
 #[command(version)]
 pub struct Cli {
-    /// Optional image(s) to attach to the initial prompt.
+    /// Optional image(s), or PDF/DOCX document(s), to attach to the initial
+    /// prompt.
     #[arg(long = "image", short = 'i', value_name = "FILE", value_delimiter = ',', num_args = 1..)]
     pub images: Vec<PathBuf>,
 
@@ -40,6 +41,16 @@ pub struct Cli {
     )]
     pub dangerously_bypass_approvals_and_sandbox: bool,
 
+    /// Only offer tools that cannot modify anything: no patches, no MCP tools,
+    /// and shell commands run under a read-only sandbox without approval
+    /// prompts.
+    #[arg(
+        long = "read-only",
+        default_value_t = false,
+        conflicts_with_all = ["full_auto", "dangerously_bypass_approvals_and_sandbox", "sandbox_mode"]
+    )]
+    pub read_only: bool,
+
     /// Tell the agent to use the specified directory as its working root.
     #[clap(long = "cd", short = 'C', value_name = "DIR")]
     pub cwd: Option<PathBuf>,
@@ -48,6 +59,11 @@ pub struct Cli {
     #[arg(long = "skip-git-repo-check", default_value_t = false)]
     pub skip_git_repo_check: bool,
 
+    /// Refuse all network traffic except to loopback and the hosts listed in
+    /// `offline_allowed_hosts` (e.g. a LAN Ollama server).
+    #[arg(long = "offline", default_value_t = false)]
+    pub offline: bool,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
 
@@ -63,6 +79,67 @@ pub struct Cli {
     #[arg(long = "output-last-message")]
     pub last_message_file: Option<PathBuf>,
 
+    /// Write a JSON summary of the run (exit code, failure reason, last agent
+    /// message, token usage) to this file when codex exits.
+    #[arg(long = "result-file", value_name = "FILE")]
+    pub result_file: Option<PathBuf>,
+
+    /// Report the outcome to the code host running this CI pipeline (GitHub
+    /// Actions, GitLab CI/CD or Bitbucket Pipelines): a comment on the pull or
+    /// merge request and a status on the commit.
+    #[arg(long = "ci", default_value_t = false)]
+    pub ci: bool,
+
+    /// Run the task N times at once, each in its own git worktree, then show
+    /// the resulting diffs side by side and offer to apply one.
+    #[arg(
+        long = "best-of",
+        value_name = "N",
+        value_parser = clap::value_parser!(u16).range(2..),
+        conflicts_with_all = ["json", "ci", "images"]
+    )]
+    pub best_of: Option<u16>,
+
+    /// Model preset for the `--best-of` variants; repeat to give each variant
+    /// a different one. Presets are assigned to the variants in turn.
+    #[arg(long = "variant-preset", value_name = "ID", requires = "best_of")]
+    pub variant_presets: Vec<String>,
+
+    /// Have a read-only session rank the `--best-of` variants before choosing.
+    #[arg(long = "judge", default_value_t = false, requires = "best_of")]
+    pub judge: bool,
+
+    /// When the agent finishes, have a second, read-only session review the
+    /// diff against the instructions and send fixes back to the agent
+    /// (`[exec_critic]` in config.toml).
+    #[arg(long = "critic", default_value_t = false, conflicts_with_all = ["best_of", "read_only"])]
+    pub critic: bool,
+
+    /// Model preset the critic runs with. Implies `--critic`.
+    #[arg(long = "critic-preset", value_name = "ID", conflicts_with_all = ["best_of", "read_only"])]
+    pub critic_preset: Option<String>,
+
+    /// Revision rounds the critic may request (default: 2). Implies
+    /// `--critic`.
+    #[arg(long = "critic-rounds", value_name = "N", conflicts_with_all = ["best_of", "read_only"])]
+    pub critic_rounds: Option<u32>,
+
+    /// Run in the background and print the session id; `codex attach <id>`
+    /// then shows the session live and can answer approvals and steer it.
+    /// Output goes to a log file under `$CODEX_HOME/log`. Unix only.
+    #[arg(long = "detach", default_value_t = false, conflicts_with_all = ["best_of", "attachable"])]
+    pub detach: bool,
+
+    /// Let `codex attach` connect to this run while it stays in the
+    /// foreground. Approval requests wait for an attached client instead of
+    /// being denied. Unix only.
+    #[arg(
+        long = "attachable",
+        default_value_t = false,
+        conflicts_with = "best_of"
+    )]
+    pub attachable: bool,
+
     /// Initial instructions for the agent. If not provided as an argument (or
     /// if `-` is used), instructions are read from stdin.
     #[arg(value_name = "PROMPT")]
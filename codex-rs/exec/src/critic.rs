@@ -0,0 +1,290 @@
+//! Critic pass for `codex exec` (`[exec_critic]`, `--critic`): when the agent
+//! finishes, a second, read-only session reviews the diff against the
+//! original instructions. It either approves or lists fixes, which are sent
+//! back to the agent for another round, up to `max_rounds` times.
+
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use codex_common::model_presets::OwnedModelPreset;
+use codex_common::model_presets::load_model_presets_owned;
+use codex_core::CodexConversation;
+use codex_core::ConversationManager;
+use codex_core::NewConversation;
+use codex_core::config::Config;
+use codex_core::config_types::ExecCritic;
+use codex_core::git_info::get_git_repo_root;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::InputItem;
+use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol::TaskCompleteEvent;
+
+use crate::git::git;
+use crate::git::snapshot_working_tree;
+
+/// Characters of the diff shown to the critic.
+const MAX_DIFF_CHARS: usize = 60_000;
+
+/// Prefix of the line holding the critic's verdict.
+const VERDICT_PREFIX: &str = "VERDICT:";
+
+#[derive(Debug, PartialEq)]
+enum Verdict {
+    Approve,
+    /// The fixes the critic asks for.
+    Revise(String),
+}
+
+pub(crate) struct Critic {
+    config: Config,
+    preset: Option<OwnedModelPreset>,
+    root: PathBuf,
+    /// Working tree before the agent started, the base of the reviewed diff.
+    base_tree: String,
+    task: String,
+    max_rounds: u32,
+    rounds: u32,
+    /// The critic's session, kept across rounds so it remembers what it
+    /// asked for.
+    conversation: Option<Arc<CodexConversation>>,
+}
+
+impl Critic {
+    /// Snapshot the working tree so the critic can later review exactly
+    /// what the run changed.
+    pub(crate) async fn start(
+        config: &Config,
+        settings: &ExecCritic,
+        task: &str,
+    ) -> anyhow::Result<Self> {
+        let Some(root) = get_git_repo_root(&config.cwd) else {
+            anyhow::bail!("the critic reviews the run's git diff; run inside a git repository");
+        };
+        let preset = match &settings.preset {
+            Some(id) => Some(
+                load_model_presets_owned()
+                    .into_iter()
+                    .find(|preset| &preset.id == id)
+                    .ok_or_else(|| anyhow::anyhow!("unknown critic preset {id}"))?,
+            ),
+            None => None,
+        };
+        let base_tree = snapshot_working_tree(&root).await?;
+
+        let mut critic_config = config.clone();
+        critic_config.sandbox_policy = SandboxPolicy::new_read_only_policy();
+        Ok(Self {
+            config: critic_config,
+            preset,
+            root,
+            base_tree,
+            task: task.to_string(),
+            max_rounds: settings.max_rounds,
+            rounds: 0,
+            conversation: None,
+        })
+    }
+
+    /// Review the run so far. Returns the message to send back to the agent
+    /// when the critic asks for fixes and rounds remain.
+    pub(crate) async fn review(
+        &mut self,
+        manager: &ConversationManager,
+        final_message: Option<&str>,
+    ) -> anyhow::Result<Option<String>> {
+        let tree = snapshot_working_tree(&self.root).await?;
+        let diff = git(
+            &self.root,
+            &["diff", "--no-color", &self.base_tree, &tree],
+            None,
+        )
+        .await?;
+        let prompt = self.review_prompt(&String::from_utf8_lossy(&diff), final_message);
+
+        let conversation = match &self.conversation {
+            Some(conversation) => conversation.clone(),
+            None => {
+                let conversation = self.start_session(manager).await?;
+                self.conversation = Some(conversation.clone());
+                conversation
+            }
+        };
+        eprintln!("critic: reviewing the changes");
+        let answer = ask(&conversation, prompt)
+            .await?
+            .ok_or_else(|| anyhow::anyhow!("the critic gave no answer"))?;
+        let verdict = parse_verdict(&answer)
+            .ok_or_else(|| anyhow::anyhow!("the critic's answer has no {VERDICT_PREFIX} line"))?;
+
+        match verdict {
+            Verdict::Approve => {
+                eprintln!("critic: approved");
+                Ok(None)
+            }
+            Verdict::Revise(fixes) if self.rounds < self.max_rounds => {
+                self.rounds += 1;
+                eprintln!(
+                    "critic: fixes requested (round {} of {}):\n{fixes}",
+                    self.rounds, self.max_rounds
+                );
+                Ok(Some(format!(
+                    "A reviewer compared your changes with the original instructions and asks \
+                     for the following fixes:\n\n{fixes}\n\nMake them, then summarize what you \
+                     changed."
+                )))
+            }
+            Verdict::Revise(fixes) => {
+                eprintln!(
+                    "critic: still requests fixes after {} rounds; finishing anyway:\n{fixes}",
+                    self.max_rounds
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    async fn start_session(
+        &self,
+        manager: &ConversationManager,
+    ) -> anyhow::Result<Arc<CodexConversation>> {
+        let NewConversation { conversation, .. } =
+            manager.new_conversation(self.config.clone()).await?;
+        if let Some(preset) = &self.preset {
+            conversation
+                .submit(Op::OverrideTurnContext {
+                    cwd: None,
+                    approval_policy: None,
+                    sandbox_policy: None,
+                    model: Some(preset.model.clone()),
+                    effort: Some(preset.effort),
+                    summary: None,
+                })
+                .await?;
+        }
+        Ok(conversation)
+    }
+
+    fn review_prompt(&self, diff: &str, final_message: Option<&str>) -> String {
+        let mut diff = diff.to_string();
+        if diff.len() > MAX_DIFF_CHARS {
+            let mut end = MAX_DIFF_CHARS;
+            while !diff.is_char_boundary(end) {
+                end -= 1;
+            }
+            diff.truncate(end);
+            diff.push_str("\n[diff truncated]");
+        }
+        if diff.trim().is_empty() {
+            diff = "(no changes)".to_string();
+        }
+        let summary = final_message.unwrap_or("(none)");
+        let intro = if self.conversation.is_none() {
+            format!(
+                "You review the work of a coding agent before it is accepted. Check whether the \
+                 diff below fully and correctly does what the instructions ask, without unrelated \
+                 changes. You may read files in the repository, but do not change anything.\n\n\
+                 Instructions given to the agent:\n{}\n",
+                self.task
+            )
+        } else {
+            "The agent has revised its work. Review the complete diff again.\n".to_string()
+        };
+        format!(
+            "{intro}\nDiff of the agent's changes:\n```diff\n{diff}\n```\n\n\
+             Agent's final message:\n{summary}\n\n\
+             End your answer with `{VERDICT_PREFIX} APPROVE` if the work is acceptable. \
+             Otherwise end it with `{VERDICT_PREFIX} REVISE` followed by a list of concrete \
+             fixes, one per line."
+        )
+    }
+}
+
+/// Send `text` to `conversation` and wait for its final message.
+async fn ask(conversation: &CodexConversation, text: String) -> anyhow::Result<Option<String>> {
+    conversation
+        .submit(Op::UserInput {
+            items: vec![InputItem::Text { text }],
+        })
+        .await?;
+    loop {
+        let event = conversation.next_event().await?;
+        match event.msg {
+            EventMsg::ExecApprovalRequest(_) => {
+                conversation
+                    .submit(Op::ExecApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::ApplyPatchApprovalRequest(_) => {
+                conversation
+                    .submit(Op::PatchApproval {
+                        id: event.id,
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::Error(err) => anyhow::bail!(err.message),
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                return Ok(last_agent_message);
+            }
+            EventMsg::ShutdownComplete => return Ok(None),
+            _ => {}
+        }
+    }
+}
+
+/// The verdict on the critic's last verdict line. For `REVISE`, the fixes
+/// are the lines after it, or the text before it when none follow.
+fn parse_verdict(answer: &str) -> Option<Verdict> {
+    let lines: Vec<&str> = answer.lines().collect();
+    let (index, rest) = lines.iter().enumerate().rev().find_map(|(i, line)| {
+        line.trim()
+            .trim_matches('`')
+            .trim()
+            .strip_prefix(VERDICT_PREFIX)
+            .map(|rest| (i, rest.trim().trim_matches('`').trim()))
+    })?;
+    let word = rest.split_whitespace().next().unwrap_or_default();
+    if word.eq_ignore_ascii_case("approve") {
+        return Some(Verdict::Approve);
+    }
+    if !word.eq_ignore_ascii_case("revise") {
+        return None;
+    }
+    let after = lines[index + 1..].join("\n");
+    let fixes = if after.trim().is_empty() {
+        lines[..index].join("\n")
+    } else {
+        after
+    };
+    Some(Verdict::Revise(fixes.trim().to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn verdict_is_read_from_the_last_verdict_line() {
+        assert_eq!(
+            parse_verdict("Looks good.\n\n`VERDICT: APPROVE`"),
+            Some(Verdict::Approve)
+        );
+        assert_eq!(
+            parse_verdict("VERDICT: APPROVE\nOn second look:\nVERDICT: REVISE\n- add a test\n"),
+            Some(Verdict::Revise("- add a test".to_string()))
+        );
+        assert_eq!(
+            parse_verdict("The error path is not handled.\nVERDICT: revise"),
+            Some(Verdict::Revise(
+                "The error path is not handled.".to_string()
+            ))
+        );
+        assert_eq!(parse_verdict("no verdict here"), None);
+    }
+}
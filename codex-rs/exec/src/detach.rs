@@ -0,0 +1,115 @@
+//! `codex exec --detach`: run the task in a background process that
+//! `codex attach` can connect to, and return once it is attachable.
+
+use std::ffi::OsString;
+use std::fs::File;
+use std::os::unix::process::CommandExt;
+use std::path::Path;
+use std::process::Command;
+use std::process::Stdio;
+use std::time::Duration;
+use std::time::Instant;
+
+use chrono::Local;
+use codex_core::attach::list_attachable_sessions;
+
+/// How long the background session may take to start.
+const STARTUP_TIMEOUT: Duration = Duration::from_secs(60);
+
+const POLL_INTERVAL: Duration = Duration::from_millis(200);
+
+/// Re-run this command in the background with `--attachable` in place of
+/// `--detach`, then print how to attach to it. Its output goes to a log file
+/// under `$CODEX_HOME/log`.
+pub(crate) async fn run_detached(codex_home: &Path) -> anyhow::Result<()> {
+    let log_dir = codex_home.join("log");
+    std::fs::create_dir_all(&log_dir)?;
+    let log_path = log_dir.join(format!("exec-{}.log", Local::now().format("%Y%m%d-%H%M%S")));
+    let log = File::create(&log_path)?;
+    let mut child = Command::new(std::env::current_exe()?)
+        .args(background_args(std::env::args_os().skip(1)))
+        .stdin(Stdio::null())
+        .stdout(log.try_clone()?)
+        .stderr(log)
+        // A process group of its own, so neither Ctrl+C nor closing this
+        // terminal stops it.
+        .process_group(0)
+        .spawn()?;
+    let pid = child.id();
+
+    let deadline = Instant::now() + STARTUP_TIMEOUT;
+    loop {
+        if let Some(session) = list_attachable_sessions(codex_home)?
+            .into_iter()
+            .find(|session| session.pid == pid)
+        {
+            let id = session.conversation_id;
+            println!("Started session {id} in the background (pid {pid}).");
+            println!("Attach with: codex attach {id}");
+            println!("Output: {}", log_path.display());
+            return Ok(());
+        }
+        if let Some(status) = child.try_wait()? {
+            anyhow::bail!(
+                "the background session exited ({status}) before it became attachable; see {}",
+                log_path.display()
+            );
+        }
+        if Instant::now() >= deadline {
+            anyhow::bail!(
+                "the background session (pid {pid}) did not become attachable within {}s; see {}",
+                STARTUP_TIMEOUT.as_secs(),
+                log_path.display()
+            );
+        }
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+}
+
+/// The arguments with the `--detach` flag swapped for `--attachable`. Only
+/// the flag itself is rewritten: anything after `--` is the prompt.
+fn background_args(args: impl Iterator<Item = OsString>) -> Vec<OsString> {
+    let mut rewritten = false;
+    let mut options_ended = false;
+    args.map(|arg| {
+        if options_ended || rewritten {
+            return arg;
+        }
+        if arg == "--" {
+            options_ended = true;
+            arg
+        } else if arg == "--detach" {
+            rewritten = true;
+            OsString::from("--attachable")
+        } else {
+            arg
+        }
+    })
+    .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn background_run_serves_attach_instead_of_detaching_again() {
+        let args = ["exec", "--detach", "-m", "gpt-5", "fix the build"].map(OsString::from);
+        assert_eq!(
+            background_args(args.into_iter()),
+            ["exec", "--attachable", "-m", "gpt-5", "fix the build"].map(OsString::from)
+        );
+
+        let args = ["exec", "--detach", "--", "--detach"].map(OsString::from);
+        assert_eq!(
+            background_args(args.into_iter()),
+            ["exec", "--attachable", "--", "--detach"].map(OsString::from)
+        );
+        let args = ["exec", "--", "--detach"].map(OsString::from);
+        assert_eq!(
+            background_args(args.into_iter()),
+            ["exec", "--", "--detach"].map(OsString::from)
+        );
+    }
+}
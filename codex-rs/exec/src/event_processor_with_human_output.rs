@@ -7,6 +7,7 @@ use codex_core::protocol::AgentMessageEvent;
 use codex_core::protocol::AgentReasoningDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
+use codex_core::protocol::ApprovalTimedOutEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
@@ -19,13 +20,18 @@ use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::PatchApplyBeginEvent;
 use codex_core::protocol::PatchApplyEndEvent;
+use codex_core::protocol::PatchGuardBlockedEvent;
+use codex_core::protocol::PatchGuardViolation;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TurnAbortCleanup;
 use codex_core::protocol::TurnAbortReason;
 use codex_core::protocol::TurnDiffEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_core::protocol::WorkspaceEscape;
+use codex_core::protocol::WorkspaceEscapeBlockedEvent;
 use codex_protocol::num_format::format_with_separators;
 use owo_colors::OwoColorize;
 use owo_colors::Style;
@@ -171,7 +177,7 @@ impl EventProcessor for EventProcessorWithHumanOutput {
     fn process_event(&mut self, event: Event) -> CodexStatus {
         let Event { id: _, msg } = event;
         match msg {
-            EventMsg::Error(ErrorEvent { message }) => {
+            EventMsg::Error(ErrorEvent { message, .. }) => {
                 let prefix = "ERROR:".style(self.red);
                 ts_println!(self, "{prefix} {message}");
             }
@@ -501,6 +507,37 @@ impl EventProcessor for EventProcessorWithHumanOutput {
             EventMsg::ApplyPatchApprovalRequest(_) => {
                 // Should we exit?
             }
+            EventMsg::ApprovalTimedOut(ApprovalTimedOutEvent {
+                timeout_secs,
+                action,
+                ..
+            }) => {
+                ts_println!(
+                    self,
+                    "{} after {timeout_secs}s; applied {action:?}",
+                    "approval timed out".style(self.red)
+                );
+            }
+            EventMsg::WorkspaceEscapeBlocked(WorkspaceEscapeBlockedEvent { escapes, .. }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    "patch blocked: writes outside the workspace".style(self.red)
+                );
+                for WorkspaceEscape { path, resolved } in escapes {
+                    println!("  {} -> {}", path.display(), resolved.display());
+                }
+            }
+            EventMsg::PatchGuardBlocked(PatchGuardBlockedEvent { violations, .. }) => {
+                ts_println!(
+                    self,
+                    "{}",
+                    "patch blocked: edits protected files".style(self.red)
+                );
+                for PatchGuardViolation { path, reason } in violations {
+                    println!("  {}: {reason}", path.display());
+                }
+            }
             EventMsg::AgentReasoning(agent_reasoning_event) => {
                 if self.show_agent_reasoning {
                     if !self.reasoning_started {
@@ -542,23 +579,45 @@ impl EventProcessor for EventProcessorWithHumanOutput {
                 ts_println!(self, "explanation: {explanation:?}");
                 ts_println!(self, "plan: {plan:?}");
             }
+            EventMsg::SubtaskBegin(_) | EventMsg::SubtaskProgress(_) | EventMsg::SubtaskEnd(_) => {
+                // The plan is printed as a whole above.
+            }
             EventMsg::GetHistoryEntryResponse(_) => {
                 // Currently ignored in exec output.
             }
             EventMsg::McpListToolsResponse(_) => {
                 // Currently ignored in exec output.
             }
+            EventMsg::McpResourceUpdated(_) => {
+                // Exec never subscribes to resources.
+            }
+            EventMsg::PromptPreview(_) => {
+                // Exec never requests the prompt.
+            }
             EventMsg::ListCustomPromptsResponse(_) => {
                 // Currently ignored in exec output.
             }
-            EventMsg::TurnAborted(abort_reason) => match abort_reason.reason {
-                TurnAbortReason::Interrupted => {
-                    ts_println!(self, "task interrupted");
+            EventMsg::TurnAborted(abort_reason) => {
+                match abort_reason.reason {
+                    TurnAbortReason::Interrupted => {
+                        ts_println!(self, "task interrupted");
+                    }
+                    TurnAbortReason::Replaced => {
+                        ts_println!(self, "task aborted: replaced by a new task");
+                    }
                 }
-                TurnAbortReason::Replaced => {
-                    ts_println!(self, "task aborted: replaced by a new task");
+                let TurnAbortCleanup {
+                    terminated_tool_calls,
+                    closed_pty_sessions,
+                } = abort_reason.cleanup;
+                if terminated_tool_calls > 0 || closed_pty_sessions > 0 {
+                    ts_println!(
+                        self,
+                        "{} terminated {terminated_tool_calls} running command(s), closed {closed_pty_sessions} terminal session(s)",
+                        "cleanup:".style(self.dimmed)
+                    );
                 }
-            },
+            }
             EventMsg::ShutdownComplete => return CodexStatus::Shutdown,
             EventMsg::ConversationPath(_) => {}
             EventMsg::UserMessage(_) => {}
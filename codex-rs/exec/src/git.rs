@@ -0,0 +1,75 @@
+//! The few git operations `codex exec` performs itself.
+
+use std::path::Path;
+use std::process::Stdio;
+
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+/// Run `git` in `dir`, feeding it `stdin`, and return its stdout.
+pub(crate) async fn git(
+    dir: &Path,
+    args: &[&str],
+    stdin: Option<&[u8]>,
+) -> anyhow::Result<Vec<u8>> {
+    run(dir, args, stdin, None).await
+}
+
+/// The tree of everything in the working tree of the repository at `root`,
+/// untracked files included. A scratch index is used, so the repository's
+/// own index is left alone.
+pub(crate) async fn snapshot_working_tree(root: &Path) -> anyhow::Result<String> {
+    let scratch = tempfile::tempdir()?;
+    let index = &scratch.path().join("index");
+    // Starting from a copy of the real index lets git skip rehashing files
+    // whose stat data is unchanged.
+    let real_index = git(
+        root,
+        &["rev-parse", "--path-format=absolute", "--git-path", "index"],
+        None,
+    )
+    .await
+    .map(|out| String::from_utf8_lossy(&out).trim().to_string())?;
+    // Without one (no commits yet), git starts from an empty index.
+    let _ = std::fs::copy(&real_index, index);
+    run(root, &["add", "-A"], None, Some(index)).await?;
+    let tree = run(root, &["write-tree"], None, Some(index)).await?;
+    Ok(String::from_utf8_lossy(&tree).trim().to_string())
+}
+
+async fn run(
+    dir: &Path,
+    args: &[&str],
+    stdin: Option<&[u8]>,
+    index: Option<&Path>,
+) -> anyhow::Result<Vec<u8>> {
+    let mut command = Command::new("git");
+    command
+        .args(args)
+        .current_dir(dir)
+        .stdin(if stdin.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(index) = index {
+        command.env("GIT_INDEX_FILE", index);
+    }
+    let mut child = command.spawn()?;
+    if let Some(input) = stdin
+        && let Some(mut pipe) = child.stdin.take()
+    {
+        pipe.write_all(input).await?;
+    }
+    let output = child.wait_with_output().await?;
+    if !output.status.success() {
+        anyhow::bail!(
+            "git {} failed: {}",
+            args.join(" "),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(output.stdout)
+}
@@ -1,38 +1,69 @@
+mod best_of;
 mod cli;
+mod critic;
+#[cfg(unix)]
+mod detach;
 mod event_processor;
 mod event_processor_with_human_output;
 mod event_processor_with_json_output;
+mod git;
+mod run_result;
 
 use std::io::IsTerminal;
 use std::io::Read;
+use std::path::Path;
 use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
+use std::time::Duration;
 
 pub use cli::Cli;
+pub use cli::Color;
 use codex_core::AuthManager;
 use codex_core::BUILT_IN_OSS_MODEL_PROVIDER_ID;
 use codex_core::ConversationManager;
 use codex_core::NewConversation;
+#[cfg(unix)]
+use codex_core::attach::AttachServer;
+use codex_core::code_host;
+use codex_core::code_host::CiReport;
+use codex_core::code_host::CodeHost;
 use codex_core::config::Config;
 use codex_core::config::ConfigOverrides;
+use codex_core::config_types::ApprovalTimeout;
+use codex_core::documents::is_document;
 use codex_core::git_info::get_git_repo_root;
+use codex_core::protocol::ApprovalTimeoutAction;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::Event;
 use codex_core::protocol::EventMsg;
 use codex_core::protocol::InputItem;
 use codex_core::protocol::Op;
+use codex_core::protocol::ReviewDecision;
 use codex_core::protocol::TaskCompleteEvent;
+use codex_core::trust_store::TrustDecision;
+use codex_core::trust_store::TrustStore;
+use codex_core::trust_store::lock_down_untrusted;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
 use event_processor_with_human_output::EventProcessorWithHumanOutput;
 use event_processor_with_json_output::EventProcessorWithJsonOutput;
+pub use run_result::FailureReason;
+use run_result::RunOutcome;
+pub use run_result::RunResult;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
-use tracing_subscriber::EnvFilter;
 
+use crate::critic::Critic;
 use crate::event_processor::CodexStatus;
 use crate::event_processor::EventProcessor;
 
+/// How long an attachable run waits for a client to answer an approval
+/// request when `approval_timeout_secs` is not set.
+const ATTACHABLE_APPROVAL_TIMEOUT: Duration = Duration::from_secs(10 * 60);
+
 pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> anyhow::Result<()> {
     let Cli {
         images,
@@ -41,16 +72,48 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         config_profile,
         full_auto,
         dangerously_bypass_approvals_and_sandbox,
+        read_only,
         cwd,
         skip_git_repo_check,
+        offline,
         color,
         last_message_file,
+        result_file,
+        ci,
+        best_of,
+        variant_presets,
+        judge,
+        critic: critic_flag,
+        critic_preset,
+        critic_rounds,
+        detach,
+        attachable,
         json: json_mode,
         sandbox_mode: sandbox_mode_cli_arg,
         prompt,
         config_overrides,
     } = cli;
 
+    if detach {
+        // The background run has no stdin to read the prompt from.
+        if prompt.as_deref().is_none_or(|p| p == "-") {
+            eprintln!("--detach needs the prompt as an argument.");
+            std::process::exit(1);
+        }
+        #[cfg(unix)]
+        {
+            return detach::run_detached(&codex_core::config::find_codex_home()?).await;
+        }
+        #[cfg(not(unix))]
+        {
+            anyhow::bail!("--detach is only supported on Unix");
+        }
+    }
+    #[cfg(not(unix))]
+    if attachable {
+        anyhow::bail!("--attachable is only supported on Unix");
+    }
+
     // Determine the prompt based on CLI arg and/or stdin.
     let prompt = match prompt {
         Some(p) if p != "-" => p,
@@ -95,20 +158,6 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         ),
     };
 
-    // TODO(mbolin): Take a more thoughtful approach to logging.
-    let default_level = "error";
-    let _ = tracing_subscriber::fmt()
-        // Fallback to the `default_level` log filter if the environment
-        // variable is not set _or_ contains an invalid value
-        .with_env_filter(
-            EnvFilter::try_from_default_env()
-                .or_else(|_| EnvFilter::try_new(default_level))
-                .unwrap_or_else(|_| EnvFilter::new(default_level)),
-        )
-        .with_ansi(stderr_with_ansi)
-        .with_writer(std::io::stderr)
-        .try_init();
-
     let sandbox_mode = if full_auto {
         Some(SandboxMode::WorkspaceWrite)
     } else if dangerously_bypass_approvals_and_sandbox {
@@ -140,8 +189,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         review_model: None,
         config_profile,
         // This CLI is intended to be headless and has no affordances for asking
-        // the user for approval.
-        approval_policy: Some(AskForApproval::Never),
+        // the user for approval, unless a client can attach to answer.
+        approval_policy: (!attachable).then_some(AskForApproval::Never),
         sandbox_mode,
         cwd: cwd.map(|p| p.canonicalize().unwrap_or(p)),
         model_provider,
@@ -152,6 +201,8 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         include_view_image_tool: None,
         show_raw_agent_reasoning: oss.then_some(true),
         tools_web_search_request: None,
+        offline: offline.then_some(true),
+        read_only: read_only.then_some(true),
     };
     // Parse `-c` overrides.
     let cli_kv_overrides = match config_overrides.parse_overrides() {
@@ -162,7 +213,32 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     };
 
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    let sandbox_overridden = overrides.sandbox_mode.is_some();
+    let mut config = Config::load_with_cli_overrides(cli_kv_overrides, overrides)?;
+    // `exec` never prompts, but a directory the user declined to trust in
+    // the TUI stays locked down here too.
+    if TrustStore::load(&config.codex_home).decision_for(&config.cwd)
+        == Some(TrustDecision::Untrusted)
+    {
+        lock_down_untrusted(&mut config, sandbox_overridden);
+    }
+    // Nobody may ever attach to an attachable run (it is how `--detach`
+    // runs), so its approval requests must not wait forever.
+    if attachable && config.approval_timeout.is_none() {
+        config.approval_timeout = Some(ApprovalTimeout {
+            duration: ATTACHABLE_APPROVAL_TIMEOUT,
+            action: ApprovalTimeoutAction::Deny,
+        });
+    }
+
+    // Logs go to stderr inline with the output; `[log]` in config.toml can
+    // raise the level for individual subsystems.
+    let _ = tracing_subscriber::fmt()
+        .with_env_filter(codex_common::logging::build_filter(&config.log, "error"))
+        .with_ansi(stderr_with_ansi)
+        .with_writer(std::io::stderr)
+        .try_init();
+
     let mut event_processor: Box<dyn EventProcessor> = if json_mode {
         Box::new(EventProcessorWithJsonOutput::new(last_message_file.clone()))
     } else {
@@ -188,23 +264,121 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         std::process::exit(1);
     }
 
+    if let Some(count) = best_of {
+        let options = best_of::BestOfOptions {
+            count: usize::from(count),
+            presets: variant_presets,
+            judge,
+        };
+        return best_of::run_best_of(config, prompt, options).await;
+    }
+
+    let mut critic_settings = config.exec_critic.clone();
+    if critic_flag || critic_preset.is_some() || critic_rounds.is_some() {
+        critic_settings.enabled = true;
+    }
+    if let Some(preset) = critic_preset {
+        critic_settings.preset = Some(preset);
+    }
+    if let Some(rounds) = critic_rounds {
+        critic_settings.max_rounds = rounds;
+    }
+    // A read-only run has nothing to review.
+    let mut critic = if critic_settings.enabled && !config.read_only {
+        match Critic::start(&config, &critic_settings, &prompt).await {
+            Ok(critic) => Some(critic),
+            Err(e) => {
+                eprintln!("critic: {e:#}; running without it");
+                None
+            }
+        }
+    } else {
+        None
+    };
+
+    // Detect the CI environment up front so a misconfigured pipeline fails
+    // before the agent does any work.
+    let ci_host = if ci {
+        match code_host::detect() {
+            Ok(Some(host)) => Some(host),
+            Ok(None) => {
+                eprintln!("--ci: no supported CI environment detected; not reporting the run.");
+                None
+            }
+            Err(e) => {
+                eprintln!("--ci: {e}");
+                std::process::exit(1);
+            }
+        }
+    } else {
+        None
+    };
+
     let conversation_manager =
         ConversationManager::new(AuthManager::shared(config.codex_home.clone()));
+    #[cfg(unix)]
+    let (codex_home, cwd) = (config.codex_home.clone(), config.cwd.clone());
+    let mut outcome = RunOutcome::default();
     let NewConversation {
         conversation_id: _,
         conversation,
         session_configured,
-    } = conversation_manager.new_conversation(config).await?;
+    } = match conversation_manager.new_conversation(config).await {
+        Ok(new_conversation) => new_conversation,
+        Err(e) => {
+            eprintln!("Failed to start session: {e}");
+            outcome.fail(FailureReason::from(e.category()), e.to_string());
+            return finish_run(outcome, result_file.as_deref(), ci_host.as_deref()).await;
+        }
+    };
     info!("Codex initialized with event: {session_configured:?}");
 
+    #[cfg(unix)]
+    let attach_server = if attachable {
+        let conversation_id = session_configured.session_id;
+        let session_configured = Event {
+            id: String::new(),
+            msg: EventMsg::SessionConfigured(session_configured.clone()),
+        };
+        match AttachServer::start(
+            &codex_home,
+            conversation_id,
+            conversation.clone(),
+            session_configured,
+            cwd,
+            &prompt,
+        ) {
+            Ok(server) => {
+                eprintln!("Attach with: codex attach {conversation_id}");
+                Some(server)
+            }
+            Err(e) => {
+                // Shut down properly so the session leaves no recovery marker
+                // behind for a run that never started.
+                conversation.submit(Op::Shutdown).await?;
+                while let Ok(event) = conversation.next_event().await {
+                    if matches!(event.msg, EventMsg::ShutdownComplete) {
+                        break;
+                    }
+                }
+                anyhow::bail!("failed to make the session attachable: {e}");
+            }
+        }
+    } else {
+        None
+    };
+
     let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel::<Event>();
+    let interrupted = Arc::new(AtomicBool::new(false));
     {
         let conversation = conversation.clone();
+        let interrupted = interrupted.clone();
         tokio::spawn(async move {
             loop {
                 tokio::select! {
                     _ = tokio::signal::ctrl_c() => {
                         tracing::debug!("Keyboard interrupt");
+                        interrupted.store(true, Ordering::Relaxed);
                         // Immediately notify Codex to abort any in‑flight task.
                         conversation.submit(Op::Interrupt).await.ok();
 
@@ -236,15 +410,27 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         });
     }
 
-    // Send images first, if any.
+    // Send images and documents first, if any.
     if !images.is_empty() {
         let items: Vec<InputItem> = images
             .into_iter()
-            .map(|path| InputItem::LocalImage { path })
+            .map(|path| {
+                if is_document(&path) {
+                    InputItem::LocalFile { path }
+                } else {
+                    InputItem::LocalImage { path }
+                }
+            })
             .collect();
         let initial_images_event_id = conversation.submit(Op::UserInput { items }).await?;
         info!("Sent images with event ID: {initial_images_event_id}");
         while let Ok(event) = conversation.next_event().await {
+            // A document whose text cannot be extracted fails the submission.
+            if event.id == initial_images_event_id
+                && let EventMsg::Error(err) = &event.msg
+            {
+                anyhow::bail!("{}", err.message);
+            }
             if event.id == initial_images_event_id
                 && matches!(
                     event.msg,
@@ -265,7 +451,57 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
 
     // Run the loop until the task is complete.
     while let Some(event) = rx.recv().await {
+        #[cfg(unix)]
+        if let Some(server) = &attach_server {
+            server.publish(&event);
+        }
+        outcome.observe(&event);
+        // Nobody can answer approval prompts in exec mode, so deny them
+        // rather than letting the turn wait forever. Attached clients answer
+        // them instead.
+        match &event.msg {
+            EventMsg::ExecApprovalRequest(_) if !attachable => {
+                conversation
+                    .submit(Op::ExecApproval {
+                        id: event.id.clone(),
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            EventMsg::ApplyPatchApprovalRequest(_) if !attachable => {
+                conversation
+                    .submit(Op::PatchApproval {
+                        id: event.id.clone(),
+                        decision: ReviewDecision::Denied,
+                    })
+                    .await?;
+            }
+            _ => {}
+        }
+        let completed = match &event.msg {
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                Some(last_agent_message.clone())
+            }
+            _ => None,
+        };
         let shutdown: CodexStatus = event_processor.process_event(event);
+        if matches!(shutdown, CodexStatus::InitiateShutdown)
+            && let Some(critic) = critic.as_mut()
+            && let Some(last_agent_message) = completed
+        {
+            match critic
+                .review(&conversation_manager, last_agent_message.as_deref())
+                .await
+            {
+                Ok(Some(fixes)) => {
+                    let items = vec![InputItem::Text { text: fixes }];
+                    conversation.submit(Op::UserInput { items }).await?;
+                    continue;
+                }
+                Ok(None) => {}
+                Err(e) => eprintln!("critic: {e:#}"),
+            }
+        }
         match shutdown {
             CodexStatus::Running => continue,
             CodexStatus::InitiateShutdown => {
@@ -277,5 +513,48 @@ pub async fn run_main(cli: Cli, codex_linux_sandbox_exe: Option<PathBuf>) -> any
         }
     }
 
+    if interrupted.load(Ordering::Relaxed) {
+        outcome.interrupted();
+    }
+    finish_run(outcome, result_file.as_deref(), ci_host.as_deref()).await
+}
+
+/// Write `--result-file` (if requested), report to the CI code host (with
+/// `--ci`) and, if the run failed, exit with its exit code.
+async fn finish_run(
+    outcome: RunOutcome,
+    result_file: Option<&Path>,
+    ci_host: Option<&dyn CodeHost>,
+) -> anyhow::Result<()> {
+    let result = outcome.finish();
+    if let Some(path) = result_file
+        && let Err(e) = result.write_to(path)
+    {
+        eprintln!("Failed to write result file {}: {e}", path.display());
+    }
+    if let Some(host) = ci_host {
+        let summary = if result.success {
+            result.last_agent_message.clone()
+        } else {
+            result.message.clone()
+        };
+        let report = CiReport {
+            success: result.success,
+            summary: summary.unwrap_or_default(),
+        };
+        // The runner reads workflow commands from stderr as well, which
+        // keeps them out of `--json` output.
+        for line in host.log_commands(&report) {
+            eprintln!("{line}");
+        }
+        for problem in code_host::report_run(host, &report).await {
+            eprintln!("Failed to report the run to {}: {problem}", host.name());
+        }
+    }
+    if let Some(reason) = result.failure_reason {
+        let message = result.message.as_deref().unwrap_or_default();
+        eprintln!("codex exec failed ({reason:?}): {message}");
+        std::process::exit(result.exit_code());
+    }
     Ok(())
 }
@@ -0,0 +1,240 @@
+//! Exit status and machine-readable summary of a `codex exec` run.
+//!
+//! Exit codes are part of the CLI's public interface; scripts branch on them,
+//! so existing values must never be renumbered.
+
+use std::path::Path;
+
+use codex_core::protocol::ApprovalTimedOutEvent;
+use codex_core::protocol::ApprovalTimeoutAction;
+use codex_core::protocol::ErrorCategory;
+use codex_core::protocol::ErrorEvent;
+use codex_core::protocol::Event;
+use codex_core::protocol::EventMsg;
+use codex_core::protocol::TaskCompleteEvent;
+use codex_core::protocol::TokenUsage;
+use codex_core::protocol::TurnAbortReason;
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum FailureReason {
+    /// Unclassified failure inside codex.
+    InternalError,
+    /// Credentials are missing, invalid or not entitled to the model.
+    AuthFailure,
+    /// The model provider failed or could not be reached.
+    ProviderError,
+    /// A usage limit or the model's context window was exhausted.
+    BudgetExceeded,
+    /// The agent needed an approval that was denied or timed out.
+    ApprovalDenied,
+    /// The sandbox could not be set up or refused an operation the turn
+    /// depended on.
+    SandboxViolation,
+    /// The session ended before the task reported completion.
+    TaskIncomplete,
+    /// The run was interrupted with Ctrl-C.
+    Interrupted,
+}
+
+impl FailureReason {
+    pub fn exit_code(self) -> i32 {
+        match self {
+            FailureReason::InternalError => 1,
+            FailureReason::AuthFailure => 3,
+            FailureReason::ProviderError => 4,
+            FailureReason::BudgetExceeded => 5,
+            FailureReason::ApprovalDenied => 6,
+            FailureReason::SandboxViolation => 7,
+            FailureReason::TaskIncomplete => 8,
+            FailureReason::Interrupted => 130,
+        }
+    }
+}
+
+impl From<ErrorCategory> for FailureReason {
+    fn from(category: ErrorCategory) -> Self {
+        match category {
+            ErrorCategory::Auth => FailureReason::AuthFailure,
+            ErrorCategory::Provider => FailureReason::ProviderError,
+            ErrorCategory::UsageLimit | ErrorCategory::ContextWindow => {
+                FailureReason::BudgetExceeded
+            }
+            ErrorCategory::Sandbox => FailureReason::SandboxViolation,
+            ErrorCategory::Internal => FailureReason::InternalError,
+        }
+    }
+}
+
+/// Contents of the file written by `--result-file`.
+#[derive(Debug, Clone, Serialize)]
+pub struct RunResult {
+    pub success: bool,
+    pub exit_code: i32,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub failure_reason: Option<FailureReason>,
+    /// Human-readable detail for `failure_reason`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub message: Option<String>,
+    pub last_agent_message: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub token_usage: Option<TokenUsage>,
+}
+
+impl RunResult {
+    pub fn exit_code(&self) -> i32 {
+        self.exit_code
+    }
+
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        let mut json = serde_json::to_string_pretty(self).map_err(std::io::Error::other)?;
+        json.push('\n');
+        std::fs::write(path, json)
+    }
+}
+
+/// Folds the event stream into a [`RunResult`]. The first failure observed
+/// wins: later errors are usually consequences of it.
+#[derive(Debug, Default)]
+pub(crate) struct RunOutcome {
+    failure: Option<(FailureReason, String)>,
+    task_complete: bool,
+    last_agent_message: Option<String>,
+    token_usage: Option<TokenUsage>,
+}
+
+impl RunOutcome {
+    pub(crate) fn observe(&mut self, event: &Event) {
+        match &event.msg {
+            // Uncategorized errors (e.g. an MCP server failing to start) do
+            // not stop the turn, so they do not fail the run either.
+            EventMsg::Error(ErrorEvent {
+                message,
+                category: Some(category),
+            }) => {
+                self.fail(FailureReason::from(*category), message.clone());
+            }
+            EventMsg::ExecApprovalRequest(_) | EventMsg::ApplyPatchApprovalRequest(_) => {
+                self.fail(
+                    FailureReason::ApprovalDenied,
+                    "approval requested in non-interactive mode; denied".to_string(),
+                );
+            }
+            EventMsg::ApprovalTimedOut(ApprovalTimedOutEvent {
+                timeout_secs,
+                action,
+                ..
+            }) if *action != ApprovalTimeoutAction::Allow => {
+                self.fail(
+                    FailureReason::ApprovalDenied,
+                    format!("approval timed out after {timeout_secs}s"),
+                );
+            }
+            EventMsg::TurnAborted(aborted) if aborted.reason == TurnAbortReason::Interrupted => {
+                self.interrupted();
+            }
+            EventMsg::TokenCount(ev) => {
+                if let Some(info) = &ev.info {
+                    self.token_usage = Some(info.total_token_usage.clone());
+                }
+            }
+            EventMsg::TaskComplete(TaskCompleteEvent { last_agent_message }) => {
+                self.task_complete = true;
+                self.last_agent_message = last_agent_message.clone();
+            }
+            _ => {}
+        }
+    }
+
+    pub(crate) fn interrupted(&mut self) {
+        self.fail(FailureReason::Interrupted, "interrupted".to_string());
+    }
+
+    pub(crate) fn fail(&mut self, reason: FailureReason, message: String) {
+        if self.failure.is_none() {
+            self.failure = Some((reason, message));
+        }
+    }
+
+    pub(crate) fn finish(self) -> RunResult {
+        let failure = match self.failure {
+            Some(failure) => Some(failure),
+            None if !self.task_complete => Some((
+                FailureReason::TaskIncomplete,
+                "session ended before the task completed".to_string(),
+            )),
+            None => None,
+        };
+        let (failure_reason, message) = match failure {
+            Some((reason, message)) => (Some(reason), Some(message)),
+            None => (None, None),
+        };
+        RunResult {
+            success: failure_reason.is_none(),
+            exit_code: failure_reason.map_or(0, FailureReason::exit_code),
+            failure_reason,
+            message,
+            last_agent_message: self.last_agent_message,
+            token_usage: self.token_usage,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn event(msg: EventMsg) -> Event {
+        Event {
+            id: "1".to_string(),
+            msg,
+        }
+    }
+
+    #[test]
+    fn completed_task_succeeds() {
+        let mut outcome = RunOutcome::default();
+        outcome.observe(&event(EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: Some("done".to_string()),
+        })));
+        let result = outcome.finish();
+        assert_eq!(result.exit_code(), 0);
+        assert_eq!(
+            serde_json::to_value(&result).expect("serialize"),
+            serde_json::json!({
+                "success": true,
+                "exit_code": 0,
+                "last_agent_message": "done",
+            })
+        );
+    }
+
+    #[test]
+    fn first_error_determines_exit_code() {
+        let mut outcome = RunOutcome::default();
+        outcome.observe(&event(EventMsg::Error(ErrorEvent {
+            message: "usage limit".to_string(),
+            category: Some(ErrorCategory::UsageLimit),
+        })));
+        outcome.observe(&event(EventMsg::Error(ErrorEvent {
+            message: "later".to_string(),
+            category: Some(ErrorCategory::Provider),
+        })));
+        outcome.observe(&event(EventMsg::TaskComplete(TaskCompleteEvent {
+            last_agent_message: None,
+        })));
+        let result = outcome.finish();
+        assert_eq!(result.failure_reason, Some(FailureReason::BudgetExceeded));
+        assert_eq!(result.exit_code(), 5);
+        assert_eq!(result.message.as_deref(), Some("usage limit"));
+    }
+
+    #[test]
+    fn missing_task_complete_is_incomplete() {
+        let result = RunOutcome::default().finish();
+        assert_eq!(result.failure_reason, Some(FailureReason::TaskIncomplete));
+        assert_eq!(result.exit_code(), 8);
+    }
+}
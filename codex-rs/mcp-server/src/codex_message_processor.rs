@@ -1261,6 +1261,8 @@ fn derive_config_from_params(
         include_view_image_tool: None,
         show_raw_agent_reasoning: None,
         tools_web_search_request: None,
+        offline: None,
+        read_only: None,
     };
 
     let cli_overrides = cli_overrides
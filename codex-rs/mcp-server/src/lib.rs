@@ -15,6 +15,7 @@ use tokio::io::AsyncWriteExt;
 use tokio::io::BufReader;
 use tokio::io::{self};
 use tokio::sync::mpsc;
+use tokio::task::JoinHandle;
 use tracing::debug;
 use tracing::error;
 use tracing::info;
@@ -26,10 +27,12 @@ mod codex_tool_runner;
 mod error_code;
 mod exec_approval;
 mod json_to_toml;
+mod local_tools_processor;
 pub(crate) mod message_processor;
 mod outgoing_message;
 mod patch_approval;
 
+use crate::local_tools_processor::LocalToolsProcessor;
 use crate::message_processor::MessageProcessor;
 use crate::outgoing_message::OutgoingMessage;
 use crate::outgoing_message::OutgoingMessageSender;
@@ -50,52 +53,15 @@ pub async fn run_main(
     codex_linux_sandbox_exe: Option<PathBuf>,
     cli_config_overrides: CliConfigOverrides,
 ) -> IoResult<()> {
-    // Install a simple subscriber so `tracing` output is visible.  Users can
-    // control the log level with `RUST_LOG`.
-    tracing_subscriber::fmt()
-        .with_writer(std::io::stderr)
-        .with_env_filter(EnvFilter::from_default_env())
-        .init();
+    init_tracing();
 
     // Set up channels.
     let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
-    let (outgoing_tx, mut outgoing_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
-
-    // Task: read from stdin, push to `incoming_tx`.
-    let stdin_reader_handle = tokio::spawn({
-        async move {
-            let stdin = io::stdin();
-            let reader = BufReader::new(stdin);
-            let mut lines = reader.lines();
-
-            while let Some(line) = lines.next_line().await.unwrap_or_default() {
-                match serde_json::from_str::<JSONRPCMessage>(&line) {
-                    Ok(msg) => {
-                        if incoming_tx.send(msg).await.is_err() {
-                            // Receiver gone – nothing left to do.
-                            break;
-                        }
-                    }
-                    Err(e) => error!("Failed to deserialize JSONRPCMessage: {e}"),
-                }
-            }
+    let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
 
-            debug!("stdin reader finished (EOF)");
-        }
-    });
+    let stdin_reader_handle = spawn_stdin_reader(incoming_tx);
 
-    // Parse CLI overrides once and derive the base Config eagerly so later
-    // components do not need to work with raw TOML values.
-    let cli_kv_overrides = cli_config_overrides.parse_overrides().map_err(|e| {
-        std::io::Error::new(
-            ErrorKind::InvalidInput,
-            format!("error parsing -c overrides: {e}"),
-        )
-    })?;
-    let config = Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default())
-        .map_err(|e| {
-            std::io::Error::new(ErrorKind::InvalidData, format!("error loading config: {e}"))
-        })?;
+    let config = load_config(cli_config_overrides)?;
 
     // Task: process incoming messages.
     let processor_handle = tokio::spawn({
@@ -119,8 +85,108 @@ pub async fn run_main(
         }
     });
 
-    // Task: write outgoing messages to stdout.
-    let stdout_writer_handle = tokio::spawn(async move {
+    let stdout_writer_handle = spawn_stdout_writer(outgoing_rx);
+
+    // Wait for all tasks to finish.  The typical exit path is the stdin reader
+    // hitting EOF which, once it drops `incoming_tx`, propagates shutdown to
+    // the processor and then to the stdout task.
+    let _ = tokio::join!(stdin_reader_handle, processor_handle, stdout_writer_handle);
+
+    Ok(())
+}
+
+/// Serve Codex's own sandboxed `shell`, `apply_patch` and `search` tools over
+/// MCP (`codex mcp-tools`), asking the client for approval the way `codex
+/// mcp` does.
+pub async fn run_local_tools_main(
+    codex_linux_sandbox_exe: Option<PathBuf>,
+    cli_config_overrides: CliConfigOverrides,
+) -> IoResult<()> {
+    init_tracing();
+
+    let (incoming_tx, mut incoming_rx) = mpsc::channel::<JSONRPCMessage>(CHANNEL_CAPACITY);
+    let (outgoing_tx, outgoing_rx) = mpsc::unbounded_channel::<OutgoingMessage>();
+
+    let stdin_reader_handle = spawn_stdin_reader(incoming_tx);
+
+    let mut config = load_config(cli_config_overrides)?;
+    config.codex_linux_sandbox_exe = codex_linux_sandbox_exe;
+
+    let processor_handle = tokio::spawn({
+        let mut processor =
+            LocalToolsProcessor::new(OutgoingMessageSender::new(outgoing_tx), &config);
+        async move {
+            while let Some(msg) = incoming_rx.recv().await {
+                match msg {
+                    JSONRPCMessage::Request(r) => processor.process_request(r).await,
+                    JSONRPCMessage::Response(r) => processor.process_response(r).await,
+                    JSONRPCMessage::Notification(n) => processor.process_notification(n),
+                    JSONRPCMessage::Error(e) => processor.process_error(e),
+                }
+            }
+
+            info!("processor task exited (channel closed)");
+        }
+    });
+
+    let stdout_writer_handle = spawn_stdout_writer(outgoing_rx);
+
+    let _ = tokio::join!(stdin_reader_handle, processor_handle, stdout_writer_handle);
+
+    Ok(())
+}
+
+fn init_tracing() {
+    // Install a simple subscriber so `tracing` output is visible.  Users can
+    // control the log level with `RUST_LOG`.
+    tracing_subscriber::fmt()
+        .with_writer(std::io::stderr)
+        .with_env_filter(EnvFilter::from_default_env())
+        .init();
+}
+
+/// Parse CLI overrides once and derive the base Config eagerly so later
+/// components do not need to work with raw TOML values.
+fn load_config(cli_config_overrides: CliConfigOverrides) -> IoResult<Config> {
+    let cli_kv_overrides = cli_config_overrides.parse_overrides().map_err(|e| {
+        std::io::Error::new(
+            ErrorKind::InvalidInput,
+            format!("error parsing -c overrides: {e}"),
+        )
+    })?;
+    Config::load_with_cli_overrides(cli_kv_overrides, ConfigOverrides::default()).map_err(|e| {
+        std::io::Error::new(ErrorKind::InvalidData, format!("error loading config: {e}"))
+    })
+}
+
+/// Task: read from stdin, push to `incoming_tx`.
+fn spawn_stdin_reader(incoming_tx: mpsc::Sender<JSONRPCMessage>) -> JoinHandle<()> {
+    tokio::spawn(async move {
+        let stdin = io::stdin();
+        let reader = BufReader::new(stdin);
+        let mut lines = reader.lines();
+
+        while let Some(line) = lines.next_line().await.unwrap_or_default() {
+            match serde_json::from_str::<JSONRPCMessage>(&line) {
+                Ok(msg) => {
+                    if incoming_tx.send(msg).await.is_err() {
+                        // Receiver gone – nothing left to do.
+                        break;
+                    }
+                }
+                Err(e) => error!("Failed to deserialize JSONRPCMessage: {e}"),
+            }
+        }
+
+        debug!("stdin reader finished (EOF)");
+    })
+}
+
+/// Task: write outgoing messages to stdout.
+fn spawn_stdout_writer(
+    mut outgoing_rx: mpsc::UnboundedReceiver<OutgoingMessage>,
+) -> JoinHandle<()> {
+    tokio::spawn(async move {
         let mut stdout = io::stdout();
         while let Some(outgoing_message) = outgoing_rx.recv().await {
             let msg: JSONRPCMessage = outgoing_message.into();
@@ -140,12 +206,5 @@ pub async fn run_main(
         }
 
         info!("stdout writer exited (channel closed)");
-    });
-
-    // Wait for all tasks to finish.  The typical exit path is the stdin reader
-    // hitting EOF which, once it drops `incoming_tx`, propagates shutdown to
-    // the processor and then to the stdout task.
-    let _ = tokio::join!(stdin_reader_handle, processor_handle, stdout_writer_handle);
-
-    Ok(())
+    })
 }
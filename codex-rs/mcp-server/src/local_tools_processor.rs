@@ -0,0 +1,464 @@
+//! Request handling for `codex mcp-tools`, which exposes Codex's sandboxed
+//! local tools rather than whole Codex sessions.
+
+use std::num::NonZeroUsize;
+use std::path::PathBuf;
+use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+
+use codex_core::config::Config;
+use codex_core::default_client::get_codex_user_agent;
+use codex_core::local_tools::ApprovalRequest;
+use codex_core::local_tools::LocalToolOutput;
+use codex_core::local_tools::LocalTools;
+use codex_core::protocol::ReviewDecision;
+use mcp_types::CallToolRequestParams;
+use mcp_types::CallToolResult;
+use mcp_types::ClientRequest as McpClientRequest;
+use mcp_types::ContentBlock;
+use mcp_types::ElicitRequest;
+use mcp_types::ElicitRequestParamsRequestedSchema;
+use mcp_types::JSONRPCError;
+use mcp_types::JSONRPCErrorError;
+use mcp_types::JSONRPCNotification;
+use mcp_types::JSONRPCRequest;
+use mcp_types::JSONRPCResponse;
+use mcp_types::ListToolsResult;
+use mcp_types::ModelContextProtocolRequest;
+use mcp_types::RequestId;
+use mcp_types::ServerCapabilitiesTools;
+use mcp_types::TextContent;
+use mcp_types::Tool;
+use mcp_types::ToolInputSchema;
+use schemars::JsonSchema;
+use schemars::r#gen::SchemaSettings;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use serde_json::json;
+use tracing::error;
+
+use crate::error_code::INVALID_REQUEST_ERROR_CODE;
+use crate::exec_approval::ExecApprovalElicitRequestParams;
+use crate::exec_approval::ExecApprovalResponse;
+use crate::outgoing_message::OutgoingMessageSender;
+use crate::patch_approval::PatchApprovalElicitRequestParams;
+use crate::patch_approval::PatchApprovalResponse;
+
+/// Default and maximum number of paths returned by the `search` tool.
+const DEFAULT_SEARCH_LIMIT: usize = 20;
+const MAX_SEARCH_LIMIT: usize = 200;
+
+/// Arguments of the `shell` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ShellToolParam {
+    /// The command to execute, as an argv array.
+    pub command: Vec<String>,
+
+    /// Working directory, relative to the server's cwd.
+    #[serde(default)]
+    pub workdir: Option<PathBuf>,
+
+    /// Timeout for the command in milliseconds.
+    #[serde(default)]
+    pub timeout_ms: Option<u64>,
+}
+
+/// Arguments of the `apply_patch` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct ApplyPatchToolParam {
+    /// The patch, starting with `*** Begin Patch` and ending with
+    /// `*** End Patch`.
+    pub patch: String,
+
+    /// Directory relative paths in the patch are resolved against, relative
+    /// to the server's cwd.
+    #[serde(default)]
+    pub workdir: Option<PathBuf>,
+}
+
+/// Arguments of the `search` tool.
+#[derive(Debug, Clone, Deserialize, JsonSchema)]
+pub struct SearchToolParam {
+    /// Fuzzy query matched against file paths under the server's cwd.
+    pub query: String,
+
+    /// Maximum number of paths to return (default 20).
+    #[serde(default)]
+    pub limit: Option<usize>,
+}
+
+pub(crate) struct LocalToolsProcessor {
+    outgoing: Arc<OutgoingMessageSender>,
+    tools: Arc<LocalTools>,
+    cwd: PathBuf,
+    initialized: bool,
+}
+
+impl LocalToolsProcessor {
+    pub(crate) fn new(outgoing: OutgoingMessageSender, config: &Config) -> Self {
+        Self {
+            outgoing: Arc::new(outgoing),
+            tools: Arc::new(LocalTools::new(config)),
+            cwd: config.cwd.clone(),
+            initialized: false,
+        }
+    }
+
+    pub(crate) async fn process_request(&mut self, request: JSONRPCRequest) {
+        let request_id = request.id.clone();
+        let client_request = match McpClientRequest::try_from(request) {
+            Ok(client_request) => client_request,
+            Err(e) => {
+                tracing::warn!("Failed to convert request: {e}");
+                return;
+            }
+        };
+
+        match client_request {
+            McpClientRequest::InitializeRequest(params) => {
+                self.handle_initialize(request_id, params).await;
+            }
+            McpClientRequest::PingRequest(_) => {
+                self.outgoing.send_response(request_id, json!({})).await;
+            }
+            McpClientRequest::ListToolsRequest(_) => {
+                let result = ListToolsResult {
+                    tools: local_tools(),
+                    next_cursor: None,
+                };
+                self.send_response::<mcp_types::ListToolsRequest>(request_id, result)
+                    .await;
+            }
+            McpClientRequest::CallToolRequest(params) => {
+                self.handle_call_tool(request_id, params);
+            }
+            other => {
+                tracing::info!("unsupported request: {other:?}");
+                let error = JSONRPCErrorError {
+                    code: INVALID_REQUEST_ERROR_CODE,
+                    message: "codex mcp-tools only serves tools".to_string(),
+                    data: None,
+                };
+                self.outgoing.send_error(request_id, error).await;
+            }
+        }
+    }
+
+    /// Responses are the client's answers to approval elicitations.
+    pub(crate) async fn process_response(&mut self, response: JSONRPCResponse) {
+        tracing::info!("<- response: {:?}", response);
+        let JSONRPCResponse { id, result, .. } = response;
+        self.outgoing.notify_client_response(id, result).await
+    }
+
+    pub(crate) fn process_notification(&mut self, notification: JSONRPCNotification) {
+        tracing::info!("<- notification: {:?}", notification);
+    }
+
+    pub(crate) fn process_error(&mut self, err: JSONRPCError) {
+        tracing::error!("<- error: {:?}", err);
+    }
+
+    async fn handle_initialize(
+        &mut self,
+        id: RequestId,
+        params: <mcp_types::InitializeRequest as ModelContextProtocolRequest>::Params,
+    ) {
+        if self.initialized {
+            let error = JSONRPCErrorError {
+                code: INVALID_REQUEST_ERROR_CODE,
+                message: "initialize called more than once".to_string(),
+                data: None,
+            };
+            self.outgoing.send_error(id, error).await;
+            return;
+        }
+        self.initialized = true;
+
+        let result = mcp_types::InitializeResult {
+            capabilities: mcp_types::ServerCapabilities {
+                completions: None,
+                experimental: None,
+                logging: None,
+                prompts: None,
+                resources: None,
+                tools: Some(ServerCapabilitiesTools {
+                    list_changed: Some(false),
+                }),
+            },
+            instructions: None,
+            protocol_version: params.protocol_version,
+            server_info: mcp_types::Implementation {
+                name: "codex-mcp-tools".to_string(),
+                version: env!("CARGO_PKG_VERSION").to_string(),
+                title: Some("Codex local tools".to_string()),
+                user_agent: Some(get_codex_user_agent()),
+            },
+        };
+        self.send_response::<mcp_types::InitializeRequest>(id, result)
+            .await;
+    }
+
+    async fn send_response<T>(&self, id: RequestId, result: T::Result)
+    where
+        T: ModelContextProtocolRequest,
+    {
+        self.outgoing.send_response(id, result).await;
+    }
+
+    /// Each call runs on its own task so one waiting for approval does not
+    /// hold up the others.
+    fn handle_call_tool(&self, id: RequestId, params: CallToolRequestParams) {
+        tracing::info!("tools/call -> params: {:?}", params);
+        let outgoing = self.outgoing.clone();
+        let tools = self.tools.clone();
+        let cwd = self.cwd.clone();
+        tokio::spawn(async move {
+            let CallToolRequestParams { name, arguments } = params;
+            let tool_call_id = match &id {
+                RequestId::String(s) => s.clone(),
+                RequestId::Integer(n) => n.to_string(),
+            };
+            let approve = |request: ApprovalRequest| {
+                request_approval(outgoing.clone(), tool_call_id.clone(), request)
+            };
+            let output = match name.as_str() {
+                "shell" => match parse_arguments::<ShellToolParam>(arguments) {
+                    Ok(param) => {
+                        tools
+                            .shell(param.command, param.workdir, param.timeout_ms, approve)
+                            .await
+                    }
+                    Err(output) => output,
+                },
+                "apply_patch" => match parse_arguments::<ApplyPatchToolParam>(arguments) {
+                    Ok(param) => tools.apply_patch(param.patch, param.workdir, approve).await,
+                    Err(output) => output,
+                },
+                "search" => match parse_arguments::<SearchToolParam>(arguments) {
+                    Ok(param) => search(param, cwd).await,
+                    Err(output) => output,
+                },
+                _ => LocalToolOutput {
+                    content: format!("Unknown tool '{name}'"),
+                    success: false,
+                },
+            };
+            let result = CallToolResult {
+                content: vec![ContentBlock::TextContent(TextContent {
+                    r#type: "text".to_string(),
+                    text: output.content,
+                    annotations: None,
+                })],
+                is_error: Some(!output.success),
+                structured_content: None,
+            };
+            outgoing.send_response(id, result).await;
+        });
+    }
+}
+
+fn parse_arguments<T: DeserializeOwned>(
+    arguments: Option<serde_json::Value>,
+) -> Result<T, LocalToolOutput> {
+    serde_json::from_value(arguments.unwrap_or_else(|| json!({}))).map_err(|e| LocalToolOutput {
+        content: format!("failed to parse tool arguments: {e}"),
+        success: false,
+    })
+}
+
+/// Ask the client to approve `request` through an elicitation, using the same
+/// payloads as `codex mcp`. A response that cannot be understood is a denial.
+async fn request_approval(
+    outgoing: Arc<OutgoingMessageSender>,
+    tool_call_id: String,
+    request: ApprovalRequest,
+) -> ReviewDecision {
+    let requested_schema = ElicitRequestParamsRequestedSchema {
+        r#type: "object".to_string(),
+        properties: json!({}),
+        required: None,
+    };
+    let (elicitation, params) = match request {
+        ApprovalRequest::Exec {
+            command,
+            cwd,
+            reason,
+        } => {
+            let escaped_command = shlex::try_join(command.iter().map(|s| s.as_str()))
+                .unwrap_or_else(|_| command.join(" "));
+            let mut message = format!(
+                "Allow Codex to run `{escaped_command}` in `{cwd}`?",
+                cwd = cwd.to_string_lossy()
+            );
+            if let Some(reason) = reason {
+                message = format!("{reason}\n{message}");
+            }
+            let params = ExecApprovalElicitRequestParams {
+                message,
+                requested_schema,
+                codex_elicitation: "exec-approval".to_string(),
+                codex_mcp_tool_call_id: tool_call_id.clone(),
+                codex_event_id: tool_call_id.clone(),
+                codex_call_id: tool_call_id,
+                codex_command: command,
+                codex_cwd: cwd,
+            };
+            ("exec", serde_json::to_value(&params))
+        }
+        ApprovalRequest::Patch { changes, .. } => {
+            let params = PatchApprovalElicitRequestParams {
+                message: "Allow Codex to apply proposed code changes?".to_string(),
+                requested_schema,
+                codex_elicitation: "patch-approval".to_string(),
+                codex_mcp_tool_call_id: tool_call_id.clone(),
+                codex_event_id: tool_call_id.clone(),
+                codex_call_id: tool_call_id,
+                codex_reason: None,
+                codex_grant_root: None,
+                codex_changes: changes,
+            };
+            ("patch", serde_json::to_value(&params))
+        }
+    };
+    let params = match params {
+        Ok(params) => params,
+        Err(err) => {
+            error!("failed to serialize {elicitation} approval request: {err}");
+            return ReviewDecision::Denied;
+        }
+    };
+
+    let on_response = outgoing
+        .send_request(ElicitRequest::METHOD, Some(params))
+        .await;
+    let value = match on_response.await {
+        Ok(value) => value,
+        Err(err) => {
+            error!("request failed: {err:?}");
+            return ReviewDecision::Denied;
+        }
+    };
+    // Both response types carry just a `decision`.
+    let decision = match elicitation {
+        "exec" => serde_json::from_value::<ExecApprovalResponse>(value).map(|r| r.decision),
+        _ => serde_json::from_value::<PatchApprovalResponse>(value).map(|r| r.decision),
+    };
+    decision.unwrap_or_else(|err| {
+        error!("failed to deserialize {elicitation} approval response: {err}");
+        ReviewDecision::Denied
+    })
+}
+
+/// Fuzzy-match file paths under `cwd`, best first, one per line.
+async fn search(param: SearchToolParam, cwd: PathBuf) -> LocalToolOutput {
+    let limit = param
+        .limit
+        .unwrap_or(DEFAULT_SEARCH_LIMIT)
+        .clamp(1, MAX_SEARCH_LIMIT);
+    let limit = NonZeroUsize::new(limit).unwrap_or(NonZeroUsize::MIN);
+    let threads = std::thread::available_parallelism().unwrap_or(NonZeroUsize::MIN);
+    let results = tokio::task::spawn_blocking(move || {
+        codex_file_search::run(
+            &param.query,
+            limit,
+            &cwd,
+            Vec::new(),
+            threads,
+            Arc::new(AtomicBool::new(false)),
+            false,
+        )
+    })
+    .await;
+    match results {
+        Ok(Ok(results)) => {
+            let paths: Vec<String> = results.matches.into_iter().map(|m| m.path).collect();
+            LocalToolOutput {
+                content: if paths.is_empty() {
+                    "no matches".to_string()
+                } else {
+                    paths.join("\n")
+                },
+                success: true,
+            }
+        }
+        Ok(Err(e)) => LocalToolOutput {
+            content: format!("search failed: {e}"),
+            success: false,
+        },
+        Err(e) => LocalToolOutput {
+            content: format!("search failed: {e}"),
+            success: false,
+        },
+    }
+}
+
+fn local_tools() -> Vec<Tool> {
+    vec![
+        create_tool::<ShellToolParam>(
+            "shell",
+            "Shell",
+            "Run a command in Codex's sandbox. Commands the sandbox cannot cover are sent to the client for approval.",
+        ),
+        create_tool::<ApplyPatchToolParam>(
+            "apply_patch",
+            "Apply patch",
+            "Apply a patch in Codex's apply_patch format. Writes outside the writable roots are rejected.",
+        ),
+        create_tool::<SearchToolParam>(
+            "search",
+            "Search files",
+            "Fuzzy-search file paths under the working directory, honoring .gitignore.",
+        ),
+    ]
+}
+
+fn create_tool<T: JsonSchema>(name: &str, title: &str, description: &str) -> Tool {
+    let schema = SchemaSettings::draft2019_09()
+        .with(|s| {
+            s.inline_subschemas = true;
+            s.option_add_null_type = false;
+        })
+        .into_generator()
+        .into_root_schema_for::<T>();
+
+    #[expect(clippy::expect_used)]
+    let schema_value = serde_json::to_value(&schema).expect("tool schema should serialise to JSON");
+
+    let tool_input_schema =
+        serde_json::from_value::<ToolInputSchema>(schema_value).unwrap_or_else(|e| {
+            panic!("failed to create Tool from schema: {e}");
+        });
+
+    Tool {
+        name: name.to_string(),
+        title: Some(title.to_string()),
+        input_schema: tool_input_schema,
+        output_schema: None,
+        description: Some(description.to_string()),
+        annotations: None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn tools_advertise_their_required_arguments() {
+        let tools = local_tools();
+        let summary: Vec<(String, Option<Vec<String>>)> = tools
+            .into_iter()
+            .map(|tool| (tool.name, tool.input_schema.required))
+            .collect();
+        assert_eq!(
+            summary,
+            vec![
+                ("shell".to_string(), Some(vec!["command".to_string()])),
+                ("apply_patch".to_string(), Some(vec!["patch".to_string()])),
+                ("search".to_string(), Some(vec!["query".to_string()])),
+            ]
+        );
+    }
+}
@@ -234,6 +234,12 @@ impl From<Vec<InputItem>> for ResponseInputItem {
                             None
                         }
                     },
+                    // Core resolves MCP items and documents into text and
+                    // images before the turn starts; anything left here was
+                    // unresolvable.
+                    InputItem::LocalFile { .. }
+                    | InputItem::McpResource { .. }
+                    | InputItem::McpPrompt { .. } => None,
                 })
                 .collect::<Vec<ContentItem>>(),
         }
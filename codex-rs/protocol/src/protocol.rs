@@ -21,6 +21,8 @@ use crate::num_format::format_with_separators;
 use crate::parse_command::ParsedCommand;
 use crate::plan_tool::UpdatePlanArgs;
 use mcp_types::CallToolResult;
+use mcp_types::Prompt as McpPrompt;
+use mcp_types::Resource as McpResource;
 use mcp_types::Tool as McpTool;
 use serde::Deserialize;
 use serde::Serialize;
@@ -34,6 +36,10 @@ pub const USER_INSTRUCTIONS_OPEN_TAG: &str = "<user_instructions>";
 pub const USER_INSTRUCTIONS_CLOSE_TAG: &str = "</user_instructions>";
 pub const ENVIRONMENT_CONTEXT_OPEN_TAG: &str = "<environment_context>";
 pub const ENVIRONMENT_CONTEXT_CLOSE_TAG: &str = "</environment_context>";
+pub const REPO_MAP_OPEN_TAG: &str = "<repo_map>";
+pub const REPO_MAP_CLOSE_TAG: &str = "</repo_map>";
+pub const FILE_CHANGES_OPEN_TAG: &str = "<file_changes>";
+pub const FILE_CHANGES_CLOSE_TAG: &str = "</file_changes>";
 pub const USER_MESSAGE_BEGIN: &str = "## My request for Codex:";
 
 /// Submission Queue Entry - requests from user
@@ -163,6 +169,14 @@ pub enum Op {
     /// Request the list of available custom prompts.
     ListCustomPrompts,
 
+    /// Request the instructions, initial context and tools the next model
+    /// request will carry. Reply is delivered via `EventMsg::PromptPreview`.
+    GetPrompt,
+
+    /// Ask an MCP server to report changes to one of its resources. Updates
+    /// are delivered via `EventMsg::McpResourceUpdated`.
+    SubscribeMcpResource { server: String, uri: String },
+
     /// Request the agent to summarize the current conversation context.
     /// The agent will use its existing context (either conversation history or previous response id)
     /// to generate a summary which will be returned as an AgentMessage event.
@@ -402,6 +416,28 @@ pub enum InputItem {
     LocalImage {
         path: std::path::PathBuf,
     },
+
+    /// A PDF or DOCX document attached by the user. Its text is extracted
+    /// and inlined before the turn starts.
+    LocalFile {
+        path: std::path::PathBuf,
+    },
+
+    /// A resource from an MCP server, attached with `@server:uri`. It is
+    /// read and inlined as text before the turn starts.
+    McpResource {
+        server: String,
+        uri: String,
+    },
+
+    /// A prompt from an MCP server, invoked as `/server:prompt`. It is
+    /// rendered by the server and its messages become the user's input.
+    McpPrompt {
+        server: String,
+        name: String,
+        #[serde(default)]
+        arguments: HashMap<String, String>,
+    },
 }
 
 /// Event Queue Entry - events from agent
@@ -477,6 +513,18 @@ pub enum EventMsg {
 
     ApplyPatchApprovalRequest(ApplyPatchApprovalRequestEvent),
 
+    /// An approval request went unanswered for `approval_timeout_secs` and
+    /// was resolved automatically.
+    ApprovalTimedOut(ApprovalTimedOutEvent),
+
+    /// A patch was rejected because some of its paths resolve, through
+    /// symlinks or `..`, outside the writable roots.
+    WorkspaceEscapeBlocked(WorkspaceEscapeBlockedEvent),
+
+    /// A patch was refused because it edits binary, oversized or generated
+    /// files (`[patch_guard]`).
+    PatchGuardBlocked(PatchGuardBlockedEvent),
+
     BackgroundEvent(BackgroundEventEvent),
 
     /// Notification that a model stream experienced an error or disconnect
@@ -501,8 +549,24 @@ pub enum EventMsg {
     /// List of custom prompts available to the agent.
     ListCustomPromptsResponse(ListCustomPromptsResponseEvent),
 
+    /// An MCP resource the user subscribed to has changed.
+    McpResourceUpdated(McpResourceUpdatedEvent),
+
+    /// The assembled prompt, in response to `Op::GetPrompt`.
+    PromptPreview(PromptPreviewEvent),
+
     PlanUpdate(UpdatePlanArgs),
 
+    /// A unit of work inside the running task started. Sub-tasks nest
+    /// through `parent_id`, so front-ends can show where a long task is
+    /// spending its time.
+    SubtaskBegin(SubtaskBeginEvent),
+
+    /// A running sub-task reported what it is doing now.
+    SubtaskProgress(SubtaskProgressEvent),
+
+    SubtaskEnd(SubtaskEndEvent),
+
     TurnAborted(TurnAbortedEvent),
 
     /// Notification that the agent is shutting down.
@@ -522,6 +586,60 @@ pub enum EventMsg {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ErrorEvent {
     pub message: String,
+    /// Coarse classification so non-interactive clients can react to the
+    /// error without parsing `message`.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub category: Option<ErrorCategory>,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ErrorCategory {
+    /// Missing, invalid or insufficient credentials.
+    Auth,
+    /// The model provider failed or could not be reached.
+    Provider,
+    /// A plan or account usage limit was reached.
+    UsageLimit,
+    /// The conversation no longer fits in the model's context window.
+    ContextWindow,
+    /// The sandbox could not be set up or refused an operation the turn
+    /// depended on.
+    Sandbox,
+    /// Anything else.
+    Internal,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubtaskBeginEvent {
+    /// Identifier unique within the task, referenced by later progress and
+    /// end events and by the `parent_id` of nested sub-tasks.
+    pub id: String,
+    /// The enclosing sub-task, or `None` for a top-level one.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub parent_id: Option<String>,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubtaskProgressEvent {
+    pub id: String,
+    pub message: String,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct SubtaskEndEvent {
+    pub id: String,
+    pub status: SubtaskStatus,
+}
+
+#[derive(Debug, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum SubtaskStatus {
+    Completed,
+    /// The sub-task was abandoned, e.g. dropped from the plan or cut short
+    /// by an interrupted task.
+    Cancelled,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -703,6 +821,10 @@ pub enum InputMessageKind {
     UserInstructions,
     /// XML-wrapped environment context (<environment_context>...)
     EnvironmentContext,
+    /// XML-wrapped repository map (<repo_map>...)
+    RepoMap,
+    /// XML-wrapped note on files changed outside the agent (<file_changes>...)
+    FileChanges,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
@@ -731,6 +853,14 @@ where
             && ends_with_ignore_ascii_case(trimmed, USER_INSTRUCTIONS_CLOSE_TAG)
         {
             InputMessageKind::UserInstructions
+        } else if starts_with_ignore_ascii_case(trimmed, REPO_MAP_OPEN_TAG)
+            && ends_with_ignore_ascii_case(trimmed, REPO_MAP_CLOSE_TAG)
+        {
+            InputMessageKind::RepoMap
+        } else if starts_with_ignore_ascii_case(trimmed, FILE_CHANGES_OPEN_TAG)
+            && ends_with_ignore_ascii_case(trimmed, FILE_CHANGES_CLOSE_TAG)
+        {
+            InputMessageKind::FileChanges
         } else {
             InputMessageKind::Plain
         }
@@ -916,6 +1046,7 @@ pub enum RolloutItem {
     Compacted(CompactedItem),
     TurnContext(TurnContextItem),
     EventMsg(EventMsg),
+    Approval(ApprovalItem),
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, TS)]
@@ -946,6 +1077,26 @@ pub struct TurnContextItem {
     pub summary: ReasoningSummaryConfig,
 }
 
+/// The answer to an approval request, recorded so that approval rates can be
+/// computed from past sessions.
+#[derive(Serialize, Deserialize, Clone, Debug, TS)]
+pub struct ApprovalItem {
+    pub call_id: String,
+    pub kind: ApprovalKind,
+    pub decision: ReviewDecision,
+    /// The request went unanswered and `decision` was applied by
+    /// `approval_timeout_secs`.
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    pub timed_out: bool,
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, Hash, TS)]
+#[serde(rename_all = "snake_case")]
+pub enum ApprovalKind {
+    Exec,
+    Patch,
+}
+
 #[derive(Serialize, Deserialize, Clone)]
 pub struct RolloutLine {
     pub timestamp: String,
@@ -1094,6 +1245,72 @@ pub struct ApplyPatchApprovalRequestEvent {
     pub grant_root: Option<PathBuf>,
 }
 
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct ApprovalTimedOutEvent {
+    /// Identifier of the exec or patch call whose approval request expired.
+    pub call_id: String,
+    /// How long the request waited before being resolved.
+    pub timeout_secs: u64,
+    /// The action that was applied on the user's behalf.
+    pub action: ApprovalTimeoutAction,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct WorkspaceEscapeBlockedEvent {
+    /// Identifier of the rejected patch call.
+    pub call_id: String,
+    /// The offending paths, sorted by path.
+    pub escapes: Vec<WorkspaceEscape>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct WorkspaceEscape {
+    /// Path as named by the patch.
+    pub path: PathBuf,
+    /// Where a write to `path` would actually land.
+    pub resolved: PathBuf,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct PatchGuardBlockedEvent {
+    /// Identifier of the refused patch call.
+    pub call_id: String,
+    /// The files the patch may not edit, sorted by path.
+    pub violations: Vec<PatchGuardViolation>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+pub struct PatchGuardViolation {
+    /// Path as named by the patch.
+    pub path: PathBuf,
+    pub reason: PatchGuardReason,
+}
+
+/// Why `[patch_guard]` protects a file.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize, TS)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum PatchGuardReason {
+    /// The file (or the content the patch writes) contains NUL bytes.
+    Binary,
+    /// The file, before or after the patch, is larger than `limit` bytes.
+    TooLarge { bytes: u64, limit: u64 },
+    /// The path is generated, per the `rule` that matched it: a
+    /// `.gitattributes` line or a `protected_paths` glob.
+    Generated { rule: String },
+}
+
+impl fmt::Display for PatchGuardReason {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            PatchGuardReason::Binary => write!(f, "binary file"),
+            PatchGuardReason::TooLarge { bytes, limit } => {
+                write!(f, "{bytes} bytes, over the {limit} byte limit")
+            }
+            PatchGuardReason::Generated { rule } => write!(f, "generated file ({rule})"),
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct BackgroundEventEvent {
     pub message: String,
@@ -1145,12 +1362,40 @@ pub struct GetHistoryEntryResponseEvent {
 pub struct McpListToolsResponseEvent {
     /// Fully qualified tool name -> tool definition.
     pub tools: std::collections::HashMap<String, McpTool>,
+    /// Server name -> resources it currently offers.
+    #[serde(default)]
+    pub resources: std::collections::HashMap<String, Vec<McpResource>>,
+    /// Server name -> prompts it offers.
+    #[serde(default)]
+    pub prompts: std::collections::HashMap<String, Vec<McpPrompt>>,
 }
 
 /// Response payload for `Op::ListCustomPrompts`.
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct ListCustomPromptsResponseEvent {
     pub custom_prompts: Vec<CustomPrompt>,
+    /// Server name -> prompts offered by that MCP server, shown as
+    /// `/server:prompt` slash commands.
+    #[serde(default)]
+    pub mcp_prompts: std::collections::HashMap<String, Vec<McpPrompt>>,
+}
+
+/// What the model is sent besides the conversation itself.
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct PromptPreviewEvent {
+    /// The `instructions` (system prompt) of the request.
+    pub instructions: String,
+    /// Messages sent at the start of the conversation: `AGENTS.md` user
+    /// instructions and the environment context.
+    pub context: Vec<String>,
+    /// Names of the tools offered to the model.
+    pub tools: Vec<String>,
+}
+
+#[derive(Debug, Clone, Deserialize, Serialize, TS)]
+pub struct McpResourceUpdatedEvent {
+    pub server: String,
+    pub uri: String,
 }
 
 #[derive(Debug, Default, Clone, Deserialize, Serialize, TS)]
@@ -1201,6 +1446,31 @@ pub enum ReviewDecision {
     Abort,
 }
 
+/// What to do when an approval request is not answered within
+/// `approval_timeout_secs`.
+#[derive(Debug, Default, Clone, Copy, Deserialize, Serialize, PartialEq, Eq, TS)]
+#[serde(rename_all = "lowercase")]
+pub enum ApprovalTimeoutAction {
+    /// Reject the request; the agent continues and may try something else.
+    #[default]
+    Deny,
+    /// Approve the request once.
+    Allow,
+    /// Reject the request and stop the current turn.
+    Abort,
+}
+
+impl ApprovalTimeoutAction {
+    /// The decision recorded on the user's behalf.
+    pub fn decision(self) -> ReviewDecision {
+        match self {
+            ApprovalTimeoutAction::Deny => ReviewDecision::Denied,
+            ApprovalTimeoutAction::Allow => ReviewDecision::Approved,
+            ApprovalTimeoutAction::Abort => ReviewDecision::Abort,
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
 #[serde(rename_all = "snake_case")]
 pub enum FileChange {
@@ -1227,6 +1497,20 @@ pub struct Chunk {
 #[derive(Debug, Clone, Deserialize, Serialize, TS)]
 pub struct TurnAbortedEvent {
     pub reason: TurnAbortReason,
+    /// What was torn down because the turn was aborted.
+    #[serde(default)]
+    pub cleanup: TurnAbortCleanup,
+}
+
+/// Summary of the work that was still running when a turn was aborted and
+/// has been terminated as a result.
+#[derive(Debug, Clone, Default, Deserialize, Serialize, PartialEq, TS)]
+pub struct TurnAbortCleanup {
+    /// Number of in-flight tool calls whose process group (the command and
+    /// every process it spawned) was killed.
+    pub terminated_tool_calls: u32,
+    /// Number of interactive PTY sessions that were closed.
+    pub closed_pty_sessions: u32,
 }
 
 #[derive(Debug, Clone, Deserialize, Serialize, PartialEq, TS)]
@@ -2,10 +2,14 @@ use crate::app_backtrack::BacktrackState;
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::chatwidget::ChatWidget;
+use crate::diff_pane::DiffPane;
 use crate::file_search::FileSearchManager;
 use crate::history_cell::HistoryCell;
 use crate::pager_overlay::Overlay;
 use crate::resume_picker::ResumeSelection;
+use crate::resume_picker::recent_sessions;
+use crate::settings;
+use crate::settings::SettingChange;
 use crate::tui;
 use crate::tui::TuiEvent;
 use codex_ansi_escape::ansi_escape_line;
@@ -14,6 +18,7 @@ use codex_core::ConversationManager;
 use codex_core::config::Config;
 use codex_core::config::persist_model_selection;
 use codex_core::model_family::find_family_for_model;
+use codex_core::protocol::Op;
 use codex_core::protocol::TokenUsage;
 use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use color_eyre::eyre::Result;
@@ -34,10 +39,17 @@ use tokio::select;
 use tokio::sync::mpsc::unbounded_channel;
 // use uuid::Uuid;
 
+/// How many other sessions the command palette offers to resume.
+const PALETTE_SESSIONS: usize = 10;
+
 pub(crate) struct App {
     pub(crate) server: Arc<ConversationManager>,
+    auth_manager: Arc<AuthManager>,
     pub(crate) app_event_tx: AppEventSender,
     pub(crate) chat_widget: ChatWidget,
+    /// Whether the session runs in another process (`codex attach`), which
+    /// rules out switching sessions from here.
+    pub(crate) attached: bool,
 
     /// Config is stored here so we can recreate ChatWidgets as needed.
     pub(crate) config: Config,
@@ -51,6 +63,9 @@ pub(crate) struct App {
 
     // Pager overlay state (Transcript or Static like Diff)
     pub(crate) overlay: Option<Overlay>,
+    /// Live diff shown beside the transcript overlay. Owned by the overlay
+    /// while it is open.
+    pub(crate) diff_pane: DiffPane,
     pub(crate) deferred_history_lines: Vec<Line<'static>>,
     has_emitted_history_lines: bool,
 
@@ -81,6 +96,7 @@ impl App {
 
         let enhanced_keys_supported = supports_keyboard_enhancement().unwrap_or(false);
 
+        let attached = matches!(resume_selection, ResumeSelection::Attach(_));
         let chat_widget = match resume_selection {
             ResumeSelection::StartFresh | ResumeSelection::Exit => {
                 let init = crate::chatwidget::ChatWidgetInit {
@@ -118,14 +134,37 @@ impl App {
                     resumed.session_configured,
                 )
             }
+            #[cfg(unix)]
+            ResumeSelection::Attach(socket) => {
+                let stream = tokio::net::UnixStream::connect(&socket)
+                    .await
+                    .wrap_err_with(|| format!("Failed to attach to {}", socket.display()))?;
+                let init = crate::chatwidget::ChatWidgetInit {
+                    config: config.clone(),
+                    frame_requester: tui.frame_requester(),
+                    app_event_tx: app_event_tx.clone(),
+                    initial_prompt: initial_prompt.clone(),
+                    initial_images: initial_images.clone(),
+                    enhanced_keys_supported,
+                };
+                ChatWidget::new_attached(init, stream)
+            }
+            #[cfg(not(unix))]
+            ResumeSelection::Attach(_) => {
+                return Err(color_eyre::eyre::eyre!(
+                    "attaching to a session is only supported on Unix"
+                ));
+            }
         };
 
         let file_search = FileSearchManager::new(config.cwd.clone(), app_event_tx.clone());
 
         let mut app = Self {
             server: conversation_manager,
+            auth_manager,
             app_event_tx,
             chat_widget,
+            attached,
             config,
             active_profile,
             model_saved_to_profile: false,
@@ -134,6 +173,7 @@ impl App {
             enhanced_keys_supported,
             transcript_cells: Vec::new(),
             overlay: None,
+            diff_pane: DiffPane::default(),
             deferred_history_lines: Vec::new(),
             has_emitted_history_lines: false,
             commit_anim_running: Arc::new(AtomicBool::new(false)),
@@ -201,6 +241,7 @@ impl App {
 
     async fn handle_event(&mut self, tui: &mut tui::Tui, event: AppEvent) -> Result<bool> {
         match event {
+            AppEvent::NewSession if self.attached => self.reject_while_attached(),
             AppEvent::NewSession => {
                 let init = crate::chatwidget::ChatWidgetInit {
                     config: self.config.clone(),
@@ -210,6 +251,7 @@ impl App {
                     initial_images: Vec::new(),
                     enhanced_keys_supported: self.enhanced_keys_supported,
                 };
+                self.reset_conversation_state();
                 self.chat_widget = ChatWidget::new(init, self.server.clone());
                 tui.frame_requester().schedule_frame();
             }
@@ -262,6 +304,13 @@ impl App {
                 self.chat_widget.on_commit_tick();
             }
             AppEvent::CodexEvent(event) => {
+                let diff_pane = match &mut self.overlay {
+                    Some(Overlay::Transcript(t)) => t.diff_pane_mut(),
+                    _ => &mut self.diff_pane,
+                };
+                if diff_pane.on_event(&event.msg) && diff_pane.is_visible() {
+                    tui.frame_requester().schedule_frame();
+                }
                 self.chat_widget.handle_codex_event(event);
             }
             AppEvent::ConversationHistory(ev) => {
@@ -271,6 +320,9 @@ impl App {
                 return Ok(false);
             }
             AppEvent::CodexOp(op) => self.chat_widget.submit_op(op),
+            AppEvent::DispatchCommand(cmd) => self.chat_widget.dispatch_command(cmd),
+            AppEvent::ResumeSession(_) if self.attached => self.reject_while_attached(),
+            AppEvent::ResumeSession(path) => self.resume_session(tui, path).await,
             AppEvent::DiffResult(text) => {
                 // Clear the in-progress state in the bottom pane
                 self.chat_widget.on_diff_complete();
@@ -287,6 +339,9 @@ impl App {
                 ));
                 tui.frame_requester().schedule_frame();
             }
+            AppEvent::CommandOutputAttached(attached) => {
+                self.chat_widget.on_command_output_attached(attached);
+            }
             AppEvent::StartFileSearch(query) => {
                 if !query.is_empty() {
                     self.file_search.on_user_query(query);
@@ -314,6 +369,8 @@ impl App {
             AppEvent::UpdateSandboxPolicy(policy) => {
                 self.chat_widget.set_sandbox_policy(policy);
             }
+            AppEvent::EditSetting(setting) => self.chat_widget.open_setting_choices(setting),
+            AppEvent::ApplySetting(change) => self.apply_setting(change).await,
         }
         Ok(true)
     }
@@ -425,6 +482,152 @@ impl App {
         }
     }
 
+    /// Apply a value chosen from `/settings` to the running session, save it
+    /// to `config.toml` and return to the settings screen.
+    async fn apply_setting(&mut self, change: SettingChange) {
+        if let Err(err) = settings::validate(&change, &self.config) {
+            self.chat_widget
+                .add_error_message(format!("Cannot change this setting: {err}"));
+            return;
+        }
+        let mut op_model = None;
+        let mut op_effort = None;
+        let mut op_approval = None;
+        let mut op_sandbox = None;
+        match &change {
+            SettingChange::Model { model, effort } => {
+                self.chat_widget.set_model(model.clone());
+                self.config.model = model.clone();
+                if let Some(family) = find_family_for_model(model) {
+                    self.config.model_family = family;
+                }
+                self.chat_widget.set_reasoning_effort(*effort);
+                self.config.model_reasoning_effort = *effort;
+                op_model = Some(model.clone());
+                op_effort = Some(*effort);
+            }
+            SettingChange::ApprovalPolicy(policy) => {
+                self.chat_widget.set_approval_policy(*policy);
+                self.config.approval_policy = *policy;
+                op_approval = Some(*policy);
+            }
+            SettingChange::SandboxMode(mode) => {
+                let policy = settings::sandbox_policy_for(*mode, &self.config);
+                self.chat_widget.set_sandbox_policy(policy.clone());
+                self.config.sandbox_policy = policy.clone();
+                op_sandbox = Some(policy);
+            }
+        }
+        self.chat_widget.submit_op(Op::OverrideTurnContext {
+            cwd: None,
+            approval_policy: op_approval,
+            sandbox_policy: op_sandbox,
+            model: op_model,
+            effort: op_effort,
+            summary: None,
+        });
+
+        let profile = self.active_profile.clone();
+        let codex_home = self.config.codex_home.clone();
+        match settings::persist_setting(&codex_home, profile.as_deref(), &change).await {
+            Ok(()) => {
+                if matches!(change, SettingChange::Model { .. }) {
+                    if profile.is_some() {
+                        self.model_saved_to_profile = true;
+                    } else {
+                        self.model_saved_to_global = true;
+                    }
+                }
+                let target = match &profile {
+                    Some(profile) => format!("profile `{profile}` in config.toml"),
+                    None => "config.toml".to_string(),
+                };
+                self.chat_widget.add_info_message(
+                    format!("Saved {} to {target}", settings::describe_change(&change)),
+                    None,
+                );
+            }
+            Err(err) => {
+                tracing::error!(error = %err, "failed to save setting");
+                self.chat_widget.add_error_message(format!(
+                    "Changed for this session, but failed to save to config.toml: {err}"
+                ));
+            }
+        }
+        self.chat_widget.open_settings();
+    }
+
+    async fn open_command_palette(&mut self) {
+        let current = self.chat_widget.conversation_id().map(|id| id.to_string());
+        let sessions = match recent_sessions(&self.config.codex_home, PALETTE_SESSIONS + 1).await {
+            Ok(sessions) => sessions,
+            Err(err) => {
+                tracing::warn!("failed to list sessions for the command palette: {err}");
+                Vec::new()
+            }
+        };
+        let sessions = sessions
+            .into_iter()
+            .filter(|(path, _)| {
+                !current
+                    .as_deref()
+                    .is_some_and(|id| path.to_string_lossy().contains(id))
+            })
+            .take(PALETTE_SESSIONS)
+            .collect();
+        self.chat_widget.open_command_palette(sessions);
+    }
+
+    pub(crate) fn reject_while_attached(&mut self) {
+        self.chat_widget.add_error_message(
+            "This session runs elsewhere; detach with /quit to start or resume another."
+                .to_string(),
+        );
+    }
+
+    /// Forget the transcript and any backtrack of the conversation being
+    /// replaced, so the transcript overlay and Esc-backtracking only see the
+    /// new one.
+    fn reset_conversation_state(&mut self) {
+        self.transcript_cells.clear();
+        self.reset_backtrack_state();
+        self.backtrack.pending = None;
+        self.backtrack.overlay_preview_active = false;
+    }
+
+    async fn resume_session(&mut self, tui: &mut tui::Tui, path: PathBuf) {
+        let resumed = match self
+            .server
+            .resume_conversation_from_rollout(
+                self.config.clone(),
+                path.clone(),
+                self.auth_manager.clone(),
+            )
+            .await
+        {
+            Ok(resumed) => resumed,
+            Err(err) => {
+                self.chat_widget.add_error_message(format!(
+                    "Failed to resume session from {}: {err}",
+                    path.display()
+                ));
+                return;
+            }
+        };
+        let init = crate::chatwidget::ChatWidgetInit {
+            config: self.config.clone(),
+            frame_requester: tui.frame_requester(),
+            app_event_tx: self.app_event_tx.clone(),
+            initial_prompt: None,
+            initial_images: Vec::new(),
+            enhanced_keys_supported: self.enhanced_keys_supported,
+        };
+        self.reset_conversation_state();
+        self.chat_widget =
+            ChatWidget::new_from_existing(init, resumed.conversation, resumed.session_configured);
+        tui.frame_requester().schedule_frame();
+    }
+
     async fn handle_key_event(&mut self, tui: &mut tui::Tui, key_event: KeyEvent) {
         match key_event {
             KeyEvent {
@@ -433,10 +636,7 @@ impl App {
                 kind: KeyEventKind::Press,
                 ..
             } => {
-                // Enter alternate screen and set viewport to full size.
-                let _ = tui.enter_alt_screen();
-                self.overlay = Some(Overlay::new_transcript(self.transcript_cells.clone()));
-                tui.frame_requester().schedule_frame();
+                self.open_transcript_overlay(tui);
             }
             KeyEvent {
                 code: KeyCode::Char('s'),
@@ -446,6 +646,14 @@ impl App {
             } => {
                 self.persist_model_shortcut().await;
             }
+            KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: crossterm::event::KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            } if !self.chat_widget.has_active_view() => {
+                self.open_command_palette().await;
+            }
             // Esc primes/advances backtracking only in normal (not working) mode
             // with an empty composer. In any other state, forward Esc so the
             // active UI (e.g. status indicator, modals, popups) handles it.
@@ -508,15 +716,17 @@ mod tests {
         let (chat_widget, app_event_tx, _rx, _op_rx) = make_chatwidget_manual_with_sender();
         let config = chat_widget.config_ref().clone();
 
-        let server = Arc::new(ConversationManager::with_auth(CodexAuth::from_api_key(
-            "Test API Key",
-        )));
+        let auth_manager =
+            AuthManager::from_auth_for_testing(CodexAuth::from_api_key("Test API Key"));
+        let server = Arc::new(ConversationManager::new(auth_manager.clone()));
         let file_search = FileSearchManager::new(config.cwd.clone(), app_event_tx.clone());
 
         App {
             server,
+            auth_manager,
             app_event_tx,
             chat_widget,
+            attached: false,
             config,
             active_profile: None,
             model_saved_to_profile: false,
@@ -524,6 +734,7 @@ mod tests {
             file_search,
             transcript_cells: Vec::new(),
             overlay: None,
+            diff_pane: DiffPane::default(),
             deferred_history_lines: Vec::new(),
             has_emitted_history_lines: false,
             enhanced_keys_supported: false,
@@ -554,4 +765,27 @@ mod tests {
         assert!(!app.model_saved_to_profile);
         assert!(!app.model_saved_to_global);
     }
+
+    #[test]
+    fn replacing_the_conversation_forgets_its_transcript_and_backtrack() {
+        let mut app = make_test_app();
+        app.transcript_cells
+            .push(Arc::new(crate::history_cell::new_user_prompt(
+                "fix the build".to_string(),
+            )));
+        app.backtrack.primed = true;
+        app.backtrack.nth_user_message = 0;
+        app.backtrack.pending = Some((
+            app.chat_widget.conversation_id().unwrap_or_default(),
+            0,
+            String::new(),
+        ));
+
+        app.reset_conversation_state();
+
+        assert!(app.transcript_cells.is_empty());
+        assert!(!app.backtrack.primed);
+        assert_eq!(app.backtrack.nth_user_message, usize::MAX);
+        assert!(app.backtrack.pending.is_none());
+    }
 }
@@ -107,7 +107,10 @@ impl App {
     /// Open transcript overlay (enters alternate screen and shows full transcript).
     pub(crate) fn open_transcript_overlay(&mut self, tui: &mut tui::Tui) {
         let _ = tui.enter_alt_screen();
-        self.overlay = Some(Overlay::new_transcript(self.transcript_cells.clone()));
+        self.overlay = Some(Overlay::new_transcript(
+            self.transcript_cells.clone(),
+            std::mem::take(&mut self.diff_pane),
+        ));
         tui.frame_requester().schedule_frame();
     }
 
@@ -119,7 +122,9 @@ impl App {
             let lines = std::mem::take(&mut self.deferred_history_lines);
             tui.insert_history_lines(lines);
         }
-        self.overlay = None;
+        if let Some(Overlay::Transcript(t)) = self.overlay.take() {
+            self.diff_pane = t.into_diff_pane();
+        }
         self.backtrack.overlay_preview_active = false;
         if was_backtrack {
             // Ensure backtrack state is fully reset when overlay closes (e.g. via 'q').
@@ -292,6 +297,11 @@ impl App {
         nth_user_message: usize,
         prefill: String,
     ) {
+        // The rollout to fork from is on the machine running the session.
+        if self.attached {
+            self.reject_while_attached();
+            return;
+        }
         let cfg = self.chat_widget.config_ref().clone();
         // Perform the fork via a thin wrapper for clarity/testability.
         let result = self
@@ -1,8 +1,14 @@
+use std::path::PathBuf;
+
 use codex_core::protocol::ConversationPathResponseEvent;
 use codex_core::protocol::Event;
 use codex_file_search::FileMatch;
 
+use crate::attach_cmd::AttachedCommandOutput;
 use crate::history_cell::HistoryCell;
+use crate::settings::Setting;
+use crate::settings::SettingChange;
+use crate::slash_command::SlashCommand;
 
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
@@ -36,9 +42,18 @@ pub(crate) enum AppEvent {
         matches: Vec<FileMatch>,
     },
 
+    /// Run a slash command chosen from the command palette.
+    DispatchCommand(SlashCommand),
+
+    /// Switch to the recorded session at the given rollout path.
+    ResumeSession(PathBuf),
+
     /// Result of computing a `/diff` command.
     DiffResult(String),
 
+    /// Output of an `/attach-cmd` command, held for the next user message.
+    CommandOutputAttached(AttachedCommandOutput),
+
     InsertHistoryCell(Box<dyn HistoryCell>),
 
     StartCommitAnimation,
@@ -57,6 +72,13 @@ pub(crate) enum AppEvent {
     /// Update the current sandbox policy in the running app and widget.
     UpdateSandboxPolicy(SandboxPolicy),
 
+    /// Show the choices for a value picked on the `/settings` screen.
+    EditSetting(Setting),
+
+    /// Apply a value chosen from `/settings` to the session and save it to
+    /// `config.toml`.
+    ApplySetting(SettingChange),
+
     /// Forwarded conversation history snapshot from the current conversation.
     ConversationHistory(ConversationPathResponseEvent),
 }
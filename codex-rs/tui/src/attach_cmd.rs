@@ -0,0 +1,130 @@
+//! `/attach-cmd <command>`: run a command on the user's behalf, outside the
+//! model loop, and attach its output to the next message.
+
+use std::path::Path;
+use std::time::Duration;
+
+use codex_core::protocol::InputItem;
+use tokio::process::Command;
+
+/// Output beyond this many bytes is elided from the middle.
+const MAX_ATTACHED_OUTPUT_BYTES: usize = 16 * 1024;
+
+/// Commands still running after this long are killed.
+const ATTACH_CMD_TIMEOUT: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct AttachedCommandOutput {
+    pub command: String,
+    /// `None` when the command could not be started, timed out or was
+    /// killed by a signal.
+    pub exit_code: Option<i32>,
+    /// Combined stdout and stderr, already truncated.
+    pub output: String,
+}
+
+impl AttachedCommandOutput {
+    /// The labeled block sent to the model ahead of the user's message.
+    pub(crate) fn to_input_item(&self) -> InputItem {
+        let exit_code = self
+            .exit_code
+            .map(|code| code.to_string())
+            .unwrap_or_else(|| "none".to_string());
+        InputItem::Text {
+            text: format!(
+                "<command_output command=\"{}\" exit_code=\"{exit_code}\">\n{}\n</command_output>",
+                self.command.replace('"', "&quot;"),
+                self.output.trim_end()
+            ),
+        }
+    }
+}
+
+/// Run `command` through the shell in `cwd` and capture what it prints.
+pub(crate) async fn run_attached_command(command: String, cwd: &Path) -> AttachedCommandOutput {
+    let mut cmd = if cfg!(windows) {
+        let mut cmd = Command::new("cmd");
+        cmd.arg("/C");
+        cmd
+    } else {
+        let mut cmd = Command::new("sh");
+        cmd.arg("-c");
+        cmd
+    };
+    cmd.arg(&command)
+        .current_dir(cwd)
+        .stdin(std::process::Stdio::null())
+        .kill_on_drop(true);
+
+    let (exit_code, output) = match tokio::time::timeout(ATTACH_CMD_TIMEOUT, cmd.output()).await {
+        Ok(Ok(out)) => {
+            let mut text = String::from_utf8_lossy(&out.stdout).into_owned();
+            text.push_str(&String::from_utf8_lossy(&out.stderr));
+            (out.status.code(), text)
+        }
+        Ok(Err(err)) => (None, format!("failed to run command: {err}")),
+        Err(_) => (
+            None,
+            format!(
+                "command timed out after {} seconds",
+                ATTACH_CMD_TIMEOUT.as_secs()
+            ),
+        ),
+    };
+    AttachedCommandOutput {
+        command,
+        exit_code,
+        output: truncate_middle(&output, MAX_ATTACHED_OUTPUT_BYTES),
+    }
+}
+
+/// Keep the first and last halves of `text` when it is longer than
+/// `max_bytes`, noting how much was left out.
+fn truncate_middle(text: &str, max_bytes: usize) -> String {
+    if text.len() <= max_bytes {
+        return text.to_string();
+    }
+    let mut head_end = max_bytes / 2;
+    while !text.is_char_boundary(head_end) {
+        head_end -= 1;
+    }
+    let mut tail_start = text.len() - max_bytes / 2;
+    while !text.is_char_boundary(tail_start) {
+        tail_start += 1;
+    }
+    let omitted = tail_start - head_end;
+    format!(
+        "{}\n[... {omitted} bytes omitted ...]\n{}",
+        &text[..head_end],
+        &text[tail_start..]
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn output_is_truncated_and_labeled() {
+        let long = format!("{}{}", "a".repeat(20), "b".repeat(20));
+        assert_eq!(
+            truncate_middle(&long, 10),
+            "aaaaa\n[... 30 bytes omitted ...]\nbbbbb"
+        );
+        assert_eq!(truncate_middle("short", 10), "short");
+
+        let attached = AttachedCommandOutput {
+            command: "grep \"todo\" src".to_string(),
+            exit_code: Some(1),
+            output: "no matches\n".to_string(),
+        };
+        assert_eq!(
+            attached.to_input_item(),
+            InputItem::Text {
+                text: "<command_output command=\"grep &quot;todo&quot; src\" exit_code=\"1\">\nno matches\n</command_output>"
+                    .to_string(),
+            }
+        );
+    }
+}
@@ -1,3 +1,6 @@
+use std::time::Duration;
+
+use codex_core::config_types::ApprovalTimeout;
 use crossterm::event::KeyEvent;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Rect;
@@ -16,14 +19,20 @@ pub(crate) struct ApprovalModalView {
     current: UserApprovalWidget,
     queue: Vec<ApprovalRequest>,
     app_event_tx: AppEventSender,
+    timeout: Option<ApprovalTimeout>,
 }
 
 impl ApprovalModalView {
-    pub fn new(request: ApprovalRequest, app_event_tx: AppEventSender) -> Self {
+    pub fn new(
+        request: ApprovalRequest,
+        app_event_tx: AppEventSender,
+        timeout: Option<ApprovalTimeout>,
+    ) -> Self {
         Self {
-            current: UserApprovalWidget::new(request, app_event_tx.clone()),
+            current: UserApprovalWidget::new(request, app_event_tx.clone(), timeout),
             queue: Vec::new(),
             app_event_tx,
+            timeout,
         }
     }
 
@@ -36,7 +45,7 @@ impl ApprovalModalView {
         if self.current.is_complete()
             && let Some(req) = self.queue.pop()
         {
-            self.current = UserApprovalWidget::new(req, self.app_event_tx.clone());
+            self.current = UserApprovalWidget::new(req, self.app_event_tx.clone(), self.timeout);
         }
     }
 }
@@ -69,6 +78,15 @@ impl BottomPaneView for ApprovalModalView {
         self.enqueue_request(req);
         None
     }
+
+    fn on_approval_timed_out(&mut self) {
+        self.current.dismiss();
+        self.maybe_advance();
+    }
+
+    fn redraw_interval(&self) -> Option<Duration> {
+        self.timeout.map(|_| Duration::from_secs(1))
+    }
 }
 
 #[cfg(test)]
@@ -90,7 +108,7 @@ mod tests {
         let (tx, _rx) = unbounded_channel::<AppEvent>();
         let tx = AppEventSender::new(tx);
         let first = make_exec_request();
-        let mut view = ApprovalModalView::new(first, tx);
+        let mut view = ApprovalModalView::new(first, tx, None);
         view.enqueue_request(make_exec_request());
 
         let (tx2, _rx2) = unbounded_channel::<AppEvent>();
@@ -101,6 +119,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
         assert_eq!(CancellationEvent::Handled, view.on_ctrl_c(&mut pane));
         assert!(view.queue.is_empty());
@@ -1,3 +1,5 @@
+use std::time::Duration;
+
 use crate::user_approval_widget::ApprovalRequest;
 use crossterm::event::KeyEvent;
 use ratatui::buffer::Buffer;
@@ -36,4 +38,14 @@ pub(crate) trait BottomPaneView {
     ) -> Option<ApprovalRequest> {
         Some(request)
     }
+
+    /// The pending approval request timed out in core and no longer needs an
+    /// answer.
+    fn on_approval_timed_out(&mut self) {}
+
+    /// If the view shows time-sensitive content, how soon it should be
+    /// redrawn.
+    fn redraw_interval(&self) -> Option<Duration> {
+        None
+    }
 }
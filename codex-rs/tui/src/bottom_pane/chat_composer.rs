@@ -1,3 +1,4 @@
+use codex_core::protocol::Op;
 use codex_core::protocol::TokenUsageInfo;
 use codex_protocol::num_format::format_si_suffix;
 use crossterm::event::KeyCode;
@@ -24,6 +25,7 @@ use ratatui::widgets::WidgetRef;
 use super::chat_composer_history::ChatComposerHistory;
 use super::command_popup::CommandItem;
 use super::command_popup::CommandPopup;
+use super::command_popup::McpPromptCommand;
 use super::file_search_popup::FileSearchPopup;
 use super::paste_burst::CharDecision;
 use super::paste_burst::PasteBurst;
@@ -38,6 +40,7 @@ use crate::bottom_pane::textarea::TextAreaState;
 use crate::clipboard_paste::normalize_pasted_path;
 use crate::clipboard_paste::pasted_image_format;
 use crate::key_hint;
+use codex_core::documents::is_document;
 use codex_file_search::FileMatch;
 use std::cell::RefCell;
 use std::collections::HashMap;
@@ -55,9 +58,63 @@ const LARGE_PASTE_CHAR_THRESHOLD: usize = 1000;
 pub enum InputResult {
     Submitted(String),
     Command(SlashCommand),
+    /// A slash command followed by arguments, e.g. `/log level debug`.
+    CommandWithArgs(SlashCommand, String),
+    /// An MCP prompt invoked as `/server:prompt key=value ...`. `text` is the
+    /// line as typed, for the transcript.
+    McpPrompt {
+        server: String,
+        name: String,
+        arguments: HashMap<String, String>,
+        text: String,
+    },
     None,
 }
 
+/// Text typed after `cmd` on the first line of a slash command, if any. The
+/// command must be spelled out in full for its arguments to be passed on.
+fn slash_command_args(text: &str, cmd: SlashCommand) -> Option<String> {
+    let first_line = text.lines().next()?.strip_prefix('/')?;
+    let (name, args) = first_line.trim_start().split_once(char::is_whitespace)?;
+    let args = args.trim();
+    (name == cmd.command() && !args.is_empty()).then(|| args.to_string())
+}
+
+/// Arguments for an MCP prompt from the text typed after `/server:prompt`.
+/// `key=value` words set that argument; any other words are joined and given
+/// to the first declared argument not set explicitly, so a prompt with a
+/// single argument can be invoked as `/db:explain select * from users`.
+fn mcp_prompt_arguments(text: &str, command: &McpPromptCommand) -> HashMap<String, String> {
+    let first_line = text.lines().next().unwrap_or("");
+    let rest = first_line
+        .trim_start()
+        .strip_prefix('/')
+        .and_then(|line| line.split_once(char::is_whitespace))
+        .map(|(_, rest)| rest)
+        .unwrap_or("");
+
+    let mut arguments = HashMap::new();
+    let mut free_words: Vec<&str> = Vec::new();
+    for word in rest.split_whitespace() {
+        match word.split_once('=') {
+            Some((key, value)) if !key.is_empty() => {
+                arguments.insert(key.to_string(), value.to_string());
+            }
+            _ => free_words.push(word),
+        }
+    }
+    if !free_words.is_empty()
+        && let Some(declared) = command.prompt.arguments.as_ref().and_then(|declared| {
+            declared
+                .iter()
+                .find(|argument| !arguments.contains_key(&argument.name))
+        })
+    {
+        arguments.insert(declared.name.clone(), free_words.join(" "));
+    }
+    arguments
+}
+
 #[derive(Clone, Debug, PartialEq)]
 struct AttachedImage {
     placeholder: String,
@@ -86,6 +143,7 @@ pub(crate) struct ChatComposer {
     // When true, disables paste-burst logic and inserts characters immediately.
     disable_paste_burst: bool,
     custom_prompts: Vec<CustomPrompt>,
+    mcp_prompts: Vec<McpPromptCommand>,
 }
 
 /// Popup state – at most one can be visible at any time.
@@ -129,6 +187,7 @@ impl ChatComposer {
             paste_burst: PasteBurst::default(),
             disable_paste_burst: false,
             custom_prompts: Vec::new(),
+            mcp_prompts: Vec::new(),
         };
         // Apply configuration via the setter to keep side-effects centralized.
         this.set_disable_paste_burst(disable_paste_burst);
@@ -230,6 +289,10 @@ impl ChatComposer {
                 self.attach_image(path_buf, w, h, format_label);
                 true
             }
+            Err(_) if is_document(&path_buf) && path_buf.is_file() => {
+                self.attach_document(path_buf);
+                true
+            }
             Err(err) => {
                 tracing::info!("ERR: {err}");
                 false
@@ -273,6 +336,19 @@ impl ChatComposer {
             .push(AttachedImage { placeholder, path });
     }
 
+    /// Attach a PDF or DOCX file. It travels with the images and core
+    /// replaces it with its text.
+    pub fn attach_document(&mut self, path: PathBuf) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let placeholder = format!("[document {name}]");
+        self.textarea.insert_element(&placeholder);
+        self.attached_images
+            .push(AttachedImage { placeholder, path });
+    }
+
     pub fn take_recent_submission_images(&mut self) -> Vec<PathBuf> {
         let images = std::mem::take(&mut self.attached_images);
         images.into_iter().map(|img| img.path).collect()
@@ -395,6 +471,16 @@ impl ChatComposer {
                                 }
                             }
                         }
+                        CommandItem::McpPrompt(idx) => {
+                            if let Some(prompt) = popup.mcp_prompt(idx) {
+                                let name = prompt.command();
+                                let starts_with_cmd =
+                                    first_line.trim_start().starts_with(&format!("/{name}"));
+                                if !starts_with_cmd {
+                                    self.textarea.set_text(&format!("/{name} "));
+                                }
+                            }
+                        }
                     }
                     // After completing the command, move cursor to the end.
                     if !self.textarea.text().is_empty() {
@@ -410,6 +496,22 @@ impl ChatComposer {
                 ..
             } => {
                 if let Some(sel) = popup.selected_item() {
+                    let args = match sel {
+                        CommandItem::Builtin(cmd) => slash_command_args(self.textarea.text(), cmd),
+                        CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_) => None,
+                    };
+                    let mcp_prompt = match sel {
+                        CommandItem::McpPrompt(idx) => popup.mcp_prompt(idx).map(|prompt| {
+                            let text = self.textarea.text();
+                            InputResult::McpPrompt {
+                                server: prompt.server.clone(),
+                                name: prompt.prompt.name.clone(),
+                                arguments: mcp_prompt_arguments(text, prompt),
+                                text: text.lines().next().unwrap_or("").trim().to_string(),
+                            }
+                        }),
+                        _ => None,
+                    };
                     // Clear textarea so no residual text remains.
                     self.textarea.set_text("");
                     // Capture any needed data from popup before clearing it.
@@ -424,7 +526,10 @@ impl ChatComposer {
 
                     match sel {
                         CommandItem::Builtin(cmd) => {
-                            return (InputResult::Command(cmd), true);
+                            return match args {
+                                Some(args) => (InputResult::CommandWithArgs(cmd, args), true),
+                                None => (InputResult::Command(cmd), true),
+                            };
                         }
                         CommandItem::UserPrompt(_) => {
                             if let Some(contents) = prompt_content {
@@ -432,6 +537,9 @@ impl ChatComposer {
                             }
                             return (InputResult::None, true);
                         }
+                        CommandItem::McpPrompt(_) => {
+                            return (mcp_prompt.unwrap_or(InputResult::None), true);
+                        }
                     }
                 }
                 // Fallback to default newline handling if no command selected.
@@ -726,7 +834,9 @@ impl ChatComposer {
                 kind: KeyEventKind::Press,
                 ..
             } if self.is_empty() => {
-                self.app_event_tx.send(AppEvent::ExitRequest);
+                // Shut the session down cleanly, like /quit; we exit on
+                // ShutdownComplete.
+                self.app_event_tx.send(AppEvent::CodexOp(Op::Shutdown));
                 (InputResult::None, true)
             }
             // -------------------------------------------------------------
@@ -1152,6 +1262,7 @@ impl ChatComposer {
             _ => {
                 if input_starts_with_slash {
                     let mut command_popup = CommandPopup::new(self.custom_prompts.clone());
+                    command_popup.set_mcp_prompts(self.mcp_prompts.clone());
                     command_popup.on_composer_text_change(first_line.to_string());
                     self.active_popup = ActivePopup::Command(command_popup);
                 }
@@ -1159,6 +1270,10 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn custom_prompts(&self) -> &[CustomPrompt] {
+        &self.custom_prompts
+    }
+
     pub(crate) fn set_custom_prompts(&mut self, prompts: Vec<CustomPrompt>) {
         self.custom_prompts = prompts.clone();
         if let ActivePopup::Command(popup) = &mut self.active_popup {
@@ -1166,6 +1281,17 @@ impl ChatComposer {
         }
     }
 
+    pub(crate) fn mcp_prompts(&self) -> &[McpPromptCommand] {
+        &self.mcp_prompts
+    }
+
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptCommand>) {
+        self.mcp_prompts = prompts.clone();
+        if let ActivePopup::Command(popup) = &mut self.active_popup {
+            popup.set_mcp_prompts(prompts);
+        }
+    }
+
     /// Synchronize `self.file_search_popup` with the current text in the textarea.
     /// Note this is only called when self.active_popup is NOT Command.
     fn sync_file_search_popup(&mut self) {
@@ -1801,7 +1927,7 @@ mod tests {
                 Some(CommandItem::Builtin(cmd)) => {
                     assert_eq!(cmd.command(), "model")
                 }
-                Some(CommandItem::UserPrompt(_)) => {
+                Some(CommandItem::UserPrompt(_) | CommandItem::McpPrompt(_)) => {
                     panic!("unexpected prompt selected for '/mo'")
                 }
                 None => panic!("no selected command for '/mo'"),
@@ -1854,6 +1980,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
+            InputResult::CommandWithArgs(cmd, args) => {
+                panic!("expected no arguments for /{}, got {args}", cmd.command())
+            }
             InputResult::None => panic!("expected Command result for '/init'"),
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
@@ -1912,6 +2041,9 @@ mod tests {
             InputResult::Submitted(text) => {
                 panic!("expected command dispatch, but composer submitted literal text: {text}")
             }
+            InputResult::CommandWithArgs(cmd, args) => {
+                panic!("expected no arguments for /{}, got {args}", cmd.command())
+            }
             InputResult::None => panic!("expected Command result for '/mention'"),
         }
         assert!(composer.textarea.is_empty(), "composer should be cleared");
@@ -1919,6 +2051,34 @@ mod tests {
         assert_eq!(composer.textarea.text(), "@");
     }
 
+    #[test]
+    fn slash_command_arguments_are_passed_through() {
+        use crossterm::event::KeyCode;
+        use crossterm::event::KeyEvent;
+        use crossterm::event::KeyModifiers;
+
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+
+        let typed: Vec<char> = "/log level debug".chars().collect();
+        type_chars_humanlike(&mut composer, &typed);
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(
+            result,
+            InputResult::CommandWithArgs(SlashCommand::Log, "level debug".to_string())
+        );
+        assert!(composer.textarea.is_empty(), "composer should be cleared");
+    }
+
     #[test]
     fn test_multiple_pastes_submission() {
         use crossterm::event::KeyCode;
@@ -2305,6 +2465,27 @@ mod tests {
         assert_eq!(imgs, vec![tmp_path]);
     }
 
+    #[test]
+    fn pasting_document_path_attaches_document() {
+        let tmp = tempdir().expect("create TempDir");
+        let tmp_path: PathBuf = tmp.path().join("spec.pdf");
+        std::fs::write(&tmp_path, b"%PDF-1.4").expect("failed to write temp pdf");
+
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+
+        assert!(composer.handle_paste(tmp_path.to_string_lossy().to_string()));
+        assert_eq!(composer.textarea.text(), "[document spec.pdf] ");
+        assert_eq!(composer.take_recent_submission_images(), vec![tmp_path]);
+    }
+
     #[test]
     fn selecting_custom_prompt_submits_file_contents() {
         let prompt_text = "Hello from saved prompt";
@@ -2337,6 +2518,52 @@ mod tests {
         assert_eq!(InputResult::Submitted(prompt_text.to_string()), result);
     }
 
+    #[test]
+    fn selecting_mcp_prompt_parses_its_arguments() {
+        let (tx, _rx) = unbounded_channel::<AppEvent>();
+        let sender = AppEventSender::new(tx);
+        let mut composer = ChatComposer::new(
+            true,
+            sender,
+            false,
+            "Ask Codex to do anything".to_string(),
+            false,
+        );
+        let argument = |name: &str| mcp_types::PromptArgument {
+            description: None,
+            name: name.to_string(),
+            required: Some(true),
+            title: None,
+        };
+        composer.set_mcp_prompts(vec![McpPromptCommand {
+            server: "db".to_string(),
+            prompt: mcp_types::Prompt {
+                arguments: Some(vec![argument("query"), argument("schema")]),
+                description: None,
+                name: "explain".to_string(),
+                title: None,
+            },
+        }]);
+
+        let typed: Vec<char> = "/db:explain schema=public select 1".chars().collect();
+        type_chars_humanlike(&mut composer, &typed);
+        let (result, _needs_redraw) =
+            composer.handle_key_event(KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+
+        assert_eq!(
+            result,
+            InputResult::McpPrompt {
+                server: "db".to_string(),
+                name: "explain".to_string(),
+                arguments: HashMap::from([
+                    ("query".to_string(), "select 1".to_string()),
+                    ("schema".to_string(), "public".to_string()),
+                ]),
+                text: "/db:explain schema=public select 1".to_string(),
+            }
+        );
+    }
+
     #[test]
     fn burst_paste_fast_small_buffers_and_flushes_on_stop() {
         use crossterm::event::KeyCode;
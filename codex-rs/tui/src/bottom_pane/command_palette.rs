@@ -0,0 +1,312 @@
+use codex_common::fuzzy_match::fuzzy_match;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+use crate::slash_command::SlashCommand;
+
+use super::BottomPane;
+use super::CancellationEvent;
+use super::bottom_pane_view::BottomPaneView;
+use super::list_selection_view::SelectionAction;
+use super::popup_consts::MAX_POPUP_ROWS;
+use super::scroll_state::ScrollState;
+use super::selection_popup_common::GenericDisplayRow;
+use super::selection_popup_common::render_rows;
+
+/// What happens when a palette entry is chosen.
+pub(crate) enum PaletteAction {
+    /// Run a slash command as if it had been typed.
+    Command(SlashCommand),
+    /// Replace the composer text, e.g. with `/prompt-name `.
+    SetComposerText(String),
+    /// Insert text at the cursor, e.g. a file path.
+    InsertText(String),
+    /// Run the same actions as the corresponding selection popup item.
+    Send(Vec<SelectionAction>),
+}
+
+pub(crate) struct PaletteItem {
+    /// Group shown before the name, e.g. "command" or "file".
+    pub kind: &'static str,
+    pub name: String,
+    pub description: Option<String>,
+    pub is_current: bool,
+    pub action: PaletteAction,
+}
+
+/// Fuzzy-searchable list of everything the TUI can do (Ctrl+P).
+pub(crate) struct CommandPaletteView {
+    query: String,
+    items: Vec<PaletteItem>,
+    state: ScrollState,
+    complete: bool,
+    app_event_tx: AppEventSender,
+}
+
+impl CommandPaletteView {
+    pub(crate) fn new(items: Vec<PaletteItem>, app_event_tx: AppEventSender) -> Self {
+        let mut view = Self {
+            query: String::new(),
+            items,
+            state: ScrollState::new(),
+            complete: false,
+            app_event_tx,
+        };
+        view.refilter();
+        view
+    }
+
+    /// Indices into `items` matching the query, with highlight positions in
+    /// the displayed label. Entries keep their given order when the query is
+    /// empty and are ranked by match score otherwise.
+    fn filtered(&self) -> Vec<(usize, Option<Vec<usize>>)> {
+        let query = self.query.trim();
+        if query.is_empty() {
+            return (0..self.items.len()).map(|idx| (idx, None)).collect();
+        }
+        let mut matches: Vec<(usize, Vec<usize>, i32)> = self
+            .items
+            .iter()
+            .enumerate()
+            .filter_map(|(idx, item)| {
+                let (indices, score) = fuzzy_match(&label(item), query)?;
+                Some((idx, indices, score))
+            })
+            .collect();
+        matches.sort_by_key(|(idx, _, score)| (*score, *idx));
+        matches
+            .into_iter()
+            .map(|(idx, indices, _)| (idx, Some(indices)))
+            .collect()
+    }
+
+    fn refilter(&mut self) {
+        let len = self.filtered().len();
+        self.state.selected_idx = (len > 0).then_some(0);
+        self.state.scroll_top = 0;
+        self.state.ensure_visible(len, MAX_POPUP_ROWS.min(len));
+    }
+
+    fn move_up(&mut self) {
+        let len = self.filtered().len();
+        self.state.move_up_wrap(len);
+        self.state.ensure_visible(len, MAX_POPUP_ROWS.min(len));
+    }
+
+    fn move_down(&mut self) {
+        let len = self.filtered().len();
+        self.state.move_down_wrap(len);
+        self.state.ensure_visible(len, MAX_POPUP_ROWS.min(len));
+    }
+
+    fn accept(&mut self, pane: &mut BottomPane) {
+        self.complete = true;
+        let Some(idx) = self
+            .state
+            .selected_idx
+            .and_then(|selected| self.filtered().get(selected).map(|(idx, _)| *idx))
+        else {
+            return;
+        };
+        let item = self.items.swap_remove(idx);
+        match item.action {
+            PaletteAction::Command(cmd) => self.app_event_tx.send(AppEvent::DispatchCommand(cmd)),
+            PaletteAction::SetComposerText(text) => pane.set_composer_text(text),
+            PaletteAction::InsertText(text) => pane.insert_str(&text),
+            PaletteAction::Send(actions) => {
+                for action in actions {
+                    action(&self.app_event_tx);
+                }
+            }
+        }
+    }
+}
+
+/// Text the query is matched against and that is shown for `item`.
+fn label(item: &PaletteItem) -> String {
+    format!("{}: {}", item.kind, item.name)
+}
+
+impl BottomPaneView for CommandPaletteView {
+    fn handle_key_event(&mut self, pane: &mut BottomPane, key_event: KeyEvent) {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Up, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_up(),
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('n'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_down(),
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => self.complete = true,
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => self.accept(pane),
+            KeyEvent {
+                code: KeyCode::Backspace,
+                ..
+            } => {
+                self.query.pop();
+                self.refilter();
+            }
+            KeyEvent {
+                code: KeyCode::Char(c),
+                modifiers: KeyModifiers::NONE | KeyModifiers::SHIFT,
+                ..
+            } => {
+                self.query.push(c);
+                self.refilter();
+            }
+            _ => {}
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn on_ctrl_c(&mut self, _pane: &mut BottomPane) -> CancellationEvent {
+        self.complete = true;
+        CancellationEvent::Handled
+    }
+
+    fn desired_height(&self, _width: u16) -> u16 {
+        // Title, query, one row per visible match, footer.
+        self.filtered().len().clamp(1, MAX_POPUP_ROWS) as u16 + 3
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        if area.height < 3 || area.width == 0 {
+            return;
+        }
+        let row = |offset: u16| Rect::new(area.x, area.y + offset, area.width, 1);
+
+        Paragraph::new(Line::from(vec!["▌ ".dim(), "Command palette".bold()])).render(row(0), buf);
+        let query: Vec<Span<'static>> = if self.query.is_empty() {
+            vec!["▌ ".dim(), "> ".cyan(), "type to search".dim().italic()]
+        } else {
+            vec!["▌ ".dim(), "> ".cyan(), self.query.clone().into()]
+        };
+        Paragraph::new(Line::from(query)).render(row(1), buf);
+
+        let rows: Vec<GenericDisplayRow> = self
+            .filtered()
+            .into_iter()
+            .map(|(idx, match_indices)| {
+                let item = &self.items[idx];
+                GenericDisplayRow {
+                    name: label(item),
+                    match_indices,
+                    is_current: item.is_current,
+                    description: item.description.clone(),
+                }
+            })
+            .collect();
+        let rows_area = Rect::new(area.x, area.y + 2, area.width, area.height - 3);
+        render_rows(
+            rows_area,
+            buf,
+            &rows,
+            &self.state,
+            MAX_POPUP_ROWS,
+            false,
+            "no matches",
+        );
+
+        Paragraph::new("Enter to run, Esc to close, ↑/↓ or Ctrl+P/Ctrl+N to move".dim())
+            .render(row(area.height - 1), buf);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app_event::AppEvent;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn item(kind: &'static str, name: &str, action: PaletteAction) -> PaletteItem {
+        PaletteItem {
+            kind,
+            name: name.to_string(),
+            description: None,
+            is_current: false,
+            action,
+        }
+    }
+
+    #[test]
+    fn typing_filters_and_enter_dispatches_the_selected_command() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let mut view = CommandPaletteView::new(
+            vec![
+                item(
+                    "command",
+                    "/model",
+                    PaletteAction::Command(SlashCommand::Model),
+                ),
+                item(
+                    "command",
+                    "/diff",
+                    PaletteAction::Command(SlashCommand::Diff),
+                ),
+                item(
+                    "file",
+                    "src/diff_pane.rs",
+                    PaletteAction::InsertText("src/diff_pane.rs".to_string()),
+                ),
+            ],
+            AppEventSender::new(tx_raw),
+        );
+        assert_eq!(view.filtered().len(), 3);
+
+        for c in "model".chars() {
+            view.query.push(c);
+        }
+        view.refilter();
+        let names: Vec<String> = view
+            .filtered()
+            .into_iter()
+            .map(|(idx, _)| view.items[idx].name.clone())
+            .collect();
+        assert_eq!(names, vec!["/model".to_string()]);
+
+        let mut pane = BottomPane::new(crate::bottom_pane::BottomPaneParams {
+            app_event_tx: view.app_event_tx.clone(),
+            frame_requester: crate::tui::FrameRequester::test_dummy(),
+            has_input_focus: true,
+            enhanced_keys_supported: false,
+            placeholder_text: String::new(),
+            disable_paste_burst: false,
+            approval_timeout: None,
+        });
+        view.handle_key_event(&mut pane, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(view.is_complete());
+        match rx.try_recv() {
+            Ok(AppEvent::DispatchCommand(cmd)) => assert_eq!(cmd, SlashCommand::Model),
+            other => panic!("expected DispatchCommand, got {other:?}"),
+        }
+    }
+}
@@ -2,12 +2,17 @@
 use std::path::PathBuf;
 
 use crate::app_event_sender::AppEventSender;
+use crate::settings::SettingsSection;
 use crate::tui::FrameRequester;
 use crate::user_approval_widget::ApprovalRequest;
 use bottom_pane_view::BottomPaneView;
+use codex_core::config_types::ApprovalTimeout;
 use codex_core::protocol::TokenUsageInfo;
 use codex_file_search::FileMatch;
+use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
+use crossterm::event::KeyEventKind;
+use crossterm::event::KeyModifiers;
 use ratatui::buffer::Buffer;
 use ratatui::layout::Constraint;
 use ratatui::layout::Layout;
@@ -19,6 +24,7 @@ mod approval_modal_view;
 mod bottom_pane_view;
 mod chat_composer;
 mod chat_composer_history;
+mod command_palette;
 mod command_popup;
 mod file_search_popup;
 mod list_selection_view;
@@ -26,6 +32,7 @@ mod paste_burst;
 mod popup_consts;
 mod scroll_state;
 mod selection_popup_common;
+mod settings_view;
 mod textarea;
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
@@ -37,8 +44,12 @@ pub(crate) enum CancellationEvent {
 pub(crate) use chat_composer::ChatComposer;
 pub(crate) use chat_composer::InputResult;
 use codex_protocol::custom_prompts::CustomPrompt;
+pub(crate) use command_palette::PaletteAction;
+pub(crate) use command_palette::PaletteItem;
+pub(crate) use command_popup::McpPromptCommand;
 
 use crate::status_indicator_widget::StatusIndicatorWidget;
+use crate::subtask_tree::SubtaskTree;
 use approval_modal_view::ApprovalModalView;
 pub(crate) use list_selection_view::SelectionAction;
 pub(crate) use list_selection_view::SelectionItem;
@@ -64,6 +75,13 @@ pub(crate) struct BottomPane {
     status: Option<StatusIndicatorWidget>,
     /// Queued user messages to show under the status indicator.
     queued_user_messages: Vec<String>,
+    /// Whether the sub-task tree under the status indicator shows every
+    /// sub-task. Toggled with Ctrl+G and kept across tasks.
+    subtasks_expanded: bool,
+
+    /// Configured `approval_timeout_secs`, shown as a countdown on approval
+    /// prompts.
+    approval_timeout: Option<ApprovalTimeout>,
 }
 
 pub(crate) struct BottomPaneParams {
@@ -73,6 +91,7 @@ pub(crate) struct BottomPaneParams {
     pub(crate) enhanced_keys_supported: bool,
     pub(crate) placeholder_text: String,
     pub(crate) disable_paste_burst: bool,
+    pub(crate) approval_timeout: Option<ApprovalTimeout>,
 }
 
 impl BottomPane {
@@ -95,7 +114,9 @@ impl BottomPane {
             ctrl_c_quit_hint: false,
             status: None,
             queued_user_messages: Vec::new(),
+            subtasks_expanded: false,
             esc_backtrack_hint: false,
+            approval_timeout: params.approval_timeout,
         }
     }
 
@@ -170,7 +191,7 @@ impl BottomPane {
         } else {
             // If a task is running and a status line is visible, allow Esc to
             // send an interrupt even while the composer has focus.
-            if matches!(key_event.code, crossterm::event::KeyCode::Esc)
+            if matches!(key_event.code, KeyCode::Esc)
                 && self.is_task_running
                 && let Some(status) = &self.status
             {
@@ -179,6 +200,20 @@ impl BottomPane {
                 self.request_redraw();
                 return InputResult::None;
             }
+            if let KeyEvent {
+                code: KeyCode::Char('g'),
+                modifiers: KeyModifiers::CONTROL,
+                kind: KeyEventKind::Press,
+                ..
+            } = key_event
+                && let Some(status) = self.status.as_mut()
+                && status.has_subtasks()
+            {
+                self.subtasks_expanded = !self.subtasks_expanded;
+                status.set_subtasks_expanded(self.subtasks_expanded);
+                self.request_redraw();
+                return InputResult::None;
+            }
             let (input_result, needs_redraw) = self.composer.handle_key_event(key_event);
             if needs_redraw {
                 self.request_redraw();
@@ -309,6 +344,7 @@ impl BottomPane {
             }
             if let Some(status) = self.status.as_mut() {
                 status.set_queued_messages(self.queued_user_messages.clone());
+                status.set_subtasks_expanded(self.subtasks_expanded);
             }
             self.request_redraw();
         } else {
@@ -336,6 +372,25 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Show the fuzzy command palette over the composer.
+    pub(crate) fn show_command_palette(&mut self, items: Vec<PaletteItem>) {
+        let view = command_palette::CommandPaletteView::new(items, self.app_event_tx.clone());
+        self.active_view = Some(Box::new(view));
+        self.request_redraw();
+    }
+
+    /// Show the `/settings` screen over the composer.
+    pub(crate) fn show_settings(&mut self, sections: Vec<SettingsSection>) {
+        let view = settings_view::SettingsView::new(sections, self.app_event_tx.clone());
+        self.active_view = Some(Box::new(view));
+        self.request_redraw();
+    }
+
+    /// Whether a modal or popup view is currently replacing the composer.
+    pub(crate) fn has_active_view(&self) -> bool {
+        self.active_view.is_some()
+    }
+
     /// Update the queued messages shown under the status header.
     pub(crate) fn set_queued_user_messages(&mut self, queued: Vec<String>) {
         self.queued_user_messages = queued.clone();
@@ -345,12 +400,34 @@ impl BottomPane {
         self.request_redraw();
     }
 
+    /// Apply a sub-task event to the tree under the status indicator.
+    pub(crate) fn update_subtasks(&mut self, update: impl FnOnce(&mut SubtaskTree)) {
+        if let Some(status) = self.status.as_mut() {
+            update(status.subtasks_mut());
+            self.request_redraw();
+        }
+    }
+
     /// Update custom prompts available for the slash popup.
     pub(crate) fn set_custom_prompts(&mut self, prompts: Vec<CustomPrompt>) {
         self.composer.set_custom_prompts(prompts);
         self.request_redraw();
     }
 
+    pub(crate) fn custom_prompts(&self) -> &[CustomPrompt] {
+        self.composer.custom_prompts()
+    }
+
+    /// Update the MCP prompts offered as `/server:prompt` commands.
+    pub(crate) fn set_mcp_prompts(&mut self, prompts: Vec<McpPromptCommand>) {
+        self.composer.set_mcp_prompts(prompts);
+        self.request_redraw();
+    }
+
+    pub(crate) fn mcp_prompts(&self) -> &[McpPromptCommand] {
+        self.composer.mcp_prompts()
+    }
+
     pub(crate) fn composer_is_empty(&self) -> bool {
         self.composer.is_empty()
     }
@@ -388,12 +465,26 @@ impl BottomPane {
         };
 
         // Otherwise create a new approval modal overlay.
-        let modal = ApprovalModalView::new(request, self.app_event_tx.clone());
+        let modal =
+            ApprovalModalView::new(request, self.app_event_tx.clone(), self.approval_timeout);
         self.pause_status_timer_for_modal();
         self.active_view = Some(Box::new(modal));
         self.request_redraw()
     }
 
+    /// Called when core resolved the pending approval request on the user's
+    /// behalf because it was not answered in time.
+    pub(crate) fn on_approval_timed_out(&mut self) {
+        if let Some(view) = self.active_view.as_mut() {
+            view.on_approval_timed_out();
+            if view.is_complete() {
+                self.active_view = None;
+                self.on_active_view_complete();
+            }
+        }
+        self.request_redraw();
+    }
+
     fn on_active_view_complete(&mut self) {
         self.resume_status_timer_after_modal();
     }
@@ -479,6 +570,9 @@ impl WidgetRef for &BottomPane {
         // When a modal view is active, it owns the whole content area.
         if let Some(view) = &self.active_view {
             view.render(content, buf);
+            if let Some(interval) = view.redraw_interval() {
+                self.request_redraw_in(interval);
+            }
         } else {
             // No active modal:
             // If a status indicator is active, render it above the composer.
@@ -519,6 +613,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
         pane.push_approval_request(exec_request());
         assert_eq!(CancellationEvent::Handled, pane.on_ctrl_c());
@@ -539,6 +634,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
 
         // Create an approval modal (active view).
@@ -570,6 +666,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
 
         // Start a running task so the status indicator is active above the composer.
@@ -638,6 +735,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
 
         // Begin a task: show initial status.
@@ -669,6 +767,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
 
         // Activate spinner (status view replaces composer) with no live ring.
@@ -720,6 +819,7 @@ mod tests {
             enhanced_keys_supported: false,
             placeholder_text: "Ask Codex to do anything".to_string(),
             disable_paste_burst: false,
+            approval_timeout: None,
         });
 
         pane.set_task_running(true);
@@ -0,0 +1,207 @@
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use crossterm::event::KeyModifiers;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::Widget;
+
+use crate::app_event::AppEvent;
+use crate::app_event_sender::AppEventSender;
+use crate::settings::SettingsEntry;
+use crate::settings::SettingsSection;
+
+use super::BottomPane;
+use super::CancellationEvent;
+use super::bottom_pane_view::BottomPaneView;
+
+/// `/settings`: the effective configuration by section. Entries that can be
+/// changed are selectable; choosing one opens its choices.
+pub(crate) struct SettingsView {
+    sections: Vec<SettingsSection>,
+    /// Index of the selected entry among the changeable ones.
+    selected: usize,
+    complete: bool,
+    app_event_tx: AppEventSender,
+}
+
+impl SettingsView {
+    pub(crate) fn new(sections: Vec<SettingsSection>, app_event_tx: AppEventSender) -> Self {
+        Self {
+            sections,
+            selected: 0,
+            complete: false,
+            app_event_tx,
+        }
+    }
+
+    fn changeable(&self) -> impl Iterator<Item = &SettingsEntry> {
+        self.sections
+            .iter()
+            .flat_map(|section| &section.entries)
+            .filter(|entry| entry.setting.is_some())
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        let len = self.changeable().count();
+        if len > 0 {
+            self.selected = (self.selected as isize + delta).rem_euclid(len as isize) as usize;
+        }
+    }
+
+    fn accept(&mut self) {
+        self.complete = true;
+        if let Some(setting) = self
+            .changeable()
+            .nth(self.selected)
+            .and_then(|entry| entry.setting)
+        {
+            self.app_event_tx.send(AppEvent::EditSetting(setting));
+        }
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        let name_width = self
+            .sections
+            .iter()
+            .flat_map(|section| &section.entries)
+            .map(|entry| entry.name.len())
+            .max()
+            .unwrap_or(0);
+        let mut lines = vec![Line::from(vec!["▌ ".dim(), "Settings".bold()])];
+        let mut changeable_idx = 0;
+        for section in &self.sections {
+            lines.push(Line::from(vec!["▌ ".dim(), section.title.bold()]));
+            for entry in &section.entries {
+                let name = format!("  {:name_width$}  ", entry.name);
+                let value = entry.value.clone();
+                let spans: Vec<Span<'static>> = if entry.setting.is_none() {
+                    vec!["▌ ".dim(), name.dim(), value.dim()]
+                } else if changeable_idx == self.selected {
+                    changeable_idx += 1;
+                    vec!["▌ ".dim(), name.cyan().bold(), value.cyan().bold()]
+                } else {
+                    changeable_idx += 1;
+                    vec!["▌ ".dim(), name.into(), value.into()]
+                };
+                lines.push(Line::from(spans));
+            }
+        }
+        lines.push(Line::from(
+            "Enter to change, Esc to close; changes are saved to config.toml".dim(),
+        ));
+        lines
+    }
+}
+
+impl BottomPaneView for SettingsView {
+    fn handle_key_event(&mut self, _pane: &mut BottomPane, key_event: KeyEvent) {
+        match key_event {
+            KeyEvent {
+                code: KeyCode::Up, ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('p'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_by(-1),
+            KeyEvent {
+                code: KeyCode::Down,
+                ..
+            }
+            | KeyEvent {
+                code: KeyCode::Char('n'),
+                modifiers: KeyModifiers::CONTROL,
+                ..
+            } => self.move_by(1),
+            KeyEvent {
+                code: KeyCode::Esc, ..
+            } => self.complete = true,
+            KeyEvent {
+                code: KeyCode::Enter,
+                ..
+            } => self.accept(),
+            _ => {}
+        }
+    }
+
+    fn is_complete(&self) -> bool {
+        self.complete
+    }
+
+    fn on_ctrl_c(&mut self, _pane: &mut BottomPane) -> CancellationEvent {
+        self.complete = true;
+        CancellationEvent::Handled
+    }
+
+    fn desired_height(&self, _width: u16) -> u16 {
+        self.lines().len() as u16
+    }
+
+    fn render(&self, area: Rect, buf: &mut Buffer) {
+        for (offset, line) in self.lines().into_iter().enumerate() {
+            let offset = offset as u16;
+            if offset >= area.height {
+                break;
+            }
+            Paragraph::new(line).render(Rect::new(area.x, area.y + offset, area.width, 1), buf);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::settings::Setting;
+    use pretty_assertions::assert_eq;
+    use tokio::sync::mpsc::unbounded_channel;
+
+    fn entry(name: &'static str, setting: Option<Setting>) -> SettingsEntry {
+        SettingsEntry {
+            name,
+            value: "value".to_string(),
+            setting,
+        }
+    }
+
+    #[test]
+    fn selection_skips_read_only_entries() {
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+        let mut view = SettingsView::new(
+            vec![
+                SettingsSection {
+                    title: "Model",
+                    entries: vec![
+                        entry("model", Some(Setting::Model)),
+                        entry("provider", None),
+                    ],
+                },
+                SettingsSection {
+                    title: "Permissions",
+                    entries: vec![entry("sandbox", Some(Setting::SandboxMode))],
+                },
+            ],
+            AppEventSender::new(tx_raw),
+        );
+
+        let mut pane = BottomPane::new(crate::bottom_pane::BottomPaneParams {
+            app_event_tx: view.app_event_tx.clone(),
+            frame_requester: crate::tui::FrameRequester::test_dummy(),
+            has_input_focus: true,
+            enhanced_keys_supported: false,
+            placeholder_text: String::new(),
+            disable_paste_burst: false,
+            approval_timeout: None,
+        });
+        view.handle_key_event(&mut pane, KeyEvent::new(KeyCode::Down, KeyModifiers::NONE));
+        view.handle_key_event(&mut pane, KeyEvent::new(KeyCode::Enter, KeyModifiers::NONE));
+        assert!(view.is_complete());
+        match rx.try_recv() {
+            Ok(AppEvent::EditSetting(setting)) => assert_eq!(setting, Setting::SandboxMode),
+            other => panic!("expected EditSetting, got {other:?}"),
+        }
+    }
+}
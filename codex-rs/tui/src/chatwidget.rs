@@ -11,6 +11,7 @@ use codex_core::protocol::AgentReasoningEvent;
 use codex_core::protocol::AgentReasoningRawContentDeltaEvent;
 use codex_core::protocol::AgentReasoningRawContentEvent;
 use codex_core::protocol::ApplyPatchApprovalRequestEvent;
+use codex_core::protocol::ApprovalTimedOutEvent;
 use codex_core::protocol::BackgroundEventEvent;
 use codex_core::protocol::ErrorEvent;
 use codex_core::protocol::Event;
@@ -22,10 +23,13 @@ use codex_core::protocol::InputItem;
 use codex_core::protocol::InputMessageKind;
 use codex_core::protocol::ListCustomPromptsResponseEvent;
 use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::McpResourceUpdatedEvent;
 use codex_core::protocol::McpToolCallBeginEvent;
 use codex_core::protocol::McpToolCallEndEvent;
 use codex_core::protocol::Op;
 use codex_core::protocol::PatchApplyBeginEvent;
+use codex_core::protocol::PatchGuardBlockedEvent;
+use codex_core::protocol::PromptPreviewEvent;
 use codex_core::protocol::StreamErrorEvent;
 use codex_core::protocol::TaskCompleteEvent;
 use codex_core::protocol::TokenUsage;
@@ -35,6 +39,7 @@ use codex_core::protocol::TurnDiffEvent;
 use codex_core::protocol::UserMessageEvent;
 use codex_core::protocol::WebSearchBeginEvent;
 use codex_core::protocol::WebSearchEndEvent;
+use codex_core::protocol::WorkspaceEscapeBlockedEvent;
 use codex_protocol::parse_command::ParsedCommand;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
@@ -52,12 +57,18 @@ use tracing::debug;
 
 use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
+use crate::attach_cmd::AttachedCommandOutput;
+use crate::attach_cmd::run_attached_command;
 use crate::bottom_pane::BottomPane;
 use crate::bottom_pane::BottomPaneParams;
 use crate::bottom_pane::CancellationEvent;
 use crate::bottom_pane::InputResult;
+use crate::bottom_pane::McpPromptCommand;
+use crate::bottom_pane::PaletteAction;
+use crate::bottom_pane::PaletteItem;
 use crate::bottom_pane::SelectionAction;
 use crate::bottom_pane::SelectionItem;
+use crate::clipboard_paste::PasteImageError;
 use crate::clipboard_paste::paste_image_to_temp_png;
 use crate::get_git_diff::get_git_diff;
 use crate::history_cell;
@@ -65,15 +76,22 @@ use crate::history_cell::CommandOutput;
 use crate::history_cell::ExecCell;
 use crate::history_cell::HistoryCell;
 use crate::history_cell::PatchEventType;
+use crate::settings;
+use crate::settings::Setting;
+use crate::settings::SettingChange;
 use crate::slash_command::SlashCommand;
+use crate::slash_command::built_in_slash_commands;
 use crate::tui::FrameRequester;
 // streaming internals are provided by crate::streaming and crate::markdown_stream
 use crate::user_approval_widget::ApprovalRequest;
+use crate::write_preview::preview_file_writes;
 mod interrupts;
 use self::interrupts::InterruptManager;
 mod agent;
 use self::agent::spawn_agent;
 use self::agent::spawn_agent_from_existing;
+#[cfg(unix)]
+use self::agent::spawn_remote_agent;
 use crate::streaming::controller::AppEventHistorySink;
 use crate::streaming::controller::StreamController;
 use codex_common::approval_presets::ApprovalPreset;
@@ -87,6 +105,9 @@ use codex_core::protocol_config_types::ReasoningEffort as ReasoningEffortConfig;
 use codex_file_search::FileMatch;
 use codex_protocol::mcp_protocol::ConversationId;
 
+/// How many recently edited files the command palette offers.
+const MAX_RECENT_FILES: usize = 10;
+
 // Track information about an in-flight exec command.
 struct RunningCommand {
     command: Vec<String>,
@@ -130,6 +151,10 @@ pub(crate) struct ChatWidget {
     suppress_session_configured_redraw: bool,
     // User messages queued while a turn is in progress
     queued_user_messages: VecDeque<UserMessage>,
+    // Files edited this session, most recent first, offered by the command palette
+    recent_files: VecDeque<PathBuf>,
+    // Command output from `/attach-cmd`, sent ahead of the next user message
+    attached_command_outputs: Vec<AttachedCommandOutput>,
 }
 
 struct UserMessage {
@@ -332,6 +357,31 @@ impl ChatWidget {
         );
     }
 
+    fn on_approval_timed_out(&mut self, ev: ApprovalTimedOutEvent) {
+        self.bottom_pane.on_approval_timed_out();
+        self.add_to_history(history_cell::new_approval_timed_out(
+            ev.timeout_secs,
+            ev.action,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_workspace_escape_blocked(&mut self, ev: WorkspaceEscapeBlockedEvent) {
+        self.add_to_history(history_cell::new_workspace_escape_blocked(
+            ev.escapes,
+            &self.config.cwd,
+        ));
+        self.request_redraw();
+    }
+
+    fn on_patch_guard_blocked(&mut self, ev: PatchGuardBlockedEvent) {
+        self.add_to_history(history_cell::new_patch_guard_blocked(
+            ev.violations,
+            &self.config.cwd,
+        ));
+        self.request_redraw();
+    }
+
     fn on_exec_command_begin(&mut self, ev: ExecCommandBeginEvent) {
         self.flush_answer_stream_with_separator();
         let ev2 = ev.clone();
@@ -346,6 +396,15 @@ impl ChatWidget {
     }
 
     fn on_patch_apply_begin(&mut self, event: PatchApplyBeginEvent) {
+        for path in event.changes.keys() {
+            let path = path
+                .strip_prefix(&self.config.cwd)
+                .unwrap_or(path)
+                .to_path_buf();
+            self.recent_files.retain(|recent| recent != &path);
+            self.recent_files.push_front(path);
+        }
+        self.recent_files.truncate(MAX_RECENT_FILES);
         self.add_to_history(history_cell::new_patch_event(
             PatchEventType::ApplyBegin {
                 auto_approved: event.auto_approved,
@@ -523,6 +582,14 @@ impl ChatWidget {
         self.flush_answer_stream_with_separator();
         // Emit the proposed command into history (like proposed patches)
         self.add_to_history(history_cell::new_proposed_command(&ev.command));
+        // For commands that plainly write files, show what they would write.
+        if let Some(changes) = preview_file_writes(&ev.command, &ev.cwd) {
+            self.add_to_history(history_cell::new_patch_event(
+                PatchEventType::ApprovalRequest,
+                changes,
+                &self.config.cwd,
+            ));
+        }
 
         let request = ApprovalRequest::Exec {
             id,
@@ -648,6 +715,7 @@ impl ChatWidget {
                 enhanced_keys_supported,
                 placeholder_text: placeholder,
                 disable_paste_burst: config.disable_paste_burst,
+                approval_timeout: config.approval_timeout,
             }),
             active_exec_cell: None,
             config: config.clone(),
@@ -664,6 +732,8 @@ impl ChatWidget {
             full_reasoning_buffer: String::new(),
             conversation_id: None,
             queued_user_messages: VecDeque::new(),
+            attached_command_outputs: Vec::new(),
+            recent_files: VecDeque::new(),
             show_welcome_banner: true,
             suppress_session_configured_redraw: false,
         }
@@ -700,6 +770,7 @@ impl ChatWidget {
                 enhanced_keys_supported,
                 placeholder_text: placeholder,
                 disable_paste_burst: config.disable_paste_burst,
+                approval_timeout: config.approval_timeout,
             }),
             active_exec_cell: None,
             config: config.clone(),
@@ -716,11 +787,65 @@ impl ChatWidget {
             full_reasoning_buffer: String::new(),
             conversation_id: None,
             queued_user_messages: VecDeque::new(),
+            attached_command_outputs: Vec::new(),
+            recent_files: VecDeque::new(),
             show_welcome_banner: false,
             suppress_session_configured_redraw: true,
         }
     }
 
+    /// Create a ChatWidget attached to a session running in another process
+    /// (`codex attach`), connected through `stream`.
+    #[cfg(unix)]
+    pub(crate) fn new_attached(common: ChatWidgetInit, stream: tokio::net::UnixStream) -> Self {
+        let ChatWidgetInit {
+            config,
+            frame_requester,
+            app_event_tx,
+            initial_prompt,
+            initial_images,
+            enhanced_keys_supported,
+        } = common;
+        let mut rng = rand::rng();
+        let placeholder = EXAMPLE_PROMPTS[rng.random_range(0..EXAMPLE_PROMPTS.len())].to_string();
+
+        let codex_op_tx = spawn_remote_agent(stream, app_event_tx.clone());
+
+        Self {
+            app_event_tx: app_event_tx.clone(),
+            frame_requester: frame_requester.clone(),
+            codex_op_tx,
+            bottom_pane: BottomPane::new(BottomPaneParams {
+                frame_requester,
+                app_event_tx,
+                has_input_focus: true,
+                enhanced_keys_supported,
+                placeholder_text: placeholder,
+                disable_paste_burst: config.disable_paste_burst,
+                approval_timeout: config.approval_timeout,
+            }),
+            active_exec_cell: None,
+            config: config.clone(),
+            initial_user_message: create_initial_user_message(
+                initial_prompt.unwrap_or_default(),
+                initial_images,
+            ),
+            token_info: None,
+            stream: StreamController::new(config),
+            running_commands: HashMap::new(),
+            task_complete_pending: false,
+            interrupts: InterruptManager::new(),
+            reasoning_buffer: String::new(),
+            full_reasoning_buffer: String::new(),
+            conversation_id: None,
+            queued_user_messages: VecDeque::new(),
+            attached_command_outputs: Vec::new(),
+            recent_files: VecDeque::new(),
+            show_welcome_banner: false,
+            suppress_session_configured_redraw: false,
+        }
+    }
+
     pub fn desired_height(&self, width: u16) -> u16 {
         self.bottom_pane.desired_height(width)
             + self
@@ -746,9 +871,7 @@ impl ChatWidget {
                 kind: KeyEventKind::Press,
                 ..
             } => {
-                if let Ok((path, info)) = paste_image_to_temp_png() {
-                    self.attach_image(path, info.width, info.height, info.encoded_format.label());
-                }
+                self.paste_clipboard_image();
                 return;
             }
             other if other.kind == KeyEventKind::Press => {
@@ -789,6 +912,24 @@ impl ChatWidget {
                     InputResult::Command(cmd) => {
                         self.dispatch_command(cmd);
                     }
+                    InputResult::CommandWithArgs(cmd, args) => {
+                        self.dispatch_command_with_args(cmd, args);
+                    }
+                    InputResult::McpPrompt {
+                        server,
+                        name,
+                        arguments,
+                        text,
+                    } => {
+                        self.submit_op(Op::UserInput {
+                            items: vec![InputItem::McpPrompt {
+                                server,
+                                name,
+                                arguments,
+                            }],
+                        });
+                        self.add_to_history(history_cell::new_user_prompt(text));
+                    }
                     InputResult::None => {}
                 }
             }
@@ -810,7 +951,103 @@ impl ChatWidget {
         self.request_redraw();
     }
 
-    fn dispatch_command(&mut self, cmd: SlashCommand) {
+    /// Commands that take arguments handle them here; the rest ignore them.
+    fn dispatch_command_with_args(&mut self, cmd: SlashCommand, args: String) {
+        match cmd {
+            SlashCommand::Log => self.handle_log_command(Some(&args)),
+            SlashCommand::Mcp => self.handle_mcp_command(&args),
+            SlashCommand::AttachCmd => self.handle_attach_cmd(args),
+            cmd => self.dispatch_command(cmd),
+        }
+    }
+
+    /// `/attach-cmd <command>` runs the command in the background; its output
+    /// arrives as [`AppEvent::CommandOutputAttached`].
+    fn handle_attach_cmd(&mut self, command: String) {
+        let command = command.trim().to_string();
+        if command.is_empty() {
+            self.add_error_message("Usage: /attach-cmd <command>".to_string());
+            return;
+        }
+        self.add_info_message(format!("Running `{command}`…"), None);
+        let tx = self.app_event_tx.clone();
+        let cwd = self.config.cwd.clone();
+        tokio::spawn(async move {
+            let attached = run_attached_command(command, &cwd).await;
+            tx.send(AppEvent::CommandOutputAttached(attached));
+        });
+    }
+
+    pub(crate) fn on_command_output_attached(&mut self, attached: AttachedCommandOutput) {
+        let status = match attached.exit_code {
+            Some(code) => format!("exit code {code}"),
+            None => "no exit code".to_string(),
+        };
+        self.add_info_message(
+            format!(
+                "Attached output of `{}` ({status}, {} bytes) to your next message",
+                attached.command,
+                attached.output.len()
+            ),
+            None,
+        );
+        self.attached_command_outputs.push(attached);
+    }
+
+    /// `/mcp subscribe <server>:<uri>` asks the server to report changes to
+    /// a resource.
+    fn handle_mcp_command(&mut self, args: &str) {
+        let target = args
+            .strip_prefix("subscribe")
+            .map(str::trim)
+            .and_then(|target| target.strip_prefix('@').or(Some(target)))
+            .and_then(|target| target.split_once(':'));
+        match target {
+            Some((server, uri)) if !server.is_empty() && !uri.is_empty() => {
+                self.submit_op(Op::SubscribeMcpResource {
+                    server: server.to_string(),
+                    uri: uri.to_string(),
+                });
+            }
+            _ => self.add_error_message("Usage: /mcp subscribe <server>:<uri>".to_string()),
+        }
+    }
+
+    /// `/log` shows the active log filter; `/log level <directives>` replaces
+    /// it without restarting the session.
+    fn handle_log_command(&mut self, args: Option<&str>) {
+        let log_file = codex_core::config::log_dir(&self.config)
+            .map(|dir| dir.join("codex-tui.log").display().to_string())
+            .unwrap_or_default();
+        match args.map(|args| args.split_once(char::is_whitespace)) {
+            None => {
+                let filter = codex_common::logging::current_filter()
+                    .unwrap_or_else(|| "(set by RUST_LOG)".to_string());
+                self.add_info_message(
+                    format!("Log filter: {filter}"),
+                    Some(format!(
+                        "Writing to {log_file}. Change with /log level <level or target=level,...>."
+                    )),
+                );
+            }
+            Some(Some(("level", directives))) => {
+                let directives = directives.trim();
+                match codex_common::logging::set_filter(directives) {
+                    Ok(()) => self.add_info_message(
+                        format!("Log filter set to {directives}"),
+                        Some(format!("Writing to {log_file}.")),
+                    ),
+                    Err(e) => self.add_error_message(format!("Invalid log filter: {e}")),
+                }
+            }
+            Some(_) => {
+                self.add_error_message("Usage: /log level <level or target=level,...>".to_string());
+            }
+        }
+        self.request_redraw();
+    }
+
+    pub(crate) fn dispatch_command(&mut self, cmd: SlashCommand) {
         if !cmd.available_during_task() && self.bottom_pane.is_task_running() {
             let message = format!(
                 "'/{}' is disabled while a task is in progress.",
@@ -838,14 +1075,18 @@ impl ChatWidget {
             SlashCommand::Approvals => {
                 self.open_approvals_popup();
             }
+            SlashCommand::Settings => {
+                self.open_settings();
+            }
             SlashCommand::Quit => {
-                self.app_event_tx.send(AppEvent::ExitRequest);
+                // Shut the session down cleanly; we exit on ShutdownComplete.
+                self.submit_op(Op::Shutdown);
             }
             SlashCommand::Logout => {
                 if let Err(e) = codex_core::auth::logout(&self.config.codex_home) {
                     tracing::error!("failed to logout: {e}");
                 }
-                self.app_event_tx.send(AppEvent::ExitRequest);
+                self.submit_op(Op::Shutdown);
             }
             SlashCommand::Diff => {
                 self.add_diff_in_progress();
@@ -873,6 +1114,15 @@ impl ChatWidget {
             SlashCommand::Mcp => {
                 self.add_mcp_output();
             }
+            SlashCommand::Prompt => {
+                self.submit_op(Op::GetPrompt);
+            }
+            SlashCommand::AttachCmd => {
+                self.add_error_message("Usage: /attach-cmd <command>".to_string());
+            }
+            SlashCommand::Log => {
+                self.handle_log_command(None);
+            }
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => {
                 use codex_core::protocol::EventMsg;
@@ -915,9 +1165,33 @@ impl ChatWidget {
     }
 
     pub(crate) fn handle_paste(&mut self, text: String) {
+        // Terminals paste an image-only clipboard as empty text, so look for
+        // the image ourselves.
+        if text.trim().is_empty() && self.paste_clipboard_image() {
+            return;
+        }
         self.bottom_pane.handle_paste(text);
     }
 
+    /// Attach the clipboard's image, if it holds one. Having no image or no
+    /// clipboard is not an error; failing to read or save the image is.
+    fn paste_clipboard_image(&mut self) -> bool {
+        match paste_image_to_temp_png() {
+            Ok((path, info)) => {
+                self.attach_image(path, info.width, info.height, info.encoded_format.label());
+                true
+            }
+            // No image, or no clipboard to read one from (e.g. over SSH or
+            // without a display server): paste the text as usual.
+            Err(PasteImageError::NoImage(_) | PasteImageError::ClipboardUnavailable(_)) => false,
+            Err(err) => {
+                tracing::warn!("failed to paste clipboard image: {err}");
+                self.add_error_message(format!("Failed to paste image: {err}"));
+                false
+            }
+        }
+    }
+
     // Returns true if caller should skip rendering this frame (a future frame is scheduled).
     pub(crate) fn handle_paste_burst_tick(&mut self, frame_requester: FrameRequester) -> bool {
         if self.bottom_pane.flush_paste_burst_if_due() {
@@ -957,16 +1231,30 @@ impl ChatWidget {
 
     fn submit_user_message(&mut self, user_message: UserMessage) {
         let UserMessage { text, image_paths } = user_message;
-        let mut items: Vec<InputItem> = Vec::new();
+        let mut items: Vec<InputItem> = self
+            .attached_command_outputs
+            .drain(..)
+            .map(|attached| attached.to_input_item())
+            .collect();
 
         if !text.is_empty() {
             items.push(InputItem::Text { text: text.clone() });
         }
 
         for path in image_paths {
+            if codex_core::documents::is_document(&path) {
+                items.push(InputItem::LocalFile { path });
+                continue;
+            }
             items.push(InputItem::LocalImage { path });
         }
 
+        for (server, uri) in crate::mcp_mentions::mcp_resource_mentions(&text, |server| {
+            self.config.mcp_servers.contains_key(server)
+        }) {
+            items.push(InputItem::McpResource { server, uri });
+        }
+
         if items.is_empty() {
             return;
         }
@@ -1045,7 +1333,7 @@ impl ChatWidget {
             EventMsg::TaskStarted(_) => self.on_task_started(),
             EventMsg::TaskComplete(TaskCompleteEvent { .. }) => self.on_task_complete(),
             EventMsg::TokenCount(ev) => self.set_token_info(ev.info),
-            EventMsg::Error(ErrorEvent { message }) => self.on_error(message),
+            EventMsg::Error(ErrorEvent { message, .. }) => self.on_error(message),
             EventMsg::TurnAborted(ev) => match ev.reason {
                 TurnAbortReason::Interrupted => {
                     self.on_interrupted_turn();
@@ -1055,10 +1343,20 @@ impl ChatWidget {
                 }
             },
             EventMsg::PlanUpdate(update) => self.on_plan_update(update),
+            EventMsg::SubtaskBegin(ev) => {
+                self.bottom_pane.update_subtasks(|tree| tree.on_begin(ev))
+            }
+            EventMsg::SubtaskProgress(ev) => self
+                .bottom_pane
+                .update_subtasks(|tree| tree.on_progress(ev)),
+            EventMsg::SubtaskEnd(ev) => self.bottom_pane.update_subtasks(|tree| tree.on_end(ev)),
             EventMsg::ExecApprovalRequest(ev) => {
                 // For replayed events, synthesize an empty id (these should not occur).
                 self.on_exec_approval_request(id.unwrap_or_default(), ev)
             }
+            EventMsg::ApprovalTimedOut(ev) => self.on_approval_timed_out(ev),
+            EventMsg::WorkspaceEscapeBlocked(ev) => self.on_workspace_escape_blocked(ev),
+            EventMsg::PatchGuardBlocked(ev) => self.on_patch_guard_blocked(ev),
             EventMsg::ApplyPatchApprovalRequest(ev) => {
                 self.on_apply_patch_approval_request(id.unwrap_or_default(), ev)
             }
@@ -1074,6 +1372,8 @@ impl ChatWidget {
             EventMsg::GetHistoryEntryResponse(ev) => self.on_get_history_entry_response(ev),
             EventMsg::McpListToolsResponse(ev) => self.on_list_mcp_tools(ev),
             EventMsg::ListCustomPromptsResponse(ev) => self.on_list_custom_prompts(ev),
+            EventMsg::McpResourceUpdated(ev) => self.on_mcp_resource_updated(ev),
+            EventMsg::PromptPreview(ev) => self.on_prompt_preview(ev),
             EventMsg::ShutdownComplete => self.on_shutdown_complete(),
             EventMsg::TurnDiff(TurnDiffEvent { unified_diff }) => self.on_turn_diff(unified_diff),
             EventMsg::BackgroundEvent(BackgroundEventEvent { message }) => {
@@ -1097,7 +1397,9 @@ impl ChatWidget {
     fn on_user_message_event(&mut self, event: UserMessageEvent) {
         match event.kind {
             Some(InputMessageKind::EnvironmentContext)
-            | Some(InputMessageKind::UserInstructions) => {
+            | Some(InputMessageKind::UserInstructions)
+            | Some(InputMessageKind::RepoMap)
+            | Some(InputMessageKind::FileChanges) => {
                 // Skip XML‑wrapped context blocks in the transcript.
             }
             Some(InputMessageKind::Plain) | None => {
@@ -1169,6 +1471,16 @@ impl ChatWidget {
 
     /// Open a popup to choose the model preset (model + reasoning effort).
     pub(crate) fn open_model_popup(&mut self) {
+        let items = self.model_selection_items();
+        self.bottom_pane.show_selection_view(
+            "Select model and reasoning level".to_string(),
+            Some("Switch between OpenAI models for this and future Codex CLI session".to_string()),
+            Some("Press Enter to confirm, Esc to go back, Ctrl+S to save".to_string()),
+            items,
+        );
+    }
+
+    fn model_selection_items(&self) -> Vec<SelectionItem> {
         let current_model = self.config.model.clone();
         let current_effort = self.config.model_reasoning_effort;
         let presets: Vec<OwnedModelPreset> = load_model_presets_owned();
@@ -1211,17 +1523,21 @@ impl ChatWidget {
                 actions,
             });
         }
+        items
+    }
 
+    /// Open a popup to choose the approvals mode (ask for approval policy + sandbox policy).
+    pub(crate) fn open_approvals_popup(&mut self) {
+        let items = self.approval_selection_items();
         self.bottom_pane.show_selection_view(
-            "Select model and reasoning level".to_string(),
-            Some("Switch between OpenAI models for this and future Codex CLI session".to_string()),
-            Some("Press Enter to confirm, Esc to go back, Ctrl+S to save".to_string()),
+            "Select Approval Mode".to_string(),
+            None,
+            Some("Press Enter to confirm or Esc to go back".to_string()),
             items,
         );
     }
 
-    /// Open a popup to choose the approvals mode (ask for approval policy + sandbox policy).
-    pub(crate) fn open_approvals_popup(&mut self) {
+    fn approval_selection_items(&self) -> Vec<SelectionItem> {
         let current_approval = self.config.approval_policy;
         let current_sandbox = self.config.sandbox_policy.clone();
         let mut items: Vec<SelectionItem> = Vec::new();
@@ -1252,15 +1568,149 @@ impl ChatWidget {
                 actions,
             });
         }
+        items
+    }
 
+    /// Open the `/settings` screen for the current configuration.
+    pub(crate) fn open_settings(&mut self) {
+        self.bottom_pane
+            .show_settings(settings::settings_sections(&self.config));
+    }
+
+    /// Open a popup with the values `setting` can take.
+    pub(crate) fn open_setting_choices(&mut self, setting: Setting) {
+        let apply = |change: SettingChange| -> Vec<SelectionAction> {
+            vec![Box::new(move |tx| {
+                tx.send(AppEvent::ApplySetting(change.clone()));
+            })]
+        };
+        let (title, items): (&str, Vec<SelectionItem>) = match setting {
+            Setting::Model => (
+                "Model and reasoning effort",
+                load_model_presets_owned()
+                    .into_iter()
+                    .map(|preset| SelectionItem {
+                        is_current: preset.model == self.config.model
+                            && preset.effort == self.config.model_reasoning_effort,
+                        name: preset.label,
+                        description: Some(preset.description),
+                        actions: apply(SettingChange::Model {
+                            model: preset.model,
+                            effort: preset.effort,
+                        }),
+                    })
+                    .collect(),
+            ),
+            Setting::ApprovalPolicy => (
+                "Approval policy",
+                settings::approval_policy_choices()
+                    .into_iter()
+                    .map(|(policy, description)| SelectionItem {
+                        name: policy.to_string(),
+                        description: Some(description.to_string()),
+                        is_current: policy == self.config.approval_policy,
+                        actions: apply(SettingChange::ApprovalPolicy(policy)),
+                    })
+                    .collect(),
+            ),
+            Setting::SandboxMode => (
+                "Sandbox",
+                settings::sandbox_mode_choices()
+                    .into_iter()
+                    .map(|(mode, description)| SelectionItem {
+                        name: mode.to_string(),
+                        description: Some(description.to_string()),
+                        is_current: mode == settings::sandbox_mode(&self.config.sandbox_policy),
+                        actions: apply(SettingChange::SandboxMode(mode)),
+                    })
+                    .collect(),
+            ),
+        };
         self.bottom_pane.show_selection_view(
-            "Select Approval Mode".to_string(),
-            None,
-            Some("Press Enter to confirm or Esc to go back".to_string()),
+            title.to_string(),
+            Some("Applies to this session and is saved to config.toml".to_string()),
+            // Esc closes the popup; it does not return to /settings.
+            Some("Press Enter to confirm or Esc to close".to_string()),
             items,
         );
     }
 
+    /// Open the command palette with every slash command, custom prompt,
+    /// model and approval preset, recently edited file, and the given
+    /// `(rollout path, preview)` sessions.
+    pub(crate) fn open_command_palette(&mut self, sessions: Vec<(PathBuf, String)>) {
+        if self.bottom_pane.has_active_view() {
+            return;
+        }
+        let mut items: Vec<PaletteItem> = built_in_slash_commands()
+            .into_iter()
+            .map(|(name, cmd)| PaletteItem {
+                kind: "command",
+                name: format!("/{name}"),
+                description: Some(cmd.description().to_string()),
+                is_current: false,
+                action: PaletteAction::Command(cmd),
+            })
+            .collect();
+        items.extend(
+            self.bottom_pane
+                .custom_prompts()
+                .iter()
+                .map(|prompt| PaletteItem {
+                    kind: "prompt",
+                    name: format!("/{}", prompt.name),
+                    description: Some("send saved prompt".to_string()),
+                    is_current: false,
+                    action: PaletteAction::SetComposerText(format!("/{} ", prompt.name)),
+                }),
+        );
+        items.extend(
+            self.bottom_pane
+                .mcp_prompts()
+                .iter()
+                .map(|prompt| PaletteItem {
+                    kind: "prompt",
+                    name: format!("/{}", prompt.command()),
+                    description: prompt.prompt.description.clone(),
+                    is_current: false,
+                    action: PaletteAction::SetComposerText(format!("/{} ", prompt.command())),
+                }),
+        );
+        let settings = self
+            .model_selection_items()
+            .into_iter()
+            .map(|item| ("model", item))
+            .chain(
+                self.approval_selection_items()
+                    .into_iter()
+                    .map(|item| ("approvals", item)),
+            );
+        items.extend(settings.map(|(group, item)| PaletteItem {
+            kind: "setting",
+            name: format!("{group} → {}", item.name),
+            description: item.description,
+            is_current: item.is_current,
+            action: PaletteAction::Send(item.actions),
+        }));
+        items.extend(self.recent_files.iter().map(|path| PaletteItem {
+            kind: "file",
+            name: path.display().to_string(),
+            description: Some("insert path".to_string()),
+            is_current: false,
+            action: PaletteAction::InsertText(path.display().to_string()),
+        }));
+        items.extend(sessions.into_iter().map(|(path, preview)| PaletteItem {
+            kind: "session",
+            name: preview,
+            description: Some("resume".to_string()),
+            is_current: false,
+            action: PaletteAction::Send(vec![Box::new(move |tx| {
+                tx.send(AppEvent::ResumeSession(path.clone()));
+            })]),
+        }));
+        self.bottom_pane.show_command_palette(items);
+    }
+
     /// Set the approval policy in the widget's config copy.
     pub(crate) fn set_approval_policy(&mut self, policy: AskForApproval) {
         self.config.approval_policy = policy;
@@ -1319,6 +1769,10 @@ impl ChatWidget {
         self.submit_op(Op::Shutdown);
     }
 
+    pub(crate) fn has_active_view(&self) -> bool {
+        self.bottom_pane.has_active_view()
+    }
+
     pub(crate) fn composer_is_empty(&self) -> bool {
         self.bottom_pane.composer_is_empty()
     }
@@ -1356,7 +1810,7 @@ impl ChatWidget {
     }
 
     fn on_list_mcp_tools(&mut self, ev: McpListToolsResponseEvent) {
-        self.add_to_history(history_cell::new_mcp_tools_output(&self.config, ev.tools));
+        self.add_to_history(history_cell::new_mcp_tools_output(&self.config, ev));
     }
 
     fn on_list_custom_prompts(&mut self, ev: ListCustomPromptsResponseEvent) {
@@ -1364,6 +1818,29 @@ impl ChatWidget {
         debug!("received {len} custom prompts");
         // Forward to bottom pane so the slash popup can show them now.
         self.bottom_pane.set_custom_prompts(ev.custom_prompts);
+        let mcp_prompts = ev
+            .mcp_prompts
+            .into_iter()
+            .flat_map(|(server, prompts)| {
+                prompts.into_iter().map(move |prompt| McpPromptCommand {
+                    server: server.clone(),
+                    prompt,
+                })
+            })
+            .collect();
+        self.bottom_pane.set_mcp_prompts(mcp_prompts);
+    }
+
+    fn on_prompt_preview(&mut self, ev: PromptPreviewEvent) {
+        self.add_to_history(history_cell::new_prompt_preview_output(ev));
+    }
+
+    fn on_mcp_resource_updated(&mut self, ev: McpResourceUpdatedEvent) {
+        let McpResourceUpdatedEvent { server, uri } = ev;
+        self.add_info_message(
+            format!("{server}:{uri} changed"),
+            Some(format!("Mention @{server}:{uri} to send the new contents.")),
+        );
     }
 
     /// Programmatically submit a user text message as if typed in the
@@ -1,10 +1,13 @@
 use std::sync::Arc;
+use std::sync::atomic::AtomicBool;
+use std::sync::atomic::Ordering;
 
 use codex_core::CodexConversation;
 use codex_core::ConversationManager;
 use codex_core::NewConversation;
 use codex_core::config::Config;
 use codex_core::protocol::Op;
+use tokio::sync::mpsc::UnboundedReceiver;
 use tokio::sync::mpsc::UnboundedSender;
 use tokio::sync::mpsc::unbounded_channel;
 
@@ -18,7 +21,7 @@ pub(crate) fn spawn_agent(
     app_event_tx: AppEventSender,
     server: Arc<ConversationManager>,
 ) -> UnboundedSender<Op> {
-    let (codex_op_tx, mut codex_op_rx) = unbounded_channel::<Op>();
+    let (codex_op_tx, codex_op_rx) = unbounded_channel::<Op>();
 
     let app_event_tx_clone = app_event_tx;
     tokio::spawn(async move {
@@ -43,19 +46,7 @@ pub(crate) fn spawn_agent(
         };
         app_event_tx_clone.send(AppEvent::CodexEvent(ev));
 
-        let conversation_clone = conversation.clone();
-        tokio::spawn(async move {
-            while let Some(op) = codex_op_rx.recv().await {
-                let id = conversation_clone.submit(op).await;
-                if let Err(e) = id {
-                    tracing::error!("failed to submit op: {e}");
-                }
-            }
-        });
-
-        while let Ok(event) = conversation.next_event().await {
-            app_event_tx_clone.send(AppEvent::CodexEvent(event));
-        }
+        drive_conversation(conversation, codex_op_rx, app_event_tx_clone).await;
     });
 
     codex_op_tx
@@ -69,7 +60,7 @@ pub(crate) fn spawn_agent_from_existing(
     session_configured: codex_core::protocol::SessionConfiguredEvent,
     app_event_tx: AppEventSender,
 ) -> UnboundedSender<Op> {
-    let (codex_op_tx, mut codex_op_rx) = unbounded_channel::<Op>();
+    let (codex_op_tx, codex_op_rx) = unbounded_channel::<Op>();
 
     let app_event_tx_clone = app_event_tx;
     tokio::spawn(async move {
@@ -80,20 +71,171 @@ pub(crate) fn spawn_agent_from_existing(
         };
         app_event_tx_clone.send(AppEvent::CodexEvent(ev));
 
-        let conversation_clone = conversation.clone();
-        tokio::spawn(async move {
-            while let Some(op) = codex_op_rx.recv().await {
-                let id = conversation_clone.submit(op).await;
-                if let Err(e) = id {
-                    tracing::error!("failed to submit op: {e}");
+        drive_conversation(conversation, codex_op_rx, app_event_tx_clone).await;
+    });
+
+    codex_op_tx
+}
+
+/// Submit the widget's ops to `conversation` and forward its events to the
+/// app. Once the widget is dropped because another session replaced it, the
+/// conversation is shut down, which also clears its crash-recovery marker,
+/// and its remaining events are no longer forwarded.
+async fn drive_conversation(
+    conversation: Arc<CodexConversation>,
+    mut codex_op_rx: UnboundedReceiver<Op>,
+    app_event_tx: AppEventSender,
+) {
+    let replaced = Arc::new(AtomicBool::new(false));
+
+    let conversation_clone = conversation.clone();
+    let replaced_clone = replaced.clone();
+    tokio::spawn(async move {
+        while let Some(op) = codex_op_rx.recv().await {
+            let id = conversation_clone.submit(op).await;
+            if let Err(e) = id {
+                tracing::error!("failed to submit op: {e}");
+            }
+        }
+        replaced_clone.store(true, Ordering::Release);
+        // Fails harmlessly when the conversation has already shut down.
+        if let Err(e) = conversation_clone.submit(Op::Shutdown).await {
+            tracing::debug!("failed to shut down replaced conversation: {e}");
+        }
+    });
+
+    while let Ok(event) = conversation.next_event().await {
+        if replaced.load(Ordering::Acquire) {
+            break;
+        }
+        app_event_tx.send(AppEvent::CodexEvent(event));
+    }
+}
+
+/// Spawn loops that drive a session running in another process (see
+/// `codex attach`): events are read from `stream` as JSON lines and ops are
+/// written back the same way. Shutting down only detaches, since the session
+/// belongs to the other process.
+#[cfg(unix)]
+pub(crate) fn spawn_remote_agent(
+    stream: tokio::net::UnixStream,
+    app_event_tx: AppEventSender,
+) -> UnboundedSender<Op> {
+    use tokio::io::AsyncBufReadExt;
+    use tokio::io::AsyncWriteExt;
+    use tokio::io::BufReader;
+
+    let (codex_op_tx, mut codex_op_rx) = unbounded_channel::<Op>();
+    let (read, mut write) = stream.into_split();
+
+    let app_event_tx_clone = app_event_tx.clone();
+    tokio::spawn(async move {
+        while let Some(op) = codex_op_rx.recv().await {
+            if matches!(op, Op::Shutdown) {
+                app_event_tx_clone.send(AppEvent::ExitRequest);
+                break;
+            }
+            let mut line = match serde_json::to_string(&op) {
+                Ok(line) => line,
+                Err(e) => {
+                    tracing::error!("failed to serialize op: {e}");
+                    continue;
                 }
+            };
+            line.push('\n');
+            if let Err(e) = write.write_all(line.as_bytes()).await {
+                tracing::error!("failed to submit op: {e}");
+                break;
             }
-        });
+        }
+    });
 
-        while let Ok(event) = conversation.next_event().await {
-            app_event_tx_clone.send(AppEvent::CodexEvent(event));
+    tokio::spawn(async move {
+        let mut lines = BufReader::new(read).lines();
+        loop {
+            match lines.next_line().await {
+                Ok(Some(line)) => match serde_json::from_str(&line) {
+                    Ok(event) => app_event_tx.send(AppEvent::CodexEvent(event)),
+                    Err(e) => tracing::warn!("ignoring unparseable event: {e}"),
+                },
+                Ok(None) => break,
+                Err(e) => {
+                    tracing::error!("failed to read from the session: {e}");
+                    break;
+                }
+            }
         }
+        // The session ended or the connection dropped; nothing is left to
+        // drive.
+        app_event_tx.send(AppEvent::ExitRequest);
     });
 
     codex_op_tx
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::AuthManager;
+    use codex_core::CodexAuth;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+    use codex_core::protocol::EventMsg;
+    use std::time::Duration;
+    use tempfile::TempDir;
+
+    fn recovery_markers(codex_home: &TempDir) -> usize {
+        std::fs::read_dir(codex_home.path().join("recovery"))
+            .map(|entries| entries.count())
+            .unwrap_or(0)
+    }
+
+    #[tokio::test]
+    async fn new_session_shuts_down_the_replaced_conversation() {
+        let codex_home = TempDir::new().expect("tempdir");
+        let config = Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides {
+                cwd: Some(codex_home.path().to_path_buf()),
+                ..Default::default()
+            },
+            codex_home.path().to_path_buf(),
+        )
+        .expect("config");
+        let server = Arc::new(ConversationManager::new(
+            AuthManager::from_auth_for_testing(CodexAuth::from_api_key("Test API Key")),
+        ));
+        let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
+
+        let codex_op_tx = spawn_agent(config, AppEventSender::new(tx_raw), server);
+        match tokio::time::timeout(Duration::from_secs(10), rx.recv()).await {
+            Ok(Some(AppEvent::CodexEvent(event))) => {
+                assert!(matches!(event.msg, EventMsg::SessionConfigured(_)));
+            }
+            other => panic!("expected SessionConfigured, got {other:?}"),
+        }
+        assert_eq!(recovery_markers(&codex_home), 1);
+
+        // `/new` drops the ChatWidget that owns the sender.
+        drop(codex_op_tx);
+        tokio::time::timeout(Duration::from_secs(10), async {
+            while recovery_markers(&codex_home) > 0 {
+                tokio::time::sleep(Duration::from_millis(20)).await;
+            }
+        })
+        .await
+        .expect("recovery marker removed");
+
+        // The old session's shutdown must not reach the app, or it would
+        // exit.
+        while let Ok(event) = rx.try_recv() {
+            assert!(
+                !matches!(
+                    &event,
+                    AppEvent::CodexEvent(ev) if matches!(ev.msg, EventMsg::ShutdownComplete)
+                ),
+                "ShutdownComplete of the replaced session was forwarded"
+            );
+        }
+    }
+}
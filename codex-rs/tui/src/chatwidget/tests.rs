@@ -223,6 +223,7 @@ fn make_chatwidget_manual() -> (
         enhanced_keys_supported: false,
         placeholder_text: "Ask Codex to do anything".to_string(),
         disable_paste_burst: false,
+        approval_timeout: None,
     });
     let widget = ChatWidget {
         app_event_tx,
@@ -242,6 +243,8 @@ fn make_chatwidget_manual() -> (
         frame_requester: FrameRequester::test_dummy(),
         show_welcome_banner: true,
         queued_user_messages: VecDeque::new(),
+        attached_command_outputs: Vec::new(),
+        recent_files: VecDeque::new(),
         suppress_session_configured_redraw: false,
     };
     (widget, rx, op_rx)
@@ -569,6 +572,7 @@ fn interrupt_exec_marks_failed_snapshot() {
         id: "call-int".into(),
         msg: EventMsg::TurnAborted(codex_core::protocol::TurnAbortedEvent {
             reason: TurnAbortReason::Interrupted,
+            cleanup: Default::default(),
         }),
     });
 
@@ -931,6 +935,7 @@ fn interrupt_restores_queued_messages_into_composer() {
         id: "turn-1".into(),
         msg: EventMsg::TurnAborted(codex_core::protocol::TurnAbortedEvent {
             reason: TurnAbortReason::Interrupted,
+            cleanup: Default::default(),
         }),
     });
 
@@ -1888,3 +1893,31 @@ printf 'fenced within fenced\n'
     let visual = vt_lines.join("\n");
     assert_snapshot!(visual);
 }
+
+#[test]
+fn recent_files_track_patches_most_recent_first() {
+    let (mut chat, _rx, _op_rx) = make_chatwidget_manual();
+    let cwd = chat.config.cwd.clone();
+    for (call_id, file) in [("c1", "a.rs"), ("c2", "b.rs"), ("c3", "a.rs")] {
+        chat.handle_codex_event(Event {
+            id: "s1".into(),
+            msg: EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+                call_id: call_id.into(),
+                auto_approved: true,
+                changes: HashMap::from([(
+                    cwd.join(file),
+                    FileChange::Add {
+                        content: String::new(),
+                    },
+                )]),
+            }),
+        });
+    }
+    assert_eq!(
+        chat.recent_files,
+        VecDeque::from([PathBuf::from("a.rs"), PathBuf::from("b.rs")])
+    );
+
+    chat.open_command_palette(Vec::new());
+    assert!(chat.has_active_view());
+}
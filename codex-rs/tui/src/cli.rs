@@ -9,7 +9,8 @@ pub struct Cli {
     /// Optional user prompt to start the session.
     pub prompt: Option<String>,
 
-    /// Optional image(s) to attach to the initial prompt.
+    /// Optional image(s), or PDF/DOCX document(s), to attach to the initial
+    /// prompt.
     #[arg(long = "image", short = 'i', value_name = "FILE", value_delimiter = ',', num_args = 1..)]
     pub images: Vec<PathBuf>,
 
@@ -88,6 +89,18 @@ pub struct Cli {
     #[arg(long = "search", default_value_t = false)]
     pub web_search: bool,
 
+    /// Refuse all network traffic except to loopback and the hosts listed in
+    /// `offline_allowed_hosts` (e.g. a LAN Ollama server). Disables update
+    /// checks and fails if the configured model provider is not reachable
+    /// under these rules.
+    #[arg(long = "offline", default_value_t = false)]
+    pub offline: bool,
+
     #[clap(skip)]
     pub config_overrides: CliConfigOverrides,
+
+    /// Socket of a running session to attach to instead of starting one; set
+    /// by `codex attach`.
+    #[clap(skip)]
+    pub attach: Option<PathBuf>,
 }
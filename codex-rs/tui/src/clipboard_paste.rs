@@ -51,40 +51,17 @@ pub struct PastedImageInfo {
 pub fn paste_image_as_png() -> Result<(Vec<u8>, PastedImageInfo), PasteImageError> {
     let _span = tracing::debug_span!("paste_image_as_png").entered();
     tracing::debug!("attempting clipboard image read");
-    let mut cb = arboard::Clipboard::new()
-        .map_err(|e| PasteImageError::ClipboardUnavailable(e.to_string()))?;
-    // Sometimes images on the clipboard come as files (e.g. when copy/pasting from
-    // Finder), sometimes they come as image data (e.g. when pasting from Chrome).
-    // Accept both, and prefer files if both are present.
-    let files = cb
-        .get()
-        .file_list()
-        .map_err(|e| PasteImageError::ClipboardUnavailable(e.to_string()));
-    let dyn_img = if let Some(img) = files
-        .unwrap_or_default()
-        .into_iter()
-        .find_map(|f| image::open(f).ok())
-    {
-        tracing::debug!(
-            "clipboard image opened from file: {}x{}",
-            img.width(),
-            img.height()
-        );
-        img
-    } else {
-        let _span = tracing::debug_span!("get_image").entered();
-        let img = cb
-            .get_image()
-            .map_err(|e| PasteImageError::NoImage(e.to_string()))?;
-        let w = img.width as u32;
-        let h = img.height as u32;
-        tracing::debug!("clipboard image opened from image: {}x{}", w, h);
-
-        let Some(rgba_img) = image::RgbaImage::from_raw(w, h, img.bytes.into_owned()) else {
-            return Err(PasteImageError::EncodeFailed("invalid RGBA buffer".into()));
-        };
-
-        image::DynamicImage::ImageRgba8(rgba_img)
+    let dyn_img = match read_clipboard_image() {
+        Ok(img) => img,
+        #[cfg(target_os = "linux")]
+        Err(err) => {
+            // arboard can only talk to X11 and to Wayland compositors that
+            // implement data-control; fall back to the usual clipboard tools.
+            tracing::debug!("arboard failed ({err}); trying clipboard tools");
+            read_clipboard_image_with_tools().ok_or(err)?
+        }
+        #[cfg(not(target_os = "linux"))]
+        Err(err) => return Err(err),
     };
 
     let mut png: Vec<u8> = Vec::new();
@@ -108,6 +85,69 @@ pub fn paste_image_as_png() -> Result<(Vec<u8>, PastedImageInfo), PasteImageErro
     ))
 }
 
+#[cfg(not(target_os = "android"))]
+fn read_clipboard_image() -> Result<image::DynamicImage, PasteImageError> {
+    let mut cb = arboard::Clipboard::new()
+        .map_err(|e| PasteImageError::ClipboardUnavailable(e.to_string()))?;
+    // Sometimes images on the clipboard come as files (e.g. when copy/pasting from
+    // Finder), sometimes they come as image data (e.g. when pasting from Chrome).
+    // Accept both, and prefer files if both are present.
+    let files = cb
+        .get()
+        .file_list()
+        .map_err(|e| PasteImageError::ClipboardUnavailable(e.to_string()));
+    if let Some(img) = files
+        .unwrap_or_default()
+        .into_iter()
+        .find_map(|f| image::open(f).ok())
+    {
+        tracing::debug!(
+            "clipboard image opened from file: {}x{}",
+            img.width(),
+            img.height()
+        );
+        return Ok(img);
+    }
+
+    let _span = tracing::debug_span!("get_image").entered();
+    let img = cb
+        .get_image()
+        .map_err(|e| PasteImageError::NoImage(e.to_string()))?;
+    let w = img.width as u32;
+    let h = img.height as u32;
+    tracing::debug!("clipboard image opened from image: {}x{}", w, h);
+
+    let Some(rgba_img) = image::RgbaImage::from_raw(w, h, img.bytes.into_owned()) else {
+        return Err(PasteImageError::EncodeFailed("invalid RGBA buffer".into()));
+    };
+    Ok(image::DynamicImage::ImageRgba8(rgba_img))
+}
+
+/// Read a PNG from the clipboard with `wl-paste` (Wayland) or `xclip` (X11),
+/// whichever is installed and succeeds first.
+#[cfg(target_os = "linux")]
+fn read_clipboard_image_with_tools() -> Option<image::DynamicImage> {
+    const TOOLS: &[(&str, &[&str])] = &[
+        ("wl-paste", &["--no-newline", "--type", "image/png"]),
+        (
+            "xclip",
+            &["-selection", "clipboard", "-target", "image/png", "-out"],
+        ),
+    ];
+    TOOLS.iter().find_map(|(program, args)| {
+        let output = std::process::Command::new(program)
+            .args(*args)
+            .stdin(std::process::Stdio::null())
+            .stderr(std::process::Stdio::null())
+            .output()
+            .ok()?;
+        if !output.status.success() {
+            return None;
+        }
+        image::load_from_memory(&output.stdout).ok()
+    })
+}
+
 /// Android/Termux does not support arboard; return a clear error.
 #[cfg(target_os = "android")]
 pub fn paste_image_as_png() -> Result<(Vec<u8>, PastedImageInfo), PasteImageError> {
@@ -0,0 +1,260 @@
+//! Live diff shown beside the transcript overlay.
+//!
+//! The pane tracks the turn's running diff (`TurnDiff` events) and the files
+//! named by the most recent `PatchApplyBegin`, and scrolls to the section for
+//! the file being edited so the change is in view as soon as it lands. It is
+//! toggled with `d` in the transcript overlay and resized with `[` / `]`.
+
+use std::path::Path;
+use std::path::PathBuf;
+
+use codex_core::protocol::EventMsg;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::WidgetRef;
+
+const DEFAULT_WIDTH_PERCENT: u16 = 50;
+const MIN_WIDTH_PERCENT: u16 = 20;
+const MAX_WIDTH_PERCENT: u16 = 80;
+const WIDTH_STEP_PERCENT: u16 = 10;
+
+#[derive(Debug, Clone)]
+pub(crate) struct DiffPane {
+    unified_diff: String,
+    /// Files touched by the latest patch, sorted.
+    editing: Vec<PathBuf>,
+    visible: bool,
+    width_percent: u16,
+}
+
+impl Default for DiffPane {
+    fn default() -> Self {
+        Self {
+            unified_diff: String::new(),
+            editing: Vec::new(),
+            visible: false,
+            width_percent: DEFAULT_WIDTH_PERCENT,
+        }
+    }
+}
+
+impl DiffPane {
+    /// Record the diff or edited files carried by `msg`. Returns true if the
+    /// pane's contents changed.
+    pub(crate) fn on_event(&mut self, msg: &EventMsg) -> bool {
+        match msg {
+            EventMsg::TurnDiff(ev) => {
+                self.unified_diff = ev.unified_diff.clone();
+                true
+            }
+            EventMsg::PatchApplyBegin(ev) => {
+                let mut editing: Vec<PathBuf> = ev.changes.keys().cloned().collect();
+                editing.sort();
+                self.editing = editing;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    pub(crate) fn is_visible(&self) -> bool {
+        self.visible
+    }
+
+    pub(crate) fn toggle(&mut self) {
+        self.visible = !self.visible;
+    }
+
+    pub(crate) fn grow(&mut self) {
+        self.width_percent = (self.width_percent + WIDTH_STEP_PERCENT).min(MAX_WIDTH_PERCENT);
+    }
+
+    pub(crate) fn shrink(&mut self) {
+        self.width_percent = self
+            .width_percent
+            .saturating_sub(WIDTH_STEP_PERCENT)
+            .max(MIN_WIDTH_PERCENT);
+    }
+
+    /// Split `area` into the part left for the transcript and, when the pane
+    /// is visible, the part it occupies on the right.
+    pub(crate) fn split(&self, area: Rect) -> (Rect, Option<Rect>) {
+        if !self.visible {
+            return (area, None);
+        }
+        let pane_width = (u32::from(area.width) * u32::from(self.width_percent) / 100) as u16;
+        let left = Rect::new(area.x, area.y, area.width - pane_width, area.height);
+        let right = Rect::new(left.right(), area.y, pane_width, area.height);
+        (left, Some(right))
+    }
+
+    pub(crate) fn render(&self, area: Rect, buf: &mut Buffer) {
+        if area.width < 2 || area.height == 0 {
+            return;
+        }
+        for y in area.top()..area.bottom() {
+            Span::from("│")
+                .dim()
+                .render_ref(Rect::new(area.x, y, 1, 1), buf);
+        }
+        let inner = Rect::new(area.x + 1, area.y, area.width - 1, area.height);
+
+        let title = match self.editing.as_slice() {
+            [] => "/ D I F F".to_string(),
+            [file] => format!("/ D I F F  editing {}", file_name(file)),
+            [file, rest @ ..] => format!(
+                "/ D I F F  editing {} (+{} more)",
+                file_name(file),
+                rest.len()
+            ),
+        };
+        title
+            .dim()
+            .render_ref(Rect::new(inner.x, inner.y, inner.width, 1), buf);
+
+        let content = Rect::new(
+            inner.x,
+            inner.y + 1,
+            inner.width,
+            inner.height.saturating_sub(1),
+        );
+        let lines = self.lines();
+        if lines.is_empty() {
+            Paragraph::new(Line::from("No changes yet.".dim().italic())).render_ref(content, buf);
+            return;
+        }
+        let height = content.height as usize;
+        let start = self
+            .focus_line()
+            .unwrap_or(0)
+            .min(lines.len().saturating_sub(height));
+        let end = (start + height).min(lines.len());
+        Paragraph::new(lines[start..end].to_vec()).render_ref(content, buf);
+    }
+
+    fn lines(&self) -> Vec<Line<'static>> {
+        self.unified_diff
+            .lines()
+            .map(|line| {
+                let owned = line.to_string();
+                if line.starts_with("diff --git ") {
+                    Line::from(owned.bold())
+                } else if line.starts_with("+++") || line.starts_with("---") {
+                    Line::from(owned.dim())
+                } else if line.starts_with("@@") {
+                    Line::from(owned.cyan())
+                } else if line.starts_with('+') {
+                    Line::from(owned.green())
+                } else if line.starts_with('-') {
+                    Line::from(owned.red())
+                } else {
+                    Line::from(owned)
+                }
+            })
+            .collect()
+    }
+
+    /// Index of the `diff --git` header for the first file being edited.
+    fn focus_line(&self) -> Option<usize> {
+        self.unified_diff
+            .lines()
+            .position(|line| self.editing.iter().any(|path| header_names(line, path)))
+    }
+}
+
+/// Whether the `diff --git a/<old> b/<new>` header `line` is for `path`. The
+/// diff names files relative to their repository root, so compare suffixes.
+fn header_names(line: &str, path: &Path) -> bool {
+    let Some(rest) = line.strip_prefix("diff --git ") else {
+        return false;
+    };
+    rest.rsplit_once(" b/")
+        .is_some_and(|(_, new)| path.ends_with(new))
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_else(|| path.display().to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::protocol::FileChange;
+    use codex_core::protocol::PatchApplyBeginEvent;
+    use codex_core::protocol::TurnDiffEvent;
+    use pretty_assertions::assert_eq;
+    use std::collections::HashMap;
+
+    const DIFF: &str = "diff --git a/src/lib.rs b/src/lib.rs\n--- a/src/lib.rs\n+++ b/src/lib.rs\n@@ -1 +1 @@\n-old\n+new\ndiff --git a/src/main.rs b/src/main.rs\n--- a/src/main.rs\n+++ b/src/main.rs\n@@ -1 +1 @@\n-fn a() {}\n+fn b() {}\n";
+
+    fn render(pane: &DiffPane, width: u16, height: u16) -> String {
+        let area = Rect::new(0, 0, width, height);
+        let mut buf = Buffer::empty(area);
+        pane.render(area, &mut buf);
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buf[(x, y)].symbol())
+                    .collect::<String>()
+                    .trim_end()
+                    .to_string()
+            })
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn follows_the_file_being_edited() {
+        let mut pane = DiffPane::default();
+        assert!(pane.on_event(&EventMsg::TurnDiff(TurnDiffEvent {
+            unified_diff: DIFF.to_string(),
+        })));
+        assert_eq!(pane.focus_line(), None);
+
+        pane.on_event(&EventMsg::PatchApplyBegin(PatchApplyBeginEvent {
+            call_id: "c1".to_string(),
+            auto_approved: true,
+            changes: HashMap::from([(
+                PathBuf::from("/repo/src/main.rs"),
+                FileChange::Update {
+                    unified_diff: String::new(),
+                    move_path: None,
+                },
+            )]),
+        }));
+        assert_eq!(pane.focus_line(), Some(6));
+        assert_eq!(
+            render(&pane, 40, 5),
+            "│/ D I F F  editing main.rs\n│diff --git a/src/main.rs b/src/main.rs\n│--- a/src/main.rs\n│+++ b/src/main.rs\n│@@ -1 +1 @@"
+        );
+    }
+
+    #[test]
+    fn split_respects_width_limits() {
+        let mut pane = DiffPane::default();
+        let area = Rect::new(0, 0, 100, 10);
+        assert_eq!(pane.split(area), (area, None));
+
+        pane.toggle();
+        for _ in 0..10 {
+            pane.grow();
+        }
+        assert_eq!(
+            pane.split(area),
+            (Rect::new(0, 0, 20, 10), Some(Rect::new(20, 0, 80, 10)))
+        );
+        for _ in 0..10 {
+            pane.shrink();
+        }
+        assert_eq!(
+            pane.split(area),
+            (Rect::new(0, 0, 80, 10), Some(Rect::new(80, 0, 20, 10)))
+        );
+    }
+}
@@ -22,11 +22,16 @@ use codex_core::plan_tool::PlanItemArg;
 use codex_core::plan_tool::StepStatus;
 use codex_core::plan_tool::UpdatePlanArgs;
 use codex_core::project_doc::discover_project_doc_paths;
+use codex_core::protocol::ApprovalTimeoutAction;
 use codex_core::protocol::FileChange;
 use codex_core::protocol::McpInvocation;
+use codex_core::protocol::McpListToolsResponseEvent;
+use codex_core::protocol::PatchGuardViolation;
+use codex_core::protocol::PromptPreviewEvent;
 use codex_core::protocol::SandboxPolicy;
 use codex_core::protocol::SessionConfiguredEvent;
 use codex_core::protocol::TokenUsage;
+use codex_core::protocol::WorkspaceEscape;
 use codex_protocol::mcp_protocol::ConversationId;
 use codex_protocol::num_format::format_with_separators;
 use codex_protocol::parse_command::ParsedCommand;
@@ -679,6 +684,93 @@ pub(crate) fn new_user_approval_decision(lines: Vec<Line<'static>>) -> PlainHist
     PlainHistoryCell { lines }
 }
 
+pub(crate) fn new_approval_timed_out(
+    timeout_secs: u64,
+    action: ApprovalTimeoutAction,
+) -> PlainHistoryCell {
+    let outcome = match action {
+        ApprovalTimeoutAction::Deny => "denied",
+        ApprovalTimeoutAction::Allow => "approved",
+        ApprovalTimeoutAction::Abort => "canceled",
+    };
+    let lines: Vec<Line<'static>> = vec![
+        vec![
+            "✗ ".red(),
+            "No response after ".into(),
+            format!("{timeout_secs}s").bold(),
+            "; request ".into(),
+            outcome.bold(),
+            " automatically".into(),
+        ]
+        .into(),
+    ];
+    PlainHistoryCell { lines }
+}
+
+/// A patch rejected because it writes, through a symlink or `..`, outside the
+/// writable roots. Paths inside `cwd` are shown relative to it.
+pub(crate) fn new_workspace_escape_blocked(
+    escapes: Vec<WorkspaceEscape>,
+    cwd: &Path,
+) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = vec![
+        vec![
+            "✗ ".red(),
+            "Patch blocked: ".bold(),
+            "writes would land outside the workspace".into(),
+        ]
+        .into(),
+    ];
+    for WorkspaceEscape { path, resolved } in escapes {
+        let shown = path
+            .strip_prefix(cwd)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        lines.push(
+            vec![
+                "  └ ".dim(),
+                shown.into(),
+                " → ".dim(),
+                resolved.display().to_string().red(),
+            ]
+            .into(),
+        );
+    }
+    PlainHistoryCell { lines }
+}
+
+pub(crate) fn new_patch_guard_blocked(
+    violations: Vec<PatchGuardViolation>,
+    cwd: &Path,
+) -> PlainHistoryCell {
+    let mut lines: Vec<Line<'static>> = vec![
+        vec![
+            "✗ ".red(),
+            "Patch blocked: ".bold(),
+            "edits binary, oversized or generated files".into(),
+        ]
+        .into(),
+    ];
+    for PatchGuardViolation { path, reason } in violations {
+        let shown = path
+            .strip_prefix(cwd)
+            .unwrap_or(&path)
+            .display()
+            .to_string();
+        lines.push(
+            vec![
+                "  └ ".dim(),
+                shown.into(),
+                " ".into(),
+                reason.to_string().dim(),
+            ]
+            .into(),
+        );
+    }
+    PlainHistoryCell { lines }
+}
+
 pub(crate) fn new_active_exec_command(
     call_id: String,
     command: Vec<String>,
@@ -912,6 +1004,21 @@ pub(crate) fn new_status_output(
     } else {
         lines.push(vec!["  • AGENTS files: ".into(), agents_list.join(", ").into()].into());
     }
+    // [provider_instructions] / [model_instructions] entries for this model
+    let snippets: Vec<String> = config
+        .instruction_snippets()
+        .into_iter()
+        .map(|(key, _)| key)
+        .collect();
+    if !snippets.is_empty() {
+        lines.push(
+            vec![
+                "  • Model instructions: ".into(),
+                snippets.join(", ").into(),
+            ]
+            .into(),
+        );
+    }
     lines.push("".into());
 
     // 👤 Account (only if ChatGPT tokens exist), shown under the first block
@@ -1014,11 +1121,53 @@ pub(crate) fn empty_mcp_output() -> PlainHistoryCell {
     PlainHistoryCell { lines }
 }
 
+/// Render the prompt the next request will carry, as reported by `/prompt`.
+pub(crate) fn new_prompt_preview_output(ev: PromptPreviewEvent) -> PlainHistoryCell {
+    let PromptPreviewEvent {
+        instructions,
+        context,
+        tools,
+    } = ev;
+    let mut lines: Vec<Line<'static>> = vec!["/prompt".magenta().into(), "".into()];
+
+    lines.push("Instructions".bold().into());
+    lines.extend(
+        instructions
+            .lines()
+            .map(|line| Line::from(line.to_string())),
+    );
+    lines.push("".into());
+
+    lines.push("Context".bold().into());
+    if context.is_empty() {
+        lines.push("  (none)".dim().into());
+    }
+    for message in context {
+        lines.extend(message.lines().map(|line| Line::from(line.to_string())));
+        lines.push("".into());
+    }
+
+    lines.push("Tools".bold().into());
+    if tools.is_empty() {
+        lines.push("  (none)".dim().into());
+    } else {
+        lines.push(format!("  {}", tools.join(", ")).into());
+    }
+    lines.push("".into());
+
+    PlainHistoryCell { lines }
+}
+
 /// Render MCP tools grouped by connection using the fully-qualified tool names.
 pub(crate) fn new_mcp_tools_output(
     config: &Config,
-    tools: std::collections::HashMap<String, mcp_types::Tool>,
+    ev: McpListToolsResponseEvent,
 ) -> PlainHistoryCell {
+    let McpListToolsResponseEvent {
+        tools,
+        resources,
+        prompts,
+    } = ev;
     let mut lines: Vec<Line<'static>> = vec![
         "/mcp".magenta().into(),
         "".into(),
@@ -1026,7 +1175,7 @@ pub(crate) fn new_mcp_tools_output(
         "".into(),
     ];
 
-    if tools.is_empty() {
+    if tools.is_empty() && resources.is_empty() && prompts.is_empty() {
         lines.push("  • No MCP tools available.".italic().into());
         lines.push("".into());
         return PlainHistoryCell { lines };
@@ -1054,6 +1203,32 @@ pub(crate) fn new_mcp_tools_output(
         } else {
             lines.push(vec!["    • Tools: ".into(), names.join(", ").into()].into());
         }
+
+        if let Some(resources) = resources.get(server) {
+            let mut uris: Vec<String> = resources
+                .iter()
+                .map(|resource| format!("@{server}:{}", resource.uri))
+                .collect();
+            uris.sort();
+            if uris.is_empty() {
+                lines.push("    • Resources: (none)".into());
+            } else {
+                lines.push(vec!["    • Resources: ".into(), uris.join(", ").into()].into());
+            }
+        }
+
+        if let Some(prompts) = prompts.get(server) {
+            let mut commands: Vec<String> = prompts
+                .iter()
+                .map(|prompt| format!("/{server}:{}", prompt.name))
+                .collect();
+            commands.sort();
+            if commands.is_empty() {
+                lines.push("    • Prompts: (none)".into());
+            } else {
+                lines.push(vec!["    • Prompts: ".into(), commands.join(", ").into()].into());
+            }
+        }
         lines.push(Line::from(""));
     }
 
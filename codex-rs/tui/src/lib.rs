@@ -17,26 +17,27 @@ use codex_core::config::load_config_as_toml_with_cli_overrides;
 use codex_core::config::persist_model_selection;
 use codex_core::protocol::AskForApproval;
 use codex_core::protocol::SandboxPolicy;
+use codex_core::trust_store::TrustDecision;
+use codex_core::trust_store::TrustStore;
+use codex_core::trust_store::lock_down_untrusted;
 use codex_ollama::DEFAULT_OSS_MODEL;
 use codex_protocol::config_types::SandboxMode;
 use codex_protocol::mcp_protocol::AuthMode;
-use std::fs::OpenOptions;
 use std::path::PathBuf;
 use tracing::error;
-use tracing_appender::non_blocking;
-use tracing_subscriber::EnvFilter;
-use tracing_subscriber::prelude::*;
 
 mod app;
 mod app_backtrack;
 mod app_event;
 mod app_event_sender;
+mod attach_cmd;
 mod bottom_pane;
 mod chatwidget;
 mod citation_regex;
 mod cli;
 mod clipboard_paste;
 pub mod custom_terminal;
+mod diff_pane;
 mod diff_render;
 mod exec_command;
 mod file_search;
@@ -48,30 +49,36 @@ pub mod live_wrap;
 mod markdown;
 mod markdown_render;
 mod markdown_stream;
+mod mcp_mentions;
 mod new_model_popup;
 pub mod onboarding;
 mod pager_overlay;
+mod recovery_prompt;
 mod render;
 mod resume_picker;
 mod session_log;
+mod settings;
 mod shimmer;
 mod slash_command;
 mod status_indicator_widget;
 mod streaming;
+mod subtask_tree;
 mod text_formatting;
 mod tui;
 mod user_approval_widget;
 mod version;
 mod wrapping;
+mod write_preview;
 
-#[cfg(not(debug_assertions))]
-mod updates;
+pub mod updates;
 
 use crate::new_model_popup::ModelUpgradeDecision;
 use crate::new_model_popup::run_model_upgrade_popup;
 use crate::onboarding::TrustDirectorySelection;
 use crate::onboarding::onboarding_screen::OnboardingScreenArgs;
 use crate::onboarding::onboarding_screen::run_onboarding_app;
+use crate::recovery_prompt::RecoveryDecision;
+use crate::recovery_prompt::run_recovery_prompt;
 use crate::tui::Tui;
 pub use cli::Cli;
 use codex_core::internal_storage::InternalStorage;
@@ -134,6 +141,8 @@ pub async fn run_main(
         include_view_image_tool: None,
         show_raw_agent_reasoning: cli.oss.then_some(true),
         tools_web_search_request: cli.web_search.then_some(true),
+        offline: cli.offline.then_some(true),
+        read_only: None,
     };
     let raw_overrides = cli.config_overrides.raw_overrides.clone();
     let overrides_cli = codex_common::CliConfigOverrides { raw_overrides };
@@ -184,7 +193,7 @@ pub async fn run_main(
         .clone()
         .or_else(|| config_toml.profile.clone());
 
-    let should_show_trust_screen = determine_repo_trust_state(
+    let pending_trust = determine_repo_trust_state(
         &mut config,
         &config_toml,
         approval_policy,
@@ -195,38 +204,13 @@ pub async fn run_main(
     let internal_storage = InternalStorage::load(&config.codex_home);
 
     let log_dir = codex_core::config::log_dir(&config)?;
-    std::fs::create_dir_all(&log_dir)?;
-    // Open (or create) your log file, appending to it.
-    let mut log_file_opts = OpenOptions::new();
-    log_file_opts.create(true).append(true);
-
-    // Ensure the file is only readable and writable by the current user.
-    // Doing the equivalent to `chmod 600` on Windows is quite a bit more code
-    // and requires the Windows API crates, so we can reconsider that when
-    // Codex CLI is officially supported on Windows.
-    #[cfg(unix)]
-    {
-        use std::os::unix::fs::OpenOptionsExt;
-        log_file_opts.mode(0o600);
-    }
-
-    let log_file = log_file_opts.open(log_dir.join("codex-tui.log"))?;
-
-    // Wrap file in non‑blocking writer.
-    let (non_blocking, _guard) = non_blocking(log_file);
-
-    // use RUST_LOG env var, default to info for codex crates.
-    let env_filter = || {
-        EnvFilter::try_from_default_env()
-            .unwrap_or_else(|_| EnvFilter::new("codex_core=info,codex_tui=info"))
-    };
-
-    // Build layered subscriber:
-    let file_layer = tracing_subscriber::fmt::layer()
-        .with_writer(non_blocking)
-        .with_target(false)
-        .with_span_events(tracing_subscriber::fmt::format::FmtSpan::CLOSE)
-        .with_filter(env_filter());
+    // RUST_LOG, then `[log]` in config.toml, then info for codex crates.
+    let _log_guard = codex_common::logging::init_file_logging(
+        &config.log,
+        &log_dir,
+        "codex-tui.log",
+        "codex_core=info,codex_tui=info",
+    )?;
 
     if cli.oss {
         codex_ollama::ensure_oss_ready(&config)
@@ -234,17 +218,9 @@ pub async fn run_main(
             .map_err(|e| std::io::Error::other(format!("OSS setup failed: {e}")))?;
     }
 
-    let _ = tracing_subscriber::registry().with(file_layer).try_init();
-
-    run_ratatui_app(
-        cli,
-        config,
-        internal_storage,
-        active_profile,
-        should_show_trust_screen,
-    )
-    .await
-    .map_err(|err| std::io::Error::other(err.to_string()))
+    run_ratatui_app(cli, config, internal_storage, active_profile, pending_trust)
+        .await
+        .map_err(|err| std::io::Error::other(err.to_string()))
 }
 
 async fn run_ratatui_app(
@@ -252,7 +228,7 @@ async fn run_ratatui_app(
     config: Config,
     mut internal_storage: InternalStorage,
     active_profile: Option<String>,
-    should_show_trust_screen: bool,
+    pending_trust: Option<TrustDefaults>,
 ) -> color_eyre::Result<codex_core::protocol::TokenUsage> {
     let mut config = config;
     color_eyre::install()?;
@@ -279,8 +255,7 @@ async fn run_ratatui_app(
         use ratatui::text::Line;
 
         let current_version = env!("CARGO_PKG_VERSION");
-        let exe = std::env::current_exe()?;
-        let managed_by_npm = std::env::var_os("CODEX_MANAGED_BY_NPM").is_some();
+        let install_method = codex_common::install_method::InstallMethod::detect();
 
         let mut lines: Vec<Line<'static>> = Vec::new();
         lines.push(Line::from(vec![
@@ -289,29 +264,17 @@ async fn run_ratatui_app(
             format!("{current_version} -> {latest_version}.").into(),
         ]));
 
-        if managed_by_npm {
-            let npm_cmd = "npm install -g @openai/codex@latest";
-            lines.push(Line::from(vec![
-                "Run ".into(),
-                npm_cmd.cyan(),
-                " to update.".into(),
-            ]));
-        } else if cfg!(target_os = "macos")
-            && (exe.starts_with("/opt/homebrew") || exe.starts_with("/usr/local"))
-        {
-            let brew_cmd = "brew upgrade codex";
-            lines.push(Line::from(vec![
-                "Run ".into(),
-                brew_cmd.cyan(),
-                " to update.".into(),
-            ]));
-        } else {
-            lines.push(Line::from(vec![
-                "See ".into(),
-                "https://github.com/openai/codex/releases/latest".cyan(),
-                " for the latest releases and installation options.".into(),
-            ]));
-        }
+        let pinned_version = latest_version
+            .contains('-')
+            .then_some(latest_version.as_str());
+        let update_cmd = install_method
+            .upgrade_command(pinned_version)
+            .unwrap_or_else(|| "codex update".to_string());
+        lines.push(Line::from(vec![
+            "Run ".into(),
+            update_cmd.cyan(),
+            " to update.".into(),
+        ]));
 
         lines.push("".into());
         tui.insert_history_lines(lines);
@@ -322,8 +285,10 @@ async fn run_ratatui_app(
 
     let auth_manager = AuthManager::shared(config.codex_home.clone());
     let login_status = get_login_status(&config);
-    let should_show_onboarding =
-        should_show_onboarding(login_status, &config, should_show_trust_screen);
+    let should_show_trust_screen = pending_trust.is_some();
+    // An attached session runs, and is signed in, elsewhere.
+    let should_show_onboarding = cli.attach.is_none()
+        && should_show_onboarding(login_status, &config, should_show_trust_screen);
     if should_show_onboarding {
         let directory_trust_decision = run_onboarding_app(
             OnboardingScreenArgs {
@@ -336,13 +301,18 @@ async fn run_ratatui_app(
             &mut tui,
         )
         .await?;
-        if let Some(TrustDirectorySelection::Trust) = directory_trust_decision {
-            config.approval_policy = AskForApproval::OnRequest;
-            config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        if let (Some(defaults), Some(selection)) = (pending_trust, directory_trust_decision) {
+            let decision = match selection {
+                TrustDirectorySelection::Trust => TrustDecision::Trusted,
+                TrustDirectorySelection::DontTrust => TrustDecision::Untrusted,
+            };
+            apply_trust_decision(&mut config, decision, defaults);
         }
     }
 
-    let resume_selection = if cli.r#continue {
+    let resume_selection = if let Some(socket) = &cli.attach {
+        resume_picker::ResumeSelection::Attach(socket.clone())
+    } else if cli.r#continue {
         match RolloutRecorder::list_conversations(&config.codex_home, 1, None).await {
             Ok(page) => page
                 .items
@@ -361,7 +331,7 @@ async fn run_ratatui_app(
             other => other,
         }
     } else {
-        resume_picker::ResumeSelection::StartFresh
+        offer_crash_recovery(&mut tui, &config).await?
     };
 
     if should_show_model_rollout_prompt(
@@ -414,6 +384,34 @@ async fn run_ratatui_app(
     app_result
 }
 
+/// If the previous session in this directory did not shut down cleanly, ask
+/// whether to resume it. Declining forgets the interrupted session.
+async fn offer_crash_recovery(
+    tui: &mut Tui,
+    config: &Config,
+) -> color_eyre::Result<resume_picker::ResumeSelection> {
+    let interrupted = match codex_core::find_interrupted_session(&config.codex_home, &config.cwd) {
+        Ok(Some(interrupted)) => interrupted,
+        Ok(None) => return Ok(resume_picker::ResumeSelection::StartFresh),
+        Err(e) => {
+            error!("failed to look for interrupted sessions: {e}");
+            return Ok(resume_picker::ResumeSelection::StartFresh);
+        }
+    };
+
+    match run_recovery_prompt(tui, &interrupted).await? {
+        RecoveryDecision::Resume => Ok(resume_picker::ResumeSelection::Resume(
+            interrupted.marker.rollout_path,
+        )),
+        RecoveryDecision::StartFresh => {
+            if let Err(e) = codex_core::discard_interrupted_session(&interrupted) {
+                error!("failed to discard interrupted session: {e}");
+            }
+            Ok(resume_picker::ResumeSelection::StartFresh)
+        }
+    }
+}
+
 #[expect(
     clippy::print_stderr,
     reason = "TUI should no longer be displayed, so we can write to stderr."
@@ -450,39 +448,64 @@ fn get_login_status(config: &Config) -> LoginStatus {
     }
 }
 
-/// Determine if user has configured a sandbox / approval policy,
-/// or if the current cwd project is trusted, and updates the config
-/// accordingly.
+/// How the session defaults react to the user's trust decision for the cwd.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+struct TrustDefaults {
+    /// An approval policy or sandbox was chosen on the command line, in a
+    /// profile or in config.toml, so trusting the directory keeps it.
+    policy_configured: bool,
+    /// The sandbox was chosen on the command line, so it applies even in an
+    /// untrusted directory.
+    sandbox_overridden: bool,
+}
+
+/// Apply the trust decision recorded for the cwd in `$CODEX_HOME/trusted.json`
+/// (or the legacy `[projects]` table in config.toml). Returns the defaults to
+/// apply once the user has answered the trust prompt if no decision exists
+/// yet, i.e. if the prompt should be shown.
 fn determine_repo_trust_state(
     config: &mut Config,
     config_toml: &ConfigToml,
     approval_policy_overide: Option<AskForApproval>,
     sandbox_mode_override: Option<SandboxMode>,
     config_profile_override: Option<String>,
-) -> std::io::Result<bool> {
+) -> std::io::Result<Option<TrustDefaults>> {
     let config_profile = config_toml.get_config_profile(config_profile_override)?;
+    let defaults = TrustDefaults {
+        policy_configured: approval_policy_overide.is_some()
+            || sandbox_mode_override.is_some()
+            // todo: profile sandbox mode?
+            || config_profile.approval_policy.is_some()
+            || config_toml.approval_policy.is_some()
+            || config_toml.sandbox_mode.is_some(),
+        sandbox_overridden: sandbox_mode_override.is_some(),
+    };
 
-    if approval_policy_overide.is_some() || sandbox_mode_override.is_some() {
-        // if the user has overridden either approval policy or sandbox mode,
-        // skip the trust flow
-        Ok(false)
-    } else if config_profile.approval_policy.is_some() {
-        // if the user has specified settings in a config profile, skip the trust flow
-        // todo: profile sandbox mode?
-        Ok(false)
-    } else if config_toml.approval_policy.is_some() || config_toml.sandbox_mode.is_some() {
-        // if the user has specified either approval policy or sandbox mode in config.toml
-        // skip the trust flow
-        Ok(false)
-    } else if config_toml.is_cwd_trusted(&config.cwd) {
-        // if the current cwd project is trusted and no config has been set
-        // skip the trust flow and set the approval policy and sandbox mode
-        config.approval_policy = AskForApproval::OnRequest;
-        config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
-        Ok(false)
-    } else {
-        // if none of the above conditions are met, show the trust screen
-        Ok(true)
+    let decision = TrustStore::load(&config.codex_home)
+        .decision_for(&config.cwd)
+        .or_else(|| {
+            config_toml
+                .is_cwd_trusted(&config.cwd)
+                .then_some(TrustDecision::Trusted)
+        });
+    match decision {
+        Some(decision) => {
+            apply_trust_decision(config, decision, defaults);
+            Ok(None)
+        }
+        // First run in this directory: ask.
+        None => Ok(Some(defaults)),
+    }
+}
+
+fn apply_trust_decision(config: &mut Config, decision: TrustDecision, defaults: TrustDefaults) {
+    match decision {
+        TrustDecision::Trusted if !defaults.policy_configured => {
+            config.approval_policy = AskForApproval::OnRequest;
+            config.sandbox_policy = SandboxPolicy::new_workspace_write_policy();
+        }
+        TrustDecision::Trusted => {}
+        TrustDecision::Untrusted => lock_down_untrusted(config, defaults.sandbox_overridden),
     }
 }
 
@@ -520,6 +543,7 @@ fn should_show_model_rollout_prompt(
         .unwrap_or(false);
 
     active_profile.is_none()
+        && cli.attach.is_none()
         && debug_high_enabled
         && cli.model.is_none()
         && !swiftfox_model_prompt_seen
@@ -595,4 +619,46 @@ mod tests {
             false,
         ));
     }
+
+    #[test]
+    fn untrusted_directories_are_locked_down_and_new_ones_prompt() {
+        let codex_home = tempfile::TempDir::new().expect("codex home");
+        let project = tempfile::TempDir::new().expect("project");
+        let config_toml = ConfigToml {
+            sandbox_mode: Some(SandboxMode::WorkspaceWrite),
+            ..Default::default()
+        };
+        let load = || {
+            Config::load_from_base_config_with_overrides(
+                config_toml.clone(),
+                ConfigOverrides {
+                    cwd: Some(project.path().to_path_buf()),
+                    ..Default::default()
+                },
+                codex_home.path().to_path_buf(),
+            )
+            .expect("load config")
+        };
+
+        let mut config = load();
+        let pending = determine_repo_trust_state(&mut config, &config_toml, None, None, None)
+            .expect("trust state");
+        assert_eq!(
+            pending,
+            Some(TrustDefaults {
+                policy_configured: true,
+                sandbox_overridden: false,
+            })
+        );
+
+        TrustStore::load(codex_home.path())
+            .record(project.path(), TrustDecision::Untrusted)
+            .expect("record decision");
+        let mut config = load();
+        let pending = determine_repo_trust_state(&mut config, &config_toml, None, None, None)
+            .expect("trust state");
+        assert_eq!(pending, None);
+        assert_eq!(config.sandbox_policy, SandboxPolicy::new_read_only_policy());
+        assert_eq!(config.project_doc_max_bytes, 0);
+    }
 }
@@ -0,0 +1,54 @@
+//! `@server:uri` mentions of MCP resources in a user message.
+
+/// Characters that end a sentence rather than a URI when they trail a
+/// mention, as in "compare @db:tables/users and @db:tables/orders.".
+const TRAILING_PUNCTUATION: &[char] = &['.', ',', ';', ':', '!', '?'];
+
+/// The `(server, uri)` pairs mentioned in `text` as `@server:uri`, for
+/// servers accepted by `is_server`. Mentions of unknown servers are left
+/// alone so `@path:with:colons` file mentions keep working.
+pub(crate) fn mcp_resource_mentions(
+    text: &str,
+    is_server: impl Fn(&str) -> bool,
+) -> Vec<(String, String)> {
+    let mut mentions: Vec<(String, String)> = Vec::new();
+    for word in text.split_whitespace() {
+        let Some((server, uri)) = word
+            .strip_prefix('@')
+            .and_then(|mention| mention.split_once(':'))
+        else {
+            continue;
+        };
+        let uri = uri.trim_end_matches(TRAILING_PUNCTUATION);
+        if uri.is_empty() || !is_server(server) {
+            continue;
+        }
+        let mention = (server.to_string(), uri.to_string());
+        if !mentions.contains(&mention) {
+            mentions.push(mention);
+        }
+    }
+    mentions
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn only_mentions_of_configured_servers_are_resources() {
+        let mentions = mcp_resource_mentions(
+            "compare @fs:file:///notes.txt with @src/main.rs and @db:tables/users. \
+             Again: @fs:file:///notes.txt",
+            |server| matches!(server, "fs" | "db"),
+        );
+        assert_eq!(
+            mentions,
+            vec![
+                ("fs".to_string(), "file:///notes.txt".to_string()),
+                ("db".to_string(), "tables/users".to_string()),
+            ]
+        );
+    }
+}
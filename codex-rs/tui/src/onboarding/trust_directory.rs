@@ -1,7 +1,7 @@
 use std::path::PathBuf;
 
-use codex_core::config::set_project_trusted;
-use codex_core::git_info::resolve_root_git_project_for_trust;
+use codex_core::trust_store::TrustDecision;
+use codex_core::trust_store::TrustStore;
 use crossterm::event::KeyCode;
 use crossterm::event::KeyEvent;
 use ratatui::buffer::Buffer;
@@ -78,7 +78,7 @@ impl WidgetRef for &TrustDirectoryWidget {
             lines.push(create_option(
                 1,
                 TrustDirectorySelection::DontTrust,
-                "No, ask me to approve edits and commands",
+                "No, keep Codex read-only here and ignore this folder's AGENTS.md",
             ));
         } else {
             lines.push(create_option(
@@ -89,10 +89,15 @@ impl WidgetRef for &TrustDirectoryWidget {
             lines.push(create_option(
                 1,
                 TrustDirectorySelection::DontTrust,
-                "Require approval of edits and commands",
+                "Keep Codex read-only here and ignore this folder's AGENTS.md",
             ));
         }
         lines.push("".into());
+        lines.push(
+            Line::from("  Codex remembers your answer in $CODEX_HOME/trusted.json.")
+                .add_modifier(Modifier::DIM),
+        );
+        lines.push("".into());
         if let Some(error) = &self.error {
             lines.push(Line::from(format!("  {error}")).fg(Color::Red));
             lines.push("".into());
@@ -138,18 +143,24 @@ impl StepStateProvider for TrustDirectoryWidget {
 
 impl TrustDirectoryWidget {
     fn handle_trust(&mut self) {
-        let target =
-            resolve_root_git_project_for_trust(&self.cwd).unwrap_or_else(|| self.cwd.clone());
-        if let Err(e) = set_project_trusted(&self.codex_home, &target) {
-            tracing::error!("Failed to set project trusted: {e:?}");
-            self.error = Some(format!("Failed to set trust for {}: {e}", target.display()));
-        }
-
+        self.record(TrustDecision::Trusted);
         self.selection = Some(TrustDirectorySelection::Trust);
     }
 
     fn handle_dont_trust(&mut self) {
         self.highlighted = TrustDirectorySelection::DontTrust;
+        self.record(TrustDecision::Untrusted);
         self.selection = Some(TrustDirectorySelection::DontTrust);
     }
+
+    fn record(&mut self, decision: TrustDecision) {
+        let mut store = TrustStore::load(&self.codex_home);
+        if let Err(e) = store.record(&self.cwd, decision) {
+            tracing::error!("Failed to record trust decision: {e:?}");
+            self.error = Some(format!(
+                "Failed to remember this choice for {}: {e}",
+                self.cwd.display()
+            ));
+        }
+    }
 }
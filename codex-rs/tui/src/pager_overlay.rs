@@ -2,6 +2,7 @@ use std::io::Result;
 use std::sync::Arc;
 use std::time::Duration;
 
+use crate::diff_pane::DiffPane;
 use crate::history_cell::HistoryCell;
 use crate::render::line_utils::push_owned_lines;
 use crate::tui;
@@ -27,8 +28,10 @@ pub(crate) enum Overlay {
 }
 
 impl Overlay {
-    pub(crate) fn new_transcript(cells: Vec<Arc<dyn HistoryCell>>) -> Self {
-        Self::Transcript(TranscriptOverlay::new(cells))
+    pub(crate) fn new_transcript(cells: Vec<Arc<dyn HistoryCell>>, diff_pane: DiffPane) -> Self {
+        let mut overlay = TranscriptOverlay::new(cells);
+        overlay.diff_pane = diff_pane;
+        Self::Transcript(overlay)
     }
 
     pub(crate) fn new_static_with_title(lines: Vec<Line<'static>>, title: String) -> Self {
@@ -351,6 +354,7 @@ pub(crate) struct TranscriptOverlay {
     view: PagerView,
     cells: Vec<Arc<dyn HistoryCell>>,
     highlight_cell: Option<usize>,
+    diff_pane: DiffPane,
     is_done: bool,
 }
 
@@ -364,6 +368,7 @@ impl TranscriptOverlay {
             ),
             cells: transcript_cells,
             highlight_cell: None,
+            diff_pane: DiffPane::default(),
             is_done: false,
         }
     }
@@ -419,6 +424,16 @@ impl TranscriptOverlay {
         }
     }
 
+    pub(crate) fn diff_pane_mut(&mut self) -> &mut DiffPane {
+        &mut self.diff_pane
+    }
+
+    /// Hand the diff pane back when the overlay closes so its contents and
+    /// layout survive until the overlay is opened again.
+    pub(crate) fn into_diff_pane(self) -> DiffPane {
+        self.diff_pane
+    }
+
     fn render_hints(&self, area: Rect, buf: &mut Buffer) {
         let line1 = Rect::new(area.x, area.y, area.width, 1);
         let line2 = Rect::new(area.x, area.y.saturating_add(1), area.width, 1);
@@ -427,6 +442,12 @@ impl TranscriptOverlay {
         if self.highlight_cell.is_some() {
             pairs.push(("⏎", "edit message"));
         }
+        if self.diff_pane.is_visible() {
+            pairs.push(("d", "hide diff"));
+            pairs.push(("[/]", "resize"));
+        } else {
+            pairs.push(("d", "show diff"));
+        }
         render_key_hints(line2, buf, &pairs);
     }
 
@@ -434,7 +455,11 @@ impl TranscriptOverlay {
         let top_h = area.height.saturating_sub(3);
         let top = Rect::new(area.x, area.y, area.width, top_h);
         let bottom = Rect::new(area.x, area.y + top_h, area.width, 3);
-        self.view.render(top, buf);
+        let (transcript, diff) = self.diff_pane.split(top);
+        self.view.render(transcript, buf);
+        if let Some(diff) = diff {
+            self.diff_pane.render(diff, buf);
+        }
         self.render_hints(bottom, buf);
     }
 }
@@ -463,6 +488,21 @@ impl TranscriptOverlay {
                     self.is_done = true;
                     Ok(())
                 }
+                KeyEvent {
+                    code: KeyCode::Char(c @ ('d' | '[' | ']')),
+                    kind: KeyEventKind::Press,
+                    ..
+                } => {
+                    match c {
+                        'd' => self.diff_pane.toggle(),
+                        '[' => self.diff_pane.shrink(),
+                        _ => self.diff_pane.grow(),
+                    }
+                    // Wrapping depends on the transcript's width.
+                    self.view.wrap_cache = None;
+                    tui.frame_requester().schedule_frame();
+                    Ok(())
+                }
                 other => self.view.handle_key_event(tui, other),
             },
             TuiEvent::Draw => {
@@ -590,6 +630,33 @@ mod tests {
         );
     }
 
+    #[test]
+    fn diff_pane_is_rendered_beside_transcript() {
+        let mut overlay = TranscriptOverlay::new(vec![Arc::new(TestCell {
+            lines: vec![Line::from("hello")],
+        })]);
+        overlay
+            .diff_pane_mut()
+            .on_event(&codex_core::protocol::EventMsg::TurnDiff(
+                codex_core::protocol::TurnDiffEvent {
+                    unified_diff: "diff --git a/a.txt b/a.txt\n+added\n".to_string(),
+                },
+            ));
+        overlay.diff_pane_mut().toggle();
+
+        let area = Rect::new(0, 0, 60, 10);
+        let mut buf = Buffer::empty(area);
+        overlay.render(area, &mut buf);
+        let row = |y: u16| -> String { (0..area.width).map(|x| buf[(x, y)].symbol()).collect() };
+
+        assert!(row(0).starts_with("/ T R A N S C R I P T"));
+        assert!(row(0).contains("│/ D I F F"));
+        assert!(row(1).starts_with("hello"));
+        assert!(row(1).contains("│diff --git a/a.txt b/a.txt"));
+        assert!(row(2).contains("│+added"));
+        assert!(row(8).contains("hide diff"));
+    }
+
     #[test]
     fn transcript_overlay_snapshot_basic() {
         // Prepare a transcript overlay with a few lines
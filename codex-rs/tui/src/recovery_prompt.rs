@@ -0,0 +1,150 @@
+use crate::tui::FrameRequester;
+use crate::tui::Tui;
+use crate::tui::TuiEvent;
+use codex_core::InterruptedSession;
+use color_eyre::eyre::Result;
+use crossterm::event::KeyCode;
+use crossterm::event::KeyEvent;
+use ratatui::buffer::Buffer;
+use ratatui::layout::Rect;
+use ratatui::prelude::Widget;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::widgets::Clear;
+use ratatui::widgets::Paragraph;
+use ratatui::widgets::WidgetRef;
+use ratatui::widgets::Wrap;
+use tokio_stream::StreamExt;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub(crate) enum RecoveryDecision {
+    Resume,
+    StartFresh,
+}
+
+struct RecoveryPrompt<'a> {
+    session: &'a InterruptedSession,
+    highlighted: RecoveryDecision,
+    decision: Option<RecoveryDecision>,
+    request_frame: FrameRequester,
+}
+
+impl<'a> RecoveryPrompt<'a> {
+    fn new(session: &'a InterruptedSession, request_frame: FrameRequester) -> Self {
+        Self {
+            session,
+            highlighted: RecoveryDecision::Resume,
+            decision: None,
+            request_frame,
+        }
+    }
+
+    fn handle_key_event(&mut self, key_event: KeyEvent) {
+        match key_event.code {
+            KeyCode::Up | KeyCode::Char('k') => self.highlight(RecoveryDecision::Resume),
+            KeyCode::Down | KeyCode::Char('j') => self.highlight(RecoveryDecision::StartFresh),
+            KeyCode::Char('1') | KeyCode::Char('y') => self.select(RecoveryDecision::Resume),
+            KeyCode::Char('2') | KeyCode::Char('n') => self.select(RecoveryDecision::StartFresh),
+            KeyCode::Enter => self.select(self.highlighted),
+            KeyCode::Esc => self.select(RecoveryDecision::StartFresh),
+            _ => {}
+        }
+    }
+
+    fn highlight(&mut self, option: RecoveryDecision) {
+        if self.highlighted != option {
+            self.highlighted = option;
+            self.request_frame.schedule_frame();
+        }
+    }
+
+    fn select(&mut self, option: RecoveryDecision) {
+        self.decision = Some(option);
+        self.request_frame.schedule_frame();
+    }
+}
+
+impl WidgetRef for &RecoveryPrompt<'_> {
+    fn render_ref(&self, area: Rect, buf: &mut Buffer) {
+        Clear.render(area, buf);
+
+        let mut lines: Vec<Line> = vec![
+            String::new().into(),
+            "   Codex did not exit cleanly last time you used it in this directory."
+                .bold()
+                .into(),
+        ];
+        if let Some(patch) = &self.session.marker.pending_patch {
+            let count = patch.files.len();
+            lines.push(
+                format!("   It was in the middle of applying a patch to {count} file(s); resuming will reconcile them.")
+                    .dim()
+                    .into(),
+            );
+        }
+        lines.push("".into());
+
+        let create_option = |index: usize, option: RecoveryDecision, text: &str| -> Line<'static> {
+            if self.highlighted == option {
+                Line::from(vec![
+                    format!("> {}. ", index + 1).cyan(),
+                    text.to_owned().cyan(),
+                ])
+            } else {
+                format!("  {}. {text}", index + 1).into()
+            }
+        };
+
+        lines.push(create_option(
+            0,
+            RecoveryDecision::Resume,
+            "Resume the interrupted session",
+        ));
+        lines.push(create_option(
+            1,
+            RecoveryDecision::StartFresh,
+            "Start a new session",
+        ));
+        lines.push("".into());
+        lines.push(
+            "  Press Enter to confirm or Esc to start a new session"
+                .dim()
+                .into(),
+        );
+
+        Paragraph::new(lines)
+            .wrap(Wrap { trim: false })
+            .render(area, buf);
+    }
+}
+
+pub(crate) async fn run_recovery_prompt(
+    tui: &mut Tui,
+    session: &InterruptedSession,
+) -> Result<RecoveryDecision> {
+    let mut prompt = RecoveryPrompt::new(session, tui.frame_requester());
+
+    tui.draw(u16::MAX, |frame| {
+        frame.render_widget_ref(&prompt, frame.area());
+    })?;
+
+    let events = tui.event_stream();
+    tokio::pin!(events);
+    while prompt.decision.is_none() {
+        if let Some(event) = events.next().await {
+            match event {
+                TuiEvent::Key(key_event) => prompt.handle_key_event(key_event),
+                TuiEvent::Draw => {
+                    let _ = tui.draw(u16::MAX, |frame| {
+                        frame.render_widget_ref(&prompt, frame.area());
+                    });
+                }
+                _ => {}
+            }
+        } else {
+            break;
+        }
+    }
+
+    Ok(prompt.decision.unwrap_or(RecoveryDecision::StartFresh))
+}
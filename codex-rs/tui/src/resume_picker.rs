@@ -33,6 +33,8 @@ const PAGE_SIZE: usize = 25;
 pub enum ResumeSelection {
     StartFresh,
     Resume(PathBuf),
+    /// Attach to a session running in another process through its socket.
+    Attach(PathBuf),
     Exit,
 }
 
@@ -253,6 +255,19 @@ impl PickerState {
     }
 }
 
+/// The `limit` most recent sessions as `(rollout path, preview)` pairs, for
+/// the command palette.
+pub(crate) async fn recent_sessions(
+    codex_home: &Path,
+    limit: usize,
+) -> Result<Vec<(PathBuf, String)>> {
+    let page = RolloutRecorder::list_conversations(codex_home, limit, None).await?;
+    Ok(to_rows(page)
+        .into_iter()
+        .map(|row| (row.path, row.preview))
+        .collect())
+}
+
 fn to_rows(page: ConversationsPage) -> Vec<Row> {
     page.items.into_iter().map(|it| head_to_row(&it)).collect()
 }
@@ -0,0 +1,357 @@
+//! `/settings`: the effective configuration, grouped by section, and the
+//! common values that can be changed from the TUI. Changes apply to the
+//! running session and are written back to `config.toml` with
+//! [`codex_core::config_edit`], which keeps comments and formatting.
+
+use std::path::Path;
+
+use codex_core::config::Config;
+use codex_core::config_edit::CONFIG_KEY_EFFORT;
+use codex_core::config_edit::CONFIG_KEY_MODEL;
+use codex_core::config_edit::persist_overrides_and_clear_if_none;
+use codex_core::protocol::AskForApproval;
+use codex_core::protocol::SandboxPolicy;
+use codex_core::protocol_config_types::ReasoningEffort;
+use codex_core::protocol_config_types::SandboxMode;
+
+const CONFIG_KEY_APPROVAL_POLICY: &str = "approval_policy";
+const CONFIG_KEY_SANDBOX_MODE: &str = "sandbox_mode";
+
+/// A value that can be changed from `/settings`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Setting {
+    /// Model and reasoning effort, chosen together from the model presets.
+    Model,
+    ApprovalPolicy,
+    SandboxMode,
+}
+
+/// A new value for a [`Setting`].
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum SettingChange {
+    Model {
+        model: String,
+        effort: Option<ReasoningEffort>,
+    },
+    ApprovalPolicy(AskForApproval),
+    SandboxMode(SandboxMode),
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SettingsEntry {
+    pub name: &'static str,
+    pub value: String,
+    /// What choosing the entry changes; `None` for values shown for
+    /// reference only.
+    pub setting: Option<Setting>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct SettingsSection {
+    pub title: &'static str,
+    pub entries: Vec<SettingsEntry>,
+}
+
+/// The settings screen for `config`.
+pub(crate) fn settings_sections(config: &Config) -> Vec<SettingsSection> {
+    let entry = |name, value: String, setting| SettingsEntry {
+        name,
+        value,
+        setting,
+    };
+    let effort = config
+        .model_reasoning_effort
+        .map(|effort| effort.to_string())
+        .unwrap_or_else(|| "none".to_string());
+    let sandbox = match &config.sandbox_policy {
+        SandboxPolicy::WorkspaceWrite {
+            network_access: true,
+            ..
+        } => "workspace-write (network enabled)".to_string(),
+        policy => policy.to_string(),
+    };
+    vec![
+        SettingsSection {
+            title: "Model",
+            entries: vec![
+                entry("model", config.model.clone(), Some(Setting::Model)),
+                entry("reasoning effort", effort, Some(Setting::Model)),
+                entry("provider", config.model_provider_id.clone(), None),
+            ],
+        },
+        SettingsSection {
+            title: "Permissions",
+            entries: vec![
+                entry(
+                    "approval policy",
+                    config.approval_policy.to_string(),
+                    Some(Setting::ApprovalPolicy),
+                ),
+                entry("sandbox", sandbox, Some(Setting::SandboxMode)),
+            ],
+        },
+        SettingsSection {
+            title: "Session",
+            entries: vec![
+                entry(
+                    "profile",
+                    config
+                        .active_profile
+                        .clone()
+                        .unwrap_or_else(|| "none".to_string()),
+                    None,
+                ),
+                entry("working directory", config.cwd.display().to_string(), None),
+                entry(
+                    "config file",
+                    config.codex_home.join("config.toml").display().to_string(),
+                    None,
+                ),
+            ],
+        },
+    ]
+}
+
+/// Approval policies offered by `/settings`, with what they mean.
+pub(crate) fn approval_policy_choices() -> Vec<(AskForApproval, &'static str)> {
+    vec![
+        (
+            AskForApproval::UnlessTrusted,
+            "ask before running anything but known read-only commands",
+        ),
+        (
+            AskForApproval::OnFailure,
+            "run commands in the sandbox; ask when one fails",
+        ),
+        (AskForApproval::OnRequest, "the model decides when to ask"),
+        (
+            AskForApproval::Never,
+            "never ask; failures go back to the model",
+        ),
+    ]
+}
+
+/// Sandbox modes offered by `/settings`, with what they mean.
+pub(crate) fn sandbox_mode_choices() -> Vec<(SandboxMode, &'static str)> {
+    vec![
+        (
+            SandboxMode::ReadOnly,
+            "commands can read files but not write them",
+        ),
+        (
+            SandboxMode::WorkspaceWrite,
+            "commands can write in the workspace; no network",
+        ),
+        (
+            SandboxMode::DangerFullAccess,
+            "no sandbox; commands can do anything",
+        ),
+    ]
+}
+
+/// The mode `policy` runs in.
+pub(crate) fn sandbox_mode(policy: &SandboxPolicy) -> SandboxMode {
+    match policy {
+        SandboxPolicy::ReadOnly => SandboxMode::ReadOnly,
+        SandboxPolicy::WorkspaceWrite { .. } => SandboxMode::WorkspaceWrite,
+        SandboxPolicy::DangerFullAccess => SandboxMode::DangerFullAccess,
+    }
+}
+
+/// The policy to switch to for `mode`. The current policy is kept when it
+/// already runs in `mode`; a switch to `workspace-write` picks up the
+/// `[sandbox_workspace_write]` settings.
+pub(crate) fn sandbox_policy_for(mode: SandboxMode, config: &Config) -> SandboxPolicy {
+    if sandbox_mode(&config.sandbox_policy) == mode {
+        return config.sandbox_policy.clone();
+    }
+    match mode {
+        SandboxMode::ReadOnly => SandboxPolicy::new_read_only_policy(),
+        SandboxMode::WorkspaceWrite => config.workspace_write_policy.clone(),
+        SandboxMode::DangerFullAccess => SandboxPolicy::DangerFullAccess,
+    }
+}
+
+/// Why `change` cannot be made to the session running with `config`.
+pub(crate) fn validate(change: &SettingChange, config: &Config) -> Result<(), String> {
+    match change {
+        SettingChange::Model { model, .. } if model.trim().is_empty() => {
+            Err("the model cannot be empty".to_string())
+        }
+        SettingChange::ApprovalPolicy(_) | SettingChange::SandboxMode(_) if config.read_only => {
+            Err("this session is read-only; permissions cannot be changed".to_string())
+        }
+        SettingChange::Model { .. }
+        | SettingChange::ApprovalPolicy(_)
+        | SettingChange::SandboxMode(_) => Ok(()),
+    }
+}
+
+/// The `config.toml` keys `change` writes; `None` removes a key.
+fn config_overrides(change: &SettingChange) -> Vec<(&'static str, Option<String>)> {
+    match change {
+        SettingChange::Model { model, effort } => vec![
+            (CONFIG_KEY_MODEL, Some(model.clone())),
+            (CONFIG_KEY_EFFORT, effort.map(|effort| effort.to_string())),
+        ],
+        SettingChange::ApprovalPolicy(policy) => {
+            vec![(CONFIG_KEY_APPROVAL_POLICY, Some(policy.to_string()))]
+        }
+        SettingChange::SandboxMode(mode) => {
+            vec![(CONFIG_KEY_SANDBOX_MODE, Some(mode.to_string()))]
+        }
+    }
+}
+
+/// Write `change` to `config.toml`, into `profile` when one is active.
+pub(crate) async fn persist_setting(
+    codex_home: &Path,
+    profile: Option<&str>,
+    change: &SettingChange,
+) -> anyhow::Result<()> {
+    let overrides = config_overrides(change);
+    let segments: Vec<[&str; 1]> = overrides.iter().map(|(key, _)| [*key]).collect();
+    let overrides: Vec<(&[&str], Option<&str>)> = segments
+        .iter()
+        .zip(&overrides)
+        .map(|(segments, (_, value))| (segments.as_slice(), value.as_deref()))
+        .collect();
+    persist_overrides_and_clear_if_none(codex_home, profile, &overrides).await
+}
+
+/// How a saved change is reported, e.g. `approval_policy = "never"`.
+pub(crate) fn describe_change(change: &SettingChange) -> String {
+    config_overrides(change)
+        .into_iter()
+        .map(|(key, value)| match value {
+            Some(value) => format!("{key} = \"{value}\""),
+            None => format!("{key} unset"),
+        })
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use codex_core::config::ConfigOverrides;
+    use codex_core::config::ConfigToml;
+    use codex_core::config_types::SandboxWorkspaceWrite;
+    use pretty_assertions::assert_eq;
+
+    fn test_config() -> Config {
+        Config::load_from_base_config_with_overrides(
+            ConfigToml::default(),
+            ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .expect("config")
+    }
+
+    #[test]
+    fn sections_mark_what_can_be_changed() {
+        let mut config = test_config();
+        config.approval_policy = AskForApproval::Never;
+        config.sandbox_policy = SandboxPolicy::WorkspaceWrite {
+            writable_roots: Vec::new(),
+            network_access: true,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+        };
+
+        let sections = settings_sections(&config);
+        let titles: Vec<&str> = sections.iter().map(|section| section.title).collect();
+        assert_eq!(titles, vec!["Model", "Permissions", "Session"]);
+        assert_eq!(
+            sections[1].entries,
+            vec![
+                SettingsEntry {
+                    name: "approval policy",
+                    value: "never".to_string(),
+                    setting: Some(Setting::ApprovalPolicy),
+                },
+                SettingsEntry {
+                    name: "sandbox",
+                    value: "workspace-write (network enabled)".to_string(),
+                    setting: Some(Setting::SandboxMode),
+                },
+            ]
+        );
+        assert!(
+            sections[2]
+                .entries
+                .iter()
+                .all(|entry| entry.setting.is_none())
+        );
+    }
+
+    #[test]
+    fn changes_are_validated_and_mapped_to_config_keys() {
+        let mut config = test_config();
+        let never = SettingChange::ApprovalPolicy(AskForApproval::Never);
+        assert_eq!(validate(&never, &config), Ok(()));
+        config.read_only = true;
+        assert!(validate(&never, &config).is_err());
+
+        let model = SettingChange::Model {
+            model: "gpt-5".to_string(),
+            effort: None,
+        };
+        assert_eq!(
+            describe_change(&model),
+            "model = \"gpt-5\", model_reasoning_effort unset"
+        );
+        assert_eq!(
+            describe_change(&SettingChange::SandboxMode(SandboxMode::WorkspaceWrite)),
+            "sandbox_mode = \"workspace-write\""
+        );
+        assert_eq!(describe_change(&never), "approval_policy = \"never\"");
+    }
+
+    #[test]
+    fn switching_sandbox_mode_keeps_current_workspace_settings() {
+        let mut config = test_config();
+        let current = SandboxPolicy::WorkspaceWrite {
+            writable_roots: vec!["/data".into()],
+            network_access: true,
+            exclude_tmpdir_env_var: false,
+            exclude_slash_tmp: false,
+        };
+        config.sandbox_policy = current.clone();
+        assert_eq!(
+            sandbox_policy_for(SandboxMode::WorkspaceWrite, &config),
+            current
+        );
+        assert_eq!(
+            sandbox_policy_for(SandboxMode::ReadOnly, &config),
+            SandboxPolicy::new_read_only_policy()
+        );
+    }
+
+    #[test]
+    fn switching_to_workspace_write_applies_its_config_table() {
+        let cfg = ConfigToml {
+            sandbox_mode: Some(SandboxMode::ReadOnly),
+            sandbox_workspace_write: Some(SandboxWorkspaceWrite {
+                network_access: true,
+                ..Default::default()
+            }),
+            ..Default::default()
+        };
+        let config = Config::load_from_base_config_with_overrides(
+            cfg,
+            ConfigOverrides::default(),
+            std::env::temp_dir(),
+        )
+        .expect("config");
+        assert_eq!(
+            sandbox_policy_for(SandboxMode::WorkspaceWrite, &config),
+            SandboxPolicy::WorkspaceWrite {
+                writable_roots: Vec::new(),
+                network_access: true,
+                exclude_tmpdir_env_var: false,
+                exclude_slash_tmp: false,
+            }
+        );
+    }
+}
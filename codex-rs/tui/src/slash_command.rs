@@ -14,13 +14,17 @@ pub enum SlashCommand {
     // more frequently used commands should be listed first.
     Model,
     Approvals,
+    Settings,
     New,
     Init,
     Compact,
     Diff,
     Mention,
+    AttachCmd,
     Status,
     Mcp,
+    Prompt,
+    Log,
     Logout,
     Quit,
     #[cfg(debug_assertions)]
@@ -37,10 +41,14 @@ impl SlashCommand {
             SlashCommand::Quit => "exit Codex",
             SlashCommand::Diff => "show git diff (including untracked files)",
             SlashCommand::Mention => "mention a file",
+            SlashCommand::AttachCmd => "run a command and attach its output to your next message",
             SlashCommand::Status => "show current session configuration and token usage",
             SlashCommand::Model => "choose what model and reasoning effort to use",
             SlashCommand::Approvals => "choose what Codex can do without approval",
-            SlashCommand::Mcp => "list configured MCP tools",
+            SlashCommand::Settings => "view the configuration and change common settings",
+            SlashCommand::Mcp => "list MCP tools, resources and prompts",
+            SlashCommand::Prompt => "show the instructions, context and tools sent to the model",
+            SlashCommand::Log => "show or change log levels, e.g. /log level debug",
             SlashCommand::Logout => "log out of Codex",
             #[cfg(debug_assertions)]
             SlashCommand::TestApproval => "test approval request",
@@ -61,11 +69,15 @@ impl SlashCommand {
             | SlashCommand::Compact
             | SlashCommand::Model
             | SlashCommand::Approvals
+            | SlashCommand::Settings
             | SlashCommand::Logout => false,
             SlashCommand::Diff
             | SlashCommand::Mention
+            | SlashCommand::AttachCmd
             | SlashCommand::Status
             | SlashCommand::Mcp
+            | SlashCommand::Prompt
+            | SlashCommand::Log
             | SlashCommand::Quit => true,
 
             #[cfg(debug_assertions)]
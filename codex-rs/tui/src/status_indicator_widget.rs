@@ -1,5 +1,6 @@
 //! A live status indicator that shows the *latest* log line emitted by the
-//! application while the agent is processing a long‑running task.
+//! application while the agent is processing a long‑running task, with the
+//! task's sub-tasks drawn as a tree beneath it.
 
 use std::time::Duration;
 use std::time::Instant;
@@ -16,6 +17,7 @@ use crate::app_event::AppEvent;
 use crate::app_event_sender::AppEventSender;
 use crate::key_hint;
 use crate::shimmer::shimmer_spans;
+use crate::subtask_tree::SubtaskTree;
 use crate::tui::FrameRequester;
 
 pub(crate) struct StatusIndicatorWidget {
@@ -23,6 +25,10 @@ pub(crate) struct StatusIndicatorWidget {
     header: String,
     /// Queued user messages to display under the status line.
     queued_messages: Vec<String>,
+    /// Sub-tasks of the running task, drawn between the status line and the
+    /// queued messages.
+    subtasks: SubtaskTree,
+    subtasks_expanded: bool,
 
     elapsed_running: Duration,
     last_resume_at: Instant,
@@ -33,7 +39,7 @@ pub(crate) struct StatusIndicatorWidget {
 
 // Format elapsed seconds into a compact human-friendly form used by the status line.
 // Examples: 0s, 59s, 1m 00s, 59m 59s, 1h 00m 00s, 2h 03m 09s
-fn fmt_elapsed_compact(elapsed_secs: u64) -> String {
+pub(crate) fn fmt_elapsed_compact(elapsed_secs: u64) -> String {
     if elapsed_secs < 60 {
         return format!("{elapsed_secs}s");
     }
@@ -53,6 +59,8 @@ impl StatusIndicatorWidget {
         Self {
             header: String::from("Working"),
             queued_messages: Vec::new(),
+            subtasks: SubtaskTree::default(),
+            subtasks_expanded: false,
             elapsed_running: Duration::ZERO,
             last_resume_at: Instant::now(),
             is_paused: false,
@@ -67,6 +75,7 @@ impl StatusIndicatorWidget {
         // + optional ellipsis line per truncated message + 1 spacer line
         let inner_width = width.max(1) as usize;
         let mut total: u16 = 1; // status line
+        total = total.saturating_add(self.subtasks.lines(self.subtasks_expanded).len() as u16);
         let text_width = inner_width.saturating_sub(3); // account for " ↳ " prefix
         if text_width > 0 {
             for q in &self.queued_messages {
@@ -105,6 +114,20 @@ impl StatusIndicatorWidget {
         self.frame_requester.schedule_frame();
     }
 
+    pub(crate) fn subtasks_mut(&mut self) -> &mut SubtaskTree {
+        &mut self.subtasks
+    }
+
+    pub(crate) fn has_subtasks(&self) -> bool {
+        !self.subtasks.is_empty()
+    }
+
+    /// Show every sub-task rather than the most recent few.
+    pub(crate) fn set_subtasks_expanded(&mut self, expanded: bool) {
+        self.subtasks_expanded = expanded;
+        self.frame_requester.schedule_frame();
+    }
+
     pub(crate) fn pause_timer(&mut self) {
         self.pause_timer_at(Instant::now());
     }
@@ -168,6 +191,7 @@ impl WidgetRef for StatusIndicatorWidget {
         // Build lines: status, then queued messages, then spacer.
         let mut lines: Vec<Line<'static>> = Vec::new();
         lines.push(Line::from(spans));
+        lines.extend(self.subtasks.lines(self.subtasks_expanded));
         // Wrap queued messages using textwrap and show up to the first 3 lines per message.
         let text_width = area.width.saturating_sub(3); // " ↳ " prefix
         for q in &self.queued_messages {
@@ -0,0 +1,252 @@
+//! The sub-tasks of the running task, drawn as a tree under the status line.
+//!
+//! Collapsed, finished sub-tasks hide their children and only the most
+//! recent rows are shown; expanded, the whole tree is.
+
+use std::time::Duration;
+use std::time::Instant;
+
+use codex_core::protocol::SubtaskBeginEvent;
+use codex_core::protocol::SubtaskEndEvent;
+use codex_core::protocol::SubtaskProgressEvent;
+use codex_core::protocol::SubtaskStatus;
+use ratatui::style::Stylize;
+use ratatui::text::Line;
+use ratatui::text::Span;
+
+use crate::key_hint;
+use crate::status_indicator_widget::fmt_elapsed_compact;
+
+/// Rows shown while the tree is collapsed, not counting the hint row.
+const COLLAPSED_ROWS: usize = 4;
+
+#[derive(Debug, Default)]
+pub(crate) struct SubtaskTree {
+    /// Every sub-task seen, in the order it began.
+    nodes: Vec<SubtaskNode>,
+}
+
+#[derive(Debug)]
+struct SubtaskNode {
+    id: String,
+    parent_id: Option<String>,
+    title: String,
+    progress: Option<String>,
+    started_at: Instant,
+    finished: Option<(SubtaskStatus, Duration)>,
+}
+
+impl SubtaskTree {
+    pub(crate) fn is_empty(&self) -> bool {
+        self.nodes.is_empty()
+    }
+
+    pub(crate) fn on_begin(&mut self, ev: SubtaskBeginEvent) {
+        self.on_begin_at(ev, Instant::now());
+    }
+
+    pub(crate) fn on_progress(&mut self, ev: SubtaskProgressEvent) {
+        if let Some(node) = self.node_mut(&ev.id) {
+            node.progress = Some(ev.message);
+        }
+    }
+
+    pub(crate) fn on_end(&mut self, ev: SubtaskEndEvent) {
+        self.on_end_at(ev, Instant::now());
+    }
+
+    fn on_begin_at(&mut self, ev: SubtaskBeginEvent, now: Instant) {
+        let SubtaskBeginEvent {
+            id,
+            parent_id,
+            title,
+        } = ev;
+        // A sub-task that begins again (a plan that gained steps after it
+        // finished) keeps its place and its start time.
+        if let Some(node) = self.node_mut(&id) {
+            node.title = title;
+            node.finished = None;
+            return;
+        }
+        self.nodes.push(SubtaskNode {
+            id,
+            parent_id,
+            title,
+            progress: None,
+            started_at: now,
+            finished: None,
+        });
+    }
+
+    fn on_end_at(&mut self, ev: SubtaskEndEvent, now: Instant) {
+        if let Some(node) = self.node_mut(&ev.id) {
+            node.progress = None;
+            node.finished = Some((ev.status, now.saturating_duration_since(node.started_at)));
+        }
+    }
+
+    fn node_mut(&mut self, id: &str) -> Option<&mut SubtaskNode> {
+        self.nodes.iter_mut().find(|node| node.id == id)
+    }
+
+    /// The rows to draw, each already indented for its depth.
+    pub(crate) fn lines(&self, expanded: bool) -> Vec<Line<'static>> {
+        self.lines_at(expanded, Instant::now())
+    }
+
+    fn lines_at(&self, expanded: bool, now: Instant) -> Vec<Line<'static>> {
+        let mut rows = Vec::new();
+        for root in self.children(None) {
+            self.push_rows(root, 0, expanded, now, &mut rows);
+        }
+        if expanded || rows.len() <= COLLAPSED_ROWS {
+            return rows;
+        }
+        let hidden = rows.len() - COLLAPSED_ROWS;
+        let mut lines = vec![Line::from(vec![
+            format!("   … {hidden} earlier, ").dim(),
+            key_hint::ctrl('G'),
+            " to expand".dim(),
+        ])];
+        lines.extend(rows.drain(hidden..));
+        lines
+    }
+
+    fn children<'a>(&'a self, parent_id: Option<&'a str>) -> impl Iterator<Item = &'a SubtaskNode> {
+        self.nodes.iter().filter(move |node| {
+            node.parent_id.as_deref() == parent_id
+                // Orphans, whose parent never began, are shown at the top level.
+                || (parent_id.is_none()
+                    && node
+                        .parent_id
+                        .as_deref()
+                        .is_some_and(|parent| !self.nodes.iter().any(|n| n.id == parent)))
+        })
+    }
+
+    fn push_rows(
+        &self,
+        node: &SubtaskNode,
+        depth: usize,
+        expanded: bool,
+        now: Instant,
+        rows: &mut Vec<Line<'static>>,
+    ) {
+        let indent = format!("   {}", "  ".repeat(depth));
+        let (elapsed, glyph) = match node.finished {
+            None => (
+                now.saturating_duration_since(node.started_at),
+                "•".cyan().bold(),
+            ),
+            Some((SubtaskStatus::Completed, elapsed)) => (elapsed, "✔".green()),
+            Some((SubtaskStatus::Cancelled, elapsed)) => (elapsed, "–".dim()),
+        };
+        let title: Span<'static> = match node.finished {
+            None => node.title.clone().into(),
+            Some(_) => node.title.clone().dim(),
+        };
+        let mut spans = vec![
+            indent.into(),
+            glyph,
+            " ".into(),
+            title,
+            format!(" ({})", fmt_elapsed_compact(elapsed.as_secs())).dim(),
+        ];
+        if let Some(progress) = &node.progress {
+            let first_line = progress.lines().next().unwrap_or_default();
+            spans.push(format!(" · {first_line}").dim().italic());
+        }
+        rows.push(Line::from(spans));
+
+        if node.finished.is_some() && !expanded {
+            return;
+        }
+        for child in self.children(Some(&node.id)) {
+            self.push_rows(child, depth + 1, expanded, now, rows);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn begin(tree: &mut SubtaskTree, id: &str, parent: Option<&str>, title: &str, at: Instant) {
+        tree.on_begin_at(
+            SubtaskBeginEvent {
+                id: id.to_string(),
+                parent_id: parent.map(str::to_string),
+                title: title.to_string(),
+            },
+            at,
+        );
+    }
+
+    fn end(tree: &mut SubtaskTree, id: &str, at: Instant) {
+        tree.on_end_at(
+            SubtaskEndEvent {
+                id: id.to_string(),
+                status: SubtaskStatus::Completed,
+            },
+            at,
+        );
+    }
+
+    fn text(lines: Vec<Line<'static>>) -> Vec<String> {
+        lines
+            .into_iter()
+            .map(|line| line.spans.into_iter().map(|s| s.content).collect())
+            .collect()
+    }
+
+    #[test]
+    fn finished_subtasks_collapse_until_expanded() {
+        let t0 = Instant::now();
+        let secs = |s| t0 + Duration::from_secs(s);
+        let mut tree = SubtaskTree::default();
+        begin(&mut tree, "plan", None, "Plan", t0);
+        begin(&mut tree, "a", Some("plan"), "Read code", t0);
+        begin(&mut tree, "a/1", Some("a"), "Grep", t0);
+        end(&mut tree, "a/1", secs(2));
+        end(&mut tree, "a", secs(5));
+        begin(&mut tree, "b", Some("plan"), "Write tests", secs(5));
+        tree.on_progress(SubtaskProgressEvent {
+            id: "b".to_string(),
+            message: "cargo test\n--all".to_string(),
+        });
+
+        assert_eq!(
+            text(tree.lines_at(false, secs(65))),
+            vec![
+                "   • Plan (1m 05s)",
+                "     ✔ Read code (5s)",
+                "     • Write tests (1m 00s) · cargo test",
+            ]
+        );
+        assert_eq!(
+            text(tree.lines_at(true, secs(65))),
+            vec![
+                "   • Plan (1m 05s)",
+                "     ✔ Read code (5s)",
+                "       ✔ Grep (2s)",
+                "     • Write tests (1m 00s) · cargo test",
+            ]
+        );
+
+        for (i, step) in ["c", "d"].into_iter().enumerate() {
+            begin(&mut tree, step, Some("plan"), step, secs(65));
+            end(&mut tree, step, secs(66 + i as u64));
+        }
+        assert_eq!(
+            text(tree.lines_at(false, secs(70))),
+            vec![
+                "   … 1 earlier, ⌃G to expand",
+                "     ✔ Read code (5s)",
+                "     • Write tests (1m 05s) · cargo test",
+                "     ✔ c (1s)",
+                "     ✔ d (2s)",
+            ]
+        );
+    }
+}
@@ -1,5 +1,3 @@
-#![cfg(any(not(debug_assertions), test))]
-
 use chrono::DateTime;
 use chrono::Duration;
 use chrono::Utc;
@@ -9,35 +7,47 @@ use std::path::Path;
 use std::path::PathBuf;
 
 use codex_core::config::Config;
+use codex_core::config_types::UpdateChannel;
 use codex_core::default_client::create_client;
 
 use crate::version::CODEX_CLI_VERSION;
 
 pub fn get_upgrade_version(config: &Config) -> Option<String> {
+    // Update checks talk to GitHub; never do that in offline mode.
+    if config.offline.is_some() {
+        return None;
+    }
+
+    let channel = config.update_channel;
     let version_file = version_filepath(config);
     let info = read_version_info(&version_file).ok();
 
     if match &info {
         None => true,
-        Some(info) => info.last_checked_at < Utc::now() - Duration::hours(20),
+        Some(info) => {
+            info.channel != channel || info.last_checked_at < Utc::now() - Duration::hours(20)
+        }
     } {
         // Refresh the cached latest version in the background so TUI startup
         // isn’t blocked by a network call. The UI reads the previously cached
         // value (if any) for this run; the next run shows the banner if needed.
         tokio::spawn(async move {
-            check_for_update(&version_file)
+            check_for_update(&version_file, channel)
                 .await
                 .inspect_err(|e| tracing::error!("Failed to update version: {e}"))
         });
     }
 
-    info.and_then(|info| {
-        if is_newer(&info.latest_version, CODEX_CLI_VERSION).unwrap_or(false) {
-            Some(info.latest_version)
-        } else {
-            None
-        }
-    })
+    info.filter(|info| info.channel == channel)
+        .and_then(|info| {
+            if is_newer_on_channel(&info.latest_version, CODEX_CLI_VERSION, channel)
+                .unwrap_or(false)
+            {
+                Some(info.latest_version)
+            } else {
+                None
+            }
+        })
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -45,15 +55,44 @@ struct VersionInfo {
     latest_version: String,
     // ISO-8601 timestamp (RFC3339)
     last_checked_at: DateTime<Utc>,
+    #[serde(default)]
+    channel: UpdateChannel,
 }
 
 #[derive(Deserialize, Debug, Clone)]
 struct ReleaseInfo {
     tag_name: String,
+    #[serde(default)]
+    draft: bool,
+    #[serde(default)]
+    assets: Vec<ReleaseAsset>,
+}
+
+/// A downloadable file attached to a GitHub release.
+#[derive(Deserialize, Debug, Clone, PartialEq, Eq)]
+pub struct ReleaseAsset {
+    pub name: String,
+    pub browser_download_url: String,
+}
+
+/// The newest published release on a channel.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Release {
+    /// Version without the `rust-v` tag prefix, e.g. `0.40.0` or `0.41.0-beta.1`.
+    pub version: String,
+    pub assets: Vec<ReleaseAsset>,
+}
+
+impl Release {
+    pub fn asset(&self, name: &str) -> Option<&ReleaseAsset> {
+        self.assets.iter().find(|asset| asset.name == name)
+    }
 }
 
 const VERSION_FILENAME: &str = "version.json";
 const LATEST_RELEASE_URL: &str = "https://api.github.com/repos/openai/codex/releases/latest";
+const RELEASES_URL: &str = "https://api.github.com/repos/openai/codex/releases?per_page=30";
+const RELEASE_TAG_PREFIX: &str = "rust-v";
 
 fn version_filepath(config: &Config) -> PathBuf {
     config.codex_home.join(VERSION_FILENAME)
@@ -64,23 +103,65 @@ fn read_version_info(version_file: &Path) -> anyhow::Result<VersionInfo> {
     Ok(serde_json::from_str(&contents)?)
 }
 
-async fn check_for_update(version_file: &Path) -> anyhow::Result<()> {
+/// Look up the newest release on `channel`. The stable channel uses GitHub's
+/// "latest release", which never points at a pre-release; the beta channel
+/// picks the highest version among recent releases, pre-releases included.
+pub async fn fetch_latest_release(channel: UpdateChannel) -> anyhow::Result<Release> {
+    match channel {
+        UpdateChannel::Stable => {
+            codex_core::offline::ensure_network_allowed(LATEST_RELEASE_URL)?;
+            let info = create_client()
+                .get(LATEST_RELEASE_URL)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<ReleaseInfo>()
+                .await?;
+            release_from_info(info)
+        }
+        UpdateChannel::Beta => {
+            codex_core::offline::ensure_network_allowed(RELEASES_URL)?;
+            let infos = create_client()
+                .get(RELEASES_URL)
+                .send()
+                .await?
+                .error_for_status()?
+                .json::<Vec<ReleaseInfo>>()
+                .await?;
+            newest_release(infos)
+                .ok_or_else(|| anyhow::anyhow!("No published releases found on the beta channel"))
+        }
+    }
+}
+
+fn release_from_info(info: ReleaseInfo) -> anyhow::Result<Release> {
     let ReleaseInfo {
-        tag_name: latest_tag_name,
-    } = create_client()
-        .get(LATEST_RELEASE_URL)
-        .send()
-        .await?
-        .error_for_status()?
-        .json::<ReleaseInfo>()
-        .await?;
+        tag_name, assets, ..
+    } = info;
+    let version = tag_name
+        .strip_prefix(RELEASE_TAG_PREFIX)
+        .ok_or_else(|| anyhow::anyhow!("Failed to parse latest tag name '{tag_name}'"))?
+        .to_string();
+    Ok(Release { version, assets })
+}
+
+fn newest_release(infos: Vec<ReleaseInfo>) -> Option<Release> {
+    infos
+        .into_iter()
+        .filter(|info| !info.draft)
+        .filter_map(|info| release_from_info(info).ok())
+        .filter_map(|release| parse_prerelease_version(&release.version).map(|v| (v, release)))
+        .max_by(|(a, _), (b, _)| a.cmp(b))
+        .map(|(_, release)| release)
+}
+
+async fn check_for_update(version_file: &Path, channel: UpdateChannel) -> anyhow::Result<()> {
+    let release = fetch_latest_release(channel).await?;
 
     let info = VersionInfo {
-        latest_version: latest_tag_name
-            .strip_prefix("rust-v")
-            .ok_or_else(|| anyhow::anyhow!("Failed to parse latest tag name '{latest_tag_name}'"))?
-            .into(),
+        latest_version: release.version,
         last_checked_at: Utc::now(),
+        channel,
     };
 
     let json_line = format!("{}\n", serde_json::to_string(&info)?);
@@ -91,6 +172,23 @@ async fn check_for_update(version_file: &Path) -> anyhow::Result<()> {
     Ok(())
 }
 
+/// Whether `latest` should be offered as an update to `current`. On the
+/// stable channel pre-release versions are never considered newer.
+pub fn is_newer_on_channel(latest: &str, current: &str, channel: UpdateChannel) -> Option<bool> {
+    match channel {
+        UpdateChannel::Stable => is_newer(latest, current),
+        UpdateChannel::Beta => {
+            match (
+                parse_prerelease_version(latest),
+                parse_prerelease_version(current),
+            ) {
+                (Some(l), Some(c)) => Some(l > c),
+                _ => None,
+            }
+        }
+    }
+}
+
 fn is_newer(latest: &str, current: &str) -> Option<bool> {
     match (parse_version(latest), parse_version(current)) {
         (Some(l), Some(c)) => Some(l > c),
@@ -106,9 +204,43 @@ fn parse_version(v: &str) -> Option<(u64, u64, u64)> {
     Some((maj, min, pat))
 }
 
+/// A version that orders pre-releases before the release they lead up to:
+/// `0.11.0-alpha.2 < 0.11.0-beta.1 < 0.11.0`.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord)]
+struct PrereleaseVersion {
+    core: (u64, u64, u64),
+    is_release: bool,
+    pre: Option<(String, u64)>,
+}
+
+fn parse_prerelease_version(v: &str) -> Option<PrereleaseVersion> {
+    let v = v.trim();
+    let (core, pre) = match v.split_once('-') {
+        Some((core, pre)) => (core, Some(pre)),
+        None => (v, None),
+    };
+    let core = parse_version(core)?;
+    let pre = match pre {
+        None => None,
+        Some(pre) => {
+            let (label, number) = match pre.split_once('.') {
+                Some((label, number)) => (label, number.parse::<u64>().ok()?),
+                None => (pre, 0),
+            };
+            Some((label.to_string(), number))
+        }
+    };
+    Some(PrereleaseVersion {
+        core,
+        is_release: pre.is_none(),
+        pre,
+    })
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
+    use pretty_assertions::assert_eq;
 
     #[test]
     fn prerelease_version_is_not_considered_newer() {
@@ -129,4 +261,48 @@ mod tests {
         assert_eq!(parse_version(" 1.2.3 \n"), Some((1, 2, 3)));
         assert_eq!(is_newer(" 1.2.3 ", "1.2.2"), Some(true));
     }
+
+    #[test]
+    fn beta_channel_orders_prereleases() {
+        let beta = UpdateChannel::Beta;
+        assert_eq!(
+            is_newer_on_channel("0.11.0-beta.1", "0.10.0", beta),
+            Some(true)
+        );
+        assert_eq!(
+            is_newer_on_channel("0.11.0-beta.2", "0.11.0-beta.1", beta),
+            Some(true)
+        );
+        assert_eq!(
+            is_newer_on_channel("0.11.0-beta.1", "0.11.0-alpha.3", beta),
+            Some(true)
+        );
+        assert_eq!(
+            is_newer_on_channel("0.11.0-beta.1", "0.11.0", beta),
+            Some(false)
+        );
+        assert_eq!(
+            is_newer_on_channel("0.11.0-beta.1", "0.10.0", UpdateChannel::Stable),
+            None
+        );
+    }
+
+    #[test]
+    fn newest_release_skips_drafts_and_foreign_tags() {
+        let info = |tag: &str, draft: bool| ReleaseInfo {
+            tag_name: tag.to_string(),
+            draft,
+            assets: Vec::new(),
+        };
+        let newest = newest_release(vec![
+            info("rust-v0.10.0", false),
+            info("rust-v0.12.0-alpha.1", true),
+            info("codex-v9.9.9", false),
+            info("rust-v0.11.0-beta.1", false),
+        ]);
+        assert_eq!(
+            newest.map(|release| release.version),
+            Some("0.11.0-beta.1".to_string())
+        );
+    }
 }
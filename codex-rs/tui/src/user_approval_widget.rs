@@ -8,7 +8,10 @@
 
 use std::path::PathBuf;
 use std::sync::LazyLock;
+use std::time::Instant;
 
+use codex_core::config_types::ApprovalTimeout;
+use codex_core::protocol::ApprovalTimeoutAction;
 use codex_core::protocol::Op;
 use codex_core::protocol::ReviewDecision;
 use crossterm::event::KeyCode;
@@ -109,10 +112,18 @@ pub(crate) struct UserApprovalWidget {
     /// Set to `true` once a decision has been sent – the parent view can then
     /// remove this widget from its queue.
     done: bool,
+
+    /// When `approval_timeout_secs` is configured: the moment core will
+    /// resolve this request on its own, and how.
+    deadline: Option<(Instant, ApprovalTimeoutAction)>,
 }
 
 impl UserApprovalWidget {
-    pub(crate) fn new(approval_request: ApprovalRequest, app_event_tx: AppEventSender) -> Self {
+    pub(crate) fn new(
+        approval_request: ApprovalRequest,
+        app_event_tx: AppEventSender,
+        timeout: Option<ApprovalTimeout>,
+    ) -> Self {
         let confirmation_prompt = match &approval_request {
             ApprovalRequest::Exec { reason, .. } => {
                 let mut contents: Vec<Line> = vec![];
@@ -154,6 +165,7 @@ impl UserApprovalWidget {
             confirmation_prompt,
             selected_option: 0,
             done: false,
+            deadline: timeout.map(|timeout| (Instant::now() + timeout.duration, timeout.action)),
         }
     }
 
@@ -311,6 +323,28 @@ impl UserApprovalWidget {
         self.done = true;
     }
 
+    /// Close the prompt without sending a decision, e.g. because core already
+    /// resolved it after the approval timeout.
+    pub(crate) fn dismiss(&mut self) {
+        self.done = true;
+    }
+
+    /// Countdown shown next to the title while an approval timeout is running.
+    fn countdown_label(&self) -> Option<String> {
+        let (deadline, action) = self.deadline?;
+        let remaining = deadline.saturating_duration_since(Instant::now()).as_secs();
+        let verb = match action {
+            ApprovalTimeoutAction::Deny => "deny",
+            ApprovalTimeoutAction::Allow => "approve",
+            ApprovalTimeoutAction::Abort => "abort",
+        };
+        Some(format!(
+            " (auto-{verb} in {}:{:02})",
+            remaining / 60,
+            remaining % 60
+        ))
+    }
+
     /// Returns `true` once the user has made a decision and the widget no
     /// longer needs to be displayed.
     pub(crate) fn is_complete(&self) -> bool {
@@ -358,7 +392,11 @@ impl WidgetRef for &UserApprovalWidget {
             ApprovalRequest::Exec { .. } => "Allow command?",
             ApprovalRequest::ApplyPatch { .. } => "Apply changes?",
         };
-        Line::from(title).render(title_area, buf);
+        let mut title_spans: Vec<Span<'static>> = vec![title.into()];
+        if let Some(countdown) = self.countdown_label() {
+            title_spans.push(countdown.dim());
+        }
+        Line::from(title_spans).render(title_area, buf);
 
         self.confirmation_prompt.clone().render(prompt_chunk, buf);
         let areas = Layout::horizontal(
@@ -405,7 +443,7 @@ mod tests {
             command: vec!["echo".to_string()],
             reason: None,
         };
-        let mut widget = UserApprovalWidget::new(req, tx);
+        let mut widget = UserApprovalWidget::new(req, tx, None);
         widget.handle_key_event(KeyEvent::new(KeyCode::Char('y'), KeyModifiers::NONE));
         assert!(widget.is_complete());
         let mut events: Vec<AppEvent> = Vec::new();
@@ -421,6 +459,30 @@ mod tests {
         )));
     }
 
+    #[test]
+    fn countdown_shows_timeout_action() {
+        let (tx_raw, _rx) = unbounded_channel::<AppEvent>();
+        let tx = AppEventSender::new(tx_raw);
+        let req = ApprovalRequest::Exec {
+            id: "3".to_string(),
+            command: vec!["echo".to_string()],
+            reason: None,
+        };
+        let timeout = ApprovalTimeout {
+            duration: std::time::Duration::from_secs(125),
+            action: ApprovalTimeoutAction::Deny,
+        };
+        let mut widget = UserApprovalWidget::new(req, tx, Some(timeout));
+        let label = widget.countdown_label().expect("countdown");
+        assert!(
+            label == " (auto-deny in 2:05)" || label == " (auto-deny in 2:04)",
+            "unexpected countdown: {label}"
+        );
+
+        widget.dismiss();
+        assert!(widget.is_complete());
+    }
+
     #[test]
     fn uppercase_shortcut_is_accepted() {
         let (tx_raw, mut rx) = unbounded_channel::<AppEvent>();
@@ -430,7 +492,7 @@ mod tests {
             command: vec!["echo".to_string()],
             reason: None,
         };
-        let mut widget = UserApprovalWidget::new(req, tx);
+        let mut widget = UserApprovalWidget::new(req, tx, None);
         widget.handle_key_event(KeyEvent::new(KeyCode::Char('Y'), KeyModifiers::NONE));
         assert!(widget.is_complete());
         let mut events: Vec<AppEvent> = Vec::new();
@@ -0,0 +1,571 @@
+//! Prospective file changes for shell commands awaiting approval.
+//!
+//! When the agent asks to run a command that plainly writes files — a heredoc
+//! or `echo` redirected into a file, `tee`, or `sed -i` with `s` expressions —
+//! the approval prompt is preceded by the diff the command would produce, in
+//! the same form as a proposed `apply_patch`. The parser is deliberately
+//! narrow: any statement it does not fully understand (pipes, variables,
+//! command substitution, unknown programs) means no preview at all, so a
+//! preview is never a guess.
+
+use std::collections::BTreeMap;
+use std::collections::HashMap;
+use std::path::Component;
+use std::path::Path;
+use std::path::PathBuf;
+use std::sync::LazyLock;
+
+use codex_core::protocol::FileChange;
+use regex_lite::Regex;
+
+/// Files larger than this are not read for a preview.
+const MAX_PREVIEW_BYTES: u64 = 1024 * 1024;
+
+/// Commands that may sit alongside a write without changing what it writes.
+const NEUTRAL_COMMANDS: &[&str] = &["mkdir", "true", "set"];
+
+/// Stands in for a heredoc operator so the statement that reads it can be
+/// found after the line is split into words.
+const HEREDOC_SENTINEL: &str = "__codex_heredoc__";
+
+/// `<<EOF`, `<<-EOF`, `<<'EOF'` or `<<"EOF"`. Group 4 is set only when the
+/// delimiter is unquoted, in which case the body is subject to expansion.
+#[expect(clippy::expect_used)]
+static HEREDOC_RE: LazyLock<Regex> = LazyLock::new(|| {
+    Regex::new(
+        r#"<<(-?)\s*(?:'([A-Za-z_][A-Za-z0-9_]*)'|"([A-Za-z_][A-Za-z0-9_]*)"|([A-Za-z_][A-Za-z0-9_]*))"#,
+    )
+    .expect("valid heredoc regex")
+});
+
+/// The changes `command` would make to files, relative to `cwd`, or `None` if
+/// the command is not a write the preview understands.
+pub(crate) fn preview_file_writes(
+    command: &[String],
+    cwd: &Path,
+) -> Option<HashMap<PathBuf, FileChange>> {
+    let script = match command {
+        [shell, flag, script] if is_shell(shell) && (flag == "-c" || flag == "-lc") => {
+            script.clone()
+        }
+        _ => shlex::try_join(command.iter().map(String::as_str)).ok()?,
+    };
+    let mut preview = Preview {
+        cwd: cwd.to_path_buf(),
+        files: BTreeMap::new(),
+    };
+    preview.run_script(&script)?;
+    let changes: HashMap<PathBuf, FileChange> = preview
+        .files
+        .into_iter()
+        .filter_map(|(path, file)| {
+            let change = match file.original {
+                None => FileChange::Add {
+                    content: file.content,
+                },
+                Some(original) if original == file.content => return None,
+                Some(original) => FileChange::Update {
+                    unified_diff: diffy::create_patch(&original, &file.content).to_string(),
+                    move_path: None,
+                },
+            };
+            Some((path, change))
+        })
+        .collect();
+    (!changes.is_empty()).then_some(changes)
+}
+
+fn is_shell(program: &str) -> bool {
+    matches!(
+        Path::new(program)
+            .file_name()
+            .and_then(|name| name.to_str()),
+        Some("bash" | "sh" | "zsh")
+    )
+}
+
+struct PreviewFile {
+    /// Contents before the command ran; `None` if the file does not exist.
+    original: Option<String>,
+    content: String,
+}
+
+struct Preview {
+    cwd: PathBuf,
+    files: BTreeMap<PathBuf, PreviewFile>,
+}
+
+impl Preview {
+    fn run_script(&mut self, script: &str) -> Option<()> {
+        let mut lines = script.lines();
+        while let Some(line) = lines.next() {
+            let mut stdin = None;
+            let line = match HEREDOC_RE.captures(line) {
+                Some(caps) => {
+                    let strip_tabs = caps.get(1).is_some_and(|m| !m.as_str().is_empty());
+                    let quoted = caps.get(4).is_none();
+                    let delimiter = caps
+                        .get(2)
+                        .or_else(|| caps.get(3))
+                        .or_else(|| caps.get(4))?
+                        .as_str();
+                    let mut body = String::new();
+                    let mut terminated = false;
+                    for body_line in lines.by_ref() {
+                        let body_line = if strip_tabs {
+                            body_line.trim_start_matches('\t')
+                        } else {
+                            body_line
+                        };
+                        if body_line == delimiter {
+                            terminated = true;
+                            break;
+                        }
+                        body.push_str(body_line);
+                        body.push('\n');
+                    }
+                    // An unquoted delimiter expands `$var` and `$(...)` in the body.
+                    if !terminated || (!quoted && (body.contains('$') || body.contains('`'))) {
+                        return None;
+                    }
+                    stdin = Some(body);
+                    let range = caps.get(0)?.range();
+                    format!(
+                        "{} {HEREDOC_SENTINEL} {}",
+                        &line[..range.start],
+                        &line[range.end..]
+                    )
+                }
+                None => line.to_string(),
+            };
+
+            let words = shlex::split(&line)?;
+            let mut statement = Vec::new();
+            for word in words {
+                let (word, ends_statement) = match word.strip_suffix(';') {
+                    Some(word) => (word.to_string(), true),
+                    None => (word, false),
+                };
+                if word == "&&" || word == ";" {
+                    self.run_statement(std::mem::take(&mut statement), &mut stdin)?;
+                    continue;
+                }
+                if word == "|" || word == "||" || word == "&" || is_dynamic(&word) {
+                    return None;
+                }
+                if !word.is_empty() || !ends_statement {
+                    statement.push(word);
+                }
+                if ends_statement {
+                    self.run_statement(std::mem::take(&mut statement), &mut stdin)?;
+                }
+            }
+            self.run_statement(statement, &mut stdin)?;
+        }
+        Some(())
+    }
+
+    /// Apply one simple command. `stdin` holds a pending heredoc body, which
+    /// is consumed by the statement that carries the heredoc operator.
+    fn run_statement(&mut self, words: Vec<String>, stdin: &mut Option<String>) -> Option<()> {
+        if words.is_empty() {
+            return Some(());
+        }
+        let mut args = Vec::new();
+        let mut redirect: Option<(bool, String)> = None;
+        let mut input = None;
+        let mut words = words.into_iter();
+        while let Some(word) = words.next() {
+            if word == HEREDOC_SENTINEL {
+                input = Some(stdin.take()?);
+            } else if let Some(rest) = word.strip_prefix(">>") {
+                let target = if rest.is_empty() {
+                    words.next()?
+                } else {
+                    rest.to_string()
+                };
+                redirect = Some((true, target));
+            } else if let Some(rest) = word.strip_prefix('>') {
+                let target = if rest.is_empty() {
+                    words.next()?
+                } else {
+                    rest.to_string()
+                };
+                redirect = Some((false, target));
+            } else if word.contains(['<', '>']) {
+                return None;
+            } else {
+                args.push(word);
+            }
+        }
+        let redirect = redirect.filter(|(_, target)| target != "/dev/null");
+
+        let (program, args) = args.split_first()?;
+        match (program.as_str(), redirect) {
+            ("cd", None) => {
+                let [dir] = args else {
+                    return None;
+                };
+                self.cwd = self.resolve(dir)?;
+            }
+            (program, None) if NEUTRAL_COMMANDS.contains(&program) => {}
+            ("cat", Some((append, target))) if args.is_empty() => {
+                self.write(&target, input?, append)?;
+            }
+            ("echo", Some((append, target))) if input.is_none() => {
+                let (newline, words) = match args.split_first() {
+                    Some((flag, rest)) if flag == "-n" => (false, rest),
+                    _ => (true, args),
+                };
+                if words.first().is_some_and(|word| word.starts_with('-')) {
+                    return None;
+                }
+                let mut text = words.join(" ");
+                if newline {
+                    text.push('\n');
+                }
+                self.write(&target, text, append)?;
+            }
+            ("tee", None) => {
+                let text = input?;
+                let (append, files) = match args.split_first() {
+                    Some((flag, rest)) if flag == "-a" => (true, rest),
+                    _ => (false, args),
+                };
+                if files.is_empty() || files.iter().any(|file| file.starts_with('-')) {
+                    return None;
+                }
+                for file in files {
+                    self.write(file, text.clone(), append)?;
+                }
+            }
+            ("sed", None) if input.is_none() => self.sed(args)?,
+            _ => return None,
+        }
+        Some(())
+    }
+
+    fn resolve(&self, path: &str) -> Option<PathBuf> {
+        if path.starts_with('~') || path.contains(['*', '?', '[']) {
+            return None;
+        }
+        Some(
+            self.cwd
+                .join(path)
+                .components()
+                .filter(|component| !matches!(component, Component::CurDir))
+                .collect(),
+        )
+    }
+
+    /// The tracked state of `path`, reading it from disk on first use.
+    fn file(&mut self, path: &str) -> Option<&mut PreviewFile> {
+        let path = self.resolve(path)?;
+        if !self.files.contains_key(&path) {
+            let original = match std::fs::metadata(&path) {
+                Ok(meta) if meta.is_file() && meta.len() <= MAX_PREVIEW_BYTES => {
+                    Some(std::fs::read_to_string(&path).ok()?)
+                }
+                Ok(_) => return None,
+                Err(err) if err.kind() == std::io::ErrorKind::NotFound => None,
+                Err(_) => return None,
+            };
+            let content = original.clone().unwrap_or_default();
+            self.files
+                .insert(path.clone(), PreviewFile { original, content });
+        }
+        self.files.get_mut(&path)
+    }
+
+    fn write(&mut self, path: &str, text: String, append: bool) -> Option<()> {
+        let file = self.file(path)?;
+        if append {
+            file.content.push_str(&text);
+        } else {
+            file.content = text;
+        }
+        Some(())
+    }
+
+    fn sed(&mut self, args: &[String]) -> Option<()> {
+        let mut in_place = false;
+        let mut extended = false;
+        let mut scripts = Vec::new();
+        let mut files = Vec::new();
+        let mut args = args.iter().peekable();
+        while let Some(arg) = args.next() {
+            match arg.as_str() {
+                "-i" | "--in-place" => {
+                    in_place = true;
+                    // BSD sed takes the backup suffix as a separate argument.
+                    args.next_if(|next| next.is_empty());
+                }
+                "-E" | "-r" | "--regexp-extended" => extended = true,
+                "-e" | "--expression" => scripts.push(args.next()?.clone()),
+                arg if arg.starts_with("-i") || arg.starts_with("--in-place=") => in_place = true,
+                arg if arg.starts_with('-') => return None,
+                _ if scripts.is_empty() => scripts.push(arg.clone()),
+                _ => files.push(arg.clone()),
+            }
+        }
+        if !in_place || files.is_empty() {
+            return None;
+        }
+        let substitutions = scripts
+            .iter()
+            .map(|script| parse_substitutions(script, extended))
+            .collect::<Option<Vec<_>>>()?
+            .into_iter()
+            .flatten()
+            .collect::<Vec<_>>();
+        for path in &files {
+            let file = self.file(path)?;
+            file.original.as_ref()?;
+            file.content = substitutions
+                .iter()
+                .fold(std::mem::take(&mut file.content), |content, sub| {
+                    sub.apply(&content)
+                });
+        }
+        Some(())
+    }
+}
+
+/// Words the shell would expand before running the command.
+fn is_dynamic(word: &str) -> bool {
+    word.contains('`')
+        || word.contains("$(")
+        || word.char_indices().any(|(idx, c)| {
+            c == '$'
+                && word[idx + 1..]
+                    .chars()
+                    .next()
+                    .is_some_and(|next| next.is_ascii_alphabetic() || next == '_' || next == '{')
+        })
+}
+
+/// One sed `s/pattern/replacement/flags` command.
+struct Substitution {
+    regex: Regex,
+    replacement: String,
+    global: bool,
+}
+
+impl Substitution {
+    /// Apply to every line of `content`, as sed does.
+    fn apply(&self, content: &str) -> String {
+        content
+            .split_inclusive('\n')
+            .map(|line| {
+                let (text, newline) = match line.strip_suffix('\n') {
+                    Some(text) => (text, "\n"),
+                    None => (line, ""),
+                };
+                let limit = if self.global { 0 } else { 1 };
+                let replaced = self.regex.replacen(text, limit, self.replacement.as_str());
+                format!("{replaced}{newline}")
+            })
+            .collect()
+    }
+}
+
+/// Parse a sed script made only of `s` commands separated by `;` or newlines.
+fn parse_substitutions(script: &str, extended: bool) -> Option<Vec<Substitution>> {
+    let mut substitutions = Vec::new();
+    let mut chars = script.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace() || *c == ';').is_some() {}
+        match chars.next() {
+            None => return Some(substitutions),
+            Some('s') => {}
+            Some(_) => return None,
+        }
+        let delimiter = chars
+            .next()
+            .filter(|c| !c.is_alphanumeric() && *c != '\\')?;
+        let pattern = read_until(&mut chars, delimiter)?;
+        let replacement = read_until(&mut chars, delimiter)?;
+        let mut global = false;
+        let mut case_insensitive = false;
+        while let Some(flag) = chars.next_if(|c| !c.is_whitespace() && *c != ';') {
+            match flag {
+                'g' => global = true,
+                'i' | 'I' => case_insensitive = true,
+                _ => return None,
+            }
+        }
+        let mut pattern = if extended {
+            pattern
+        } else {
+            basic_to_extended(&pattern)
+        };
+        if case_insensitive {
+            pattern.insert_str(0, "(?i)");
+        }
+        substitutions.push(Substitution {
+            regex: Regex::new(&pattern).ok()?,
+            replacement: sed_replacement(&replacement)?,
+            global,
+        });
+    }
+}
+
+/// Read up to the next unescaped `delimiter`, unescaping it.
+fn read_until(
+    chars: &mut std::iter::Peekable<std::str::Chars<'_>>,
+    delimiter: char,
+) -> Option<String> {
+    let mut out = String::new();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                next if next == delimiter => out.push(next),
+                next => {
+                    out.push('\\');
+                    out.push(next);
+                }
+            },
+            c if c == delimiter => return Some(out),
+            c => out.push(c),
+        }
+    }
+    None
+}
+
+/// Translate a POSIX basic regular expression into the extended syntax
+/// `regex_lite` understands.
+fn basic_to_extended(pattern: &str) -> String {
+    let mut out = String::new();
+    let mut chars = pattern.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next() {
+                Some(next @ ('(' | ')' | '{' | '}' | '+' | '?' | '|')) => out.push(next),
+                Some(next) => {
+                    out.push('\\');
+                    out.push(next);
+                }
+                None => out.push_str("\\\\"),
+            },
+            '(' | ')' | '{' | '}' | '+' | '?' | '|' => {
+                out.push('\\');
+                out.push(c);
+            }
+            c => out.push(c),
+        }
+    }
+    out
+}
+
+/// Translate a sed replacement (`&`, `\1`) into `regex_lite` syntax.
+fn sed_replacement(replacement: &str) -> Option<String> {
+    let mut out = String::new();
+    let mut chars = replacement.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\\' => match chars.next()? {
+                digit @ '0'..='9' => out.push_str(&format!("${{{digit}}}")),
+                'n' => out.push('\n'),
+                't' => out.push('\t'),
+                other => out.push(other),
+            },
+            '&' => out.push_str("${0}"),
+            '$' => out.push_str("$$"),
+            c => out.push(c),
+        }
+    }
+    Some(out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use pretty_assertions::assert_eq;
+
+    fn bash(script: &str) -> Vec<String> {
+        vec!["bash".to_string(), "-lc".to_string(), script.to_string()]
+    }
+
+    #[test]
+    fn heredoc_and_echo_writes_are_previewed() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("log.txt"), "one\n").expect("write");
+
+        let changes = preview_file_writes(
+            &bash(
+                "mkdir -p src && cat > src/new.txt <<'EOF'\nhello $name\nEOF\necho two >> log.txt",
+            ),
+            dir.path(),
+        )
+        .expect("preview");
+
+        let mut paths: Vec<&PathBuf> = changes.keys().collect();
+        paths.sort();
+        assert_eq!(
+            paths,
+            vec![&dir.path().join("log.txt"), &dir.path().join("src/new.txt")]
+        );
+        match &changes[&dir.path().join("src/new.txt")] {
+            FileChange::Add { content } => assert_eq!(content, "hello $name\n"),
+            other => panic!("expected add, got {other:?}"),
+        }
+        match &changes[&dir.path().join("log.txt")] {
+            FileChange::Update { unified_diff, .. } => {
+                assert!(unified_diff.contains(" one\n+two\n"), "{unified_diff}");
+            }
+            other => panic!("expected update, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn sed_in_place_substitutions_are_applied() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        std::fs::write(dir.path().join("a.rs"), "let foo = foo(1);\nfoo\n").expect("write");
+
+        let changes = preview_file_writes(
+            &[
+                "sed".to_string(),
+                "-i".to_string(),
+                r"s/foo(\([0-9]\))/bar(\1)/;2s/x/y/".to_string(),
+                "a.rs".to_string(),
+            ],
+            dir.path(),
+        );
+        // Addresses are not supported, so nothing is shown.
+        assert!(changes.is_none());
+
+        let changes = preview_file_writes(
+            &bash(r"sed -i -e 's/foo(\([0-9]\))/bar(\1)/' -e 's|^foo$|&!|g' a.rs"),
+            dir.path(),
+        )
+        .expect("preview");
+        match &changes[&dir.path().join("a.rs")] {
+            FileChange::Update { unified_diff, .. } => {
+                assert!(
+                    unified_diff.contains("+let foo = bar(1);\n+foo!\n"),
+                    "{unified_diff}"
+                );
+            }
+            other => panic!("expected update, got {other:?}"),
+        }
+    }
+
+    #[test]
+    fn anything_unpredictable_yields_no_preview() {
+        let dir = tempfile::tempdir().expect("tempdir");
+        for script in [
+            "echo hello world",
+            "python gen.py > out.txt",
+            "echo $HOME > out.txt",
+            "cat > out.txt <<EOF\n$(date)\nEOF",
+            "cat > out.txt <<'EOF'\nno terminator",
+            "ls | tee out.txt",
+            "sed 's/a/b/' file.txt",
+        ] {
+            assert!(
+                preview_file_writes(&bash(script), dir.path()).is_none(),
+                "{script}"
+            );
+        }
+    }
+}